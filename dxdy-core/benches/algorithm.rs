@@ -0,0 +1,97 @@
+//! Benchmarks of the passes of a step of the simulation, and of whole runs,
+//! to catch regressions in the spatial index and the force pass. Run with
+//! `cargo bench -p dxdy-core --features bench`.
+
+use criterion::{
+    BatchSize, BenchmarkId, Criterion, criterion_group, criterion_main,
+};
+use dxdy_core::algorithm::{ONE, Params, Simulation, bench};
+
+/// Vertices of the circles the simulations are seeded with.
+const SEED_SIZES: [usize; 3] = [32, 128, 512];
+
+/// Steps taken before each pass is measured, for the vertices to have
+/// spread out into a realistic growth.
+const GROWN: usize = 200;
+
+/// A simulation seeded with a circle of `n_vertices` grown for [`GROWN`]
+/// steps.
+fn grown(params: &Params, n_vertices: usize) -> Simulation {
+    let mut simulation = bench::circle(params, n_vertices);
+    for _ in 0..GROWN {
+        simulation.step(params).unwrap();
+    }
+    simulation
+}
+
+fn optimize_position(c: &mut Criterion) {
+    let params = Params::DEFAULT;
+    let mut group = c.benchmark_group("optimize_position");
+    for n in SEED_SIZES {
+        let mut simulation = grown(&params, n);
+        group.bench_function(BenchmarkId::from_parameter(n), |b| {
+            b.iter(|| bench::optimize_position(&mut simulation, &params))
+        });
+    }
+    group.finish();
+}
+
+fn sphere_vertices(c: &mut Criterion) {
+    let params = Params::DEFAULT;
+    let mut group = c.benchmark_group("sphere_vertices");
+    for n in SEED_SIZES {
+        let simulation = grown(&params, n);
+        group.bench_function(BenchmarkId::from_parameter(n), |b| {
+            b.iter(|| bench::sphere_vertices(&simulation, params.far_l))
+        });
+    }
+    group.finish();
+}
+
+fn split_long_edges(c: &mut Criterion) {
+    let params = Params::DEFAULT;
+    let mut group = c.benchmark_group("split_long_edges");
+    for n in SEED_SIZES {
+        // every edge of the seeds is longer than this
+        group.bench_function(BenchmarkId::from_parameter(n), |b| {
+            b.iter_batched(
+                || bench::circle(&params, n),
+                |mut simulation| {
+                    bench::split_long_edges(&mut simulation, ONE / 4.)
+                },
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+fn runs(c: &mut Criterion) {
+    let params = Params::DEFAULT;
+    let mut group = c.benchmark_group("run_1000");
+    group.sample_size(10);
+    for n in SEED_SIZES {
+        group.bench_function(BenchmarkId::from_parameter(n), |b| {
+            b.iter_batched(
+                || bench::circle(&params, n),
+                |mut simulation| {
+                    for _ in 0..1000 {
+                        simulation.step(&params).unwrap();
+                    }
+                    simulation
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    optimize_position,
+    sphere_vertices,
+    split_long_edges,
+    runs
+);
+criterion_main!(benches);
@@ -0,0 +1,74 @@
+//! entry points into the inner passes of a step, for the benchmarks, which
+//! are built with the `bench` feature.
+
+use glam::DVec2;
+
+use super::{
+    Params, Primitive, PrimitiveKind, SegmentsError, Simulation,
+    spatial_index::SpatialIndex,
+};
+
+/// a simulation seeded with a circle of `n_vertices` vertices.
+pub fn circle(params: &Params, n_vertices: usize) -> Simulation {
+    let mut simulation =
+        Simulation::new(params, 1).expect("the parameters are valid");
+    simulation
+        .add_primitive(&Primitive {
+            kind: PrimitiveKind::Circle,
+            center: DVec2::splat(0.5),
+            radius: 0.05,
+            rotation: 0.,
+            n_vertices,
+            passive: false,
+        })
+        .expect("a circle fits the simulation");
+    simulation
+}
+
+/// the force pass of a step, which moves every active vertex.
+pub fn optimize_position(
+    simulation: &mut Simulation,
+    params: &Params,
+) -> Result<(), SegmentsError> {
+    let df = &mut simulation.df;
+    df.set_distances(params.near_l, params.far_l);
+    df.optimize_position(params.step)
+}
+
+/// query the spatial index for the vertices within `rad` of every vertex.
+/// returns the number found in all.
+pub fn sphere_vertices(simulation: &Simulation, rad: f64) -> usize {
+    let segments = &simulation.df.segments;
+    let mut vertices = Vec::new();
+    segments
+        .active_vertex_ids()
+        .map(|v| {
+            segments.index.sphere_vertices(
+                v.slot(),
+                &segments.x,
+                &segments.y,
+                rad,
+                &mut vertices,
+            )
+        })
+        .sum()
+}
+
+/// split every edge longer than `limit` that has an active vertex in two.
+pub fn split_long_edges(
+    simulation: &mut Simulation,
+    limit: f64,
+) -> Result<(), SegmentsError> {
+    let segments = &mut simulation.df.segments;
+    let edges = segments
+        .edge_ids()
+        .filter(|&e| {
+            let Ok(ends) = segments.edge_ends(e) else {
+                return false;
+            };
+            ends.into_iter().any(|v| segments.vertex_active(v))
+                && segments.get_edge_length(e).is_ok_and(|len| len > limit)
+        })
+        .collect::<Vec<_>>();
+    segments.split_edges(&edges).map(drop)
+}
@@ -0,0 +1,169 @@
+use glam::DVec2;
+
+use super::segments::Segments;
+
+/// similarity of the final geometries of two runs.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RunComparison {
+    /// symmetric hausdorff distance between the vertices of both runs, in
+    /// unit square coordinates. `0.` for identical vertex sets.
+    pub hausdorff: f64,
+    /// intersection over union of the cells covered by the edges of both runs
+    /// after rasterization. `1.` for identical coverage.
+    pub overlap: f64,
+}
+
+//===================================================================
+// Helpers
+//===================================================================
+
+/// directed hausdorff distance from `a` to `b`.
+///
+/// uses the early break of Taha & Hanbury: the inner loop stops as soon as a
+/// point of `b` is closer than the running maximum, which makes the search
+/// far cheaper than `O(|a| * |b|)` for similar point sets.
+fn directed_hausdorff(a: &[DVec2], b: &[DVec2]) -> f64 {
+    let mut cmax2: f64 = 0.;
+
+    for &pa in a {
+        let mut cmin2 = f64::INFINITY;
+        for &pb in b {
+            let d2 = pa.distance_squared(pb);
+            if d2 < cmax2 {
+                cmin2 = 0.;
+                break;
+            }
+            cmin2 = cmin2.min(d2);
+        }
+        cmax2 = cmax2.max(cmin2);
+    }
+
+    cmax2.sqrt()
+}
+
+/// symmetric hausdorff distance between the point sets `a` and `b`.
+fn hausdorff_distance(a: &[DVec2], b: &[DVec2]) -> f64 {
+    if a.is_empty() || b.is_empty() {
+        return if a.len() == b.len() {
+            0.
+        } else {
+            f64::INFINITY
+        };
+    }
+
+    directed_hausdorff(a, b).max(directed_hausdorff(b, a))
+}
+
+/// rasterize edges `[a, b]` onto a `resolution` square grid over the unit
+/// square.
+fn rasterize(edges: &[[DVec2; 2]], resolution: usize) -> Vec<bool> {
+    let mut grid = vec![false; resolution * resolution];
+    let cell = |x: f64| ((x * resolution as f64) as usize).min(resolution - 1);
+
+    for &[a, b] in edges {
+        let len = a.distance(b);
+        let samples = (2. * len * resolution as f64).ceil() as usize + 1;
+        for i in 0..=samples {
+            let t = i as f64 / samples as f64;
+            let p = a.lerp(b, t).clamp(DVec2::ZERO, DVec2::ONE);
+            grid[cell(p.y) * resolution + cell(p.x)] = true;
+        }
+    }
+
+    grid
+}
+
+/// intersection over union of the rasterized edges of `a` and `b`.
+fn raster_overlap(
+    a: &[[DVec2; 2]],
+    b: &[[DVec2; 2]],
+    resolution: usize,
+) -> f64 {
+    let (ga, gb) = (rasterize(a, resolution), rasterize(b, resolution));
+
+    let (mut intersection, mut union) = (0, 0);
+    for (&ca, &cb) in ga.iter().zip(&gb) {
+        intersection += (ca && cb) as usize;
+        union += (ca || cb) as usize;
+    }
+
+    if union == 0 {
+        1.
+    } else {
+        intersection as f64 / union as f64
+    }
+}
+
+/// compare the final geometries of two runs, rasterizing at `resolution`
+/// cells per side for the overlap.
+pub(super) fn compare_runs(
+    a: &Segments,
+    b: &Segments,
+    resolution: usize,
+) -> RunComparison {
+    RunComparison {
+        hausdorff: hausdorff_distance(
+            &a.get_vertex_coordinates(),
+            &b.get_vertex_coordinates(),
+        ),
+        overlap: raster_overlap(
+            &a.get_edges_coordinates(),
+            &b.get_edges_coordinates(),
+            resolution,
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::{Params, Primitive, PrimitiveKind, Simulation};
+
+    fn circle(center: DVec2, steps: usize) -> Simulation {
+        let params = Params::DEFAULT;
+        let mut simulation = Simulation::new(&params, 1).unwrap();
+        simulation
+            .add_primitive(&Primitive {
+                kind: PrimitiveKind::Circle,
+                center,
+                radius: 0.05,
+                rotation: 0.,
+                n_vertices: 32,
+                passive: false,
+            })
+            .unwrap();
+        for _ in 0..steps {
+            simulation.step(&params).unwrap();
+        }
+        simulation
+    }
+
+    #[test]
+    fn identical_runs_are_alike() {
+        let (a, b) =
+            (circle(DVec2::splat(0.5), 50), circle(DVec2::splat(0.5), 50));
+        assert_eq!(
+            a.compare(&b, 200),
+            RunComparison {
+                hausdorff: 0.,
+                overlap: 1.,
+            }
+        );
+    }
+
+    #[test]
+    fn translated_runs_are_as_far_apart_as_they_were_moved() {
+        let a = [DVec2::new(0.2, 0.2), DVec2::new(0.7, 0.3)];
+        let b = a.map(|p| p + DVec2::new(0.03, 0.04));
+        assert!((hausdorff_distance(&a, &b) - 0.05).abs() < 1e-12);
+        assert_eq!(hausdorff_distance(&a, &[]), f64::INFINITY);
+
+        // the leftmost vertex of one circle is the whole offset away from
+        // the other, whose edges it shares no cell with
+        let a = circle(DVec2::new(0.35, 0.5), 0);
+        let b = circle(DVec2::new(0.5, 0.5), 0);
+        let comparison = a.compare(&b, 200);
+        assert!((comparison.hausdorff - 0.15).abs() < 1e-12);
+        assert_eq!(comparison.overlap, 0.);
+    }
+}
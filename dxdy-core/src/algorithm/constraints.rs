@@ -0,0 +1,281 @@
+use glam::DVec2;
+
+use super::{
+    differential_line::closest_point_on_polyline,
+    ids::VertexId,
+    segments::{Remap, Segments, SegmentsError},
+};
+
+/// a hard constraint on the geometry of [`Segments`], enforced by projecting
+/// vertices back onto the constraint after every optimization step.
+#[derive(Clone, Debug, PartialEq)]
+pub(super) enum Constraint {
+    /// vertex v is pinned to `pos`.
+    Fixed { v: VertexId, pos: DVec2 },
+    /// the active vertices of each segment longer than `max_len` are drawn
+    /// toward its centroid to shrink it. passive vertices stay put, so a
+    /// segment with them, like a spine with passive ends, may stay longer.
+    MaxLength { max_len: f64 },
+    /// the active vertices must stay within the rectangle `min..=max`.
+    /// passive vertices stay put, wherever they are.
+    Region { min: DVec2, max: DVec2 },
+    /// the active vertices of segment s must stay within `half_width` of the
+    /// polyline through `spine`.
+    Corridor {
+        s: i64,
+        spine: Vec<DVec2>,
+        half_width: f64,
+    },
+    /// vertex v, the root of a branch, is kept `length` from vertex `to` of
+    /// the line it sprouted from.
+    Stem {
+        v: VertexId,
+        to: VertexId,
+        length: f64,
+    },
+}
+
+#[derive(Clone, Default)]
+pub(super) struct Constraints {
+    constraints: Vec<Constraint>,
+}
+
+//===================================================================
+// Helpers
+//===================================================================
+
+/// total length of the edges of every segment, and the centroid of its
+/// vertices, by segment index.
+fn segment_lengths_and_centroids(
+    segments: &Segments,
+) -> Result<Vec<(f64, DVec2)>, SegmentsError> {
+    let s_num = segments.s_num() as usize;
+    let mut lengths = vec![0.; s_num];
+    for e in segments.edge_ids() {
+        let [v1, _] = segments.edge_ends(e)?;
+        let s = segments.vertex_segment(v1)? as usize;
+        lengths[s] += segments.get_edge_length(e)?;
+    }
+
+    let mut sums = vec![(DVec2::ZERO, 0); s_num];
+    for v in segments.vertex_ids() {
+        let (sum, n) = &mut sums[segments.vertex_segment(v)? as usize];
+        *sum += segments.vertex_position(v)?;
+        *n += 1;
+    }
+
+    Ok(lengths
+        .into_iter()
+        .zip(sums)
+        .map(|(len, (sum, n))| (len, sum / n.max(1) as f64))
+        .collect())
+}
+
+//===================================================================
+// Public Methods
+//===================================================================
+
+impl Constraints {
+    pub(super) fn iter(&self) -> impl Iterator<Item = &Constraint> {
+        self.constraints.iter()
+    }
+
+    pub(super) fn push(&mut self, constraint: Constraint) {
+        self.constraints.push(constraint);
+    }
+
+    pub(super) fn retain(&mut self, f: impl FnMut(&Constraint) -> bool) {
+        self.constraints.retain(f);
+    }
+
+    /// bring the vertices of the constraints along to where
+    /// [`Segments::compact`] moved them, dropping the constraints of
+    /// vertices that were deleted.
+    pub(super) fn remap(&mut self, remap: &Remap) {
+        self.constraints.retain_mut(|constraint| match constraint {
+            Constraint::Fixed { v, .. } => match remap.vertex(*v) {
+                Some(new) => {
+                    *v = new;
+                    true
+                }
+                None => false,
+            },
+            Constraint::Stem { v, to, .. } => {
+                match (remap.vertex(*v), remap.vertex(*to)) {
+                    (Some(new_v), Some(new_to)) => {
+                        (*v, *to) = (new_v, new_to);
+                        true
+                    }
+                    _ => false,
+                }
+            }
+            _ => true,
+        });
+    }
+
+    /// project the vertices of `segments` onto every constraint, in the order
+    /// the constraints were added. constraints of vertices that have been
+    /// deleted are skipped.
+    pub(super) fn apply(
+        &self,
+        segments: &mut Segments,
+    ) -> Result<(), SegmentsError> {
+        for constraint in &self.constraints {
+            match *constraint {
+                Constraint::Fixed { v, pos } => {
+                    if segments.vertex_exists(v) {
+                        segments.set_vertex_position(v, pos)?;
+                    }
+                }
+                Constraint::MaxLength { max_len } => {
+                    let lines = segment_lengths_and_centroids(segments)?;
+                    let active =
+                        segments.active_vertex_ids().collect::<Vec<_>>();
+                    for v in active {
+                        let s = segments.vertex_segment(v)? as usize;
+                        let (len, centroid) = lines[s];
+                        if len <= max_len {
+                            continue;
+                        }
+
+                        // shrink the segment about its centroid, which
+                        // scales the length by the same factor
+                        let scale = max_len / len;
+                        let p = segments.vertex_position(v)?;
+                        segments.set_vertex_position(
+                            v,
+                            centroid + (p - centroid) * scale,
+                        )?;
+                    }
+                }
+                Constraint::Corridor {
+                    s,
+                    ref spine,
+                    half_width,
+                } => {
+                    if spine.is_empty() {
+                        continue;
+                    }
+                    let active =
+                        segments.active_vertex_ids().collect::<Vec<_>>();
+                    for v in active {
+                        if segments.vertex_segment(v)? != s {
+                            continue;
+                        }
+                        let p = segments.vertex_position(v)?;
+                        let q = closest_point_on_polyline(p, spine);
+                        if p.distance(q) <= half_width {
+                            continue;
+                        }
+                        segments.set_vertex_position(
+                            v,
+                            q + (p - q).normalize() * half_width,
+                        )?;
+                    }
+                }
+                Constraint::Stem { v, to, length } => {
+                    let (Ok(p), Ok(q)) = (
+                        segments.vertex_position(v),
+                        segments.vertex_position(to),
+                    ) else {
+                        continue;
+                    };
+                    let d = (p - q).normalize_or_zero();
+                    if d == DVec2::ZERO {
+                        continue;
+                    }
+                    segments.set_vertex_position(v, q + d * length)?;
+                }
+                Constraint::Region { min, max } => {
+                    let active =
+                        segments.active_vertex_ids().collect::<Vec<_>>();
+                    for v in active {
+                        let p = segments.vertex_position(v)?;
+                        segments.set_vertex_position(v, p.clamp(min, max))?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::{Params, Primitive, PrimitiveKind, Simulation};
+
+    /// the growing circle of `params`, and the total length of its edges
+    /// and the bounds of its vertices after `steps` steps.
+    fn grow(
+        params: &Params,
+        steps: usize,
+        setup: impl FnOnce(&mut Simulation),
+    ) -> (Simulation, f64, [DVec2; 2]) {
+        let mut simulation = Simulation::new(params, 1).unwrap();
+        simulation
+            .add_primitive(&Primitive {
+                kind: PrimitiveKind::Circle,
+                center: DVec2::splat(0.5),
+                radius: 0.05,
+                rotation: 0.,
+                n_vertices: 32,
+                passive: false,
+            })
+            .unwrap();
+        setup(&mut simulation);
+
+        let (mut len, mut min, mut max) = (0., DVec2::ONE, DVec2::ZERO);
+        for _ in 0..steps {
+            simulation.step(params).unwrap();
+            len = segment_lengths_and_centroids(&simulation.df.segments)
+                .unwrap()[0]
+                .0;
+            for p in simulation.positions().iter() {
+                (min, max) = (min.min(p), max.max(p));
+            }
+        }
+        (simulation, len, [min, max])
+    }
+
+    const GROWTH: Params = Params {
+        spawn_probability: 0.1,
+        ..Params::DEFAULT
+    };
+
+    #[test]
+    fn fixed_vertices_stay_put() {
+        let pos = DVec2::new(0.56, 0.5);
+        let (simulation, ..) = grow(&GROWTH, 100, |simulation| {
+            let v = simulation.df.segments.vertex_id(0).unwrap();
+            simulation.df.pin_vertex(v, pos);
+        });
+        let v = simulation.df.segments.vertex_id(0).unwrap();
+        assert_eq!(simulation.df.segments.vertex_position(v), Ok(pos));
+    }
+
+    #[test]
+    fn lines_stay_under_the_max_length() {
+        let (_, free, _) = grow(&GROWTH, 200, |_| {});
+        let params = Params {
+            max_length: 0.4,
+            ..GROWTH
+        };
+        assert!(free > params.max_length);
+
+        let (_, len, _) = grow(&params, 200, |_| {});
+        assert!(len <= params.max_length + 1e-9, "{len}");
+    }
+
+    #[test]
+    fn vertices_stay_inside_the_region() {
+        let region = [DVec2::splat(0.44), DVec2::splat(0.56)];
+        let (_, _, [min, max]) = grow(&GROWTH, 200, |_| {});
+        assert!(min.cmplt(region[0]).any() || max.cmpgt(region[1]).any());
+
+        let params = Params { region, ..GROWTH };
+        let (_, _, [min, max]) = grow(&params, 200, |_| {});
+        assert!(min.cmpge(region[0]).all() && max.cmple(region[1]).all());
+    }
+}
@@ -0,0 +1,940 @@
+use glam::DVec2;
+use rand::{SeedableRng, rngs::SmallRng};
+
+use super::{
+    constraints::{Constraint, Constraints},
+    domain::Domain,
+    fields::{self, Field},
+    ids::VertexId,
+    neighbor_lists::NeighborLists,
+    noise::Noise,
+    segments::{SegmentsError, SegmentsSnapshot, check_length},
+    spatial_index::{IndexKind, OccupancyStats, SpatialIndex},
+    spawn_schedule::SpawnSchedule,
+    symmetry::Symmetry,
+};
+
+/// how much harder passive vertices push than active ones, so that a fixed
+/// obstacle holds against the pressure of a whole growing line instead of
+/// being pushed through like another line would be.
+const OBSTACLE_REPULSION: f64 = 10.;
+
+/// how the spawn pass picks the edges to split.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SpawnMode {
+    /// every edge splits with the spawn probability, scaled by the weights
+    /// of its vertices.
+    #[default]
+    Uniform,
+    /// like [`Self::Uniform`], also scaled by the curvature of the edge
+    /// relative to the most curved edge, so that growth concentrates where
+    /// the line already bends the most.
+    Curvature,
+}
+
+/// what happens to vertices that come within the boundary margin of the
+/// edge of the unit square.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BoundaryMode {
+    /// the simulation stops.
+    #[default]
+    Stop,
+    /// vertices are held at the margin.
+    Clamp,
+    /// vertices are mirrored back inside across the margin.
+    Reflect,
+    /// vertices leave through one side and come back through the opposite
+    /// one. edges that would then span the square are cut. neighbors are
+    /// not searched across the sides, so lines on opposite sides do not
+    /// repel each other.
+    Wrap,
+    /// vertices are deleted, splitting the line they are on.
+    Kill,
+}
+
+#[derive(Clone)]
+pub(super) struct DifferentialLine {
+    pub(super) segments: super::segments::Segments,
+
+    /// the closest comfortable distance between two vertices.
+    near_l: f64,
+    /// the distance beyond which disconnected vertices will ignore each other
+    far_l: f64,
+
+    /// constraints that are enforced after every step.
+    pub(super) constraints: Constraints,
+
+    /// the region vertices are kept in, at least `boundary_margin` inside
+    /// its edge.
+    domain: Domain,
+    pub(super) boundary_margin: f64,
+    pub(super) boundary_mode: BoundaryMode,
+
+    /// cached neighbors, used instead of querying the spatial index for
+    /// every vertex on every step when enabled.
+    neighbor_lists: Option<NeighborLists>,
+
+    /// source of randomness for spawn decisions. seeded, so that a run can
+    /// be reproduced.
+    pub(super) rng: SmallRng,
+    pub(super) spawn_mode: SpawnMode,
+    /// fraction of the difference of the lengths of its edges that every
+    /// vertex moves along the line per step to even them out, off at 0.
+    pub(super) equalize: f64,
+    /// how strongly vertices resist the line bending at them, for segments
+    /// without a stiffness of their own.
+    pub(super) stiffness: f64,
+    /// stiffness of each segment by segment index, if it has its own.
+    segment_stiffness: Vec<Option<f64>>,
+    /// how many branches deep each segment is by segment index, 0 for the
+    /// seeds and anything past the end.
+    segment_generation: Vec<u32>,
+    /// how far active vertices were moved by the forces of the last step,
+    /// on average.
+    pub(super) displacement: f64,
+    /// whether a vertex is kept where it is instead of moved by the forces
+    /// of a step when one of its edges would then cross another edge.
+    pub(super) strict_topology: bool,
+    /// forces placed in the simulation space, summed on every vertex.
+    pub(super) fields: Vec<Field>,
+    /// the force of the cursor, on top of the placed fields.
+    pub(super) cursor: Option<Field>,
+    /// flow field that vertices are pushed along, `noise_amplitude` steps
+    /// per step, off at 0.
+    noise: Noise,
+    pub(super) noise_amplitude: f64,
+    /// distance in the simulation space that the noise changes over.
+    pub(super) noise_scale: f64,
+    /// which edges the next spawn pass considers in
+    /// [`SpawnMode::Uniform`].
+    pub(super) spawn_schedule: SpawnSchedule,
+    /// symmetry, and its center, that the moves of each step are kept to.
+    pub(super) symmetry: Option<(Symmetry, DVec2)>,
+
+    /// how far each vertex is moved by the step being taken, by vertex
+    /// index, as many as there are vertex slots in use.
+    sx: Vec<f64>,
+    sy: Vec<f64>,
+}
+
+/// what the steps of a [`DifferentialLine`] change, taken by
+/// [`DifferentialLine::checkpoint`] to be put back by
+/// [`DifferentialLine::restore`]. the settings, fields and cursor are not
+/// part of it, and stay as they are.
+#[derive(Clone)]
+pub(super) struct Checkpoint {
+    segments: SegmentsSnapshot,
+    constraints: Constraints,
+    /// kept so that a restored run finds the neighbors in the same order,
+    /// and grows the same.
+    neighbor_lists: Option<NeighborLists>,
+    rng: SmallRng,
+    spawn_schedule: SpawnSchedule,
+    segment_stiffness: Vec<Option<f64>>,
+    segment_generation: Vec<u32>,
+    displacement: f64,
+}
+
+//===================================================================
+// Constructors
+//===================================================================
+
+impl DifferentialLine {
+    /// growth inside `domain`, choosing the structure used for neighbor
+    /// search. the zone map covers the bounding box of the domain. the
+    /// zone width and distances are finite and positive.
+    pub(super) fn with_domain(
+        n_max: u64,
+        zone_width: f64,
+        near_l: f64,
+        far_l: f64,
+        seed: u64,
+        index_kind: IndexKind,
+        domain: Domain,
+    ) -> Result<Self, SegmentsError> {
+        check_length("near_l", near_l)?;
+        check_length("far_l", far_l)?;
+        let (origin, size) = domain.bounds();
+
+        Ok(Self {
+            segments: super::segments::Segments::new(
+                n_max, zone_width, index_kind, origin, size,
+            )?,
+            near_l,
+            far_l,
+            constraints: Constraints::default(),
+            domain,
+            boundary_margin: 0.,
+            boundary_mode: BoundaryMode::default(),
+            neighbor_lists: None,
+            rng: SmallRng::seed_from_u64(seed),
+            spawn_mode: SpawnMode::default(),
+            equalize: 0.,
+            stiffness: 0.,
+            segment_stiffness: Vec::new(),
+            segment_generation: Vec::new(),
+            displacement: 0.,
+            strict_topology: false,
+            fields: Vec::new(),
+            cursor: None,
+            noise: Noise::new(seed),
+            noise_amplitude: 0.,
+            noise_scale: 1.,
+            spawn_schedule: SpawnSchedule::new(),
+            symmetry: None,
+            sx: Vec::new(),
+            sy: Vec::new(),
+        })
+    }
+}
+
+//===================================================================
+// Helpers
+//===================================================================
+
+/// the point on the polyline through `xys` that is closest to `p`.
+pub(super) fn closest_point_on_polyline(p: DVec2, xys: &[DVec2]) -> DVec2 {
+    let mut closest = xys[0];
+    let mut min_dist2 = f64::INFINITY;
+
+    for ab in xys.windows(2) {
+        let (a, b) = (ab[0], ab[1]);
+        let ab = b - a;
+        let len2 = ab.length_squared();
+        let t = if len2 > 0. {
+            ((p - a).dot(ab) / len2).clamp(0., 1.)
+        } else {
+            0.
+        };
+
+        let q = a + t * ab;
+        let dist2 = p.distance_squared(q);
+        if dist2 < min_dist2 {
+            min_dist2 = dist2;
+            closest = q;
+        }
+    }
+
+    closest
+}
+
+/// whether the segments `a`-`b` and `c`-`d` cross, touching at a point
+/// counting as crossing.
+pub(super) fn segments_cross(a: DVec2, b: DVec2, c: DVec2, d: DVec2) -> bool {
+    let side = |p: DVec2, q: DVec2, r: DVec2| (q - p).perp_dot(r - p);
+    let (d1, d2) = (side(c, d, a), side(c, d, b));
+    let (d3, d4) = (side(a, b, c), side(a, b, d));
+
+    if d1 * d2 < 0. && d3 * d4 < 0. {
+        return true;
+    }
+
+    // collinear: touching if an end lies on the other segment
+    let on = |p: DVec2, q: DVec2, r: DVec2| {
+        r.cmpge(p.min(q)).all() && r.cmple(p.max(q)).all()
+    };
+    (d1 == 0. && on(c, d, a))
+        || (d2 == 0. && on(c, d, b))
+        || (d3 == 0. && on(a, b, c))
+        || (d4 == 0. && on(a, b, d))
+}
+
+//===================================================================
+// Private Methods
+//===================================================================
+
+impl DifferentialLine {
+    /// all vertices will move away from all neighboring (closer than farl)
+    /// vertices
+    ///
+    /// TODO: are `vertices`, `sx`, and `sy` not from `self` ??
+    fn reject(
+        &mut self,
+        v: VertexId,
+        vertices: &[i64],
+        n_vertices: usize,
+        step: f64,
+    ) -> Result<bool, SegmentsError> {
+        if !self.segments.vertex_active(v) {
+            return Ok(false);
+        }
+
+        // the ends of an open segment only have one linked vertex
+        let [v1, v2] = self
+            .segments
+            .edges_of(v)?
+            .map(|e| self.segments.other_vertex(e?, v));
+
+        let p = self.segments.vertex_position(v)?;
+        let mut res = DVec2::ZERO;
+
+        // bending: pulled towards the middle of the linked vertices, the
+        // more the sharper the line turns at v, from nothing where it is
+        // straight to the full stiffness where it folds back on itself
+        let stiffness =
+            self.segment_stiffness(self.segments.vertex_segment(v)?);
+        if let (true, Some(v1), Some(v2)) = (stiffness > 0., v1, v2) {
+            let (p1, p2) = (
+                self.segments.vertex_position(v1)?,
+                self.segments.vertex_position(v2)?,
+            );
+            let (a, b) =
+                ((p1 - p).normalize_or_zero(), (p2 - p).normalize_or_zero());
+            let turn = 0.5 * (1. + a.dot(b));
+            let middle = 0.5 * (p1 + p2) - p;
+            res += stiffness * step * turn * middle.normalize_or_zero();
+        }
+
+        res += step
+            * fields::total_force(self.fields.iter().chain(&self.cursor), p);
+
+        if self.noise_amplitude > 0. && self.noise_scale > 0. {
+            res += self.noise_amplitude
+                * step
+                * self.noise.flow(p / self.noise_scale);
+        }
+
+        for &neighbor in vertices.iter().take(n_vertices) {
+            let Some(neighbor) = self.segments.vertex_id(neighbor) else {
+                continue;
+            };
+            let d = p - self.segments.vertex_position(neighbor)?;
+            let norm = d.length();
+
+            if Some(neighbor) == v1 || Some(neighbor) == v2 {
+                // linked
+
+                if norm < self.near_l || norm <= 0. {
+                    continue;
+                }
+
+                res += step * -d / norm;
+            } else {
+                // not linked
+
+                if norm > self.far_l || norm <= 0. {
+                    continue;
+                }
+
+                let strength = if self.segments.vertex_active(neighbor) {
+                    1.
+                } else {
+                    OBSTACLE_REPULSION
+                };
+                res += strength * step * d * (self.far_l / norm - 1.);
+            }
+        }
+
+        let v = v.slot() as usize;
+        self.sx[v] += res.x;
+        self.sy[v] += res.y;
+
+        Ok(true)
+    }
+
+    /// whether vertex v can move to `q` without its edges crossing an edge
+    /// they do not share a vertex with. edges are found with the spatial
+    /// index, among those with a vertex close enough to v for them to reach
+    /// the new edges, being no longer than `longest_edge`.
+    fn keeps_topology(
+        &self,
+        v: VertexId,
+        q: DVec2,
+        longest_edge: f64,
+        vertices: &mut Vec<i64>,
+    ) -> Result<bool, SegmentsError> {
+        let mut linked = Vec::with_capacity(2);
+        for e in self.segments.edges_of(v)?.into_iter().flatten() {
+            if let Some(w) = self.segments.other_vertex(e, v) {
+                linked.push((w, self.segments.vertex_position(w)?));
+            }
+        }
+        let reach = linked
+            .iter()
+            .map(|&(_, pw)| q.distance(pw))
+            .fold(0., f64::max);
+        let p = self.segments.vertex_position(v)?;
+        let rad = p.distance(q) + reach + longest_edge;
+
+        let n_vertices = self.segments.index.sphere_vertices(
+            v.slot(),
+            &self.segments.x,
+            &self.segments.y,
+            rad,
+            vertices,
+        );
+        for &u in &vertices[..n_vertices] {
+            let Some(u) = self.segments.vertex_id(u) else {
+                continue;
+            };
+            for e in self.segments.edges_of(u)?.into_iter().flatten() {
+                let [a, b] = self.segments.edge_ends(e)?;
+                if a == v || b == v {
+                    continue;
+                }
+                let (pa, pb) = (
+                    self.segments.vertex_position(a)?,
+                    self.segments.vertex_position(b)?,
+                );
+                for &(w, pw) in &linked {
+                    if a != w && b != w && segments_cross(q, pw, pa, pb) {
+                        return Ok(false);
+                    }
+                }
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// enforce the boundary mode on every active vertex outside the
+    /// boundary margin of the unit square.
+    fn apply_boundary(&mut self) -> Result<(), SegmentsError> {
+        let (lo, hi) = (self.boundary_margin, 1. - self.boundary_margin);
+        if self.boundary_mode == BoundaryMode::Stop || lo >= hi {
+            return Ok(());
+        }
+
+        let (min, max) = (DVec2::splat(lo), DVec2::splat(hi));
+        let side = hi - lo;
+        let mut wrapped = Vec::new();
+
+        let active = self.segments.active_vertex_ids().collect::<Vec<_>>();
+        for v in active {
+            // killed along with a vertex it was linked to
+            let Ok(p) = self.segments.vertex_position(v) else {
+                continue;
+            };
+            if p.cmpge(min).all() && p.cmple(max).all() {
+                continue;
+            }
+
+            let q = match self.boundary_mode {
+                BoundaryMode::Stop => continue,
+                BoundaryMode::Clamp => p.clamp(min, max),
+                // mirror across the side that was crossed, clamped in case
+                // the vertex moved further than the square is wide
+                BoundaryMode::Reflect => {
+                    let q = DVec2::select(p.cmplt(min), 2. * min - p, p);
+                    let q = DVec2::select(q.cmpgt(max), 2. * max - q, q);
+                    q.clamp(min, max)
+                }
+                BoundaryMode::Wrap => {
+                    wrapped.push(v);
+                    min + (p - min).rem_euclid(DVec2::splat(side))
+                }
+                BoundaryMode::Kill => {
+                    self.unpin_vertex(v);
+                    self.segments.remove(v)?;
+                    continue;
+                }
+            };
+
+            self.segments.set_vertex_position(v, q)?;
+        }
+
+        // edges of wrapped vertices that now reach across the square. checked
+        // once every vertex has moved, so that an edge which crossed as a
+        // whole is kept
+        for v in wrapped {
+            for e in self.segments.edges_of(v)?.into_iter().flatten() {
+                if self.segments.get_edge_length(e)? > side / 2. {
+                    self.segments.cut(e)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+//===================================================================
+// Public Methods
+//===================================================================
+
+impl DifferentialLine {
+    /// initialize a line segment through `xys` where each vertex spawns new
+    /// vertices in proportion to the matching entry in `weights`, returning
+    /// its vertices. if `closed`, the last vertex is joined back to the
+    /// first.
+    pub(super) fn init_weighted_line_segment(
+        &mut self,
+        xys: &[DVec2],
+        weights: &[f64],
+        closed: bool,
+    ) -> Result<Vec<VertexId>, SegmentsError> {
+        let vertices = if closed {
+            self.segments.init_closed_line_segment(xys)?
+        } else {
+            self.segments.init_line_segment(xys, false)?
+        };
+
+        for (&v, &weight) in vertices.iter().zip(weights) {
+            self.segments.set_vertex_weight(v, weight)?;
+        }
+
+        Ok(vertices)
+    }
+
+    /// initialize a spine through the open polyline `xys`: a line along it
+    /// with passive ends, where each vertex spawns new vertices in
+    /// proportion to the matching entry in `weights`, and that is kept
+    /// within `half_width` of the polyline as it grows.
+    pub(super) fn init_spine(
+        &mut self,
+        xys: &[DVec2],
+        weights: &[f64],
+        half_width: f64,
+    ) -> Result<(), SegmentsError> {
+        let s = self.segments.s_num() as i64;
+        let vertices = self.segments.init_line_segment(xys, true)?;
+
+        for (&v, &weight) in vertices.iter().zip(weights) {
+            self.segments.set_vertex_weight(v, weight)?;
+        }
+        self.constraints.push(Constraint::Corridor {
+            s,
+            spine: xys.to_vec(),
+            half_width,
+        });
+
+        Ok(())
+    }
+
+    /// initialize a branch through the open polyline `xys`, a line segment
+    /// of its own weighted like [`Self::init_weighted_line_segment`], whose
+    /// first vertex is kept where it is relative to vertex `from` of the
+    /// line it sprouts from. it is not added if any of `xys` is outside the
+    /// domain or within the boundary margin of the edge of the unit square.
+    pub(super) fn init_branch(
+        &mut self,
+        from: VertexId,
+        xys: &[DVec2],
+        weights: &[f64],
+    ) -> Result<(), SegmentsError> {
+        let margin = self.boundary_margin;
+        let (min, max) = (DVec2::splat(margin), DVec2::splat(1. - margin));
+        let inside = |p: DVec2| {
+            p.clamp(min, max) == p && self.domain.contain(p, margin) == p
+        };
+        if !xys.iter().all(|&p| inside(p)) {
+            return Ok(());
+        }
+
+        let parent = self.segments.vertex_segment(from)?;
+        let length = self.segments.vertex_position(from)?.distance(xys[0]);
+        let root = self.init_weighted_line_segment(xys, weights, false)?[0];
+        let s = self.segments.vertex_segment(root)?;
+        // branches are as stiff as the line they sprout from
+        if let Some(&Some(stiffness)) =
+            self.segment_stiffness.get(parent as usize)
+        {
+            self.set_segment_stiffness(s, Some(stiffness));
+        }
+        let generation = self.segment_generation(parent) + 1;
+        let s = s as usize;
+        if self.segment_generation.len() <= s {
+            self.segment_generation.resize(s + 1, 0);
+        }
+        self.segment_generation[s] = generation;
+        self.constraints.push(Constraint::Stem {
+            v: root,
+            to: from,
+            length,
+        });
+        Ok(())
+    }
+
+    /// initialize a fixed obstacle through `xys`, made of passive vertices
+    /// that repel the growing line but never move or split. if `closed`,
+    /// the last vertex is joined back to the first.
+    pub(super) fn init_obstacle(
+        &mut self,
+        xys: &[DVec2],
+        closed: bool,
+    ) -> Result<(), SegmentsError> {
+        if closed {
+            self.segments.init_passive_closed_line_segment(xys)?;
+        } else {
+            self.segments.init_passive_line_segment(xys)?;
+        }
+        Ok(())
+    }
+
+    /// how strongly vertices of segment s resist the line bending at them.
+    pub(super) fn segment_stiffness(&self, s: i64) -> f64 {
+        match self.segment_stiffness.get(s as usize) {
+            Some(&Some(stiffness)) => stiffness,
+            _ => self.stiffness,
+        }
+    }
+
+    /// give segment s a stiffness of its own, or with `None` the one of
+    /// every other segment.
+    pub(super) fn set_segment_stiffness(
+        &mut self,
+        s: i64,
+        stiffness: Option<f64>,
+    ) {
+        let s = s as usize;
+        if self.segment_stiffness.len() <= s {
+            self.segment_stiffness.resize(s + 1, None);
+        }
+        self.segment_stiffness[s] = stiffness;
+    }
+
+    /// how many branches deep segment s is: 0 for a seed, 1 for a branch
+    /// of a seed, and so on.
+    pub(super) fn segment_generation(&self, s: i64) -> u32 {
+        self.segment_generation
+            .get(s as usize)
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// occupancy of the spatial index, with query counts since the last
+    /// call.
+    pub(super) fn take_index_stats(&self) -> OccupancyStats {
+        let stats = self.segments.index.occupancy_stats();
+        self.segments.index.reset_query_stats();
+        stats
+    }
+
+    pub(super) fn set_distances(&mut self, near_l: f64, far_l: f64) {
+        if far_l != self.far_l {
+            if let Some(neighbor_lists) = &mut self.neighbor_lists {
+                neighbor_lists.invalidate();
+            }
+        }
+
+        self.near_l = near_l;
+        self.far_l = far_l;
+    }
+
+    /// cache neighbor lists with the given skin radius, or query the spatial
+    /// index on every step if 0. the lists are kept while the skin stays
+    /// the same.
+    pub(super) fn set_neighbor_skin(&mut self, skin: f64) {
+        let current = self
+            .neighbor_lists
+            .as_ref()
+            .map_or(0., |lists| lists.skin());
+        if skin != current {
+            self.neighbor_lists =
+                (skin > 0.).then(|| NeighborLists::new(skin));
+        }
+    }
+
+    /// keep the total length of the edges of every segment under
+    /// `max_length`, off at 0, and the active vertices within the rectangle
+    /// between the corners of `region`, off for the whole unit square. they
+    /// replace the limits set before, and apply after every other
+    /// constraint.
+    pub(super) fn set_limits(&mut self, max_length: f64, region: [DVec2; 2]) {
+        self.constraints.retain(|c| {
+            !matches!(
+                c,
+                Constraint::MaxLength { .. } | Constraint::Region { .. }
+            )
+        });
+        if max_length > 0. {
+            self.constraints.push(Constraint::MaxLength {
+                max_len: max_length,
+            });
+        }
+        if region != [DVec2::ZERO, DVec2::ONE] {
+            let [a, b] = region;
+            self.constraints.push(Constraint::Region {
+                min: a.min(b),
+                max: a.max(b),
+            });
+        }
+    }
+
+    /// pin vertex v to `pos`, replacing any existing anchor of v. the pin
+    /// lapses once v is deleted, even if another vertex takes its slot.
+    pub(super) fn pin_vertex(&mut self, v: VertexId, pos: DVec2) {
+        self.unpin_vertex(v);
+        if self.segments.vertex_exists(v) {
+            self.constraints.push(Constraint::Fixed { v, pos });
+        }
+    }
+
+    pub(super) fn unpin_vertex(&mut self, v: VertexId) {
+        self.constraints.retain(
+            |c| !matches!(*c, Constraint::Fixed { v: v1, .. } if v1 == v),
+        );
+    }
+
+    /// move every vertex and edge into the lowest slots, see
+    /// [`Segments::compact`], bringing along everything that refers to
+    /// them.
+    ///
+    /// [`Segments::compact`]: super::segments::Segments::compact
+    pub(super) fn compact(&mut self) {
+        let remap = self.segments.compact();
+        self.constraints.remap(&remap);
+        self.spawn_schedule.remap(&remap);
+        if let Some(neighbor_lists) = &mut self.neighbor_lists {
+            neighbor_lists.invalidate();
+        }
+    }
+
+    /// capture the state of the growth, see [`Checkpoint`].
+    pub(super) fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            segments: self.segments.snapshot(),
+            constraints: self.constraints.clone(),
+            neighbor_lists: self.neighbor_lists.clone(),
+            rng: self.rng.clone(),
+            spawn_schedule: self.spawn_schedule.clone(),
+            segment_stiffness: self.segment_stiffness.clone(),
+            segment_generation: self.segment_generation.clone(),
+            displacement: self.displacement,
+        }
+    }
+
+    /// put back the growth captured by `checkpoint`. cached neighbor lists
+    /// are only taken from it if they have the skin set now.
+    pub(super) fn restore(
+        &mut self,
+        checkpoint: &Checkpoint,
+    ) -> Result<(), SegmentsError> {
+        self.segments.restore(&checkpoint.segments)?;
+        self.constraints.clone_from(&checkpoint.constraints);
+        if let Some(neighbor_lists) = &mut self.neighbor_lists {
+            match &checkpoint.neighbor_lists {
+                Some(lists) if lists.skin() == neighbor_lists.skin() => {
+                    neighbor_lists.clone_from(lists);
+                }
+                _ => neighbor_lists.invalidate(),
+            }
+        }
+        self.rng.clone_from(&checkpoint.rng);
+        self.spawn_schedule.clone_from(&checkpoint.spawn_schedule);
+        self.segment_stiffness
+            .clone_from(&checkpoint.segment_stiffness);
+        self.segment_generation
+            .clone_from(&checkpoint.segment_generation);
+        self.displacement = checkpoint.displacement;
+        Ok(())
+    }
+
+    /// pin each of `vertices` to the closest point on the polyline through
+    /// `xys`, e.g. a shape drawn by the user as a scaffold.
+    pub(super) fn anchor_to_polyline(
+        &mut self,
+        vertices: &[VertexId],
+        xys: &[DVec2],
+    ) -> Result<(), SegmentsError> {
+        if xys.is_empty() {
+            return Ok(());
+        }
+
+        for &v in vertices {
+            let p = self.segments.vertex_position(v)?;
+            self.pin_vertex(v, closest_point_on_polyline(p, xys));
+        }
+
+        Ok(())
+    }
+
+    /// move every active vertex along the line, towards the longer of its
+    /// edges, by `equalize` of half the difference of their lengths. the
+    /// vertex moves parallel to the chord between its linked vertices, so
+    /// the spacing evens out while the shape of the line barely changes.
+    fn equalize_edges(&mut self) -> Result<(), SegmentsError> {
+        self.sx.fill(0.);
+        self.sy.fill(0.);
+
+        for v in self.segments.active_vertex_ids() {
+            let Some([v1, v2]) = self.segments.linked_vertices(v) else {
+                continue;
+            };
+
+            let p = self.segments.vertex_position(v)?;
+            let (p1, p2) = (
+                self.segments.vertex_position(v1)?,
+                self.segments.vertex_position(v2)?,
+            );
+            let Some(tangent) = (p2 - p1).try_normalize() else {
+                continue;
+            };
+
+            let shift =
+                0.5 * self.equalize * (p.distance(p2) - p.distance(p1));
+            self.sx[v.slot() as usize] = shift * tangent.x;
+            self.sy[v.slot() as usize] = shift * tangent.y;
+        }
+
+        for v in 0..self.segments.v_num() as usize {
+            self.segments.x[v] += self.sx[v];
+            self.segments.y[v] += self.sy[v];
+        }
+
+        Ok(())
+    }
+
+    /// move every active vertex by the average of its own move and those
+    /// of its images under `symmetry` about `center`, mapped back, so that
+    /// a line seeded symmetrically grows on symmetrically. the image of a
+    /// vertex is the closest active vertex within `near_l` of where it maps
+    /// to; images without one are left out.
+    fn symmetrize(
+        &mut self,
+        symmetry: Symmetry,
+        center: DVec2,
+        vertices: &mut Vec<i64>,
+    ) -> Result<(), SegmentsError> {
+        let maps = symmetry.maps();
+        let mut moves = Vec::with_capacity(self.sx.len());
+        for v in 0..self.segments.v_num() as i64 {
+            let s = DVec2::new(self.sx[v as usize], self.sy[v as usize]);
+            let active = self.segments.vertex_id(v);
+            let Some(v) = active.filter(|&v| self.segments.vertex_active(v))
+            else {
+                moves.push(s);
+                continue;
+            };
+            let p = self.segments.vertex_position(v)?;
+            let (mut sum, mut count) = (p + s, 1.);
+            for m in &maps[1..] {
+                let q = center + *m * (p - center);
+                let n_vertices = self.segments.index.sphere_at(
+                    q.x,
+                    q.y,
+                    &self.segments.x,
+                    &self.segments.y,
+                    self.near_l,
+                    vertices,
+                );
+                let image = vertices[..n_vertices]
+                    .iter()
+                    .filter_map(|&u| self.segments.vertex_id(u))
+                    .filter(|&u| self.segments.vertex_active(u))
+                    .filter_map(|u| {
+                        let p = self.segments.vertex_position(u).ok()?;
+                        Some((u.slot() as usize, p))
+                    })
+                    .min_by(|(_, a), (_, b)| {
+                        a.distance(q).total_cmp(&b.distance(q))
+                    });
+                if let Some((u, p_u)) = image {
+                    let moved = p_u + DVec2::new(self.sx[u], self.sy[u]);
+                    sum += center + m.inverse() * (moved - center);
+                    count += 1.;
+                }
+            }
+            moves.push(sum / count - p);
+        }
+        for (v, s) in moves.into_iter().enumerate() {
+            self.sx[v] = s.x;
+            self.sy[v] = s.y;
+        }
+
+        Ok(())
+    }
+
+    pub(super) fn optimize_position(
+        &mut self,
+        step: f64,
+    ) -> Result<(), SegmentsError> {
+        let mut vertices = Vec::<i64>::with_capacity(
+            self.segments.index.get_max_sphere_count() as usize,
+        );
+        let v_num = self.segments.v_num() as usize;
+        self.sx.resize(v_num, 0.);
+        self.sy.resize(v_num, 0.);
+
+        if let Some(neighbor_lists) = &mut self.neighbor_lists {
+            neighbor_lists.refresh(&self.segments, self.far_l);
+        }
+
+        for v in 0..self.segments.v_num() as i64 {
+            self.sx[v as usize] = 0.;
+            self.sy[v as usize] = 0.;
+            let Some(id) = self.segments.vertex_id(v) else {
+                continue;
+            };
+
+            let n_vertices = match &self.neighbor_lists {
+                Some(neighbor_lists) => {
+                    neighbor_lists.neighbors(v, &self.segments, &mut vertices)
+                }
+                None => self.segments.index.sphere_vertices(
+                    v,
+                    &self.segments.x,
+                    &self.segments.y,
+                    self.far_l,
+                    &mut vertices,
+                ),
+            };
+
+            self.reject(id, &vertices, n_vertices, step)?;
+        }
+
+        if let Some((symmetry, center)) = self.symmetry {
+            self.symmetrize(symmetry, center, &mut vertices)?;
+        }
+
+        // vertices move one at a time, each checked against the others as
+        // they are, so that the moves kept never cross each other either
+        let longest_edge = self.strict_topology.then(|| {
+            self.segments
+                .edge_ids()
+                .filter_map(|e| self.segments.get_edge_length(e).ok())
+                .fold(0., f64::max)
+        });
+
+        let (mut moved, mut active) = (0., 0);
+        for v in 0..self.segments.v_num() as usize {
+            let Some(id) = self.segments.vertex_id(v as i64) else {
+                continue;
+            };
+
+            if let Some(longest_edge) = longest_edge {
+                let s = DVec2::new(self.sx[v], self.sy[v]);
+                let q = self.segments.vertex_position(id)? + s;
+                if s != DVec2::ZERO
+                    && !self.keeps_topology(
+                        id,
+                        q,
+                        longest_edge,
+                        &mut vertices,
+                    )?
+                {
+                    self.sx[v] = 0.;
+                    self.sy[v] = 0.;
+                }
+            }
+
+            self.segments.x[v] += self.sx[v];
+            self.segments.y[v] += self.sy[v];
+            if longest_edge.is_some() {
+                // the zones of the vertices checked next stay right
+                self.segments.index.update_vertex(
+                    v as i64,
+                    self.segments.x[v],
+                    self.segments.y[v],
+                );
+            }
+            if self.segments.vertex_active(id) {
+                moved += self.sx[v].hypot(self.sy[v]);
+                active += 1;
+            }
+        }
+        self.displacement = moved / active.max(1) as f64;
+
+        if self.equalize > 0. {
+            self.equalize_edges()?;
+        }
+
+        self.constraints.apply(&mut self.segments)?;
+        self.domain
+            .apply(&mut self.segments, self.boundary_margin)?;
+        self.apply_boundary()?;
+        self.segments.update_index();
+
+        Ok(())
+    }
+}
@@ -0,0 +1,246 @@
+use glam::DVec2;
+
+use super::segments::{Segments, SegmentsError};
+
+/// the region of the unit square the line grows in.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum Domain {
+    /// the whole unit square. vertices that come within the boundary margin
+    /// of its edge are handled by the boundary mode.
+    #[default]
+    Square,
+    /// a disk. vertices are kept at least the boundary margin inside its
+    /// edge.
+    Circle { center: DVec2, radius: f64 },
+    /// the inside of a closed polygon, which does not repeat its first
+    /// point. vertices are kept at least the boundary margin inside its
+    /// edges.
+    Polygon(Vec<DVec2>),
+}
+
+//===================================================================
+// Helpers
+//===================================================================
+
+/// the closest point to `p` on the edges of the closed polygon `points`.
+fn closest_point_on_outline(p: DVec2, points: &[DVec2]) -> DVec2 {
+    let n = points.len();
+    let mut closest = points[0];
+    let mut min_dist2 = f64::INFINITY;
+
+    for i in 0..n {
+        let (a, b) = (points[i], points[(i + 1) % n]);
+        let ab = b - a;
+        let len2 = ab.length_squared();
+        let t = if len2 > 0. {
+            ((p - a).dot(ab) / len2).clamp(0., 1.)
+        } else {
+            0.
+        };
+
+        let q = a + t * ab;
+        let dist2 = p.distance_squared(q);
+        if dist2 < min_dist2 {
+            min_dist2 = dist2;
+            closest = q;
+        }
+    }
+
+    closest
+}
+
+/// whether `p` is inside the closed polygon `points`, by the even-odd rule.
+fn polygon_contains(p: DVec2, points: &[DVec2]) -> bool {
+    let n = points.len();
+    let mut inside = false;
+
+    for i in 0..n {
+        let (a, b) = (points[i], points[(i + 1) % n]);
+        if (a.y > p.y) != (b.y > p.y) {
+            let x = a.x + (p.y - a.y) / (b.y - a.y) * (b.x - a.x);
+            if p.x < x {
+                inside = !inside;
+            }
+        }
+    }
+
+    inside
+}
+
+//===================================================================
+// Public Methods
+//===================================================================
+
+impl Domain {
+    /// lower corner and side length of the square the zone map covers. the
+    /// bounding box of the domain, extended along its shorter side.
+    pub(super) fn bounds(&self) -> (DVec2, f64) {
+        let (min, max) = match self {
+            Self::Square => (DVec2::ZERO, DVec2::ONE),
+            Self::Circle { center, radius } => {
+                (center - radius, center + radius)
+            }
+            Self::Polygon(points) if !points.is_empty() => points.iter().fold(
+                (DVec2::INFINITY, DVec2::NEG_INFINITY),
+                |(min, max), &p| (min.min(p), max.max(p)),
+            ),
+            Self::Polygon(_) => (DVec2::ZERO, DVec2::ONE),
+        };
+
+        let size = (max - min).max_element();
+        let origin = (min + max - size) / 2.;
+        (origin, size)
+    }
+
+    /// the point closest to `p` that is at least `margin` inside the domain,
+    /// or `p` itself if it already is. the unit square is not enforced here,
+    /// see [`BoundaryMode`](super::BoundaryMode).
+    pub(super) fn contain(&self, p: DVec2, margin: f64) -> DVec2 {
+        match self {
+            Self::Square => p,
+            Self::Circle { center, radius } => {
+                let limit = (radius - margin).max(0.);
+                let d = p - center;
+                if d.length() <= limit {
+                    p
+                } else {
+                    center + d.normalize_or_zero() * limit
+                }
+            }
+            Self::Polygon(points) if points.len() >= 3 => {
+                let q = closest_point_on_outline(p, points);
+                let inside = polygon_contains(p, points);
+                if inside && p.distance(q) >= margin {
+                    return p;
+                }
+
+                // move from the outline towards the inside
+                let inward = if inside { p - q } else { q - p };
+                q + inward.normalize_or_zero() * margin
+            }
+            Self::Polygon(_) => p,
+        }
+    }
+
+    /// move every active vertex of `segments` inside the domain.
+    pub(super) fn apply(
+        &self,
+        segments: &mut Segments,
+        margin: f64,
+    ) -> Result<(), SegmentsError> {
+        if *self == Self::Square {
+            return Ok(());
+        }
+
+        let active = segments.active_vertex_ids().collect::<Vec<_>>();
+        for v in active {
+            let p = self.contain(segments.vertex_position(v)?, margin);
+            segments.set_vertex_position(v, p)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::{Params, Primitive, PrimitiveKind, Simulation};
+
+    const EPSILON: f64 = 1e-12;
+
+    /// an L of two unit squares side by side and one on top of the left.
+    fn l_shape() -> Domain {
+        Domain::Polygon(
+            [(0., 0.), (2., 0.), (2., 1.), (1., 1.), (1., 2.), (0., 2.)]
+                .map(|(x, y)| DVec2::new(x, y) / 4.)
+                .to_vec(),
+        )
+    }
+
+    #[test]
+    fn zone_maps_cover_the_bounds_of_the_domain() {
+        assert_eq!(Domain::Square.bounds(), (DVec2::ZERO, 1.));
+        let circle = Domain::Circle {
+            center: DVec2::new(0.5, 0.3),
+            radius: 0.2,
+        };
+        let (origin, size) = circle.bounds();
+        assert!(origin.distance(DVec2::new(0.3, 0.1)) < EPSILON);
+        assert!((size - 0.4).abs() < EPSILON);
+
+        // extended along the shorter side, about the middle
+        let wide = Domain::Polygon(vec![
+            DVec2::new(0.1, 0.2),
+            DVec2::new(0.5, 0.2),
+            DVec2::new(0.5, 0.4),
+        ]);
+        let (origin, size) = wide.bounds();
+        assert!(origin.distance(DVec2::new(0.1, 0.1)) < EPSILON);
+        assert!((size - 0.4).abs() < EPSILON);
+        assert_eq!(l_shape().bounds(), (DVec2::ZERO, 0.5));
+    }
+
+    #[test]
+    fn points_are_kept_a_margin_inside() {
+        let margin = 0.01;
+        let circle = Domain::Circle {
+            center: DVec2::splat(0.5),
+            radius: 0.2,
+        };
+        let inside = DVec2::new(0.6, 0.5);
+        assert_eq!(circle.contain(inside, margin), inside);
+        let moved = circle.contain(DVec2::new(0.9, 0.5), margin);
+        assert!(moved.distance(DVec2::new(0.69, 0.5)) < EPSILON);
+
+        let l = l_shape();
+        let inside = DVec2::new(0.1, 0.1);
+        assert_eq!(l.contain(inside, margin), inside);
+        // in the notch of the L, outside it
+        let moved = l.contain(DVec2::new(0.4, 0.3), margin);
+        assert!(moved.distance(DVec2::new(0.4, 0.24)) < EPSILON);
+        // inside, but closer to an edge than the margin
+        let moved = l.contain(DVec2::new(0.1, 0.495), margin);
+        assert!(moved.distance(DVec2::new(0.1, 0.49)) < EPSILON);
+
+        // the unit square is left to the boundary mode
+        let outside = DVec2::new(1.5, -0.5);
+        assert_eq!(Domain::Square.contain(outside, margin), outside);
+    }
+
+    #[test]
+    fn growth_stays_inside_the_domain() {
+        let params = Params {
+            spawn_probability: 0.05,
+            ..Params::DEFAULT
+        };
+        let (center, radius) = (DVec2::splat(0.5), 0.08);
+        let domain = Domain::Circle { center, radius };
+        let mut simulation =
+            Simulation::with_domain(&params, 1, domain).unwrap();
+        simulation
+            .add_primitive(&Primitive {
+                kind: PrimitiveKind::Circle,
+                center,
+                radius: 0.05,
+                rotation: 0.,
+                n_vertices: 32,
+                passive: false,
+            })
+            .unwrap();
+
+        for _ in 0..300 {
+            simulation.step(&params).unwrap();
+        }
+
+        let limit = radius - params.boundary_margin;
+        let positions = simulation.positions();
+        let farthest = positions
+            .iter()
+            .map(|p| p.distance(center))
+            .fold(0., f64::max);
+        assert!(farthest <= limit + EPSILON, "{farthest} vs {limit}");
+        // the growth did reach the edge
+        assert!(farthest > limit - params.near_l, "{farthest} vs {limit}");
+    }
+}
@@ -0,0 +1,96 @@
+use glam::DVec2;
+
+/// the radius new fields reach out to.
+pub const DEFAULT_RADIUS: f64 = 0.1;
+
+/// how far new fields move the vertices at their center per step, in steps.
+pub const DEFAULT_STRENGTH: f64 = 2.;
+
+/// what a field does to the vertices within its reach.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FieldKind {
+    /// pulls vertices towards the center.
+    Attractor,
+    /// pushes vertices away from the center.
+    Repulsor,
+    /// pushes vertices along `angle`, in radians from the x axis.
+    Wind { angle: f64 },
+}
+
+/// a force placed in the simulation space, on the active vertices within
+/// `radius` of `position`. it fades out from the center to the radius.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Field {
+    pub kind: FieldKind,
+    pub position: DVec2,
+    pub radius: f64,
+    /// how far a vertex at the center is moved per step, in steps.
+    pub strength: f64,
+}
+
+impl Field {
+    /// a field of `kind` at `position` with the default reach and strength.
+    pub fn new(kind: FieldKind, position: DVec2) -> Self {
+        Self {
+            kind,
+            position,
+            radius: DEFAULT_RADIUS,
+            strength: DEFAULT_STRENGTH,
+        }
+    }
+
+    /// how far, in steps, and which way the field moves a vertex at `p`.
+    pub fn force(&self, p: DVec2) -> DVec2 {
+        let d = p - self.position;
+        let norm = d.length();
+        if norm >= self.radius {
+            return DVec2::ZERO;
+        }
+
+        let falloff = self.strength * (1. - norm / self.radius);
+        match self.kind {
+            // nothing at the center itself, which has no direction
+            FieldKind::Attractor => -falloff * d.normalize_or_zero(),
+            FieldKind::Repulsor => falloff * d.normalize_or_zero(),
+            FieldKind::Wind { angle } => falloff * DVec2::from_angle(angle),
+        }
+    }
+}
+
+/// the sum of the forces of `fields` on a vertex at `p`, in steps.
+pub fn total_force<'a>(
+    fields: impl IntoIterator<Item = &'a Field>,
+    p: DVec2,
+) -> DVec2 {
+    fields.into_iter().map(|field| field.force(p)).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fields_fade_out_to_their_radius() {
+        let center = DVec2::splat(0.5);
+        let p = center + DVec2::new(0.05, 0.);
+
+        let repulsor = Field::new(FieldKind::Repulsor, center);
+        assert!(repulsor.force(p).abs_diff_eq(DVec2::new(1., 0.), 1e-12));
+        assert_eq!(repulsor.force(center + DVec2::new(0.2, 0.)), DVec2::ZERO);
+
+        let attractor = Field::new(FieldKind::Attractor, center);
+        assert!(attractor.force(p).abs_diff_eq(DVec2::new(-1., 0.), 1e-12));
+        assert_eq!(attractor.force(center), DVec2::ZERO);
+
+        let wind = Field::new(
+            FieldKind::Wind {
+                angle: std::f64::consts::FRAC_PI_2,
+            },
+            center,
+        );
+        assert!(wind.force(p).abs_diff_eq(DVec2::new(0., 1.), 1e-12));
+
+        let total = total_force(&[repulsor, attractor, wind], p);
+        assert!(total.abs_diff_eq(DVec2::new(0., 1.), 1e-12));
+    }
+}
@@ -0,0 +1,146 @@
+use std::collections::VecDeque;
+
+use super::{
+    differential_line::{Checkpoint, DifferentialLine},
+    segments::SegmentsError,
+};
+
+/// bounded checkpoints of a running simulation, used to rewind it by a number
+/// of steps.
+///
+/// stepping is deterministic, so rewinding restores the newest checkpoint at
+/// or before the target iteration and replays the remaining steps.
+pub(super) struct History {
+    /// number of steps between checkpoints.
+    interval: u64,
+    /// maximum number of checkpoints kept. the oldest are dropped first.
+    capacity: usize,
+    /// checkpoints and the iteration they were taken at, oldest first.
+    checkpoints: VecDeque<(u64, Checkpoint)>,
+}
+
+//===================================================================
+// Constructors
+//===================================================================
+
+impl History {
+    pub(super) fn new(interval: u64, capacity: usize) -> Self {
+        Self {
+            interval: interval.max(1),
+            capacity,
+            checkpoints: VecDeque::with_capacity(capacity),
+        }
+    }
+}
+
+//===================================================================
+// Public Methods
+//===================================================================
+
+impl History {
+    /// the oldest iteration that can be rewound to.
+    pub(super) fn oldest(&self) -> Option<u64> {
+        self.checkpoints.front().map(|(i, _)| *i)
+    }
+
+    /// take a checkpoint of `df` if `iteration` falls on the interval.
+    pub(super) fn record(&mut self, iteration: u64, df: &DifferentialLine) {
+        if iteration % self.interval != 0 || self.capacity == 0 {
+            return;
+        }
+
+        if self.checkpoints.len() == self.capacity {
+            self.checkpoints.pop_front();
+        }
+        self.checkpoints.push_back((iteration, df.checkpoint()));
+    }
+
+    /// rewind `df` from `iteration` back by `n` steps, or as far as the
+    /// checkpoints allow. checkpoints after the new iteration are discarded.
+    ///
+    /// returns the new iteration, or `None` if there is no checkpoint to
+    /// rewind to.
+    pub(super) fn rewind(
+        &mut self,
+        df: &mut DifferentialLine,
+        iteration: u64,
+        n: u64,
+        step: impl Fn(&mut DifferentialLine) -> Result<bool, SegmentsError>,
+    ) -> Result<Option<u64>, SegmentsError> {
+        let target =
+            iteration.saturating_sub(n).max(self.oldest().unwrap_or(0));
+
+        while self.checkpoints.back().is_some_and(|(i, _)| *i > target) {
+            self.checkpoints.pop_back();
+        }
+        let Some((checkpoint_iteration, checkpoint)) = self.checkpoints.back()
+        else {
+            return Ok(None);
+        };
+
+        df.restore(checkpoint)?;
+
+        let mut i = *checkpoint_iteration;
+        while i < target {
+            if !step(df)? {
+                break;
+            }
+            i += 1;
+        }
+
+        Ok(Some(i))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::DVec2;
+
+    use crate::algorithm::{Params, Primitive, PrimitiveKind, Simulation};
+
+    #[test]
+    fn rewound_runs_grow_the_same_again() {
+        let params = Params {
+            spawn_probability: 0.1,
+            ..Params::DEFAULT
+        };
+        let mut simulation = Simulation::new(&params, 3).unwrap();
+        simulation
+            .add_primitive(&Primitive {
+                kind: PrimitiveKind::Circle,
+                center: DVec2::splat(0.5),
+                radius: 0.05,
+                rotation: 0.,
+                n_vertices: 32,
+                passive: false,
+            })
+            .unwrap();
+        simulation.keep_history(10, 5);
+
+        let ids = |simulation: &Simulation| {
+            simulation.df.segments.edge_ids().collect::<Vec<_>>()
+        };
+        let mut paths = vec![simulation.paths()];
+        let mut edges = vec![ids(&simulation)];
+        for _ in 0..45 {
+            assert!(simulation.step(&params).unwrap());
+            paths.push(simulation.paths());
+            edges.push(ids(&simulation));
+        }
+
+        // back to the checkpoint of step 20, then on to step 28, with the
+        // edges under the same ids as the first time
+        assert_eq!(simulation.rewind(17, &params).unwrap(), 17);
+        assert_eq!(simulation.paths(), paths[28]);
+        assert_eq!(ids(&simulation), edges[28]);
+        for _ in 0..17 {
+            simulation.step(&params).unwrap();
+        }
+        assert_eq!(simulation.paths(), paths[45]);
+
+        // no further back than the oldest checkpoint
+        assert_eq!(simulation.rewind(100, &params).unwrap(), 45);
+        assert_eq!(simulation.paths(), paths[0]);
+        assert_eq!(simulation.rewind(1, &params).unwrap(), 0);
+    }
+}
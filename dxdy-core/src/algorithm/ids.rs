@@ -0,0 +1,185 @@
+/// a vertex of [`Segments`](super::segments::Segments): the slot it is
+/// stored in, and the generation of the slot when the vertex was added.
+/// once the vertex is deleted its id stays stale, even if the slot comes to
+/// hold another vertex.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct VertexId {
+    slot: u32,
+    generation: u32,
+}
+
+/// an edge of [`Segments`](super::segments::Segments), like a
+/// [`VertexId`]. ordered by slot, to be queued.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct EdgeId {
+    slot: u32,
+    generation: u32,
+}
+
+/// the generation of every slot of the vertices or the edges, which moves
+/// on whenever what the slot holds is deleted.
+#[derive(Clone, Debug)]
+pub(super) struct Generations(Vec<u32>);
+
+//===================================================================
+// Constructors
+//===================================================================
+
+impl VertexId {
+    pub(super) fn new(slot: i64, generation: u32) -> Self {
+        Self {
+            slot: slot as u32,
+            generation,
+        }
+    }
+}
+
+impl EdgeId {
+    pub(super) fn new(slot: i64, generation: u32) -> Self {
+        Self {
+            slot: slot as u32,
+            generation,
+        }
+    }
+}
+
+impl Generations {
+    /// the first generation of `n` slots.
+    pub(super) fn new(n: usize) -> Self {
+        Self(vec![0; n])
+    }
+}
+
+//===================================================================
+// Public Methods
+//===================================================================
+
+impl VertexId {
+    pub(super) fn slot(self) -> i64 {
+        self.slot as i64
+    }
+
+    pub(super) fn generation(self) -> u32 {
+        self.generation
+    }
+}
+
+impl EdgeId {
+    pub(super) fn slot(self) -> i64 {
+        self.slot as i64
+    }
+
+    pub(super) fn generation(self) -> u32 {
+        self.generation
+    }
+}
+
+impl Generations {
+    pub(super) fn get(&self, slot: i64) -> u32 {
+        self.0[slot as usize]
+    }
+
+    /// make room for `n` slots, the slots added being in their first
+    /// generation.
+    pub(super) fn resize(&mut self, n: usize) {
+        self.0.resize(n, 0);
+    }
+
+    /// make the ids of whatever `slot` holds stale.
+    pub(super) fn bump(&mut self, slot: i64) {
+        let generation = &mut self.0[slot as usize];
+        *generation = generation.wrapping_add(1);
+    }
+
+    /// make every id stale.
+    pub(super) fn bump_all(&mut self) {
+        for generation in &mut self.0 {
+            *generation = generation.wrapping_add(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::segments::{SegmentsError, tests::circle};
+
+    #[test]
+    fn generations_move_on_per_slot() {
+        let mut generations = Generations::new(3);
+        generations.bump(1);
+        generations.bump(1);
+        assert_eq!(
+            (0..3).map(|s| generations.get(s)).collect::<Vec<_>>(),
+            [0, 2, 0]
+        );
+
+        generations.resize(4);
+        generations.resize(4);
+        generations.bump_all();
+        assert_eq!(
+            (0..4).map(|s| generations.get(s)).collect::<Vec<_>>(),
+            [1, 3, 1, 1]
+        );
+
+        let mut generations = Generations(vec![u32::MAX]);
+        generations.bump(0);
+        assert_eq!(generations.get(0), 0);
+    }
+
+    #[test]
+    fn stale_ids_are_rejected_by_every_accessor() {
+        let mut segments = circle();
+        let v = segments.vertex_id(4).unwrap();
+        let e = segments.edges_of(v).unwrap()[0].unwrap();
+        segments.remove(v).unwrap();
+
+        let stale_vertex = Err(SegmentsError::StaleVertex { v1: v.slot() });
+        let stale_edge = Err(SegmentsError::StaleEdge { e1: e.slot() });
+        assert_eq!(segments.vertex_slot(v), stale_vertex);
+        assert!(!segments.vertex_exists(v));
+        assert!(!segments.vertex_active(v));
+        assert!(segments.vertex_position(v).is_err());
+        assert!(segments.vertex_weight(v).is_err());
+        assert!(segments.edges_of(v).is_err());
+        assert_eq!(segments.linked_vertices(v), None);
+        assert_eq!(segments.remove(v), stale_vertex.map(drop));
+        assert_eq!(segments.edge_slot(e), stale_edge);
+        assert!(!segments.edge_exists(e));
+        assert!(segments.edge_ends(e).is_err());
+        assert!(segments.get_edge_length(e).is_err());
+        assert_eq!(segments.other_vertex(e, v), None);
+        assert!(segments.collapse(e, None).is_err());
+        assert_eq!(segments.cut(e), stale_edge.map(drop));
+        assert_eq!(segments.split_edges(&[e]), stale_edge.map(|_| vec![]));
+
+        // nothing was changed by the stale ids
+        assert_eq!(segments.vertex_ids().count(), 31);
+        assert_eq!(segments.edge_ids().count(), 30);
+    }
+
+    #[test]
+    fn freed_slots_are_reused_last_freed_first() {
+        let mut segments = circle();
+        let vertices = [2, 6, 10].map(|v| segments.vertex_id(v).unwrap());
+        for v in vertices {
+            segments.remove(v).unwrap();
+        }
+
+        let mut slots = vec![];
+        for _ in vertices {
+            let e = segments.edge_ids().next().unwrap();
+            slots.push(segments.split_edges(&[e]).unwrap()[0].slot());
+        }
+        assert_eq!(slots, [10, 6, 2]);
+
+        // with the freed slots taken, new vertices go after the others, and
+        // those that took a freed slot are a generation past the last
+        assert_eq!(
+            segments.vertex_id(2).map(VertexId::generation),
+            Some(vertices[0].generation() + 1)
+        );
+        let e = segments.edge_ids().next().unwrap();
+        assert_eq!(segments.split_edges(&[e]).unwrap()[0].slot(), 32);
+    }
+}
@@ -0,0 +1,145 @@
+use glam::{DAffine2, DVec2};
+use rand::{Rng, rngs::SmallRng};
+
+/// ranges of the random perturbation applied to every seed when a
+/// simulation is created, so that runs with the same drawing differ slightly
+/// from seed to seed. all zero by default.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SeedJitter {
+    /// largest distance a seed is moved along each axis.
+    pub offset: f64,
+    /// largest angle in radians a seed is turned about its center.
+    pub rotation: f64,
+    /// largest fraction a seed is grown or shrunk by about its center.
+    pub scale: f64,
+}
+
+/// one draw from the ranges of a [`SeedJitter`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) struct Jitter {
+    pub(super) offset: DVec2,
+    pub(super) rotation: f64,
+    pub(super) scale: f64,
+}
+
+//===================================================================
+// Constructors
+//===================================================================
+
+impl SeedJitter {
+    pub const NONE: Self = Self {
+        offset: 0.,
+        rotation: 0.,
+        scale: 0.,
+    };
+}
+
+impl Jitter {
+    pub(super) const NONE: Self = Self {
+        offset: DVec2::ZERO,
+        rotation: 0.,
+        scale: 1.,
+    };
+}
+
+//===================================================================
+// Helpers
+//===================================================================
+
+/// uniform in `-range..=range`, without drawing from `rng` if the range is
+/// empty.
+fn symmetric(rng: &mut SmallRng, range: f64) -> f64 {
+    if range > 0. {
+        rng.random_range(-range..=range)
+    } else {
+        0.
+    }
+}
+
+//===================================================================
+// Public Methods
+//===================================================================
+
+impl SeedJitter {
+    /// draw a perturbation. nothing is drawn from `rng` for empty ranges, so
+    /// that runs without jitter are not changed by it.
+    pub(super) fn sample(&self, rng: &mut SmallRng) -> Jitter {
+        Jitter {
+            offset: DVec2::new(
+                symmetric(rng, self.offset),
+                symmetric(rng, self.offset),
+            ),
+            rotation: symmetric(rng, self.rotation),
+            scale: 1. + symmetric(rng, self.scale.min(1.)),
+        }
+    }
+}
+
+impl Jitter {
+    /// the perturbation as a transform about `center`.
+    pub(super) fn about(&self, center: DVec2) -> DAffine2 {
+        DAffine2::from_translation(center + self.offset)
+            * DAffine2::from_scale_angle_translation(
+                DVec2::splat(self.scale),
+                self.rotation,
+                DVec2::ZERO,
+            )
+            * DAffine2::from_translation(-center)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::FRAC_PI_2;
+
+    use rand::SeedableRng;
+
+    use super::*;
+
+    #[test]
+    fn no_jitter_draws_nothing() {
+        let mut rng = SmallRng::seed_from_u64(1);
+        let mut unused = rng.clone();
+        assert_eq!(SeedJitter::NONE.sample(&mut rng), Jitter::NONE);
+        assert_eq!(rng.random::<u64>(), unused.random::<u64>());
+    }
+
+    #[test]
+    fn samples_stay_in_their_ranges() {
+        let mut rng = SmallRng::seed_from_u64(1);
+        let jitter = SeedJitter {
+            offset: 0.1,
+            rotation: 0.5,
+            // more than shrinking to nothing
+            scale: 2.,
+        };
+        for _ in 0..1000 {
+            let sample = jitter.sample(&mut rng);
+            assert!(sample.offset.abs().max_element() <= 0.1);
+            assert!(sample.rotation.abs() <= 0.5);
+            assert!((0. ..=2.).contains(&sample.scale));
+        }
+        assert_ne!(jitter.sample(&mut rng), jitter.sample(&mut rng));
+    }
+
+    #[test]
+    fn jitter_turns_and_scales_about_the_center() {
+        let center = DVec2::new(0.5, 0.5);
+        let jitter = Jitter {
+            offset: DVec2::new(0.1, 0.),
+            rotation: FRAC_PI_2,
+            scale: 2.,
+        };
+        let transform = jitter.about(center);
+
+        // the center is only moved
+        let moved = transform.transform_point2(center);
+        assert!(moved.distance(DVec2::new(0.6, 0.5)) < 1e-12);
+        // a point to its right ends up twice as far above it
+        let p = transform.transform_point2(center + DVec2::new(0.1, 0.));
+        assert!(p.distance(moved + DVec2::new(0., 0.2)) < 1e-12);
+
+        let identity = Jitter::NONE.about(center);
+        assert_eq!(identity.transform_point2(p), p);
+    }
+}
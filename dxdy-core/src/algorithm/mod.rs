@@ -0,0 +1,1110 @@
+//! differential line growth in the unit square: lines seeded from points
+//! and primitives that grow by splitting their edges, while their vertices
+//! push apart and pull along the lines, kept within a domain and away from
+//! obstacles.
+
+#[cfg(feature = "bench")]
+#[doc(hidden)]
+pub mod bench;
+mod compare;
+mod constraints;
+mod differential_line;
+mod domain;
+mod fields;
+mod history;
+mod ids;
+mod jitter;
+mod mutation;
+mod neighbor_lists;
+mod noise;
+mod primitive;
+#[cfg(feature = "pyo3")]
+mod python;
+mod quad_tree;
+mod segments;
+mod spatial_index;
+mod spawn_schedule;
+mod symmetry;
+mod zone_map;
+
+use std::{collections::HashSet, iter::FusedIterator};
+
+use glam::DVec2;
+use rand::Rng;
+
+pub use compare::RunComparison;
+use constraints::Constraint;
+pub use differential_line::{BoundaryMode, SpawnMode};
+use differential_line::{DifferentialLine, closest_point_on_polyline};
+pub use domain::Domain;
+pub use fields::{Field, FieldKind};
+use history::History;
+use jitter::Jitter;
+pub use jitter::SeedJitter;
+pub use primitive::{Primitive, PrimitiveKind};
+pub use segments::{Positions, SegmentPath, SegmentsError};
+pub use spatial_index::{IndexKind, OccupancyStats};
+pub use symmetry::Symmetry;
+
+pub const ONE: f64 = 1. / SIZE as f64;
+
+const SIZE: u64 = 1000;
+
+/// growth parameters that can be changed while a simulation runs.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Params {
+    /// the closest comfortable distance between two vertices.
+    pub near_l: f64,
+    /// the distance beyond which disconnected vertices will ignore each other.
+    pub far_l: f64,
+    /// distance vertices move per step.
+    pub step: f64,
+    /// probability per step that an edge is split.
+    pub spawn_probability: f64,
+    /// maximal number of vertices/edges, at which the growth stops. storage
+    /// grows as they are added, up to it. only takes effect when a new
+    /// simulation is created.
+    pub n_max: u64,
+    /// how the edges to split are picked.
+    pub spawn_mode: SpawnMode,
+    /// probability per step that a branch sprouts from the line, off at 0.
+    /// branches sprout where the line bends the most.
+    pub branch_probability: f64,
+    /// how far vertices move along the line per step to even out the
+    /// lengths of their edges, as a fraction of the difference, off at 0.
+    /// evenly spaced vertices split and collapse less, and export smoother.
+    pub equalize: f64,
+    /// how strongly the line resists bending, pulling vertices where it
+    /// turns sharply towards the middle of their linked vertices, off at 0.
+    /// segments can have a stiffness of their own instead. stiff lines grow
+    /// smooth lobes, floppy ones dense mazes.
+    pub stiffness: f64,
+    /// the boundary mode applies once a vertex is closer than this to the
+    /// edge of the unit square, and vertices are kept at least this far
+    /// inside other [`Domain`]s.
+    pub boundary_margin: f64,
+    /// what happens to vertices that reach the boundary margin of the unit
+    /// square.
+    pub boundary_mode: BoundaryMode,
+    /// seeds are moved at least this far inside the edge of the unit square.
+    /// only takes effect when a new simulation is created.
+    pub seed_padding: f64,
+    /// random perturbation of every seed. only takes effect when a new
+    /// simulation is created.
+    pub seed_jitter: SeedJitter,
+    /// growth seeded along a spine is kept within this distance of it. only
+    /// takes effect when a new simulation is created.
+    pub spine_corridor: f64,
+    /// how far vertices are pushed along a noise flow field per step, in
+    /// steps, off at 0. the flow field is the same for the same seed.
+    pub noise_amplitude: f64,
+    /// the distance over which the flow field turns around.
+    pub noise_scale: f64,
+    /// whether vertices are kept from moving where the line would cross
+    /// itself or another line, which repulsion alone does not rule out when
+    /// the line is pushed hard. slower.
+    pub strict_topology: bool,
+    /// the total length of the edges of every line is kept under this, by
+    /// drawing its active vertices toward its centroid, off at 0. lines with
+    /// passive vertices may stay longer.
+    pub max_length: f64,
+    /// active vertices are kept within the rectangle between these corners,
+    /// off for the whole unit square.
+    pub region: [DVec2; 2],
+    /// the structure neighbors are searched in. only takes effect when a
+    /// new simulation is created.
+    pub index: IndexKind,
+    /// the neighbors of every vertex are cached up to this much further
+    /// than the far distance, and searched for again once the vertex moves
+    /// a quarter of it, off at 0. faster where the line moves slowly.
+    pub neighbor_skin: f64,
+    /// edges shorter than this are collapsed after every step, off at 0.
+    /// keeps the vertices from bunching up where the line is squeezed.
+    pub min_edge_length: f64,
+}
+
+impl Params {
+    pub const DEFAULT: Self = Self {
+        near_l: 2. * ONE,
+        far_l: 40. * ONE,
+        step: 0.4 * ONE,
+        spawn_probability: 0.001,
+        n_max: 10_u64.pow(6),
+        spawn_mode: SpawnMode::Uniform,
+        branch_probability: 0.,
+        equalize: 0.,
+        stiffness: 0.,
+        boundary_margin: 1.2 * ONE,
+        boundary_mode: BoundaryMode::Stop,
+        seed_padding: 20. * ONE,
+        seed_jitter: SeedJitter::NONE,
+        spine_corridor: 60. * ONE,
+        noise_amplitude: 0.,
+        noise_scale: 100. * ONE,
+        strict_topology: false,
+        max_length: 0.,
+        region: [DVec2::ZERO, DVec2::ONE],
+        index: IndexKind::ZoneMap,
+        neighbor_skin: 0.,
+        min_edge_length: 0.,
+    };
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+fn steps(
+    df: &mut DifferentialLine,
+    params: &Params,
+) -> Result<bool, SegmentsError> {
+    df.set_distances(params.near_l, params.far_l);
+    df.spawn_mode = params.spawn_mode;
+    df.equalize = params.equalize;
+    df.stiffness = params.stiffness;
+    df.noise_amplitude = params.noise_amplitude;
+    df.noise_scale = params.noise_scale;
+    df.boundary_margin = params.boundary_margin;
+    df.boundary_mode = params.boundary_mode;
+    df.strict_topology = params.strict_topology;
+    df.set_limits(params.max_length, params.region);
+    df.set_neighbor_skin(params.neighbor_skin);
+
+    df.optimize_position(params.step)?;
+
+    match df.spawn_mode {
+        SpawnMode::Uniform => {
+            spawn(df, params.near_l, params.spawn_probability)?
+        }
+        SpawnMode::Curvature => {
+            spawn_curvature(df, params.near_l, params.spawn_probability)?
+        }
+    }
+
+    // runs without branches draw the same random numbers as before there
+    // were branches
+    if params.branch_probability > 0. {
+        branch(df, params.near_l, params.branch_probability)?;
+    }
+
+    if params.min_edge_length > 0. {
+        df.segments.collapse_short_edges(params.min_edge_length)?;
+    }
+
+    if df.boundary_mode == BoundaryMode::Stop
+        && !df.segments.safe_vertex_positions(params.boundary_margin)
+    {
+        return Ok(false);
+    }
+
+    Ok(true)
+}
+
+/// [`steps`], finding that the simulation can not continue once there is
+/// no room left for the vertices spawned by the step.
+fn step(
+    df: &mut DifferentialLine,
+    params: &Params,
+) -> Result<bool, SegmentsError> {
+    match steps(df, params) {
+        Err(SegmentsError::CapacityExceeded { .. }) => Ok(false),
+        result => result,
+    }
+}
+
+fn spawn(
+    df: &mut DifferentialLine,
+    near_l /* d */: f64,
+    limit: f64,
+) -> Result<(), SegmentsError> {
+    let due =
+        df.spawn_schedule
+            .next_pass(&mut df.segments, limit, &mut df.rng);
+
+    let mut split = Vec::with_capacity(due.len());
+    for e in due {
+        if df.segments.get_edge_length(e)? < near_l {
+            df.spawn_schedule.reschedule(e, &df.segments, &mut df.rng);
+            continue;
+        }
+
+        split.push(e);
+    }
+
+    df.segments.split_edges(&split).map(drop)
+}
+
+/// like [`spawn`], with the probability of each edge also scaled by its
+/// curvature relative to the most curved edge.
+fn spawn_curvature(
+    df: &mut DifferentialLine,
+    near_l: f64,
+    limit: f64,
+) -> Result<(), SegmentsError> {
+    // curvature changes on every step, so every edge is visited and the
+    // uniform schedule is rebuilt if the mode is switched back
+    df.segments.take_dirty_edges();
+    df.spawn_schedule.invalidate();
+
+    let curvatures = df
+        .segments
+        .edge_ids()
+        .filter_map(|e| Some((e, df.segments.get_edge_curvature(e).ok()?)))
+        .collect::<Vec<_>>();
+
+    let max_curvature = curvatures
+        .iter()
+        .map(|&(_, t)| t)
+        .fold(f64::NEG_INFINITY, f64::max);
+
+    let mut split = Vec::new();
+    for (e, t) in curvatures {
+        let x = df.rng.random::<f64>();
+        let p = limit * df.segments.get_edge_weight(e)? * t / max_curvature;
+        if x < p && df.segments.get_edge_length(e)? >= near_l {
+            split.push(e);
+        }
+    }
+
+    df.segments.split_edges(&split).map(drop)
+}
+
+/// number of vertices of a new branch.
+const BRANCH_VERTICES: usize = 4;
+
+/// with probability `limit`, sprout a branch from a vertex picked in
+/// proportion to how sharply the line turns there: a short open line of its
+/// own, `near_l` apart and pointing to the outside of the turn, which then
+/// grows and repels like any other. its vertices spawn at the rate of the
+/// vertex, and its root stays by the vertex. a vertex sprouts one branch at
+/// most.
+fn branch(
+    df: &mut DifferentialLine,
+    near_l: f64,
+    limit: f64,
+) -> Result<(), SegmentsError> {
+    if df.rng.random::<f64>() >= limit {
+        return Ok(());
+    }
+
+    let stems = df
+        .constraints
+        .iter()
+        .filter_map(|c| match *c {
+            Constraint::Stem { to, .. } => Some(to),
+            _ => None,
+        })
+        .collect::<HashSet<_>>();
+    let bends = df
+        .segments
+        .active_vertex_ids()
+        .filter(|v| !stems.contains(v))
+        .filter_map(|v| Some((v, df.segments.get_vertex_bend(v)?)))
+        .collect::<Vec<_>>();
+
+    let total = bends.iter().map(|&(_, (bend, _))| bend).sum::<f64>();
+    let mut x = df.rng.random::<f64>() * total;
+    let Some(&(v, (_, outside))) = bends
+        .iter()
+        .find(|&&(_, (bend, _))| {
+            x -= bend;
+            x < 0.
+        })
+        .or(bends.last())
+    else {
+        return Ok(());
+    };
+
+    let p = df.segments.vertex_position(v)?;
+    let xys = (1..=BRANCH_VERTICES)
+        .map(|i| p + outside * (i as f64 * near_l))
+        .collect::<Vec<_>>();
+    let weights = [df.segments.vertex_weight(v)?; BRANCH_VERTICES];
+    df.init_branch(v, &xys, &weights)
+}
+
+/// a differential line growing from seeds in the unit square.
+pub struct Simulation {
+    df: DifferentialLine,
+    seed_padding: f64,
+    seed_jitter: SeedJitter,
+    spine_corridor: f64,
+    /// the steps taken so far.
+    steps: u64,
+    /// checkpoints to rewind to, if kept.
+    history: Option<History>,
+}
+
+impl Simulation {
+    /// an empty simulation. `params.n_max` is fixed from here on, the other
+    /// parameters are read again on every step. runs with the same seed,
+    /// seeds, and parameters grow identically. `params.near_l` and
+    /// `params.far_l` that are not finite and positive are an error.
+    pub fn new(params: &Params, seed: u64) -> Result<Self, SegmentsError> {
+        Self::with_domain(params, seed, Domain::Square)
+    }
+
+    /// like [`Self::new`], growing inside `domain`.
+    pub fn with_domain(
+        params: &Params,
+        seed: u64,
+        domain: Domain,
+    ) -> Result<Self, SegmentsError> {
+        Ok(Self {
+            df: DifferentialLine::with_domain(
+                params.n_max,
+                params.far_l,
+                params.near_l,
+                params.far_l,
+                seed,
+                params.index,
+                domain,
+            )?,
+            seed_padding: params.seed_padding,
+            seed_jitter: params.seed_jitter,
+            spine_corridor: params.spine_corridor,
+            steps: 0,
+            history: None,
+        })
+    }
+
+    /// add a seed through `xys` where each vertex spawns new vertices in
+    /// proportion to the matching entry in `weights`. the seed is jittered
+    /// about its centroid, then points outside the seed padding are moved
+    /// onto its edge. like every seed and obstacle, a line of fewer than 2
+    /// points, or a loop of fewer than 3, is an error.
+    pub fn add_seed(
+        &mut self,
+        xys: &[DVec2],
+        weights: &[f64],
+        closed: bool,
+    ) -> Result<(), SegmentsError> {
+        // checked before the jitter is sampled, for a seed that is not
+        // added to leave the rng as it was
+        segments::check_vertex_count(xys.len(), closed)?;
+        let centroid = xys.iter().sum::<DVec2>() / xys.len() as f64;
+        let jitter = self.seed_jitter.sample(&mut self.df.rng).about(centroid);
+
+        let (min, max) = self.padded_region();
+        let xys = xys
+            .iter()
+            .map(|&p| jitter.transform_point2(p).clamp(min, max))
+            .collect::<Vec<_>>();
+        self.df
+            .init_weighted_line_segment(&xys, weights, closed)
+            .map(drop)
+    }
+
+    /// add a spine through the open polyline `xys`: a seed along it whose
+    /// ends stay put, where each vertex spawns new vertices in proportion
+    /// to the matching entry in `weights`, and whose growth is kept within
+    /// the spine corridor of it. spines are not jittered, as they are drawn
+    /// to direct the growth, but points outside the seed padding are moved
+    /// onto its edge.
+    pub fn add_spine(
+        &mut self,
+        xys: &[DVec2],
+        weights: &[f64],
+    ) -> Result<(), SegmentsError> {
+        let (min, max) = self.padded_region();
+        let xys = xys.iter().map(|p| p.clamp(min, max)).collect::<Vec<_>>();
+        self.df.init_spine(&xys, weights, self.spine_corridor)
+    }
+
+    /// add a fixed obstacle through `xys`, which the growing line is
+    /// repelled by but which never moves or splits.
+    pub fn add_obstacle(
+        &mut self,
+        xys: &[DVec2],
+        closed: bool,
+    ) -> Result<(), SegmentsError> {
+        self.df.init_obstacle(xys, closed)
+    }
+
+    /// add `path`, as taken from the paths of another simulation, where it
+    /// is: a seed of its points that every vertex spawns from at the same
+    /// rate, or an obstacle if it is passive. nothing is jittered or moved
+    /// inside the seed padding.
+    pub fn add_path(
+        &mut self,
+        path: &SegmentPath,
+    ) -> Result<(), SegmentsError> {
+        if path.passive {
+            return self.df.init_obstacle(&path.points, path.closed);
+        }
+        let weights = vec![1.; path.points.len()];
+        self.df
+            .init_weighted_line_segment(&path.points, &weights, path.closed)
+            .map(drop)
+    }
+
+    /// add a seed with the outline of `primitive`, where every vertex
+    /// spawns new vertices at the same rate, or an obstacle if it is
+    /// passive. seeds are jittered, obstacles are not.
+    pub fn add_primitive(
+        &mut self,
+        primitive: &Primitive,
+    ) -> Result<(), SegmentsError> {
+        let jitter = if primitive.passive {
+            Jitter::NONE
+        } else {
+            self.seed_jitter.sample(&mut self.df.rng)
+        };
+
+        let (min, max) = self.padded_region();
+        let primitive = primitive.jittered(&jitter).fitted(min, max);
+
+        match primitive.kind {
+            PrimitiveKind::Circle if primitive.passive => self
+                .df
+                .segments
+                .init_passive_circle_segment(
+                    primitive.center.x,
+                    primitive.center.y,
+                    primitive.radius,
+                    &primitive.circle_angles(),
+                )
+                .map(drop),
+            PrimitiveKind::Circle => self
+                .df
+                .segments
+                .init_circle_segment(
+                    primitive.center.x,
+                    primitive.center.y,
+                    primitive.radius,
+                    &primitive.circle_angles(),
+                )
+                .map(drop),
+            _ if primitive.passive => self
+                .df
+                .init_obstacle(&primitive.points(), primitive.is_closed()),
+            _ => {
+                let xys = primitive.points();
+                let weights = vec![1.; xys.len()];
+                self.df
+                    .init_weighted_line_segment(
+                        &xys,
+                        &weights,
+                        primitive.is_closed(),
+                    )
+                    .map(drop)
+            }
+        }
+    }
+
+    /// pin the active vertices within `reach` of the polyline through `xys`
+    /// to the closest points on it, where every later step keeps them, e.g.
+    /// to tie the growth to a scaffold drawn by the user. returns how many
+    /// vertices were anchored.
+    pub fn anchor_to_polyline(&mut self, xys: &[DVec2], reach: f64) -> usize {
+        if xys.is_empty() {
+            return 0;
+        }
+        let segments = &self.df.segments;
+        let vertices = segments
+            .active_vertex_ids()
+            .filter(|&v| {
+                segments.vertex_position(v).is_ok_and(|p| {
+                    p.distance(closest_point_on_polyline(p, xys)) <= reach
+                })
+            })
+            .collect::<Vec<_>>();
+        match self.df.anchor_to_polyline(&vertices, xys) {
+            Ok(()) => vertices.len(),
+            Err(_) => 0,
+        }
+    }
+
+    /// give `segment`, as in [`SegmentPath::segment`], a stiffness of its
+    /// own instead of [`Params::stiffness`], or with `None` take that one
+    /// again. branches of the segment take its stiffness.
+    pub fn set_segment_stiffness(
+        &mut self,
+        segment: i64,
+        stiffness: Option<f64>,
+    ) {
+        self.df.set_segment_stiffness(segment, stiffness);
+    }
+
+    /// how many branches deep `segment`, as in [`SegmentPath::segment`], is:
+    /// 0 for a seed or obstacle, 1 for a branch sprouted from one, and so on.
+    pub fn segment_generation(&self, segment: i64) -> u32 {
+        self.df.segment_generation(segment)
+    }
+
+    /// replace the fields acting on the vertices from the next step.
+    pub fn set_fields(&mut self, fields: Vec<Field>) {
+        self.df.fields = fields;
+    }
+
+    /// act on the vertices with the field of the cursor, on top of the
+    /// others, on every step until it is set again, or with `None` stop.
+    pub fn set_cursor_field(&mut self, field: Option<Field>) {
+        self.df.cursor = field;
+    }
+
+    /// keep the moves of every step from the next one to `symmetry` about
+    /// `center`, or with `None` stop.
+    pub fn set_symmetry(&mut self, symmetry: Option<Symmetry>, center: DVec2) {
+        self.df.symmetry = symmetry.map(|symmetry| (symmetry, center));
+    }
+
+    /// corners of the region seeds are placed in.
+    fn padded_region(&self) -> (DVec2, DVec2) {
+        // the region is never empty, even if the padding is over a half
+        let padding = self.seed_padding.min(0.5);
+        (DVec2::splat(padding), DVec2::splat(1. - padding))
+    }
+
+    /// advance the simulation by one step. returns `false` once it can not
+    /// continue, either because a vertex reached the boundary or because
+    /// there was no room left for the vertices spawned by the step.
+    pub fn step(&mut self, params: &Params) -> Result<bool, SegmentsError> {
+        let stepped = step(&mut self.df, params)?;
+        if stepped {
+            self.steps += 1;
+            if let Some(history) = &mut self.history {
+                history.record(self.steps, &self.df);
+            }
+        }
+        Ok(stepped)
+    }
+
+    /// keep a checkpoint every `interval` steps from now on, the last
+    /// `capacity` of them, to [`Self::rewind`] to. replaces the checkpoints
+    /// kept so far.
+    pub fn keep_history(&mut self, interval: u64, capacity: usize) {
+        let mut history = History::new(interval, capacity);
+        history.record(self.steps, &self.df);
+        self.history = Some(history);
+    }
+
+    /// go back `n` steps, or as far as the checkpoints allow, by going back
+    /// to the checkpoint before and stepping on from it with `params`. the
+    /// simulation is then as it was, with the seeds, fields and symmetry it
+    /// had, and grows the same again for the same parameters. returns the
+    /// number of steps gone back, 0 without checkpoints.
+    pub fn rewind(
+        &mut self,
+        n: u64,
+        params: &Params,
+    ) -> Result<u64, SegmentsError> {
+        let Some(history) = &mut self.history else {
+            return Ok(0);
+        };
+        let rewound = history
+            .rewind(&mut self.df, self.steps, n, |df| step(df, params))?;
+        let Some(steps) = rewound else {
+            return Ok(0);
+        };
+        let back = self.steps - steps;
+        self.steps = steps;
+        Ok(back)
+    }
+
+    /// step the simulation with `params` as it is iterated, yielding its
+    /// geometry after each step until it can not continue or a step fails.
+    /// every item takes the paths, so skip steps with
+    /// [`Iterator::step_by`] sparingly on large simulations.
+    pub fn run(&mut self, params: Params) -> Run<'_> {
+        Run {
+            simulation: self,
+            params,
+            iteration: 0,
+            done: false,
+        }
+    }
+
+    /// every connected run of edges as a path.
+    pub fn paths(&self) -> Vec<SegmentPath> {
+        self.df.segments.paths()
+    }
+
+    /// the position of every vertex, to show the paths moving from them
+    /// with [`Self::paths_between`] once the simulation has stepped.
+    pub fn positions(&self) -> Positions {
+        self.df.segments.positions()
+    }
+
+    /// every connected run of edges as a path, with the vertices `t` of the
+    /// way from `previous`, as returned by [`Self::positions`], to where
+    /// they are now.
+    pub fn paths_between(
+        &self,
+        previous: &Positions,
+        t: f64,
+    ) -> Vec<SegmentPath> {
+        self.df.segments.paths_between(previous, t)
+    }
+
+    /// the number of vertices ever added, including those since deleted.
+    pub fn vertices_added(&self) -> usize {
+        self.df.segments.v_added() as usize
+    }
+
+    /// the number of segments ever added, which is also the id of the next
+    /// one, as in [`SegmentPath::segment`].
+    pub fn segment_count(&self) -> i64 {
+        self.df.segments.s_num() as i64
+    }
+
+    /// the number of vertices that have not been deleted.
+    pub fn vertex_count(&self) -> usize {
+        self.df.segments.vertex_ids().count()
+    }
+
+    /// move every vertex and edge into the lowest slots of storage, so that
+    /// steps no longer walk over the slots of deleted ones. the growth
+    /// carries on the same.
+    pub fn compact(&mut self) {
+        self.df.compact();
+    }
+
+    /// the number of edges that have not been deleted.
+    pub fn edge_count(&self) -> usize {
+        self.df.segments.edge_ids().count()
+    }
+
+    /// the number of vertices that still move, which are not passive.
+    pub fn active_vertex_count(&self) -> usize {
+        self.df.segments.get_active_vertex_count()
+    }
+
+    /// occupancy of the spatial index, with the sphere queries since the
+    /// last call.
+    pub fn take_index_stats(&self) -> OccupancyStats {
+        self.df.take_index_stats()
+    }
+
+    /// how far the active vertices moved in the last step on average, which
+    /// drops towards 0 as growth stalls.
+    pub fn mean_displacement(&self) -> f64 {
+        self.df.displacement
+    }
+
+    /// how alike the geometry of this simulation and of `other` are, with
+    /// the edges of both rasterized at `resolution` cells per side of the
+    /// unit square for their overlap.
+    pub fn compare(
+        &self,
+        other: &Simulation,
+        resolution: usize,
+    ) -> RunComparison {
+        compare::compare_runs(
+            &self.df.segments,
+            &other.df.segments,
+            resolution,
+        )
+    }
+
+    /// end points `[a, b]` of every edge.
+    pub fn edges(&self) -> Vec<[DVec2; 2]> {
+        self.df.segments.get_edges_coordinates()
+    }
+}
+
+/// the geometry of a simulation after a step of [`Simulation::run`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct GeometrySnapshot {
+    /// the steps taken by the run so far, counting this one.
+    pub iteration: u64,
+    pub paths: Vec<SegmentPath>,
+}
+
+/// the steps of a simulation, returned by [`Simulation::run`]. ends after
+/// the step that finds the simulation can not continue, or after the
+/// error of a step that failed.
+pub struct Run<'a> {
+    simulation: &'a mut Simulation,
+    params: Params,
+    iteration: u64,
+    done: bool,
+}
+
+impl Iterator for Run<'_> {
+    type Item = Result<GeometrySnapshot, SegmentsError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.simulation.step(&self.params) {
+            Ok(true) => {
+                self.iteration += 1;
+                Some(Ok(GeometrySnapshot {
+                    iteration: self.iteration,
+                    paths: self.simulation.paths(),
+                }))
+            }
+            Ok(false) => {
+                self.done = true;
+                None
+            }
+            Err(err) => {
+                self.done = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+impl FusedIterator for Run<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn circle_simulation(params: &Params) -> Simulation {
+        let mut simulation = Simulation::new(params, 1).unwrap();
+        simulation
+            .add_primitive(&Primitive {
+                kind: PrimitiveKind::Circle,
+                center: DVec2::splat(0.5),
+                radius: 0.05,
+                rotation: 0.,
+                n_vertices: 32,
+                passive: false,
+            })
+            .unwrap();
+        simulation
+    }
+
+    #[test]
+    fn seeds_of_too_few_points_are_errors() {
+        let mut simulation = Simulation::new(&Params::DEFAULT, 1).unwrap();
+        let p = [DVec2::splat(0.5)];
+        let too_few = |n, min| Err(SegmentsError::TooFewVertices { n, min });
+
+        assert_eq!(simulation.add_seed(&[], &[], true), too_few(0, 3));
+        assert_eq!(simulation.add_seed(&p, &[1.], false), too_few(1, 2));
+        assert_eq!(simulation.add_spine(&[], &[]), too_few(0, 2));
+        assert_eq!(simulation.add_spine(&p, &[1.]), too_few(1, 2));
+        assert_eq!(simulation.add_obstacle(&[], true), too_few(0, 3));
+        assert_eq!(simulation.add_obstacle(&p, false), too_few(1, 2));
+        assert_eq!(simulation.vertex_count(), 0);
+        assert_eq!(simulation.segment_count(), 0);
+    }
+
+    #[test]
+    fn lengths_that_are_not_positive_are_errors() {
+        let invalid =
+            |name, len| Err(SegmentsError::InvalidLength { name, len });
+        let new = |params| Simulation::new(&params, 1).map(drop);
+        let far_l = Params {
+            far_l: 0.,
+            ..Params::DEFAULT
+        };
+        assert_eq!(new(far_l), invalid("far_l", 0.));
+        let near_l = Params {
+            near_l: -1.,
+            ..Params::DEFAULT
+        };
+        assert_eq!(new(near_l), invalid("near_l", -1.));
+    }
+
+    #[test]
+    fn runs_yield_the_geometry_of_each_step() {
+        let params = Params::DEFAULT;
+        let mut simulation = circle_simulation(&params);
+        let snapshots = simulation
+            .run(params)
+            .step_by(5)
+            .take(4)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let iterations = snapshots.iter().map(|s| s.iteration);
+        assert_eq!(iterations.collect::<Vec<_>>(), [1, 6, 11, 16]);
+        assert_eq!(snapshots[3].paths, simulation.paths());
+
+        // the same as stepping by hand
+        let mut stepped = circle_simulation(&params);
+        for _ in 0..16 {
+            stepped.step(&params).unwrap();
+        }
+        assert_eq!(stepped.paths(), simulation.paths());
+    }
+
+    #[test]
+    fn fields_push_and_pull_the_line() {
+        let params = Params::DEFAULT;
+        // mean distance of the vertices from the center of the circle
+        let spread = |kind: Option<FieldKind>| {
+            let mut simulation = circle_simulation(&params);
+            let field = kind.map(|kind| Field::new(kind, DVec2::splat(0.5)));
+            simulation.set_fields(field.into_iter().collect());
+            for _ in 0..20 {
+                simulation.step(&params).unwrap();
+            }
+            let positions = simulation.positions();
+            let total = positions
+                .iter()
+                .map(|p| p.distance(DVec2::splat(0.5)))
+                .sum::<f64>();
+            total / positions.iter().count() as f64
+        };
+
+        let free = spread(None);
+        assert!(spread(Some(FieldKind::Repulsor)) > free);
+        assert!(spread(Some(FieldKind::Attractor)) < free);
+    }
+
+    #[test]
+    fn short_edges_are_collapsed_as_the_line_grows() {
+        let grow = |min_edge_length| {
+            let params = Params {
+                spawn_probability: 0.05,
+                min_edge_length,
+                ..Params::DEFAULT
+            };
+            let mut simulation = circle_simulation(&params);
+            for _ in 0..100 {
+                simulation.step(&params).unwrap();
+            }
+            simulation
+        };
+
+        let free = grow(0.);
+        let collapsed = grow(0.75 * Params::DEFAULT.near_l);
+        assert!(collapsed.vertex_count() < free.vertex_count());
+        assert!(collapsed.vertices_added() > collapsed.vertex_count());
+        let paths = collapsed.paths();
+        assert_eq!(paths.len(), 1);
+        assert!(paths[0].closed);
+        assert_eq!(paths[0].points.len(), collapsed.vertex_count());
+        assert_eq!(collapsed.edge_count(), collapsed.vertex_count());
+    }
+
+    #[test]
+    fn compaction_keeps_the_growth_and_its_branches() {
+        // vertices past the margin are deleted, leaving their slots free
+        let params = Params {
+            branch_probability: 0.5,
+            boundary_mode: BoundaryMode::Kill,
+            boundary_margin: 0.455,
+            ..Params::DEFAULT
+        };
+        let mut simulation = circle_simulation(&params);
+        for _ in 0..30 {
+            simulation.step(&params).unwrap();
+        }
+        let stems = |simulation: &Simulation| {
+            simulation
+                .df
+                .constraints
+                .iter()
+                .filter(|c| match c {
+                    Constraint::Stem { v, to, .. } => {
+                        simulation.df.segments.vertex_slot(*v).is_ok()
+                            && simulation.df.segments.vertex_slot(*to).is_ok()
+                    }
+                    _ => false,
+                })
+                .count()
+        };
+        let paths = simulation.paths();
+        let n_stems = stems(&simulation);
+        assert!(simulation.vertices_added() > simulation.vertex_count());
+        assert!(n_stems > 0);
+
+        simulation.compact();
+        assert_eq!(simulation.paths(), paths);
+        assert_eq!(stems(&simulation), n_stems);
+        assert_eq!(
+            simulation.df.segments.v_num() as usize,
+            simulation.vertex_count()
+        );
+        simulation.step(&params).unwrap();
+    }
+
+    #[test]
+    fn branches_are_a_generation_past_their_line() {
+        let params = Params {
+            branch_probability: 0.5,
+            ..Params::DEFAULT
+        };
+        let mut simulation = circle_simulation(&params);
+        for _ in 0..30 {
+            simulation.step(&params).unwrap();
+        }
+        let mut segments = simulation
+            .paths()
+            .iter()
+            .map(|path| path.segment)
+            .collect::<Vec<_>>();
+        segments.sort();
+        let generations = segments
+            .iter()
+            .map(|&s| simulation.segment_generation(s))
+            .collect::<Vec<_>>();
+        assert!(generations.len() > 1);
+        assert_eq!(generations[0], 0);
+        assert!(generations[1..].iter().all(|&g| g >= 1), "{generations:?}");
+
+        // a branch of the newest branch
+        let s = simulation.segment_count() - 1;
+        let v = simulation
+            .df
+            .segments
+            .vertex_ids()
+            .find(|&v| simulation.df.segments.vertex_segment(v) == Ok(s))
+            .unwrap();
+        let p = simulation.df.segments.vertex_position(v).unwrap();
+        let xys = [p + DVec2::X * 0.002, p + DVec2::X * 0.004];
+        simulation.df.init_branch(v, &xys, &[1.; 2]).unwrap();
+        assert_eq!(
+            simulation.segment_generation(s + 1),
+            simulation.segment_generation(s) + 1
+        );
+        assert_eq!(simulation.segment_generation(s + 2), 0);
+    }
+
+    #[test]
+    fn runs_end_once_the_simulation_can_not_continue() {
+        // a seed touching the boundary stops on the first step
+        let params = Params {
+            boundary_margin: 0.49,
+            ..Params::DEFAULT
+        };
+        let mut simulation = circle_simulation(&params);
+        let mut run = simulation.run(params);
+        assert!(run.next().is_none());
+        assert!(run.next().is_none());
+    }
+
+    /// the number of edges of the growth crossing edges of the obstacle.
+    fn crossings(simulation: &Simulation) -> usize {
+        let edges = |passive| {
+            simulation
+                .paths()
+                .into_iter()
+                .filter(|path| path.passive == passive)
+                .flat_map(|path| {
+                    path.points
+                        .windows(2)
+                        .map(|w| [w[0], w[1]])
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>()
+        };
+        let obstacle = edges(true);
+        edges(false)
+            .iter()
+            .map(|&[a, b]| {
+                obstacle
+                    .iter()
+                    .filter(|&&[c, d]| {
+                        differential_line::segments_cross(a, b, c, d)
+                    })
+                    .count()
+            })
+            .sum()
+    }
+
+    #[test]
+    fn strict_topology_keeps_lines_from_crossing() {
+        let grow = |strict_topology| {
+            let params = Params {
+                strict_topology,
+                ..Params::DEFAULT
+            };
+            let mut simulation = Simulation::new(&params, 1).unwrap();
+            let line = (0..=40)
+                .map(|i| DVec2::new(0.49, 0.3 + 0.01 * i as f64))
+                .collect::<Vec<_>>();
+            simulation.add_seed(&line, &[1.; 41], false).unwrap();
+            simulation
+                .add_obstacle(
+                    &[DVec2::new(0.5, 0.3), DVec2::new(0.5, 0.7)],
+                    false,
+                )
+                .unwrap();
+            // blowing the middle of the line through the obstacle, faster
+            // than the obstacle pushes back
+            simulation.set_fields(vec![Field {
+                kind: FieldKind::Wind { angle: 0. },
+                position: DVec2::new(0.49, 0.5),
+                radius: 0.15,
+                strength: 50.,
+            }]);
+            for _ in 0..10 {
+                simulation.step(&params).unwrap();
+            }
+            simulation
+        };
+
+        assert!(crossings(&grow(false)) > 0);
+        let strict = grow(true);
+        assert_eq!(crossings(&strict), 0);
+        assert!(strict.paths().iter().any(|path| !path.passive));
+    }
+
+    #[test]
+    fn symmetry_keeps_the_growth_mirrored() {
+        let params = Params::DEFAULT;
+        let center = DVec2::splat(0.5);
+        // mean distance from the mirror image of each vertex to the closest
+        // vertex, with the wind blowing on the right only
+        let asymmetry = |symmetry: Option<Symmetry>| {
+            let mut simulation = circle_simulation(&params);
+            simulation.set_symmetry(symmetry, center);
+            simulation.set_fields(vec![Field {
+                kind: FieldKind::Wind { angle: 0. },
+                position: DVec2::new(0.55, 0.5),
+                radius: 0.04,
+                strength: 10.,
+            }]);
+            for _ in 0..40 {
+                simulation.step(&params).unwrap();
+            }
+            let positions = simulation.positions();
+            let total = positions
+                .iter()
+                .map(|p| {
+                    let image = DVec2::new(2. * center.x - p.x, p.y);
+                    positions
+                        .iter()
+                        .map(|q| q.distance(image))
+                        .fold(f64::INFINITY, f64::min)
+                })
+                .sum::<f64>();
+            total / positions.iter().count() as f64
+        };
+
+        let free = asymmetry(None);
+        let mirrored = asymmetry(Some(Symmetry::Horizontal));
+        assert!(mirrored < free / 2., "{mirrored} vs {free}");
+    }
+
+    #[test]
+    fn anchored_vertices_stay_on_the_polyline() {
+        let params = Params {
+            spawn_probability: 0.05,
+            ..Params::DEFAULT
+        };
+        let mut simulation = circle_simulation(&params);
+        // a stroke across the right of the circle
+        let xys = [DVec2::new(0.55, 0.4), DVec2::new(0.55, 0.6)];
+        let reach = 0.01;
+        let segments = &simulation.df.segments;
+        let near = segments
+            .active_vertex_ids()
+            .filter(|&v| {
+                segments.vertex_position(v).unwrap().x >= 0.55 - reach
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(simulation.anchor_to_polyline(&xys, reach), near.len());
+        assert!(near.len() > 1);
+        for _ in 0..100 {
+            simulation.step(&params).unwrap();
+        }
+        assert!(simulation.vertex_count() > 32);
+        for v in near {
+            let p = simulation.df.segments.vertex_position(v).unwrap();
+            let on = closest_point_on_polyline(p, &xys);
+            assert!(p.distance(on) < 1e-12, "{v:?} at {p}");
+        }
+    }
+}
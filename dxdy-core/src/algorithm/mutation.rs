@@ -0,0 +1,77 @@
+use rand::{Rng, SeedableRng, rngs::SmallRng};
+
+use super::Params;
+
+//===================================================================
+// Public Methods
+//===================================================================
+
+impl Params {
+    /// these parameters with each of the ones that shape the growth scaled
+    /// by a random factor within `amount` of 1, e.g. `0.2` for up to 20%
+    /// more or less, the same for the same seed. parameters that are off at
+    /// 0 stay off, probabilities stay probabilities, and the far distance
+    /// stays beyond the near one. the size of the simulation, its boundary
+    /// and its seeds are left as they are.
+    pub fn mutated(&self, amount: f64, seed: u64) -> Self {
+        let amount = amount.clamp(0., 1.);
+        let mut rng = SmallRng::seed_from_u64(seed);
+        let mut scale = |value: f64| {
+            // drawn even when 0, so that each parameter is scaled the same
+            // whatever the others are
+            value * (1. + amount * rng.random_range(-1.0..=1.0))
+        };
+
+        let near_l = scale(self.near_l);
+        Self {
+            near_l,
+            far_l: scale(self.far_l).max(near_l),
+            step: scale(self.step),
+            spawn_probability: scale(self.spawn_probability).min(1.),
+            branch_probability: scale(self.branch_probability).min(1.),
+            equalize: scale(self.equalize).min(1.),
+            stiffness: scale(self.stiffness),
+            noise_amplitude: scale(self.noise_amplitude),
+            noise_scale: scale(self.noise_scale),
+            ..*self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mutations_stay_within_their_amount() {
+        let params = Params {
+            stiffness: 2.,
+            ..Params::DEFAULT
+        };
+        let mutated = params.mutated(0.2, 1);
+
+        assert_eq!(params.mutated(0.2, 1), mutated);
+        assert_ne!(params.mutated(0.2, 2), mutated);
+        assert_eq!(params.mutated(0., 1), params);
+        assert_ne!(mutated.near_l, params.near_l);
+        assert!((mutated.stiffness / params.stiffness - 1.).abs() <= 0.2);
+        assert!((mutated.step / params.step - 1.).abs() <= 0.2);
+        assert_eq!(mutated.branch_probability, 0., "off stays off");
+        assert_eq!(mutated.n_max, params.n_max);
+        assert_eq!(mutated.boundary_mode, params.boundary_mode);
+
+        // however much, probabilities stay probabilities
+        let certain = Params {
+            spawn_probability: 1.,
+            equalize: 1.,
+            ..params
+        };
+        for seed in 0..20 {
+            let mutated = certain.mutated(5., seed);
+            assert!(mutated.spawn_probability <= 1.);
+            assert!(mutated.equalize <= 1.);
+            assert!(mutated.near_l >= 0.);
+            assert!(mutated.far_l >= mutated.near_l);
+        }
+    }
+}
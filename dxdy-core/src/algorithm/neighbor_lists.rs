@@ -0,0 +1,182 @@
+use glam::DVec2;
+
+use super::{ids::VertexId, segments::Segments, spatial_index::SpatialIndex};
+
+/// per-vertex neighbor cache, reused across steps to avoid a spatial index
+/// query for every vertex on every step.
+///
+/// each list holds the vertices within `far_l + skin` of its vertex when the
+/// list was built, and is rebuilt once the vertex moves more than `skin / 4`
+/// from there. when a list is rebuilt its vertex is also added to the lists
+/// of its new neighbors, so for any pair closer than `far_l` the more
+/// recently rebuilt of the two has seen the other within `far_l + skin`.
+#[derive(Clone)]
+pub(super) struct NeighborLists {
+    skin: f64,
+    /// map of vertex to its cached neighbors by vertex index.
+    lists: Vec<Vec<i64>>,
+    /// map of vertex to its position when its list was built by vertex
+    /// index. `NaN` for vertices without a list.
+    origin: Vec<DVec2>,
+    /// map of vertex to its id when its list was built by vertex index, so
+    /// that a vertex added in the slot of a deleted one builds its own.
+    built: Vec<Option<VertexId>>,
+}
+
+//===================================================================
+// Constructors
+//===================================================================
+
+impl NeighborLists {
+    pub(super) fn new(skin: f64) -> Self {
+        Self {
+            skin,
+            lists: Vec::new(),
+            origin: Vec::new(),
+            built: Vec::new(),
+        }
+    }
+}
+
+//===================================================================
+// Private Methods
+//===================================================================
+
+impl NeighborLists {
+    fn is_stale(&self, id: VertexId, segments: &Segments) -> bool {
+        let origin = self.origin[id.slot() as usize];
+        origin.is_nan()
+            || self.built[id.slot() as usize] != Some(id)
+            || segments.vertex_position(id).is_ok_and(|p| {
+                p.distance_squared(origin) > self.skin * self.skin / 16.
+            })
+    }
+
+    fn rebuild(&mut self, id: VertexId, segments: &Segments, far_l: f64) {
+        let v = id.slot();
+        let vu = v as usize;
+
+        let mut list = std::mem::take(&mut self.lists[vu]);
+        segments.index.sphere_vertices(
+            v,
+            &segments.x,
+            &segments.y,
+            far_l + self.skin,
+            &mut list,
+        );
+
+        for &u in &list {
+            if u != v && !self.lists[u as usize].contains(&v) {
+                self.lists[u as usize].push(v);
+            }
+        }
+
+        self.lists[vu] = list;
+        // no list, like a vertex never built, if the id is stale
+        self.origin[vu] = segments.vertex_position(id).unwrap_or(DVec2::NAN);
+        self.built[vu] = Some(id);
+    }
+}
+
+//===================================================================
+// Public Methods
+//===================================================================
+
+impl NeighborLists {
+    /// how much further than `far_l` the lists reach.
+    pub(super) fn skin(&self) -> f64 {
+        self.skin
+    }
+
+    /// rebuild the stale lists of every vertex, before the neighbors of any
+    /// are taken, so that vertices added since the last step are in the
+    /// lists of all their neighbors.
+    pub(super) fn refresh(&mut self, segments: &Segments, far_l: f64) {
+        let v_num = segments.v_num() as usize;
+        if self.lists.len() < v_num {
+            self.lists.resize_with(v_num, Vec::new);
+            self.origin.resize(v_num, DVec2::NAN);
+            self.built.resize(v_num, None);
+        }
+
+        for v in segments.vertex_ids() {
+            if self.is_stale(v, segments) {
+                self.rebuild(v, segments, far_l);
+            }
+        }
+    }
+
+    /// fill `vertices` with the live cached neighbors of vertex v, as of
+    /// the last [`Self::refresh`]. returns the number of neighbors.
+    ///
+    /// like a sphere query the result includes v itself, but it may also
+    /// include vertices up to `far_l + skin` away.
+    pub(super) fn neighbors(
+        &self,
+        v: i64,
+        segments: &Segments,
+        vertices: &mut Vec<i64>,
+    ) -> usize {
+        vertices.clear();
+        vertices.extend(
+            self.lists[v as usize]
+                .iter()
+                .copied()
+                .filter(|&u| segments.vertex_id(u).is_some()),
+        );
+
+        vertices.len()
+    }
+
+    /// drop every list, forcing them all to be rebuilt.
+    pub(super) fn invalidate(&mut self) {
+        self.lists.clear();
+        self.origin.clear();
+        self.built.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::{
+        ONE, Params, Primitive, PrimitiveKind, Simulation,
+    };
+
+    #[test]
+    fn cached_neighbors_grow_the_same() {
+        // vertices spawned by a step are in the lists of their neighbors
+        // from the next one on, as they would be found by a query
+        let grow = |neighbor_skin| {
+            let params = Params {
+                spawn_probability: 0.05,
+                neighbor_skin,
+                ..Params::DEFAULT
+            };
+            let mut simulation = Simulation::new(&params, 1).unwrap();
+            simulation
+                .add_primitive(&Primitive {
+                    kind: PrimitiveKind::Circle,
+                    center: DVec2::splat(0.5),
+                    radius: 0.05,
+                    rotation: 0.,
+                    n_vertices: 32,
+                    passive: false,
+                })
+                .unwrap();
+            for _ in 0..200 {
+                simulation.step(&params).unwrap();
+            }
+            simulation.paths()
+        };
+
+        let (queried, cached) = (grow(0.), grow(4. * ONE));
+        assert_eq!(queried.len(), cached.len());
+        for (a, b) in queried.iter().zip(&cached) {
+            assert_eq!(a.points.len(), b.points.len());
+            for (p, q) in a.points.iter().zip(&b.points) {
+                assert!(p.abs_diff_eq(*q, 1e-12), "{p} {q}");
+            }
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use std::f64::consts::{FRAC_1_SQRT_2, TAU};
+
+use glam::DVec2;
+use rand::{SeedableRng, rngs::SmallRng, seq::SliceRandom};
+
+/// skews the input grid of squares onto the grid of triangles.
+const F2: f64 = 0.366_025_403_784_438_6; // (sqrt(3) - 1) / 2
+/// skews the grid of triangles back onto the input grid of squares.
+const G2: f64 = 0.211_324_865_405_187_1; // (3 - sqrt(3)) / 6
+
+/// directions of the gradients at the corners of the triangles.
+const GRADIENTS: [DVec2; 8] = [
+    DVec2::new(1., 0.),
+    DVec2::new(-1., 0.),
+    DVec2::new(0., 1.),
+    DVec2::new(0., -1.),
+    DVec2::new(FRAC_1_SQRT_2, FRAC_1_SQRT_2),
+    DVec2::new(-FRAC_1_SQRT_2, FRAC_1_SQRT_2),
+    DVec2::new(FRAC_1_SQRT_2, -FRAC_1_SQRT_2),
+    DVec2::new(-FRAC_1_SQRT_2, -FRAC_1_SQRT_2),
+];
+
+/// scales the sum of the corners to about `-1..=1`.
+const SCALE: f64 = 70.;
+
+/// 2d simplex noise: a smooth random value at every point that changes
+/// over distances of about 1, the same for the same seed.
+#[derive(Clone, Debug)]
+pub(super) struct Noise {
+    /// a shuffle of `0..256`, twice over, to pick the gradient of a corner
+    /// without wrapping the index.
+    perm: [u8; 512],
+}
+
+//===================================================================
+// Constructors
+//===================================================================
+
+impl Noise {
+    /// noise shuffled by its own generator, so that the generator of the
+    /// simulation draws the same numbers with or without noise.
+    pub(super) fn new(seed: u64) -> Self {
+        let mut shuffle = [0_u8; 256];
+        for (i, p) in shuffle.iter_mut().enumerate() {
+            *p = i as u8;
+        }
+        shuffle.shuffle(&mut SmallRng::seed_from_u64(seed));
+
+        let mut perm = [0; 512];
+        perm[..256].copy_from_slice(&shuffle);
+        perm[256..].copy_from_slice(&shuffle);
+        Self { perm }
+    }
+}
+
+//===================================================================
+// Public Methods
+//===================================================================
+
+impl Noise {
+    /// the noise at `p`, in about `-1..=1`.
+    pub(super) fn get(&self, p: DVec2) -> f64 {
+        // the corner of the triangle p is in that is skewed to the origin
+        let cell = (p + (p.x + p.y) * F2).floor();
+        let origin = cell - (cell.x + cell.y) * G2;
+        let d0 = p - origin;
+
+        // the middle corner, above or below the diagonal of the square
+        let step = if d0.x > d0.y { DVec2::X } else { DVec2::Y };
+        let d1 = d0 - step + G2;
+        let d2 = d0 - 1. + 2. * G2;
+
+        let i = cell.x.rem_euclid(256.) as usize;
+        let j = cell.y.rem_euclid(256.) as usize;
+        let gradient = |di: usize, dj: usize| {
+            let k = self.perm[j + dj] as usize;
+            GRADIENTS[self.perm[i + di + k] as usize % GRADIENTS.len()]
+        };
+
+        let corner = |d: DVec2, g: DVec2| {
+            let t = 0.5 - d.length_squared();
+            if t < 0. { 0. } else { t.powi(4) * g.dot(d) }
+        };
+        let sum = corner(d0, gradient(0, 0))
+            + corner(d1, gradient(step.x as usize, step.y as usize))
+            + corner(d2, gradient(1, 1));
+
+        SCALE * sum
+    }
+
+    /// a direction at `p` that turns smoothly from point to point, as in a
+    /// flow field.
+    pub(super) fn flow(&self, p: DVec2) -> DVec2 {
+        DVec2::from_angle(self.get(p) * TAU)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn noise_is_smooth_and_seeded() {
+        let noise = Noise::new(1);
+        let points = (0..1000)
+            .map(|i| DVec2::new(i as f64 * 0.037, i as f64 * 0.011 - 5.))
+            .collect::<Vec<_>>();
+
+        let values = points.iter().map(|&p| noise.get(p)).collect::<Vec<_>>();
+        assert!(values.iter().all(|v| v.abs() <= 1.));
+        assert!(values.iter().any(|&v| v > 0.3));
+        assert!(values.iter().any(|&v| v < -0.3));
+        assert_eq!(noise.get(DVec2::ZERO), 0., "zero at the lattice");
+
+        // close points have close values
+        for &p in &points {
+            let near = noise.get(p + DVec2::splat(1e-4));
+            assert!((noise.get(p) - near).abs() < 1e-2);
+        }
+
+        let same = Noise::new(1);
+        assert!(points.iter().all(|&p| noise.get(p) == same.get(p)));
+        let other = Noise::new(2);
+        assert!(points.iter().any(|&p| noise.get(p) != other.get(p)));
+
+        assert!(noise.flow(points[7]).is_normalized());
+    }
+}
@@ -0,0 +1,116 @@
+use std::f64::consts::TAU;
+
+use glam::DVec2;
+
+use super::jitter::Jitter;
+
+/// the outline of a [`Primitive`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PrimitiveKind {
+    Circle,
+    /// a line, `2 * radius` long, horizontal unless rotated.
+    Line,
+    /// a regular polygon with its corners on the circle, the first one at
+    /// the top unless rotated.
+    Polygon {
+        sides: usize,
+    },
+}
+
+/// a seed with a simple outline, created without drawing it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Primitive {
+    pub kind: PrimitiveKind,
+    pub center: DVec2,
+    pub radius: f64,
+    /// angle in radians the outline is turned by about its center.
+    pub rotation: f64,
+    /// number of vertices along the whole outline. at least 3 for closed
+    /// outlines and 2 for a line.
+    pub n_vertices: usize,
+    /// whether this is a fixed obstacle rather than a seed.
+    pub passive: bool,
+}
+
+//===================================================================
+// Public Methods
+//===================================================================
+
+impl Primitive {
+    pub fn is_closed(&self) -> bool {
+        !matches!(self.kind, PrimitiveKind::Line)
+    }
+
+    /// this primitive moved and shrunk as little as possible to fit within
+    /// the rectangle `min..=max`.
+    pub(super) fn fitted(&self, min: DVec2, max: DVec2) -> Self {
+        let center = self.center.clamp(min, max);
+        let room = (center - min).min(max - center).min_element();
+        Self {
+            center,
+            radius: self.radius.min(room),
+            ..*self
+        }
+    }
+
+    /// this primitive perturbed by `jitter` about its center.
+    pub(super) fn jittered(&self, jitter: &Jitter) -> Self {
+        Self {
+            center: self.center + jitter.offset,
+            radius: self.radius * jitter.scale,
+            rotation: self.rotation + jitter.rotation,
+            ..*self
+        }
+    }
+
+    /// angles of the vertices of a circle, starting at the rotation to the
+    /// right of the center.
+    pub(super) fn circle_angles(&self) -> Vec<f64> {
+        let n = self.n_vertices.max(3);
+        (0..n)
+            .map(|i| self.rotation + TAU * i as f64 / n as f64)
+            .collect()
+    }
+
+    /// the vertices along the outline, in order. a closed outline does not
+    /// repeat its first vertex.
+    pub(super) fn points(&self) -> Vec<DVec2> {
+        match self.kind {
+            PrimitiveKind::Circle => self
+                .circle_angles()
+                .into_iter()
+                .map(|theta| {
+                    self.center + self.radius * DVec2::from_angle(theta)
+                })
+                .collect(),
+            PrimitiveKind::Line => {
+                let n = self.n_vertices.max(2);
+                let half = DVec2::from_angle(self.rotation) * self.radius;
+                let (a, b) = (self.center - half, self.center + half);
+                (0..n)
+                    .map(|i| a.lerp(b, i as f64 / (n - 1) as f64))
+                    .collect()
+            }
+            PrimitiveKind::Polygon { sides } => {
+                let sides = sides.max(3);
+                // every side gets the same number of vertices, so the total
+                // is rounded down to a multiple of the number of sides
+                let per_side = (self.n_vertices / sides).max(1);
+                let corner = |k: usize| {
+                    let theta = self.rotation + TAU * k as f64 / sides as f64
+                        - TAU / 4.;
+                    self.center + self.radius * DVec2::from_angle(theta)
+                };
+
+                (0..sides)
+                    .flat_map(|k| {
+                        let (a, b) = (corner(k), corner(k + 1));
+                        (0..per_side).map(move |j| {
+                            a.lerp(b, j as f64 / per_side as f64)
+                        })
+                    })
+                    .collect()
+            }
+        }
+    }
+}
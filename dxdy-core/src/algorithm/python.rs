@@ -0,0 +1,577 @@
+//! the python module `dxdy_core`, built with the `pyo3` feature: the
+//! segments and differential lines as classes with the methods of the
+//! cython modules they were ported from, so that scripts written against
+//! those can use these instead.
+//!
+//! the `np_*` methods fill an array allocated by the caller, with a row for
+//! every edge or vertex, and return the number of rows filled, as they did.
+//! an array with too few rows raises a `ValueError` rather than writing
+//! past its end.
+
+use glam::DVec2;
+use numpy::{
+    PyReadwriteArray1, PyReadwriteArray2,
+    ndarray::{ArrayViewMut2, Axis},
+};
+use pyo3::{exceptions::PyValueError, prelude::*};
+
+use super::{
+    DifferentialLine, SegmentsError,
+    domain::Domain,
+    ids::{EdgeId, VertexId},
+    segments::Segments,
+    spatial_index::IndexKind,
+    spawn, spawn_curvature,
+};
+
+impl From<SegmentsError> for PyErr {
+    fn from(err: SegmentsError) -> Self {
+        PyValueError::new_err(err.to_string())
+    }
+}
+
+/// copy `rows` into the first rows of `array`.
+fn fill<const N: usize, T: Copy>(
+    mut array: ArrayViewMut2<'_, T>,
+    rows: &[[T; N]],
+) -> PyResult<usize> {
+    if array.nrows() < rows.len() || array.ncols() != N {
+        return Err(PyValueError::new_err(format!(
+            "array of shape {:?} does not fit {} rows of {N}",
+            array.shape(),
+            rows.len(),
+        )));
+    }
+    for (mut to, row) in array.rows_mut().into_iter().zip(rows) {
+        to.iter_mut().zip(row).for_each(|(to, &x)| *to = x);
+    }
+    Ok(rows.len())
+}
+
+fn points(xys: Vec<[f64; 2]>) -> Vec<DVec2> {
+    xys.into_iter().map(DVec2::from).collect()
+}
+
+/// the slots of `vertices`, which python knows them by.
+fn slots(vertices: Vec<VertexId>) -> Vec<i64> {
+    vertices.into_iter().map(VertexId::slot).collect()
+}
+
+/// the id of the edge in slot `e1`, if there is one.
+fn edge(segments: &Segments, e1: i64) -> Result<EdgeId, SegmentsError> {
+    segments
+        .edge_id(e1)
+        .ok_or(SegmentsError::EdgeDoesNotExist { e1 })
+}
+
+/// split the edge of `id` at its midpoint, returning the new vertex, unless
+/// it is shorter than `min_len`.
+fn split(
+    segments: &mut Segments,
+    id: EdgeId,
+    min_len: Option<f64>,
+) -> Result<VertexId, SegmentsError> {
+    let len = segments.get_edge_length(id)?;
+    if let Some(min) = min_len.filter(|&min| len < min) {
+        let e1 = id.slot();
+        return Err(SegmentsError::EdgeTooShort { e1, len, min });
+    }
+    Ok(segments.split_edges(&[id])?[0])
+}
+
+/// split every edge longer than `limit` that has an active vertex.
+fn split_long_edges(
+    segments: &mut Segments,
+    limit: f64,
+) -> Result<(), SegmentsError> {
+    let edges = segments
+        .edge_ids()
+        .filter(|&e| {
+            let Ok(ends) = segments.edge_ends(e) else {
+                return false;
+            };
+            ends.into_iter().any(|v| segments.vertex_active(v))
+                && segments.get_edge_length(e).is_ok_and(|len| len > limit)
+        })
+        .collect::<Vec<_>>();
+    segments.split_edges(&edges).map(drop)
+}
+
+/// the vertices of every segment in the order they are linked, one segment
+/// after another in the order they were added.
+fn sorted_vertices(segments: &Segments) -> Vec<VertexId> {
+    let mut runs = segments
+        .runs()
+        .into_iter()
+        .map(|(vertices, _)| {
+            vertices
+                .into_iter()
+                .filter_map(|v| segments.vertex_id(v))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    // stable, so the runs of a segment keep their order
+    runs.sort_by_key(|vertices| segments.vertex_segment(vertices[0]).ok());
+    runs.concat()
+}
+
+/// the distance from `p` to the vertex farthest from it.
+fn greatest_distance(segments: &Segments, p: DVec2) -> f64 {
+    segments
+        .get_vertex_coordinates()
+        .into_iter()
+        .map(|q| p.distance_squared(q))
+        .fold(0., f64::max)
+        .sqrt()
+}
+
+/// the rows of `np_get_edges_coordinates`: both ends of every edge.
+fn edge_coordinate_rows(segments: &Segments) -> Vec<[f64; 4]> {
+    segments
+        .get_edges_coordinates()
+        .into_iter()
+        .map(|[p, q]| [p.x, p.y, q.x, q.y])
+        .collect()
+}
+
+/// the rows of `np_get_edges`: the vertices of every edge.
+fn edge_rows(segments: &Segments) -> Vec<[i64; 2]> {
+    segments
+        .edge_ids()
+        .filter_map(|e| segments.edge_ends(e).ok())
+        .map(|ends| ends.map(VertexId::slot))
+        .collect()
+}
+
+/// the rows of `np_get_vertex_coordinates`: every vertex, in slot order.
+fn vertex_coordinate_rows(segments: &Segments) -> Vec<[f64; 2]> {
+    segments
+        .get_vertex_coordinates()
+        .into_iter()
+        .map(|p| p.to_array())
+        .collect()
+}
+
+/// the rows of `np_get_sorted_vertices`: every vertex, in order along the
+/// segments.
+fn sorted_vertex_rows(segments: &Segments) -> Vec<[i64; 1]> {
+    sorted_vertices(segments)
+        .into_iter()
+        .map(|v| [v.slot()])
+        .collect()
+}
+
+/// the rows of `np_get_sorted_vertex_coordinates`.
+fn sorted_vertex_coordinate_rows(segments: &Segments) -> Vec<[f64; 2]> {
+    sorted_vertices(segments)
+        .into_iter()
+        .filter_map(|v| segments.vertex_position(v).ok())
+        .map(|p| p.to_array())
+        .collect()
+}
+
+/// the methods of `$class`: those of its own, and those shared by both
+/// classes, on its segments.
+macro_rules! methods {
+    ($class:ident, $($segments:ident).+, { $($own:tt)* }) => {
+        #[pymethods]
+        impl $class {
+            $($own)*
+
+            #[pyo3(signature = (xys, lock_edges = true))]
+            fn init_line_segment(
+                &mut self,
+                xys: Vec<[f64; 2]>,
+                lock_edges: bool,
+            ) -> PyResult<Vec<i64>> {
+                let xys = points(xys);
+                let segments = &mut self.$($segments).+;
+                Ok(slots(segments.init_line_segment(&xys, lock_edges)?))
+            }
+
+            fn init_closed_line_segment(
+                &mut self,
+                xys: Vec<[f64; 2]>,
+            ) -> PyResult<Vec<i64>> {
+                let xys = points(xys);
+                let segments = &mut self.$($segments).+;
+                Ok(slots(segments.init_closed_line_segment(&xys)?))
+            }
+
+            fn init_circle_segment(
+                &mut self,
+                x: f64,
+                y: f64,
+                r: f64,
+                angles: Vec<f64>,
+            ) -> PyResult<Vec<i64>> {
+                let segments = &mut self.$($segments).+;
+                Ok(slots(segments.init_circle_segment(x, y, r, &angles)?))
+            }
+
+            /// split edge `e1` at its midpoint, unless it is shorter than
+            /// `minimum_length`, returning the new vertex.
+            #[pyo3(signature = (e1, minimum_length = None))]
+            fn split_edge(
+                &mut self,
+                e1: i64,
+                minimum_length: Option<f64>,
+            ) -> PyResult<i64> {
+                let segments = &mut self.$($segments).+;
+                let id = edge(segments, e1)?;
+                Ok(split(segments, id, minimum_length)?.slot())
+            }
+
+            /// collapse edge `e1` into its second vertex, unless it is
+            /// longer than `maximum_length`.
+            #[pyo3(signature = (e1, maximum_length = None))]
+            fn collapse_edge(
+                &mut self,
+                e1: i64,
+                maximum_length: Option<f64>,
+            ) -> PyResult<()> {
+                let segments = &mut self.$($segments).+;
+                let id = edge(segments, e1)?;
+                segments.collapse(id, maximum_length)?;
+                Ok(())
+            }
+
+            fn split_long_edges(&mut self, limit: f64) -> PyResult<()> {
+                Ok(split_long_edges(&mut self.$($segments).+, limit)?)
+            }
+
+            fn np_get_edges_coordinates(
+                &self,
+                mut a: PyReadwriteArray2<'_, f64>,
+            ) -> PyResult<usize> {
+                let rows = edge_coordinate_rows(&self.$($segments).+);
+                fill(a.as_array_mut(), &rows)
+            }
+
+            fn np_get_edges(
+                &self,
+                mut a: PyReadwriteArray2<'_, i64>,
+            ) -> PyResult<usize> {
+                fill(a.as_array_mut(), &edge_rows(&self.$($segments).+))
+            }
+
+            fn np_get_vertex_coordinates(
+                &self,
+                mut a: PyReadwriteArray2<'_, f64>,
+            ) -> PyResult<usize> {
+                let rows = vertex_coordinate_rows(&self.$($segments).+);
+                fill(a.as_array_mut(), &rows)
+            }
+
+            fn np_get_sorted_vertices(
+                &self,
+                mut a: PyReadwriteArray1<'_, i64>,
+            ) -> PyResult<usize> {
+                let rows = sorted_vertex_rows(&self.$($segments).+);
+                fill(a.as_array_mut().insert_axis(Axis(1)), &rows)
+            }
+
+            fn np_get_sorted_vertex_coordinates(
+                &self,
+                mut a: PyReadwriteArray2<'_, f64>,
+            ) -> PyResult<usize> {
+                let rows =
+                    sorted_vertex_coordinate_rows(&self.$($segments).+);
+                fill(a.as_array_mut(), &rows)
+            }
+
+            fn get_greatest_distance(&self, x: f64, y: f64) -> f64 {
+                greatest_distance(&self.$($segments).+, DVec2::new(x, y))
+            }
+
+            fn get_active_vertex_count(&self) -> usize {
+                self.$($segments).+.get_active_vertex_count()
+            }
+
+            fn get_vnum(&self) -> u64 {
+                self.$($segments).+.v_num()
+            }
+
+            /// the number of edges, as many as `np_get_edges` fills rows.
+            fn get_enum(&self) -> usize {
+                self.$($segments).+.edge_ids().count()
+            }
+
+            fn get_snum(&self) -> u64 {
+                self.$($segments).+.s_num()
+            }
+        }
+    };
+}
+
+/// lines of vertices joined by edges, without any growth.
+#[pyclass(name = "Segments", unsendable)]
+struct PySegments {
+    segments: Segments,
+}
+
+methods!(PySegments, segments, {
+    #[new]
+    fn new(nmax: u64, zonewidth: f64) -> PyResult<Self> {
+        Ok(Self {
+            segments: Segments::new(
+                nmax,
+                zonewidth,
+                IndexKind::ZoneMap,
+                DVec2::ZERO,
+                1.,
+            )?,
+        })
+    }
+});
+
+/// segments that grow: vertices push apart and pull along their lines, and
+/// edges split.
+#[pyclass(name = "DifferentialLine", unsendable)]
+struct PyDifferentialLine {
+    df: DifferentialLine,
+}
+
+methods!(PyDifferentialLine, df.segments, {
+    #[new]
+    #[pyo3(signature = (nmax, zonewidth, nearl, farl, seed = 0))]
+    fn new(
+        nmax: u64,
+        zonewidth: f64,
+        nearl: f64,
+        farl: f64,
+        seed: u64,
+    ) -> PyResult<Self> {
+        Ok(Self {
+            df: DifferentialLine::with_domain(
+                nmax,
+                zonewidth,
+                nearl,
+                farl,
+                seed,
+                IndexKind::ZoneMap,
+                Domain::Square,
+            )?,
+        })
+    }
+
+    /// move every vertex by at most `step`.
+    fn optimize_position(&mut self, step: f64) -> PyResult<()> {
+        Ok(self.df.optimize_position(step)?)
+    }
+
+    /// split edges at least `nearl` long, each with probability `limit`.
+    fn spawn(&mut self, nearl: f64, limit: f64) -> PyResult<()> {
+        Ok(spawn(&mut self.df, nearl, limit)?)
+    }
+
+    /// like `spawn`, in proportion to how sharply the line turns at each
+    /// edge.
+    fn spawn_curvature(&mut self, nearl: f64, limit: f64) -> PyResult<()> {
+        Ok(spawn_curvature(&mut self.df, nearl, limit)?)
+    }
+});
+
+#[pymodule]
+fn dxdy_core(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PySegments>()?;
+    m.add_class::<PyDifferentialLine>()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use numpy::ndarray::{Array1, Array2, array};
+
+    use super::*;
+
+    #[test]
+    fn seeds_of_too_few_points_raise_value_errors() {
+        Python::initialize();
+        Python::attach(|py| {
+            let mut segments = PySegments::new(64, 0.1).unwrap();
+            let xys = |n| vec![[0.5; 2]; n];
+            let raised = |result: PyResult<_>| {
+                result.unwrap_err().is_instance_of::<PyValueError>(py)
+            };
+
+            for n in 0..2 {
+                for lock_edges in [false, true] {
+                    let line = segments.init_line_segment(xys(n), lock_edges);
+                    assert!(raised(line));
+                }
+            }
+            for n in 0..3 {
+                assert!(raised(segments.init_closed_line_segment(xys(n))));
+                let angles = vec![0.; n];
+                let circle =
+                    segments.init_circle_segment(0.5, 0.5, 0.1, angles);
+                assert!(raised(circle));
+            }
+            assert_eq!(segments.get_vnum(), 0);
+            assert_eq!(segments.get_snum(), 0);
+        });
+    }
+
+    #[test]
+    fn edges_split_and_collapse_within_their_bounds() {
+        Python::initialize();
+        Python::attach(|py| {
+            let mut segments = PySegments::new(64, 0.1).unwrap();
+            let angles = (0..8)
+                .map(|i| i as f64 * std::f64::consts::TAU / 8.)
+                .collect();
+            segments.init_circle_segment(0.5, 0.5, 0.1, angles).unwrap();
+            let raised = |result: PyResult<_>| {
+                result.unwrap_err().is_instance_of::<PyValueError>(py)
+            };
+
+            // the edges of the octagon are about 0.077 long
+            assert!(raised(segments.split_edge(0, Some(0.1)).map(drop)));
+            assert_eq!(segments.split_edge(0, Some(0.05)).unwrap(), 8);
+            assert!(raised(segments.collapse_edge(1, Some(0.01))));
+            segments.collapse_edge(1, None).unwrap();
+            assert!(raised(segments.collapse_edge(1, None)));
+            assert_eq!(segments.get_active_vertex_count(), 8);
+        });
+    }
+
+    #[test]
+    fn long_edges_split_in_two() {
+        let mut segments = PySegments::new(64, 0.1).unwrap();
+        let angles = (0..8)
+            .map(|i| i as f64 * std::f64::consts::TAU / 8.)
+            .collect();
+        segments.init_circle_segment(0.5, 0.5, 0.1, angles).unwrap();
+        let greatest = segments.get_greatest_distance(0.5, 0.5);
+        assert!((greatest - 0.1).abs() < 1e-12);
+
+        // the edges of the octagon are about 0.077 long
+        segments.split_long_edges(0.08).unwrap();
+        assert_eq!(segments.get_enum(), 8);
+        segments.split_long_edges(0.05).unwrap();
+        assert_eq!(segments.get_enum(), 16);
+        assert_eq!(segments.get_active_vertex_count(), 16);
+    }
+
+    #[test]
+    fn lengths_that_are_not_positive_raise_value_errors() {
+        Python::initialize();
+        Python::attach(|py| {
+            let raised = |result: PyResult<_>| {
+                result.unwrap_err().is_instance_of::<PyValueError>(py)
+            };
+
+            for len in [0., -0.1, f64::NAN, f64::INFINITY] {
+                assert!(raised(PySegments::new(64, len).map(drop)));
+                let df = |zonewidth, nearl, farl| {
+                    PyDifferentialLine::new(64, zonewidth, nearl, farl, 0)
+                        .map(drop)
+                };
+                assert!(raised(df(len, 0.01, 0.1)));
+                assert!(raised(df(0.1, len, 0.1)));
+                assert!(raised(df(0.1, 0.01, len)));
+            }
+        });
+    }
+
+    #[test]
+    fn arrays_are_filled_with_a_row_for_every_edge_or_vertex() {
+        Python::initialize();
+        Python::attach(|py| {
+            let mut segments = PySegments::new(64, 0.1).unwrap();
+            let xys = vec![[0.1, 0.2], [0.3, 0.4], [0.5, 0.6]];
+            segments.init_line_segment(xys, false).unwrap();
+            let segments = &segments.segments;
+
+            // the rows filled, then the rest of the array as it was
+            let mut a = Array2::from_elem((4, 2), -1.);
+            let rows = vertex_coordinate_rows(segments);
+            assert_eq!(fill(a.view_mut(), &rows).unwrap(), 3);
+            assert_eq!(
+                a,
+                array![[0.1, 0.2], [0.3, 0.4], [0.5, 0.6], [-1., -1.]]
+            );
+
+            let mut a = Array2::zeros((2, 2));
+            assert_eq!(fill(a.view_mut(), &edge_rows(segments)).unwrap(), 2);
+            assert_eq!(a, array![[0, 1], [1, 2]]);
+
+            let mut a = Array2::zeros((2, 4));
+            let rows = edge_coordinate_rows(segments);
+            assert_eq!(fill(a.view_mut(), &rows).unwrap(), 2);
+            assert_eq!(a, array![[0.1, 0.2, 0.3, 0.4], [0.3, 0.4, 0.5, 0.6]]);
+
+            let mut a = Array1::zeros(3);
+            let rows = sorted_vertex_rows(segments);
+            let column = a.view_mut().insert_axis(Axis(1));
+            assert_eq!(fill(column, &rows).unwrap(), 3);
+            assert_eq!(a, array![0, 1, 2]);
+
+            let mut a = Array2::zeros((3, 2));
+            let rows = sorted_vertex_coordinate_rows(segments);
+            assert_eq!(fill(a.view_mut(), &rows).unwrap(), 3);
+            assert_eq!(a, array![[0.1, 0.2], [0.3, 0.4], [0.5, 0.6]]);
+
+            // too few rows, or columns that do not fit a row
+            let rows = vertex_coordinate_rows(segments);
+            for shape in [(2, 2), (4, 3)] {
+                let mut a = Array2::zeros(shape);
+                let filled = fill(a.view_mut(), &rows);
+                assert!(
+                    filled.unwrap_err().is_instance_of::<PyValueError>(py)
+                );
+            }
+        });
+    }
+
+    #[test]
+    fn linked_vertices_farther_apart_than_nearl_are_pulled_together() {
+        Python::initialize();
+        Python::attach(|_| {
+            let mut df =
+                PyDifferentialLine::new(64, 0.2, 0.01, 0.2, 0).unwrap();
+            df.init_line_segment(vec![[0.4, 0.5], [0.45, 0.5]], false)
+                .unwrap();
+
+            // each moves a step towards the other, and nothing else pushes
+            df.optimize_position(0.001).unwrap();
+            let [p, q] = vertex_coordinate_rows(&df.df.segments)[..] else {
+                panic!("the vertices are gone");
+            };
+            assert!((p[0] - 0.401).abs() < 1e-12);
+            assert!((q[0] - 0.449).abs() < 1e-12);
+            assert_eq!([p[1], q[1]], [0.5; 2]);
+        });
+    }
+
+    #[test]
+    fn edges_spawn_with_their_probability_if_at_least_nearl_long() {
+        Python::initialize();
+        Python::attach(|_| {
+            let octagon = || {
+                let mut df =
+                    PyDifferentialLine::new(64, 0.1, 0.01, 0.1, 0).unwrap();
+                let angles = (0..8)
+                    .map(|i| i as f64 * std::f64::consts::TAU / 8.)
+                    .collect();
+                df.init_circle_segment(0.5, 0.5, 0.1, angles).unwrap();
+                df
+            };
+
+            // the edges of the octagon are about 0.077 long
+            let mut df = octagon();
+            df.spawn(0.05, 1.).unwrap();
+            assert_eq!(df.get_vnum(), 16);
+            assert_eq!(df.get_enum(), 16);
+
+            let mut df = octagon();
+            df.spawn(0.1, 1.).unwrap();
+            assert_eq!(df.get_vnum(), 8);
+
+            let mut df = octagon();
+            df.spawn(0.05, 0.).unwrap();
+            assert_eq!(df.get_vnum(), 8);
+        });
+    }
+}
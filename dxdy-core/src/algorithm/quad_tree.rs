@@ -0,0 +1,266 @@
+use std::cell::Cell;
+
+use super::spatial_index::{OccupancyStats, SpatialIndex};
+
+/// Maximum number of vertices in a leaf before it is subdivided.
+const BUCKET_SIZE: usize = 16;
+/// Maximum depth of the tree. Leaves at this depth are never subdivided.
+const MAX_DEPTH: u32 = 16;
+
+#[derive(Clone)]
+struct Node {
+    x0: f64,
+    y0: f64,
+    size: f64,
+    depth: u32,
+    /// Index of the first of the four child nodes, `-1` for leaves.
+    children: i64,
+    /// Vertices in this node. Always empty for internal nodes.
+    items: Vec<i64>,
+}
+
+impl Node {
+    fn contains(&self, x: f64, y: f64) -> bool {
+        (self.x0..=self.x0 + self.size).contains(&x)
+            && (self.y0..=self.y0 + self.size).contains(&y)
+    }
+
+    /// Whether this node intersects the square of half-width `rad` about
+    /// `(x, y)`.
+    fn intersects(&self, x: f64, y: f64, rad: f64) -> bool {
+        x + rad >= self.x0
+            && x - rad <= self.x0 + self.size
+            && y + rad >= self.y0
+            && y - rad <= self.y0 + self.size
+    }
+}
+
+/// Point quadtree over the unit square that adapts to uneven vertex density,
+/// unlike the fixed grid of [`super::zone_map::ZoneMap`].
+///
+/// Positions outside of the unit square are clamped onto it for placement.
+/// Clamping never increases the distance between two points, so queries
+/// remain exact.
+#[derive(Clone)]
+pub(super) struct QuadTree {
+    v_num: u64,
+    nodes: Vec<Node>,
+    /// Map of vertex to leaf node by vertex index, `-1` for vertices that are
+    /// not in the tree.
+    vn: Vec<i64>,
+    /// Map of vertex to its clamped position in the tree by vertex index.
+    vp: Vec<[f64; 2]>,
+    /// Number of sphere queries.
+    queries: Cell<u64>,
+    /// Number of vertices examined by sphere queries.
+    query_candidates: Cell<u64>,
+}
+
+//===================================================================
+// Constructors
+//===================================================================
+
+impl QuadTree {
+    pub(super) fn new() -> Self {
+        Self {
+            v_num: 0,
+            nodes: vec![Node {
+                x0: 0.,
+                y0: 0.,
+                size: 1.,
+                depth: 0,
+                children: -1,
+                items: Vec::with_capacity(BUCKET_SIZE),
+            }],
+            vn: Vec::new(),
+            vp: Vec::new(),
+            queries: Cell::new(0),
+            query_candidates: Cell::new(0),
+        }
+    }
+}
+
+//===================================================================
+// Helpers
+//===================================================================
+
+fn clamp_to_unit(x: f64, y: f64) -> [f64; 2] {
+    [x.clamp(0., 1.), y.clamp(0., 1.)]
+}
+
+//===================================================================
+// Private Methods
+//===================================================================
+
+impl QuadTree {
+    /// Index of the leaf containing `(x, y)`.
+    fn find_leaf(&self, x: f64, y: f64) -> usize {
+        let mut n = 0;
+        while self.nodes[n].children > -1 {
+            let node = &self.nodes[n];
+            let half = node.size / 2.;
+            let i = (x > node.x0 + half) as usize;
+            let j = (y > node.y0 + half) as usize;
+            n = node.children as usize + 2 * i + j;
+        }
+        n
+    }
+
+    fn insert(&mut self, v1: i64) {
+        let [x, y] = self.vp[v1 as usize];
+        let n = self.find_leaf(x, y);
+
+        self.nodes[n].items.push(v1);
+        self.vn[v1 as usize] = n as i64;
+
+        let node = &self.nodes[n];
+        if node.items.len() > BUCKET_SIZE && node.depth < MAX_DEPTH {
+            self.subdivide(n);
+        }
+    }
+
+    fn subdivide(&mut self, n: usize) {
+        let Node {
+            x0,
+            y0,
+            size,
+            depth,
+            ..
+        } = self.nodes[n];
+        let half = size / 2.;
+
+        let children = self.nodes.len();
+        for (dx, dy) in [(0., 0.), (0., half), (half, 0.), (half, half)] {
+            self.nodes.push(Node {
+                x0: x0 + dx,
+                y0: y0 + dy,
+                size: half,
+                depth: depth + 1,
+                children: -1,
+                items: Vec::with_capacity(BUCKET_SIZE),
+            });
+        }
+
+        self.nodes[n].children = children as i64;
+        for v1 in std::mem::take(&mut self.nodes[n].items) {
+            self.insert(v1);
+        }
+    }
+
+    fn remove(&mut self, v1: i64) {
+        let n = self.vn[v1 as usize] as usize;
+        let items = &mut self.nodes[n].items;
+        if let Some(i) = items.iter().position(|&v| v == v1) {
+            items.swap_remove(i);
+        }
+    }
+}
+
+//===================================================================
+// Public Methods
+//===================================================================
+
+impl SpatialIndex for QuadTree {
+    fn add_vertex(&mut self, v1: u64, xs: &[f64], ys: &[f64]) -> u64 {
+        if v1 as usize >= self.vn.len() {
+            self.vn.resize(v1 as usize + 1, -1);
+            self.vp.resize(v1 as usize + 1, [0., 0.]);
+        }
+
+        self.vp[v1 as usize] = clamp_to_unit(xs[v1 as usize], ys[v1 as usize]);
+        self.insert(v1 as i64);
+
+        self.v_num += 1;
+        v1
+    }
+
+    fn delete_vertex(&mut self, v1: i64) {
+        if self.vn[v1 as usize] < 0 {
+            return;
+        }
+
+        self.remove(v1);
+        self.vn[v1 as usize] = -1;
+        self.v_num -= 1;
+    }
+
+    fn update_vertex(&mut self, v1: i64, x: f64, y: f64) {
+        let n = self.vn[v1 as usize];
+        if n < 0 {
+            return;
+        }
+
+        let [x, y] = clamp_to_unit(x, y);
+        self.vp[v1 as usize] = [x, y];
+
+        if !self.nodes[n as usize].contains(x, y) {
+            self.remove(v1);
+            self.insert(v1);
+        }
+    }
+
+    fn get_max_sphere_count(&self) -> u64 {
+        self.v_num
+    }
+
+    fn sphere_at(
+        &self,
+        x: f64,
+        y: f64,
+        xs: &[f64],
+        ys: &[f64],
+        rad: f64,
+        vertices: &mut Vec<i64>,
+    ) -> usize {
+        let [cx, cy] = clamp_to_unit(x, y);
+
+        let rad2 = rad * rad;
+
+        vertices.clear();
+
+        self.queries.set(self.queries.get() + 1);
+
+        let mut stack = vec![0];
+        while let Some(n) = stack.pop() {
+            let node = &self.nodes[n];
+            if !node.intersects(cx, cy, rad) {
+                continue;
+            }
+
+            if node.children > -1 {
+                let c = node.children as usize;
+                stack.extend(c..c + 4);
+                continue;
+            }
+
+            self.query_candidates
+                .set(self.query_candidates.get() + node.items.len() as u64);
+            for &l in &node.items {
+                let dx = x - xs[l as usize];
+                let dy = y - ys[l as usize];
+                if dx * dx + dy * dy < rad2 {
+                    vertices.push(l);
+                }
+            }
+        }
+
+        vertices.len()
+    }
+
+    /// Stats over the leaves of the tree, which play the role of zones.
+    fn occupancy_stats(&self) -> OccupancyStats {
+        OccupancyStats::from_zone_sizes(
+            self.nodes
+                .iter()
+                .filter(|n| n.children < 0)
+                .map(|n| n.items.len()),
+            self.queries.get(),
+            self.query_candidates.get(),
+        )
+    }
+
+    fn reset_query_stats(&self) {
+        self.queries.set(0);
+        self.query_candidates.set(0);
+    }
+}
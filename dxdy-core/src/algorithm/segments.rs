@@ -0,0 +1,2190 @@
+use std::{fmt, ops};
+
+use glam::DVec2;
+
+use super::{
+    ids::{EdgeId, Generations, VertexId},
+    spatial_index::{Index, IndexKind, SpatialIndex},
+};
+
+/// errors from operations on the segments of a simulation with invalid
+/// input.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SegmentsError {
+    /// the vertex position is outside the unit square.
+    VertexOutOfBounds { x: f64, y: f64 },
+    /// an edge cannot be created between the vertices.
+    InvalidNewEdge { v1: i64, v2: i64 },
+    /// the edge index is out of range.
+    InvalidEdge { e1: i64 },
+    /// the edge has been deleted.
+    EdgeDoesNotExist { e1: i64 },
+    /// the edge has a vertex that does not belong to a segment.
+    InvalidSegment { e1: i64, v1: i64 },
+    /// the edge cannot be collapsed because it has a passive vertex.
+    PassiveVertex { e1: i64, v1: i64 },
+    /// the edge cannot be collapsed because it ends an open segment.
+    SegmentEnd { e1: i64, v1: i64 },
+    /// the edge cannot be collapsed because its loop is a triangle.
+    TriangleLoop { e1: i64 },
+    /// the edge is longer than the maximum allowed length.
+    EdgeTooLong { e1: i64, len: f64, max: f64 },
+    /// the edge is shorter than the minimum allowed length.
+    EdgeTooShort { e1: i64, len: f64, min: f64 },
+    /// the edge is not connected to its neighbors.
+    EdgesNotConnected { e1: i64 },
+    /// the edge and its neighbors are colinear.
+    NoCurvature { e1: i64 },
+    /// there is not enough storage left for the new vertices/edges.
+    CapacityExceeded { n_max: u64 },
+    /// the vertex of an id has been deleted.
+    StaleVertex { v1: i64 },
+    /// the edge of an id has been deleted.
+    StaleEdge { e1: i64 },
+    /// a new segment has fewer vertices than a line, or a loop, needs.
+    TooFewVertices { n: usize, min: usize },
+    /// a length the segments are made with is not finite and positive.
+    InvalidLength { name: &'static str, len: f64 },
+}
+
+impl fmt::Display for SegmentsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::VertexOutOfBounds { x, y } => {
+                write!(f, "vertex is outside the unit square: ({x}, {y})")
+            }
+            Self::InvalidNewEdge { v1, v2 } => {
+                write!(f, "invalid vertex: v{v1} -> v{v2}")
+            }
+            Self::InvalidEdge { e1 } => write!(f, "invalid edge: e{e1}"),
+            Self::EdgeDoesNotExist { e1 } => {
+                write!(f, "edge does not exist: e{e1}")
+            }
+            Self::InvalidSegment { e1, v1 } => {
+                write!(f, "invalid segment: e{e1} | v{v1}")
+            }
+            Self::PassiveVertex { e1, v1 } => {
+                write!(f, "edge has passive vertex: e{e1} | v{v1}")
+            }
+            Self::SegmentEnd { e1, v1 } => {
+                write!(f, "edge ends an open segment: e{e1} | v{v1}")
+            }
+            Self::TriangleLoop { e1 } => {
+                write!(f, "edge is part of a triangle: e{e1}")
+            }
+            Self::EdgeTooLong { e1, len, max } => write!(
+                f,
+                "cannot collapse edge longer than the maximum: e{e1}, len={len:.4}, max={max:.4}"
+            ),
+            Self::EdgeTooShort { e1, len, min } => write!(
+                f,
+                "cannot split edge shorter than the minimum: e{e1}, len={len:.4}, min={min:.4}"
+            ),
+            Self::EdgesNotConnected { e1 } => {
+                write!(f, "edges are not connected: e{e1}")
+            }
+            Self::NoCurvature { e1 } => write!(f, "no curvature: e{e1}"),
+            Self::CapacityExceeded { n_max } => {
+                write!(f, "capacity exceeded: n_max={n_max}")
+            }
+            Self::StaleVertex { v1 } => {
+                write!(f, "vertex has been deleted: v{v1}")
+            }
+            Self::StaleEdge { e1 } => {
+                write!(f, "edge has been deleted: e{e1}")
+            }
+            Self::TooFewVertices { n, min } => {
+                write!(f, "too few vertices: {n}, at least {min} needed")
+            }
+            Self::InvalidLength { name, len } => {
+                write!(f, "{name} is not a positive length: {len}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for SegmentsError {}
+
+/// check that a new segment of `n` vertices has enough for a line, or for
+/// a loop if `closed`, which takes three not to join two vertices twice.
+pub(super) fn check_vertex_count(
+    n: usize,
+    closed: bool,
+) -> Result<(), SegmentsError> {
+    let min = if closed { 3 } else { 2 };
+    if n < min {
+        Err(SegmentsError::TooFewVertices { n, min })
+    } else {
+        Ok(())
+    }
+}
+
+/// check that `len`, the length called `name`, is finite and positive.
+pub(super) fn check_length(
+    name: &'static str,
+    len: f64,
+) -> Result<(), SegmentsError> {
+    if len.is_finite() && len > 0. {
+        Ok(())
+    } else {
+        Err(SegmentsError::InvalidLength { name, len })
+    }
+}
+
+/// the vertex coordinates of one connected run of edges, in order.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SegmentPath {
+    /// the segment every vertex of the path belongs to.
+    pub segment: i64,
+    /// whether the path is a fixed obstacle made of passive vertices.
+    pub passive: bool,
+    pub points: Vec<DVec2>,
+    /// whether the last vertex is joined back to the first.
+    pub closed: bool,
+}
+
+/// where every vertex was at one point, taken by
+/// [`Simulation::positions`](super::Simulation::positions).
+/// vertices added since, even in the slot of one deleted since, are not in
+/// it.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Positions(Vec<Option<(VertexId, DVec2)>>);
+
+impl Positions {
+    /// the position of every vertex there was.
+    pub fn iter(&self) -> impl Iterator<Item = DVec2> {
+        self.0.iter().flatten().map(|&(_, p)| p)
+    }
+}
+
+/// where [`Segments::compact`] moved each vertex and edge, to bring along
+/// anything that refers to them.
+pub(super) struct Remap {
+    /// the id of each vertex before and after by its old slot, `None` for
+    /// slots that were free.
+    vertices: Vec<Option<(VertexId, VertexId)>>,
+    /// like `vertices`, for the edges.
+    edges: Vec<Option<(EdgeId, EdgeId)>>,
+}
+
+impl Remap {
+    /// the new id of the vertex of `id`, or `None` if it was deleted before
+    /// the compaction.
+    pub(super) fn vertex(&self, id: VertexId) -> Option<VertexId> {
+        match self.vertices.get(id.slot() as usize) {
+            Some(&Some((old, new))) if old == id => Some(new),
+            _ => None,
+        }
+    }
+
+    /// the new id of the edge of `id`, like [`Self::vertex`].
+    pub(super) fn edge(&self, id: EdgeId) -> Option<EdgeId> {
+        match self.edges.get(id.slot() as usize) {
+            Some(&Some((old, new))) if old == id => Some(new),
+            _ => None,
+        }
+    }
+}
+
+/// what the slot of a vertex holds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum VertexStatus {
+    /// nothing: the vertex was deleted, or the slot was never used.
+    Deleted,
+    /// a vertex that repels the others but never moves or splits.
+    Passive,
+    /// a vertex that moves and spawns.
+    Active,
+}
+
+/// vertices and edges that storage is made for at first. it grows as they
+/// are added, by doubling up to `n_max`.
+const INITIAL_CAPACITY: u64 = 1024;
+
+/// linked vertex segments optimized for differential growth-like operations
+/// like spltting edges by inserting new vertices, and collapsing edges.
+///
+/// all vertices must exist within the unit square.
+#[derive(Clone)]
+pub(super) struct Segments {
+    /// the most vertices, and the most edges, there is ever storage for.
+    n_max: u64,
+
+    /// Number of vertex slots that have been used, including those of
+    /// deleted vertices.
+    v_num: u64,
+    /// Number of vertices ever added, including those since deleted.
+    v_added: u64,
+    /// Number of edge slots that have been used, including those of deleted
+    /// edges.
+    e_num: u64,
+    /// Number of line segments.
+    s_num: u64,
+
+    /// Map of vertex `x` coordinates by vertex index.
+    pub(super) x: Vec<f64>,
+    /// Map of vertex `y` coordinates by vertex index.
+    pub(super) y: Vec<f64>,
+    /// Map of vertex status by vertex index.
+    va: Vec<VertexStatus>,
+    /// Map of vertex to line segment index by vertex index.
+    vs: Vec<i64>,
+    /// Map of vertex spawn weight by vertex index. Scales the probability
+    /// that edges incident to the vertex are split.
+    vw: Vec<f64>,
+    /// Map of edge to vertices (`v1` and `v2`) by edge index.
+    /// `v1, v2 = self.ev[2 * e], self.ev[2 * e + 1]`
+    ev: Vec<i64>,
+    /// Map of vertex to its edges (`e1` and `e2`) by vertex index, `-1`
+    /// where it has fewer than 2.
+    ve: Vec<i64>,
+    /// Generation of each vertex slot, for [`VertexId`]s.
+    vg: Generations,
+    /// Generation of each edge slot, for [`EdgeId`]s.
+    eg: Generations,
+    /// Slots of deleted vertices below `v_num`, taken again by the next
+    /// vertices added, the most recently freed first.
+    free_vertices: Vec<i64>,
+    /// Slots of deleted edges below `e_num`, like `free_vertices`.
+    free_edges: Vec<i64>,
+
+    /// Spatial index of all live vertices.
+    pub(super) index: Index,
+
+    /// Edges that were added, or had a vertex weight changed, since the last
+    /// call to [`Self::take_dirty_edges`]. May contain duplicates and edges
+    /// that have since been deleted.
+    dirty_edges: Vec<EdgeId>,
+}
+
+/// the state of [`Segments`] at one point, taken by [`Segments::snapshot`]
+/// to be put back by [`Segments::restore`].
+///
+/// only the slots in use are kept, rather than all the storage grown for
+/// them, so snapshots are small next to the segments while many slots are
+/// left.
+#[derive(Clone)]
+pub(super) struct SegmentsSnapshot {
+    v_num: u64,
+    v_added: u64,
+    e_num: u64,
+    s_num: u64,
+    x: Vec<f64>,
+    y: Vec<f64>,
+    va: Vec<VertexStatus>,
+    vs: Vec<i64>,
+    vw: Vec<f64>,
+    ev: Vec<i64>,
+    ve: Vec<i64>,
+    /// kept as they were, so that the ids handed out before the snapshot
+    /// name the same vertices and edges again once it is restored.
+    vg: Generations,
+    eg: Generations,
+    free_vertices: Vec<i64>,
+    free_edges: Vec<i64>,
+    /// kept as is rather than rebuilt, so that neighbor queries return
+    /// vertices in the same order and a restored run grows the same.
+    index: Index,
+    dirty_edges: Vec<EdgeId>,
+}
+
+//===================================================================
+// Constructors
+//===================================================================
+
+impl Segments {
+    /// initialize triangular mesh, using `index_kind` for the spatial index,
+    /// with the zones covering the square with lower corner `origin` and
+    /// side `size`, which should contain every vertex.
+    ///
+    /// - nmax is the maximal number of vertices/edges. storage is grown as
+    ///   they are added, up to it
+    /// - `zone_width` is only used by [`IndexKind::ZoneMap`], but must be
+    ///   finite and positive either way
+    pub(super) fn new(
+        n_max: u64,
+        zone_width: f64,
+        index_kind: IndexKind,
+        origin: DVec2,
+        size: f64,
+    ) -> Result<Self, SegmentsError> {
+        check_length("zone width", zone_width)?;
+        let mut nz = (size / zone_width) as u64;
+        if nz < 3 {
+            nz = 1;
+        }
+
+        let mut segments = Self {
+            n_max,
+            v_num: 0,
+            v_added: 0,
+            e_num: 0,
+            s_num: 0,
+            x: Vec::new(),
+            y: Vec::new(),
+            va: Vec::new(),
+            vs: Vec::new(),
+            vw: Vec::new(),
+            ev: Vec::new(),
+            ve: Vec::new(),
+            vg: Generations::new(0),
+            eg: Generations::new(0),
+            free_vertices: Vec::new(),
+            free_edges: Vec::new(),
+            index: Index::covering(index_kind, nz, origin.to_array(), size),
+            dirty_edges: Vec::new(),
+        };
+        let initial = INITIAL_CAPACITY.min(n_max);
+        segments.reserve_vertices(initial);
+        segments.reserve_edges(initial);
+        Ok(segments)
+    }
+}
+
+//===================================================================
+// Helpers
+//===================================================================
+
+fn valid_new_vertex(x: f64, y: f64) -> bool {
+    const R: ops::RangeInclusive<f64> = 0.0..=1.0;
+    R.contains(&x) && R.contains(&y)
+}
+
+//===================================================================
+// Private Methods
+//===================================================================
+
+impl Segments {
+    fn add_vertex(
+        &mut self,
+        x: f64,
+        y: f64,
+        s: i64,
+    ) -> Result<i64, SegmentsError> {
+        if !valid_new_vertex(x, y) {
+            return Err(SegmentsError::VertexOutOfBounds { x, y });
+        }
+
+        let v = self.take_vertex_slot()?;
+
+        self.x[v] = x;
+        self.y[v] = y;
+        self.va[v] = VertexStatus::Active;
+        self.vs[v] = s;
+        self.vw[v] = 1.;
+
+        self.index.add_vertex(v as u64, &self.x, &self.y);
+
+        Ok(v as i64)
+    }
+
+    fn add_passive_vertex(
+        &mut self,
+        x: f64,
+        y: f64,
+        s: i64,
+    ) -> Result<i64, SegmentsError> {
+        if !valid_new_vertex(x, y) {
+            return Err(SegmentsError::VertexOutOfBounds { x, y });
+        }
+
+        let v = self.take_vertex_slot()?;
+
+        self.x[v] = x;
+        self.y[v] = y;
+        self.va[v] = VertexStatus::Passive;
+        self.vs[v] = s;
+        // passive vertices never spawn, so their edges are never split
+        self.vw[v] = 0.;
+
+        self.index.add_vertex(v as u64, &self.x, &self.y);
+
+        Ok(v as i64)
+    }
+
+    /// a slot for a new vertex without edges: the most recently freed one,
+    /// or else the first that was never used.
+    fn take_vertex_slot(&mut self) -> Result<usize, SegmentsError> {
+        let v = match self.free_vertices.pop() {
+            Some(v) => v as usize,
+            None if self.v_num < self.n_max => {
+                self.reserve_vertices(self.v_num + 1);
+                self.v_num += 1;
+                self.v_num as usize - 1
+            }
+            None => {
+                return Err(SegmentsError::CapacityExceeded {
+                    n_max: self.n_max,
+                });
+            }
+        };
+
+        self.ve[2 * v] = -1;
+        self.ve[2 * v + 1] = -1;
+        self.v_added += 1;
+        Ok(v)
+    }
+
+    /// a slot for a new edge, like [`Self::take_vertex_slot`].
+    fn take_edge_slot(&mut self) -> Result<usize, SegmentsError> {
+        match self.free_edges.pop() {
+            Some(e) => Ok(e as usize),
+            None if self.e_num < self.n_max => {
+                self.reserve_edges(self.e_num + 1);
+                self.e_num += 1;
+                Ok(self.e_num as usize - 1)
+            }
+            None => Err(SegmentsError::CapacityExceeded { n_max: self.n_max }),
+        }
+    }
+
+    /// grow the storage of the vertices to hold at least `n`, and no more
+    /// than `n_max`, at least doubling it so that adding vertices one by one
+    /// takes amortized constant time.
+    fn reserve_vertices(&mut self, n: u64) {
+        let len = self.x.len() as u64;
+        if n <= len {
+            return;
+        }
+        let len = n.max(2 * len).min(self.n_max) as usize;
+        self.x.resize(len, 0.);
+        self.y.resize(len, 0.);
+        self.va.resize(len, VertexStatus::Deleted);
+        self.vs.resize(len, -1);
+        self.vw.resize(len, 1.);
+        self.ve.resize(2 * len, -1);
+        self.vg.resize(len);
+    }
+
+    /// grow the storage of the edges, like [`Self::reserve_vertices`].
+    fn reserve_edges(&mut self, n: u64) {
+        let len = self.ev.len() as u64 / 2;
+        if n <= len {
+            return;
+        }
+        let len = n.max(2 * len).min(self.n_max) as usize;
+        self.ev.resize(2 * len, -1);
+        self.eg.resize(len);
+    }
+
+    /// how many vertices can be added before running out of room.
+    fn vertex_room(&self) -> u64 {
+        self.n_max - self.v_num + self.free_vertices.len() as u64
+    }
+
+    /// how many edges can be added before running out of room.
+    fn edge_room(&self) -> u64 {
+        self.n_max - self.e_num + self.free_edges.len() as u64
+    }
+
+    fn valid_new_edge(&self, v1: i64, v2: i64) -> bool {
+        self.has_vertex(v1) && self.has_vertex(v2)
+    }
+
+    /// add edge between vertices v1 and v2. returns id of new edge
+    fn add_edge(&mut self, v1: i64, v2: i64) -> Result<i64, SegmentsError> {
+        if !self.valid_new_edge(v1, v2) {
+            return Err(SegmentsError::InvalidNewEdge { v1, v2 });
+        }
+
+        let e = self.take_edge_slot()?;
+
+        self.ev[2 * e] = v1;
+        self.ev[2 * e + 1] = v2;
+
+        self.add_e_to_ve(v1, e as i64);
+        self.add_e_to_ve(v2, e as i64);
+        self.dirty_edges.push(self.live_edge_id(e as i64));
+
+        Ok(e as i64)
+    }
+
+    #[inline(always)]
+    fn add_e_to_ve(&mut self, v: i64, e: i64) {
+        let v = v as usize;
+        if self.ve[2 * v] < 0 {
+            self.ve[2 * v] = e;
+        } else {
+            self.ve[2 * v + 1] = e;
+        }
+    }
+
+    /// link `vertices` of a new segment in order, and the last back to the
+    /// first if `closed`, returning their ids.
+    fn link(
+        &mut self,
+        vertices: &[i64],
+        closed: bool,
+    ) -> Result<Vec<VertexId>, SegmentsError> {
+        for e in vertices.windows(2) {
+            self.add_edge(e[0], e[1])?;
+        }
+        if closed {
+            self.add_edge(vertices[0], vertices[vertices.len() - 1])?;
+        }
+
+        self.s_num += 1;
+        Ok(vertices.iter().map(|&v| self.live_vertex_id(v)).collect())
+    }
+
+    fn delete_vertex(&mut self, v1: i64) {
+        if !self.has_vertex(v1) {
+            return;
+        }
+
+        self.va[v1 as usize] = VertexStatus::Deleted;
+        self.vg.bump(v1);
+        self.index.delete_vertex(v1);
+        self.free_vertices.push(v1);
+    }
+
+    fn delete_edge(&mut self, e1: i64) -> Result<(), SegmentsError> {
+        if e1 < 0 || e1 >= self.e_num as i64 {
+            return Err(SegmentsError::InvalidEdge { e1 });
+        }
+
+        if !self.has_edge(e1) {
+            return Ok(());
+        }
+
+        let i = 2 * e1 as usize;
+        let (v1, v2) = (self.ev[i], self.ev[i + 1]);
+        self.ev[i] = -1;
+        self.ev[i + 1] = -1;
+        self.eg.bump(e1);
+        self.free_edges.push(e1);
+
+        if v1 > -1 {
+            self.delete_e_from_ve(v1, e1);
+        }
+        if v2 > -1 {
+            self.delete_e_from_ve(v2, e1);
+        }
+
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn delete_e_from_ve(&mut self, v: i64, e: i64) {
+        let v = v as usize;
+        if self.ve[2 * v] == e {
+            self.ve[2 * v] = self.ve[2 * v + 1];
+            self.ve[2 * v + 1] = -1;
+        } else if self.ve[2 * v + 1] == e {
+            self.ve[2 * v + 1] = -1;
+        }
+    }
+
+    /// what slot v1 holds, [`VertexStatus::Deleted`] for slots out of range.
+    fn status(&self, v1: i64) -> VertexStatus {
+        usize::try_from(v1)
+            .ok()
+            .and_then(|v| self.va.get(v).copied())
+            .unwrap_or(VertexStatus::Deleted)
+    }
+
+    /// whether slot v1 holds a vertex. it may be passive.
+    fn has_vertex(&self, v1: i64) -> bool {
+        self.status(v1) != VertexStatus::Deleted
+    }
+
+    /// whether slot e1 holds an edge.
+    fn has_edge(&self, e1: i64) -> bool {
+        (0..self.e_num as i64).contains(&e1) && {
+            let e1 = e1 as usize;
+            self.ev[2 * e1] > -1 && self.ev[2 * e1 + 1] > -1
+        }
+    }
+
+    /// the id of the vertex in slot v1, which must hold one.
+    fn live_vertex_id(&self, v1: i64) -> VertexId {
+        VertexId::new(v1, self.vg.get(v1))
+    }
+
+    /// the id of the edge in slot e1, which must hold one.
+    fn live_edge_id(&self, e1: i64) -> EdgeId {
+        EdgeId::new(e1, self.eg.get(e1))
+    }
+
+    /// slots of all vertices that have not been deleted, including passive
+    /// ones.
+    fn vertex_slots(&self) -> impl Iterator<Item = i64> {
+        (0..self.v_num as i64).filter(|&v| self.has_vertex(v))
+    }
+
+    /// slots of all edges that have not been deleted.
+    fn edge_slots(&self) -> impl Iterator<Item = i64> {
+        (0..self.e_num as i64).filter(|&e| self.has_edge(e))
+    }
+
+    fn xy(&self, v1: i64) -> DVec2 {
+        DVec2::new(self.x[v1 as usize], self.y[v1 as usize])
+    }
+
+    fn ends(&self, e1: i64) -> [i64; 2] {
+        let e1 = e1 as usize;
+        [self.ev[2 * e1], self.ev[2 * e1 + 1]]
+    }
+
+    /// the vertex across edge e1 from vertex v1.
+    fn other_end(&self, e1: i64, v1: i64) -> i64 {
+        let [a, b] = self.ends(e1);
+        if a == v1 { b } else { a }
+    }
+
+    fn vertex_degree(&self, v1: i64) -> usize {
+        let v1 = v1 as usize;
+        (self.ve[2 * v1] > -1) as usize + (self.ve[2 * v1 + 1] > -1) as usize
+    }
+
+    /// follow the edges from v1 until reaching either an end or v1 again,
+    /// marking every vertex on the way as visited.
+    fn walk_path(&self, v1: i64, visited: &mut [bool]) -> (Vec<i64>, bool) {
+        let mut vertices = Vec::new();
+        let (mut v, mut prev_e) = (v1, -1);
+
+        let closed = loop {
+            visited[v as usize] = true;
+            vertices.push(v);
+
+            let (e1, e2) =
+                (self.ve[2 * v as usize], self.ve[2 * v as usize + 1]);
+            let e = if e1 > -1 && e1 != prev_e {
+                e1
+            } else if e2 > -1 && e2 != prev_e {
+                e2
+            } else {
+                break false;
+            };
+
+            let next = self.other_end(e, v);
+            if next == v1 {
+                break true;
+            }
+
+            (v, prev_e) = (next, e);
+        };
+
+        (vertices, closed)
+    }
+
+    /// every connected run of edges as its vertices in order, open runs
+    /// first, and whether the run is closed.
+    pub(super) fn runs(&self) -> Vec<(Vec<i64>, bool)> {
+        let mut visited = vec![false; self.v_num as usize];
+        let mut runs = Vec::new();
+
+        // open runs first, so that they are walked from an end
+        for degree in [1, 2] {
+            for v in self.vertex_slots() {
+                if !visited[v as usize] && self.vertex_degree(v) == degree {
+                    runs.push(self.walk_path(v, &mut visited));
+                }
+            }
+        }
+
+        runs
+    }
+
+    // fn get_edge_normal(&self, s1: i64, normals: &mut [f64]) {}
+}
+
+//===================================================================
+// Public Methods
+//===================================================================
+
+impl Segments {
+    /// whether the edge of `id` has not been deleted.
+    pub(super) fn edge_exists(&self, id: EdgeId) -> bool {
+        self.edge_slot(id).is_ok()
+    }
+
+    /// whether the vertex of `id` has not been deleted. it may be passive.
+    pub(super) fn vertex_exists(&self, id: VertexId) -> bool {
+        self.vertex_slot(id).is_ok()
+    }
+
+    /// whether the vertex of `id` has not been deleted and is not passive.
+    pub(super) fn vertex_active(&self, id: VertexId) -> bool {
+        self.vertex_slot(id)
+            .is_ok_and(|v| self.status(v) == VertexStatus::Active)
+    }
+
+    pub(super) fn vertex_position(
+        &self,
+        id: VertexId,
+    ) -> Result<DVec2, SegmentsError> {
+        Ok(self.xy(self.vertex_slot(id)?))
+    }
+
+    /// move the vertex of `id` to `p`. its zone in the spatial index is
+    /// only updated by [`Self::update_index`].
+    pub(super) fn set_vertex_position(
+        &mut self,
+        id: VertexId,
+        p: DVec2,
+    ) -> Result<(), SegmentsError> {
+        let v = self.vertex_slot(id)? as usize;
+        self.x[v] = p.x;
+        self.y[v] = p.y;
+        Ok(())
+    }
+
+    /// the segment the vertex of `id` belongs to.
+    pub(super) fn vertex_segment(
+        &self,
+        id: VertexId,
+    ) -> Result<i64, SegmentsError> {
+        Ok(self.vs[self.vertex_slot(id)? as usize])
+    }
+
+    /// the spawn weight of the vertex of `id`.
+    pub(super) fn vertex_weight(
+        &self,
+        id: VertexId,
+    ) -> Result<f64, SegmentsError> {
+        Ok(self.vw[self.vertex_slot(id)? as usize])
+    }
+
+    /// the edges of the vertex of `id`. the second is `None` at the ends of
+    /// open segments.
+    pub(super) fn edges_of(
+        &self,
+        id: VertexId,
+    ) -> Result<[Option<EdgeId>; 2], SegmentsError> {
+        let v = self.vertex_slot(id)? as usize;
+        Ok([self.ve[2 * v], self.ve[2 * v + 1]]
+            .map(|e| (e > -1).then(|| self.live_edge_id(e))))
+    }
+
+    /// the vertices at both ends of the edge of `id`.
+    pub(super) fn edge_ends(
+        &self,
+        id: EdgeId,
+    ) -> Result<[VertexId; 2], SegmentsError> {
+        let e1 = self.edge_slot(id)?;
+        Ok(self.ends(e1).map(|v| self.live_vertex_id(v)))
+    }
+
+    /// the vertex across the edge of `e` from the vertex of `v`, or `None`
+    /// if the edge has been deleted.
+    pub(super) fn other_vertex(
+        &self,
+        e: EdgeId,
+        v: VertexId,
+    ) -> Option<VertexId> {
+        let [a, b] = self.edge_ends(e).ok()?;
+        Some(if a == v { b } else { a })
+    }
+
+    /// ids of all vertices that have not been deleted, including passive
+    /// ones.
+    pub(super) fn vertex_ids(&self) -> impl Iterator<Item = VertexId> {
+        self.vertex_slots().map(|v| self.live_vertex_id(v))
+    }
+
+    /// ids of all vertices that have not been deleted and are not passive.
+    pub(super) fn active_vertex_ids(&self) -> impl Iterator<Item = VertexId> {
+        (0..self.v_num as i64)
+            .filter(|&v| self.status(v) == VertexStatus::Active)
+            .map(|v| self.live_vertex_id(v))
+    }
+
+    /// ids of all edges that have not been deleted.
+    pub(super) fn edge_ids(&self) -> impl Iterator<Item = EdgeId> {
+        self.edge_slots().map(|e| self.live_edge_id(e))
+    }
+
+    /// every connected run of edges as a path, open runs starting from one
+    /// of their ends.
+    pub(super) fn paths(&self) -> Vec<SegmentPath> {
+        self.paths_with(|v| self.xy(v))
+    }
+
+    /// like [`Self::paths`], with every vertex in `previous`, as returned
+    /// by [`Self::positions`], moved `t` of the way from there to where it
+    /// is now. vertices added since are where they are now.
+    pub(super) fn paths_between(
+        &self,
+        previous: &Positions,
+        t: f64,
+    ) -> Vec<SegmentPath> {
+        self.paths_with(|v| match previous.0.get(v as usize) {
+            Some(&Some((id, from))) if self.vertex_id(v) == Some(id) => {
+                from.lerp(self.xy(v), t)
+            }
+            _ => self.xy(v),
+        })
+    }
+
+    fn paths_with(&self, position: impl Fn(i64) -> DVec2) -> Vec<SegmentPath> {
+        self.runs()
+            .into_iter()
+            .map(|(vertices, closed)| SegmentPath {
+                segment: self.vs[vertices[0] as usize],
+                passive: vertices
+                    .iter()
+                    .all(|&v| self.status(v) != VertexStatus::Active),
+                points: vertices.iter().map(|&v| position(v)).collect(),
+                closed,
+            })
+            .collect()
+    }
+
+    /// the position of every vertex, to tell where it was once it moved.
+    pub(super) fn positions(&self) -> Positions {
+        Positions(
+            (0..self.v_num as i64)
+                .map(|v| Some((self.vertex_id(v)?, self.xy(v))))
+                .collect(),
+        )
+    }
+
+    /// get the coordinates of both ends of all edges
+    pub(super) fn get_edges_coordinates(&self) -> Vec<[DVec2; 2]> {
+        self.edge_slots()
+            .map(|e| self.ends(e).map(|v| self.xy(v)))
+            .collect()
+    }
+
+    /// get all coordinates of all alive vertices
+    pub(super) fn get_vertex_coordinates(&self) -> Vec<DVec2> {
+        self.vertex_slots().map(|v| self.xy(v)).collect()
+    }
+
+    pub(super) fn get_edge_length(
+        &self,
+        id: EdgeId,
+    ) -> Result<f64, SegmentsError> {
+        let [v1, v2] = self.ends(self.edge_slot(id)?);
+        Ok(self.xy(v1).distance(self.xy(v2)))
+    }
+
+    /// mean spawn weight of the vertices of the edge of `id`
+    pub(super) fn get_edge_weight(
+        &self,
+        id: EdgeId,
+    ) -> Result<f64, SegmentsError> {
+        let [v1, v2] = self.ends(self.edge_slot(id)?);
+        Ok((self.vw[v1 as usize] + self.vw[v2 as usize]) / 2.)
+    }
+
+    pub(super) fn set_vertex_weight(
+        &mut self,
+        id: VertexId,
+        weight: f64,
+    ) -> Result<(), SegmentsError> {
+        let v1 = self.vertex_slot(id)? as usize;
+        self.vw[v1] = weight;
+
+        for e in [self.ve[2 * v1], self.ve[2 * v1 + 1]] {
+            if e > -1 {
+                self.dirty_edges.push(self.live_edge_id(e));
+            }
+        }
+        Ok(())
+    }
+
+    /// edges added or reweighted since the last call, see
+    /// [`Self::dirty_edges`].
+    pub(super) fn take_dirty_edges(&mut self) -> Vec<EdgeId> {
+        std::mem::take(&mut self.dirty_edges)
+    }
+
+    /// move every vertex to the zone of the spatial index it is in now,
+    /// once vertices were moved with [`Self::set_vertex_position`] or
+    /// through `x` and `y`.
+    pub(super) fn update_index(&mut self) {
+        for v in 0..self.v_num as i64 {
+            if self.has_vertex(v) {
+                self.index.update_vertex(
+                    v,
+                    self.x[v as usize],
+                    self.y[v as usize],
+                );
+            }
+        }
+    }
+
+    /// add an open segment through `xys`, returning its vertices in order
+    /// like every `init_*` method.
+    pub(super) fn init_line_segment(
+        &mut self,
+        xys: &[DVec2],
+        lock_edges: bool,
+    ) -> Result<Vec<VertexId>, SegmentsError> {
+        check_vertex_count(xys.len(), false)?;
+        let s_num = self.s_num as i64;
+        let mut vertices = Vec::<i64>::new();
+
+        if lock_edges {
+            vertices.push({
+                let DVec2 { x, y } = xys[0];
+                self.add_passive_vertex(x, y, s_num)?
+            });
+            for &DVec2 { x, y } in &xys[1..xys.len() - 1] {
+                vertices.push(self.add_vertex(x, y, s_num)?);
+            }
+            vertices.push({
+                let DVec2 { x, y } = xys[xys.len() - 1];
+                self.add_passive_vertex(x, y, s_num)?
+            });
+        } else {
+            for &DVec2 { x, y } in xys {
+                vertices.push(self.add_vertex(x, y, s_num)?);
+            }
+        }
+
+        self.link(&vertices, false)
+    }
+
+    pub(super) fn init_passive_line_segment(
+        &mut self,
+        xys: &[DVec2],
+    ) -> Result<Vec<VertexId>, SegmentsError> {
+        check_vertex_count(xys.len(), false)?;
+        let s_num = self.s_num as i64;
+        let mut vertices = Vec::<i64>::new();
+
+        for &DVec2 { x, y } in xys {
+            vertices.push(self.add_passive_vertex(x, y, s_num)?);
+        }
+
+        self.link(&vertices, false)
+    }
+
+    /// like [`Self::init_passive_line_segment`], with an edge joining the
+    /// last vertex back to the first.
+    pub(super) fn init_passive_closed_line_segment(
+        &mut self,
+        xys: &[DVec2],
+    ) -> Result<Vec<VertexId>, SegmentsError> {
+        check_vertex_count(xys.len(), true)?;
+        let s_num = self.s_num as i64;
+        let mut vertices = Vec::<i64>::new();
+
+        for &DVec2 { x, y } in xys {
+            vertices.push(self.add_passive_vertex(x, y, s_num)?);
+        }
+
+        self.link(&vertices, true)
+    }
+
+    /// like [`Self::init_line_segment`], with an edge joining the last vertex
+    /// back to the first.
+    pub(super) fn init_closed_line_segment(
+        &mut self,
+        xys: &[DVec2],
+    ) -> Result<Vec<VertexId>, SegmentsError> {
+        check_vertex_count(xys.len(), true)?;
+        let s_num = self.s_num as i64;
+        let mut vertices = Vec::<i64>::new();
+
+        for &DVec2 { x, y } in xys {
+            vertices.push(self.add_vertex(x, y, s_num)?);
+        }
+
+        self.link(&vertices, true)
+    }
+
+    pub(super) fn init_circle_segment(
+        &mut self,
+        x: f64,
+        y: f64,
+        r: f64,
+        angles: &[f64],
+    ) -> Result<Vec<VertexId>, SegmentsError> {
+        check_vertex_count(angles.len(), true)?;
+        let s_num = self.s_num as i64;
+        let mut vertices = Vec::<i64>::new();
+
+        for &theta in angles {
+            vertices.push(self.add_vertex(
+                x + r * theta.cos(),
+                y + r * theta.sin(),
+                s_num,
+            )?);
+        }
+
+        self.link(&vertices, true)
+    }
+
+    pub(super) fn init_passive_circle_segment(
+        &mut self,
+        x: f64,
+        y: f64,
+        r: f64,
+        angles: &[f64],
+    ) -> Result<Vec<VertexId>, SegmentsError> {
+        check_vertex_count(angles.len(), true)?;
+        let s_num = self.s_num as i64;
+        let mut vertices = Vec::<i64>::new();
+
+        for &theta in angles {
+            vertices.push(self.add_passive_vertex(
+                x + r * theta.cos(),
+                y + r * theta.sin(),
+                s_num,
+            )?);
+        }
+
+        self.link(&vertices, true)
+    }
+
+    /// collapse the edge of `id` into the vertex at its second end, which is
+    /// moved to the midpoint and returned. the id of the other vertex is
+    /// stale from then on.
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`SegmentsError::EdgeTooLong`] if `max_len` is given and the
+    /// edge length is greater than it, and [`SegmentsError::TriangleLoop`]
+    /// if the edge is part of a loop of three vertices, whose other two
+    /// vertices would be joined twice.
+    pub(super) fn collapse(
+        &mut self,
+        id: EdgeId,
+        max_len: Option<f64>,
+    ) -> Result<VertexId, SegmentsError> {
+        let e1 = self.edge_slot(id)?;
+        let [v1, v2] = self.ends(e1);
+
+        for v1 in [v1, v2] {
+            if self.status(v1) != VertexStatus::Active {
+                return Err(SegmentsError::PassiveVertex { e1, v1 });
+            }
+        }
+
+        let e2 = match self.ve[2 * v1 as usize] {
+            e if e == e1 => self.ve[2 * v1 as usize + 1],
+            e => e,
+        };
+        if e2 < 0 {
+            return Err(SegmentsError::SegmentEnd { e1, v1 });
+        }
+        let v3 = self.other_end(e2, v1);
+        let [a, b] = [self.ve[2 * v3 as usize], self.ve[2 * v3 as usize + 1]];
+        if b > -1 && [a, b].map(|e3| self.other_end(e3, v3)).contains(&v2) {
+            return Err(SegmentsError::TriangleLoop { e1 });
+        }
+
+        let len = self.xy(v1).distance(self.xy(v2));
+        if let Some(max) = max_len.filter(|&max| len > max) {
+            return Err(SegmentsError::EdgeTooLong { e1, len, max });
+        }
+
+        let (v1, v2) = (v1 as usize, v2 as usize);
+        self.x[v2] = (self.x[v1] + self.x[v2]) / 2.;
+        self.y[v2] = (self.y[v1] + self.y[v2]) / 2.;
+        self.vw[v2] = (self.vw[v1] + self.vw[v2]) / 2.;
+
+        self.delete_edge(e1)?;
+        self.delete_edge(e2)?;
+
+        self.delete_vertex(v1 as i64);
+        self.add_edge(v3, v2 as i64)?;
+
+        Ok(self.live_vertex_id(v2 as i64))
+    }
+
+    /// split each of `edges` at its midpoint, returning the new vertices in
+    /// the same order, with the new vertices added to the spatial index all
+    /// at once.
+    ///
+    /// ## Errors
+    ///
+    /// All edges are checked before any are split, so on error nothing has
+    /// changed. Returns [`SegmentsError::StaleEdge`] if an id no longer
+    /// names its edge, and [`SegmentsError::CapacityExceeded`] if there is
+    /// not enough storage left for every split.
+    pub(super) fn split_edges(
+        &mut self,
+        edges: &[EdgeId],
+    ) -> Result<Vec<VertexId>, SegmentsError> {
+        for &id in edges {
+            let e1 = self.edge_slot(id)?;
+            let v1 = self.ev[2 * e1 as usize];
+            if self.vs[v1 as usize] < 0 {
+                return Err(SegmentsError::InvalidSegment { e1, v1 });
+            }
+        }
+
+        // every split frees the slot of its edge before adding two
+        let n = edges.len() as u64;
+        if n > self.vertex_room() || n > self.edge_room() {
+            return Err(SegmentsError::CapacityExceeded { n_max: self.n_max });
+        }
+
+        let mut added = Vec::with_capacity(edges.len());
+
+        for &id in edges {
+            // an edge repeated in `edges` is split already once its id is
+            // stale, even though its slot is taken again straight away
+            let Ok(e1) = self.edge_slot(id) else {
+                continue;
+            };
+
+            let (v1, v2) = (
+                self.ev[2 * e1 as usize] as usize,
+                self.ev[2 * e1 as usize + 1] as usize,
+            );
+
+            // the midpoint of two vertices in the unit square is too, so
+            // this can skip the checks in `add_vertex`
+            let v3 = self.take_vertex_slot()?;
+            self.x[v3] = (self.x[v1] + self.x[v2]) / 2.;
+            self.y[v3] = (self.y[v1] + self.y[v2]) / 2.;
+            self.va[v3] = VertexStatus::Active;
+            self.vs[v3] = self.vs[v1];
+            self.vw[v3] = (self.vw[v1] + self.vw[v2]) / 2.;
+            added.push(v3 as i64);
+
+            self.delete_edge(e1)?;
+            self.add_edge(v1 as i64, v3 as i64)?;
+            self.add_edge(v2 as i64, v3 as i64)?;
+        }
+
+        for &v in &added {
+            self.index.add_vertex(v as u64, &self.x, &self.y);
+        }
+
+        Ok(added.into_iter().map(|v| self.live_vertex_id(v)).collect())
+    }
+
+    /// collapse all edges shorter than limit, skipping those with a passive
+    /// vertex, those that end an open segment and those of a triangle.
+    pub(super) fn collapse_short_edges(
+        &mut self,
+        limit: f64,
+    ) -> Result<(), SegmentsError> {
+        // collapsing deletes edges, and adds one in the slot of one of
+        // them, so check each edge there was as it comes up
+        let edges = self.edge_ids().collect::<Vec<_>>();
+        for id in edges {
+            let Ok(e) = self.edge_slot(id) else {
+                continue;
+            };
+
+            let [v1, v2] = self.ends(e);
+            if self.status(v1) != VertexStatus::Active
+                || self.status(v2) != VertexStatus::Active
+            {
+                continue; // edge has a passive/dead vertex
+            }
+            if self.ve[2 * v1 as usize + 1] < 0 {
+                continue; // v1 is the end of an open segment
+            }
+
+            if self.xy(v1).distance(self.xy(v2)) < limit {
+                match self.collapse(id, None) {
+                    // a triangle is left as it is
+                    Ok(_) | Err(SegmentsError::TriangleLoop { .. }) => {}
+                    Err(err) => return Err(err),
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Gives an estimate of the edge of `id`, using the cross product of it
+    /// and both of its connected edges. This is not really the curvature in
+    /// the mathematical sense.
+    pub(super) fn get_edge_curvature(
+        &self,
+        id: EdgeId,
+    ) -> Result<f64, SegmentsError> {
+        let e1 = self.edge_slot(id)?;
+        let [v1, v2] = self.ends(e1);
+        let (v1, v2) = (v1 as usize, v2 as usize);
+
+        let (e2, e3) = if self.ve[2 * v1] == self.ve[2 * v2] {
+            (self.ve[2 * v1 + 1], self.ve[2 * v2 + 1])
+        } else if self.ve[2 * v1] == self.ve[2 * v2 + 1] {
+            (self.ve[2 * v1 + 1], self.ve[2 * v2])
+        } else if self.ve[2 * v1 + 1] == self.ve[2 * v2] {
+            (self.ve[2 * v1], self.ve[2 * v2 + 1])
+        } else if self.ve[2 * v1 + 1] == self.ve[2 * v2 + 1] {
+            (self.ve[2 * v1], self.ve[2 * v2])
+        } else {
+            return Err(SegmentsError::EdgesNotConnected { e1 });
+        };
+
+        let b = self.xy(v1 as i64) - self.xy(v2 as i64);
+        let mut t: f64 = 0.0;
+
+        // half the area of the parallelogram spanned by the edge and each of
+        // its neighbors
+        for e2 in [e2, e3] {
+            if e2 > -1 {
+                let [v1, v2] = self.ends(e2);
+                let a = self.xy(v1) - self.xy(v2);
+                t += a.perp_dot(b).abs() / 2.;
+            }
+        }
+
+        if t <= 0. {
+            return Err(SegmentsError::NoCurvature { e1 });
+        }
+
+        Ok(t)
+    }
+
+    /// The vertices across both edges of the vertex of `id`, or `None` at
+    /// the ends of open segments and if the vertex has been deleted.
+    pub(super) fn linked_vertices(
+        &self,
+        id: VertexId,
+    ) -> Option<[VertexId; 2]> {
+        let v1 = self.vertex_slot(id).ok()?;
+        let v = v1 as usize;
+        let (e1, e2) = (self.ve[2 * v], self.ve[2 * v + 1]);
+        if e1 < 0 || e2 < 0 {
+            return None;
+        }
+
+        Some([e1, e2].map(|e| self.live_vertex_id(self.other_end(e, v1))))
+    }
+
+    /// How sharply the line turns at the vertex of `id`, from 0 where it
+    /// runs straight to 2 where it folds back on itself, and the direction
+    /// away from both of its linked vertices, to the outside of the turn.
+    /// `None` at the ends of open segments, where the line runs straight,
+    /// and if the vertex has been deleted.
+    pub(super) fn get_vertex_bend(
+        &self,
+        id: VertexId,
+    ) -> Option<(f64, DVec2)> {
+        let p = self.xy(self.vertex_slot(id).ok()?);
+        let [a, b] = self
+            .linked_vertices(id)?
+            .map(|v| (p - self.xy(v.slot())).normalize_or_zero());
+        let outside = (a + b).try_normalize()?;
+        Some((1. + a.dot(b), outside))
+    }
+
+    pub(super) fn get_active_vertex_count(&self) -> usize {
+        self.va
+            .iter()
+            .take(self.v_num as usize)
+            .filter(|&&a| a == VertexStatus::Active)
+            .count()
+    }
+
+    /// delete the vertex of `id` and its edges, splitting the line it is
+    /// on. linked active vertices that are left without edges are deleted
+    /// too.
+    pub(super) fn remove(
+        &mut self,
+        id: VertexId,
+    ) -> Result<(), SegmentsError> {
+        let v1 = self.vertex_slot(id)?;
+
+        for _ in 0..2 {
+            let e = self.ve[2 * v1 as usize];
+            if e < 0 {
+                break;
+            }
+
+            let other = self.other_end(e, v1);
+            self.delete_edge(e)?;
+            if self.status(other) == VertexStatus::Active
+                && self.vertex_degree(other) == 0
+            {
+                self.delete_vertex(other);
+            }
+        }
+
+        self.delete_vertex(v1);
+        Ok(())
+    }
+
+    /// delete the edge of `id`, splitting the line it is on. its vertices
+    /// are kept.
+    pub(super) fn cut(&mut self, id: EdgeId) -> Result<(), SegmentsError> {
+        let e1 = self.edge_slot(id)?;
+        self.delete_edge(e1)
+    }
+
+    /// check that all active vertices are within limit of unit square
+    /// boundary. passive vertices never move, so they are not checked.
+    pub(super) fn safe_vertex_positions(&self, limit: f64) -> bool {
+        let range = limit..=1. - limit;
+
+        self.active_vertex_ids().all(|id| {
+            let DVec2 { x, y } = self.xy(id.slot());
+            range.contains(&x) && range.contains(&y)
+        })
+    }
+
+    /// the number of line segments, which is also the id of the next one.
+    pub(super) fn s_num(&self) -> u64 {
+        self.s_num
+    }
+
+    pub(super) fn v_num(&self) -> u64 {
+        self.v_num
+    }
+
+    /// the number of vertices ever added, including those since deleted.
+    pub(super) fn v_added(&self) -> u64 {
+        self.v_added
+    }
+}
+
+//===================================================================
+// Ids
+//===================================================================
+
+impl Segments {
+    /// the id of the vertex in slot v1, or `None` if there is no such
+    /// vertex.
+    pub(super) fn vertex_id(&self, v1: i64) -> Option<VertexId> {
+        self.has_vertex(v1).then(|| self.live_vertex_id(v1))
+    }
+
+    /// the id of the edge in slot e1, or `None` if there is no such edge.
+    pub(super) fn edge_id(&self, e1: i64) -> Option<EdgeId> {
+        self.has_edge(e1).then(|| self.live_edge_id(e1))
+    }
+
+    /// the slot of the vertex of `id`.
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`SegmentsError::StaleVertex`] if the vertex has been deleted,
+    /// even if the slot holds another vertex since.
+    pub(super) fn vertex_slot(
+        &self,
+        id: VertexId,
+    ) -> Result<i64, SegmentsError> {
+        let v1 = id.slot();
+        if self.has_vertex(v1) && self.vg.get(v1) == id.generation() {
+            Ok(v1)
+        } else {
+            Err(SegmentsError::StaleVertex { v1 })
+        }
+    }
+
+    /// the slot of the edge of `id`.
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`SegmentsError::StaleEdge`] if the edge has been deleted,
+    /// even if the slot holds another edge since.
+    pub(super) fn edge_slot(&self, id: EdgeId) -> Result<i64, SegmentsError> {
+        let e1 = id.slot();
+        if self.has_edge(e1) && self.eg.get(e1) == id.generation() {
+            Ok(e1)
+        } else {
+            Err(SegmentsError::StaleEdge { e1 })
+        }
+    }
+}
+
+//===================================================================
+// Compaction
+//===================================================================
+
+impl Segments {
+    /// move every vertex and edge into the lowest slots, keeping their
+    /// order, so that the slots of deleted ones are no longer walked over
+    /// and the storage left is all after `v_num` and `e_num` again. every
+    /// id is stale, see the returned [`Remap`] for the new ones.
+    pub(super) fn compact(&mut self) -> Remap {
+        let (v_num, e_num) = (self.v_num as usize, self.e_num as usize);
+
+        let mut vmap = vec![-1; v_num];
+        for (new, v) in self.vertex_slots().enumerate() {
+            vmap[v as usize] = new as i64;
+        }
+        let mut emap = vec![-1; e_num];
+        for (new, e) in self.edge_slots().enumerate() {
+            emap[e as usize] = new as i64;
+        }
+        let old_vertices = (0..v_num as i64)
+            .map(|v| self.vertex_id(v))
+            .collect::<Vec<_>>();
+        let old_edges = (0..e_num as i64)
+            .map(|e| self.edge_id(e))
+            .collect::<Vec<_>>();
+
+        // every slot moves down if at all, so is read before it is written
+        let remap_edge = |e: i64| if e < 0 { -1 } else { emap[e as usize] };
+        for (v, &new) in vmap.iter().enumerate() {
+            if new < 0 {
+                continue;
+            }
+            self.index.delete_vertex(v as i64);
+            let new = new as usize;
+            self.x[new] = self.x[v];
+            self.y[new] = self.y[v];
+            self.va[new] = self.va[v];
+            self.vs[new] = self.vs[v];
+            self.vw[new] = self.vw[v];
+            self.ve[2 * new] = remap_edge(self.ve[2 * v]);
+            self.ve[2 * new + 1] = remap_edge(self.ve[2 * v + 1]);
+        }
+        for (e, &new) in emap.iter().enumerate() {
+            if new < 0 {
+                continue;
+            }
+            let new = new as usize;
+            self.ev[2 * new] = vmap[self.ev[2 * e] as usize];
+            self.ev[2 * new + 1] = vmap[self.ev[2 * e + 1] as usize];
+        }
+
+        let n_vertices = vmap.iter().filter(|&&v| v > -1).count();
+        let n_edges = emap.iter().filter(|&&e| e > -1).count();
+        self.va[n_vertices..v_num].fill(VertexStatus::Deleted);
+        self.vs[n_vertices..v_num].fill(-1);
+        self.vw[n_vertices..v_num].fill(1.);
+        self.ve[2 * n_vertices..2 * v_num].fill(-1);
+        self.ev[2 * n_edges..2 * e_num].fill(-1);
+        self.v_num = n_vertices as u64;
+        self.e_num = n_edges as u64;
+        self.free_vertices.clear();
+        self.free_edges.clear();
+
+        for v in 0..self.v_num {
+            self.index.add_vertex(v, &self.x, &self.y);
+        }
+
+        // the slots now hold other vertices and edges than before
+        self.vg.bump_all();
+        self.eg.bump_all();
+        let remap = Remap {
+            vertices: old_vertices
+                .into_iter()
+                .zip(vmap)
+                .map(|(old, new)| Some((old?, self.vertex_id(new)?)))
+                .collect(),
+            edges: old_edges
+                .into_iter()
+                .zip(emap)
+                .map(|(old, new)| Some((old?, self.edge_id(new)?)))
+                .collect(),
+        };
+
+        self.dirty_edges = std::mem::take(&mut self.dirty_edges)
+            .into_iter()
+            .filter_map(|id| remap.edge(id))
+            .collect();
+        remap
+    }
+}
+
+//===================================================================
+// Snapshots
+//===================================================================
+
+impl Segments {
+    /// capture the vertices, edges, their generations and the spatial
+    /// index.
+    pub(super) fn snapshot(&self) -> SegmentsSnapshot {
+        let (v, e) = (self.v_num as usize, self.e_num as usize);
+        SegmentsSnapshot {
+            v_num: self.v_num,
+            v_added: self.v_added,
+            e_num: self.e_num,
+            s_num: self.s_num,
+            x: self.x[..v].to_vec(),
+            y: self.y[..v].to_vec(),
+            va: self.va[..v].to_vec(),
+            vs: self.vs[..v].to_vec(),
+            vw: self.vw[..v].to_vec(),
+            ev: self.ev[..2 * e].to_vec(),
+            ve: self.ve[..2 * v].to_vec(),
+            vg: self.vg.clone(),
+            eg: self.eg.clone(),
+            free_vertices: self.free_vertices.clone(),
+            free_edges: self.free_edges.clone(),
+            index: self.index.clone(),
+            dirty_edges: self.dirty_edges.clone(),
+        }
+    }
+
+    /// put back the state captured by `snapshot`, which may have been taken
+    /// of other segments with as much storage. the generations are put back
+    /// too, so the ids from before the snapshot are valid again and those
+    /// handed out since are stale, or name what the slot held back then.
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`SegmentsError::CapacityExceeded`] if the snapshot has more
+    /// vertices or edges than there is storage for.
+    pub(super) fn restore(
+        &mut self,
+        snapshot: &SegmentsSnapshot,
+    ) -> Result<(), SegmentsError> {
+        if snapshot.v_num > self.n_max || snapshot.e_num > self.n_max {
+            return Err(SegmentsError::CapacityExceeded { n_max: self.n_max });
+        }
+        self.reserve_vertices(snapshot.v_num);
+        self.reserve_edges(snapshot.e_num);
+
+        // clear what was added since, so that it is free to add again
+        let (v, e) = (self.v_num as usize, self.e_num as usize);
+        let (v_new, e_new) =
+            (snapshot.v_num as usize, snapshot.e_num as usize);
+        if v > v_new {
+            self.va[v_new..v].fill(VertexStatus::Deleted);
+            self.vs[v_new..v].fill(-1);
+            self.vw[v_new..v].fill(1.);
+            self.ve[2 * v_new..2 * v].fill(-1);
+        }
+        if e > e_new {
+            self.ev[2 * e_new..2 * e].fill(-1);
+        }
+
+        self.x[..v_new].copy_from_slice(&snapshot.x);
+        self.y[..v_new].copy_from_slice(&snapshot.y);
+        self.va[..v_new].copy_from_slice(&snapshot.va);
+        self.vs[..v_new].copy_from_slice(&snapshot.vs);
+        self.vw[..v_new].copy_from_slice(&snapshot.vw);
+        self.ev[..2 * e_new].copy_from_slice(&snapshot.ev);
+        self.ve[..2 * v_new].copy_from_slice(&snapshot.ve);
+
+        // the storage may have grown since
+        self.vg.clone_from(&snapshot.vg);
+        self.vg.resize(self.x.len());
+        self.eg.clone_from(&snapshot.eg);
+        self.eg.resize(self.ev.len() / 2);
+
+        self.v_num = snapshot.v_num;
+        self.v_added = snapshot.v_added;
+        self.e_num = snapshot.e_num;
+        self.s_num = snapshot.s_num;
+        self.free_vertices.clone_from(&snapshot.free_vertices);
+        self.free_edges.clone_from(&snapshot.free_edges);
+        self.index.clone_from(&snapshot.index);
+        self.dirty_edges.clone_from(&snapshot.dirty_edges);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use proptest::{collection::vec, prelude::*};
+
+    use super::*;
+
+    /// segments in the unit square with room for `n_max` vertices.
+    fn empty(n_max: u64) -> Segments {
+        Segments::new(n_max, 0.1, IndexKind::ZoneMap, DVec2::ZERO, 1.).unwrap()
+    }
+
+    /// a loop of 32 vertices, with room for 1000.
+    pub(crate) fn circle() -> Segments {
+        let angles = (0..32)
+            .map(|i| i as f64 * std::f64::consts::TAU / 32.)
+            .collect::<Vec<_>>();
+        let mut segments = empty(1000);
+        segments
+            .init_circle_segment(0.5, 0.5, 0.1, &angles)
+            .unwrap();
+        segments
+    }
+
+    /// split every edge at once.
+    fn split_all(segments: &mut Segments) {
+        let edges = segments.edge_ids().collect::<Vec<_>>();
+        segments.split_edges(&edges).unwrap();
+    }
+
+    #[test]
+    fn ids_of_split_edges_go_stale() {
+        let mut segments = circle();
+        let e = segments.edge_id(0).unwrap();
+        let [a, b] = segments.edge_ends(e).unwrap();
+
+        let mid = segments.split_edges(&[e]).unwrap()[0];
+        assert_eq!(
+            segments.edge_slot(e),
+            Err(SegmentsError::StaleEdge { e1: e.slot() })
+        );
+        assert_eq!(
+            segments.split_edges(&[e]),
+            Err(SegmentsError::StaleEdge { e1: e.slot() })
+        );
+        let [pa, pb] = [a, b].map(|v| segments.vertex_position(v).unwrap());
+        let p = segments.vertex_position(mid).unwrap();
+        assert!(p.abs_diff_eq(pa.midpoint(pb), 1e-12));
+
+        // the new vertex links both ends
+        let mut linked = segments.linked_vertices(mid).unwrap();
+        linked.sort_by_key(|v| v.slot());
+        let mut expected = [a, b];
+        expected.sort_by_key(|v| v.slot());
+        assert_eq!(linked, expected);
+    }
+
+    #[test]
+    fn ids_of_collapsed_and_removed_vertices_go_stale() {
+        let mut segments = circle();
+        let e = segments.edge_id(3).unwrap();
+        let [v1, v2] = segments.edge_ends(e).unwrap();
+
+        assert_eq!(segments.collapse(e, None), Ok(v2));
+        assert_eq!(
+            segments.vertex_slot(v1),
+            Err(SegmentsError::StaleVertex { v1: v1.slot() })
+        );
+        assert_eq!(segments.vertex_slot(v2), Ok(v2.slot()));
+        assert_eq!(segments.vertex_ids().count(), 31);
+        assert_eq!(segments.edge_ids().count(), 31);
+
+        // only the id of the removed vertex goes stale
+        let ids = segments.vertex_ids().collect::<Vec<_>>();
+        segments.remove(v2).unwrap();
+        let stale = ids
+            .into_iter()
+            .filter(|&id| !segments.vertex_exists(id))
+            .collect::<Vec<_>>();
+        assert_eq!(stale, [v2]);
+    }
+
+    #[test]
+    fn edges_of_a_triangle_are_not_collapsed() {
+        let mut segments = empty(16);
+        let xys = [(0.4, 0.4), (0.6, 0.4), (0.5, 0.6)].map(DVec2::from);
+        segments.init_closed_line_segment(&xys).unwrap();
+
+        for e in segments.edge_ids().collect::<Vec<_>>() {
+            assert_eq!(
+                segments.collapse(e, None),
+                Err(SegmentsError::TriangleLoop { e1: e.slot() })
+            );
+        }
+        assert_consistent(&segments);
+        assert_eq!(segments.vertex_ids().count(), 3);
+        assert_eq!(segments.edge_ids().count(), 3);
+    }
+
+    #[test]
+    fn only_vertices_and_edges_there_are_have_ids() {
+        let mut segments = circle();
+        let v = segments.vertex_id(0).unwrap();
+        let [e1, e2] = segments.edges_of(v).unwrap().map(Option::unwrap);
+        segments.remove(v).unwrap();
+        assert_eq!(segments.vertex_id(0), None);
+        assert_eq!(segments.edge_id(e1.slot()), None);
+        assert_eq!(segments.edge_id(e2.slot()), None);
+        assert!(segments.vertex_id(1).is_some());
+
+        // and slots out of range hold nothing, rather than panicking
+        assert_eq!(segments.vertex_id(-1), None);
+        assert_eq!(segments.vertex_id(32), None);
+        assert_eq!(segments.vertex_id(i64::MAX), None);
+        assert_eq!(segments.edge_id(-1), None);
+        assert_eq!(segments.edge_id(1000), None);
+        let far = VertexId::new(5000, 0);
+        assert_eq!(
+            segments.vertex_position(far),
+            Err(SegmentsError::StaleVertex { v1: 5000 })
+        );
+        assert!(!segments.vertex_active(far));
+        assert_eq!(segments.linked_vertices(far), None);
+        assert_eq!(segments.get_vertex_bend(far), None);
+        let far = EdgeId::new(5000, 0);
+        assert_eq!(
+            segments.get_edge_length(far),
+            Err(SegmentsError::StaleEdge { e1: 5000 })
+        );
+        assert_eq!(segments.other_vertex(far, v), None);
+    }
+
+    #[test]
+    fn vertices_know_their_edges() {
+        let mut segments = empty(16);
+        let [v1, v2] = segments
+            .init_line_segment(
+                &[DVec2::new(0.2, 0.2), DVec2::new(0.3, 0.2)],
+                false,
+            )
+            .unwrap()[..]
+        else {
+            unreachable!()
+        };
+
+        // the ends of an open line have one edge
+        let [e, none] = segments.edges_of(v1).unwrap();
+        let e = e.unwrap();
+        assert_eq!(none, None);
+        assert_eq!(segments.edge_ends(e), Ok([v1, v2]));
+        assert_eq!(segments.other_vertex(e, v1), Some(v2));
+        assert_eq!(segments.other_vertex(e, v2), Some(v1));
+        assert_eq!(segments.linked_vertices(v1), None);
+    }
+
+    #[test]
+    fn deleted_slots_are_added_to_again() {
+        let angles = (0..8)
+            .map(|i| i as f64 * std::f64::consts::TAU / 8.)
+            .collect::<Vec<_>>();
+        let mut segments = empty(8);
+        segments
+            .init_circle_segment(0.5, 0.5, 0.1, &angles)
+            .unwrap();
+        let e = segments.edge_id(0).unwrap();
+        assert_eq!(
+            segments.split_edges(&[e]),
+            Err(SegmentsError::CapacityExceeded { n_max: 8 })
+        );
+
+        // full, but for the slots freed by the collapses, the last first
+        let e4 = segments.edge_id(4).unwrap();
+        let [v1, _] = segments.edge_ends(e4).unwrap();
+        segments.collapse(e, None).unwrap();
+        segments.collapse(e4, None).unwrap();
+        let e = segments.edge_ids().next().unwrap();
+        let v = segments.split_edges(&[e]).unwrap()[0];
+        assert_eq!(v.slot(), v1.slot());
+        let e = segments.edge_ids().next().unwrap();
+        segments.split_edges(&[e]).unwrap();
+        assert_eq!((segments.v_num(), segments.e_num), (8, 8));
+        assert_eq!(segments.vertex_ids().count(), 8);
+        assert_eq!(segments.v_added(), 10);
+        assert_eq!(segments.paths()[0].points.len(), 8);
+        assert!(segments.paths()[0].closed);
+        let edges = segments.edge_ids().collect::<Vec<_>>();
+        assert!(segments.split_edges(&edges).is_err());
+    }
+
+    #[test]
+    fn edges_repeated_in_a_split_are_split_once() {
+        let mut segments = circle();
+        let id = segments.edge_ids().next().unwrap();
+        let [v1, v2] = segments.edge_ends(id).unwrap();
+        segments
+            .collapse(segments.edge_id(5).unwrap(), None)
+            .unwrap();
+
+        segments.split_edges(&[id, id, id]).unwrap();
+        assert_eq!(segments.vertex_ids().count(), 32);
+        assert_eq!(segments.edge_ids().count(), 32);
+        let [p1, p2] = [v1, v2].map(|v| segments.vertex_position(v).unwrap());
+        let mid = p1.midpoint(p2);
+        let at_mid = segments
+            .get_vertex_coordinates()
+            .into_iter()
+            .filter(|p| p.abs_diff_eq(mid, 1e-12))
+            .count();
+        assert_eq!(at_mid, 1);
+    }
+
+    #[test]
+    fn edges_collapse_within_their_bounds() {
+        let mut segments = circle();
+        let e = segments.edge_id(1).unwrap();
+        let len = segments.get_edge_length(e).unwrap();
+        assert!(matches!(
+            segments.collapse(e, Some(len / 2.)),
+            Err(SegmentsError::EdgeTooLong { .. })
+        ));
+        segments.collapse(e, Some(2. * len)).unwrap();
+        assert!(!segments.edge_exists(e));
+
+        // nor past the end of an open line, or into a passive vertex
+        let mut segments = empty(16);
+        let xys = [0.2, 0.3, 0.4, 0.5].map(|x| DVec2::new(x, 0.5));
+        let vertices = segments.init_line_segment(&xys, true).unwrap();
+        let [first, _] = segments.edges_of(vertices[0]).unwrap();
+        assert!(matches!(
+            segments.collapse(first.unwrap(), None),
+            Err(SegmentsError::PassiveVertex { .. })
+        ));
+        let vertices = segments.init_line_segment(&xys, false).unwrap();
+        let [first, _] = segments.edges_of(vertices[0]).unwrap();
+        assert!(matches!(
+            segments.collapse(first.unwrap(), None),
+            Err(SegmentsError::SegmentEnd { .. })
+        ));
+    }
+
+    #[test]
+    fn short_edges_collapse_into_a_valid_loop() {
+        let mut segments = circle();
+        split_all(&mut segments);
+        let e = segments.edge_ids().next().unwrap();
+        let len = segments.get_edge_length(e).unwrap();
+        segments.collapse_short_edges(1.5 * len).unwrap();
+
+        assert_consistent(&segments);
+        let runs = segments.runs();
+        assert_eq!(runs.len(), 1);
+        let (run, closed) = &runs[0];
+        assert!(closed);
+        let n = segments.vertex_ids().count();
+        assert!((32..64).contains(&n), "{n}");
+        assert_eq!(run.len(), n);
+        assert_eq!(segments.edge_ids().count(), n);
+
+        // a triangle is left alone rather than joining two vertices twice
+        segments.collapse_short_edges(1.).unwrap();
+        assert_consistent(&segments);
+        assert_eq!(segments.vertex_ids().count(), 3);
+        assert_eq!(segments.edge_ids().count(), 3);
+    }
+
+    #[test]
+    fn short_edges_of_open_lines_keep_their_ends() {
+        let mut segments = empty(64);
+        let xys = (0..8)
+            .map(|i| DVec2::new(0.2 + i as f64 * 0.01, 0.5))
+            .collect::<Vec<_>>();
+        segments.init_line_segment(&xys, true).unwrap();
+        segments.collapse_short_edges(1.).unwrap();
+
+        assert_consistent(&segments);
+        let points = &segments.paths()[0].points;
+        assert_eq!(points.len(), 3);
+        assert_eq!([points[0], points[2]], [xys[0], xys[7]]);
+    }
+
+    #[test]
+    fn restored_segments_match_the_snapshot() {
+        let mut segments = circle();
+        // a freed slot, and generations past the first
+        segments.remove(segments.vertex_id(5).unwrap()).unwrap();
+        split_all(&mut segments);
+        let snapshot = segments.snapshot();
+        let vertices = segments
+            .vertex_ids()
+            .map(|v| (v, segments.vertex_position(v).unwrap()))
+            .collect::<Vec<_>>();
+        let edges = segments.edge_ids().collect::<Vec<_>>();
+        let paths = segments.paths();
+
+        split_all(&mut segments);
+        let e = segments.edge_ids().nth(3).unwrap();
+        segments.collapse(e, None).unwrap();
+        segments
+            .init_line_segment(
+                &[DVec2::new(0.2, 0.2), DVec2::new(0.3, 0.2)],
+                false,
+            )
+            .unwrap();
+        let added = segments.vertex_ids().collect::<Vec<_>>();
+        assert_ne!(segments.paths(), paths);
+
+        // the same vertices and edges, under the same ids
+        segments.restore(&snapshot).unwrap();
+        assert_eq!(segments.paths(), paths);
+        let restored = segments
+            .vertex_ids()
+            .map(|v| (v, segments.vertex_position(v).unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(restored, vertices);
+        assert_eq!(segments.edge_ids().collect::<Vec<_>>(), edges);
+        assert_eq!(segments.s_num(), 1);
+        assert!(
+            added
+                .iter()
+                .filter(|v| !vertices.iter().any(|(u, _)| u == *v))
+                .all(|&v| !segments.vertex_exists(v))
+        );
+
+        // and growing again hands out the same ids as the first time
+        split_all(&mut segments);
+        let e = segments.edge_ids().nth(3).unwrap();
+        segments.collapse(e, None).unwrap();
+        segments
+            .init_line_segment(
+                &[DVec2::new(0.2, 0.2), DVec2::new(0.3, 0.2)],
+                false,
+            )
+            .unwrap();
+        assert_eq!(segments.vertex_ids().collect::<Vec<_>>(), added);
+    }
+
+    #[test]
+    fn snapshots_need_enough_storage() {
+        let segments = circle();
+        let mut small = empty(16);
+        assert_eq!(
+            small.restore(&segments.snapshot()),
+            Err(SegmentsError::CapacityExceeded { n_max: 16 })
+        );
+    }
+
+    #[test]
+    fn compacted_segments_keep_their_paths() {
+        let mut segments = circle();
+        split_all(&mut segments);
+        for e in [3, 10, 40, 41] {
+            if let Some(e) = segments.edge_id(e) {
+                segments.collapse(e, None).unwrap();
+            }
+        }
+        let kept = segments.vertex_id(20).unwrap();
+        let position = segments.vertex_position(kept).unwrap();
+        let deleted = segments.vertex_id(7).unwrap();
+        segments.remove(deleted).unwrap();
+        let edge = segments.edge_ids().last().unwrap();
+        let ends = segments.edge_ends(edge).unwrap();
+        segments.take_dirty_edges();
+        let dirty = segments.edge_id(0).unwrap();
+        segments
+            .set_vertex_weight(segments.edge_ends(dirty).unwrap()[0], 2.)
+            .unwrap();
+
+        let paths = segments.paths();
+        let n = segments.vertex_ids().count();
+        let remap = segments.compact();
+        assert_eq!(segments.paths(), paths);
+        assert_eq!(segments.v_num(), n as u64);
+        assert_eq!(segments.e_num, segments.edge_ids().count() as u64);
+
+        // old ids are stale, the remapped ones are where they were
+        assert!(!segments.vertex_exists(kept));
+        let kept = remap.vertex(kept).unwrap();
+        assert_eq!(segments.vertex_position(kept), Ok(position));
+        assert_eq!(remap.vertex(deleted), None);
+        let edge = remap.edge(edge).unwrap();
+        assert_eq!(
+            segments.edge_ends(edge),
+            Ok(ends.map(|v| remap.vertex(v).unwrap()))
+        );
+        assert!(
+            segments
+                .take_dirty_edges()
+                .contains(&remap.edge(dirty).unwrap())
+        );
+
+        // and there is room after the compacted slots again
+        let n_edges = segments.edge_ids().count();
+        split_all(&mut segments);
+        assert_eq!(segments.v_num(), (n + n_edges) as u64);
+    }
+
+    #[test]
+    fn storage_grows_up_to_n_max() {
+        let n_max = 5 * INITIAL_CAPACITY;
+        let mut segments = empty(n_max);
+        assert_eq!(segments.x.len() as u64, INITIAL_CAPACITY);
+
+        let xys = (0..3 * INITIAL_CAPACITY)
+            .map(|i| DVec2::new(0.1 + i as f64 * 1e-4, 0.5))
+            .collect::<Vec<_>>();
+        segments.init_line_segment(&xys, false).unwrap();
+        assert_eq!(segments.x.len() as u64, 4 * INITIAL_CAPACITY);
+        assert_eq!(segments.paths()[0].points, xys);
+
+        // a snapshot of more than there is storage for grows it
+        let mut restored = empty(n_max);
+        restored.restore(&segments.snapshot()).unwrap();
+        assert_eq!(restored.paths(), segments.paths());
+
+        // the rest up to n_max, and then no more
+        let room = n_max - segments.v_num();
+        let xys = (0..room)
+            .map(|i| DVec2::new(0.1 + i as f64 * 1e-4, 0.6))
+            .collect::<Vec<_>>();
+        segments.init_line_segment(&xys, false).unwrap();
+        assert_eq!(segments.x.len() as u64, n_max);
+        assert_eq!(
+            segments.init_line_segment(&xys[..2], false),
+            Err(SegmentsError::CapacityExceeded { n_max })
+        );
+
+        // edges past the end are stale rather than out of bounds
+        let e1 = 2 * n_max as i64;
+        let id = EdgeId::new(e1, 0);
+        assert!(!segments.edge_exists(id));
+        assert_eq!(
+            segments.split_edges(&[id]),
+            Err(SegmentsError::StaleEdge { e1 })
+        );
+    }
+
+    #[test]
+    fn every_pair_of_neighboring_seed_vertices_is_joined() {
+        // joining the vertices two at a time, rather than each with the
+        // next, left lines of 5 vertices as 2 edges and a vertex apart
+        let xys = (0..5)
+            .map(|i| {
+                DVec2::new(0.2 + i as f64 * 0.1, 0.5 + (i % 2) as f64 * 0.1)
+            })
+            .collect::<Vec<_>>();
+        let seeds: [fn(&mut Segments, &[DVec2]) -> _; 4] = [
+            |s, xys| s.init_line_segment(xys, false),
+            |s, xys| s.init_passive_line_segment(xys),
+            |s, xys| s.init_closed_line_segment(xys),
+            |s, xys| s.init_passive_closed_line_segment(xys),
+        ];
+        for (i, seed) in seeds.into_iter().enumerate() {
+            let closed = i >= 2;
+            let mut segments = empty(16);
+            seed(&mut segments, &xys).unwrap();
+
+            assert_consistent(&segments);
+            assert_eq!(segments.edge_ids().count(), 4 + closed as usize);
+            let runs = segments.runs();
+            assert_eq!(runs.len(), 1);
+            assert_eq!(runs[0].1, closed);
+            let mut run = runs[0].0.clone();
+            run.sort();
+            assert_eq!(run, [0, 1, 2, 3, 4]);
+        }
+    }
+
+    #[test]
+    fn segments_of_too_few_vertices_are_errors() {
+        let mut segments = empty(64);
+        let xys = [0.2, 0.4].map(|x| DVec2::new(x, 0.5));
+
+        for n in 0..=2 {
+            let xys = &xys[..n];
+            let angles = vec![0.; n];
+            let open =
+                (n < 2).then_some(SegmentsError::TooFewVertices { n, min: 2 });
+            let closed = Err(SegmentsError::TooFewVertices { n, min: 3 });
+            for lock_edges in [false, true] {
+                assert_eq!(
+                    segments.init_line_segment(xys, lock_edges).err(),
+                    open
+                );
+            }
+            assert_eq!(segments.init_passive_line_segment(xys).err(), open);
+            assert_eq!(segments.init_closed_line_segment(xys), closed);
+            assert_eq!(segments.init_passive_closed_line_segment(xys), closed);
+            assert_eq!(
+                segments.init_circle_segment(0.5, 0.5, 0.1, &angles),
+                closed
+            );
+            assert_eq!(
+                segments.init_passive_circle_segment(0.5, 0.5, 0.1, &angles),
+                closed
+            );
+        }
+
+        // only the lines of two vertices were added, one edge each
+        assert_eq!(segments.s_num(), 3);
+        assert_eq!(segments.v_num(), 6);
+        assert_eq!(segments.edge_ids().count(), 3);
+    }
+
+    /// a change to the segments, to the live edge or vertex at an index
+    /// modulo the number of them.
+    #[derive(Clone, Copy, Debug)]
+    enum Op {
+        Split(usize),
+        Collapse(usize),
+        Remove(usize),
+        Cut(usize),
+    }
+
+    /// changes that keep a closed loop closed, and with `deletes`, those
+    /// that break it up too.
+    fn op(deletes: bool) -> impl Strategy<Value = Op> {
+        let keeping = prop_oneof![
+            any::<usize>().prop_map(Op::Split),
+            any::<usize>().prop_map(Op::Collapse),
+        ];
+        if deletes {
+            prop_oneof![
+                keeping,
+                any::<usize>().prop_map(Op::Remove),
+                any::<usize>().prop_map(Op::Cut),
+            ]
+            .boxed()
+        } else {
+            keeping.boxed()
+        }
+    }
+
+    fn apply(segments: &mut Segments, op: Op) -> Result<(), SegmentsError> {
+        let edges = segments.edge_ids().collect::<Vec<_>>();
+        let vertices = segments.vertex_ids().collect::<Vec<_>>();
+        let edge = |i: usize| edges[i % edges.len()];
+        match op {
+            _ if edges.is_empty() => Ok(()),
+            Op::Split(i) => segments.split_edges(&[edge(i)]).map(drop),
+            Op::Collapse(i) => segments.collapse(edge(i), None).map(drop),
+            Op::Remove(i) => segments.remove(vertices[i % vertices.len()]),
+            Op::Cut(i) => segments.cut(edge(i)),
+        }
+    }
+
+    /// check that the edges and vertices of `segments` agree with each other
+    /// and with its counts and spatial index.
+    fn assert_consistent(segments: &Segments) {
+        let (v_num, e_num) = (segments.v_num(), segments.e_num);
+        let edges = segments.edge_ids().collect::<Vec<_>>();
+        for &e in &edges {
+            let [v1, v2] = segments.edge_ends(e).unwrap();
+            assert_ne!(v1, v2, "{e:?} is a loop");
+            for v in [v1, v2] {
+                assert!(segments.vertex_exists(v), "{e:?} has dead {v:?}");
+                assert!(segments.edges_of(v).unwrap().contains(&Some(e)));
+            }
+        }
+        assert_eq!(edges.len() + segments.free_edges.len(), e_num as usize);
+
+        let vertices = segments.vertex_ids().collect::<Vec<_>>();
+        let mut found = Vec::new();
+        for &v in &vertices {
+            let [e1, e2] = segments.edges_of(v).unwrap();
+            assert!(e1.is_some() || e2.is_none(), "{v:?} edges not packed");
+            for e in [e1, e2].into_iter().flatten() {
+                assert!(segments.edge_exists(e), "{v:?} has dead {e:?}");
+                assert!(segments.edge_ends(e).unwrap().contains(&v));
+            }
+            assert!(e1.is_none() || e1 != e2, "{v:?} has {e1:?} twice");
+
+            let p = segments.vertex_position(v).unwrap();
+            segments.index.sphere_at(
+                p.x,
+                p.y,
+                &segments.x,
+                &segments.y,
+                1e-9,
+                &mut found,
+            );
+            assert!(found.contains(&v.slot()), "{v:?} is not in the index");
+        }
+        assert_eq!(
+            vertices.len() + segments.free_vertices.len(),
+            v_num as usize
+        );
+        assert_eq!(
+            segments.get_active_vertex_count(),
+            segments.active_vertex_ids().count()
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn segments_stay_consistent(ops in vec(op(true), 0..64)) {
+            let mut segments = circle();
+            for op in ops {
+                match apply(&mut segments, op) {
+                    Ok(())
+                    | Err(SegmentsError::SegmentEnd { .. })
+                    | Err(SegmentsError::TriangleLoop { .. }) => {}
+                    Err(err) => panic!("{op:?} failed: {err}"),
+                }
+                assert_consistent(&segments);
+            }
+        }
+
+        #[test]
+        fn closed_loops_stay_closed(ops in vec(op(false), 0..64)) {
+            let mut segments = circle();
+            for op in ops {
+                match apply(&mut segments, op) {
+                    Ok(()) | Err(SegmentsError::TriangleLoop { .. }) => {}
+                    Err(err) => panic!("{op:?} failed: {err}"),
+                }
+                assert_consistent(&segments);
+                let runs = segments.runs();
+                assert_eq!(runs.len(), 1);
+                let (run, closed) = &runs[0];
+                assert!(closed);
+                assert_eq!(run.len(), segments.vertex_ids().count());
+                assert_eq!(run.len(), segments.edge_ids().count());
+            }
+        }
+    }
+}
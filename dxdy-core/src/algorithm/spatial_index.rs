@@ -0,0 +1,331 @@
+use std::fmt;
+
+use super::{quad_tree::QuadTree, zone_map::ZoneMap};
+
+/// occupancy of the zones (or leaves) of a spatial index, for tuning the
+/// zone width and spotting pathological clustering.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct OccupancyStats {
+    /// number of zones.
+    pub zones: u64,
+    /// number of zones with at least one vertex.
+    pub occupied_zones: u64,
+    /// most vertices in a single zone.
+    pub max_per_zone: u64,
+    /// mean vertices per occupied zone.
+    pub mean_per_zone: f64,
+    /// sphere queries since the query counters were last reset.
+    pub queries: u64,
+    /// vertices examined by those queries, whether or not they were within
+    /// the query radius.
+    pub query_candidates: u64,
+}
+
+impl OccupancyStats {
+    /// build stats from the number of vertices in each zone.
+    pub(super) fn from_zone_sizes(
+        sizes: impl Iterator<Item = usize>,
+        queries: u64,
+        query_candidates: u64,
+    ) -> Self {
+        let mut stats = Self {
+            queries,
+            query_candidates,
+            ..Self::default()
+        };
+
+        let mut total = 0;
+        for size in sizes {
+            stats.zones += 1;
+            if size > 0 {
+                stats.occupied_zones += 1;
+                stats.max_per_zone = stats.max_per_zone.max(size as u64);
+                total += size;
+            }
+        }
+        stats.mean_per_zone =
+            total as f64 / stats.occupied_zones.max(1) as f64;
+
+        stats
+    }
+
+    /// mean vertices examined per sphere query.
+    pub fn candidates_per_query(&self) -> f64 {
+        self.query_candidates as f64 / self.queries.max(1) as f64
+    }
+}
+
+impl fmt::Display for OccupancyStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "zones: {}/{} occupied, max {} / mean {:.1} per zone, {:.1} candidates/query",
+            self.occupied_zones,
+            self.zones,
+            self.max_per_zone,
+            self.mean_per_zone,
+            self.candidates_per_query(),
+        )
+    }
+}
+
+/// spatial lookup of vertices by position, used for the neighbor queries of
+/// the force pass.
+pub(super) trait SpatialIndex {
+    /// add vertex v1 at `(xs[v1], ys[v1])`. returns v1.
+    fn add_vertex(&mut self, v1: u64, xs: &[f64], ys: &[f64]) -> u64;
+
+    fn delete_vertex(&mut self, v1: i64);
+
+    /// move vertex v1 to `(x, y)`.
+    fn update_vertex(&mut self, v1: i64, x: f64, y: f64);
+
+    /// upper bound on the number of vertices a single sphere query can
+    /// return, useful as a capacity hint for the query buffer.
+    fn get_max_sphere_count(&self) -> u64;
+
+    /// find all vertices within `rad` of vertex v, including v itself.
+    ///
+    /// `vertices` is cleared and filled with the results. returns the number
+    /// of vertices found.
+    fn sphere_vertices(
+        &self,
+        v: i64,
+        xs: &[f64],
+        ys: &[f64],
+        rad: f64,
+        vertices: &mut Vec<i64>,
+    ) -> usize {
+        let (x, y) = (xs[v as usize], ys[v as usize]);
+        self.sphere_at(x, y, xs, ys, rad, vertices)
+    }
+
+    /// find all vertices within `rad` of `(x, y)`, like
+    /// [`Self::sphere_vertices`].
+    fn sphere_at(
+        &self,
+        x: f64,
+        y: f64,
+        xs: &[f64],
+        ys: &[f64],
+        rad: f64,
+        vertices: &mut Vec<i64>,
+    ) -> usize;
+
+    fn occupancy_stats(&self) -> OccupancyStats;
+
+    /// reset the query counters of [`Self::occupancy_stats`].
+    fn reset_query_stats(&self);
+}
+
+/// the structure used for the spatial index of a simulation.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum IndexKind {
+    /// fixed grid of square zones. fast when vertex density is even.
+    #[default]
+    ZoneMap,
+    /// adaptive quadtree. better when vertices are clustered.
+    QuadTree,
+}
+
+#[derive(Clone)]
+pub(super) enum Index {
+    ZoneMap(ZoneMap),
+    QuadTree(QuadTree),
+}
+
+//===================================================================
+// Constructors
+//===================================================================
+
+impl Index {
+    /// create an empty index. `nz` is the number of zones per side for
+    /// [`IndexKind::ZoneMap`], covering the square with lower corner
+    /// `origin` and side `size`.
+    pub(super) fn covering(
+        kind: IndexKind,
+        nz: u64,
+        origin: [f64; 2],
+        size: f64,
+    ) -> Self {
+        match kind {
+            IndexKind::ZoneMap => {
+                Self::ZoneMap(ZoneMap::covering(nz, origin, size))
+            }
+            IndexKind::QuadTree => Self::QuadTree(QuadTree::new()),
+        }
+    }
+}
+
+//===================================================================
+// Public Methods
+//===================================================================
+
+impl SpatialIndex for Index {
+    fn add_vertex(&mut self, v1: u64, xs: &[f64], ys: &[f64]) -> u64 {
+        match self {
+            Self::ZoneMap(zm) => zm.add_vertex(v1, xs, ys),
+            Self::QuadTree(qt) => qt.add_vertex(v1, xs, ys),
+        }
+    }
+
+    fn delete_vertex(&mut self, v1: i64) {
+        match self {
+            Self::ZoneMap(zm) => zm.delete_vertex(v1),
+            Self::QuadTree(qt) => qt.delete_vertex(v1),
+        }
+    }
+
+    fn update_vertex(&mut self, v1: i64, x: f64, y: f64) {
+        match self {
+            Self::ZoneMap(zm) => zm.update_vertex(v1, x, y),
+            Self::QuadTree(qt) => qt.update_vertex(v1, x, y),
+        }
+    }
+
+    fn get_max_sphere_count(&self) -> u64 {
+        match self {
+            Self::ZoneMap(zm) => zm.get_max_sphere_count(),
+            Self::QuadTree(qt) => qt.get_max_sphere_count(),
+        }
+    }
+
+    fn sphere_at(
+        &self,
+        x: f64,
+        y: f64,
+        xs: &[f64],
+        ys: &[f64],
+        rad: f64,
+        vertices: &mut Vec<i64>,
+    ) -> usize {
+        match self {
+            Self::ZoneMap(zm) => zm.sphere_at(x, y, xs, ys, rad, vertices),
+            Self::QuadTree(qt) => qt.sphere_at(x, y, xs, ys, rad, vertices),
+        }
+    }
+
+    fn occupancy_stats(&self) -> OccupancyStats {
+        match self {
+            Self::ZoneMap(zm) => zm.occupancy_stats(),
+            Self::QuadTree(qt) => qt.occupancy_stats(),
+        }
+    }
+
+    fn reset_query_stats(&self) {
+        match self {
+            Self::ZoneMap(zm) => zm.reset_query_stats(),
+            Self::QuadTree(qt) => qt.reset_query_stats(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, SeedableRng, rngs::SmallRng};
+
+    use super::*;
+
+    #[test]
+    fn quad_trees_find_the_neighbors_zone_maps_do() {
+        const NZ: u64 = 20;
+        let mut rng = SmallRng::seed_from_u64(1);
+        // random points, and points on the borders and corners of zones
+        let (mut xs, mut ys): (Vec<f64>, Vec<f64>) =
+            (0..500).map(|_| rng.random::<(f64, f64)>()).unzip();
+        for i in 0..=NZ {
+            let border = i as f64 / NZ as f64;
+            xs.extend([border, border, rng.random()]);
+            ys.extend([border, rng.random(), border]);
+        }
+
+        let indexes = [IndexKind::ZoneMap, IndexKind::QuadTree].map(|kind| {
+            let mut index = Index::covering(kind, NZ, [0., 0.], 1.);
+            for v in 0..xs.len() as u64 {
+                index.add_vertex(v, &xs, &ys);
+            }
+            index
+        });
+
+        let mut vertices = Vec::new();
+        let mut neighbors = |index: &Index, v, rad| {
+            let n = index.sphere_vertices(v, &xs, &ys, rad, &mut vertices);
+            let mut found = vertices[..n].to_vec();
+            found.sort_unstable();
+            found
+        };
+        for v in 0..xs.len() as i64 {
+            // up to the width of a zone, and exactly it
+            for rad in [0.01, 0.03, 1. / NZ as f64] {
+                let [zone_map, quad_tree] =
+                    indexes.each_ref().map(|index| neighbors(index, v, rad));
+                assert_eq!(zone_map, quad_tree, "vertex {v}, radius {rad}");
+                assert!(zone_map.contains(&v));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod benches {
+    extern crate test;
+
+    use test::Bencher;
+
+    use super::*;
+
+    const N: usize = 20_000;
+    const RAD: f64 = 0.04;
+
+    /// `N` vertices where 90% are packed into a small disc and the rest are
+    /// spread over the unit square.
+    fn clustered_seed() -> (Vec<f64>, Vec<f64>) {
+        // xorshift, to keep the seed deterministic without extra deps
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state >> 11) as f64 / (1_u64 << 53) as f64
+        };
+
+        (0..N)
+            .map(|i| {
+                if i % 10 == 0 {
+                    (next(), next())
+                } else {
+                    let (r, theta) = (0.05 * next().sqrt(), 6.3 * next());
+                    (0.3 + r * theta.cos(), 0.3 + r * theta.sin())
+                }
+            })
+            .unzip()
+    }
+
+    fn bench_sphere_vertices(b: &mut Bencher, kind: IndexKind) {
+        let (xs, ys) = clustered_seed();
+        let mut index = Index::covering(kind, (1. / RAD) as u64, [0., 0.], 1.);
+        for v in 0..N as u64 {
+            index.add_vertex(v, &xs, &ys);
+        }
+
+        let mut vertices = Vec::new();
+        b.iter(|| {
+            (0..N as i64)
+                .step_by(50)
+                .map(|v| {
+                    index.sphere_vertices(v, &xs, &ys, RAD, &mut vertices)
+                })
+                .sum::<usize>()
+        });
+    }
+
+    #[bench]
+    fn zone_map_clustered(b: &mut Bencher) {
+        bench_sphere_vertices(b, IndexKind::ZoneMap);
+    }
+
+    #[bench]
+    fn quad_tree_clustered(b: &mut Bencher) {
+        bench_sphere_vertices(b, IndexKind::QuadTree);
+    }
+}
@@ -0,0 +1,294 @@
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+use rand::{Rng, rngs::SmallRng};
+
+use super::{
+    ids::EdgeId,
+    segments::{Remap, Segments},
+};
+
+/// the pass in which each edge will next be considered for splitting.
+///
+/// every spawn pass splits an edge with probability `limit * weight`, so the
+/// number of passes until it is split is geometrically distributed. drawing
+/// that number once, when the edge is added or changed, lets a pass visit
+/// only the edges that are due rather than drawing for every edge.
+#[derive(Clone)]
+pub(super) struct SpawnSchedule {
+    /// number of passes so far.
+    pass: u64,
+    /// the spawn limit the schedule was drawn with. `NaN` before the first
+    /// pass.
+    limit: f64,
+    /// map of edge to the pass it is due in by edge index. `u64::MAX` for
+    /// edges that are not scheduled.
+    due: Vec<u64>,
+    queue: BinaryHeap<Reverse<(u64, EdgeId)>>,
+}
+
+//===================================================================
+// Constructors
+//===================================================================
+
+impl SpawnSchedule {
+    pub(super) fn new() -> Self {
+        Self {
+            pass: 0,
+            limit: f64::NAN,
+            due: Vec::new(),
+            queue: BinaryHeap::new(),
+        }
+    }
+}
+
+//===================================================================
+// Helpers
+//===================================================================
+
+/// number of passes before the first success of a trial with probability
+/// `p` per pass, or `u64::MAX` if it never succeeds.
+fn passes_until_split(p: f64, rng: &mut SmallRng) -> u64 {
+    if p <= 0. || p.is_nan() {
+        return u64::MAX;
+    }
+    if p >= 1. {
+        return 0;
+    }
+
+    // in (0, 1], so the log is finite
+    let u = 1. - rng.random::<f64>();
+    (u.ln() / (1. - p).ln()).floor() as u64
+}
+
+//===================================================================
+// Private Methods
+//===================================================================
+
+impl SpawnSchedule {
+    /// draw the pass the edge of `id` is due in, if it has not been
+    /// deleted.
+    fn schedule(
+        &mut self,
+        id: EdgeId,
+        segments: &Segments,
+        rng: &mut SmallRng,
+    ) {
+        let Ok(weight) = segments.get_edge_weight(id) else {
+            return;
+        };
+        let wait = passes_until_split(self.limit * weight, rng);
+        let due = self.pass.saturating_add(wait);
+
+        let eu = id.slot() as usize;
+        if self.due.len() <= eu {
+            self.due.resize(eu + 1, u64::MAX);
+        }
+        self.due[eu] = due;
+
+        if due != u64::MAX {
+            self.queue.push(Reverse((due, id)));
+        }
+    }
+}
+
+//===================================================================
+// Public Methods
+//===================================================================
+
+impl SpawnSchedule {
+    /// start the next pass, scheduling the edges that are dirty in
+    /// `segments`. returns the live edges that are due in this pass.
+    ///
+    /// every edge is rescheduled if `limit` differs from the last pass.
+    pub(super) fn next_pass(
+        &mut self,
+        segments: &mut Segments,
+        limit: f64,
+        rng: &mut SmallRng,
+    ) -> Vec<EdgeId> {
+        let dirty = segments.take_dirty_edges();
+
+        if limit != self.limit {
+            self.limit = limit;
+            self.queue.clear();
+            self.due.fill(u64::MAX);
+            for e in segments.edge_ids() {
+                self.schedule(e, segments, rng);
+            }
+        } else {
+            for e in dirty {
+                self.schedule(e, segments, rng);
+            }
+        }
+
+        let mut due = Vec::new();
+        while let Some(&Reverse((pass, e))) = self.queue.peek() {
+            if pass > self.pass {
+                break;
+            }
+            self.queue.pop();
+
+            // stale if the edge was rescheduled or deleted since
+            let eu = e.slot() as usize;
+            if self.due[eu] == pass && segments.edge_exists(e) {
+                self.due[eu] = u64::MAX;
+                due.push(e);
+            }
+        }
+
+        self.pass += 1;
+        due
+    }
+
+    /// forget the schedule, so that every edge is rescheduled in the next
+    /// pass.
+    pub(super) fn invalidate(&mut self) {
+        self.limit = f64::NAN;
+    }
+
+    /// schedule the edge of `id` again after it was due but not split.
+    pub(super) fn reschedule(
+        &mut self,
+        id: EdgeId,
+        segments: &Segments,
+        rng: &mut SmallRng,
+    ) {
+        self.schedule(id, segments, rng);
+    }
+
+    /// bring the scheduled edges along to where [`Segments::compact`] moved
+    /// them, keeping the passes they are due in.
+    pub(super) fn remap(&mut self, remap: &Remap) {
+        let mut due = vec![u64::MAX; self.due.len()];
+        let queue = std::mem::take(&mut self.queue);
+        for Reverse((pass, e)) in queue {
+            // entries of edges since rescheduled are dropped on the way
+            if self.due[e.slot() as usize] != pass {
+                continue;
+            }
+            if let Some(new) = remap.edge(e) {
+                due[new.slot() as usize] = pass;
+                self.queue.push(Reverse((pass, new)));
+            }
+        }
+        self.due = due;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::algorithm::spatial_index::IndexKind;
+
+    fn circle(n: usize) -> Segments {
+        let angles = (0..n)
+            .map(|i| i as f64 * std::f64::consts::TAU / n as f64)
+            .collect::<Vec<_>>();
+        let mut segments = Segments::new(
+            1000,
+            0.1,
+            IndexKind::ZoneMap,
+            glam::DVec2::ZERO,
+            1.,
+        )
+        .unwrap();
+        segments
+            .init_circle_segment(0.5, 0.5, 0.1, &angles)
+            .unwrap();
+        segments
+    }
+
+    #[test]
+    fn waits_are_geometric() {
+        let mut rng = SmallRng::seed_from_u64(1);
+        assert_eq!(passes_until_split(0., &mut rng), u64::MAX);
+        assert_eq!(passes_until_split(f64::NAN, &mut rng), u64::MAX);
+        assert_eq!(passes_until_split(1., &mut rng), 0);
+
+        // failures before the first success, (1 - p) / p on average
+        let n = 100_000;
+        let mean = (0..n)
+            .map(|_| passes_until_split(0.1, &mut rng) as f64)
+            .sum::<f64>()
+            / n as f64;
+        assert!((mean - 9.).abs() < 0.2, "{mean}");
+    }
+
+    #[test]
+    fn edges_are_due_at_the_spawn_rate() {
+        let mut segments = circle(100);
+        let mut schedule = SpawnSchedule::new();
+        let mut rng = SmallRng::seed_from_u64(1);
+
+        // edges that are due but not split are rescheduled
+        let passes = 2000;
+        let mut due = 0;
+        for _ in 0..passes {
+            let edges = schedule.next_pass(&mut segments, 0.01, &mut rng);
+            due += edges.len();
+            for e in edges {
+                schedule.reschedule(e, &segments, &mut rng);
+            }
+        }
+
+        // one edge in a pass on average
+        let rate = due as f64 / passes as f64;
+        assert!((rate - 1.).abs() < 0.1, "{rate}");
+    }
+
+    #[test]
+    fn only_live_edges_are_due() {
+        let mut segments = circle(16);
+        let mut schedule = SpawnSchedule::new();
+        let mut rng = SmallRng::seed_from_u64(1);
+
+        // every edge is due in every pass at a limit of 1
+        let due = schedule.next_pass(&mut segments, 1., &mut rng);
+        assert_eq!(due.len(), 16);
+        for e in due {
+            schedule.reschedule(e, &segments, &mut rng);
+        }
+
+        segments.remove(segments.vertex_id(0).unwrap()).unwrap();
+        let due = schedule.next_pass(&mut segments, 1., &mut rng);
+        let live = segments.edge_ids().collect::<HashSet<_>>();
+        assert_eq!(live.len(), 14);
+        assert_eq!(due.into_iter().collect::<HashSet<_>>(), live);
+
+        // and none once the limit is 0
+        assert!(schedule.next_pass(&mut segments, 0., &mut rng).is_empty());
+    }
+
+    #[test]
+    fn compaction_keeps_the_schedule() {
+        let mut segments = circle(32);
+        let mut schedule = SpawnSchedule::new();
+        let mut rng = SmallRng::seed_from_u64(1);
+        schedule.next_pass(&mut segments, 0.1, &mut rng);
+        segments.remove(segments.vertex_id(3).unwrap()).unwrap();
+
+        let (mut compacted, mut moved) = (segments.clone(), schedule.clone());
+        let remap = compacted.compact();
+        moved.remap(&remap);
+
+        // the same edges are due in the same passes, by their new ids
+        let mut due = 0;
+        let mut moved_rng = rng.clone();
+        for _ in 0..50 {
+            let edges = schedule.next_pass(&mut segments, 0.1, &mut rng);
+            let moved_edges =
+                moved.next_pass(&mut compacted, 0.1, &mut moved_rng);
+            due += edges.len();
+            let remapped = edges.into_iter().map(|e| remap.edge(e));
+            assert_eq!(
+                remapped.collect::<Vec<_>>(),
+                moved_edges.into_iter().map(Some).collect::<Vec<_>>()
+            );
+        }
+        assert!(due > 0);
+    }
+}
@@ -0,0 +1,106 @@
+use std::{f64::consts::TAU, fmt, str::FromStr};
+
+use anyhow::Context;
+use glam::{DMat2, DVec2};
+
+/// a mirror or rotational symmetry about a center, that drawn strokes are
+/// copied under, and that the growth can be kept to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Symmetry {
+    /// mirrored across the vertical line through the center, left to right.
+    Horizontal,
+    /// mirrored across the horizontal line through the center, top to
+    /// bottom.
+    Vertical,
+    /// turned about the center by each of `n` equal parts of a turn.
+    Radial(u32),
+}
+
+impl Symmetry {
+    /// the linear maps of the symmetry, starting with the identity.
+    pub fn maps(self) -> Vec<DMat2> {
+        let mirror = |x, y| DMat2::from_diagonal(DVec2::new(x, y));
+        match self {
+            Self::Horizontal => vec![DMat2::IDENTITY, mirror(-1., 1.)],
+            Self::Vertical => vec![DMat2::IDENTITY, mirror(1., -1.)],
+            Self::Radial(n) => {
+                let n = n.max(1);
+                (0..n)
+                    .map(|i| DMat2::from_angle(TAU * i as f64 / n as f64))
+                    .collect()
+            }
+        }
+    }
+}
+
+impl fmt::Display for Symmetry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Horizontal => write!(f, "horizontal"),
+            Self::Vertical => write!(f, "vertical"),
+            Self::Radial(n) => write!(f, "radial-{n}"),
+        }
+    }
+}
+
+impl FromStr for Symmetry {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "horizontal" => Ok(Self::Horizontal),
+            "vertical" => Ok(Self::Vertical),
+            _ => {
+                let n = s
+                    .strip_prefix("radial-")
+                    .and_then(|n| n.parse().ok())
+                    .filter(|&n| n > 0)
+                    .with_context(|| format!("unknown symmetry: {s}"))?;
+                Ok(Self::Radial(n))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_cover_the_group() {
+        let center = DVec2::splat(0.5);
+        let p = DVec2::new(0.75, 0.5);
+        let images = |symmetry: Symmetry| {
+            symmetry
+                .maps()
+                .into_iter()
+                .map(|m| (center + m * (p - center)) * 1e9)
+                .map(|q| q.round() / 1e9)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(images(Symmetry::Horizontal), [p, DVec2::new(0.25, 0.5)]);
+        assert_eq!(images(Symmetry::Vertical), [p, p]);
+        assert_eq!(
+            images(Symmetry::Radial(4)),
+            [
+                p,
+                DVec2::new(0.5, 0.75),
+                DVec2::new(0.25, 0.5),
+                DVec2::new(0.5, 0.25),
+            ]
+        );
+
+        for symmetry in [
+            Symmetry::Horizontal,
+            Symmetry::Vertical,
+            Symmetry::Radial(6),
+        ] {
+            assert_eq!(
+                symmetry.to_string().parse::<Symmetry>().ok(),
+                Some(symmetry)
+            );
+        }
+        assert!("radial-0".parse::<Symmetry>().is_err());
+        assert!("diagonal".parse::<Symmetry>().is_err());
+    }
+}
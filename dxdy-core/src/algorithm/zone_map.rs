@@ -0,0 +1,297 @@
+use std::cell::Cell;
+
+use super::spatial_index::{OccupancyStats, SpatialIndex};
+
+/// Initial capacity of each zone.
+const SIZE: usize = 1024;
+
+/// Grid of `nz * nz` square zones over a square region, given to
+/// [`ZoneMap::covering`]. Positions outside of the region are placed in the
+/// closest zone.
+#[derive(Clone)]
+pub(super) struct ZoneMap {
+    nz: u64,
+    /// Lower corner of the region covered by the map.
+    origin: [f64; 2],
+    /// Side length of the region covered by the map.
+    size: f64,
+    greatest_zone_size: u64,
+    /// Map of vertex to `z` by vertex index, `-1` for vertices that are not
+    /// in the map.
+    vz: Vec<i64>,
+    /// Map of zone to the vertices in it by zone index.
+    z: Vec<Vec<i64>>,
+    /// Number of sphere queries.
+    queries: Cell<u64>,
+    /// Number of vertices examined by sphere queries.
+    query_candidates: Cell<u64>,
+}
+
+//===================================================================
+// Constructors
+//===================================================================
+
+impl ZoneMap {
+    /// A map over the square with lower corner `origin` and side `size`.
+    pub(super) fn covering(nz: u64, origin: [f64; 2], size: f64) -> Self {
+        let total_zones = nz * nz;
+
+        let z = (0..total_zones).map(|_| Vec::with_capacity(SIZE)).collect();
+
+        Self {
+            nz,
+            origin,
+            size,
+            greatest_zone_size: 0,
+            vz: Vec::with_capacity(SIZE),
+            z,
+            queries: Cell::new(0),
+            query_candidates: Cell::new(0),
+        }
+    }
+}
+
+//===================================================================
+// Private Methods
+//===================================================================
+
+impl ZoneMap {
+    fn add_vertex_to_zone(&mut self, z1: i64, v1: i64) {
+        let zv = &mut self.z[z1 as usize];
+
+        zv.push(v1);
+
+        if zv.len() as u64 > self.greatest_zone_size {
+            self.greatest_zone_size = zv.len() as u64;
+        }
+    }
+
+    fn remove_vertex_from_zone(&mut self, z1: i64, v1: i64) {
+        let zv = &mut self.z[z1 as usize];
+
+        if let Some(i) = zv.iter().position(|&v| v == v1) {
+            zv.swap_remove(i);
+        }
+    }
+
+    /// Zone coordinate of `x` along the axis with origin `o`, clamped to
+    /// the map.
+    fn get_zi(&self, x: f64, o: f64) -> i64 {
+        let nz = self.nz as i64;
+        (((x - o) / self.size * nz as f64) as i64).clamp(0, nz - 1)
+    }
+
+    fn get_z(&self, x: f64, y: f64) -> i64 {
+        let nz = self.nz as i64;
+        let i = self.get_zi(x, self.origin[0]);
+        let j = self.get_zi(y, self.origin[1]);
+        nz * i + j
+    }
+}
+
+//===================================================================
+// Public Methods
+//===================================================================
+
+impl SpatialIndex for ZoneMap {
+    fn add_vertex(&mut self, v1: u64, xs: &[f64], ys: &[f64]) -> u64 {
+        let (x, y) = (xs[v1 as usize], ys[v1 as usize]);
+
+        let z1 = self.get_z(x, y);
+        self.add_vertex_to_zone(z1, v1 as i64);
+
+        if v1 as usize >= self.vz.len() {
+            self.vz.resize(v1 as usize + 1, -1);
+        }
+        self.vz[v1 as usize] = z1;
+
+        v1
+    }
+
+    fn delete_vertex(&mut self, v1: i64) {
+        let z1 = self.vz[v1 as usize];
+        if z1 < 0 {
+            return;
+        }
+
+        self.remove_vertex_from_zone(z1, v1);
+        self.vz[v1 as usize] = -1;
+    }
+
+    fn get_max_sphere_count(&self) -> u64 {
+        self.greatest_zone_size * 9
+    }
+
+    fn sphere_at(
+        &self,
+        x: f64,
+        y: f64,
+        xs: &[f64],
+        ys: &[f64],
+        rad: f64,
+        vertices: &mut Vec<i64>,
+    ) -> usize {
+        let nz = self.nz as i64;
+        let zx = self.get_zi(x, self.origin[0]);
+        let zy = self.get_zi(y, self.origin[1]);
+
+        // number of zones to search on each side, usually 1 when the zone
+        // width is at least `rad`
+        let span = ((rad / self.size * nz as f64).ceil() as i64).max(1);
+
+        let rad2 = rad * rad;
+
+        vertices.clear();
+
+        self.queries.set(self.queries.get() + 1);
+
+        for i in (zx - span).max(0)..(zx + span + 1).min(nz) {
+            for j in (zy - span).max(0)..(zy + span + 1).min(nz) {
+                let zv = &self.z[(i * nz + j) as usize];
+                self.query_candidates
+                    .set(self.query_candidates.get() + zv.len() as u64);
+                for &l in zv {
+                    let dx = x - xs[l as usize];
+                    let dy = y - ys[l as usize];
+                    if dx * dx + dy * dy < rad2 {
+                        vertices.push(l);
+                    }
+                }
+            }
+        }
+
+        vertices.len()
+    }
+
+    fn update_vertex(&mut self, v1: i64, x: f64, y: f64) {
+        let old_z = self.vz[v1 as usize];
+        if old_z < 0 {
+            return;
+        }
+
+        let new_z = self.get_z(x, y);
+
+        if new_z != old_z {
+            self.remove_vertex_from_zone(old_z, v1);
+            self.add_vertex_to_zone(new_z, v1);
+            self.vz[v1 as usize] = new_z;
+        }
+    }
+
+    fn occupancy_stats(&self) -> OccupancyStats {
+        OccupancyStats::from_zone_sizes(
+            self.z.iter().map(Vec::len),
+            self.queries.get(),
+            self.query_candidates.get(),
+        )
+    }
+
+    fn reset_query_stats(&self) {
+        self.queries.set(0);
+        self.query_candidates.set(0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(mut vertices: Vec<i64>) -> Vec<i64> {
+        vertices.sort();
+        vertices
+    }
+
+    /// the number of vertices in the zones of `zm`.
+    fn len(zm: &ZoneMap) -> u64 {
+        zm.z.iter().map(|zv| zv.len() as u64).sum()
+    }
+
+    #[test]
+    fn add_vertex_beyond_initial_capacity() {
+        let n = 3 * SIZE;
+        let xs = vec![0.5; n];
+        let ys = vec![0.5; n];
+        let mut zm = ZoneMap::covering(10, [0., 0.], 1.);
+
+        for v in 0..n as u64 {
+            assert_eq!(zm.add_vertex(v, &xs, &ys), v);
+        }
+
+        assert_eq!(len(&zm), n as u64);
+        assert_eq!(zm.get_max_sphere_count(), 9 * n as u64);
+    }
+
+    #[test]
+    fn vertices_on_the_boundary_are_in_the_map() {
+        let xs = [0., 1., 1., 0.];
+        let ys = [0., 0., 1., 1.];
+        let mut zm = ZoneMap::covering(10, [0., 0.], 1.);
+        for v in 0..4 {
+            zm.add_vertex(v, &xs, &ys);
+        }
+
+        let mut vertices = Vec::new();
+        assert_eq!(zm.sphere_vertices(2, &xs, &ys, 0.1, &mut vertices), 1);
+        assert_eq!(vertices, [2]);
+    }
+
+    #[test]
+    fn sphere_vertices_within_radius() {
+        let xs = [0.50, 0.52, 0.58, 0.90];
+        let ys = [0.50, 0.50, 0.50, 0.90];
+        let mut zm = ZoneMap::covering(10, [0., 0.], 1.);
+        for v in 0..4 {
+            zm.add_vertex(v, &xs, &ys);
+        }
+
+        let mut vertices = Vec::new();
+        zm.sphere_vertices(0, &xs, &ys, 0.05, &mut vertices);
+        assert_eq!(sorted(vertices.clone()), [0, 1]);
+
+        zm.sphere_vertices(0, &xs, &ys, 0.1, &mut vertices);
+        assert_eq!(sorted(vertices.clone()), [0, 1, 2]);
+    }
+
+    #[test]
+    fn deleted_vertex_is_not_found() {
+        let xs = [0.5, 0.51];
+        let ys = [0.5, 0.51];
+        let mut zm = ZoneMap::covering(10, [0., 0.], 1.);
+        zm.add_vertex(0, &xs, &ys);
+        zm.add_vertex(1, &xs, &ys);
+
+        zm.delete_vertex(1);
+        assert_eq!(len(&zm), 1);
+
+        let mut vertices = Vec::new();
+        zm.sphere_vertices(0, &xs, &ys, 0.1, &mut vertices);
+        assert_eq!(vertices, [0]);
+
+        // deleting twice is a no-op
+        zm.delete_vertex(1);
+        assert_eq!(len(&zm), 1);
+    }
+
+    #[test]
+    fn update_vertex_moves_it_between_zones() {
+        let mut xs = [0.05, 0.95];
+        let mut ys = [0.05, 0.95];
+        let mut zm = ZoneMap::covering(10, [0., 0.], 1.);
+        zm.add_vertex(0, &xs, &ys);
+        zm.add_vertex(1, &xs, &ys);
+
+        let mut vertices = Vec::new();
+        zm.sphere_vertices(1, &xs, &ys, 0.1, &mut vertices);
+        assert_eq!(vertices, [1]);
+
+        (xs[0], ys[0]) = (0.93, 0.93);
+        zm.update_vertex(0, xs[0], ys[0]);
+
+        zm.sphere_vertices(1, &xs, &ys, 0.1, &mut vertices);
+        assert_eq!(sorted(vertices.clone()), [0, 1]);
+
+        // the old zone no longer holds the vertex
+        let old_z = zm.get_z(0.05, 0.05);
+        assert!(zm.z[old_z as usize].is_empty());
+    }
+}
@@ -0,0 +1,38 @@
+//! The differential line growth of dxdy.draw, without the window: the
+//! simulation that grows lines from seeds in the unit square, and the
+//! shapes they are drawn as.
+//!
+//! A [`Simulation`](algorithm::Simulation) is seeded with
+//! [`add_seed`](algorithm::Simulation::add_seed) or from a
+//! [`Primitive`](algorithm::Primitive), then stepped with the growth
+//! [`Params`](algorithm::Params), which can change from one step to the
+//! next:
+//!
+//! ```
+//! use dxdy_core::algorithm::{Params, Primitive, PrimitiveKind, Simulation};
+//! use glam::DVec2;
+//!
+//! let params = Params::DEFAULT;
+//! let mut simulation = Simulation::new(&params, 1)?;
+//! simulation.add_primitive(&Primitive {
+//!     kind: PrimitiveKind::Circle,
+//!     center: DVec2::splat(0.5),
+//!     radius: 0.05,
+//!     rotation: 0.,
+//!     n_vertices: 32,
+//!     passive: false,
+//! })?;
+//! for _ in 0..100 {
+//!     simulation.step(&params)?;
+//! }
+//! let paths = simulation.paths();
+//! # assert!(!paths.is_empty());
+//! # Ok::<(), dxdy_core::algorithm::SegmentsError>(())
+//! ```
+
+#![cfg_attr(test, feature(test))]
+
+pub mod algorithm;
+pub mod shape;
+#[cfg(feature = "wasm")]
+pub mod wasm;
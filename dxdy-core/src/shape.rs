@@ -0,0 +1,352 @@
+//! Shapes drawn as polylines, with the state of the stylus at each vertex,
+//! that the growth is seeded from.
+
+use glam::DVec2;
+
+pub mod smooth;
+
+/// State of the stylus when a vertex was drawn.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Pen {
+    /// Pressure from `0.` to `1.`.
+    pub pressure: f64,
+    /// Tilt of the stylus in x and y, each from `-1.` to `1.`.
+    pub tilt: DVec2,
+}
+
+impl Pen {
+    /// Pen state for input without pressure or tilt, like a mouse.
+    pub const DEFAULT: Self = Self {
+        pressure: 1.,
+        tilt: DVec2::ZERO,
+    };
+}
+
+/// A move, rotation and uniform scale of a shape about its center.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Similarity {
+    pub offset: DVec2,
+    /// Angle in radians it is turned by.
+    pub angle: f64,
+    pub scale: f64,
+}
+
+impl Similarity {
+    pub const IDENTITY: Self = Self {
+        offset: DVec2::ZERO,
+        angle: 0.,
+        scale: 1.,
+    };
+
+    /// Where `pos` goes when turned and scaled about `center`, then moved.
+    pub fn apply(self, center: DVec2, pos: DVec2) -> DVec2 {
+        let turned = DVec2::from_angle(self.angle).rotate(pos - center);
+        center + self.offset + turned * self.scale
+    }
+}
+
+/// A line drawn on the canvas, as vertices in document coordinates, which
+/// seeds the growth or stands in its way.
+#[derive(Clone)]
+pub struct Shape {
+    start: DVec2,
+    /// Position of each vertex relative to `start`.
+    verticies: Vec<DVec2>,
+    /// Event time of the first vertex in milliseconds.
+    start_time: u32,
+    /// Time of each vertex in milliseconds since the first vertex.
+    timestamps: Vec<u32>,
+    /// Stylus state at each vertex.
+    pens: Vec<Pen>,
+    /// Whether the last vertex is joined back to the first.
+    closed: bool,
+    /// Whether the shape is a fixed obstacle for the simulation rather than
+    /// a seed.
+    obstacle: bool,
+    /// Whether the shape, if open, is a spine that the growth seeded along
+    /// it follows, rather than a seed.
+    spine: bool,
+    /// How fast the growth seeded from the shape spawns new vertices,
+    /// relative to other seeds.
+    weight: f64,
+    /// Stiffness of the growth seeded from the shape, or `None` for the one
+    /// of the parameters.
+    stiffness: Option<f64>,
+    /// Words that describe where the shape came from, such as the segment
+    /// of the growth it was baked from, for sorting shapes afterwards.
+    tags: Vec<String>,
+    /// Name of the layer the shape is exported in, or `None` for the one of
+    /// every shape without a layer.
+    layer: Option<String>,
+}
+
+impl Default for Shape {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Shape {
+    pub const fn new() -> Self {
+        Self {
+            start: DVec2::ZERO,
+            verticies: Vec::new(),
+            start_time: 0,
+            timestamps: Vec::new(),
+            pens: Vec::new(),
+            closed: false,
+            obstacle: false,
+            spine: false,
+            weight: 1.,
+            stiffness: None,
+            tags: Vec::new(),
+            layer: None,
+        }
+    }
+
+    pub fn from_pos(x: f64, y: f64, time: u32, pen: Pen) -> Self {
+        Self {
+            start: DVec2::new(x, y),
+            verticies: vec![DVec2::ZERO],
+            start_time: time,
+            timestamps: vec![0],
+            pens: vec![pen],
+            closed: false,
+            obstacle: false,
+            spine: false,
+            weight: 1.,
+            stiffness: None,
+            tags: Vec::new(),
+            layer: None,
+        }
+    }
+
+    /// A shape at `start` without any vertices yet, to be added with
+    /// [`Self::next_vertex_at`] at times relative to the first one.
+    pub fn starting_at(start: DVec2) -> Self {
+        Self {
+            start,
+            ..Self::new()
+        }
+    }
+
+    pub fn start(&self) -> DVec2 {
+        self.start
+    }
+
+    pub fn closed(&self) -> bool {
+        self.closed
+    }
+
+    pub fn set_closed(&mut self, closed: bool) {
+        self.closed = closed;
+    }
+
+    pub fn obstacle(&self) -> bool {
+        self.obstacle
+    }
+
+    pub fn set_obstacle(&mut self, obstacle: bool) {
+        self.obstacle = obstacle;
+    }
+
+    pub fn spine(&self) -> bool {
+        self.spine
+    }
+
+    pub fn set_spine(&mut self, spine: bool) {
+        self.spine = spine;
+    }
+
+    pub fn weight(&self) -> f64 {
+        self.weight
+    }
+
+    pub fn set_weight(&mut self, weight: f64) {
+        self.weight = weight;
+    }
+
+    pub fn stiffness(&self) -> Option<f64> {
+        self.stiffness
+    }
+
+    pub fn set_stiffness(&mut self, stiffness: Option<f64>) {
+        self.stiffness = stiffness;
+    }
+
+    pub fn tags(&self) -> &[String] {
+        &self.tags
+    }
+
+    /// Tag the shape with `tag`, a single word.
+    pub fn add_tag(&mut self, tag: String) {
+        self.tags.push(tag);
+    }
+
+    pub fn layer(&self) -> Option<&str> {
+        self.layer.as_deref()
+    }
+
+    pub fn set_layer(&mut self, layer: Option<String>) {
+        self.layer = layer;
+    }
+
+    /// Offset of the last vertex from the start, or zero without vertices.
+    pub fn last_offset(&self) -> DVec2 {
+        self.verticies().last().unwrap_or(DVec2::ZERO)
+    }
+
+    pub fn n_verticies(&self) -> usize {
+        self.verticies.len()
+    }
+
+    pub fn verticies(&self) -> impl Iterator<Item = DVec2> {
+        self.verticies.iter().copied()
+    }
+
+    pub fn timestamps(&self) -> impl Iterator<Item = u32> {
+        self.timestamps.iter().copied()
+    }
+
+    pub fn pens(&self) -> impl Iterator<Item = Pen> {
+        self.pens.iter().copied()
+    }
+
+    /// Time in milliseconds between the first and last vertex.
+    pub fn duration(&self) -> u32 {
+        self.timestamps.last().copied().unwrap_or(0)
+    }
+
+    /// Number of vertices that had been drawn `t` milliseconds into the
+    /// stroke.
+    pub fn n_verticies_at(&self, t: u32) -> usize {
+        self.timestamps.partition_point(|&ts| ts <= t)
+    }
+
+    /// Drawing speed at each vertex relative to the mean speed of the whole
+    /// stroke, for use as per-vertex growth weights when seeding along with
+    /// the weight of the shape.
+    ///
+    /// The speed at a vertex is the average of the speeds along its adjacent
+    /// edges. Strokes that never moved get a weight of `1.` everywhere.
+    pub fn speed_weights(&self) -> Vec<f64> {
+        let n = self.verticies.len();
+
+        let edge_speeds = (1..n)
+            .map(|i| {
+                let dist = self.verticies[i].distance(self.verticies[i - 1]);
+                let dt =
+                    self.timestamps[i].saturating_sub(self.timestamps[i - 1]);
+                dist / dt.max(1) as f64
+            })
+            .collect::<Vec<_>>();
+
+        let mut speeds = (0..n)
+            .map(|i| {
+                let before = i.checked_sub(1).and_then(|i| edge_speeds.get(i));
+                let after = edge_speeds.get(i);
+                match (before, after) {
+                    (Some(a), Some(b)) => (a + b) / 2.,
+                    (Some(s), None) | (None, Some(s)) => *s,
+                    (None, None) => 0.,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let mean = speeds.iter().sum::<f64>() / n.max(1) as f64;
+        if mean <= 0. {
+            speeds.fill(1.);
+        } else {
+            speeds.iter_mut().for_each(|s| *s /= mean);
+        }
+
+        speeds
+    }
+
+    pub fn next_vertex(&mut self, x: f64, y: f64, time: u32, pen: Pen) {
+        self.next_vertex_at(DVec2::new(x, y), time, pen);
+    }
+
+    pub fn next_vertex_at(&mut self, offset: DVec2, time: u32, pen: Pen) {
+        self.verticies.push(offset);
+        self.timestamps.push(time.wrapping_sub(self.start_time));
+        self.pens.push(pen);
+    }
+
+    /// Smallest and largest corners of the box around the vertices, if
+    /// there are any.
+    pub fn bounds(&self) -> Option<(DVec2, DVec2)> {
+        let first = self.start + *self.verticies.first()?;
+        Some(
+            self.verticies
+                .iter()
+                .fold((first, first), |(min, max), &v| {
+                    (min.min(self.start + v), max.max(self.start + v))
+                }),
+        )
+    }
+
+    /// Center of the box around the vertices, which shapes are turned and
+    /// scaled about.
+    pub fn center(&self) -> DVec2 {
+        match self.bounds() {
+            Some((min, max)) => (min + max) / 2.,
+            None => self.start,
+        }
+    }
+
+    /// This shape with each vertex moved by `similarity` about its center.
+    /// The vertices keep their times and pen states.
+    pub fn transformed(&self, similarity: Similarity) -> Self {
+        let center = self.center();
+        self.mapped(|pos| similarity.apply(center, pos))
+    }
+
+    /// This shape with each vertex moved to `f` of where it is. The
+    /// vertices keep their times and pen states.
+    pub fn mapped(&self, f: impl Fn(DVec2) -> DVec2) -> Self {
+        let start = f(self.start);
+        let verticies = self
+            .verticies
+            .iter()
+            .map(|&v| f(self.start + v) - start)
+            .collect();
+        Self {
+            start,
+            verticies,
+            ..self.clone()
+        }
+    }
+
+    /// Squared distance from `pos` to the closest point on the polyline of
+    /// this shape.
+    pub fn dist2_to(&self, pos: DVec2) -> f64 {
+        let n = self.verticies.len();
+        if n == 0 {
+            return f64::INFINITY;
+        }
+
+        let p = pos - self.start;
+        let mut min_dist2 = f64::INFINITY;
+
+        // a single vertex is treated as a zero length edge
+        let n_edges = if self.closed || n == 1 { n } else { n - 1 };
+        for i in 0..n_edges {
+            let a = self.verticies[i];
+            let b = self.verticies[(i + 1) % n];
+            let ab = b - a;
+            let len2 = ab.length_squared();
+            let t = if len2 > 0. {
+                ((p - a).dot(ab) / len2).clamp(0., 1.)
+            } else {
+                0.
+            };
+            let dist2 = (p - (a + ab * t)).length_squared();
+            if dist2 < min_dist2 {
+                min_dist2 = dist2;
+            }
+        }
+
+        min_dist2
+    }
+}
@@ -0,0 +1,332 @@
+use std::sync::RwLock;
+
+use glam::DVec2;
+
+use super::{Pen, Shape};
+
+/// How a stroke is smoothed when the drag that draws it ends.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Method {
+    Off,
+    /// Chaikin corner cutting. Each iteration replaces every edge with
+    /// points a quarter and three quarters along it, rounding off corners
+    /// without passing through the drawn points.
+    Chaikin,
+    /// Catmull-Rom subdivision. Each iteration inserts the midpoint of the
+    /// spline through every pair of neighboring points, keeping the drawn
+    /// points.
+    CatmullRom,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SmoothOptions {
+    pub method: Method,
+    /// Number of passes, each of which doubles the number of vertices.
+    pub iterations: u32,
+    /// Distance on screen between the vertices of a stroke, which is
+    /// resampled to even spacing along its length before it is smoothed.
+    /// 0 keeps every sample the pointer gave.
+    pub spacing: f64,
+}
+
+impl SmoothOptions {
+    pub const DEFAULT: Self = Self {
+        method: Method::Chaikin,
+        iterations: 2,
+        spacing: 20.,
+    };
+}
+
+pub static SMOOTH_OPTIONS: RwLock<SmoothOptions> =
+    RwLock::new(SmoothOptions::DEFAULT);
+
+/// A vertex of a stroke with the time and pen state it was drawn with, so
+/// that they can all be interpolated together.
+#[derive(Clone, Copy)]
+struct Sample {
+    offset: DVec2,
+    time: f64,
+    pen: Pen,
+}
+
+impl Sample {
+    /// Weighted sum of samples. The weights should sum to 1.
+    fn combine<const N: usize>(samples: [(Sample, f64); N]) -> Self {
+        samples.into_iter().fold(
+            Self {
+                offset: DVec2::ZERO,
+                time: 0.,
+                pen: Pen {
+                    pressure: 0.,
+                    tilt: DVec2::ZERO,
+                },
+            },
+            |acc, (s, w)| Self {
+                offset: acc.offset + s.offset * w,
+                time: acc.time + s.time * w,
+                pen: Pen {
+                    pressure: acc.pen.pressure + s.pen.pressure * w,
+                    tilt: acc.pen.tilt + s.pen.tilt * w,
+                },
+            },
+        )
+    }
+}
+
+/// Samples `spacing` apart along the edges of `samples`, starting at the
+/// first one, interpolating time and pen along with the position. The end
+/// of an open stroke is kept, and a last sample closer to the end than half
+/// the spacing is dropped for it.
+fn resample(samples: &[Sample], closed: bool, spacing: f64) -> Vec<Sample> {
+    let n = samples.len();
+    let mut out = vec![samples[0]];
+    // distance along the stroke to the next sample
+    let mut next = spacing;
+    // distance along the stroke to the start of the current edge
+    let mut travelled = 0.;
+
+    let n_edges = if closed { n } else { n - 1 };
+    for i in 0..n_edges {
+        let (a, mut b) = (samples[i], samples[(i + 1) % n]);
+        if i == n - 1 {
+            // the closing edge was drawn at the end of the stroke
+            b.time = a.time;
+        }
+        let len = a.offset.distance(b.offset);
+        while next <= travelled + len {
+            let t = (next - travelled) / len;
+            out.push(Sample::combine([(a, 1. - t), (b, t)]));
+            next += spacing;
+        }
+        travelled += len;
+    }
+
+    let remaining = travelled - (next - spacing);
+    if remaining < spacing / 2. && out.len() > 1 {
+        out.pop();
+    }
+    if !closed {
+        out.push(samples[n - 1]);
+    }
+
+    out
+}
+
+fn chaikin(samples: &[Sample], closed: bool) -> Vec<Sample> {
+    let n = samples.len();
+    let mut out = Vec::with_capacity(2 * n);
+
+    if !closed {
+        out.push(samples[0]);
+    }
+
+    let n_edges = if closed { n } else { n - 1 };
+    for i in 0..n_edges {
+        let (a, mut b) = (samples[i], samples[(i + 1) % n]);
+        if i == n - 1 {
+            // the closing edge was drawn at the end of the stroke
+            b.time = a.time;
+        }
+        out.push(Sample::combine([(a, 0.75), (b, 0.25)]));
+        out.push(Sample::combine([(a, 0.25), (b, 0.75)]));
+    }
+
+    if !closed {
+        out.push(samples[n - 1]);
+    }
+
+    out
+}
+
+fn catmull_rom(samples: &[Sample], closed: bool) -> Vec<Sample> {
+    let n = samples.len();
+    let mut out = Vec::with_capacity(2 * n);
+
+    // neighbors past the ends of an open stroke are clamped to the ends
+    let get = |i: isize| {
+        let i = if closed {
+            i.rem_euclid(n as isize)
+        } else {
+            i.clamp(0, n as isize - 1)
+        };
+        samples[i as usize]
+    };
+
+    let n_edges = if closed { n } else { n - 1 };
+    for i in 0..n_edges as isize {
+        let (p0, p1, p2, p3) = (get(i - 1), get(i), get(i + 1), get(i + 2));
+
+        let mut mid = Sample::combine([
+            (p0, -1. / 16.),
+            (p1, 9. / 16.),
+            (p2, 9. / 16.),
+            (p3, -1. / 16.),
+        ]);
+        // interpolate time and pen linearly so that time keeps increasing
+        // along the stroke and pressure stays in range
+        let linear = Sample::combine([(p1, 0.5), (p2, 0.5)]);
+        mid.time = if i as usize == n - 1 {
+            p1.time
+        } else {
+            linear.time
+        };
+        mid.pen = linear.pen;
+
+        out.push(p1);
+        out.push(mid);
+    }
+
+    if !closed {
+        out.push(samples[n - 1]);
+    }
+
+    out
+}
+
+impl Shape {
+    fn samples(&self) -> Vec<Sample> {
+        self.verticies
+            .iter()
+            .zip(&self.timestamps)
+            .zip(&self.pens)
+            .map(|((&offset, &time), &pen)| Sample {
+                offset,
+                time: time as f64,
+                pen,
+            })
+            .collect()
+    }
+
+    /// This shape with its vertices replaced by `samples`.
+    fn with_samples(&self, samples: &[Sample]) -> Self {
+        Self {
+            start: self.start,
+            verticies: samples.iter().map(|s| s.offset).collect(),
+            start_time: self.start_time,
+            timestamps: samples.iter().map(|s| s.time as u32).collect(),
+            pens: samples.iter().map(|s| s.pen).collect(),
+            closed: self.closed,
+            obstacle: self.obstacle,
+            spine: self.spine,
+            weight: self.weight,
+            stiffness: self.stiffness,
+            tags: self.tags.clone(),
+            layer: self.layer.clone(),
+        }
+    }
+
+    /// This shape with its vertices `spacing` apart along its edges, so
+    /// that how fast it was drawn does not change how dense it is. Shapes
+    /// with fewer than 2 vertices, or a spacing that is not positive, are
+    /// returned unchanged.
+    pub fn resampled(&self, spacing: f64) -> Self {
+        if self.verticies.len() < 2 || spacing <= 0. {
+            return self.clone();
+        }
+        self.with_samples(&resample(&self.samples(), self.closed, spacing))
+    }
+
+    /// This shape smoothed with `options`. Shapes with fewer than 3 vertices
+    /// are returned unchanged.
+    pub fn smoothed(&self, options: SmoothOptions) -> Self {
+        let pass = match options.method {
+            Method::Off => return self.clone(),
+            Method::Chaikin => chaikin,
+            Method::CatmullRom => catmull_rom,
+        };
+
+        if self.verticies.len() < 3 {
+            return self.clone();
+        }
+
+        let mut samples = self.samples();
+        for _ in 0..options.iterations {
+            samples = pass(&samples, self.closed);
+        }
+        self.with_samples(&samples)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An open zig-zag of `n` vertices, each a unit to the right of the
+    /// last and a unit above or below the middle, drawn 10 ms apart.
+    fn zig_zag(n: u32) -> Shape {
+        let mut shape = Shape::starting_at(DVec2::ZERO);
+        for i in 0..n {
+            let y = if i % 2 == 0 { -1. } else { 1. };
+            let offset = DVec2::new(i as f64, y);
+            shape.next_vertex_at(offset, i * 10, Pen::DEFAULT);
+        }
+        shape
+    }
+
+    /// The sharpest turn of `shape` at any of its vertices, in radians.
+    fn sharpest_turn(shape: &Shape) -> f64 {
+        let vertices = shape.verticies().collect::<Vec<_>>();
+        vertices
+            .windows(3)
+            .map(|w| (w[1] - w[0]).angle_to(w[2] - w[1]).abs())
+            .fold(0., f64::max)
+    }
+
+    #[test]
+    fn smoothing_rounds_off_zig_zags() {
+        let shape = zig_zag(8);
+        for method in [Method::Chaikin, Method::CatmullRom] {
+            let smoothed = shape.smoothed(SmoothOptions {
+                method,
+                iterations: 2,
+                spacing: 0.,
+            });
+
+            assert!(smoothed.n_verticies() > shape.n_verticies());
+            let (before, after) =
+                (sharpest_turn(&shape), sharpest_turn(&smoothed));
+            assert!(after < before, "{method:?}: {after} vs {before}");
+            // open lines keep their ends
+            let ends = |shape: &Shape| {
+                let mut vertices = shape.verticies();
+                (vertices.next(), vertices.last())
+            };
+            assert_eq!(ends(&smoothed), ends(&shape), "{method:?}");
+            // and time goes on along them
+            let times = smoothed.timestamps().collect::<Vec<_>>();
+            assert!(times.is_sorted(), "{method:?}: {times:?}");
+        }
+    }
+
+    #[test]
+    fn catmull_rom_keeps_the_drawn_points() {
+        let mut shape = zig_zag(6);
+        shape.set_closed(true);
+        let smoothed = shape.smoothed(SmoothOptions {
+            method: Method::CatmullRom,
+            iterations: 1,
+            spacing: 0.,
+        });
+
+        assert!(smoothed.closed());
+        assert_eq!(smoothed.n_verticies(), 2 * shape.n_verticies());
+        let drawn = smoothed.verticies().step_by(2).collect::<Vec<_>>();
+        assert_eq!(drawn, shape.verticies().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn resampled_strokes_are_evenly_spaced() {
+        let mut shape = Shape::starting_at(DVec2::ZERO);
+        // drawn fast, then slowly
+        for (i, x) in [0., 7., 8., 8.5, 9., 10.].into_iter().enumerate() {
+            let offset = DVec2::new(x, 0.);
+            shape.next_vertex_at(offset, i as u32 * 10, Pen::DEFAULT);
+        }
+
+        let resampled = shape.resampled(2.);
+        let xs = resampled.verticies().map(|v| v.x).collect::<Vec<_>>();
+        assert_eq!(xs, [0., 2., 4., 6., 8., 10.]);
+        // a spacing that is not positive keeps every sample
+        assert_eq!(shape.resampled(0.).n_verticies(), shape.n_verticies());
+    }
+}
@@ -0,0 +1,63 @@
+//! bindings for running the growth in a browser, built for
+//! `wasm32-unknown-unknown` with the `wasm` feature. see `www/index.html`
+//! for a canvas drawing it.
+
+use glam::DVec2;
+use wasm_bindgen::prelude::*;
+
+use crate::algorithm::{Params, Primitive, PrimitiveKind, Simulation};
+
+/// a differential line growing from a circle in the unit square, with the
+/// default parameters.
+#[wasm_bindgen]
+pub struct DifferentialLine {
+    simulation: Simulation,
+    params: Params,
+}
+
+#[wasm_bindgen]
+impl DifferentialLine {
+    /// a line seeded with a circle of `n_vertices` vertices of `radius`
+    /// about `(x, y)`. lines with the same seed grow identically.
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        seed: u32,
+        x: f64,
+        y: f64,
+        radius: f64,
+        n_vertices: usize,
+    ) -> Result<DifferentialLine, JsError> {
+        let params = Params::DEFAULT;
+        let mut simulation = Simulation::new(&params, seed.into())?;
+        simulation.add_primitive(&Primitive {
+            kind: PrimitiveKind::Circle,
+            center: DVec2::new(x, y),
+            radius,
+            rotation: 0.,
+            n_vertices,
+            passive: false,
+        })?;
+        Ok(Self { simulation, params })
+    }
+
+    /// advance the growth by one step. returns `false` once it can not
+    /// continue.
+    pub fn step(&mut self) -> Result<bool, JsError> {
+        Ok(self.simulation.step(&self.params)?)
+    }
+
+    /// end points of every edge, as `x1, y1, x2, y2` one edge after
+    /// another.
+    pub fn get_edges(&self) -> Vec<f64> {
+        self.simulation
+            .edges()
+            .into_iter()
+            .flat_map(|[a, b]| [a.x, a.y, b.x, b.y])
+            .collect()
+    }
+
+    /// number of vertices of the line.
+    pub fn vertex_count(&self) -> usize {
+        self.simulation.vertex_count()
+    }
+}
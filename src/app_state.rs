@@ -0,0 +1,1370 @@
+//! What is drawn and grown in a window, owned by the application and shared
+//! with the callbacks of the window that change it.
+
+#[cfg(feature = "gui")]
+use std::{cell::RefCell, path::PathBuf, rc::Rc, time::Instant};
+
+use anyhow::Result;
+use glam::DVec2;
+
+use super::{
+    algorithm::{
+        Field, FieldKind, Params, Primitive, SegmentsError, Symmetry,
+    },
+    bake::{self, BakeLayers},
+    damage::Damage,
+    export::Region,
+    grow_preset::GrowPreset,
+    keys::Action,
+    macros::MacroRecording,
+    playback::{FAST_SPEED, Playback},
+    polyline::Polyline,
+    project::Project,
+    recorder::Recorder,
+    replay::{Event, SessionLog},
+    shape::{Pen, Shape, Similarity, smooth::SmoothOptions},
+    simulation::{REWIND_STEPS, SimulationController},
+    snap::SnapOptions,
+    transform::{DOCUMENT_SIZE, Transform},
+    viewport::Viewport,
+};
+#[cfg(feature = "gui")]
+use super::{replay::Replay, stats::Stats};
+
+pub(crate) mod pointer;
+
+/// What dragging on the canvas does.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) enum Tool {
+    /// Draw a shape.
+    #[default]
+    Draw,
+    /// Place a field of this kind, or move one already placed.
+    Field(FieldKind),
+    /// Draw a stroke that the growth near it is anchored onto, rather than
+    /// a shape.
+    Anchor,
+    /// Select a shape, then move, turn and scale it.
+    Select,
+}
+
+/// How close to the center of a field it is picked up by the field tool,
+/// in pixels of the canvas.
+const FIELD_PICK_RADIUS: f64 = 10.;
+
+/// A field being dragged with the field tool.
+#[derive(Clone, Copy, Debug)]
+struct FieldDrag {
+    /// Index of the field among those of the simulation.
+    index: usize,
+    /// Its position when the drag started, in world coordinates.
+    start: DVec2,
+    /// Whether the drag sets which way the wind blows rather than moving
+    /// the field, as when placing wind.
+    aiming: bool,
+}
+
+/// How far above the selected shape the handle that turns it is, in pixels
+/// of the window.
+const ROTATE_HANDLE_GAP: f64 = 24.;
+
+/// Angle in radians the selected shape is turned by with a key.
+const NUDGE_ANGLE: f64 = std::f64::consts::PI / 12.;
+
+/// Factor the selected shape is scaled by with a key.
+const NUDGE_SCALE: f64 = 1.1;
+
+/// A handle of the selected shape, which changes it when dragged.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Handle {
+    /// Above the shape, turning it about its center.
+    Rotate,
+    /// At the bottom right corner of its box, scaling it about its center.
+    Scale,
+}
+
+/// The selected shape being dragged with the select tool.
+#[derive(Clone)]
+struct ShapeDrag {
+    /// The shape when the drag started.
+    original: Shape,
+    /// Where the drag started, in world coordinates.
+    grab: DVec2,
+    /// The handle being dragged, or `None` if the shape is being moved.
+    handle: Option<Handle>,
+    /// What the drag has done to the shape so far.
+    similarity: Similarity,
+}
+
+/// [`AppState`] as shared between the callbacks of its window.
+#[cfg(feature = "gui")]
+pub(crate) type SharedState = Rc<RefCell<AppState>>;
+
+/// The state of one window, each of which has its own drawing and
+/// simulation.
+pub(crate) struct AppState {
+    /// Position of the pointer in the window, or `None` if it is outside.
+    cursor_position: Option<DVec2>,
+    /// Which of the two colors the cursor blinks between it is in.
+    cursor_color: bool,
+    current_shape: Shape,
+    /// Latest stylus state, recorded with each vertex of the current shape.
+    current_pen: Pen,
+    /// Whether new shapes are obstacles for the simulation instead of seeds.
+    draw_obstacles: bool,
+    /// Whether new open shapes are spines for the growth to follow instead
+    /// of seeds.
+    draw_spines: bool,
+    /// The completed shapes, which seed the simulation.
+    shapes: Vec<Shape>,
+    /// Counts the changes to `shapes`, so that what is drawn from them can
+    /// be kept until they change again.
+    shapes_generation: u64,
+    simulation: SimulationController,
+    viewport: Viewport,
+    playback: Option<Playback>,
+    /// The recording of the simulation in progress, if any.
+    recorder: Option<Recorder>,
+    /// Damage to the window since it was last drawn.
+    damage: Damage,
+    /// Rectangle of the drawing recordings are cropped to, if any.
+    region: Option<Region>,
+    /// Corner the region being dragged out starts at, while it is.
+    region_start: Option<DVec2>,
+    /// The log of what is done to the state, if it is being logged.
+    session_log: Option<SessionLog>,
+    /// The macro being recorded, if any.
+    macro_recording: Option<MacroRecording>,
+    #[cfg(feature = "gui")]
+    stats: Stats,
+    /// Whether the stats are shown over the drawing.
+    #[cfg(feature = "gui")]
+    show_stats: bool,
+    /// Whether the cursor pushes the growth away, or pulls it in.
+    #[cfg(feature = "gui")]
+    sculpting: bool,
+    tool: Tool,
+    /// The field being dragged with the field tool, while it is.
+    field_drag: Option<FieldDrag>,
+    /// Index of the shape selected with the select tool, and the
+    /// generation of the shapes it was selected in, as any other change to
+    /// the shapes deselects it.
+    selection: Option<(usize, u64)>,
+    /// The selected shape being dragged, while it is.
+    shape_drag: Option<ShapeDrag>,
+    snap: SnapOptions,
+    /// Where the segment of the stroke being drawn that is snapped to an
+    /// angle of 45° ends, as an offset from its start, and when it was
+    /// dragged there, while Ctrl is held. It is added once Ctrl is let go.
+    snapped_segment: Option<(DVec2, u32)>,
+    /// Symmetry each stroke is copied under, about the center of the
+    /// canvas.
+    symmetry: Option<Symmetry>,
+}
+
+impl Default for AppState {
+    fn default() -> Self {
+        Self {
+            cursor_position: None,
+            cursor_color: true,
+            current_shape: Shape::new(),
+            current_pen: Pen::DEFAULT,
+            draw_obstacles: false,
+            draw_spines: false,
+            shapes: Vec::new(),
+            shapes_generation: 0,
+            simulation: SimulationController::rewindable(),
+            viewport: Viewport::DEFAULT,
+            playback: None,
+            recorder: None,
+            damage: Damage::None,
+            region: None,
+            region_start: None,
+            session_log: None,
+            macro_recording: None,
+            #[cfg(feature = "gui")]
+            stats: Stats::default(),
+            #[cfg(feature = "gui")]
+            show_stats: false,
+            #[cfg(feature = "gui")]
+            sculpting: false,
+            tool: Tool::Draw,
+            field_drag: None,
+            selection: None,
+            shape_drag: None,
+            snap: SnapOptions::DEFAULT,
+            snapped_segment: None,
+            symmetry: None,
+        }
+    }
+}
+
+impl AppState {
+    #[cfg(feature = "gui")]
+    pub(crate) fn cursor_position(&self) -> Option<DVec2> {
+        self.cursor_position
+    }
+
+    /// Move the cursor to `pos`, returning where it was.
+    pub(crate) fn move_cursor(&mut self, pos: Option<DVec2>) -> Option<DVec2> {
+        std::mem::replace(&mut self.cursor_position, pos)
+    }
+
+    pub(crate) fn cursor_color(&self) -> bool {
+        self.cursor_color
+    }
+
+    /// Switch the cursor to its other color.
+    pub(crate) fn blink(&mut self) {
+        self.cursor_color = !self.cursor_color;
+    }
+
+    pub(crate) fn current_shape(&self) -> &Shape {
+        &self.current_shape
+    }
+
+    pub(crate) fn pen(&self) -> Pen {
+        self.current_pen
+    }
+
+    pub(crate) fn set_pen(&mut self, pen: Pen) {
+        self.current_pen = pen;
+    }
+
+    pub(crate) fn draw_obstacles(&self) -> bool {
+        self.draw_obstacles
+    }
+
+    pub(crate) fn set_draw_obstacles(&mut self, draw_obstacles: bool) {
+        self.draw_obstacles = draw_obstacles;
+    }
+
+    pub(crate) fn draw_spines(&self) -> bool {
+        self.draw_spines
+    }
+
+    pub(crate) fn set_draw_spines(&mut self, draw_spines: bool) {
+        self.draw_spines = draw_spines;
+    }
+
+    /// Start a new current shape at `pos` in world coordinates.
+    pub(crate) fn begin_stroke(&mut self, pos: DVec2, time: u32) {
+        self.current_shape =
+            Shape::from_pos(pos.x, pos.y, time, self.current_pen);
+    }
+
+    /// Extend the current shape to `offset` from its start, unless the
+    /// last vertex is already there. Every sample is kept until the stroke
+    /// ends. Returns the new edge in world coordinates if it was extended.
+    pub(crate) fn continue_stroke(
+        &mut self,
+        offset: DVec2,
+        time: u32,
+    ) -> Option<(DVec2, DVec2)> {
+        let shape = &mut self.current_shape;
+        let last_offset = shape.last_offset();
+        if offset == last_offset {
+            return None;
+        }
+
+        shape.next_vertex_at(offset, time, self.current_pen);
+        let start = shape.start();
+        Some((start + last_offset, start + offset))
+    }
+
+    /// Finish the current shape at `offset` from its start, closing it back
+    /// to the start if `closed`, and add it to the shapes resampled and
+    /// smoothed with `options`, its spacing being seen at `zoom`, so that
+    /// zooming in gives finer strokes. Copies of it under the symmetry, if
+    /// any, are added after it.
+    pub(crate) fn end_stroke(
+        &mut self,
+        offset: DVec2,
+        time: u32,
+        closed: bool,
+        zoom: f64,
+        options: SmoothOptions,
+    ) {
+        let mut shape =
+            std::mem::replace(&mut self.current_shape, Shape::new());
+        shape.next_vertex_at(offset, time, self.current_pen);
+        shape.set_closed(closed);
+        shape.set_obstacle(self.draw_obstacles);
+        shape.set_spine(self.draw_spines);
+        let shape = shape.resampled(options.spacing / zoom).smoothed(options);
+        // copied under the symmetry, if any, after the stroke itself
+        let center = DOCUMENT_SIZE / 2.;
+        let images = self.symmetry.map_or_else(Vec::new, |symmetry| {
+            symmetry.maps()[1..]
+                .iter()
+                .map(|&m| shape.mapped(|pos| center + m * (pos - center)))
+                .collect()
+        });
+        let shapes = self.shapes_mut();
+        shapes.push(shape);
+        shapes.extend(images);
+    }
+
+    /// Finish the current stroke at `offset` from its start and anchor the
+    /// growth within `reach` of it, in world coordinates, onto it, instead
+    /// of adding it to the shapes. Returns how many vertices were anchored.
+    pub(crate) fn end_anchor_stroke(
+        &mut self,
+        offset: DVec2,
+        time: u32,
+        reach: f64,
+    ) -> usize {
+        let mut stroke =
+            std::mem::replace(&mut self.current_shape, Shape::new());
+        stroke.next_vertex_at(offset, time, self.current_pen);
+        let anchored = self.simulation.anchor(&stroke, reach);
+        self.damage_mut().add_all();
+        anchored
+    }
+
+    pub(crate) fn region(&self) -> Option<Region> {
+        self.region
+    }
+
+    /// Start dragging out a new region from `pos` in world coordinates.
+    pub(crate) fn begin_region(&mut self, pos: DVec2) {
+        self.region = None;
+        self.region_start = Some(pos);
+    }
+
+    pub(crate) fn is_dragging_region(&self) -> bool {
+        self.region_start.is_some()
+    }
+
+    /// Stretch the region being dragged out to `offset` from where it
+    /// started. A region without area is none at all.
+    pub(crate) fn drag_region(&mut self, offset: DVec2) {
+        if let Some(start) = self.region_start {
+            let region = Region::spanning(start, start + offset);
+            self.region = (!region.is_empty()).then_some(region);
+        }
+    }
+
+    /// Finish the region being dragged out at `offset` from where it
+    /// started.
+    pub(crate) fn end_region(&mut self, offset: DVec2) {
+        self.drag_region(offset);
+        self.region_start = None;
+    }
+
+    pub(crate) fn tool(&self) -> Tool {
+        self.tool
+    }
+
+    pub(crate) fn set_tool(&mut self, tool: Tool) {
+        self.tool = tool;
+        if tool != Tool::Select {
+            self.select(None);
+        }
+    }
+
+    /// Start dragging the field at `pos` in world coordinates with the
+    /// field tool, placing one of its kind there if there is none. With
+    /// `delete` the field there is removed instead.
+    pub(crate) fn begin_field_drag(&mut self, pos: DVec2, delete: bool) {
+        let Tool::Field(kind) = self.tool else {
+            return;
+        };
+        let transform = Transform::DEFAULT;
+        let mut fields = self.simulation.fields().to_vec();
+
+        let picked = fields
+            .iter()
+            .map(|field| transform.unit_to_document(field.position))
+            .enumerate()
+            .map(|(i, center)| (i, center.distance(pos)))
+            .filter(|&(_, distance)| distance <= FIELD_PICK_RADIUS)
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(i, _)| i);
+
+        self.field_drag = match (picked, delete) {
+            (Some(index), true) => {
+                fields.remove(index);
+                None
+            }
+            (None, true) => None,
+            (Some(index), false) => Some(FieldDrag {
+                index,
+                start: transform.unit_to_document(fields[index].position),
+                aiming: false,
+            }),
+            (None, false) => {
+                let position = transform.document_to_unit(pos);
+                fields.push(Field::new(kind, position));
+                Some(FieldDrag {
+                    index: fields.len() - 1,
+                    start: pos,
+                    aiming: matches!(kind, FieldKind::Wind { .. }),
+                })
+            }
+        };
+        self.simulation.set_fields(fields);
+    }
+
+    pub(crate) fn is_dragging_field(&self) -> bool {
+        self.field_drag.is_some()
+    }
+
+    /// Move the field being dragged to `offset` from where it started, or
+    /// turn wind being placed to blow towards it.
+    pub(crate) fn drag_field(&mut self, offset: DVec2) {
+        let Some(drag) = self.field_drag else {
+            return;
+        };
+        let mut fields = self.simulation.fields().to_vec();
+        let Some(field) = fields.get_mut(drag.index) else {
+            return;
+        };
+        match field.kind {
+            FieldKind::Wind { ref mut angle } if drag.aiming => {
+                if offset != DVec2::ZERO {
+                    *angle = offset.to_angle();
+                }
+            }
+            _ => {
+                let pos = drag.start + offset;
+                field.position = Transform::DEFAULT.document_to_unit(pos);
+            }
+        }
+        self.simulation.set_fields(fields);
+    }
+
+    /// Finish dragging the field at `offset` from where it started.
+    pub(crate) fn end_field_drag(&mut self, offset: DVec2) {
+        self.drag_field(offset);
+        self.field_drag = None;
+    }
+
+    pub(crate) fn snap(&self) -> SnapOptions {
+        self.snap
+    }
+
+    pub(crate) fn set_snap(&mut self, snap: SnapOptions) {
+        self.snap = snap;
+    }
+
+    /// The symmetry strokes are copied under, and whether the growth is
+    /// kept to it.
+    pub(crate) fn symmetry(&self) -> (Option<Symmetry>, bool) {
+        let growth = self.simulation.symmetry().is_some();
+        (self.symmetry, growth)
+    }
+
+    /// Copy each stroke from now on under `symmetry` about the center of
+    /// the canvas, and keep the growth to it too if `growth`.
+    pub(crate) fn set_symmetry(
+        &mut self,
+        symmetry: Option<Symmetry>,
+        growth: bool,
+    ) {
+        self.symmetry = symmetry;
+        self.simulation.set_symmetry(symmetry.filter(|_| growth));
+    }
+
+    /// Where the segment of the stroke being drawn that is snapped to an
+    /// angle of 45° ends, in world coordinates, while there is one.
+    pub(crate) fn snapped_segment(&self) -> Option<DVec2> {
+        let (offset, _) = self.snapped_segment?;
+        Some(self.current_shape.start() + offset)
+    }
+
+    /// Index of the shape selected with the select tool, if any.
+    pub(crate) fn selected(&self) -> Option<usize> {
+        let (index, generation) = self.selection?;
+        (generation == self.shapes_generation).then_some(index)
+    }
+
+    fn select(&mut self, index: Option<usize>) {
+        self.selection = index.map(|i| (i, self.shapes_generation));
+    }
+
+    /// The handles of the selected shape and where they are in world
+    /// coordinates, if a shape is selected.
+    pub(crate) fn selection_handles(&self) -> Option<[(Handle, DVec2); 2]> {
+        let (min, max) = self.shapes[self.selected()?].bounds()?;
+        let gap = ROTATE_HANDLE_GAP / self.viewport.zoom();
+        let rotate = DVec2::new((min.x + max.x) / 2., min.y - gap);
+        Some([(Handle::Rotate, rotate), (Handle::Scale, max)])
+    }
+
+    /// Start dragging with the select tool at `pos` in world coordinates:
+    /// a handle of the selected shape within `tolerance` of it, or else the
+    /// shape nearest to it within `tolerance`, or the topmost closed shape
+    /// it is inside of, which is selected. Anywhere else deselects.
+    pub(crate) fn begin_shape_drag(&mut self, pos: DVec2, tolerance: f64) {
+        let handle = self
+            .selection_handles()
+            .into_iter()
+            .flatten()
+            .find(|&(_, at)| at.distance(pos) <= tolerance)
+            .map(|(handle, _)| handle);
+        if handle.is_none() {
+            let near = self
+                .shapes
+                .iter()
+                .map(|shape| shape.dist2_to(pos))
+                .enumerate()
+                .filter(|&(_, dist2)| dist2 <= tolerance * tolerance)
+                .min_by(|(_, a), (_, b)| a.total_cmp(b))
+                .map(|(i, _)| i);
+            let inside = || self.shapes.iter().rposition(|s| s.contains(pos));
+            self.select(near.or_else(inside));
+        }
+
+        self.shape_drag = self.selected().map(|index| ShapeDrag {
+            original: self.shapes[index].clone(),
+            grab: pos,
+            handle,
+            similarity: Similarity::IDENTITY,
+        });
+    }
+
+    pub(crate) fn is_dragging_shape(&self) -> bool {
+        self.shape_drag.is_some()
+    }
+
+    /// Move the selected shape being dragged by `offset` from where the
+    /// drag started, or turn or scale it about its center if a handle is
+    /// being dragged.
+    pub(crate) fn drag_shape(&mut self, offset: DVec2) {
+        let (Some(mut drag), Some(index)) =
+            (self.shape_drag.take(), self.selected())
+        else {
+            return;
+        };
+        let center = drag.original.center();
+        let (from, to) = (drag.grab - center, drag.grab + offset - center);
+        drag.similarity = match drag.handle {
+            None => Similarity {
+                offset,
+                ..Similarity::IDENTITY
+            },
+            Some(Handle::Rotate) => Similarity {
+                angle: to.to_angle() - from.to_angle(),
+                ..Similarity::IDENTITY
+            },
+            Some(Handle::Scale) if from.length() > 0. => Similarity {
+                // never so small that it can not be picked up again
+                scale: (to.length() / from.length()).max(0.05),
+                ..Similarity::IDENTITY
+            },
+            Some(Handle::Scale) => Similarity::IDENTITY,
+        };
+        self.shapes_mut()[index] = drag.original.transformed(drag.similarity);
+        self.select(Some(index));
+        self.shape_drag = Some(drag);
+    }
+
+    /// Finish dragging the selected shape at `offset` from where the drag
+    /// started. Returns the index of the shape and what the drag did to it,
+    /// if it did anything.
+    pub(crate) fn end_shape_drag(
+        &mut self,
+        offset: DVec2,
+    ) -> Option<(usize, Similarity)> {
+        self.drag_shape(offset);
+        let drag = self.shape_drag.take()?;
+        let index = self.selected()?;
+        (drag.similarity != Similarity::IDENTITY)
+            .then_some((index, drag.similarity))
+    }
+
+    /// Move, turn and scale the shape at `index` by `similarity`, keeping it
+    /// selected if it is. Returns whether there is a shape at `index`.
+    pub(crate) fn transform_shape(
+        &mut self,
+        index: usize,
+        similarity: Similarity,
+    ) -> bool {
+        let Some(shape) = self.shapes.get(index) else {
+            return false;
+        };
+        let selected = self.selected();
+        let shape = shape.transformed(similarity);
+        self.shapes_mut()[index] = shape;
+        if selected == Some(index) {
+            self.select(selected);
+        }
+        true
+    }
+
+    /// Turn or scale the selected shape a step, as `action` does, logging
+    /// it with `params` and `smooth`. Returns whether `action` is one that
+    /// does, whether or not a shape is selected.
+    pub(crate) fn nudge_selection(
+        &mut self,
+        action: Action,
+        params: &Params,
+        smooth: SmoothOptions,
+    ) -> bool {
+        let (angle, scale) = match action {
+            Action::RotateSelectionLeft => (-NUDGE_ANGLE, 1.),
+            Action::RotateSelectionRight => (NUDGE_ANGLE, 1.),
+            Action::GrowSelection => (0., NUDGE_SCALE),
+            Action::ShrinkSelection => (0., 1. / NUDGE_SCALE),
+            _ => return false,
+        };
+        if let Some(index) = self.selected() {
+            let similarity = Similarity {
+                angle,
+                scale,
+                ..Similarity::IDENTITY
+            };
+            self.log(Event::Transform { index, similarity }, params, smooth);
+            self.transform_shape(index, similarity);
+        }
+        true
+    }
+
+    pub(crate) fn shapes(&self) -> &[Shape] {
+        &self.shapes
+    }
+
+    /// The shapes, for changing them.
+    pub(crate) fn shapes_mut(&mut self) -> &mut Vec<Shape> {
+        self.shapes_generation += 1;
+        &mut self.shapes
+    }
+
+    /// Number of times the shapes have been borrowed for changing them.
+    pub(crate) fn shapes_generation(&self) -> u64 {
+        self.shapes_generation
+    }
+
+    /// Remove the shape nearest to `pos` if it is within `tolerance` of it.
+    pub(crate) fn erase_at(
+        &mut self,
+        pos: DVec2,
+        tolerance: f64,
+    ) -> Option<Shape> {
+        let (i, dist2) = self
+            .shapes
+            .iter()
+            .map(|shape| shape.dist2_to(pos))
+            .enumerate()
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))?;
+
+        if dist2 > tolerance * tolerance {
+            return None;
+        }
+
+        Some(self.shapes_mut().remove(i))
+    }
+
+    /// Index of the topmost closed seed shape that `pos` is inside of, if
+    /// any.
+    pub(crate) fn closed_shape_at(&self, pos: DVec2) -> Option<usize> {
+        self.shapes
+            .iter()
+            .rposition(|shape| !shape.obstacle() && shape.contains(pos))
+    }
+
+    /// Grow the shape at `index` as `preset`: seed a new segment of the
+    /// simulation from it and run it, with `params` set for the preset.
+    /// Returns the parameters of the preset, which the whole simulation
+    /// grows with from then on.
+    pub(crate) fn grow_as(
+        &mut self,
+        index: usize,
+        preset: GrowPreset,
+        params: &Params,
+    ) -> Result<Params, SegmentsError> {
+        let params = preset.params(params);
+        self.simulation.grow_shape(
+            &self.shapes[index],
+            &self.shapes,
+            &params,
+        )?;
+        Ok(params)
+    }
+
+    /// Remove the last drawn shape, if any.
+    pub(crate) fn undo(&mut self) -> Option<Shape> {
+        if self.shapes.is_empty() {
+            return None;
+        }
+        self.shapes_mut().pop()
+    }
+
+    /// Add the paths of the growth to the shapes, in `layers`, as
+    /// obstacles if `obstacles`, and discard the simulation, which they
+    /// would otherwise be drawn over. As shapes like any other, they seed
+    /// the next simulation, or are grown around as obstacles.
+    pub(crate) fn bake(&mut self, layers: BakeLayers, obstacles: bool) {
+        let Some(simulation) = self.simulation.simulation() else {
+            return;
+        };
+        let mut shapes = bake::bake(simulation, layers);
+        for shape in &mut shapes {
+            shape.set_obstacle(obstacles);
+        }
+        self.shapes_mut().extend(shapes);
+        self.simulation.clear();
+    }
+
+    /// Remove all shapes, and the one being drawn.
+    pub(crate) fn clear(&mut self) {
+        self.shapes_mut().clear();
+        self.current_shape = Shape::new();
+    }
+
+    /// The drawing as a project, seeded like the simulation.
+    pub(crate) fn project(&self) -> Project {
+        let controller = &self.simulation;
+        Project {
+            shapes: self.shapes.clone(),
+            primitives: controller.primitives().to_vec(),
+            fields: controller.fields().to_vec(),
+            domain: controller.domain().clone(),
+            seed: controller.seed(),
+        }
+    }
+
+    /// Replace the drawing with `project`, seeding the simulation like it
+    /// and discarding the current one.
+    pub(crate) fn open(&mut self, project: Project) {
+        project.configure(&mut self.simulation);
+        *self.shapes_mut() = project.shapes;
+    }
+
+    pub(crate) fn simulation(&self) -> &SimulationController {
+        &self.simulation
+    }
+
+    pub(crate) fn simulation_mut(&mut self) -> &mut SimulationController {
+        &mut self.simulation
+    }
+
+    /// Start the simulation again from the shapes.
+    pub(crate) fn reset(
+        &mut self,
+        params: &Params,
+    ) -> Result<(), SegmentsError> {
+        self.simulation.reset(&self.shapes, params)
+    }
+
+    /// Start the simulation again from the shapes with `params`, and take
+    /// up to `steps` steps of it at once, for a look at how it grows.
+    pub(crate) fn preview(
+        &mut self,
+        params: &Params,
+        steps: u64,
+    ) -> Result<(), SegmentsError> {
+        self.reset(params)?;
+        for _ in 0..steps {
+            self.simulation.step_once(params)?;
+        }
+        Ok(())
+    }
+
+    /// Pause or resume the simulation, seeding it from the shapes if there
+    /// is none.
+    pub(crate) fn toggle_pause(
+        &mut self,
+        params: &Params,
+    ) -> Result<(), SegmentsError> {
+        self.simulation.toggle_pause(&self.shapes, params)
+    }
+
+    /// Add `primitive` to the seeds, restarting the simulation with it.
+    pub(crate) fn add_primitive(
+        &mut self,
+        primitive: Primitive,
+        params: &Params,
+    ) -> Result<(), SegmentsError> {
+        self.simulation
+            .add_primitive(primitive, &self.shapes, params)
+    }
+
+    /// Add the current state of the simulation to the recording in
+    /// progress, if any.
+    pub(crate) fn capture_frame(&mut self) -> Result<()> {
+        let controller = &self.simulation;
+        match (&mut self.recorder, controller.simulation()) {
+            (Some(recorder), Some(simulation)) => recorder.capture(
+                controller.iteration(),
+                &self.shapes,
+                simulation,
+            ),
+            _ => Ok(()),
+        }
+    }
+
+    pub(crate) fn viewport(&self) -> Viewport {
+        self.viewport
+    }
+
+    pub(crate) fn viewport_mut(&mut self) -> &mut Viewport {
+        &mut self.viewport
+    }
+
+    #[cfg(feature = "gui")]
+    pub(crate) fn playback(&self) -> Option<&Playback> {
+        self.playback.as_ref()
+    }
+
+    /// Replay the shapes with `playback`.
+    pub(crate) fn play(&mut self, playback: Playback) {
+        self.playback = Some(playback);
+    }
+
+    /// How far the playback has got, as the index of a shape and its
+    /// number of vertices, or `None` if there is no playback. A finished
+    /// playback is dropped.
+    #[cfg(feature = "gui")]
+    pub(crate) fn playback_progress(&mut self) -> Option<(usize, usize)> {
+        let progress = self
+            .playback
+            .as_ref()
+            .and_then(|playback| playback.progress(&self.shapes));
+        if progress.is_none() {
+            self.playback = None;
+        }
+        progress
+    }
+
+    #[cfg(feature = "gui")]
+    pub(crate) fn is_recording(&self) -> bool {
+        self.recorder.is_some()
+    }
+
+    /// Record the simulation with `recorder` from now on, finishing the
+    /// recording in progress, if any.
+    #[cfg(feature = "gui")]
+    pub(crate) fn start_recording(
+        &mut self,
+        recorder: Recorder,
+    ) -> Result<()> {
+        match self.recorder.replace(recorder) {
+            Some(previous) => previous.finish().map(drop),
+            None => Ok(()),
+        }
+    }
+
+    /// Finish the recording in progress, if any, returning where it was
+    /// written.
+    #[cfg(feature = "gui")]
+    pub(crate) fn stop_recording(&mut self) -> Result<Option<PathBuf>> {
+        self.recorder.take().map(Recorder::finish).transpose()
+    }
+
+    /// Do `action` to the drawing and its simulation. Returns `Ok(false)`
+    /// for the actions that need the window instead.
+    pub(crate) fn act(
+        &mut self,
+        action: Action,
+        params: &Params,
+    ) -> Result<bool, SegmentsError> {
+        match action {
+            Action::Clear => {
+                self.clear();
+                self.simulation.clear_primitives();
+                self.simulation.set_fields(Vec::new());
+            }
+            Action::Undo => {
+                self.undo();
+            }
+            Action::BakeSegments => self.bake(BakeLayers::Segment, false),
+            Action::BakeGenerations => {
+                self.bake(BakeLayers::Generation, false)
+            }
+            Action::BakeObstacles => self.bake(BakeLayers::Generation, true),
+            Action::ResetView => self.viewport = Viewport::DEFAULT,
+            Action::Play => self.play(Playback::new(1.)),
+            Action::PlayFast => self.play(Playback::new(FAST_SPEED)),
+            Action::PauseSim => self.toggle_pause(params)?,
+            Action::StepSim => self.simulation.step_once(params)?,
+            Action::RewindSim => {
+                self.simulation.rewind(REWIND_STEPS, params)?
+            }
+            Action::ResetSim => self.reset(params)?,
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    /// Log what is done to the state with `log` from now on.
+    #[cfg(feature = "gui")]
+    pub(crate) fn start_session_log(&mut self, log: SessionLog) {
+        self.session_log = Some(log);
+    }
+
+    /// Add `event`, done with `params` and `smooth`, to the session log and
+    /// the macro being recorded, if there are. The log is dropped if it can
+    /// not be written.
+    pub(crate) fn log(
+        &mut self,
+        event: Event,
+        params: &Params,
+        smooth: SmoothOptions,
+    ) {
+        if let Some(mut recording) = self.macro_recording.take() {
+            recording.record(event.clone(), self, params, smooth);
+            self.macro_recording = Some(recording);
+        }
+        let Some(mut log) = self.session_log.take() else {
+            return;
+        };
+        match log.log(event, self, params, smooth) {
+            Ok(()) => self.session_log = Some(log),
+            Err(err) => tracing::error!("session log stopped: {err:#}"),
+        }
+    }
+
+    /// Record what is done to the state with `params` and `smooth` as a
+    /// macro from now on, discarding the one being recorded, if any.
+    #[cfg(feature = "gui")]
+    pub(crate) fn start_macro(
+        &mut self,
+        params: &Params,
+        smooth: SmoothOptions,
+    ) {
+        let recording = MacroRecording::start(self, params, smooth);
+        self.macro_recording = Some(recording);
+    }
+
+    /// Stop recording the macro, and take it if one was being recorded.
+    #[cfg(feature = "gui")]
+    pub(crate) fn finish_macro(&mut self) -> Option<Replay> {
+        self.macro_recording.take().map(MacroRecording::finish)
+    }
+
+    #[cfg(feature = "gui")]
+    pub(crate) fn is_recording_macro(&self) -> bool {
+        self.macro_recording.is_some()
+    }
+
+    #[cfg(feature = "gui")]
+    pub(crate) fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    #[cfg(feature = "gui")]
+    pub(crate) fn stats_mut(&mut self) -> &mut Stats {
+        &mut self.stats
+    }
+
+    /// Count `iterations` more steps of the simulation in the stats, taken
+    /// by `now`.
+    #[cfg(feature = "gui")]
+    pub(crate) fn record_steps(&mut self, iterations: u64, now: Instant) {
+        let simulation = self.simulation.simulation();
+        self.stats.record_steps(iterations, simulation, now);
+    }
+
+    #[cfg(feature = "gui")]
+    pub(crate) fn show_stats(&self) -> bool {
+        self.show_stats
+    }
+
+    #[cfg(feature = "gui")]
+    pub(crate) fn toggle_stats(&mut self) {
+        self.show_stats = !self.show_stats;
+    }
+
+    #[cfg(feature = "gui")]
+    pub(crate) fn sculpting(&self) -> bool {
+        self.sculpting
+    }
+
+    #[cfg(feature = "gui")]
+    pub(crate) fn toggle_sculpting(&mut self) {
+        self.sculpting = !self.sculpting;
+    }
+
+    pub(crate) fn damage_mut(&mut self) -> &mut Damage {
+        &mut self.damage
+    }
+
+    /// The damage to the window since this was last called.
+    #[cfg(feature = "gui")]
+    pub(crate) fn take_damage(&mut self) -> Damage {
+        std::mem::take(&mut self.damage)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{shape::smooth::Method, simulation::RunState};
+
+    const UNSMOOTHED: SmoothOptions = SmoothOptions {
+        method: Method::Off,
+        iterations: 0,
+        spacing: 0.,
+    };
+
+    #[test]
+    fn stroke_keeps_every_sample_until_it_ends() {
+        let mut state = AppState::default();
+        state.begin_stroke(DVec2::new(10., 10.), 0);
+
+        assert_eq!(
+            state.continue_stroke(DVec2::new(1., 0.), 10),
+            Some((DVec2::new(10., 10.), DVec2::new(11., 10.)))
+        );
+        assert_eq!(state.continue_stroke(DVec2::new(1., 0.), 20), None);
+        assert!(state.continue_stroke(DVec2::new(3., 0.), 30).is_some());
+        assert_eq!(state.current_shape().n_verticies(), 3);
+        assert!(state.shapes().is_empty());
+    }
+
+    #[test]
+    fn ended_stroke_is_resampled_by_length() {
+        let options = SmoothOptions {
+            spacing: 20.,
+            ..UNSMOOTHED
+        };
+        let mut state = AppState::default();
+        state.begin_stroke(DVec2::ZERO, 0);
+        // slow at first and then fast, as a drag gives
+        for (x, time) in [(1., 10), (2., 20), (3., 30), (40., 40)] {
+            state.continue_stroke(DVec2::new(x, 0.), time);
+        }
+        state.end_stroke(DVec2::new(40., 40.), 80, false, 2., options);
+
+        let [shape] = state.shapes() else {
+            panic!("expected one shape");
+        };
+        // 10 apart in the world at 2x zoom, ending at the end
+        let points = shape.verticies().collect::<Vec<_>>();
+        assert_eq!(points.len(), 9);
+        for pair in points[..8].windows(2) {
+            assert!((pair[0].distance(pair[1]) - 10.).abs() < 1e-9);
+        }
+        assert_eq!(points[8], DVec2::new(40., 40.));
+        // times are those of where the samples fall on the stroke
+        let times = shape.timestamps().collect::<Vec<_>>();
+        assert_eq!(times[..5], [0, 31, 34, 37, 40]);
+        assert!(times.is_sorted());
+    }
+
+    #[test]
+    fn ended_stroke_is_added_to_shapes() {
+        let mut state = AppState::default();
+        state.set_draw_obstacles(true);
+        state.begin_stroke(DVec2::ZERO, 0);
+        state.continue_stroke(DVec2::new(50., 0.), 10);
+        let generation = state.shapes_generation();
+
+        state.end_stroke(DVec2::new(50., 50.), 20, true, 1., UNSMOOTHED);
+
+        assert_eq!(state.current_shape().n_verticies(), 0);
+        let [shape] = state.shapes() else {
+            panic!("expected one shape");
+        };
+        assert_eq!(shape.n_verticies(), 3);
+        assert!(shape.closed());
+        assert!(shape.obstacle());
+        assert!(state.shapes_generation() > generation);
+    }
+
+    #[test]
+    fn strokes_are_copied_under_the_symmetry() {
+        let mut state = AppState::default();
+        state.set_symmetry(Some(Symmetry::Radial(4)), true);
+        assert_eq!(state.symmetry(), (Some(Symmetry::Radial(4)), true));
+        let center = DOCUMENT_SIZE / 2.;
+        state.begin_stroke(center + DVec2::new(100., 0.), 0);
+        state.end_stroke(DVec2::new(0., 50.), 10, false, 1., UNSMOOTHED);
+
+        let starts = state
+            .shapes()
+            .iter()
+            .map(|shape| ((shape.start() - center) * 1e9).round() / 1e9)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            starts,
+            [
+                DVec2::new(100., 0.),
+                DVec2::new(0., 100.),
+                DVec2::new(-100., 0.),
+                DVec2::new(0., -100.),
+            ]
+        );
+
+        // mirrored top to bottom, without copying the growth
+        state.clear();
+        state.set_symmetry(Some(Symmetry::Vertical), false);
+        assert_eq!(state.symmetry(), (Some(Symmetry::Vertical), false));
+        state.begin_stroke(center + DVec2::new(0., -100.), 0);
+        state.end_stroke(DVec2::new(50., 0.), 10, false, 1., UNSMOOTHED);
+        let [_, copy] = state.shapes() else {
+            panic!("expected a stroke and its copy");
+        };
+        assert_eq!(copy.start(), center + DVec2::new(0., 100.));
+        let points = copy.verticies().collect::<Vec<_>>();
+        assert_eq!(points, [DVec2::ZERO, DVec2::new(50., 0.)]);
+    }
+
+    #[test]
+    fn erase_removes_only_shapes_within_tolerance() {
+        let mut state = AppState::default();
+        for y in [0., 100.] {
+            state.begin_stroke(DVec2::new(0., y), 0);
+            state.end_stroke(DVec2::new(100., 0.), 10, false, 1., UNSMOOTHED);
+        }
+
+        assert!(state.erase_at(DVec2::new(50., 50.), 10.).is_none());
+        let erased = state.erase_at(DVec2::new(50., 95.), 10.).unwrap();
+        assert_eq!(erased.start(), DVec2::new(0., 100.));
+        assert_eq!(state.shapes().len(), 1);
+    }
+
+    #[test]
+    fn undo_removes_last_shape() {
+        let mut state = AppState::default();
+        let generation = state.shapes_generation();
+        // nothing to undo leaves what is drawn from the shapes as it is
+        assert!(state.undo().is_none());
+        assert_eq!(state.shapes_generation(), generation);
+
+        for x in [0., 100.] {
+            state.begin_stroke(DVec2::new(x, 0.), 0);
+            state.end_stroke(DVec2::new(0., 100.), 10, false, 1., UNSMOOTHED);
+        }
+
+        assert_eq!(state.undo().unwrap().start(), DVec2::new(100., 0.));
+        assert_eq!(state.shapes().len(), 1);
+    }
+
+    #[test]
+    fn clear_removes_shapes_and_current_shape() {
+        let mut state = AppState::default();
+        state.begin_stroke(DVec2::ZERO, 0);
+        state.end_stroke(DVec2::new(100., 0.), 10, false, 1., UNSMOOTHED);
+        state.begin_stroke(DVec2::ZERO, 20);
+
+        state.clear();
+
+        assert!(state.shapes().is_empty());
+        assert_eq!(state.current_shape().n_verticies(), 0);
+    }
+
+    #[test]
+    fn cursor_moves_and_blinks() {
+        let mut state = AppState::default();
+        let color = state.cursor_color();
+
+        assert_eq!(state.move_cursor(Some(DVec2::ONE)), None);
+        assert_eq!(state.move_cursor(None), Some(DVec2::ONE));
+        state.blink();
+        assert_ne!(state.cursor_color(), color);
+    }
+
+    #[test]
+    fn dragged_region_spans_start_and_end() {
+        let mut state = AppState::default();
+        state.begin_region(DVec2::new(100., 100.));
+        state.drag_region(DVec2::new(-50., 20.));
+        state.end_region(DVec2::new(-50., 80.));
+
+        assert!(!state.is_dragging_region());
+        assert_eq!(
+            state.region(),
+            Some(Region::spanning(
+                DVec2::new(50., 100.),
+                DVec2::new(100., 180.)
+            ))
+        );
+
+        // a click without a drag clears it
+        state.begin_region(DVec2::ZERO);
+        state.end_region(DVec2::ZERO);
+        assert_eq!(state.region(), None);
+    }
+
+    #[test]
+    fn field_tool_places_moves_and_deletes_fields() {
+        let mut state = AppState::default();
+        let fields = |state: &AppState| state.simulation().fields().to_vec();
+        let at = |pos| Transform::DEFAULT.document_to_unit(pos);
+
+        state.set_tool(Tool::Field(FieldKind::Repulsor));
+        state.begin_field_drag(DVec2::new(100., 100.), false);
+        state.end_field_drag(DVec2::ZERO);
+        assert_eq!(
+            fields(&state),
+            [Field::new(FieldKind::Repulsor, at(DVec2::new(100., 100.)))]
+        );
+
+        // picked up near its center and moved
+        state.begin_field_drag(DVec2::new(105., 100.), false);
+        state.drag_field(DVec2::new(50., 0.));
+        state.end_field_drag(DVec2::new(50., 50.));
+        assert_eq!(fields(&state)[0].position, at(DVec2::new(150., 150.)));
+
+        // wind blows the way it is dragged as it is placed
+        state.set_tool(Tool::Field(FieldKind::Wind { angle: 0. }));
+        state.begin_field_drag(DVec2::new(400., 300.), false);
+        state.end_field_drag(DVec2::new(0., 10.));
+        assert_eq!(
+            fields(&state)[1].kind,
+            FieldKind::Wind {
+                angle: std::f64::consts::FRAC_PI_2
+            }
+        );
+
+        state.begin_field_drag(DVec2::new(150., 150.), true);
+        assert!(!state.is_dragging_field());
+        assert_eq!(fields(&state).len(), 1);
+        assert!(matches!(fields(&state)[0].kind, FieldKind::Wind { .. }));
+    }
+
+    #[test]
+    fn select_tool_moves_turns_and_scales_shapes() {
+        let mut state = AppState::default();
+        state.begin_stroke(DVec2::new(100., 100.), 0);
+        state.continue_stroke(DVec2::new(100., 0.), 10);
+        state.continue_stroke(DVec2::new(100., 100.), 20);
+        state.end_stroke(DVec2::new(0., 100.), 30, true, 1., UNSMOOTHED);
+        let points = |state: &AppState| {
+            state.shapes()[0]
+                .points()
+                .map(|p| p.round())
+                .collect::<Vec<_>>()
+        };
+        state.set_tool(Tool::Select);
+
+        // picked up from inside, and moved
+        state.begin_shape_drag(DVec2::new(150., 150.), 10.);
+        assert_eq!(state.selected(), Some(0));
+        state.drag_shape(DVec2::new(10., 0.));
+        let moved = state.end_shape_drag(DVec2::new(50., 0.));
+        assert_eq!(moved.map(|(_, s)| s.offset), Some(DVec2::new(50., 0.)));
+        assert_eq!(state.selected(), Some(0));
+        assert_eq!(points(&state)[0], DVec2::new(150., 100.));
+
+        // the scale handle is at the bottom right corner
+        let [_, (Handle::Scale, corner)] = state.selection_handles().unwrap()
+        else {
+            panic!("expected the scale handle second");
+        };
+        assert_eq!(corner, DVec2::new(250., 200.));
+        state.begin_shape_drag(corner, 10.);
+        state.end_shape_drag(DVec2::new(50., 50.));
+        assert_eq!(points(&state)[0], DVec2::new(100., 50.));
+
+        // turned about its center by a quarter
+        let quarter = Similarity {
+            angle: std::f64::consts::FRAC_PI_2,
+            ..Similarity::IDENTITY
+        };
+        assert!(state.transform_shape(0, quarter));
+        assert_eq!(points(&state)[0], DVec2::new(300., 50.));
+        assert_eq!(state.selected(), Some(0));
+        assert!(!state.transform_shape(1, quarter));
+
+        // a click away from it deselects it, as does any other change
+        state.begin_shape_drag(DVec2::new(600., 600.), 10.);
+        assert_eq!(state.end_shape_drag(DVec2::ZERO), None);
+        assert_eq!(state.selected(), None);
+        state.begin_shape_drag(DVec2::new(200., 150.), 10.);
+        assert_eq!(state.selected(), Some(0));
+        state.undo();
+        assert_eq!(state.selected(), None);
+    }
+
+    #[test]
+    fn opened_project_replaces_shapes() {
+        let mut state = AppState::default();
+        state.begin_stroke(DVec2::ZERO, 0);
+        state.end_stroke(DVec2::new(100., 0.), 10, false, 1., UNSMOOTHED);
+        let project = state.project();
+
+        state.clear();
+        state.open(project);
+
+        assert_eq!(state.shapes().len(), 1);
+        assert_eq!(state.shapes()[0].n_verticies(), 2);
+    }
+
+    #[test]
+    fn baked_growth_replaces_the_simulation() {
+        let params = Params::DEFAULT;
+        let mut state = AppState::default();
+        state.begin_stroke(DVec2::new(300., 300.), 0);
+        state.continue_stroke(DVec2::new(100., 0.), 10);
+        state.continue_stroke(DVec2::new(100., 100.), 20);
+        state.end_stroke(DVec2::new(0., 100.), 30, true, 1., UNSMOOTHED);
+        // nothing to bake before the simulation is seeded
+        state.act(Action::BakeSegments, &params).unwrap();
+        assert_eq!(state.shapes().len(), 1);
+
+        state.act(Action::ResetSim, &params).unwrap();
+        state.act(Action::StepSim, &params).unwrap();
+        let generation = state.shapes_generation();
+        state.act(Action::BakeSegments, &params).unwrap();
+        assert!(state.simulation().simulation().is_none());
+        assert!(state.shapes_generation() > generation);
+        let [drawn, baked] = state.shapes() else {
+            panic!("expected a drawn and a baked shape");
+        };
+        assert_eq!(drawn.layer(), None);
+        assert_eq!(baked.layer(), Some("segment-0"));
+        assert!(baked.closed());
+        assert!(!baked.obstacle());
+
+        // the baked growth seeds the next one, or is grown around
+        state.act(Action::ResetSim, &params).unwrap();
+        let simulation = state.simulation().simulation().unwrap();
+        assert_eq!(simulation.segment_count(), 2);
+        state.act(Action::BakeObstacles, &params).unwrap();
+        let baked = &state.shapes()[2..];
+        assert_eq!(baked.len(), 2);
+        assert!(baked.iter().all(Shape::obstacle));
+        state.act(Action::ResetSim, &params).unwrap();
+        let simulation = state.simulation().simulation().unwrap();
+        let passive = simulation.paths().iter().filter(|p| p.passive).count();
+        assert_eq!(passive, 2);
+    }
+
+    #[test]
+    fn shapes_grow_as_presets() {
+        let params = Params::DEFAULT;
+        let mut state = AppState::default();
+        state.begin_stroke(DVec2::new(300., 300.), 0);
+        state.continue_stroke(DVec2::new(100., 0.), 10);
+        state.continue_stroke(DVec2::new(100., 100.), 20);
+        state.end_stroke(DVec2::new(0., 100.), 30, true, 1., UNSMOOTHED);
+        assert_eq!(state.closed_shape_at(DVec2::new(350., 350.)), Some(0));
+        assert_eq!(state.closed_shape_at(DVec2::new(250., 350.)), None);
+
+        let coral = state.grow_as(0, GrowPreset::Coral, &params).unwrap();
+        assert_eq!(coral, GrowPreset::Coral.params(&params));
+        assert!(coral.branch_probability > 0.);
+        assert_eq!(coral.n_max, params.n_max);
+        let controller = state.simulation();
+        assert_eq!(controller.state(), RunState::Running);
+        assert_eq!(controller.simulation().unwrap().segment_count(), 1);
+
+        // a new segment of the simulation already growing
+        state.grow_as(0, GrowPreset::Maze, &coral).unwrap();
+        let simulation = state.simulation().simulation().unwrap();
+        assert_eq!(simulation.segment_count(), 2);
+
+        let meander = state.grow_as(0, GrowPreset::Meander, &coral).unwrap();
+        assert!(meander.noise_amplitude > 0.);
+        let simulation = state.simulation().simulation().unwrap();
+        assert_eq!(simulation.segment_count(), 3);
+    }
+
+    #[test]
+    fn preview_restarts_and_steps_the_simulation() {
+        let params = Params::DEFAULT;
+        let mut state = AppState::default();
+        state.begin_stroke(DVec2::new(300., 300.), 0);
+        state.continue_stroke(DVec2::new(100., 0.), 10);
+        state.end_stroke(DVec2::new(0., 100.), 20, true, 1., UNSMOOTHED);
+
+        state.preview(&params, 20).unwrap();
+        assert_eq!(state.simulation().iteration(), 20);
+        state.preview(&params.mutated(0.2, 1), 5).unwrap();
+        assert_eq!(state.simulation().iteration(), 5);
+    }
+}
@@ -0,0 +1,423 @@
+//! What the pointer does on the canvas with each tool, apart from the
+//! gestures of the window that it comes from, so that it can be driven
+//! without a display.
+
+use glam::DVec2;
+
+use super::{AppState, Tool};
+use crate::{
+    algorithm::Params, replay::Event, shape::smooth::SmoothOptions, sizes,
+    snap,
+};
+
+/// The keys held during a pointer event.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct Modifiers {
+    pub(crate) ctrl: bool,
+    pub(crate) shift: bool,
+}
+
+impl AppState {
+    /// Start a drag with the primary button at `pos` in the window, at
+    /// `time` in milliseconds. With Ctrl held it marks the region
+    /// recordings are cropped to, and otherwise it does what the tool does:
+    /// draw, starting on the grid if snapping to it, place a field, which
+    /// Shift removes instead, or select a shape. What is done is logged with
+    /// `params` and `smooth`.
+    pub(crate) fn pointer_down(
+        &mut self,
+        pos: DVec2,
+        time: u32,
+        modifiers: Modifiers,
+        params: &Params,
+        smooth: SmoothOptions,
+    ) {
+        let viewport = self.viewport();
+        let pos = viewport.to_world(pos);
+        if modifiers.ctrl {
+            self.begin_region(pos);
+            return;
+        }
+        match self.tool() {
+            Tool::Field(_) => self.begin_field_drag(pos, modifiers.shift),
+            Tool::Select => {
+                let tolerance = sizes::ERASER_TOLERANCE / viewport.zoom();
+                self.begin_shape_drag(pos, tolerance);
+            }
+            Tool::Draw => {
+                let pos = self.snap().point(pos);
+                self.snapped_segment = None;
+                self.log(Event::BeginStroke { pos, time }, params, smooth);
+                self.begin_stroke(pos, time);
+            }
+            Tool::Anchor => self.begin_stroke(pos, time),
+        }
+    }
+
+    /// Continue the drag to `offset` in the window from where it started.
+    /// While Ctrl is held, the stroke being drawn goes on from where it was
+    /// in a straight segment at an angle of 45°, which is added once Ctrl
+    /// is let go. Returns the edge drawn of the stroke, if one was, in world
+    /// coordinates.
+    pub(crate) fn pointer_drag(
+        &mut self,
+        offset: DVec2,
+        time: u32,
+        modifiers: Modifiers,
+        params: &Params,
+        smooth: SmoothOptions,
+    ) -> Option<(DVec2, DVec2)> {
+        let offset = self.viewport().to_world_vector(offset);
+        if self.is_dragging_region() {
+            self.drag_region(offset);
+        } else if self.is_dragging_field() {
+            self.drag_field(offset);
+        } else if self.is_dragging_shape() {
+            self.drag_shape(offset);
+        } else if self.tool() == Tool::Draw {
+            if modifiers.ctrl {
+                let end = self.snap_segment(offset);
+                self.snapped_segment = Some((end, time));
+                // the segment is drawn from the last vertex until it is added
+                self.damage_mut().add_all();
+                return None;
+            }
+            self.add_snapped_segment(params, smooth);
+            let event = Event::ContinueStroke { offset, time };
+            self.log(event, params, smooth);
+            return self.continue_stroke(offset, time);
+        } else if self.tool() == Tool::Anchor {
+            return self.continue_stroke(offset, time);
+        }
+        None
+    }
+
+    /// Finish the drag at `offset` in the window from where it started. A
+    /// stroke is left open unless Shift is held as it ends.
+    pub(crate) fn pointer_up(
+        &mut self,
+        offset: DVec2,
+        time: u32,
+        modifiers: Modifiers,
+        params: &Params,
+        smooth: SmoothOptions,
+    ) {
+        let viewport = self.viewport();
+        let offset = viewport.to_world_vector(offset);
+        if self.is_dragging_region() {
+            self.end_region(offset);
+        } else if self.is_dragging_field() {
+            self.end_field_drag(offset);
+        } else if self.is_dragging_shape() {
+            if let Some((index, similarity)) = self.end_shape_drag(offset) {
+                let event = Event::Transform { index, similarity };
+                self.log(event, params, smooth);
+            }
+        } else if self.tool() == Tool::Draw {
+            let offset = if modifiers.ctrl {
+                self.snap_segment(offset)
+            } else {
+                self.add_snapped_segment(params, smooth);
+                offset
+            };
+            self.snapped_segment = None;
+            let (closed, zoom) = (modifiers.shift, viewport.zoom());
+            let event = Event::EndStroke {
+                offset,
+                time,
+                closed,
+                zoom,
+            };
+            self.log(event, params, smooth);
+            self.end_stroke(offset, time, closed, zoom, smooth);
+        } else if self.tool() == Tool::Anchor {
+            let reach = sizes::ANCHOR_REACH / viewport.zoom();
+            let anchored = self.end_anchor_stroke(offset, time, reach);
+            tracing::info!("anchored {anchored} vertices to the stroke");
+        }
+    }
+
+    /// `offset` from the start of the stroke being drawn moved onto the
+    /// nearest line at an angle of 45° through its last vertex.
+    fn snap_segment(&self, offset: DVec2) -> DVec2 {
+        let last = self.current_shape().last_offset();
+        last + snap::constrain_angle(offset - last)
+    }
+
+    /// Add the segment snapped to an angle of 45° to the stroke being
+    /// drawn, if there is one.
+    fn add_snapped_segment(&mut self, params: &Params, smooth: SmoothOptions) {
+        let Some((offset, time)) = self.snapped_segment.take() else {
+            return;
+        };
+        self.log(Event::ContinueStroke { offset, time }, params, smooth);
+        self.continue_stroke(offset, time);
+        self.damage_mut().add_all();
+    }
+
+    /// Click with the secondary button at `pos` in the window, which erases
+    /// the shape there. Returns the index of the closed seed shape the
+    /// click was inside of instead, if any, for a menu of what to grow it
+    /// as.
+    pub(crate) fn secondary_click(
+        &mut self,
+        pos: DVec2,
+        params: &Params,
+        smooth: SmoothOptions,
+    ) -> Option<usize> {
+        let viewport = self.viewport();
+        let pos = viewport.to_world(pos);
+        let tolerance = sizes::ERASER_TOLERANCE / viewport.zoom();
+        self.log(Event::Erase { pos, tolerance }, params, smooth);
+        match self.erase_at(pos, tolerance) {
+            Some(_) => None,
+            // inside a closed shape rather than on it
+            None => self.closed_shape_at(pos),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        algorithm::FieldKind, export::Region, keys::Action,
+        polyline::Polyline, shape::smooth::Method, snap::SnapOptions,
+        transform::Transform,
+    };
+
+    /// A window without a display: pointer events and keys as its gestures
+    /// and actions deliver them, at a time that goes on by 10 milliseconds
+    /// with each.
+    struct Harness {
+        state: AppState,
+        params: Params,
+        smooth: SmoothOptions,
+        time: u32,
+    }
+
+    impl Harness {
+        fn new() -> Self {
+            Self {
+                state: AppState::default(),
+                params: Params::DEFAULT,
+                smooth: SmoothOptions {
+                    method: Method::Off,
+                    iterations: 0,
+                    spacing: 0.,
+                },
+                time: 0,
+            }
+        }
+
+        fn tick(&mut self) -> u32 {
+            self.time += 10;
+            self.time
+        }
+
+        /// Drag with the primary button through `points` in the window,
+        /// with `modifiers` held, ending at the last one.
+        fn drag(&mut self, points: &[DVec2], modifiers: Modifiers) {
+            let (&start, rest) = points.split_first().unwrap();
+            let through = &rest[..rest.len().saturating_sub(1)];
+            let time = self.tick();
+            let (params, smooth) = (&self.params, self.smooth);
+            self.state
+                .pointer_down(start, time, modifiers, params, smooth);
+            for &point in through {
+                let time = self.tick();
+                let (params, smooth) = (&self.params, self.smooth);
+                let offset = point - start;
+                self.state
+                    .pointer_drag(offset, time, modifiers, params, smooth);
+            }
+            let end = points.last().unwrap();
+            let time = self.tick();
+            let (params, smooth) = (&self.params, self.smooth);
+            self.state.pointer_up(
+                *end - start,
+                time,
+                modifiers,
+                params,
+                smooth,
+            );
+        }
+
+        fn right_click(&mut self, pos: DVec2) -> Option<usize> {
+            self.state.secondary_click(pos, &self.params, self.smooth)
+        }
+
+        fn key(&mut self, action: Action) {
+            if !self
+                .state
+                .nudge_selection(action, &self.params, self.smooth)
+            {
+                self.state.act(action, &self.params).unwrap();
+            }
+        }
+    }
+
+    const NONE: Modifiers = Modifiers {
+        ctrl: false,
+        shift: false,
+    };
+    const SHIFT: Modifiers = Modifiers {
+        ctrl: false,
+        shift: true,
+    };
+    const CTRL: Modifiers = Modifiers {
+        ctrl: true,
+        shift: false,
+    };
+
+    fn square(min: DVec2, size: f64) -> Vec<DVec2> {
+        [(0., 0.), (1., 0.), (1., 1.), (0., 1.)]
+            .map(|(x, y)| min + DVec2::new(x, y) * size)
+            .to_vec()
+    }
+
+    #[test]
+    fn strokes_are_drawn_closed_erased_and_undone() {
+        let mut window = Harness::new();
+        window.drag(&square(DVec2::new(100., 100.), 100.), SHIFT);
+        window.drag(&[DVec2::new(400., 100.), DVec2::new(500., 100.)], NONE);
+
+        let [closed, open] = window.state.shapes() else {
+            panic!("expected two shapes");
+        };
+        assert!(closed.closed());
+        assert_eq!(closed.n_verticies(), 4);
+        assert!(!open.closed());
+        assert!(window.state.current_shape().n_verticies() == 0);
+
+        // inside the closed one, for a menu rather than erasing it
+        assert_eq!(window.right_click(DVec2::new(150., 150.)), Some(0));
+        assert_eq!(window.state.shapes().len(), 2);
+        assert_eq!(window.right_click(DVec2::new(450., 105.)), None);
+        assert_eq!(window.state.shapes().len(), 1);
+
+        window.key(Action::Undo);
+        assert!(window.state.shapes().is_empty());
+    }
+
+    #[test]
+    fn strokes_snap_to_the_grid_and_to_angles() {
+        let mut window = Harness::new();
+        window.state.set_snap(SnapOptions {
+            to_grid: true,
+            ..SnapOptions::DEFAULT
+        });
+        let (params, smooth) = (Params::DEFAULT, window.smooth);
+        let state = &mut window.state;
+
+        // Ctrl pressed once the stroke has started
+        state.pointer_down(DVec2::new(103., 98.), 0, NONE, &params, smooth);
+        state.pointer_drag(DVec2::new(50., 0.), 10, NONE, &params, smooth);
+        assert_eq!(state.snapped_segment(), None);
+        state.pointer_drag(DVec2::new(90., 43.), 20, CTRL, &params, smooth);
+        let diagonal =
+            state.snapped_segment().unwrap() - DVec2::new(150., 100.);
+        assert!((diagonal.x - diagonal.y).abs() < 1e-9);
+        state.pointer_drag(DVec2::new(150., 5.), 30, CTRL, &params, smooth);
+        state.pointer_up(DVec2::new(150., 5.), 40, NONE, &params, smooth);
+
+        // one straight segment from where Ctrl was pressed
+        let points = state.shapes()[0].points().collect::<Vec<_>>();
+        assert_eq!(
+            points,
+            [
+                DVec2::new(100., 100.),
+                DVec2::new(150., 100.),
+                DVec2::new(250., 100.),
+                DVec2::new(250., 105.),
+            ]
+        );
+    }
+
+    #[test]
+    fn drags_follow_the_tool_and_the_view() {
+        let mut window = Harness::new();
+        window.state.viewport_mut().zoom_at(DVec2::ZERO, 2.);
+
+        // a region with Ctrl held whatever the tool, in world coordinates
+        let ctrl = Modifiers {
+            ctrl: true,
+            shift: false,
+        };
+        window.drag(&[DVec2::new(20., 20.), DVec2::new(220., 120.)], ctrl);
+        assert_eq!(
+            window.state.region(),
+            Some(Region::spanning(
+                DVec2::new(10., 10.),
+                DVec2::new(110., 60.)
+            ))
+        );
+        assert!(window.state.shapes().is_empty());
+
+        window.state.set_tool(Tool::Field(FieldKind::Attractor));
+        window.drag(&[DVec2::new(200., 200.)], NONE);
+        assert_eq!(window.state.simulation().fields().len(), 1);
+        window.drag(&[DVec2::new(200., 200.)], SHIFT);
+        assert!(window.state.simulation().fields().is_empty());
+        assert!(window.state.shapes().is_empty());
+    }
+
+    #[test]
+    fn anchor_strokes_hold_the_growth_on_them() {
+        let mut window = Harness::new();
+        let circle = (0..16)
+            .map(|i| {
+                let angle = i as f64 / 16. * std::f64::consts::TAU;
+                DVec2::new(300., 300.) + DVec2::from_angle(angle) * 100.
+            })
+            .collect::<Vec<_>>();
+        window.state.simulation_mut().set_seed(Some(1));
+        window.drag(&circle, SHIFT);
+        window.key(Action::ResetSim);
+
+        // through the right of the circle, which is not kept as a shape
+        window.state.set_tool(Tool::Anchor);
+        window.drag(&[DVec2::new(400., 200.), DVec2::new(400., 400.)], NONE);
+        assert_eq!(window.state.shapes().len(), 1);
+        for _ in 0..50 {
+            window.key(Action::StepSim);
+        }
+
+        let x = Transform::DEFAULT.document_to_unit(DVec2::splat(400.)).x;
+        let simulation = window.state.simulation().simulation().unwrap();
+        let on_stroke = simulation
+            .positions()
+            .iter()
+            .filter(|p| (p.x - x).abs() < 1e-12)
+            .count();
+        // the three anchored, and any spawned in between them last step
+        assert!(on_stroke >= 3, "{on_stroke} on the stroke");
+    }
+
+    #[test]
+    fn selected_shapes_are_moved_and_nudged() {
+        let mut window = Harness::new();
+        window.drag(&square(DVec2::new(100., 100.), 100.), SHIFT);
+        window.state.set_tool(Tool::Select);
+
+        // nothing is selected to nudge, which the keys then leave alone
+        window.key(Action::GrowSelection);
+        window.drag(&[DVec2::new(150., 150.), DVec2::new(250., 150.)], NONE);
+        assert_eq!(window.state.selected(), Some(0));
+        let moved = window.state.shapes()[0].points().collect::<Vec<_>>();
+        assert_eq!(moved, square(DVec2::new(200., 100.), 100.));
+
+        window.key(Action::GrowSelection);
+        window.key(Action::ShrinkSelection);
+        window.key(Action::RotateSelectionLeft);
+        window.key(Action::RotateSelectionRight);
+        let nudged = window.state.shapes()[0].points();
+        for (a, b) in nudged.zip(&moved) {
+            assert!(a.distance(*b) < 1e-9, "{a} is back at {b}");
+        }
+
+        // drawing again deselects it
+        window.state.set_tool(Tool::Draw);
+        assert_eq!(window.state.selected(), None);
+    }
+}
@@ -0,0 +1,253 @@
+//! Autosaves: the drawing of each window and the geometry of its growth,
+//! written to the cache directory every so often, so that a session that
+//! did not end cleanly can be recovered the next time the app starts.
+//!
+//! Each window saves to `autosave/N.project`, and the growth to
+//! `autosave/N.growth` as a snapshot file of one snapshot, `N` counting the
+//! windows of the session from 1. `autosave/running` is there from the
+//! start of a session until it ends cleanly, so that if it is there at the
+//! start, the autosaves of the last session are offered for recovery.
+
+use std::path::{Path, PathBuf};
+#[cfg(feature = "gui")]
+use std::{
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+
+#[cfg(feature = "gui")]
+use super::cache_dir;
+use super::{
+    algorithm::{GeometrySnapshot, Params},
+    app_state::AppState,
+    project::Project,
+    snapshots::{SnapshotReader, SnapshotWriter},
+};
+
+/// How often the drawing of each window is saved.
+#[cfg(feature = "gui")]
+pub(crate) const INTERVAL: Duration = Duration::from_secs(60);
+
+/// Slot of the next window to autosave.
+#[cfg(feature = "gui")]
+static NEXT_SLOT: AtomicUsize = AtomicUsize::new(1);
+
+/// The drawing of a window of a session that did not end cleanly, and its
+/// growth, as last autosaved.
+pub(crate) struct Recovered {
+    pub(crate) project: Project,
+    pub(crate) growth: Option<GeometrySnapshot>,
+}
+
+impl Recovered {
+    /// Replace the drawing of `state` with the recovered one, and its
+    /// simulation with the recovered growth, paused, with `params`.
+    pub(crate) fn restore(
+        self,
+        state: &mut AppState,
+        params: &Params,
+    ) -> Result<()> {
+        state.open(self.project);
+        if let Some(growth) = self.growth {
+            state.simulation_mut().restore(
+                &growth.paths,
+                growth.iteration,
+                params,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "gui")]
+fn dir() -> Result<PathBuf> {
+    Ok(cache_dir().context("no cache directory")?.join("autosave"))
+}
+
+/// Start the autosaves of a session, returning those of the last session
+/// if it did not end cleanly, in the order of its windows. The autosaves
+/// of the last session are removed either way.
+#[cfg(feature = "gui")]
+pub(crate) fn start() -> Result<Vec<Recovered>> {
+    start_in(&dir()?)
+}
+
+/// Slot to autosave a new window to, counting from 1.
+#[cfg(feature = "gui")]
+pub(crate) fn next_slot() -> usize {
+    NEXT_SLOT.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Autosave the drawing and growth of `state` to `slot`.
+#[cfg(feature = "gui")]
+pub(crate) fn save(slot: usize, state: &AppState) -> Result<()> {
+    save_in(&dir()?, slot, state)
+}
+
+/// Remove the autosave of `slot`, whose window was closed.
+#[cfg(feature = "gui")]
+pub(crate) fn discard(slot: usize) -> Result<()> {
+    discard_in(&dir()?, slot)
+}
+
+/// End the session cleanly, removing every autosave.
+#[cfg(feature = "gui")]
+pub(crate) fn finish() -> Result<()> {
+    finish_in(&dir()?)
+}
+
+fn start_in(dir: &Path) -> Result<Vec<Recovered>> {
+    let crashed = dir.join("running").exists();
+    let mut recovered = Vec::new();
+    if crashed {
+        let mut slots = std::fs::read_dir(dir)
+            .with_context(|| format!("failed to read {}", dir.display()))?
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                let slot = path.file_stem()?.to_str()?.parse().ok()?;
+                (path.extension()? == "project").then_some(slot)
+            })
+            .collect::<Vec<usize>>();
+        slots.sort_unstable();
+        for slot in slots {
+            match load(dir, slot) {
+                Ok(saved) => recovered.push(saved),
+                // a save cut short by the crash is lost, not the others
+                Err(err) => tracing::warn!("{err:#}"),
+            }
+        }
+    }
+
+    finish_in(dir)?;
+    std::fs::create_dir_all(dir)
+        .with_context(|| format!("failed to create {}", dir.display()))?;
+    let running = dir.join("running");
+    std::fs::write(&running, std::process::id().to_string())
+        .with_context(|| format!("failed to write {}", running.display()))?;
+    Ok(recovered)
+}
+
+fn finish_in(dir: &Path) -> Result<()> {
+    if dir.exists() {
+        std::fs::remove_dir_all(dir)
+            .with_context(|| format!("failed to remove {}", dir.display()))?;
+    }
+    Ok(())
+}
+
+fn paths(dir: &Path, slot: usize) -> [PathBuf; 2] {
+    ["project", "growth"].map(|ext| dir.join(format!("{slot}.{ext}")))
+}
+
+fn load(dir: &Path, slot: usize) -> Result<Recovered> {
+    let [project, growth] = paths(dir, slot);
+    let project = Project::load(&project)?;
+    let growth = if growth.exists() {
+        SnapshotReader::open(&growth)?.next().transpose()?
+    } else {
+        None
+    };
+    Ok(Recovered { project, growth })
+}
+
+fn save_in(dir: &Path, slot: usize, state: &AppState) -> Result<()> {
+    let [project, growth] = paths(dir, slot);
+    let simulation = state.simulation();
+    match simulation.simulation() {
+        Some(grown) => {
+            // written aside first, for a crash while writing to leave the
+            // last save as it was
+            let partial = growth.with_extension("growth.partial");
+            let mut writer = SnapshotWriter::create(&partial)?;
+            writer.write(&GeometrySnapshot {
+                iteration: simulation.iteration(),
+                paths: grown.paths(),
+            })?;
+            writer.finish()?;
+            rename(&partial, &growth)?;
+        }
+        None => remove_if_exists(&growth)?,
+    }
+    let partial = project.with_extension("project.partial");
+    state.project().save(&partial)?;
+    rename(&partial, &project)
+}
+
+fn rename(from: &Path, to: &Path) -> Result<()> {
+    std::fs::rename(from, to)
+        .with_context(|| format!("failed to write {}", to.display()))
+}
+
+fn remove_if_exists(path: &Path) -> Result<()> {
+    if path.exists() {
+        std::fs::remove_file(path)
+            .with_context(|| format!("failed to remove {}", path.display()))?;
+    }
+    Ok(())
+}
+
+fn discard_in(dir: &Path, slot: usize) -> Result<()> {
+    for path in paths(dir, slot) {
+        remove_if_exists(&path)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::DVec2;
+
+    use super::*;
+    use crate::shape::smooth::SmoothOptions;
+
+    #[test]
+    fn autosaves_are_recovered_after_a_crash_only() {
+        let dir = std::env::temp_dir().join("dxdy-draw-autosave-test");
+        _ = std::fs::remove_dir_all(&dir);
+        let params = Params::DEFAULT;
+        let smooth = SmoothOptions {
+            iterations: 0,
+            ..SmoothOptions::DEFAULT
+        };
+
+        let mut state = AppState::default();
+        state.simulation_mut().set_seed(Some(5));
+        state.begin_stroke(DVec2::new(400., 400.), 0);
+        state.continue_stroke(DVec2::new(60., 0.), 10);
+        state.end_stroke(DVec2::new(60., 60.), 20, true, 1., smooth);
+        let shapes = state.shapes().to_vec();
+        let controller = state.simulation_mut();
+        controller.reset(&shapes, &params).unwrap();
+        for _ in 0..10 {
+            controller.step_once(&params).unwrap();
+        }
+        let grown = state.simulation().simulation().unwrap().paths();
+
+        assert!(start_in(&dir).unwrap().is_empty());
+        save_in(&dir, 1, &state).unwrap();
+        save_in(&dir, 2, &AppState::default()).unwrap();
+        save_in(&dir, 3, &state).unwrap();
+        discard_in(&dir, 3).unwrap();
+
+        // the session crashed, so the next one recovers it
+        let mut recovered = start_in(&dir).unwrap();
+        assert_eq!(recovered.len(), 2);
+        assert!(recovered[1].growth.is_none());
+        let mut again = AppState::default();
+        recovered.remove(0).restore(&mut again, &params).unwrap();
+        assert_eq!(again.shapes().len(), 1);
+        assert_eq!(again.simulation().seed(), Some(5));
+        assert_eq!(again.simulation().iteration(), 10);
+        let restored = again.simulation().simulation().unwrap().paths();
+        assert_eq!(restored.len(), grown.len());
+        assert_eq!(restored[0].points, grown[0].points);
+
+        // and once it ends cleanly, nothing is left to recover
+        save_in(&dir, 1, &state).unwrap();
+        finish_in(&dir).unwrap();
+        assert!(start_in(&dir).unwrap().is_empty());
+        finish_in(&dir).unwrap();
+    }
+}
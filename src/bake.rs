@@ -0,0 +1,126 @@
+//! Baking the growth of a simulation into the drawing, as shapes that can
+//! be exported, tagged and layered by where in the growth they came from,
+//! so that each part can be drawn with a pen of its own.
+
+use super::{
+    algorithm::Simulation,
+    polyline::Polyline,
+    shape::{Pen, Shape},
+    transform::Transform,
+};
+
+/// Which shapes baked from the growth share a layer.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum BakeLayers {
+    /// Every segment of the growth, a seed or a branch, in a layer of its
+    /// own.
+    Segment,
+    /// The seeds in one layer, the branches sprouted from them in another,
+    /// their branches in a third, and so on.
+    #[default]
+    Generation,
+}
+
+/// A shape for every path of the growth of `simulation`, in document
+/// coordinates, in layers by `layers`. Every shape is tagged with the
+/// segment it was baked from and its generation, as `segment-3` and
+/// `generation-1`. The shapes are in the order the segments were added.
+/// Obstacles are left out, as they are drawn already.
+pub(crate) fn bake(simulation: &Simulation, layers: BakeLayers) -> Vec<Shape> {
+    let mut paths = simulation.paths();
+    // stable, so the paths of a segment keep their order
+    paths.sort_by_key(|path| path.segment);
+    paths
+        .into_iter()
+        .filter(|path| !path.passive)
+        .filter_map(|path| {
+            let points = path
+                .points()
+                .map(|pos| Transform::DEFAULT.unit_to_document(pos))
+                .collect::<Vec<_>>();
+            let &start = points.first()?;
+            let mut shape = Shape::starting_at(start);
+            for pos in points {
+                shape.next_vertex_at(pos - start, 0, Pen::DEFAULT);
+            }
+            shape.set_closed(path.is_closed());
+
+            let generation = simulation.segment_generation(path.segment);
+            let segment = format!("segment-{}", path.segment);
+            let generation = format!("generation-{generation}");
+            shape.set_layer(Some(match layers {
+                BakeLayers::Segment => segment.clone(),
+                BakeLayers::Generation => generation.clone(),
+            }));
+            shape.add_tag(segment);
+            shape.add_tag(generation);
+            Some(shape)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::DVec2;
+
+    use super::*;
+    use crate::{
+        algorithm::{Params, Primitive, PrimitiveKind},
+        project::Project,
+    };
+
+    #[test]
+    fn baked_growth_is_layered_by_generation() {
+        let params = Params {
+            branch_probability: 0.5,
+            ..Params::DEFAULT
+        };
+        let mut simulation = Simulation::new(&params, 1).unwrap();
+        simulation
+            .add_primitive(&Primitive {
+                kind: PrimitiveKind::Circle,
+                center: DVec2::splat(0.5),
+                radius: 0.05,
+                rotation: 0.,
+                n_vertices: 32,
+                passive: false,
+            })
+            .unwrap();
+        simulation
+            .add_obstacle(&[DVec2::splat(0.2), DVec2::splat(0.3)], false)
+            .unwrap();
+        for _ in 0..30 {
+            simulation.step(&params).unwrap();
+        }
+
+        let shapes = bake(&simulation, BakeLayers::Generation);
+        let growing = simulation.paths().iter().filter(|p| !p.passive).count();
+        assert_eq!(shapes.len(), growing);
+        assert!(shapes.len() > 1);
+        assert!(shapes[0].is_closed());
+        assert_eq!(shapes[0].tags(), ["segment-0", "generation-0"]);
+        assert_eq!(shapes[0].layer(), Some("generation-0"));
+        assert_eq!(shapes[1].layer(), Some("generation-1"));
+        let circle = simulation.paths().into_iter().find(|p| p.segment == 0);
+        let first = circle.unwrap().points().next().unwrap();
+        assert!(
+            shapes[0]
+                .start()
+                .abs_diff_eq(Transform::DEFAULT.unit_to_document(first), 1e-9)
+        );
+
+        let shapes = bake(&simulation, BakeLayers::Segment);
+        assert_eq!(shapes[1].tags()[0], "segment-2");
+        assert_eq!(shapes[1].layer(), Some("segment-2"));
+
+        // the tags and layers are saved with the shapes
+        let project = Project {
+            shapes,
+            ..Project::default()
+        };
+        let opened = project.to_string().parse::<Project>().unwrap();
+        assert_eq!(opened.to_string(), project.to_string());
+        assert_eq!(opened.shapes[1].tags(), project.shapes[1].tags());
+        assert_eq!(opened.shapes[1].layer(), Some("segment-2"));
+    }
+}
@@ -0,0 +1,262 @@
+//! Candidates: short simulations from the same seeds, each with parameters
+//! of its own, grown side by side on a few threads with a small image of
+//! each kept up to date, to pick the one to grow on from.
+
+use std::{
+    collections::VecDeque,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread::JoinHandle,
+};
+
+use super::{
+    algorithm::{Params, Simulation},
+    export::{self, DOCUMENT_DPI, Image},
+    scene::Scene,
+    shape::Shape,
+    style::Style,
+};
+
+/// Width of the thumbnails in pixels.
+pub(crate) const THUMBNAIL_WIDTH: f64 = 160.;
+
+/// Steps a candidate takes in a turn, after which its thumbnail is drawn
+/// again and the others take their turns.
+const STEPS_PER_TURN: u64 = 25;
+
+/// A candidate as last drawn.
+#[derive(Clone, Debug)]
+pub(crate) struct Thumbnail {
+    pub(crate) image: Image,
+    pub(crate) iteration: u64,
+}
+
+/// The simulation of a candidate and how far it has grown.
+struct Growth {
+    simulation: Simulation,
+    iteration: u64,
+    done: bool,
+}
+
+struct Candidate {
+    params: Params,
+    /// `None` once the candidate is promoted.
+    growth: Mutex<Option<Growth>>,
+    thumbnail: Mutex<Option<Thumbnail>>,
+}
+
+struct Shared {
+    candidates: Vec<Candidate>,
+    /// Candidates waiting for a turn, in turn order.
+    queue: Mutex<VecDeque<usize>>,
+    style: Style,
+    shapes: Vec<Shape>,
+    steps: u64,
+    stopped: AtomicBool,
+}
+
+/// Candidates growing in the background until each has taken its steps or
+/// can not grow on. They stop when dropped.
+pub(crate) struct Candidates {
+    shared: Arc<Shared>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl Candidates {
+    /// Grow each of `candidates` for up to `steps` steps, on as many
+    /// threads as there are processors or candidates, whichever is fewer.
+    /// Their thumbnails show them with `shapes` in `style`.
+    pub(crate) fn start(
+        style: Style,
+        shapes: Vec<Shape>,
+        candidates: Vec<(Params, Simulation)>,
+        steps: u64,
+    ) -> Self {
+        let n_workers = std::thread::available_parallelism()
+            .map_or(1, |n| n.get())
+            .min(candidates.len());
+        let shared = Arc::new(Shared {
+            queue: Mutex::new((0..candidates.len()).collect()),
+            candidates: candidates
+                .into_iter()
+                .map(|(params, simulation)| Candidate {
+                    params,
+                    growth: Mutex::new(Some(Growth {
+                        simulation,
+                        iteration: 0,
+                        done: steps == 0,
+                    })),
+                    thumbnail: Mutex::new(None),
+                })
+                .collect(),
+            style,
+            shapes,
+            steps,
+            stopped: AtomicBool::new(false),
+        });
+
+        let workers = (0..n_workers)
+            .map(|_| {
+                let shared = shared.clone();
+                std::thread::spawn(move || shared.work())
+            })
+            .collect();
+        Self { shared, workers }
+    }
+
+    /// The candidate at `index` as last drawn, if it has been yet.
+    pub(crate) fn thumbnail(&self, index: usize) -> Option<Thumbnail> {
+        self.shared.candidates[index]
+            .thumbnail
+            .lock()
+            .unwrap()
+            .clone()
+    }
+
+    /// Whether every candidate is done growing.
+    pub(crate) fn is_done(&self) -> bool {
+        self.workers.iter().all(|worker| worker.is_finished())
+    }
+
+    /// Stop growing the candidates, and take the one at `index` with its
+    /// parameters and the iteration it has grown to.
+    pub(crate) fn promote(
+        mut self,
+        index: usize,
+    ) -> Option<(Params, Simulation, u64)> {
+        self.stop();
+        let candidate = &self.shared.candidates[index];
+        let growth = candidate.growth.lock().unwrap().take()?;
+        Some((candidate.params, growth.simulation, growth.iteration))
+    }
+
+    fn stop(&mut self) {
+        self.shared.stopped.store(true, Ordering::Relaxed);
+        for worker in self.workers.drain(..) {
+            _ = worker.join();
+        }
+    }
+}
+
+impl Drop for Candidates {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+impl Shared {
+    /// Give candidates their turns until none is waiting for one. Once the
+    /// queue is empty, the candidates still growing are left to the workers
+    /// taking their turns.
+    fn work(&self) {
+        while !self.stopped.load(Ordering::Relaxed) {
+            let Some(index) = self.queue.lock().unwrap().pop_front() else {
+                return;
+            };
+            if self.turn(index) {
+                self.queue.lock().unwrap().push_back(index);
+            }
+        }
+    }
+
+    /// Grow the candidate at `index` for a turn and draw it, returning
+    /// whether it has more to grow.
+    fn turn(&self, index: usize) -> bool {
+        let candidate = &self.candidates[index];
+        let mut growth = candidate.growth.lock().unwrap();
+        let Some(growth) = growth.as_mut() else {
+            return false;
+        };
+
+        let end = (growth.iteration + STEPS_PER_TURN).min(self.steps);
+        while !growth.done && growth.iteration < end {
+            match growth.simulation.step(&candidate.params) {
+                Ok(true) => growth.iteration += 1,
+                Ok(false) => growth.done = true,
+                Err(err) => {
+                    tracing::warn!("candidate {index} stopped: {err}");
+                    growth.done = true;
+                }
+            }
+        }
+        growth.done |= growth.iteration >= self.steps;
+
+        let scene =
+            Scene::frame(&self.style, &self.shapes, Some(&growth.simulation));
+        let dpi = DOCUMENT_DPI * THUMBNAIL_WIDTH / scene.size.x;
+        match export::rasterize(&scene, dpi) {
+            Ok(image) => {
+                *candidate.thumbnail.lock().unwrap() = Some(Thumbnail {
+                    image,
+                    iteration: growth.iteration,
+                });
+            }
+            Err(err) => {
+                tracing::warn!("failed to draw candidate {index}: {err:#}")
+            }
+        }
+        !growth.done
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use glam::DVec2;
+
+    use super::*;
+    use crate::algorithm::{Primitive, PrimitiveKind};
+
+    fn seeded(params: &Params) -> Simulation {
+        let mut simulation = Simulation::new(params, 1).unwrap();
+        simulation
+            .add_primitive(&Primitive {
+                kind: PrimitiveKind::Circle,
+                center: DVec2::splat(0.5),
+                radius: 0.1,
+                rotation: 0.,
+                n_vertices: 32,
+                passive: false,
+            })
+            .unwrap();
+        simulation
+    }
+
+    #[test]
+    fn candidates_grow_side_by_side_until_promoted() {
+        let params = (0..3)
+            .map(|seed| Params::DEFAULT.mutated(0.5, seed))
+            .collect::<Vec<_>>();
+        let candidates = Candidates::start(
+            Style::default(),
+            Vec::new(),
+            params.iter().map(|p| (*p, seeded(p))).collect(),
+            60,
+        );
+
+        let start = Instant::now();
+        while !candidates.is_done() {
+            assert!(start.elapsed() < Duration::from_secs(10), "hangs");
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        for i in 0..3 {
+            let thumbnail = candidates.thumbnail(i).unwrap();
+            assert_eq!(thumbnail.iteration, 60);
+            assert_eq!(thumbnail.image.width, THUMBNAIL_WIDTH as u32);
+            assert_eq!(thumbnail.image.height, 120);
+        }
+
+        // taking turns grows each like growing it on its own
+        let mut alone = seeded(&params[1]);
+        for _ in 0..60 {
+            alone.step(&params[1]).unwrap();
+        }
+        let (promoted, simulation, iteration) = candidates.promote(1).unwrap();
+        assert_eq!(promoted, params[1]);
+        assert_eq!(iteration, 60);
+        assert_eq!(simulation.paths(), alone.paths());
+    }
+}
@@ -0,0 +1,226 @@
+use std::{cell::RefCell, rc::Rc};
+
+use gtk::{glib, prelude::*};
+
+use super::{
+    app_state::SharedState,
+    candidates::{Candidates, THUMBNAIL_WIDTH, Thumbnail},
+    params_panel::PARAMS,
+    render::texture,
+    seed_menu::spin,
+    style::STYLE,
+    transform::DOCUMENT_SIZE,
+    window::show_simulation_error,
+};
+
+/// How often the thumbnails are drawn again.
+const POLL_INTERVAL: std::time::Duration =
+    std::time::Duration::from_millis(200);
+
+/// Thumbnails in a row of the panel.
+const COLUMNS: usize = 3;
+
+/// The thumbnail of a candidate in the panel.
+struct Slot {
+    picture: gtk::Picture,
+    label: gtk::Label,
+    /// The iteration of the thumbnail shown, if any is yet.
+    shown: Option<u64>,
+}
+
+impl Slot {
+    fn update(&mut self, thumbnail: Option<Thumbnail>) {
+        let Some(Thumbnail { image, iteration }) = thumbnail else {
+            return;
+        };
+        if self.shown == Some(iteration) {
+            return;
+        }
+        self.picture.set_paintable(Some(&texture(image)));
+        self.label.set_label(&format!("{iteration} iterations"));
+        self.shown = Some(iteration);
+    }
+}
+
+/// Button with a popover that grows candidates from the seeds of `state`,
+/// each with the current parameters changed at random by up to the amount,
+/// side by side in the background, showing a thumbnail of each as it grows.
+/// Clicking a thumbnail stops the others and grows that candidate on as
+/// the simulation of `state`, with its parameters.
+///
+/// The candidates share the seed of the simulation, or a random one if it
+/// has none, so that they differ by their parameters alone.
+pub(crate) fn build(state: &SharedState) -> gtk::MenuButton {
+    let content = gtk::Box::new(gtk::Orientation::Vertical, 8);
+
+    let grid = gtk::Grid::builder()
+        .row_spacing(4)
+        .column_spacing(8)
+        .build();
+    let count = spin(&grid, 0, "Candidates", (2., 12., 1.), 6.);
+    let amount = spin(&grid, 1, "Amount (%)", (1., 100., 1.), 20.);
+    let steps = spin(&grid, 2, "Steps", (50., 10_000., 50.), 1000.);
+    let grow = gtk::Button::with_label("Grow candidates");
+    grid.attach(&grow, 0, 3, 2, 1);
+    content.append(&grid);
+
+    let thumbnails = gtk::Grid::builder()
+        .row_spacing(4)
+        .column_spacing(4)
+        .build();
+    content.append(&thumbnails);
+
+    let popover = gtk::Popover::builder().child(&content).build();
+    let button = gtk::MenuButton::builder()
+        .label("Candidates")
+        .popover(&popover)
+        .build();
+
+    let candidates = Rc::new(RefCell::new(None::<Candidates>));
+    let slots = Rc::new(RefCell::new(Vec::<Slot>::new()));
+
+    grow.connect_clicked(glib::clone!(
+        #[strong]
+        state,
+        #[strong]
+        candidates,
+        #[strong]
+        slots,
+        #[weak]
+        thumbnails,
+        #[weak]
+        popover,
+        move |grow| {
+            // the candidates grown before stop first
+            candidates.take();
+
+            let app_state = state.borrow();
+            let controller = app_state.simulation();
+            let seed = controller.seed().unwrap_or_else(rand::random);
+            let params = *PARAMS.read().unwrap();
+            let mutation: u64 = rand::random();
+            let grown = (0..count.value_as_int() as u64)
+                .map(|i| {
+                    let params = params.mutated(
+                        amount.value() / 100.,
+                        mutation.wrapping_add(i),
+                    );
+                    let simulation = controller.seeded(
+                        app_state.shapes(),
+                        &params,
+                        seed,
+                    )?;
+                    Ok((params, simulation))
+                })
+                .collect::<Result<Vec<_>, _>>();
+            let grown = match grown {
+                Ok(grown) => grown,
+                Err(err) => {
+                    let window = grow.root().and_downcast::<gtk::Window>();
+                    show_simulation_error(window.as_ref(), err);
+                    return;
+                }
+            };
+
+            while let Some(child) = thumbnails.first_child() {
+                thumbnails.remove(&child);
+            }
+            let mut shown = Vec::new();
+            for i in 0..grown.len() {
+                let picture = gtk::Picture::builder()
+                    .width_request(THUMBNAIL_WIDTH as i32)
+                    .height_request(
+                        (THUMBNAIL_WIDTH * DOCUMENT_SIZE.y / DOCUMENT_SIZE.x)
+                            as i32,
+                    )
+                    .build();
+                let label = gtk::Label::new(Some("Waiting"));
+                let content = gtk::Box::new(gtk::Orientation::Vertical, 2);
+                content.append(&picture);
+                content.append(&label);
+                let promote = gtk::Button::builder().child(&content).build();
+                promote.connect_clicked(glib::clone!(
+                    #[strong]
+                    state,
+                    #[strong]
+                    candidates,
+                    #[strong]
+                    slots,
+                    #[weak]
+                    thumbnails,
+                    #[weak]
+                    popover,
+                    move |_| {
+                        let promoted = candidates
+                            .take()
+                            .and_then(|candidates| candidates.promote(i));
+                        let Some((params, simulation, iteration)) = promoted
+                        else {
+                            return;
+                        };
+                        *PARAMS.write().unwrap() = params;
+                        state
+                            .borrow_mut()
+                            .simulation_mut()
+                            .run_from(simulation, iteration);
+
+                        slots.borrow_mut().clear();
+                        while let Some(child) = thumbnails.first_child() {
+                            thumbnails.remove(&child);
+                        }
+                        popover.popdown();
+                    }
+                ));
+                thumbnails.attach(
+                    &promote,
+                    (i % COLUMNS) as i32,
+                    (i / COLUMNS) as i32,
+                    1,
+                    1,
+                );
+                shown.push(Slot {
+                    picture,
+                    label,
+                    shown: None,
+                });
+            }
+            *slots.borrow_mut() = shown;
+
+            *candidates.borrow_mut() = Some(Candidates::start(
+                STYLE.read().unwrap().clone(),
+                app_state.shapes().to_vec(),
+                grown,
+                steps.value_as_int() as u64,
+            ));
+        }
+    ));
+
+    glib::timeout_add_local(
+        POLL_INTERVAL,
+        glib::clone!(
+            #[weak]
+            button,
+            #[upgrade_or]
+            glib::ControlFlow::Break,
+            move || {
+                let candidates = candidates.borrow();
+                if let Some(candidates) = &*candidates {
+                    let mut slots = slots.borrow_mut();
+                    for (i, slot) in slots.iter_mut().enumerate() {
+                        slot.update(candidates.thumbnail(i));
+                    }
+                }
+                let growing =
+                    candidates.as_ref().is_some_and(|c| !c.is_done());
+                button.set_label(if growing {
+                    "Candidates (growing)"
+                } else {
+                    "Candidates"
+                });
+                glib::ControlFlow::Continue
+            }
+        ),
+    );
+
+    button
+}
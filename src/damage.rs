@@ -0,0 +1,87 @@
+//! Tracking the parts of the window that changed since it was last drawn.
+
+use glam::DVec2;
+
+/// Rectangles past which damage is merged into their bounds, as clipping to
+/// many small rectangles costs more than drawing a little more.
+const MAX_RECTS: usize = 16;
+
+/// A rectangle in screen coordinates.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Rect {
+    pub(crate) min: DVec2,
+    pub(crate) max: DVec2,
+}
+
+impl Rect {
+    /// The bounds of `points` grown by `margin` on every side, or `None` if
+    /// there are no points.
+    #[cfg(feature = "gui")]
+    pub(crate) fn around(
+        points: impl IntoIterator<Item = DVec2>,
+        margin: f64,
+    ) -> Option<Self> {
+        let mut points = points.into_iter();
+        let first = points.next()?;
+        let (min, max) = points
+            .fold((first, first), |(min, max), p| (min.min(p), max.max(p)));
+        Some(Self {
+            min: min - margin,
+            max: max + margin,
+        })
+    }
+
+    pub(crate) fn union(self, other: Self) -> Self {
+        Self {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+
+    #[cfg(feature = "gui")]
+    pub(crate) fn size(self) -> DVec2 {
+        self.max - self.min
+    }
+}
+
+/// The parts of the window that need to be drawn again.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) enum Damage {
+    #[default]
+    None,
+    Rects(Vec<Rect>),
+    All,
+}
+
+impl Damage {
+    fn add(&mut self, rect: Rect) {
+        match self {
+            Self::None => *self = Self::Rects(vec![rect]),
+            Self::Rects(rects) => {
+                rects.push(rect);
+                if rects.len() > MAX_RECTS {
+                    let bounds = rects.iter().copied().reduce(Rect::union);
+                    *rects = bounds.into_iter().collect();
+                }
+            }
+            Self::All => {}
+        }
+    }
+
+    pub(crate) fn add_all(&mut self) {
+        *self = Self::All;
+    }
+
+    #[cfg(feature = "gui")]
+    pub(crate) fn is_empty(&self) -> bool {
+        *self == Self::None
+    }
+}
+
+impl Extend<Rect> for Damage {
+    fn extend<T: IntoIterator<Item = Rect>>(&mut self, rects: T) {
+        for rect in rects {
+            self.add(rect);
+        }
+    }
+}
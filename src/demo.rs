@@ -0,0 +1,203 @@
+use std::{f64::consts::FRAC_PI_2, path::Path};
+
+use anyhow::Result;
+use glam::DVec2;
+use rand::{Rng, SeedableRng, rngs::SmallRng};
+
+use super::{
+    algorithm::{
+        BoundaryMode, ONE, Params, Primitive, PrimitiveKind, SegmentsError,
+        Simulation,
+    },
+    export::Crop,
+    preset::Preset,
+    recorder::Steering,
+    simulation::{SimulationController, StopConditions},
+    style::STYLE,
+};
+
+/// Seed of demos unless one is given with `--seed`, so that they grow the
+/// same way every time.
+pub(crate) const DEFAULT_SEED: u64 = 1;
+
+/// Built-in scenes that set up a simulation through the algorithm API, as
+/// examples of it and to check by eye that growth still looks right.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub(crate) enum Demo {
+    /// A small circle growing in the middle of the canvas.
+    Circle,
+    /// A circle growing out of nested square walls with gaps.
+    Maze,
+    /// Many small circles placed by the darkness of a simple drawn face.
+    Portrait,
+}
+
+/// Points along the polyline through `points`, no more than `spacing`
+/// apart, so that the line can not slip between them.
+fn along(points: &[DVec2], spacing: f64) -> Vec<DVec2> {
+    let mut out = Vec::new();
+    for ab in points.windows(2) {
+        let (a, b) = (ab[0], ab[1]);
+        let n = (a.distance(b) / spacing).ceil().max(1.) as usize;
+        out.extend((0..n).map(|i| a.lerp(b, i as f64 / n as f64)));
+    }
+    out.extend(points.last());
+    out
+}
+
+/// The outline of a square around `center` with sides `2 * half` long and a
+/// gap `2 * gap` wide in the middle of the side `quarter_turns` quarter turns
+/// clockwise from the top.
+fn square_with_gap(
+    center: DVec2,
+    half: f64,
+    gap: f64,
+    quarter_turns: u32,
+) -> Vec<DVec2> {
+    let turn = DVec2::from_angle(quarter_turns as f64 * FRAC_PI_2);
+    [
+        DVec2::new(gap, -half),
+        DVec2::new(half, -half),
+        DVec2::new(half, half),
+        DVec2::new(-half, half),
+        DVec2::new(-half, -half),
+        DVec2::new(-gap, -half),
+    ]
+    .map(|p| center + turn.rotate(p))
+    .to_vec()
+}
+
+/// Darkness from `0.` to `1.` of a face drawn with an outline, two eyes, and
+/// a smile.
+fn face(p: DVec2) -> f64 {
+    let center = DVec2::splat(0.5);
+    let d = p - center;
+
+    // how close `dist` is to `target`, fading out over `width`
+    let near = |dist: f64, target: f64, width: f64| {
+        (1. - (dist - target).abs() / width).max(0.)
+    };
+    let eye = |x: f64| near(p.distance(DVec2::new(x, 0.42)), 0., 0.05);
+
+    let outline = near((d / DVec2::new(0.28, 0.36)).length(), 1., 0.1);
+    let eyes = eye(0.4).max(eye(0.6));
+    let smile = if d.y > 0.08 {
+        near(d.length(), 0.17, 0.025)
+    } else {
+        0.
+    };
+
+    outline.max(eyes).max(smile)
+}
+
+impl Demo {
+    /// Growth parameters the scene is made for.
+    pub(crate) fn params(self) -> Params {
+        match self {
+            Self::Circle => Params::DEFAULT,
+            Self::Maze => Params {
+                boundary_mode: BoundaryMode::Clamp,
+                ..Params::DEFAULT
+            },
+            Self::Portrait => Params {
+                spawn_probability: 0.005,
+                boundary_mode: BoundaryMode::Kill,
+                ..Params::DEFAULT
+            },
+        }
+    }
+
+    /// A new simulation of the scene.
+    pub(crate) fn build(
+        self,
+        params: &Params,
+        seed: u64,
+    ) -> Result<Simulation, SegmentsError> {
+        let mut simulation = Simulation::new(params, seed)?;
+        let center = DVec2::splat(0.5);
+
+        let circle = |center, radius, n_vertices| Primitive {
+            kind: PrimitiveKind::Circle,
+            center,
+            radius,
+            rotation: 0.,
+            n_vertices,
+            passive: false,
+        };
+
+        match self {
+            Self::Circle => {
+                simulation.add_primitive(&circle(center, 50. * ONE, 60))?;
+            }
+            Self::Maze => {
+                for (i, half) in [0.12, 0.24, 0.36].into_iter().enumerate() {
+                    let wall =
+                        square_with_gap(center, half, 0.03, 2 * i as u32);
+                    simulation
+                        .add_obstacle(&along(&wall, params.near_l), false)?;
+                }
+                simulation.add_primitive(&circle(center, 30. * ONE, 40))?;
+            }
+            Self::Portrait => {
+                // darker places are more likely to get a seed, and seeds
+                // keep apart so that they do not start out tangled
+                let mut rng = SmallRng::seed_from_u64(seed);
+                let mut centers = Vec::<DVec2>::new();
+                for _ in 0..20_000 {
+                    if centers.len() == 80 {
+                        break;
+                    }
+                    let p = DVec2::new(rng.random(), rng.random());
+                    if rng.random::<f64>() < face(p)
+                        && centers.iter().all(|c| c.distance(p) > 0.035)
+                    {
+                        centers.push(p);
+                    }
+                }
+
+                for c in centers {
+                    simulation.add_primitive(&circle(c, 8. * ONE, 12))?;
+                }
+            }
+        }
+
+        Ok(simulation)
+    }
+
+    /// Grow the scene without opening the window until it finishes or one
+    /// of `stop` is met, steered by `steering`, then render it to `out`
+    /// with `preset`, limited to `crop` if there is one.
+    pub(crate) fn render(
+        self,
+        seed: u64,
+        out: &Path,
+        mut steering: Steering,
+        stop: StopConditions,
+        crop: Option<&Crop>,
+        preset: &Preset,
+    ) -> Result<()> {
+        // checked before growing, which can take a while
+        preset.format_of(out)?;
+        let mut params = self.params();
+        let mut controller = SimulationController::new();
+        steering.poll(&controller, &[], &mut params)?;
+        controller.set_stop_conditions(stop);
+        controller.run(self.build(&params, seed)?);
+
+        while controller.tick(&params)? {
+            steering.poll(&controller, &[], &mut params)?;
+        }
+        tracing::info!(
+            "{self:?} demo stopped after {} iterations",
+            controller.iteration()
+        );
+
+        preset.export(
+            out,
+            &STYLE.read().unwrap(),
+            &[],
+            controller.simulation(),
+            crop,
+        )
+    }
+}
@@ -0,0 +1,90 @@
+use gtk::{glib, prelude::*};
+
+use super::{
+    algorithm::Domain,
+    app_state::{AppState, SharedState},
+    params_panel::PARAMS,
+    polyline::Polyline,
+    transform::{DOCUMENT_SIZE, Transform},
+    window::show_simulation_error,
+};
+
+/// The circle inscribed in the canvas.
+fn canvas_circle() -> Domain {
+    let transform = Transform::DEFAULT;
+    Domain::Circle {
+        center: transform.document_to_unit(DOCUMENT_SIZE / 2.),
+        radius: transform
+            .document_to_unit_length(DOCUMENT_SIZE.min_element() / 2.),
+    }
+}
+
+/// The last drawn shape, removed from the seeds, or `None` if there are no
+/// shapes.
+fn take_last_shape(state: &mut AppState) -> Option<Domain> {
+    let shape = state.shapes_mut().pop()?;
+    let points = shape
+        .points()
+        .map(|pos| Transform::DEFAULT.document_to_unit(pos))
+        .collect();
+    Some(Domain::Polygon(points))
+}
+
+/// A button label and the domain it picks, `None` if there is none to
+/// pick.
+type Choice = (&'static str, fn(&mut AppState) -> Option<Domain>);
+
+/// Set the domain of the simulation of `state` and restart it in the
+/// domain, seeded with the shapes.
+fn set_domain(button: &gtk::Button, state: &mut AppState, domain: Domain) {
+    let params = *PARAMS.read().unwrap();
+    state.simulation_mut().set_domain(domain);
+
+    if let Err(err) = state.reset(&params) {
+        let window = button.root().and_downcast::<gtk::Window>();
+        show_simulation_error(window.as_ref(), err);
+    }
+}
+
+/// Button with a popover that sets the region the simulation grows in:
+/// the whole simulation space, the circle inscribed in the canvas, or the
+/// outline of the last drawn shape of `state`, which is then no longer a
+/// seed.
+pub(crate) fn build(state: &SharedState) -> gtk::MenuButton {
+    let buttons = gtk::Box::builder()
+        .orientation(gtk::Orientation::Vertical)
+        .spacing(4)
+        .build();
+
+    let popover = gtk::Popover::builder().child(&buttons).build();
+
+    let choices: [Choice; 3] = [
+        ("Square", |_| Some(Domain::Square)),
+        ("Circle", |_| Some(canvas_circle())),
+        ("Last shape", take_last_shape),
+    ];
+
+    for (label, domain) in choices {
+        let button = gtk::Button::with_label(label);
+        button.connect_clicked(glib::clone!(
+            #[weak]
+            popover,
+            #[strong]
+            state,
+            move |button| {
+                let mut state = state.borrow_mut();
+                match domain(&mut state) {
+                    Some(domain) => set_domain(button, &mut state, domain),
+                    None => tracing::warn!("no shape to use as the domain"),
+                }
+                popover.popdown();
+            }
+        ));
+        buttons.append(&button);
+    }
+
+    gtk::MenuButton::builder()
+        .label("Domain")
+        .popover(&popover)
+        .build()
+}
@@ -0,0 +1,519 @@
+//! Writing scenes to image files, with whichever renderer the build has.
+
+use std::{
+    fmt::Write as _, fs::File, io::BufWriter, path::Path, str::FromStr,
+};
+
+use anyhow::{Context, Result};
+use glam::DVec2;
+
+use super::{
+    algorithm::Simulation,
+    layers::CanvasLayers,
+    polyline::Polyline,
+    scene::{self, Color, Paint, Scene, Stroke},
+    shape::Shape,
+    style::Style,
+};
+
+#[cfg(not(any(feature = "gui", feature = "tiny-skia")))]
+compile_error!("enable the `gui` or `tiny-skia` feature to render images");
+
+#[cfg(test)]
+mod golden;
+
+/// Pixels per inch of the document, at which exports are the size of the
+/// canvas.
+pub(crate) const DOCUMENT_DPI: f64 = 96.;
+
+/// Image formats documents can be written in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub(crate) enum ImageFormat {
+    Svg,
+    Png,
+}
+
+impl ImageFormat {
+    /// The format of `path`, picked by its extension.
+    pub(crate) fn of_path(path: &Path) -> Result<Self> {
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(str::to_ascii_lowercase);
+
+        match extension.as_deref() {
+            Some("svg") => Ok(Self::Svg),
+            Some("png") => Ok(Self::Png),
+            _ => anyhow::bail!(
+                "unsupported output format, expected .svg or .png: {}",
+                path.display()
+            ),
+        }
+    }
+
+    pub(crate) fn extension(self) -> &'static str {
+        match self {
+            Self::Svg => "svg",
+            Self::Png => "png",
+        }
+    }
+}
+
+/// A rectangle of the document, in document coordinates.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Region {
+    pub(crate) min: DVec2,
+    pub(crate) max: DVec2,
+}
+
+impl Region {
+    /// The rectangle with opposite corners `a` and `b`.
+    pub(crate) fn spanning(a: DVec2, b: DVec2) -> Self {
+        Self {
+            min: a.min(b),
+            max: a.max(b),
+        }
+    }
+
+    pub(crate) fn size(self) -> DVec2 {
+        self.max - self.min
+    }
+
+    /// Whether the rectangle covers no area, and so can not be exported.
+    pub(crate) fn is_empty(self) -> bool {
+        self.size().min_element() <= 0.
+    }
+}
+
+/// Parses `X,Y,WIDTH,HEIGHT`, as given on the command line.
+impl FromStr for Region {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let values = s
+            .split(',')
+            .map(|value| {
+                value
+                    .trim()
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|value| value.is_finite())
+                    .with_context(|| format!("invalid number: {value}"))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let &[x, y, width, height] = &values[..] else {
+            anyhow::bail!("expected X,Y,WIDTH,HEIGHT: {s}");
+        };
+
+        anyhow::ensure!(
+            width > 0. && height > 0.,
+            "the region must have a positive width and height: {s}"
+        );
+        let min = DVec2::new(x, y);
+        Ok(Self::spanning(min, min + DVec2::new(width, height)))
+    }
+}
+
+/// The part of the document an export is limited to.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Crop {
+    /// The rectangle images are cut down to.
+    pub(crate) region: Region,
+    /// Indices of the only shapes drawn, or `None` for all of them.
+    pub(crate) selection: Option<Vec<usize>>,
+}
+
+impl Crop {
+    /// Everything in `region`.
+    pub(crate) fn region(region: Region) -> Self {
+        Self {
+            region,
+            selection: None,
+        }
+    }
+
+    /// Only the shapes of `shapes` at the indices of `selection`, in the
+    /// rectangle around them, which also takes in as much of the growth as
+    /// is within it.
+    pub(crate) fn selection(
+        style: &Style,
+        shapes: &[Shape],
+        selection: Vec<usize>,
+    ) -> Result<Self> {
+        let mut points = Vec::new();
+        for &i in &selection {
+            let shape = shapes.get(i).with_context(|| {
+                format!("no shape {i}, there are {}", shapes.len())
+            })?;
+            points.extend(shape.points());
+        }
+
+        let first = *points.first().context("no shapes are selected")?;
+        let (min, max) = points
+            .iter()
+            .fold((first, first), |(min, max), &p| (min.min(p), max.max(p)));
+        // lines are as wide as the stroke at full pressure
+        let margin = DVec2::splat(style.stroke_width / 2.);
+        let region = Region::spanning(min - margin, max + margin);
+        anyhow::ensure!(!region.is_empty(), "the selected shapes are empty");
+
+        Ok(Self {
+            region,
+            selection: Some(selection),
+        })
+    }
+
+    /// The selected shapes of `shapes`.
+    pub(crate) fn select(&self, shapes: &[Shape]) -> Vec<Shape> {
+        match &self.selection {
+            Some(selection) => selection
+                .iter()
+                .filter_map(|&i| shapes.get(i).cloned())
+                .collect(),
+            None => shapes.to_vec(),
+        }
+    }
+
+    /// [`Scene::layered`] of the selected shapes of `shapes` and
+    /// `simulation`, cut down to the region.
+    pub(crate) fn frame(
+        &self,
+        style: &Style,
+        canvas: &CanvasLayers,
+        shapes: &[Shape],
+        simulation: Option<&Simulation>,
+    ) -> Scene {
+        Scene::layered(style, canvas, &self.select(shapes), simulation)
+            .crop(self.region.min, self.region.max)
+    }
+}
+
+/// [`Scene::layered`] of `shapes` and `simulation`, limited to `crop` if
+/// there is one.
+pub(crate) fn frame(
+    style: &Style,
+    canvas: &CanvasLayers,
+    shapes: &[Shape],
+    simulation: Option<&Simulation>,
+    crop: Option<&Crop>,
+) -> Scene {
+    match crop {
+        Some(crop) => crop.frame(style, canvas, shapes, simulation),
+        None => Scene::layered(style, canvas, shapes, simulation),
+    }
+}
+
+/// Pixels of a rendered scene, row by row, as red, green, blue, and
+/// straight alpha bytes.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Image {
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    pub(crate) data: Vec<u8>,
+}
+
+impl Image {
+    pub(crate) fn write_png(&self, path: &Path) -> Result<()> {
+        let file = File::create(path)
+            .with_context(|| format!("failed to create {}", path.display()))?;
+        self.encode_png(BufWriter::new(file))
+    }
+
+    /// The image as the bytes of a PNG file, for the clipboard.
+    pub(crate) fn png(&self) -> Result<Vec<u8>> {
+        let mut png = Vec::new();
+        self.encode_png(&mut png)?;
+        Ok(png)
+    }
+
+    fn encode_png(&self, out: impl std::io::Write) -> Result<()> {
+        let mut encoder = png::Encoder::new(out, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.write_header()?.write_image_data(&self.data)?;
+        Ok(())
+    }
+}
+
+/// `scene` as an image of its size at [`DOCUMENT_DPI`], scaled up or down
+/// to `dpi`.
+pub(crate) fn rasterize(scene: &Scene, dpi: f64) -> Result<Image> {
+    anyhow::ensure!(dpi > 0., "invalid dpi: {dpi}");
+
+    #[cfg(feature = "tiny-skia")]
+    let rasterize = super::raster::rasterize;
+    #[cfg(not(feature = "tiny-skia"))]
+    let rasterize = super::render::rasterize;
+
+    rasterize(scene, dpi)
+}
+
+fn svg_color(color: Color) -> String {
+    let [r, g, b] = color.to_rgb8();
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+/// `paths` as the data of an SVG path.
+fn svg_path_data(paths: &[scene::Path]) -> String {
+    let mut d = String::new();
+    for path in paths {
+        match path {
+            scene::Path::Line { points, closed } => {
+                for (i, p) in points.iter().enumerate() {
+                    let command = if i == 0 { 'M' } else { 'L' };
+                    _ = write!(d, "{command}{} {}", p.x, p.y);
+                }
+                if *closed {
+                    d.push('Z');
+                }
+            }
+            // two half circles, as a single arc can not end where it starts
+            #[cfg(any(feature = "gui", test))]
+            scene::Path::Circle { center, radius } => {
+                _ = write!(
+                    d,
+                    "M{} {}a{radius} {radius} 0 1 0 {} 0\
+                     a{radius} {radius} 0 1 0 {} 0Z",
+                    center.x - radius,
+                    center.y,
+                    2. * radius,
+                    -2. * radius,
+                );
+            }
+        }
+    }
+    d
+}
+
+/// Attributes of a path of data `d` with `stroke`, writing a clip path to
+/// `svg` first if only the inside of the path is stroked.
+fn svg_stroke(
+    stroke: Stroke,
+    d: &str,
+    svg: &mut String,
+    n_clips: &mut usize,
+) -> Result<String, std::fmt::Error> {
+    let (cap, join) = if stroke.round {
+        ("round", "round")
+    } else {
+        ("butt", "miter")
+    };
+    let mut attributes = format!(
+        "fill=\"none\" stroke=\"{}\" stroke-opacity=\"{}\" \
+         stroke-width=\"{}\" stroke-linecap=\"{cap}\" \
+         stroke-linejoin=\"{join}\"",
+        svg_color(stroke.color),
+        stroke.color.alpha,
+        stroke.width
+    );
+    if let Some(dash) = stroke.dash {
+        write!(attributes, " stroke-dasharray=\"{dash}\"")?;
+    }
+    if stroke.inside {
+        *n_clips += 1;
+        writeln!(
+            svg,
+            "<clipPath id=\"clip{n_clips}\"><path d=\"{d}\"/></clipPath>"
+        )?;
+        write!(attributes, " clip-path=\"url(#clip{n_clips})\"")?;
+    }
+    Ok(attributes)
+}
+
+/// `text` with the characters that end an SVG attribute value escaped.
+fn svg_text(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('"', "&quot;")
+}
+
+/// `scene` as an SVG document, measured in points like the images of other
+/// programs, with the document in its view box. Named layers are Inkscape
+/// layers, which plotter software draws one at a time.
+pub(crate) fn svg(scene: &Scene) -> String {
+    let size = scene.size;
+    let points = size * 72. / DOCUMENT_DPI;
+    let named = scene.layers.iter().any(|layer| layer.name.is_some());
+
+    let mut svg = String::new();
+    let mut write = || -> std::fmt::Result {
+        let inkscape = if named {
+            " xmlns:inkscape=\"http://www.inkscape.org/namespaces/inkscape\""
+        } else {
+            ""
+        };
+        writeln!(
+            svg,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\"{inkscape} \
+             width=\"{}pt\" height=\"{}pt\" viewBox=\"0 0 {} {}\">",
+            points.x, points.y, size.x, size.y
+        )?;
+        if let Some(background) = scene.background {
+            writeln!(
+                svg,
+                "<rect width=\"{}\" height=\"{}\" fill=\"{}\" \
+                 fill-opacity=\"{}\"/>",
+                size.x,
+                size.y,
+                svg_color(background),
+                background.alpha
+            )?;
+        }
+
+        // clip paths are numbered through the document
+        let mut n_clips = 0;
+        for layer in &scene.layers {
+            if let Some(name) = &layer.name {
+                writeln!(
+                    svg,
+                    "<g inkscape:groupmode=\"layer\" inkscape:label=\"{}\">",
+                    svg_text(name)
+                )?;
+            }
+            let transform = layer.scale != 1. || layer.offset != DVec2::ZERO;
+            if transform {
+                writeln!(
+                    svg,
+                    "<g transform=\"translate({} {}) scale({})\">",
+                    layer.offset.x, layer.offset.y, layer.scale
+                )?;
+            }
+
+            for item in &layer.items {
+                let d = svg_path_data(&item.paths);
+                let attributes = match item.paint {
+                    #[cfg(feature = "gui")]
+                    Paint::Fill(color) => format!(
+                        "fill=\"{}\" fill-opacity=\"{}\"",
+                        svg_color(color),
+                        color.alpha
+                    ),
+                    Paint::Stroke(stroke) => {
+                        svg_stroke(stroke, &d, &mut svg, &mut n_clips)?
+                    }
+                };
+                writeln!(svg, "<path {attributes} d=\"{d}\"/>")?;
+            }
+
+            if transform {
+                writeln!(svg, "</g>")?;
+            }
+            if layer.name.is_some() {
+                writeln!(svg, "</g>")?;
+            }
+        }
+
+        writeln!(svg, "</svg>")
+    };
+    write().expect("writing to a string can not fail");
+
+    svg
+}
+
+/// Write `scene` to `out` as an image of the canvas. PNG images are scaled
+/// to `dpi`.
+pub(crate) fn write_image(
+    out: &Path,
+    format: ImageFormat,
+    dpi: f64,
+    scene: &Scene,
+) -> Result<()> {
+    match format {
+        ImageFormat::Svg => std::fs::write(out, svg(scene))
+            .with_context(|| format!("failed to write {}", out.display())),
+        ImageFormat::Png => rasterize(scene, dpi)?.write_png(out),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shape::Pen;
+
+    #[test]
+    fn images_encode_to_png() {
+        let image = Image {
+            width: 2,
+            height: 1,
+            data: vec![255, 0, 0, 255, 0, 0, 255, 128],
+        };
+        let png = image.png().unwrap();
+        let decoder = png::Decoder::new(png.as_slice());
+        let mut reader = decoder.read_info().unwrap();
+        let mut data = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut data).unwrap();
+        assert_eq!((info.width, info.height), (2, 1));
+        assert_eq!(data[..info.buffer_size()], image.data);
+    }
+
+    #[test]
+    fn region_parses_position_and_size() {
+        let region = "10,20.5,300,40".parse::<Region>().unwrap();
+        assert_eq!(region.min, DVec2::new(10., 20.5));
+        assert_eq!(region.max, DVec2::new(310., 60.5));
+
+        for invalid in ["10,20,300", "10,20,0,40", "a,20,300,40", ""] {
+            assert!(invalid.parse::<Region>().is_err(), "{invalid:?}");
+        }
+    }
+
+    #[test]
+    fn selection_is_cropped_around_selected_shapes() {
+        let mut line = Shape::from_pos(100., 100., 0, Pen::DEFAULT);
+        line.next_vertex(50., 20., 10, Pen::DEFAULT);
+        let other = Shape::from_pos(400., 400., 0, Pen::DEFAULT);
+        let shapes = [other, line];
+        let style = Style::default();
+
+        let crop = Crop::selection(&style, &shapes, vec![1]).unwrap();
+        let margin = style.stroke_width / 2.;
+        assert_eq!(crop.region.min, DVec2::splat(100. - margin));
+        assert_eq!(crop.region.max, DVec2::new(150., 120.) + margin);
+
+        let scene = crop.frame(&style, &CanvasLayers::DEFAULT, &shapes, None);
+        assert_eq!(scene.size, crop.region.size());
+        assert_eq!(scene.layers[0].offset, -crop.region.min);
+        assert_eq!(scene.layers[0].items.len(), 1);
+
+        assert!(Crop::selection(&style, &shapes, vec![2]).is_err());
+        assert!(Crop::selection(&style, &shapes, Vec::new()).is_err());
+    }
+
+    #[test]
+    fn shapes_with_a_layer_are_exported_in_inkscape_layers() {
+        let line = |x, layer: Option<&str>| {
+            let mut shape = Shape::from_pos(x, 100., 0, Pen::DEFAULT);
+            shape.next_vertex(0., 50., 10, Pen::DEFAULT);
+            shape.set_layer(layer.map(str::to_owned));
+            shape
+        };
+        let shapes = [
+            line(100., Some("generation-1")),
+            line(200., None),
+            line(300., Some("a&b")),
+            line(400., Some("generation-1")),
+        ];
+        let style = Style::default();
+
+        let scene = Scene::frame(&style, &shapes, None);
+        let names = scene.layers.iter().map(|l| l.name.as_deref());
+        assert_eq!(
+            names.collect::<Vec<_>>(),
+            [None, Some("generation-1"), Some("a&b")]
+        );
+        assert_eq!(scene.layers[1].items.len(), 2);
+
+        let document = svg(&scene);
+        assert!(document.contains("xmlns:inkscape="));
+        assert!(document.contains(
+            "<g inkscape:groupmode=\"layer\" \
+             inkscape:label=\"generation-1\">"
+        ));
+        assert!(document.contains("inkscape:label=\"a&amp;b\""));
+        let closed = document.matches("</g>").count();
+        assert_eq!(document.matches("<g").count(), closed);
+
+        let unnamed = Scene::frame(&style, &shapes[1..2], None);
+        assert!(!svg(&unnamed).contains("inkscape"));
+    }
+}
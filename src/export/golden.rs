@@ -0,0 +1,355 @@
+//! Golden image tests of the renderers.
+//!
+//! Each test draws a known document or simulation offscreen with every
+//! renderer of the build and compares it with the PNG of the same name in
+//! `tests/golden/<renderer>`. Antialiasing and rounding differ a little
+//! between machines and library versions, so pixels only count as
+//! different past a perceptual threshold, and a few of them may differ
+//! before a test fails.
+//!
+//! After an intended change to a renderer, run the tests with
+//! `UPDATE_GOLDEN=1` to write new goldens, and check them by eye before
+//! committing them. The images of failed tests and their differences are
+//! written to `target/golden`.
+
+use std::{f64::consts::TAU, fs::File, path::PathBuf};
+
+use glam::{DVec2, DVec3};
+
+use super::*;
+use crate::{
+    algorithm::{Domain, Params, Simulation},
+    demo::{DEFAULT_SEED, Demo},
+    layers::CanvasLayer,
+    scene::{self, Layer},
+    shape::{Pen, Shape},
+    style::Style,
+};
+
+type Rasterize = fn(&Scene, f64) -> Result<Image>;
+
+/// Renderers the goldens are drawn with, and the directories they are kept
+/// in.
+const RENDERERS: &[(&str, Rasterize)] = &[
+    #[cfg(feature = "gui")]
+    ("cairo", crate::render::rasterize),
+    #[cfg(feature = "tiny-skia")]
+    ("tiny-skia", crate::raster::rasterize),
+];
+
+/// Smallest perceptual difference between two pixels, from `0.` to `1.`, at
+/// which they count as different.
+const THRESHOLD: f64 = 0.1;
+
+/// Largest fraction of pixels that may differ before an image no longer
+/// matches its golden. Lines cover little of an image, so even this few are
+/// enough to miss a missing line.
+const MAX_DIFFERENT: f64 = 0.0001;
+
+/// Where goldens of `renderer` are kept.
+fn golden_path(renderer: &str, name: &str) -> PathBuf {
+    [
+        env!("CARGO_MANIFEST_DIR"),
+        "tests",
+        "golden",
+        renderer,
+        name,
+    ]
+    .iter()
+    .collect::<PathBuf>()
+    .with_extension("png")
+}
+
+/// Where images of failed tests are written.
+fn failure_dir() -> PathBuf {
+    [env!("CARGO_MANIFEST_DIR"), "target", "golden"]
+        .iter()
+        .collect()
+}
+
+fn read_png(path: &Path) -> Result<Image> {
+    let file = File::open(path)?;
+    let mut decoder = png::Decoder::new(file);
+    decoder.set_transformations(
+        png::Transformations::EXPAND | png::Transformations::ALPHA,
+    );
+    let mut reader = decoder.read_info()?;
+    let mut data = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut data)?;
+    anyhow::ensure!(
+        info.color_type == png::ColorType::Rgba
+            && info.bit_depth == png::BitDepth::Eight,
+        "unsupported png"
+    );
+    data.truncate(info.buffer_size());
+
+    Ok(Image {
+        width: info.width,
+        height: info.height,
+        data,
+    })
+}
+
+fn write_png(image: &Image, path: &Path) {
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    image.write_png(path).unwrap();
+}
+
+/// Color of every pixel of `image`, row by row.
+fn pixels(image: &Image) -> impl Iterator<Item = [u8; 4]> {
+    image.data.chunks_exact(4).map(|p| [p[0], p[1], p[2], p[3]])
+}
+
+/// Perceptual difference between two colors, from `0.` to `1.`, as the
+/// distance between them in the YIQ color space weighted by how sensitive
+/// the eye is to each channel.
+///
+/// See Kotsarenko and Ramos, "Measuring perceived color difference using
+/// YIQ NTSC transmission color space in mobile applications", 2010.
+fn difference(a: [u8; 4], b: [u8; 4]) -> f64 {
+    let yiq = |c: [u8; 4]| {
+        let [r, g, b, _] = c.map(|c| c as f64 / u8::MAX as f64);
+        DVec3::new(
+            0.29889531 * r + 0.58662247 * g + 0.11448223 * b,
+            0.59597799 * r - 0.27417610 * g - 0.32180189 * b,
+            0.21147017 * r - 0.52261711 * g + 0.31114694 * b,
+        )
+    };
+    let d = yiq(a) - yiq(b);
+    let weighted = 0.5053 * d.x * d.x + 0.299 * d.y * d.y + 0.1957 * d.z * d.z;
+    // the largest weighted distance, between black and white
+    (weighted / 0.5053).sqrt()
+}
+
+/// Check that `image` matches the golden of `renderer` named `name`, or
+/// replace the golden with it if `UPDATE_GOLDEN` is set.
+fn assert_golden(renderer: &str, name: &str, image: Image) {
+    let path = golden_path(renderer, name);
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        write_png(&image, &path);
+        return;
+    }
+
+    let golden = read_png(&path).unwrap_or_else(|err| {
+        panic!(
+            "missing golden {}, run with UPDATE_GOLDEN=1 to write it: {err:#}",
+            path.display()
+        )
+    });
+    assert_eq!(
+        (golden.width, golden.height),
+        (image.width, image.height),
+        "{renderer}/{name}: size differs from the golden",
+    );
+
+    let different = pixels(&golden)
+        .zip(pixels(&image))
+        .map(|(a, b)| difference(a, b) > THRESHOLD)
+        .collect::<Vec<_>>();
+    let n_different = different.iter().filter(|&&d| d).count();
+    let fraction = n_different as f64 / different.len() as f64;
+    if fraction <= MAX_DIFFERENT {
+        return;
+    }
+
+    // the differences in red over a faded copy of the golden
+    let diff = Image {
+        data: pixels(&golden)
+            .zip(&different)
+            .flat_map(|(p, &d)| {
+                if d {
+                    [u8::MAX, 0, 0, u8::MAX]
+                } else {
+                    p.map(|c| (c as f64 * 0.2) as u8)
+                }
+            })
+            .collect(),
+        ..golden
+    };
+
+    let dir = failure_dir().join(renderer);
+    write_png(&image, &dir.join(format!("{name}.png")));
+    write_png(&diff, &dir.join(format!("{name}.diff.png")));
+    panic!(
+        "{renderer}/{name}: {n_different} pixels ({:.2}%) differ from the \
+         golden, see {}",
+        100. * fraction,
+        dir.display()
+    );
+}
+
+/// Check `scene` drawn by every renderer at `dpi` against their goldens.
+fn assert_scene(name: &str, scene: &Scene, dpi: f64) {
+    for (renderer, rasterize) in RENDERERS {
+        assert_golden(renderer, name, rasterize(scene, dpi).unwrap());
+    }
+}
+
+/// `demo` grown for `iterations` iterations from the default seed.
+fn grown(demo: Demo, iterations: u64) -> Simulation {
+    let params = demo.params();
+    let mut simulation = demo.build(&params, DEFAULT_SEED).unwrap();
+    for _ in 0..iterations {
+        if !simulation.step(&params).unwrap() {
+            break;
+        }
+    }
+    simulation
+}
+
+/// An open stroke getting lighter toward its end, a closed triangle, and a
+/// closed obstacle.
+fn sample_shapes() -> Vec<Shape> {
+    let pen = |pressure| Pen {
+        pressure,
+        ..Pen::DEFAULT
+    };
+
+    let mut stroke = Shape::from_pos(100., 300., 0, pen(1.));
+    for i in 1..=40 {
+        let t = i as f64 / 40.;
+        let offset = DVec2::new(600. * t, 120. * (TAU * t).sin());
+        stroke.next_vertex_at(offset, 10 * i, pen(1. - 0.8 * t));
+    }
+
+    let mut triangle = Shape::from_pos(200., 100., 0, pen(0.5));
+    triangle.next_vertex(150., 0., 10, pen(0.5));
+    triangle.next_vertex(75., 120., 20, pen(0.5));
+    triangle.set_closed(true);
+
+    let mut obstacle = Shape::from_pos(500., 450., 0, pen(1.));
+    for i in 1..12 {
+        let offset = DVec2::from_angle(TAU * i as f64 / 12.) - DVec2::X;
+        obstacle.next_vertex_at(60. * offset, 10 * i, pen(1.));
+    }
+    obstacle.set_closed(true);
+    obstacle.set_obstacle(true);
+
+    vec![stroke, triangle, obstacle]
+}
+
+#[test]
+fn empty_document() {
+    let scene = Scene::frame(&Style::default(), &[], None);
+    assert_scene("empty_document", &scene, DOCUMENT_DPI);
+}
+
+#[test]
+fn drawn_shapes() {
+    let scene = Scene::frame(&Style::default(), &sample_shapes(), None);
+    assert_scene("drawn_shapes", &scene, DOCUMENT_DPI);
+}
+
+/// The shapes drawn up to vertex 2 of shape 1 during a playback, and
+/// `markers` at their vertices if set.
+fn playback_scene(markers: bool) -> Scene {
+    let shapes = sample_shapes();
+    let playback = Some((1, 2));
+    let style = Style::default();
+    let mut items = scene::layer_shapes(
+        &style,
+        &shapes,
+        style.shape,
+        playback,
+        CanvasLayer::Sketches,
+    );
+    if markers {
+        items.extend(scene::vertex_markers(
+            &style,
+            &shapes,
+            playback,
+            1.,
+            usize::MAX,
+        ));
+    }
+    Scene {
+        layers: vec![Layer::new(items)],
+        ..Scene::frame(&style, &[], None)
+    }
+}
+
+#[test]
+fn drawn_shapes_during_playback() {
+    let scene = playback_scene(false);
+    assert_scene("drawn_shapes_during_playback", &scene, DOCUMENT_DPI);
+}
+
+#[test]
+fn drawn_shapes_with_markers() {
+    let style = Style::default();
+    let shapes = sample_shapes();
+    let mut scene = Scene::frame(&style, &shapes, None);
+    scene.layers.push(Layer::new(scene::vertex_markers(
+        &style,
+        &shapes,
+        None,
+        1.,
+        usize::MAX,
+    )));
+    assert_scene("drawn_shapes_with_markers", &scene, DOCUMENT_DPI);
+}
+
+#[test]
+fn drawn_shapes_with_markers_during_playback() {
+    let scene = playback_scene(true);
+    assert_scene(
+        "drawn_shapes_with_markers_during_playback",
+        &scene,
+        DOCUMENT_DPI,
+    );
+}
+
+#[test]
+fn domain_guides_circle() {
+    let domain = Domain::Circle {
+        center: DVec2::splat(0.5),
+        radius: 0.4,
+    };
+    let style = Style::default();
+    let scene = Scene {
+        layers: vec![Layer::new(scene::domain_guides(
+            &style,
+            &domain,
+            &Params::DEFAULT,
+            1.,
+        ))],
+        ..Scene::frame(&style, &[], None)
+    };
+    assert_scene("domain_guides_circle", &scene, DOCUMENT_DPI);
+}
+
+#[test]
+fn circle_demo_growth() {
+    let simulation = grown(Demo::Circle, 1500);
+    let scene = Scene::frame(&Style::default(), &[], Some(&simulation));
+    assert_scene("circle_demo_growth", &scene, DOCUMENT_DPI);
+}
+
+#[test]
+fn maze_demo_growth() {
+    let simulation = grown(Demo::Maze, 1500);
+    let scene = Scene::frame(&Style::default(), &[], Some(&simulation));
+    assert_scene("maze_demo_growth", &scene, DOCUMENT_DPI);
+}
+
+#[test]
+fn growth_at_export_dpi() {
+    let simulation = grown(Demo::Circle, 200);
+    let scene =
+        Scene::frame(&Style::default(), &sample_shapes(), Some(&simulation));
+    assert_scene("growth_at_export_dpi", &scene, 2. * DOCUMENT_DPI);
+}
+
+#[test]
+fn selected_shapes_with_growth() {
+    let simulation = grown(Demo::Circle, 200);
+    let style = Style::default();
+    let crop = Crop::selection(&style, &sample_shapes(), vec![1, 2]).unwrap();
+    let scene = crop.frame(
+        &style,
+        &CanvasLayers::DEFAULT,
+        &sample_shapes(),
+        Some(&simulation),
+    );
+    assert_scene("selected_shapes_with_growth", &scene, 2. * DOCUMENT_DPI);
+}
@@ -0,0 +1,368 @@
+//! Drawing the growth with OpenGL, for simulations with more edges than
+//! cairo can stroke at the frame rate.
+//!
+//! The edges are uploaded to a vertex buffer and drawn as lines in a
+//! [`gtk::GLArea`] under the cairo drawing area, which then only draws the
+//! shapes, guides, and cursor on top.
+
+use std::{
+    cell::RefCell,
+    ffi::{CString, c_void},
+    ptr,
+    rc::Rc,
+    sync::{OnceLock, RwLock},
+};
+
+use anyhow::{Context, Result};
+use gl::types::{GLchar, GLenum, GLint, GLuint};
+use glam::DVec2;
+use gtk::{glib, prelude::*};
+
+use super::{
+    app_state::SharedState,
+    layers::{CanvasLayer, LAYERS},
+    scene::{self, Color, Layer, Paint, Path},
+    style::STYLE,
+    transform::DOCUMENT_SIZE,
+};
+
+/// How the window draws the growth, picked at startup.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub(crate) enum Renderer {
+    /// Everything with cairo.
+    Cairo,
+    /// The background and the growth with OpenGL, and the rest with cairo
+    /// on top.
+    Gl,
+}
+
+pub(crate) static RENDERER: RwLock<Renderer> = RwLock::new(Renderer::Cairo);
+
+const VERTEX_SHADER: &str = "
+in vec2 position;
+in vec3 color;
+uniform vec2 scale;
+uniform vec2 offset;
+out vec3 v_color;
+
+void main() {
+    gl_Position = vec4(position * scale + offset, 0.0, 1.0);
+    v_color = color;
+}
+";
+
+const FRAGMENT_SHADER: &str = "
+in vec3 v_color;
+out vec4 out_color;
+
+void main() {
+    out_color = vec4(v_color, 1.0);
+}
+";
+
+/// Floats per vertex: the position in the coordinates of the layer and the
+/// color.
+const VERTEX_LEN: usize = 5;
+
+/// Load the OpenGL functions from libepoxy, which GTK draws with.
+fn load_gl() -> Result<()> {
+    static EPOXY: OnceLock<libloading::Library> = OnceLock::new();
+
+    let name = if cfg!(target_os = "macos") {
+        "libepoxy.0.dylib"
+    } else if cfg!(windows) {
+        "libepoxy-0.dll"
+    } else {
+        "libepoxy.so.0"
+    };
+    let epoxy = match EPOXY.get() {
+        Some(epoxy) => epoxy,
+        None => {
+            let epoxy = unsafe { libloading::Library::new(name) }
+                .with_context(|| format!("failed to load {name}"))?;
+            EPOXY.get_or_init(|| epoxy)
+        }
+    };
+
+    // libepoxy keeps a pointer to each function in a variable named after
+    // it, which resolves the function on the first call
+    gl::load_with(|function| unsafe {
+        epoxy
+            .get::<*const *const c_void>(
+                format!("epoxy_{function}").as_bytes(),
+            )
+            .map(|variable| **variable)
+            .unwrap_or(ptr::null())
+    });
+
+    Ok(())
+}
+
+/// Log of a shader or program that failed to compile or link.
+unsafe fn info_log(
+    object: GLuint,
+    get_iv: unsafe fn(GLuint, GLenum, *mut GLint),
+    get_log: unsafe fn(GLuint, GLint, *mut GLint, *mut GLchar),
+) -> String {
+    let mut len = 0;
+    unsafe { get_iv(object, gl::INFO_LOG_LENGTH, &mut len) };
+    let mut log = vec![0_u8; len.max(1) as usize];
+    unsafe { get_log(object, len, ptr::null_mut(), log.as_mut_ptr().cast()) };
+    String::from_utf8_lossy(&log)
+        .trim_end_matches('\0')
+        .to_owned()
+}
+
+unsafe fn compile_shader(
+    kind: GLenum,
+    header: &str,
+    source: &str,
+) -> Result<GLuint> {
+    let source = CString::new(format!("{header}{source}"))?;
+    unsafe {
+        let shader = gl::CreateShader(kind);
+        gl::ShaderSource(shader, 1, &source.as_ptr(), ptr::null());
+        gl::CompileShader(shader);
+
+        let mut ok = 0;
+        gl::GetShaderiv(shader, gl::COMPILE_STATUS, &mut ok);
+        if ok == 0 {
+            let log = info_log(shader, gl::GetShaderiv, gl::GetShaderInfoLog);
+            gl::DeleteShader(shader);
+            anyhow::bail!("failed to compile shader: {log}");
+        }
+
+        Ok(shader)
+    }
+}
+
+/// The OpenGL objects the growth is drawn with, made when the area is
+/// realized.
+struct Lines {
+    program: GLuint,
+    vao: GLuint,
+    vbo: GLuint,
+    scale: GLint,
+    offset: GLint,
+    /// Positions and colors of the ends of every edge, reused between
+    /// frames.
+    vertices: Vec<f32>,
+}
+
+impl Lines {
+    fn new(es: bool) -> Result<Self> {
+        load_gl()?;
+
+        let header = if es {
+            "#version 300 es\nprecision mediump float;\n"
+        } else {
+            "#version 150\n"
+        };
+
+        unsafe {
+            let vertex =
+                compile_shader(gl::VERTEX_SHADER, header, VERTEX_SHADER)?;
+            let fragment =
+                compile_shader(gl::FRAGMENT_SHADER, header, FRAGMENT_SHADER);
+            let fragment = match fragment {
+                Ok(fragment) => fragment,
+                Err(err) => {
+                    gl::DeleteShader(vertex);
+                    return Err(err);
+                }
+            };
+
+            let program = gl::CreateProgram();
+            gl::AttachShader(program, vertex);
+            gl::AttachShader(program, fragment);
+            gl::BindAttribLocation(program, 0, c"position".as_ptr());
+            gl::BindAttribLocation(program, 1, c"color".as_ptr());
+            gl::LinkProgram(program);
+            gl::DeleteShader(vertex);
+            gl::DeleteShader(fragment);
+
+            let mut ok = 0;
+            gl::GetProgramiv(program, gl::LINK_STATUS, &mut ok);
+            if ok == 0 {
+                let log =
+                    info_log(program, gl::GetProgramiv, gl::GetProgramInfoLog);
+                gl::DeleteProgram(program);
+                anyhow::bail!("failed to link shaders: {log}");
+            }
+
+            let (mut vao, mut vbo) = (0, 0);
+            gl::GenVertexArrays(1, &mut vao);
+            gl::GenBuffers(1, &mut vbo);
+            gl::BindVertexArray(vao);
+            gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+            let stride = (VERTEX_LEN * size_of::<f32>()) as i32;
+            gl::VertexAttribPointer(
+                0,
+                2,
+                gl::FLOAT,
+                gl::FALSE,
+                stride,
+                ptr::null(),
+            );
+            gl::VertexAttribPointer(
+                1,
+                3,
+                gl::FLOAT,
+                gl::FALSE,
+                stride,
+                ptr::without_provenance(2 * size_of::<f32>()),
+            );
+            gl::EnableVertexAttribArray(0);
+            gl::EnableVertexAttribArray(1);
+            gl::BindVertexArray(0);
+
+            Ok(Self {
+                program,
+                vao,
+                vbo,
+                scale: gl::GetUniformLocation(program, c"scale".as_ptr()),
+                offset: gl::GetUniformLocation(program, c"offset".as_ptr()),
+                vertices: Vec::new(),
+            })
+        }
+    }
+
+    /// Delete the objects, while the context they were made in is current.
+    fn delete(self) {
+        unsafe {
+            gl::DeleteBuffers(1, &self.vbo);
+            gl::DeleteVertexArrays(1, &self.vao);
+            gl::DeleteProgram(self.program);
+        }
+    }
+
+    /// Fill the area with `background` and draw the lines of `layer` over
+    /// it, one pixel wide, in an area of `size` pixels.
+    fn draw(&mut self, size: DVec2, background: Color, layer: &Layer) {
+        self.vertices.clear();
+        for item in &layer.items {
+            let Paint::Stroke(stroke) = item.paint else {
+                continue;
+            };
+            let color =
+                [stroke.color.red, stroke.color.green, stroke.color.blue];
+            for path in &item.paths {
+                let Path::Line { points, closed } = path else {
+                    continue;
+                };
+                let closing = (*closed && points.len() > 2)
+                    .then(|| [points[points.len() - 1], points[0]]);
+                let edges = points.windows(2).map(|w| [w[0], w[1]]);
+                for pos in edges.chain(closing).flatten() {
+                    self.vertices.extend([pos.x as f32, pos.y as f32]);
+                    self.vertices.extend(color);
+                }
+            }
+        }
+
+        // the coordinates of the layer to the screen, and then to clip
+        // space, which spans -1 to 1 with y up
+        let flip = DVec2::new(1., -1.);
+        let scale = (2. * layer.scale / size * flip).as_vec2();
+        let offset = ((2. * layer.offset / size - 1.) * flip).as_vec2();
+
+        unsafe {
+            let bg = background;
+            gl::ClearColor(bg.red, bg.green, bg.blue, bg.alpha);
+            gl::Clear(gl::COLOR_BUFFER_BIT);
+
+            gl::UseProgram(self.program);
+            gl::Uniform2f(self.scale, scale.x, scale.y);
+            gl::Uniform2f(self.offset, offset.x, offset.y);
+
+            gl::BindVertexArray(self.vao);
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.vbo);
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                size_of_val(self.vertices.as_slice()) as isize,
+                self.vertices.as_ptr().cast(),
+                gl::STREAM_DRAW,
+            );
+            gl::DrawArrays(
+                gl::LINES,
+                0,
+                (self.vertices.len() / VERTEX_LEN) as i32,
+            );
+            gl::BindVertexArray(0);
+            gl::UseProgram(0);
+        }
+    }
+}
+
+/// An area that draws the background and the growth of `state` with
+/// OpenGL, redrawn every frame while it [follows the growth](follow_growth).
+pub(crate) fn build(state: &SharedState) -> gtk::GLArea {
+    let area = gtk::GLArea::new();
+    area.set_size_request(DOCUMENT_SIZE.x as i32, DOCUMENT_SIZE.y as i32);
+
+    let lines = Rc::new(RefCell::new(None::<Lines>));
+
+    area.connect_realize(glib::clone!(
+        #[strong]
+        lines,
+        move |area| {
+            area.make_current();
+            if let Some(err) = area.error() {
+                tracing::error!("OpenGL is not available: {err}");
+                return;
+            }
+            let es = area.context().is_some_and(|ctx| ctx.uses_es());
+            match Lines::new(es) {
+                Ok(new) => *lines.borrow_mut() = Some(new),
+                Err(err) => {
+                    tracing::error!("failed to set up OpenGL: {err:#}")
+                }
+            }
+        }
+    ));
+
+    area.connect_unrealize(glib::clone!(
+        #[strong]
+        lines,
+        move |area| {
+            area.make_current();
+            if let Some(lines) = lines.borrow_mut().take() {
+                lines.delete();
+            }
+        }
+    ));
+
+    area.connect_render(glib::clone!(
+        #[strong]
+        state,
+        move |area, _ctx| {
+            let Some(lines) = &mut *lines.borrow_mut() else {
+                return glib::Propagation::Proceed;
+            };
+            let size = DVec2::new(area.width() as f64, area.height() as f64);
+            let style = STYLE.read().unwrap();
+            let state = state.borrow();
+            let paths = state.simulation().shown_paths();
+            let canvas = LAYERS.read().unwrap();
+            let growth = scene::growth_paths(&style, paths, 1.);
+            let growth = canvas.show(CanvasLayer::Growth, growth);
+            let layer = state.viewport().layer(None, growth);
+            let background = canvas
+                .background(style.background)
+                .unwrap_or(style.background.with_alpha(0.));
+            lines.draw(size, background, &layer);
+            glib::Propagation::Stop
+        }
+    ));
+
+    area
+}
+
+/// Draw the growth in `area` on every frame, until the returned callback is
+/// removed.
+pub(crate) fn follow_growth(area: &gtk::GLArea) -> gtk::TickCallbackId {
+    area.add_tick_callback(|area, _clock| {
+        area.queue_render();
+        glib::ControlFlow::Continue
+    })
+}
@@ -0,0 +1,60 @@
+use gtk::{glib, prelude::*};
+
+use super::{app_state::SharedState, seed_menu::spin, snap::SnapOptions};
+
+/// Button with a popover showing a grid over the canvas of `state`, and
+/// snapping the strokes drawn and the primitive seeds added to it.
+pub(crate) fn build(state: &SharedState) -> gtk::MenuButton {
+    let grid = gtk::Grid::builder()
+        .row_spacing(4)
+        .column_spacing(8)
+        .build();
+
+    let snap = state.borrow().snap();
+    let show = gtk::CheckButton::builder()
+        .label("Show grid")
+        .active(snap.show_grid)
+        .build();
+    grid.attach(&show, 0, 0, 2, 1);
+    let to_grid = gtk::CheckButton::builder()
+        .label("Snap to grid")
+        .active(snap.to_grid)
+        .build();
+    grid.attach(&to_grid, 0, 1, 2, 1);
+    let spacing = spin(&grid, 2, "Spacing", (5., 200., 5.), snap.spacing);
+
+    let update = glib::clone!(
+        #[strong]
+        state,
+        #[weak]
+        show,
+        #[weak]
+        to_grid,
+        #[weak]
+        spacing,
+        move || {
+            state.borrow_mut().set_snap(SnapOptions {
+                show_grid: show.is_active(),
+                to_grid: to_grid.is_active(),
+                spacing: spacing.value(),
+            });
+        }
+    );
+    show.connect_toggled(glib::clone!(
+        #[strong]
+        update,
+        move |_| update()
+    ));
+    to_grid.connect_toggled(glib::clone!(
+        #[strong]
+        update,
+        move |_| update()
+    ));
+    spacing.connect_value_changed(move |_| update());
+
+    let popover = gtk::Popover::builder().child(&grid).build();
+    gtk::MenuButton::builder()
+        .label("Grid")
+        .popover(&popover)
+        .build()
+}
@@ -0,0 +1,89 @@
+//! Parameters for a few well-known kinds of growth, to grow a shape as one
+//! of them without setting up every parameter.
+
+use super::algorithm::{ONE, Params, SpawnMode};
+
+/// A kind of growth, with the parameters it grows with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum GrowPreset {
+    /// Stiff lobes that branch where they bend the most.
+    Coral,
+    /// A floppy line packed tightly into winding corridors.
+    Maze,
+    /// A smooth line drifting along the flow of the noise.
+    Meander,
+}
+
+impl GrowPreset {
+    #[cfg(feature = "gui")]
+    pub(crate) const ALL: [Self; 3] = [Self::Coral, Self::Maze, Self::Meander];
+
+    /// Label of the preset in menus.
+    #[cfg(feature = "gui")]
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            Self::Coral => "Coral",
+            Self::Maze => "Maze",
+            Self::Meander => "Meander",
+        }
+    }
+
+    /// `params` with those that shape the growth set for the preset. The
+    /// size of the simulation, its boundary and its seeds are left as they
+    /// are.
+    pub(crate) fn params(self, params: &Params) -> Params {
+        let growth = match self {
+            Self::Coral => Params {
+                near_l: 2. * ONE,
+                far_l: 30. * ONE,
+                step: 0.4 * ONE,
+                spawn_probability: 0.005,
+                spawn_mode: SpawnMode::Curvature,
+                branch_probability: 0.002,
+                equalize: 0.1,
+                stiffness: 0.6,
+                noise_amplitude: 0.,
+                ..Params::DEFAULT
+            },
+            Self::Maze => Params {
+                near_l: 2. * ONE,
+                far_l: 15. * ONE,
+                step: 0.3 * ONE,
+                spawn_probability: 0.01,
+                spawn_mode: SpawnMode::Uniform,
+                branch_probability: 0.,
+                equalize: 0.3,
+                stiffness: 0.,
+                noise_amplitude: 0.,
+                ..Params::DEFAULT
+            },
+            Self::Meander => Params {
+                near_l: 3. * ONE,
+                far_l: 50. * ONE,
+                step: 0.5 * ONE,
+                spawn_probability: 0.002,
+                spawn_mode: SpawnMode::Uniform,
+                branch_probability: 0.,
+                equalize: 0.5,
+                stiffness: 1.5,
+                noise_amplitude: 0.5,
+                noise_scale: 150. * ONE,
+                ..Params::DEFAULT
+            },
+        };
+
+        Params {
+            near_l: growth.near_l,
+            far_l: growth.far_l,
+            step: growth.step,
+            spawn_probability: growth.spawn_probability,
+            spawn_mode: growth.spawn_mode,
+            branch_probability: growth.branch_probability,
+            equalize: growth.equalize,
+            stiffness: growth.stiffness,
+            noise_amplitude: growth.noise_amplitude,
+            noise_scale: growth.noise_scale,
+            ..*params
+        }
+    }
+}
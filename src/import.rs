@@ -0,0 +1,580 @@
+//! Geometry drawn in other programs, imported as seeds: the paths,
+//! polylines, polygons, lines, rectangles and circles of SVG files, the
+//! lines and polygons of GeoJSON files, and points listed in CSV files.
+//!
+//! Files are in units of their own, so the geometry is placed on the
+//! canvas by a [`Placement`], fit to the canvas or to the unit square of
+//! the simulation by default.
+//!
+//! - SVG elements are read without their transforms or styles, and paths
+//!   without arcs.
+//! - GeoJSON coordinates are taken as x and y, with y pointing up as on a
+//!   map rather than down as on the canvas.
+//! - CSV files list an `x,y` point on each line, with a blank line between
+//!   outlines. A first line that is not a point is a header.
+//!
+//! Lines that end where they start are closed.
+
+use std::{f64::consts::TAU, path::Path, str::FromStr};
+
+use anyhow::{Context, Result};
+use glam::DVec2;
+use serde_json::Value;
+
+#[cfg(any(feature = "gui", test))]
+use super::transform::Transform;
+use super::{
+    project::Project,
+    seed_file::{outline, parse_path},
+    shape::Shape,
+    transform::DOCUMENT_SIZE,
+};
+
+/// Corners of the circles of SVG files.
+const CIRCLE_CORNERS: usize = 64;
+
+/// The outlines of an imported file, each as its points and whether it is
+/// closed, in the units of the file.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct Geometry {
+    outlines: Vec<(Vec<DVec2>, bool)>,
+}
+
+/// Where on the canvas imported geometry is scaled to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Fit {
+    /// Not scaled, in pixels of the canvas as in the file.
+    #[cfg(any(feature = "gui", test))]
+    Keep,
+    /// Fit into the canvas.
+    Canvas,
+    /// Fit into the unit square of the simulation, which the growth stays
+    /// in.
+    #[cfg(any(feature = "gui", test))]
+    UnitSquare,
+}
+
+/// How imported geometry is placed on the canvas.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Placement {
+    pub(crate) fit: Fit,
+    /// Whether it is centered on where it is fit, rather than placed in its
+    /// top left corner.
+    pub(crate) center: bool,
+    /// Whether it is scaled as much across as down, rather than stretched
+    /// to fill where it is fit.
+    pub(crate) preserve_aspect: bool,
+    /// Pixels of the canvas left free around it.
+    pub(crate) margin: f64,
+}
+
+impl Placement {
+    pub(crate) const DEFAULT: Self = Self {
+        fit: Fit::Canvas,
+        center: true,
+        preserve_aspect: true,
+        margin: 20.,
+    };
+
+    /// The corners of where the geometry goes, within the margin.
+    fn target(self) -> (DVec2, DVec2) {
+        let (min, max) = match self.fit {
+            #[cfg(any(feature = "gui", test))]
+            Fit::Keep => (DVec2::ZERO, DOCUMENT_SIZE),
+            Fit::Canvas => (DVec2::ZERO, DOCUMENT_SIZE),
+            #[cfg(any(feature = "gui", test))]
+            Fit::UnitSquare => (
+                Transform::DEFAULT.unit_to_document(DVec2::ZERO),
+                Transform::DEFAULT.unit_to_document(DVec2::ONE),
+            ),
+        };
+        let margin = DVec2::splat(self.margin).min((max - min) / 2.);
+        (min + margin, max - margin)
+    }
+
+    /// The scale and the offset that place geometry spanning `min` to
+    /// `max`.
+    fn transform(self, (min, max): (DVec2, DVec2)) -> (DVec2, DVec2) {
+        let (target_min, target_max) = self.target();
+        let size = max - min;
+        let target_size = target_max - target_min;
+
+        let scale = match self.fit {
+            #[cfg(any(feature = "gui", test))]
+            Fit::Keep => DVec2::ONE,
+            Fit::Canvas => self.fit_scale(size, target_size),
+            #[cfg(any(feature = "gui", test))]
+            Fit::UnitSquare => self.fit_scale(size, target_size),
+        };
+
+        let offset = if self.center {
+            (target_min + target_max) / 2. - (min + max) / 2. * scale
+        } else {
+            target_min - min * scale
+        };
+        (scale, offset)
+    }
+
+    /// The scale that fits geometry of `size` into `target_size`.
+    fn fit_scale(self, size: DVec2, target_size: DVec2) -> DVec2 {
+        // a line along an axis is scaled along the other alone
+        let fit = |target: f64, size: f64| (size > 0.).then(|| target / size);
+        let (x, y) = (fit(target_size.x, size.x), fit(target_size.y, size.y));
+        match (x, y) {
+            (Some(x), Some(y)) if self.preserve_aspect => {
+                DVec2::splat(x.min(y))
+            }
+            (Some(x), Some(y)) => DVec2::new(x, y),
+            (Some(s), None) | (None, Some(s)) => DVec2::splat(s),
+            (None, None) => DVec2::ONE,
+        }
+    }
+}
+
+impl Geometry {
+    /// The geometry of the SVG, GeoJSON or CSV file at `path`, by its
+    /// extension.
+    pub(crate) fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let extension = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase());
+        let geometry = match extension.as_deref() {
+            Some("svg") => svg(&text),
+            Some("geojson" | "json") => geojson(&text),
+            Some("csv") => csv(&text),
+            _ => anyhow::bail!(
+                "unknown geometry file: {}, expected .svg, .geojson or .csv",
+                path.display()
+            ),
+        };
+        let geometry = geometry.with_context(|| {
+            format!("invalid geometry: {}", path.display())
+        })?;
+        anyhow::ensure!(
+            !geometry.outlines.is_empty(),
+            "no geometry in {}",
+            path.display()
+        );
+        Ok(geometry)
+    }
+
+    /// The corners of the box around every outline, if there are any.
+    fn bounds(&self) -> Option<(DVec2, DVec2)> {
+        let mut points = self.outlines.iter().flat_map(|(p, _)| p);
+        let first = *points.next()?;
+        Some(
+            points.fold((first, first), |(min, max), &p| {
+                (min.min(p), max.max(p))
+            }),
+        )
+    }
+
+    /// A seed shape for every outline, placed by `placement`, with evenly
+    /// spaced vertices.
+    pub(crate) fn place(&self, placement: &Placement) -> Vec<Shape> {
+        let Some(bounds) = self.bounds() else {
+            return Vec::new();
+        };
+        let (scale, offset) = placement.transform(bounds);
+        self.outlines
+            .iter()
+            .map(|(points, closed)| {
+                let points = points
+                    .iter()
+                    .map(|&p| p * scale + offset)
+                    .collect::<Vec<_>>();
+                outline(&points, *closed, None)
+            })
+            .collect()
+    }
+
+    /// Add the outline through `points`, closing it if it ends where it
+    /// starts, unless it is too short to be a line.
+    fn push(&mut self, mut points: Vec<DVec2>, closed: bool) {
+        let closed =
+            closed || (points.len() > 2 && points.first() == points.last());
+        if closed && points.len() > 2 && points.first() == points.last() {
+            points.pop();
+        }
+        if points.len() >= 2 {
+            self.outlines.push((points, closed));
+        }
+    }
+}
+
+/// A project of the geometry of the file at `path`, placed the default way.
+pub(crate) fn load(path: &Path) -> Result<Project> {
+    Ok(Project {
+        shapes: Geometry::load(path)?.place(&Placement::DEFAULT),
+        ..Project::default()
+    })
+}
+
+/// Whether `path` is of a kind of file that is imported.
+pub(crate) fn is_geometry(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| {
+        ["svg", "geojson", "csv"]
+            .iter()
+            .any(|known| ext.eq_ignore_ascii_case(known))
+    })
+}
+
+//===================================================================
+// SVG
+//===================================================================
+
+/// The elements of an SVG document that make outlines, each as its name and
+/// its attributes, in the order they appear.
+fn svg_elements(text: &str) -> Vec<(&str, Vec<(&str, &str)>)> {
+    let mut elements = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start + 1..];
+        let Some(end) = rest.find('>') else {
+            break;
+        };
+        let tag = &rest[..end];
+        rest = &rest[end + 1..];
+
+        let name_end = tag
+            .find(|c: char| c.is_whitespace() || c == '/')
+            .unwrap_or(tag.len());
+        let name = &tag[..name_end];
+        if !["path", "polyline", "polygon", "line", "rect", "circle"]
+            .contains(&name)
+        {
+            continue;
+        }
+        elements.push((name, svg_attributes(&tag[name_end..])));
+    }
+    elements
+}
+
+/// The `name="value"` attributes of a tag, with either kind of quotes.
+fn svg_attributes(mut tag: &str) -> Vec<(&str, &str)> {
+    let mut attributes = Vec::new();
+    while let Some(eq) = tag.find('=') {
+        let name = tag[..eq].trim();
+        let value = tag[eq + 1..].trim_start();
+        let Some(quote) = value.chars().next().filter(|c| "\"'".contains(*c))
+        else {
+            break;
+        };
+        let Some(end) = value[1..].find(quote) else {
+            break;
+        };
+        attributes.push((name, &value[1..end + 1]));
+        tag = &value[end + 2..];
+    }
+    attributes
+}
+
+/// The numbers of an SVG attribute, separated by whitespace or commas.
+fn svg_numbers(value: &str) -> Result<Vec<f64>> {
+    value
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|n| !n.is_empty())
+        .map(|n| n.parse().with_context(|| format!("invalid number: {n}")))
+        .collect()
+}
+
+fn svg(text: &str) -> Result<Geometry> {
+    let mut geometry = Geometry::default();
+    for (name, attributes) in svg_elements(text) {
+        let get = |key: &str| {
+            attributes.iter().find(|(k, _)| *k == key).map(|&(_, v)| v)
+        };
+        let number = |key: &str| -> Result<f64> {
+            match get(key) {
+                Some(value) => value
+                    .trim()
+                    .trim_end_matches("px")
+                    .parse()
+                    .with_context(|| format!("invalid {key} of {name}")),
+                None => Ok(0.),
+            }
+        };
+
+        match name {
+            "path" => {
+                let Some(d) = get("d") else { continue };
+                for (points, closed) in parse_path(d)? {
+                    geometry.push(points, closed);
+                }
+            }
+            "polyline" | "polygon" => {
+                let numbers = svg_numbers(get("points").unwrap_or_default())?;
+                let points = numbers
+                    .chunks_exact(2)
+                    .map(|xy| DVec2::new(xy[0], xy[1]))
+                    .collect();
+                geometry.push(points, name == "polygon");
+            }
+            "line" => {
+                let a = DVec2::new(number("x1")?, number("y1")?);
+                let b = DVec2::new(number("x2")?, number("y2")?);
+                geometry.push(vec![a, b], false);
+            }
+            "rect" => {
+                let min = DVec2::new(number("x")?, number("y")?);
+                let size = DVec2::new(number("width")?, number("height")?);
+                let corners = [
+                    min,
+                    min + DVec2::X * size,
+                    min + size,
+                    min + DVec2::Y * size,
+                ];
+                geometry.push(corners.to_vec(), true);
+            }
+            "circle" => {
+                let center = DVec2::new(number("cx")?, number("cy")?);
+                let r = number("r")?;
+                let points = (0..CIRCLE_CORNERS)
+                    .map(|k| {
+                        let theta = TAU * k as f64 / CIRCLE_CORNERS as f64;
+                        center + r * DVec2::from_angle(theta)
+                    })
+                    .collect();
+                geometry.push(points, true);
+            }
+            _ => {}
+        }
+    }
+    Ok(geometry)
+}
+
+//===================================================================
+// GeoJSON
+//===================================================================
+
+fn geojson(text: &str) -> Result<Geometry> {
+    let value = serde_json::from_str::<Value>(text)?;
+    let mut geometry = Geometry::default();
+    geojson_object(&value, &mut geometry)?;
+    Ok(geometry)
+}
+
+/// Add the lines and polygons of a GeoJSON object of any type to
+/// `geometry`. Points are skipped.
+fn geojson_object(value: &Value, geometry: &mut Geometry) -> Result<()> {
+    let kind = value
+        .get("type")
+        .and_then(Value::as_str)
+        .context("expected an object with a type")?;
+    let coordinates =
+        || value.get("coordinates").context("missing coordinates");
+
+    match kind {
+        "FeatureCollection" => {
+            let features = value
+                .get("features")
+                .and_then(Value::as_array)
+                .context("expected a list of features")?;
+            for feature in features {
+                geojson_object(feature, geometry)?;
+            }
+        }
+        "Feature" => {
+            // features without a geometry are allowed
+            if let Some(g) = value.get("geometry").filter(|g| !g.is_null()) {
+                geojson_object(g, geometry)?;
+            }
+        }
+        "GeometryCollection" => {
+            let geometries = value
+                .get("geometries")
+                .and_then(Value::as_array)
+                .context("expected a list of geometries")?;
+            for g in geometries {
+                geojson_object(g, geometry)?;
+            }
+        }
+        "LineString" => geometry.push(geojson_line(coordinates()?)?, false),
+        "MultiLineString" | "Polygon" => {
+            for line in geojson_list(coordinates()?)? {
+                geometry.push(geojson_line(line)?, kind == "Polygon");
+            }
+        }
+        "MultiPolygon" => {
+            for polygon in geojson_list(coordinates()?)? {
+                for ring in geojson_list(polygon)? {
+                    geometry.push(geojson_line(ring)?, true);
+                }
+            }
+        }
+        "Point" | "MultiPoint" => {}
+        _ => anyhow::bail!("unknown GeoJSON type: {kind}"),
+    }
+    Ok(())
+}
+
+fn geojson_list(value: &Value) -> Result<&Vec<Value>> {
+    value.as_array().context("expected a list of coordinates")
+}
+
+/// The points of a line of GeoJSON coordinates, with y pointing down.
+fn geojson_line(value: &Value) -> Result<Vec<DVec2>> {
+    geojson_list(value)?
+        .iter()
+        .map(|position| {
+            let xy = position
+                .as_array()
+                .filter(|xy| xy.len() >= 2)
+                .and_then(|xy| {
+                    Some(DVec2::new(xy[0].as_f64()?, xy[1].as_f64()?))
+                })
+                .with_context(|| {
+                    format!("expected [x, y], found {position}")
+                })?;
+            Ok(DVec2::new(xy.x, -xy.y))
+        })
+        .collect()
+}
+
+//===================================================================
+// CSV
+//===================================================================
+
+fn csv(text: &str) -> Result<Geometry> {
+    let mut geometry = Geometry::default();
+    let mut points = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            geometry.push(std::mem::take(&mut points), false);
+            continue;
+        }
+
+        let point = line
+            .split([',', ';', '\t'])
+            .map(|n| f64::from_str(n.trim()))
+            .collect::<Result<Vec<_>, _>>();
+        match point.as_deref() {
+            Ok(&[x, y, ..]) => points.push(DVec2::new(x, y)),
+            _ if i == 0 => {}
+            _ => anyhow::bail!("expected x,y on line {}: {line}", i + 1),
+        }
+    }
+    geometry.push(points, false);
+    Ok(geometry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::polyline::Polyline;
+
+    #[test]
+    fn geometry_is_read_from_svg_geojson_and_csv() {
+        let svg = svg(r#"
+            <svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 100 100">
+              <g><path d="M0,0 h10 v10 z"/></g>
+              <polyline points="0,0 10,0 20,10" />
+              <rect x='5' y='5' width="10" height="20"/>
+              <circle cx="50" cy="50" r="10"/>
+              <text>not an outline</text>
+            </svg>
+        "#)
+        .unwrap();
+        let closed = svg.outlines.iter().map(|(_, c)| *c).collect::<Vec<_>>();
+        assert_eq!(closed, [true, false, true, true]);
+        assert_eq!(svg.outlines[2].0[2], DVec2::new(15., 25.));
+        assert_eq!(svg.outlines[3].0.len(), CIRCLE_CORNERS);
+
+        let geojson = geojson(
+            r#"{
+            "type": "FeatureCollection",
+            "features": [
+                {"type": "Feature", "geometry": {
+                    "type": "Polygon",
+                    "coordinates": [[[0, 0], [4, 0], [4, 3], [0, 0]]]
+                }},
+                {"type": "Feature", "geometry": {
+                    "type": "LineString", "coordinates": [[0, 0], [1, 1]]
+                }},
+                {"type": "Feature", "geometry": {
+                    "type": "Point", "coordinates": [5, 5]
+                }}
+            ]
+        }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            geojson.outlines,
+            [
+                (
+                    vec![DVec2::ZERO, DVec2::new(4., 0.), DVec2::new(4., -3.)],
+                    true
+                ),
+                (vec![DVec2::ZERO, DVec2::new(1., -1.)], false),
+            ]
+        );
+
+        let csv = csv("x,y\n0,0\n1,0\n1,1\n0,0\n\n5;5\n6;6\n").unwrap();
+        assert_eq!(csv.outlines.len(), 2);
+        assert!(csv.outlines[0].1, "ends where it starts");
+        assert_eq!(csv.outlines[0].0.len(), 3);
+        assert!(super::csv("0,0\nnot a point\n").is_err());
+    }
+
+    #[test]
+    fn placements_fit_center_and_stretch() {
+        let mut geometry = Geometry::default();
+        geometry.push(
+            vec![
+                DVec2::new(10., 10.),
+                DVec2::new(30., 10.),
+                DVec2::new(30., 20.),
+                DVec2::new(10., 20.),
+            ],
+            true,
+        );
+        let bounds = |placement: Placement| {
+            let shapes = geometry.place(&placement);
+            let points = shapes[0].points().collect::<Vec<_>>();
+            let min = points.iter().fold(DVec2::INFINITY, |m, &p| m.min(p));
+            let max =
+                points.iter().fold(DVec2::NEG_INFINITY, |m, &p| m.max(p));
+            (min.round(), max.round())
+        };
+
+        // 2:1 into the 800 by 600 canvas, less a margin of 20
+        assert_eq!(
+            bounds(Placement::DEFAULT),
+            (DVec2::new(20., 110.), DVec2::new(780., 490.))
+        );
+        assert_eq!(
+            bounds(Placement {
+                preserve_aspect: false,
+                ..Placement::DEFAULT
+            }),
+            (DVec2::new(20., 20.), DVec2::new(780., 580.))
+        );
+        assert_eq!(
+            bounds(Placement {
+                center: false,
+                ..Placement::DEFAULT
+            }),
+            (DVec2::new(20., 20.), DVec2::new(780., 400.))
+        );
+        assert_eq!(
+            bounds(Placement {
+                fit: Fit::Keep,
+                ..Placement::DEFAULT
+            }),
+            (DVec2::new(390., 295.), DVec2::new(410., 305.))
+        );
+
+        let (min, max) = bounds(Placement {
+            fit: Fit::UnitSquare,
+            margin: 0.,
+            ..Placement::DEFAULT
+        });
+        let unit = |p| Transform::DEFAULT.document_to_unit(p);
+        assert!(unit(min).cmpge(DVec2::splat(-1e-2)).all());
+        assert!(unit(max).cmple(DVec2::splat(1. + 1e-2)).all());
+        assert!((unit(max).x - unit(min).x - 1.).abs() < 1e-2);
+    }
+}
@@ -0,0 +1,216 @@
+use std::{cell::Cell, path::Path, rc::Rc};
+
+use gtk::{gio, glib, prelude::*};
+
+use super::{
+    app_state::SharedState,
+    export::{self, DOCUMENT_DPI},
+    import::{Fit, Geometry, Placement},
+    render::texture,
+    scene::Scene,
+    seed_menu::spin,
+    style::STYLE,
+    transform::DOCUMENT_SIZE,
+    window::show_error,
+};
+
+/// Width of the preview in pixels.
+const PREVIEW_WIDTH: f64 = 480.;
+
+/// Choices of where the geometry is fit, in the order of the dropdown.
+const FITS: [(&str, Fit); 3] = [
+    ("Canvas", Fit::Canvas),
+    ("Unit square", Fit::UnitSquare),
+    ("Keep size", Fit::Keep),
+];
+
+/// Ask for an SVG, GeoJSON or CSV file, and add its geometry to the
+/// drawing of `state` once it is placed.
+pub(crate) fn import(window: Option<gtk::Window>, state: SharedState) {
+    let filter = gtk::FileFilter::new();
+    filter.set_name(Some("Geometry files"));
+    for suffix in ["svg", "geojson", "json", "csv"] {
+        filter.add_suffix(suffix);
+    }
+    let filters = gio::ListStore::new::<gtk::FileFilter>();
+    filters.append(&filter);
+
+    let dialog = gtk::FileDialog::builder()
+        .title("Import geometry")
+        .filters(&filters)
+        .build();
+    dialog.open(
+        window.clone().as_ref(),
+        None::<&gio::Cancellable>,
+        move |result| {
+            let Some(path) = result.ok().and_then(|file| file.path()) else {
+                return;
+            };
+            match Geometry::load(&path) {
+                Ok(geometry) => place(window.as_ref(), state, &path, geometry),
+                Err(err) => {
+                    tracing::error!("{err:#}");
+                    show_error(
+                        window.as_ref(),
+                        "Import failed",
+                        format!("{err:#}"),
+                    );
+                }
+            }
+        },
+    );
+}
+
+/// Dialog placing `geometry` on the canvas, showing it over the drawing of
+/// `state` as it will be placed, and adding it to the drawing as seeds once
+/// the placement is confirmed.
+fn place(
+    parent: Option<&gtk::Window>,
+    state: SharedState,
+    path: &Path,
+    geometry: Geometry,
+) {
+    let grid = gtk::Grid::builder()
+        .row_spacing(4)
+        .column_spacing(8)
+        .build();
+
+    grid.attach(
+        &gtk::Label::builder().label("Fit to").xalign(0.).build(),
+        0,
+        0,
+        1,
+        1,
+    );
+    let fit = gtk::DropDown::from_strings(&FITS.map(|(label, _)| label));
+    grid.attach(&fit, 1, 0, 1, 1);
+    let center = gtk::CheckButton::builder()
+        .label("Center")
+        .active(Placement::DEFAULT.center)
+        .build();
+    grid.attach(&center, 0, 1, 2, 1);
+    let preserve_aspect = gtk::CheckButton::builder()
+        .label("Preserve aspect ratio")
+        .active(Placement::DEFAULT.preserve_aspect)
+        .build();
+    grid.attach(&preserve_aspect, 0, 2, 2, 1);
+    let margin = spin(
+        &grid,
+        3,
+        "Margin",
+        (0., 300., 5.),
+        Placement::DEFAULT.margin,
+    );
+
+    let preview = gtk::Picture::builder()
+        .width_request(PREVIEW_WIDTH as i32)
+        .height_request(
+            (PREVIEW_WIDTH * DOCUMENT_SIZE.y / DOCUMENT_SIZE.x) as i32,
+        )
+        .build();
+
+    let cancel = gtk::Button::with_label("Cancel");
+    let import = gtk::Button::with_label("Import");
+    import.add_css_class("suggested-action");
+    let buttons = gtk::Box::new(gtk::Orientation::Horizontal, 8);
+    buttons.set_halign(gtk::Align::End);
+    buttons.append(&cancel);
+    buttons.append(&import);
+
+    let content = gtk::Box::new(gtk::Orientation::Vertical, 8);
+    content.set_margin_top(12);
+    content.set_margin_bottom(12);
+    content.set_margin_start(12);
+    content.set_margin_end(12);
+    content.append(&grid);
+    content.append(&preview);
+    content.append(&buttons);
+
+    let title = match path.file_name() {
+        Some(name) => format!("Import {}", name.to_string_lossy()),
+        None => "Import".to_owned(),
+    };
+    let dialog = gtk::Window::builder()
+        .title(title)
+        .modal(true)
+        .resizable(false)
+        .child(&content)
+        .build();
+    dialog.set_transient_for(parent);
+
+    let geometry = Rc::new(geometry);
+    let placement = Rc::new(Cell::new(Placement::DEFAULT));
+
+    // place the geometry as set, and show it over the drawing
+    let update = Rc::new(glib::clone!(
+        #[strong]
+        state,
+        #[strong]
+        geometry,
+        #[strong]
+        placement,
+        #[weak]
+        fit,
+        #[weak]
+        center,
+        #[weak]
+        preserve_aspect,
+        #[weak]
+        margin,
+        #[weak]
+        preview,
+        move || {
+            let set = Placement {
+                fit: FITS[fit.selected() as usize].1,
+                center: center.is_active(),
+                preserve_aspect: preserve_aspect.is_active(),
+                margin: margin.value(),
+            };
+            placement.set(set);
+
+            let mut shapes = state.borrow().shapes().to_vec();
+            shapes.extend(geometry.place(&set));
+            let scene = Scene::frame(&STYLE.read().unwrap(), &shapes, None);
+            let dpi = DOCUMENT_DPI * PREVIEW_WIDTH / scene.size.x;
+            match export::rasterize(&scene, dpi) {
+                Ok(image) => preview.set_paintable(Some(&texture(image))),
+                Err(err) => tracing::warn!("failed to draw preview: {err:#}"),
+            }
+        }
+    ));
+    update();
+
+    fit.connect_selected_notify(glib::clone!(
+        #[strong]
+        update,
+        move |_| update()
+    ));
+    center.connect_toggled(glib::clone!(
+        #[strong]
+        update,
+        move |_| update()
+    ));
+    preserve_aspect.connect_toggled(glib::clone!(
+        #[strong]
+        update,
+        move |_| update()
+    ));
+    margin.connect_value_changed(move |_| update());
+
+    cancel.connect_clicked(glib::clone!(
+        #[weak]
+        dialog,
+        move |_| dialog.close()
+    ));
+    import.connect_clicked(glib::clone!(
+        #[weak]
+        dialog,
+        move |_| {
+            let shapes = geometry.place(&placement.get());
+            state.borrow_mut().shapes_mut().extend(shapes);
+            dialog.close();
+        }
+    ));
+
+    dialog.present();
+}
@@ -0,0 +1,263 @@
+//! Long-running work started from the window, as exports and video
+//! encodes, done in the background one job at a time so that the window
+//! keeps responding.
+
+use std::sync::{
+    Arc, LazyLock, Mutex,
+    atomic::{AtomicBool, AtomicU64, Ordering},
+    mpsc,
+};
+
+use anyhow::Result;
+
+/// Where a job is in the queue.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Status {
+    Queued,
+    Running,
+    /// Done, with a message saying what was done.
+    Finished(String),
+    Failed(String),
+    Cancelled,
+}
+
+impl Status {
+    /// Whether the job will not run any more.
+    pub(crate) fn is_done(&self) -> bool {
+        matches!(self, Self::Finished(_) | Self::Failed(_) | Self::Cancelled)
+    }
+}
+
+struct JobInner {
+    id: u64,
+    title: String,
+    status: Mutex<Status>,
+    /// Steps done and steps in all, or 0 if the number is not known.
+    done: AtomicU64,
+    total: AtomicU64,
+    cancelled: AtomicBool,
+    /// Whether the job being done has been reported by
+    /// [`JobQueue::take_done`].
+    reported: AtomicBool,
+}
+
+/// A job in a [`JobQueue`], shared by the queue, the work done for it, and
+/// whatever shows it.
+#[derive(Clone)]
+pub(crate) struct Job(Arc<JobInner>);
+
+impl Job {
+    pub(crate) fn id(&self) -> u64 {
+        self.0.id
+    }
+
+    pub(crate) fn title(&self) -> &str {
+        &self.0.title
+    }
+
+    pub(crate) fn status(&self) -> Status {
+        self.0.status.lock().unwrap().clone()
+    }
+
+    fn set_status(&self, status: Status) {
+        *self.0.status.lock().unwrap() = status;
+    }
+
+    /// Steps done, and the number of steps in all if it is known.
+    pub(crate) fn progress(&self) -> (u64, Option<u64>) {
+        let total = self.0.total.load(Ordering::Relaxed);
+        (
+            self.0.done.load(Ordering::Relaxed),
+            (total > 0).then_some(total),
+        )
+    }
+
+    /// Report that `done` steps of `total`, if known, are done.
+    pub(crate) fn set_progress(&self, done: u64, total: Option<u64>) {
+        self.0.done.store(done, Ordering::Relaxed);
+        self.0.total.store(total.unwrap_or(0), Ordering::Relaxed);
+    }
+
+    /// Ask the job to stop. A queued job does not run, and a running one
+    /// stops once its work checks [`Job::check_cancelled`].
+    pub(crate) fn cancel(&self) {
+        self.0.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.0.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Fail if the job was cancelled, for the work to stop with `?`.
+    pub(crate) fn check_cancelled(&self) -> Result<()> {
+        anyhow::ensure!(!self.is_cancelled(), "cancelled");
+        Ok(())
+    }
+}
+
+/// The work of a job, given the job to report progress to and check for
+/// cancellation, which returns what was done.
+type Work = Box<dyn FnOnce(&Job) -> Result<String> + Send>;
+
+/// Jobs run in the order they are added, one at a time, on a thread of
+/// their own.
+pub(crate) struct JobQueue {
+    jobs: Mutex<Vec<Job>>,
+    next_id: AtomicU64,
+    sender: mpsc::Sender<(Job, Work)>,
+}
+
+impl JobQueue {
+    pub(crate) fn new() -> Self {
+        let (sender, receiver) = mpsc::channel::<(Job, Work)>();
+
+        std::thread::spawn(move || {
+            for (job, work) in receiver {
+                if job.is_cancelled() {
+                    job.set_status(Status::Cancelled);
+                    continue;
+                }
+
+                job.set_status(Status::Running);
+                let status = match work(&job) {
+                    Ok(message) => Status::Finished(message),
+                    Err(_) if job.is_cancelled() => Status::Cancelled,
+                    Err(err) => {
+                        tracing::error!("{}: {err:#}", job.title());
+                        Status::Failed(format!("{err:#}"))
+                    }
+                };
+                job.set_status(status);
+            }
+        });
+
+        Self {
+            jobs: Mutex::new(Vec::new()),
+            next_id: AtomicU64::new(0),
+            sender,
+        }
+    }
+
+    /// Queue `work` as a job named `title`.
+    pub(crate) fn add(
+        &self,
+        title: impl Into<String>,
+        work: impl FnOnce(&Job) -> Result<String> + Send + 'static,
+    ) -> Job {
+        let job = Job(Arc::new(JobInner {
+            id: self.next_id.fetch_add(1, Ordering::Relaxed),
+            title: title.into(),
+            status: Mutex::new(Status::Queued),
+            done: AtomicU64::new(0),
+            total: AtomicU64::new(0),
+            cancelled: AtomicBool::new(false),
+            reported: AtomicBool::new(false),
+        }));
+        tracing::info!("queued {}", job.title());
+
+        self.jobs.lock().unwrap().push(job.clone());
+        self.sender
+            .send((job.clone(), Box::new(work)))
+            .expect("the job thread runs as long as the queue");
+        job
+    }
+
+    /// Every job, in the order they were added, until they are cleared.
+    pub(crate) fn jobs(&self) -> Vec<Job> {
+        self.jobs.lock().unwrap().clone()
+    }
+
+    /// Jobs that are done and were not returned by this before.
+    pub(crate) fn take_done(&self) -> Vec<Job> {
+        self.jobs()
+            .into_iter()
+            .filter(|job| {
+                job.status().is_done()
+                    && !job.0.reported.swap(true, Ordering::Relaxed)
+            })
+            .collect()
+    }
+
+    /// Forget the jobs that are done.
+    pub(crate) fn clear_done(&self) {
+        self.jobs
+            .lock()
+            .unwrap()
+            .retain(|job| !job.status().is_done());
+    }
+}
+
+/// The jobs of every window.
+pub(crate) static JOBS: LazyLock<JobQueue> = LazyLock::new(JobQueue::new);
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::*;
+
+    fn wait_until_done(job: &Job) -> Status {
+        let start = Instant::now();
+        loop {
+            let status = job.status();
+            if status.is_done() {
+                return status;
+            }
+            assert!(start.elapsed() < Duration::from_secs(5), "job hangs");
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn jobs_report_what_they_did() {
+        let queue = JobQueue::new();
+        let ok = queue.add("ok", |job| {
+            job.set_progress(1, Some(1));
+            Ok("did it".to_owned())
+        });
+        let failing = queue.add("failing", |_| anyhow::bail!("no disk"));
+
+        assert_eq!(
+            wait_until_done(&ok),
+            Status::Finished("did it".to_owned())
+        );
+        assert_eq!(ok.progress(), (1, Some(1)));
+        assert_eq!(
+            wait_until_done(&failing),
+            Status::Failed("no disk".to_owned())
+        );
+
+        let done = queue.take_done();
+        assert_eq!(done.len(), 2);
+        assert!(queue.take_done().is_empty());
+        queue.clear_done();
+        assert!(queue.jobs().is_empty());
+    }
+
+    #[test]
+    fn cancelled_jobs_stop() {
+        let queue = JobQueue::new();
+        let (unblock, blocked) = mpsc::channel::<()>();
+        let running = queue.add("running", move |job| {
+            blocked.recv()?;
+            job.check_cancelled()?;
+            Ok(String::new())
+        });
+        let ran = Arc::new(AtomicBool::new(false));
+        let queued = queue.add("queued", {
+            let ran = ran.clone();
+            move |_| {
+                ran.store(true, Ordering::Relaxed);
+                Ok(String::new())
+            }
+        });
+
+        queued.cancel();
+        running.cancel();
+        unblock.send(()).unwrap();
+
+        assert_eq!(wait_until_done(&running), Status::Cancelled);
+        assert_eq!(wait_until_done(&queued), Status::Cancelled);
+        assert!(!ran.load(Ordering::Relaxed));
+    }
+}
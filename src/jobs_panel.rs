@@ -0,0 +1,385 @@
+use std::{cell::RefCell, fmt::Write, ops::ControlFlow, path::Path, rc::Rc};
+
+use gtk::{gio, glib, prelude::*};
+
+use super::{
+    algorithm::{ONE, RunComparison},
+    app_state::SharedState,
+    export::{self, Crop, DOCUMENT_DPI, ImageFormat},
+    jobs::{JOBS, Job, Status},
+    layers::LAYERS,
+    params_panel::PARAMS,
+    recorder::{self, RECORD_OPTIONS, Recorder, Steering},
+    seed_menu::spin,
+    simulation::{STOP_CONDITIONS, SimulationController, StopConditions},
+    style::STYLE,
+};
+
+/// Cells per side of the unit square that the growth of each seed of a
+/// sweep is rasterized to, to compare it with that of the first seed.
+const COMPARISON_RESOLUTION: usize = 500;
+
+/// How often the panel and notifications follow the jobs.
+const POLL_INTERVAL: std::time::Duration =
+    std::time::Duration::from_millis(200);
+
+/// A job in the list of the panel.
+struct JobRow {
+    job: Job,
+    progress: gtk::ProgressBar,
+    status: gtk::Label,
+    cancel: gtk::Button,
+}
+
+impl JobRow {
+    fn new(job: Job, list: &gtk::Box) -> Self {
+        let row = gtk::Box::new(gtk::Orientation::Vertical, 2);
+        row.append(
+            &gtk::Label::builder().label(job.title()).xalign(0.).build(),
+        );
+        let progress = gtk::ProgressBar::new();
+        row.append(&progress);
+        let status = gtk::Label::builder().xalign(0.).wrap(true).build();
+        row.append(&status);
+        let cancel = gtk::Button::with_label("Cancel");
+        cancel.connect_clicked(glib::clone!(
+            #[strong]
+            job,
+            move |_| job.cancel()
+        ));
+        row.append(&cancel);
+        list.append(&row);
+
+        let row = Self {
+            job,
+            progress,
+            status,
+            cancel,
+        };
+        row.update();
+        row
+    }
+
+    fn update(&self) {
+        let (done, total) = self.job.progress();
+        let status = self.job.status();
+        let text = match (&status, total) {
+            (Status::Queued, _) => "Queued".to_owned(),
+            (Status::Running, Some(total)) => format!("{done} of {total}"),
+            (Status::Running, None) => format!("{done} iterations"),
+            (Status::Finished(message), _) => message.clone(),
+            (Status::Failed(err), _) => format!("Failed: {err}"),
+            (Status::Cancelled, _) => "Cancelled".to_owned(),
+        };
+        self.status.set_label(&text);
+
+        match (&status, total) {
+            (Status::Finished(_), _) => self.progress.set_fraction(1.),
+            (_, Some(total)) => {
+                self.progress.set_fraction(done as f64 / total as f64)
+            }
+            (Status::Running, None) => self.progress.pulse(),
+            _ => {}
+        }
+        self.cancel
+            .set_sensitive(!status.is_done() && !self.job.is_cancelled());
+    }
+}
+
+/// Ask for a video file and queue a job that grows the drawing of `state`
+/// from its seeds with the current parameters and encodes the growth to
+/// it, cut down to its region if it has one.
+fn encode_video(window: Option<gtk::Window>, state: SharedState) {
+    let dialog = gtk::FileDialog::builder()
+        .title("Encode video")
+        .initial_name("growth.mp4")
+        .build();
+    dialog.save(window.as_ref(), None::<&gio::Cancellable>, move |result| {
+        let Some(path) = result.ok().and_then(|file| file.path()) else {
+            return;
+        };
+        let state = state.borrow();
+        let project = state.project();
+        let crop = state.region().map(Crop::region);
+        let params = *PARAMS.read().unwrap();
+        let every = RECORD_OPTIONS.read().unwrap().every;
+        let stop = *STOP_CONDITIONS.read().unwrap();
+
+        let title = format!("Encode {}", file_name(&path));
+        JOBS.add(title, move |job| {
+            let mut recorder = Recorder::video(&path, every, crop)?;
+            recorder::grow_headless(
+                &project,
+                &params,
+                Steering::default(),
+                stop,
+                |controller| {
+                    job.check_cancelled()?;
+                    job.set_progress(controller.iteration(), None);
+                    recorder.capture(
+                        controller.iteration(),
+                        &project.shapes,
+                        controller.simulation().unwrap(),
+                    )?;
+                    Ok(ControlFlow::Continue(()))
+                },
+            )?;
+            let path = recorder.finish()?;
+            Ok(format!("Encoded {}", path.display()))
+        });
+    });
+}
+
+/// Ask for a directory and queue a job that grows the drawing of `state`
+/// with each seed from 1 to `seeds` for up to `max_iterations`, or until it
+/// stops if 0, and writes an image of each to `seed_1.png` and so on. The
+/// other stop conditions of the window apply too.
+///
+/// How far the growth of each other seed is from that of seed 1, as the
+/// Hausdorff distance of their vertices in units of [`ONE`] and the overlap
+/// of their edges, is written to `comparison.csv`, and its average shown
+/// once the job is done, for how much the drawing depends on the seed.
+fn sweep_seeds(
+    window: Option<gtk::Window>,
+    state: SharedState,
+    seeds: u64,
+    max_iterations: u64,
+) {
+    let dialog = gtk::FileDialog::builder().title("Sweep seeds").build();
+    dialog.select_folder(
+        window.as_ref(),
+        None::<&gio::Cancellable>,
+        move |result| {
+            let Some(dir) = result.ok().and_then(|file| file.path()) else {
+                return;
+            };
+            let state = state.borrow();
+            let mut project = state.project();
+            let crop = state.region().map(Crop::region);
+            let params = *PARAMS.read().unwrap();
+            let style = STYLE.read().unwrap().clone();
+            let canvas = LAYERS.read().unwrap().clone();
+            let stop = *STOP_CONDITIONS.read().unwrap();
+            let stop = StopConditions {
+                max_iterations: (max_iterations > 0)
+                    .then_some(max_iterations)
+                    .or(stop.max_iterations),
+                ..stop
+            };
+
+            let title = format!("Sweep {seeds} seeds to {}", file_name(&dir));
+            JOBS.add(title, move |job| {
+                let mut first = None;
+                let mut comparison = String::from("seed,hausdorff,overlap\n");
+                let mut total = RunComparison {
+                    hausdorff: 0.,
+                    overlap: 0.,
+                };
+                for seed in 1..=seeds {
+                    job.set_progress(seed - 1, Some(seeds));
+                    project.seed = Some(seed);
+                    let controller = recorder::grow_headless(
+                        &project,
+                        &params,
+                        Steering::default(),
+                        stop,
+                        |_| {
+                            job.check_cancelled()?;
+                            Ok(ControlFlow::Continue(()))
+                        },
+                    )?;
+                    let scene = export::frame(
+                        &style,
+                        &canvas,
+                        &project.shapes,
+                        controller.simulation(),
+                        crop.as_ref(),
+                    );
+                    export::write_image(
+                        &dir.join(format!("seed_{seed}.png")),
+                        ImageFormat::Png,
+                        DOCUMENT_DPI,
+                        &scene,
+                    )?;
+
+                    let compared = first
+                        .as_ref()
+                        .and_then(SimulationController::simulation)
+                        .zip(controller.simulation());
+                    match compared {
+                        Some((first, simulation)) => {
+                            let RunComparison { hausdorff, overlap } =
+                                simulation
+                                    .compare(first, COMPARISON_RESOLUTION);
+                            total.hausdorff += hausdorff;
+                            total.overlap += overlap;
+                            writeln!(
+                                comparison,
+                                "{seed},{},{overlap}",
+                                hausdorff / ONE
+                            )?;
+                        }
+                        None => first = Some(controller),
+                    }
+                }
+                job.set_progress(seeds, Some(seeds));
+
+                let written =
+                    format!("Wrote {seeds} images to {}", dir.display());
+                if seeds < 2 {
+                    return Ok(written);
+                }
+                std::fs::write(dir.join("comparison.csv"), comparison)?;
+                let n = (seeds - 1) as f64;
+                Ok(format!(
+                    "{written}, apart from seed 1 by {:.1} and overlapping \
+                     it by {:.0}% on average",
+                    total.hausdorff / n / ONE,
+                    total.overlap / n * 100.,
+                ))
+            });
+        },
+    );
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .unwrap_or(path.as_os_str())
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Button with a popover listing the background jobs of every window, with
+/// their progress and a button to cancel each, and buttons that queue a
+/// video encode or a seed sweep of the drawing of `state`.
+///
+/// The label of the button counts the jobs not done yet.
+pub(crate) fn build(state: &SharedState) -> gtk::MenuButton {
+    let content = gtk::Box::new(gtk::Orientation::Vertical, 8);
+
+    let encode = gtk::Button::with_label("Encode video…");
+    content.append(&encode);
+
+    let grid = gtk::Grid::builder()
+        .row_spacing(4)
+        .column_spacing(8)
+        .build();
+    let seeds = spin(&grid, 0, "Seeds", (1., 100., 1.), 8.);
+    let iterations = spin(&grid, 1, "Iterations", (0., 100_000., 100.), 2000.);
+    let sweep = gtk::Button::with_label("Sweep seeds…");
+    grid.attach(&sweep, 0, 2, 2, 1);
+    content.append(&grid);
+
+    content.append(&gtk::Separator::new(gtk::Orientation::Horizontal));
+    let list = gtk::Box::new(gtk::Orientation::Vertical, 8);
+    content.append(&list);
+    let clear = gtk::Button::with_label("Clear finished");
+    content.append(&clear);
+
+    let popover = gtk::Popover::builder().child(&content).build();
+    let button = gtk::MenuButton::builder()
+        .label("Jobs")
+        .popover(&popover)
+        .build();
+
+    encode.connect_clicked(glib::clone!(
+        #[weak]
+        popover,
+        #[strong]
+        state,
+        move |encode| {
+            popover.popdown();
+            let window = encode.root().and_downcast::<gtk::Window>();
+            encode_video(window, state.clone());
+        }
+    ));
+    sweep.connect_clicked(glib::clone!(
+        #[weak]
+        popover,
+        #[strong]
+        state,
+        move |sweep| {
+            popover.popdown();
+            let window = sweep.root().and_downcast::<gtk::Window>();
+            sweep_seeds(
+                window,
+                state.clone(),
+                seeds.value_as_int() as u64,
+                iterations.value_as_int() as u64,
+            );
+        }
+    ));
+    clear.connect_clicked(|_| JOBS.clear_done());
+
+    // rows are made again only when jobs are added or cleared
+    let rows = Rc::new(RefCell::new(Vec::<JobRow>::new()));
+    glib::timeout_add_local(
+        POLL_INTERVAL,
+        glib::clone!(
+            #[weak]
+            button,
+            #[weak]
+            list,
+            #[upgrade_or]
+            glib::ControlFlow::Break,
+            move || {
+                let jobs = JOBS.jobs();
+                let mut rows = rows.borrow_mut();
+                let same = rows.len() == jobs.len()
+                    && rows
+                        .iter()
+                        .zip(&jobs)
+                        .all(|(r, j)| r.job.id() == j.id());
+                if same {
+                    rows.iter().for_each(JobRow::update);
+                } else {
+                    while let Some(child) = list.first_child() {
+                        list.remove(&child);
+                    }
+                    *rows = jobs
+                        .into_iter()
+                        .map(|job| JobRow::new(job, &list))
+                        .collect();
+                }
+
+                let pending =
+                    rows.iter().filter(|r| !r.job.status().is_done()).count();
+                button.set_label(&match pending {
+                    0 => "Jobs".to_owned(),
+                    n => format!("Jobs ({n})"),
+                });
+                glib::ControlFlow::Continue
+            }
+        ),
+    );
+
+    button
+}
+
+/// Send a desktop notification from `app` whenever a job finishes or
+/// fails.
+pub(crate) fn notify_when_done(app: &gtk::Application) {
+    glib::timeout_add_local(
+        POLL_INTERVAL,
+        glib::clone!(
+            #[weak]
+            app,
+            #[upgrade_or]
+            glib::ControlFlow::Break,
+            move || {
+                for job in JOBS.take_done() {
+                    let body = match job.status() {
+                        Status::Finished(message) => message,
+                        Status::Failed(err) => format!("Failed: {err}"),
+                        _ => continue,
+                    };
+                    let notification = gio::Notification::new(job.title());
+                    notification.set_body(Some(&body));
+                    let id = format!("job-{}", job.id());
+                    app.send_notification(Some(&id), &notification);
+                }
+                glib::ControlFlow::Continue
+            }
+        ),
+    );
+}
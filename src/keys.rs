@@ -0,0 +1,544 @@
+//! The keys that do things in the window, which can be bound to other keys
+//! in a config file. Each is the accelerator of a GTK action, which the
+//! menu of the window triggers too.
+
+use std::{fmt, str::FromStr};
+#[cfg(feature = "gui")]
+use std::{
+    path::{Path, PathBuf},
+    sync::{LazyLock, RwLock},
+};
+
+use anyhow::{Context, Result};
+
+#[cfg(feature = "gui")]
+use super::config_dir;
+
+/// Something done by pressing a key in the window.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Action {
+    Quit,
+    NewWindow,
+    Save,
+    Open,
+    /// Replace the drawing with the seeds of a seed file.
+    LoadSeeds,
+    /// Add the geometry of an SVG, GeoJSON or CSV file to the drawing.
+    Import,
+    /// Write the drawing and its growth to an image.
+    Export,
+    /// Put the drawing and its growth on the clipboard, as a PNG image and
+    /// as SVG.
+    CopyImage,
+    Record,
+    RecordFrames,
+    /// Run a script that seeds, grows and exports drawings.
+    RunScript,
+    /// Remove every shape, seed and field.
+    Clear,
+    /// Remove the last drawn shape.
+    Undo,
+    /// Replace the growth with shapes of its paths, a layer for each
+    /// segment.
+    BakeSegments,
+    /// Replace the growth with shapes of its paths, a layer for each
+    /// generation of branches.
+    BakeGenerations,
+    /// Replace the growth with obstacle shapes of its paths, for the next
+    /// growth to grow around it.
+    BakeObstacles,
+    ResetView,
+    Play,
+    PlayFast,
+    /// Turn the shape selected with the select tool counterclockwise.
+    RotateSelectionLeft,
+    /// Turn the selected shape clockwise.
+    RotateSelectionRight,
+    /// Scale the selected shape up about its center.
+    GrowSelection,
+    /// Scale the selected shape down about its center.
+    ShrinkSelection,
+    PauseSim,
+    StepSim,
+    RewindSim,
+    ResetSim,
+    SlowerSim,
+    FasterSim,
+    /// Show or hide the stats of the simulation over the drawing.
+    ToggleStats,
+    /// Push the growth away from the cursor, or pull it in with Shift held.
+    ToggleSculpt,
+    /// Start recording a macro, or discard the one being recorded.
+    RecordMacro,
+    /// Play the macro kept in the first slot, or keep the one being
+    /// recorded there.
+    PlayMacro1,
+    PlayMacro2,
+    PlayMacro3,
+    PlayMacro4,
+}
+
+impl Action {
+    pub(crate) const ALL: [Self; 36] = [
+        Self::Quit,
+        Self::NewWindow,
+        Self::Save,
+        Self::Open,
+        Self::LoadSeeds,
+        Self::Import,
+        Self::Export,
+        Self::CopyImage,
+        Self::Record,
+        Self::RecordFrames,
+        Self::RunScript,
+        Self::Clear,
+        Self::Undo,
+        Self::BakeSegments,
+        Self::BakeGenerations,
+        Self::BakeObstacles,
+        Self::ResetView,
+        Self::Play,
+        Self::PlayFast,
+        Self::RotateSelectionLeft,
+        Self::RotateSelectionRight,
+        Self::GrowSelection,
+        Self::ShrinkSelection,
+        Self::PauseSim,
+        Self::StepSim,
+        Self::RewindSim,
+        Self::ResetSim,
+        Self::SlowerSim,
+        Self::FasterSim,
+        Self::ToggleStats,
+        Self::ToggleSculpt,
+        Self::RecordMacro,
+        Self::PlayMacro1,
+        Self::PlayMacro2,
+        Self::PlayMacro3,
+        Self::PlayMacro4,
+    ];
+
+    /// Name of the action in key files.
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Self::Quit => "quit",
+            Self::NewWindow => "new-window",
+            Self::Save => "save",
+            Self::Open => "open",
+            Self::LoadSeeds => "load-seeds",
+            Self::Import => "import",
+            Self::Export => "export",
+            Self::CopyImage => "copy-image",
+            Self::Record => "record",
+            Self::RecordFrames => "record-frames",
+            Self::RunScript => "run-script",
+            Self::Clear => "clear",
+            Self::Undo => "undo",
+            Self::BakeSegments => "bake-segments",
+            Self::BakeGenerations => "bake-generations",
+            Self::BakeObstacles => "bake-obstacles",
+            Self::ResetView => "reset-view",
+            Self::Play => "play",
+            Self::PlayFast => "play-fast",
+            Self::RotateSelectionLeft => "rotate-selection-left",
+            Self::RotateSelectionRight => "rotate-selection-right",
+            Self::GrowSelection => "grow-selection",
+            Self::ShrinkSelection => "shrink-selection",
+            Self::PauseSim => "pause-sim",
+            Self::StepSim => "step-sim",
+            Self::RewindSim => "rewind-sim",
+            Self::ResetSim => "reset-sim",
+            Self::SlowerSim => "slower-sim",
+            Self::FasterSim => "faster-sim",
+            Self::ToggleStats => "toggle-stats",
+            Self::ToggleSculpt => "toggle-sculpt",
+            Self::RecordMacro => "record-macro",
+            Self::PlayMacro1 => "play-macro-1",
+            Self::PlayMacro2 => "play-macro-2",
+            Self::PlayMacro3 => "play-macro-3",
+            Self::PlayMacro4 => "play-macro-4",
+        }
+    }
+
+    /// Name of the GTK action, in its group: `app` for the application,
+    /// `win` for the window, and `canvas` and `sim` for the drawing and the
+    /// simulation of the window.
+    #[cfg(feature = "gui")]
+    pub(crate) fn detailed_name(self) -> &'static str {
+        match self {
+            Self::Quit => "app.quit",
+            Self::NewWindow => "win.new-window",
+            Self::Save => "win.save",
+            Self::Open => "win.open",
+            Self::LoadSeeds => "win.load-seeds",
+            Self::Import => "win.import",
+            Self::Export => "win.export",
+            Self::CopyImage => "win.copy-image",
+            Self::Record => "win.record",
+            Self::RecordFrames => "win.record-frames",
+            Self::RunScript => "win.run-script",
+            Self::Clear => "canvas.clear",
+            Self::Undo => "canvas.undo",
+            Self::BakeSegments => "canvas.bake-segments",
+            Self::BakeGenerations => "canvas.bake-generations",
+            Self::BakeObstacles => "canvas.bake-obstacles",
+            Self::ResetView => "canvas.reset-view",
+            Self::Play => "canvas.play",
+            Self::PlayFast => "canvas.play-fast",
+            Self::RotateSelectionLeft => "canvas.rotate-selection-left",
+            Self::RotateSelectionRight => "canvas.rotate-selection-right",
+            Self::GrowSelection => "canvas.grow-selection",
+            Self::ShrinkSelection => "canvas.shrink-selection",
+            Self::PauseSim => "sim.toggle",
+            Self::StepSim => "sim.step",
+            Self::RewindSim => "sim.rewind",
+            Self::ResetSim => "sim.reset",
+            Self::SlowerSim => "sim.slower",
+            Self::FasterSim => "sim.faster",
+            Self::ToggleStats => "sim.stats",
+            Self::ToggleSculpt => "sim.sculpt",
+            Self::RecordMacro => "win.record-macro",
+            Self::PlayMacro1 => "win.play-macro-1",
+            Self::PlayMacro2 => "win.play-macro-2",
+            Self::PlayMacro3 => "win.play-macro-3",
+            Self::PlayMacro4 => "win.play-macro-4",
+        }
+    }
+
+    /// Label of the action in menus and the shortcuts window.
+    #[cfg(feature = "gui")]
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            Self::Quit => "Quit",
+            Self::NewWindow => "New Window",
+            Self::Save => "Save Project…",
+            Self::Open => "Open Project…",
+            Self::LoadSeeds => "Load Seeds…",
+            Self::Import => "Import Geometry…",
+            Self::Export => "Export Image…",
+            Self::CopyImage => "Copy Image",
+            Self::Record => "Record Video…",
+            Self::RecordFrames => "Record Frames…",
+            Self::RunScript => "Run Script…",
+            Self::Clear => "Clear",
+            Self::Undo => "Undo",
+            Self::BakeSegments => "Bake Growth by Segment",
+            Self::BakeGenerations => "Bake Growth by Generation",
+            Self::BakeObstacles => "Bake Growth as Obstacles",
+            Self::ResetView => "Reset View",
+            Self::Play => "Play Drawing",
+            Self::PlayFast => "Play Drawing Fast",
+            Self::RotateSelectionLeft => "Rotate Selection Left",
+            Self::RotateSelectionRight => "Rotate Selection Right",
+            Self::GrowSelection => "Grow Selection",
+            Self::ShrinkSelection => "Shrink Selection",
+            Self::PauseSim => "Pause or Resume",
+            Self::StepSim => "Step",
+            Self::RewindSim => "Rewind",
+            Self::ResetSim => "Reset Simulation",
+            Self::SlowerSim => "Slower",
+            Self::FasterSim => "Faster",
+            Self::ToggleStats => "Show Stats",
+            Self::ToggleSculpt => "Sculpt with Cursor",
+            Self::RecordMacro => "Record Macro",
+            Self::PlayMacro1 => "Play Macro 1",
+            Self::PlayMacro2 => "Play Macro 2",
+            Self::PlayMacro3 => "Play Macro 3",
+            Self::PlayMacro4 => "Play Macro 4",
+        }
+    }
+
+    /// The macro slot the action plays, counting from 1, if it plays one.
+    #[cfg(feature = "gui")]
+    pub(crate) fn macro_slot(self) -> Option<usize> {
+        match self {
+            Self::PlayMacro1 => Some(1),
+            Self::PlayMacro2 => Some(2),
+            Self::PlayMacro3 => Some(3),
+            Self::PlayMacro4 => Some(4),
+            _ => None,
+        }
+    }
+
+    fn default_chords(self) -> &'static [&'static str] {
+        match self {
+            Self::Quit => &["Meta+q"],
+            Self::NewWindow => &["Ctrl+n", "Meta+n"],
+            Self::Save => &["Ctrl+s"],
+            Self::Open => &["Ctrl+o"],
+            Self::LoadSeeds => &[],
+            Self::Import => &["Ctrl+i"],
+            Self::Export => &["Ctrl+e"],
+            Self::CopyImage => &["Ctrl+c", "Meta+c"],
+            Self::Record => &["v"],
+            Self::RecordFrames => &["V"],
+            Self::RunScript => &[],
+            Self::Clear => &["BackSpace"],
+            Self::Undo => &["Ctrl+z", "Meta+z"],
+            Self::BakeSegments => &["b"],
+            Self::BakeGenerations => &["B"],
+            Self::BakeObstacles => &["Ctrl+b"],
+            Self::ResetView => &["Home"],
+            Self::Play => &["p"],
+            Self::PlayFast => &["P"],
+            Self::RotateSelectionLeft => &["Ctrl+Left"],
+            Self::RotateSelectionRight => &["Ctrl+Right"],
+            Self::GrowSelection => &["Ctrl+Up"],
+            Self::ShrinkSelection => &["Ctrl+Down"],
+            Self::PauseSim => &["space"],
+            Self::StepSim => &["period"],
+            Self::RewindSim => &["comma"],
+            Self::ResetSim => &["R"],
+            Self::SlowerSim => &["bracketleft"],
+            Self::FasterSim => &["bracketright"],
+            Self::ToggleStats => &["F1"],
+            Self::ToggleSculpt => &["s"],
+            Self::RecordMacro => &["Ctrl+m"],
+            Self::PlayMacro1 => &["F5"],
+            Self::PlayMacro2 => &["F6"],
+            Self::PlayMacro3 => &["F7"],
+            Self::PlayMacro4 => &["F8"],
+        }
+    }
+}
+
+/// Modifier keys held down with a key. Shift is not one of them, as it
+/// changes the key instead, as in `V` for Shift and `v`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Modifiers {
+    pub(crate) ctrl: bool,
+    pub(crate) alt: bool,
+    /// Command on macOS.
+    pub(crate) meta: bool,
+}
+
+/// A key pressed with modifiers, written as the modifiers and then the GDK
+/// name of the key, joined by `+`, as in `Ctrl+s` or `Meta+BackSpace`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Chord {
+    pub(crate) modifiers: Modifiers,
+    pub(crate) key: String,
+}
+
+impl fmt::Display for Chord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Modifiers { ctrl, alt, meta } = self.modifiers;
+        for (held, name) in [(ctrl, "Ctrl"), (alt, "Alt"), (meta, "Meta")] {
+            if held {
+                write!(f, "{name}+")?;
+            }
+        }
+        write!(f, "{}", self.key)
+    }
+}
+
+impl FromStr for Chord {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (modifier_names, key) = match s.rsplit_once('+') {
+            Some((modifiers, key)) => (Some(modifiers), key),
+            None => (None, s),
+        };
+        anyhow::ensure!(!key.is_empty(), "missing key: {s}");
+
+        let mut modifiers = Modifiers::default();
+        for name in modifier_names.into_iter().flat_map(|m| m.split('+')) {
+            let held = match name {
+                "Ctrl" => &mut modifiers.ctrl,
+                "Alt" => &mut modifiers.alt,
+                "Meta" | "Cmd" => &mut modifiers.meta,
+                _ => anyhow::bail!("unknown modifier: {name}"),
+            };
+            *held = true;
+        }
+
+        Ok(Self {
+            modifiers,
+            key: key.to_owned(),
+        })
+    }
+}
+
+/// The chords that do each action.
+///
+/// Key files are TOML, with the name of an action set to the chord or the
+/// list of chords that do it, as in `undo = ["Ctrl+z", "Ctrl+u"]`.
+/// Actions without a chord keep their default ones, and an empty list
+/// leaves an action without any.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Keymap {
+    bindings: Vec<(Action, Vec<Chord>)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let bindings = Action::ALL
+            .into_iter()
+            .map(|action| {
+                let chords = action
+                    .default_chords()
+                    .iter()
+                    .map(|chord| chord.parse().expect("valid default chord"))
+                    .collect();
+                (action, chords)
+            })
+            .collect();
+        Self { bindings }
+    }
+}
+
+impl Keymap {
+    /// `keys.toml` in the config directory.
+    #[cfg(feature = "gui")]
+    pub(crate) fn default_path() -> Option<PathBuf> {
+        Some(config_dir()?.join("keys.toml"))
+    }
+
+    #[cfg(feature = "gui")]
+    pub(crate) fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        text.parse()
+            .with_context(|| format!("invalid key file: {}", path.display()))
+    }
+
+    /// The chords that do `action`.
+    pub(crate) fn chords_of(&self, action: Action) -> &[Chord] {
+        self.bindings
+            .iter()
+            .find(|&&(a, _)| a == action)
+            .map_or(&[], |(_, chords)| chords)
+    }
+
+    /// Every chord that does something.
+    pub(crate) fn chords(&self) -> impl Iterator<Item = &Chord> {
+        self.bindings.iter().flat_map(|(_, chords)| chords)
+    }
+}
+
+impl fmt::Display for Keymap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (action, chords) in &self.bindings {
+            write!(f, "{} = [", action.name())?;
+            for (i, chord) in chords.iter().enumerate() {
+                let separator = if i == 0 { "" } else { ", " };
+                write!(f, "{separator}\"{chord}\"")?;
+            }
+            writeln!(f, "]")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Keymap {
+    type Err = anyhow::Error;
+
+    fn from_str(text: &str) -> Result<Self> {
+        let table = text.parse::<toml::Table>()?;
+        let mut keymap = Self::default();
+
+        for (name, value) in table {
+            let (_, chords) = keymap
+                .bindings
+                .iter_mut()
+                .find(|(action, _)| action.name() == name)
+                .with_context(|| format!("unknown action: {name}"))?;
+
+            let values = match value {
+                toml::Value::Array(values) => values,
+                value => vec![value],
+            };
+            *chords = values
+                .into_iter()
+                .map(|value| match value {
+                    toml::Value::String(chord) => chord.parse(),
+                    value => anyhow::bail!(
+                        "expected a chord, found {}",
+                        value.type_str()
+                    ),
+                })
+                .collect::<Result<_>>()
+                .with_context(|| format!("invalid chord for {name}"))?;
+        }
+
+        // a chord can only do one thing
+        let chords = keymap.chords().collect::<Vec<_>>();
+        for (i, chord) in chords.iter().enumerate() {
+            anyhow::ensure!(
+                !chords[..i].contains(chord),
+                "{chord} is bound to more than one action"
+            );
+        }
+
+        Ok(keymap)
+    }
+}
+
+/// The key bindings of the window, loaded when it opens.
+#[cfg(feature = "gui")]
+pub(crate) static KEYMAP: LazyLock<RwLock<Keymap>> =
+    LazyLock::new(|| RwLock::new(Keymap::default()));
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chord(s: &str) -> Chord {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn chords_parse_modifiers_and_key() {
+        assert_eq!(
+            chord("Ctrl+Alt+plus"),
+            Chord {
+                modifiers: Modifiers {
+                    ctrl: true,
+                    alt: true,
+                    meta: false,
+                },
+                key: "plus".to_owned(),
+            }
+        );
+        assert_eq!(chord("Cmd+q"), chord("Meta+q"));
+        assert_eq!(chord("Meta+q").to_string(), "Meta+q");
+        assert!("Hyper+q".parse::<Chord>().is_err());
+        assert!("Ctrl+".parse::<Chord>().is_err());
+    }
+
+    #[test]
+    fn defaults_are_kept_for_unbound_actions() {
+        let keymap =
+            "undo = \"Ctrl+u\"\nclear = [\"Delete\", \"Ctrl+BackSpace\"]"
+                .parse::<Keymap>()
+                .unwrap();
+
+        assert_eq!(keymap.chords_of(Action::Undo), [chord("Ctrl+u")]);
+        assert_eq!(
+            keymap.chords_of(Action::Clear),
+            [chord("Delete"), chord("Ctrl+BackSpace")]
+        );
+        assert_eq!(keymap.chords_of(Action::Quit), [chord("Meta+q")]);
+    }
+
+    #[test]
+    fn invalid_key_files_are_rejected() {
+        for text in [
+            "fly = \"f\"",
+            "quit = 1",
+            "quit = [1]",
+            "quit = \"Hyper+q\"",
+            "quit = \"Ctrl+s\"",
+            "quit =",
+        ] {
+            assert!(text.parse::<Keymap>().is_err(), "{text:?}");
+        }
+    }
+
+    #[test]
+    fn printed_keymap_parses_back() {
+        let keymap = Keymap::default();
+        assert_eq!(keymap.to_string().parse::<Keymap>().unwrap(), keymap);
+    }
+}
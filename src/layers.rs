@@ -0,0 +1,245 @@
+//! The layers of the canvas: the background, the obstacles, the sketches,
+//! which are the other drawn shapes, and the growth. Each can be hidden or
+//! drawn see-through, and all but the background, which is always at the
+//! bottom, drawn in any order. Exports are of the layers as they are shown.
+
+use std::sync::RwLock;
+#[cfg(feature = "gui")]
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use super::{
+    scene::{Color, Item, Paint},
+    shape::Shape,
+};
+
+/// A layer of the canvas.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum CanvasLayer {
+    Background,
+    /// The shapes that the growth grows around.
+    Obstacles,
+    /// The shapes that are not obstacles, which seed the growth.
+    Sketches,
+    /// The lines of the simulation.
+    Growth,
+}
+
+impl CanvasLayer {
+    /// Label of the layer in the layer list.
+    #[cfg(feature = "gui")]
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            Self::Background => "Background",
+            Self::Obstacles => "Obstacles",
+            Self::Sketches => "Sketches",
+            Self::Growth => "Growth",
+        }
+    }
+
+    /// Whether `shape` is drawn in the layer.
+    pub(crate) fn holds(self, shape: &Shape) -> bool {
+        match self {
+            Self::Obstacles => shape.obstacle(),
+            Self::Sketches => !shape.obstacle(),
+            Self::Background | Self::Growth => false,
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// How a layer is shown.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct LayerSettings {
+    pub(crate) visible: bool,
+    /// From `0.` for see-through to `1.` for as drawn.
+    pub(crate) opacity: f64,
+}
+
+impl LayerSettings {
+    const DEFAULT: Self = Self {
+        visible: true,
+        opacity: 1.,
+    };
+}
+
+/// How each layer of the canvas is shown, and the order they are drawn in.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct CanvasLayers {
+    /// The layers above the background, from the bottom up.
+    order: [CanvasLayer; 3],
+    /// The settings of each layer, in the order of [`CanvasLayer`].
+    settings: [LayerSettings; 4],
+}
+
+impl CanvasLayers {
+    /// Every layer shown as drawn, with the growth over the shapes.
+    pub(crate) const DEFAULT: Self = Self {
+        order: [
+            CanvasLayer::Obstacles,
+            CanvasLayer::Sketches,
+            CanvasLayer::Growth,
+        ],
+        settings: [LayerSettings::DEFAULT; 4],
+    };
+
+    /// The layers from the bottom up, starting with the background.
+    pub(crate) fn order(&self) -> impl Iterator<Item = CanvasLayer> {
+        std::iter::once(CanvasLayer::Background).chain(self.order)
+    }
+
+    pub(crate) fn get(&self, layer: CanvasLayer) -> LayerSettings {
+        self.settings[layer.index()]
+    }
+
+    #[cfg(any(feature = "gui", test))]
+    pub(crate) fn set(&mut self, layer: CanvasLayer, settings: LayerSettings) {
+        self.settings[layer.index()] = LayerSettings {
+            opacity: settings.opacity.clamp(0., 1.),
+            ..settings
+        };
+    }
+
+    /// Draw `layer` one further up, if it is not the top or the background.
+    #[cfg(any(feature = "gui", test))]
+    pub(crate) fn raise(&mut self, layer: CanvasLayer) {
+        if let Some(i) = self.order.iter().position(|&l| l == layer) {
+            if i + 1 < self.order.len() {
+                self.order.swap(i, i + 1);
+            }
+        }
+    }
+
+    /// Draw `layer` one further down, if it is not the bottom or the
+    /// background.
+    #[cfg(any(feature = "gui", test))]
+    pub(crate) fn lower(&mut self, layer: CanvasLayer) {
+        if let Some(i) = self.order.iter().position(|&l| l == layer) {
+            if i > 0 {
+                self.order.swap(i - 1, i);
+            }
+        }
+    }
+
+    /// `items` of `layer` as it is shown: none if it is hidden, and with
+    /// their colors see-through by its opacity otherwise.
+    pub(crate) fn show(
+        &self,
+        layer: CanvasLayer,
+        mut items: Vec<Item>,
+    ) -> Vec<Item> {
+        let settings = self.get(layer);
+        if !settings.visible {
+            return Vec::new();
+        }
+        for item in &mut items {
+            let color = match &mut item.paint {
+                #[cfg(feature = "gui")]
+                Paint::Fill(color) => color,
+                Paint::Stroke(stroke) => &mut stroke.color,
+            };
+            *color = fade(*color, settings.opacity);
+        }
+        items
+    }
+
+    /// `background` as it is shown, if it is.
+    pub(crate) fn background(&self, background: Color) -> Option<Color> {
+        let settings = self.get(CanvasLayer::Background);
+        settings.visible.then(|| fade(background, settings.opacity))
+    }
+}
+
+fn fade(color: Color, opacity: f64) -> Color {
+    color.with_alpha(color.alpha * opacity as f32)
+}
+
+/// How the layers of the canvas are shown in the window and exported.
+pub(crate) static LAYERS: RwLock<CanvasLayers> =
+    RwLock::new(CanvasLayers::DEFAULT);
+
+/// Counts the changes to [`LAYERS`], so that what is drawn with them can be
+/// kept until they change again.
+#[cfg(feature = "gui")]
+static LAYERS_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Replace [`LAYERS`] with `layers`.
+#[cfg(feature = "gui")]
+pub(crate) fn set_layers(layers: CanvasLayers) {
+    *LAYERS.write().unwrap() = layers;
+    LAYERS_GENERATION.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Number of times [`LAYERS`] has been replaced.
+#[cfg(feature = "gui")]
+pub(crate) fn layers_generation() -> u64 {
+    LAYERS_GENERATION.load(Ordering::Relaxed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene::{Path, Stroke};
+
+    #[test]
+    fn layers_are_reordered_hidden_and_faded() {
+        let mut layers = CanvasLayers::DEFAULT;
+        layers.raise(CanvasLayer::Sketches);
+        layers.raise(CanvasLayer::Sketches);
+        layers.lower(CanvasLayer::Obstacles);
+        layers.raise(CanvasLayer::Background);
+        assert_eq!(
+            layers.order().collect::<Vec<_>>(),
+            [
+                CanvasLayer::Background,
+                CanvasLayer::Obstacles,
+                CanvasLayer::Growth,
+                CanvasLayer::Sketches,
+            ]
+        );
+
+        let items = vec![Item {
+            paint: Paint::Stroke(Stroke::new(Color::rgb8(0, 0, 0), 1.)),
+            paths: vec![Path::Circle {
+                center: glam::DVec2::ZERO,
+                radius: 1.,
+            }],
+        }];
+        layers.set(
+            CanvasLayer::Growth,
+            LayerSettings {
+                visible: true,
+                opacity: 2.,
+            },
+        );
+        assert_eq!(layers.show(CanvasLayer::Growth, items.clone()), items);
+
+        layers.set(
+            CanvasLayer::Growth,
+            LayerSettings {
+                visible: true,
+                opacity: 0.25,
+            },
+        );
+        let faded = Color::rgb8(0, 0, 0).with_alpha(0.25);
+        assert_eq!(
+            layers.show(CanvasLayer::Growth, items.clone()),
+            [Item {
+                paint: Paint::Stroke(Stroke::new(faded, 1.)),
+                ..items[0].clone()
+            }]
+        );
+
+        layers.set(
+            CanvasLayer::Background,
+            LayerSettings {
+                visible: false,
+                opacity: 1.,
+            },
+        );
+        assert_eq!(layers.background(Color::rgb8(1, 2, 3)), None);
+        assert!(layers.show(CanvasLayer::Sketches, items).len() == 1);
+    }
+}
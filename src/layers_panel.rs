@@ -0,0 +1,95 @@
+use gtk::{glib, prelude::*};
+
+use super::layers::{CanvasLayer, LAYERS, LayerSettings, set_layers};
+
+/// Change the settings of `layer` with `change`.
+fn change(layer: CanvasLayer, change: impl FnOnce(&mut LayerSettings)) {
+    let mut layers = LAYERS.read().unwrap().clone();
+    let mut settings = layers.get(layer);
+    change(&mut settings);
+    layers.set(layer, settings);
+    set_layers(layers);
+}
+
+/// Fill `list` with a row for each layer of the canvas, the top one first:
+/// whether it is shown, its opacity, and buttons moving it up and down.
+fn fill(list: &gtk::Grid) {
+    while let Some(child) = list.first_child() {
+        list.remove(&child);
+    }
+
+    let layers = LAYERS.read().unwrap().clone();
+    let order = layers.order().collect::<Vec<_>>();
+    for (row, &layer) in order.iter().rev().enumerate() {
+        let row = row as i32;
+        let settings = layers.get(layer);
+
+        let visible = gtk::CheckButton::builder()
+            .label(layer.label())
+            .active(settings.visible)
+            .build();
+        visible.connect_toggled(move |visible| {
+            change(layer, |s| s.visible = visible.is_active());
+        });
+        list.attach(&visible, 0, row, 1, 1);
+
+        let opacity =
+            gtk::Scale::with_range(gtk::Orientation::Horizontal, 0., 1., 0.05);
+        opacity.set_value(settings.opacity);
+        opacity.set_width_request(100);
+        opacity.set_tooltip_text(Some("Opacity"));
+        opacity.connect_value_changed(move |opacity| {
+            change(layer, |s| s.opacity = opacity.value());
+        });
+        list.attach(&opacity, 1, row, 1, 1);
+
+        // the background is always at the bottom
+        if layer == CanvasLayer::Background {
+            continue;
+        }
+        let moves = [("go-up-symbolic", true), ("go-down-symbolic", false)];
+        for (column, (icon, up)) in moves.into_iter().enumerate() {
+            let button = gtk::Button::from_icon_name(icon);
+            button.connect_clicked(glib::clone!(
+                #[weak]
+                list,
+                move |_| {
+                    let mut layers = LAYERS.read().unwrap().clone();
+                    if up {
+                        layers.raise(layer);
+                    } else {
+                        layers.lower(layer);
+                    }
+                    set_layers(layers);
+                    // not while the button is being clicked
+                    glib::idle_add_local_once(move || fill(&list));
+                }
+            ));
+            list.attach(&button, 2 + column as i32, row, 1, 1);
+        }
+    }
+}
+
+/// Button with a popover listing the layers of the canvas, to show or hide
+/// each, make it see-through, and change the order they are drawn in. The
+/// changes apply to every window, and to the images exported from them.
+pub(crate) fn build() -> gtk::MenuButton {
+    let list = gtk::Grid::builder()
+        .row_spacing(4)
+        .column_spacing(8)
+        .build();
+    fill(&list);
+
+    let popover = gtk::Popover::builder().child(&list).build();
+    // other windows may have changed the layers since
+    popover.connect_show(glib::clone!(
+        #[weak]
+        list,
+        move |_| fill(&list)
+    ));
+
+    gtk::MenuButton::builder()
+        .label("Layers")
+        .popover(&popover)
+        .build()
+}
@@ -0,0 +1,167 @@
+//! Macros: short runs of what is done in a window, such as drawing seeds,
+//! adding primitives and starting the growth, recorded like a session log
+//! and kept in a slot bound to a key, to do them again with a keystroke.
+//!
+//! Each slot is kept as a session log in `macros` in the config directory,
+//! as `N.replay` for slot `N`, so that macros last from one session to the
+//! next. A macro is played back at the pace it was recorded, with the
+//! settings of the window at the time, other than those changed while
+//! recording it.
+
+#[cfg(feature = "gui")]
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "gui")]
+use anyhow::{Context, Result};
+
+#[cfg(feature = "gui")]
+use super::config_dir;
+use super::{
+    algorithm::Params,
+    app_state::AppState,
+    replay::{Event, LoggedSettings, Replay},
+    shape::smooth::SmoothOptions,
+};
+
+/// Number of macro slots, each bound to a key.
+#[cfg(feature = "gui")]
+pub(crate) const SLOTS: usize = 4;
+
+/// A macro being recorded.
+pub(crate) struct MacroRecording {
+    start: Instant,
+    settings: LoggedSettings,
+    events: Vec<(Duration, Event)>,
+}
+
+impl MacroRecording {
+    /// Start recording what is done to `state`, with `params` and `smooth`
+    /// as the settings.
+    pub(crate) fn start(
+        state: &AppState,
+        params: &Params,
+        smooth: SmoothOptions,
+    ) -> Self {
+        Self {
+            start: Instant::now(),
+            settings: LoggedSettings::of(state, params, smooth),
+            events: Vec::new(),
+        }
+    }
+
+    /// Record `event`, done to `state` with `params` and `smooth`, after any
+    /// of the settings that changed since the last event. The steps of the
+    /// simulation are left out, for the growth to go on at its own pace
+    /// once the macro is played back.
+    pub(crate) fn record(
+        &mut self,
+        event: Event,
+        state: &AppState,
+        params: &Params,
+        smooth: SmoothOptions,
+    ) {
+        if matches!(event, Event::Steps(_) | Event::Seed(_)) {
+            return;
+        }
+        let at = self.start.elapsed();
+        for change in self.settings.changes(state, params, smooth) {
+            self.events.push((at, change));
+        }
+        self.events.push((at, event));
+    }
+
+    /// The recorded macro, to play back as a replay.
+    pub(crate) fn finish(self) -> Replay {
+        Replay::new(self.events)
+    }
+}
+
+/// The file of the macro in `slot`, counting from 1.
+#[cfg(feature = "gui")]
+fn path(slot: usize) -> Result<PathBuf> {
+    anyhow::ensure!((1..=SLOTS).contains(&slot), "no macro slot {slot}");
+    let dir = config_dir().context("no config directory")?.join("macros");
+    Ok(dir.join(format!("{slot}.replay")))
+}
+
+/// Keep `recorded` in `slot`, replacing the macro kept there, if any.
+#[cfg(feature = "gui")]
+pub(crate) fn save(slot: usize, recorded: &Replay) -> Result<PathBuf> {
+    let path = path(slot)?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("failed to create {}", dir.display()))?;
+    }
+    std::fs::write(&path, recorded.to_string())
+        .with_context(|| format!("failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+/// The macro kept in `slot`, or `None` if none has been recorded there.
+#[cfg(feature = "gui")]
+pub(crate) fn load(slot: usize) -> Result<Option<Replay>> {
+    let path = path(slot)?;
+    if !path.exists() {
+        return Ok(None);
+    }
+    Replay::load(&path).map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::DVec2;
+
+    use super::*;
+    use crate::{keys::Action, replay};
+
+    #[test]
+    fn recorded_macros_play_back_without_steps() {
+        let mut state = AppState::default();
+        let params = Params::DEFAULT;
+        let smooth = SmoothOptions::DEFAULT;
+        let mut recording = MacroRecording::start(&state, &params, smooth);
+
+        let changed = Params {
+            stiffness: 1.,
+            ..params
+        };
+        let events = [
+            Event::BeginStroke {
+                pos: DVec2::new(300., 300.),
+                time: 0,
+            },
+            Event::EndStroke {
+                offset: DVec2::new(60., 60.),
+                time: 10,
+                closed: true,
+                zoom: 1.,
+            },
+            Event::Steps(20),
+            Event::Action(Action::ResetSim),
+        ];
+        for event in events {
+            recording.record(event.clone(), &state, &changed, smooth);
+            let (mut p, mut s) = (changed, smooth);
+            replay::apply(&mut state, &event, &mut p, &mut s).unwrap();
+        }
+
+        let recorded = recording.finish();
+        let text = recorded.to_string();
+        assert!(text.contains(" params "), "the change is recorded");
+        assert!(!text.contains(" steps "), "the steps are not");
+
+        // the macro does it all again, in a window of its own
+        let mut again = AppState::default();
+        let (mut p, mut s) = (params, smooth);
+        for line in text.lines().skip(1) {
+            let (_, event) = line.split_once(' ').unwrap();
+            let event = event.parse().unwrap();
+            replay::apply(&mut again, &event, &mut p, &mut s).unwrap();
+        }
+        assert_eq!(p, changed);
+        assert_eq!(again.shapes().len(), 1);
+        assert_eq!(again.simulation().iteration(), 0);
+        assert!(again.simulation().simulation().is_some());
+    }
+}
@@ -1,301 +1,825 @@
+// the benchmarks of the renderer
+#![cfg_attr(all(test, feature = "gui"), feature(test))]
+
 use std::{
-    f64::consts::TAU,
-    sync::{
-        RwLock,
-        atomic::{AtomicBool, AtomicU64, Ordering},
-    },
+    fs::File,
+    io::{self, BufReader, BufWriter},
+    net::IpAddr,
+    ops::ControlFlow,
+    path::PathBuf,
+    time::Duration,
 };
 
-use anyhow::Result;
-use gtk::{cairo, gdk, glib, prelude::*};
+use anyhow::{Context, Result};
+use clap::{CommandFactory, Parser};
+use dxdy_core::{algorithm, shape};
+use glam::DVec2;
 use tracing::level_filters;
 use tracing_subscriber::{
     Layer, layer::SubscriberExt, util::SubscriberInitExt,
 };
 
-mod algorithm;
-mod pos;
-mod shape;
-
-use pos::*;
-use shape::*;
-
-const APP_ID: &str = "com.nelsonearle.dxdy.draw";
-
-static CURSOR_POSITION: RwLock<Option<Pos>> = RwLock::new(None);
-
-static CURSOR_COLOR: AtomicBool = AtomicBool::new(true);
+#[cfg(any(feature = "gui", test))]
+mod app_state;
+#[cfg(any(feature = "gui", test))]
+mod autosave;
+#[cfg(any(feature = "gui", test))]
+mod bake;
+#[cfg(feature = "gui")]
+mod candidates;
+#[cfg(feature = "gui")]
+mod candidates_panel;
+#[cfg(any(feature = "gui", test))]
+mod damage;
+mod demo;
+#[cfg(feature = "gui")]
+mod domain_menu;
+mod export;
+#[cfg(feature = "gui")]
+mod gpu;
+#[cfg(feature = "gui")]
+mod grid_panel;
+#[cfg(any(feature = "gui", test))]
+mod grow_preset;
+mod import;
+#[cfg(feature = "gui")]
+mod import_dialog;
+#[cfg(feature = "gui")]
+mod jobs;
+#[cfg(feature = "gui")]
+mod jobs_panel;
+#[cfg(any(feature = "gui", test))]
+mod keys;
+mod layers;
+#[cfg(feature = "gui")]
+mod layers_panel;
+#[cfg(any(feature = "gui", test))]
+mod macros;
+#[cfg(any(feature = "gui", feature = "script", test))]
+mod modes;
+#[cfg(feature = "gui")]
+mod mutate_menu;
+mod params_file;
+#[cfg(feature = "gui")]
+mod params_panel;
+#[cfg(any(feature = "gui", test))]
+mod playback;
+mod polyline;
+mod preset;
+#[cfg(feature = "gui")]
+mod preset_menu;
+mod project;
+#[cfg(feature = "tiny-skia")]
+mod raster;
+mod recorder;
+mod remote;
+#[cfg(feature = "gui")]
+mod render;
+#[cfg(any(feature = "gui", test))]
+mod replay;
+mod scene;
+#[cfg(feature = "script")]
+mod script;
+mod seed_file;
+#[cfg(feature = "gui")]
+mod seed_menu;
+mod simulation;
+#[cfg(any(feature = "gui", test))]
+mod snap;
+mod snapshots;
+#[cfg(feature = "gui")]
+mod speed_scale;
+#[cfg(any(feature = "gui", test))]
+mod stats;
+mod style;
+#[cfg(feature = "gui")]
+mod symmetry_panel;
+mod theme;
+mod transform;
+#[cfg(any(feature = "gui", test))]
+mod viewport;
+#[cfg(feature = "gui")]
+mod window;
+
+use algorithm::{GeometrySnapshot, ONE, Params};
+use demo::Demo;
+use export::{Crop, ImageFormat, Region};
+#[cfg(feature = "gui")]
+use gpu::Renderer;
+use params_file::ParamsFile;
+use preset::{Layers, Preset};
+use project::Project;
+use recorder::{Recorder, Steering};
+use remote::Remote;
+use scene::Color;
+#[cfg(feature = "script")]
+use script::Script;
+use simulation::StopConditions;
+use snapshots::{SnapshotReader, SnapshotWriter};
+use style::{Style, StyleFile};
+use theme::Palette;
+use transform::DOCUMENT_SIZE;
+
+/// `~/.config/dxdy-draw`, or the same under `$XDG_CONFIG_HOME`, where key
+/// bindings and export presets are kept.
+fn config_dir() -> Option<PathBuf> {
+    let config = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| {
+            std::env::var_os("HOME")
+                .map(|home| PathBuf::from(home).join(".config"))
+        })?;
+    Some(config.join("dxdy-draw"))
+}
 
-static CURRENT_SHAPE: RwLock<Shape> = RwLock::new(Shape::new());
+/// `~/.cache/dxdy-draw`, or the same under `$XDG_CACHE_HOME`, where
+/// drawings are autosaved.
+#[cfg(feature = "gui")]
+fn cache_dir() -> Option<PathBuf> {
+    let cache = std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| {
+            std::env::var_os("HOME")
+                .map(|home| PathBuf::from(home).join(".cache"))
+        })?;
+    Some(cache.join("dxdy-draw"))
+}
 
-fn main() -> Result<()> {
-    let stdout_log = tracing_subscriber::fmt::layer().pretty();
+/// Work done from the command line instead of opening the window.
+#[derive(clap::Subcommand)]
+enum Command {
+    /// Render a project file to an SVG or PNG image.
+    Render {
+        /// Project file to render.
+        input: PathBuf,
+        /// Image to write, as SVG or PNG by its extension. Defaults to the
+        /// output of the preset.
+        #[arg(long, required_unless_present = "preset")]
+        out: Option<PathBuf>,
+        /// Resolution of the image. The canvas is 96 DPI.
+        #[arg(long, default_value_t = export::DOCUMENT_DPI)]
+        dpi: f64,
+        /// Export with a saved preset instead of the options above.
+        #[arg(long, value_name = "NAME", conflicts_with = "dpi")]
+        preset: Option<String>,
+        #[command(flatten)]
+        crop: CropArgs,
+    },
+    /// Grow a project file with the default parameters, writing numbered
+    /// images of the steps to a directory.
+    Frames {
+        /// Project file to grow.
+        input: PathBuf,
+        /// Directory to write `frame_00001.png` and so on to.
+        #[arg(long)]
+        out_dir: PathBuf,
+        #[arg(long, value_enum, default_value_t = ImageFormat::Png)]
+        format: ImageFormat,
+        /// Resolution of the images. The canvas is 96 DPI.
+        #[arg(long, default_value_t = export::DOCUMENT_DPI)]
+        dpi: f64,
+        /// Only write every this many iterations.
+        #[arg(long, value_name = "N", default_value_t = 1)]
+        every: u64,
+        #[command(flatten)]
+        crop: CropArgs,
+    },
+    /// Run a built-in scene in the window, or render it once it stops
+    /// growing.
+    Demo {
+        demo: Demo,
+        /// Grow without opening the window and render the result to this
+        /// SVG or PNG file.
+        #[arg(long)]
+        out: Option<PathBuf>,
+        /// Only render this rectangle of the canvas with `--out`, in pixels
+        /// of the canvas.
+        #[arg(long, value_name = "X,Y,WIDTH,HEIGHT", requires = "out")]
+        region: Option<Region>,
+        /// Render with a saved preset with `--out`.
+        #[arg(long, value_name = "NAME", requires = "out")]
+        preset: Option<String>,
+    },
+    /// Record the geometry of a grown project to a compressed snapshot
+    /// file, and convert snapshot files to and from JSON.
+    Snapshots {
+        #[command(subcommand)]
+        command: SnapshotCommand,
+    },
+    /// Save, list, and print export presets.
+    Preset {
+        #[command(subcommand)]
+        command: PresetCommand,
+    },
+    /// Print the default style, to start a style file from.
+    Style,
+    /// Print the colors of a theme, to start a custom theme from, or list
+    /// the themes without one.
+    Theme { name: Option<String> },
+    /// Print the default key bindings, to start a key file from.
+    #[cfg(feature = "gui")]
+    Keys,
+    /// Print a completion script for a shell.
+    Completions { shell: clap_complete::Shell },
+    /// Write man pages for the command and every subcommand.
+    Man {
+        /// Directory to write the pages to.
+        #[arg(default_value = ".")]
+        out_dir: PathBuf,
+    },
+}
 
-    let env_filter = tracing_subscriber::EnvFilter::builder()
-        .with_default_directive(level_filters::LevelFilter::INFO.into())
-        .from_env_lossy();
+impl Command {
+    /// Whether the command grows a drawing without the window, which
+    /// `--serve` steers.
+    fn grows_headless(&self) -> bool {
+        matches!(
+            self,
+            Self::Frames { .. }
+                | Self::Demo { out: Some(_), .. }
+                | Self::Snapshots {
+                    command: SnapshotCommand::Record { .. }
+                }
+        )
+    }
+}
 
-    let tracy_layer = tracing_tracy::TracyLayer::default();
+/// Work done with snapshot files.
+#[derive(clap::Subcommand)]
+enum SnapshotCommand {
+    /// Grow a project file with the default parameters, writing the
+    /// geometry of the steps to a snapshot file.
+    Record {
+        /// Project file to grow.
+        input: PathBuf,
+        /// Snapshot file to write.
+        #[arg(long)]
+        out: PathBuf,
+        /// Only write every this many iterations.
+        #[arg(long, value_name = "N", default_value_t = 1)]
+        every: u64,
+    },
+    /// Print the snapshots of a snapshot file as JSON Lines, one snapshot
+    /// per line.
+    ToJson {
+        input: PathBuf,
+        /// File to write instead of printing.
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+    /// Write a snapshot file from JSON Lines as printed by `to-json`.
+    FromJson {
+        input: PathBuf,
+        #[arg(long)]
+        out: PathBuf,
+    },
+}
 
-    tracing_subscriber::registry()
-        .with(stdout_log.with_filter(env_filter))
-        .with(tracy_layer)
-        .init();
+/// Work done with export presets.
+#[derive(clap::Subcommand)]
+enum PresetCommand {
+    /// Save export settings under a name, replacing any preset saved under
+    /// it before.
+    Save {
+        /// Name of the preset, in letters, digits, `-` and `_`.
+        name: String,
+        #[command(flatten)]
+        preset: PresetArgs,
+    },
+    /// Print the names of the saved presets.
+    List,
+    /// Print a saved preset.
+    Show { name: String },
+}
 
-    let app = gtk::Application::builder().application_id(APP_ID).build();
-    app.connect_activate(cb_activate);
+/// The settings of a preset.
+#[derive(clap::Args)]
+struct PresetArgs {
+    /// Format of the images. Defaults to the extension of each output.
+    #[arg(long, value_enum)]
+    format: Option<ImageFormat>,
+    /// Resolution of PNG images. The canvas is 96 DPI.
+    #[arg(long, default_value_t = export::DOCUMENT_DPI)]
+    dpi: f64,
+    /// Scale the document to fit in this size, in pixels of the canvas.
+    #[arg(long, value_name = "WIDTH,HEIGHT", value_parser = preset::parse_size)]
+    size: Option<DVec2>,
+    /// Only export these layers.
+    #[arg(
+        long,
+        value_name = "LAYER,...",
+        default_value = "background,shapes,growth"
+    )]
+    layers: Layers,
+    /// Draw lines of one color with a pen of another, as in
+    /// `--pen '#ff6060=#000000'`. Can be given for each pen.
+    #[arg(long, value_name = "FROM=TO", value_parser = preset::parse_pen)]
+    pen: Vec<(Color, Color)>,
+    /// Image to write when the preset is used without an output.
+    #[arg(long)]
+    out: Option<PathBuf>,
+}
 
-    let exit_code = app.run_with_args(&[] as &[&str]);
-    if exit_code != glib::ExitCode::SUCCESS {
-        eprintln!("{exit_code:?}");
+impl PresetArgs {
+    fn preset(&self) -> Preset {
+        Preset {
+            format: self.format,
+            dpi: self.dpi,
+            size: self.size,
+            layers: self.layers,
+            pens: self.pen.clone(),
+            out: self.out.clone(),
+        }
     }
+}
 
-    Ok(())
+/// The part of a project to export, all of it unless one is given.
+#[derive(clap::Args)]
+struct CropArgs {
+    /// Only export this rectangle of the canvas, in pixels of the canvas.
+    #[arg(long, value_name = "X,Y,WIDTH,HEIGHT", conflicts_with = "select")]
+    region: Option<Region>,
+    /// Only export the shapes with these indices, counting from 0 in the
+    /// order they were drawn, cut down to the rectangle around them.
+    #[arg(long, value_name = "INDEX", value_delimiter = ',')]
+    select: Vec<usize>,
 }
 
-fn eat_err(r: Result<()>) {
-    if let Err(err) = r {
-        glib::g_error!("dxdy.draw", "{err}");
+impl CropArgs {
+    /// The crop of `project` asked for, if any.
+    fn crop(&self, project: &Project) -> Result<Option<Crop>> {
+        if let Some(region) = self.region {
+            return Ok(Some(Crop::region(region)));
+        }
+        if self.select.is_empty() {
+            return Ok(None);
+        }
+        let style = style::STYLE.read().unwrap();
+        Crop::selection(&style, &project.shapes, self.select.clone()).map(Some)
     }
 }
 
-fn cb_activate(app: &gtk::Application) {
-    // Drawing Area
-
-    let drawing_area = gtk::DrawingArea::builder()
-        .content_width(800)
-        .content_height(600)
-        .build();
-
-    // Window
+/// When to stop growing before the simulation can not go on, so that long
+/// runs end on their own.
+#[derive(clap::Args)]
+struct StopArgs {
+    /// Stop after this many iterations.
+    #[arg(long, value_name = "N", global = true)]
+    max_iterations: Option<u64>,
+    /// Stop once there are this many vertices.
+    #[arg(long, value_name = "N", global = true)]
+    max_vertices: Option<usize>,
+    /// Stop after growing for this many seconds.
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        global = true,
+        value_parser = parse_seconds
+    )]
+    time_limit: Option<Duration>,
+    /// Stop once growth has stalled, with the vertices moving less than
+    /// this far in a step on average, in thousandths of the simulation
+    /// space.
+    #[arg(long, value_name = "DISTANCE", global = true)]
+    stall: Option<f64>,
+}
 
-    let window = gtk::ApplicationWindow::builder()
-        .application(app)
-        .title("DxDy Draw")
-        .default_width(800)
-        .default_height(600)
-        .resizable(false)
-        .child(&drawing_area)
-        .build();
+impl StopArgs {
+    fn conditions(&self) -> StopConditions {
+        StopConditions {
+            max_iterations: self.max_iterations,
+            max_vertices: self.max_vertices,
+            time_limit: self.time_limit,
+            stall: self.stall.map(|stall| stall * ONE),
+        }
+    }
+}
 
-    // Draw
+/// Parses a number of seconds, as given on the command line.
+fn parse_seconds(s: &str) -> Result<Duration> {
+    let seconds = s.parse().with_context(|| format!("not a number: {s}"))?;
+    Duration::try_from_secs_f64(seconds)
+        .with_context(|| format!("not a number of seconds: {s}"))
+}
 
-    drawing_area.set_draw_func(glib::clone!(
-        move |widget, ctx, w, h| eat_err(draw(widget, ctx, w, h))
-    ));
+/// Parses a number of steps per second, as given on the command line.
+#[cfg(feature = "gui")]
+fn parse_rate(s: &str) -> Result<f64> {
+    let rate = s.parse().with_context(|| format!("not a number: {s}"))?;
+    anyhow::ensure!(rate > 0. && f64::is_finite(rate), "not a rate: {s}");
+    Ok(rate)
+}
 
-    // Key Press
+// GTK is not given any arguments, so these are the only ones accepted
+
+/// Draw shapes and grow differential lines from them.
+#[derive(clap::Parser)]
+#[command(name = "dxdy-draw", version)]
+struct Args {
+    /// Seed for the simulation RNG, to reproduce a previous run.
+    #[arg(long)]
+    seed: Option<u64>,
+    /// Style file to draw with, which the window reloads whenever it
+    /// changes. Print the default style with `dxdy-draw style`.
+    #[arg(long, value_name = "PATH")]
+    style: Option<PathBuf>,
+    /// Theme to draw in instead of the colors of the default style: dark,
+    /// light, high-contrast, or a custom theme in the themes config
+    /// directory. List them with `dxdy-draw theme`.
+    #[arg(long, value_name = "NAME", conflicts_with = "style")]
+    theme: Option<String>,
+    /// Width of the canvas of the window in pixels, which images rendered
+    /// from the command line are also scaled to fit in. With only
+    /// `--height`, follows it at the aspect ratio of the drawing.
+    #[arg(long, value_name = "PIXELS", value_parser = clap::value_parser!(u32).range(1..))]
+    width: Option<u32>,
+    /// Height of the canvas of the window in pixels, like `--width`.
+    #[arg(long, value_name = "PIXELS", value_parser = clap::value_parser!(u32).range(1..))]
+    height: Option<u32>,
+    /// Open the window fullscreen.
+    #[cfg(feature = "gui")]
+    #[arg(long)]
+    fullscreen: bool,
+    /// Color the canvas is drawn on instead of the background of the style
+    /// or theme, as in style files, such as `#ffffff`.
+    #[arg(long, value_name = "COLOR", value_parser = style::parse_color)]
+    bg_color: Option<Color>,
+    /// Serve a control API on this port to watch, pause, resume, tune and
+    /// export the growth of a command over HTTP.
+    #[arg(long, value_name = "PORT")]
+    serve: Option<u16>,
+    /// Address the control API of `--serve` listens on. It has no
+    /// authentication, so only give an address others can reach on a
+    /// network they are trusted on.
+    #[arg(
+        long,
+        value_name = "ADDR",
+        default_value = "127.0.0.1",
+        requires = "serve"
+    )]
+    serve_bind: IpAddr,
+    /// Params file of growth parameters and colors to grow with, which is
+    /// applied again whenever it changes, in the window or without it.
+    #[arg(long, value_name = "PATH")]
+    params: Option<PathBuf>,
+    /// Only record every this many iterations.
+    #[cfg(feature = "gui")]
+    #[arg(long, value_name = "N", default_value_t = 1)]
+    record_every: u64,
+    /// Format of frame sequences recorded from the window.
+    #[cfg(feature = "gui")]
+    #[arg(long, value_enum, default_value_t = ImageFormat::Png)]
+    frame_format: ImageFormat,
+    /// Hide the markers at the vertices of drawn shapes once there are more
+    /// than this many vertices, which keeps large drawings responsive.
+    #[cfg(feature = "gui")]
+    #[arg(long, value_name = "N", default_value_t = 10_000)]
+    max_vertex_markers: usize,
+    /// How the window draws the growth. OpenGL keeps up with larger
+    /// simulations.
+    #[cfg(feature = "gui")]
+    #[arg(long, value_enum, default_value_t = Renderer::Cairo)]
+    renderer: Renderer,
+    /// Steps of the simulation per second, however often the window is
+    /// drawn, instead of 60.
+    #[cfg(feature = "gui")]
+    #[arg(long, value_name = "RATE", value_parser = parse_rate)]
+    steps_per_second: Option<f64>,
+    /// Show the growth only as it is after each step, rather than moving
+    /// from one step to the next in between them.
+    #[cfg(feature = "gui")]
+    #[arg(long)]
+    no_interpolation: bool,
+    /// Step the simulation of windows out of focus only 10 times a second,
+    /// which can also be turned on from the menu.
+    #[cfg(feature = "gui")]
+    #[arg(long)]
+    throttle_unfocused: bool,
+    /// Log what is done in the window to this file, to do it again with
+    /// `--replay`.
+    #[cfg(feature = "gui")]
+    #[arg(long, value_name = "PATH")]
+    record_session: Option<PathBuf>,
+    /// Do what a session log written with `--record-session` has in it
+    /// again in the window, at the pace it was done.
+    #[cfg(feature = "gui")]
+    #[arg(long, value_name = "PATH")]
+    replay: Option<PathBuf>,
+    /// Export the drawing to this SVG or PNG file when the window stops
+    /// growing it on one of the stop conditions.
+    #[cfg(feature = "gui")]
+    #[arg(long, value_name = "PATH")]
+    export_on_stop: Option<PathBuf>,
+    #[command(flatten)]
+    stop: StopArgs,
+    /// Run a Rhai script that seeds, grows and exports drawings, instead
+    /// of opening the window.
+    #[cfg(feature = "script")]
+    #[arg(long, value_name = "PATH")]
+    script: Option<PathBuf>,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
 
-    let key_controller = gtk::EventControllerKey::new();
-    key_controller.connect_key_pressed(glib::clone!(
-        #[weak]
-        app,
-        #[upgrade_or]
-        glib::Propagation::Proceed,
-        move |controller, keyval, keycode, modifier| {
-            cb_key_pressed(app, controller, keyval, keycode, modifier)
+impl Args {
+    /// The size given with `--width` and `--height`, if either is, the
+    /// other one following the aspect ratio of the canvas if not given.
+    fn size(&self) -> Option<DVec2> {
+        let aspect = DOCUMENT_SIZE.y / DOCUMENT_SIZE.x;
+        match (self.width.map(f64::from), self.height.map(f64::from)) {
+            (Some(width), Some(height)) => Some(DVec2::new(width, height)),
+            (Some(width), None) => Some(DVec2::new(width, width * aspect)),
+            (None, Some(height)) => Some(DVec2::new(height / aspect, height)),
+            (None, None) => None,
         }
-    ));
-    window.add_controller(key_controller);
-
-    // Drag Gesture
+    }
+}
 
-    let gesture_drag = gtk::GestureDrag::new();
-    gesture_drag.set_button(gdk::BUTTON_PRIMARY);
+fn main() -> Result<()> {
+    let args = Args::parse();
 
-    gesture_drag.connect_drag_begin(|gesture, x, y| {
-        gesture.set_state(gtk::EventSequenceState::Claimed);
-        *CURRENT_SHAPE.write().unwrap() = Shape::from_pos(x, y);
-    });
+    let stdout_log = tracing_subscriber::fmt::layer().pretty();
 
-    static DRAG_APP_START: std::sync::LazyLock<std::time::Instant> =
-        std::sync::LazyLock::new(std::time::Instant::now);
-    _ = *DRAG_APP_START;
+    let env_filter = tracing_subscriber::EnvFilter::builder()
+        .with_default_directive(level_filters::LevelFilter::INFO.into())
+        .from_env_lossy();
 
-    static DRAG_LAST_UPDATE: AtomicU64 = AtomicU64::new(0);
+    let tracy_layer = tracing_tracy::TracyLayer::default();
 
-    gesture_drag.connect_drag_update(|gesture, _dx, _dy| {
-        gesture.set_state(gtk::EventSequenceState::Claimed);
+    tracing_subscriber::registry()
+        .with(stdout_log.with_filter(env_filter))
+        .with(tracy_layer)
+        .init();
 
-        let t = DRAG_APP_START.elapsed().as_millis() as u64;
-        if t - DRAG_LAST_UPDATE.load(Ordering::Relaxed) < 50 {
-            return;
+    // loaded for every command, and watched by the window
+    #[cfg_attr(not(feature = "gui"), expect(unused_variables))]
+    let style_file = args.style.clone().map(StyleFile::load).transpose()?;
+    if let Some(name) = &args.theme {
+        theme::apply(Palette::named(name)?);
+    }
+    if let Some(color) = args.bg_color {
+        style::set_background(color);
+    }
+    let mut params_file =
+        args.params.clone().map(ParamsFile::open).transpose()?;
+    let mut remote = match args.serve {
+        Some(port) => {
+            anyhow::ensure!(
+                args.command.as_ref().is_some_and(Command::grows_headless),
+                "only growth without the window is served, give --serve to \
+                 frames, demo --out or snapshots record"
+            );
+            Some(Remote::bind(args.serve_bind, port)?)
         }
-        DRAG_LAST_UPDATE.store(t, Ordering::Relaxed);
+        None => None,
+    };
 
-        if let Some((dx, dy)) = gesture.offset() {
-            let offset = PosOffset::new(dx, dy);
-            let mut current_shape = CURRENT_SHAPE.write().unwrap();
+    #[cfg(feature = "script")]
+    if let Some(path) = &args.script {
+        anyhow::ensure!(
+            args.command.is_none(),
+            "a script is run instead of a command"
+        );
+        let project = Project {
+            seed: args.seed,
+            ..Project::default()
+        };
+        let written =
+            Script::load(path)?.run(project, Params::DEFAULT, |_| Ok(()))?;
+        tracing::info!("script wrote {} images", written.len());
+        return Ok(());
+    }
 
-            let last_offset = current_shape.last_offset();
-            let dist_to_last = (offset - last_offset).dist2();
-            if dist_to_last < 400. {
-                return;
+    match &args.command {
+        Some(Command::Render {
+            input,
+            out,
+            dpi,
+            preset,
+            crop,
+        }) => {
+            let mut preset = match preset {
+                Some(name) => Preset::named(name)?,
+                None => Preset {
+                    dpi: *dpi,
+                    ..Preset::default()
+                },
+            };
+            preset.size = args.size().or(preset.size);
+            let out = out
+                .as_ref()
+                .or(preset.out.as_ref())
+                .context("the preset has no output, give one with --out")?;
+            let project = Project::load(input)?;
+            let crop = crop.crop(&project)?;
+            preset.export(
+                out,
+                &style::STYLE.read().unwrap(),
+                &project.shapes,
+                None,
+                crop.as_ref(),
+            )
+        }
+        Some(Command::Frames {
+            input,
+            out_dir,
+            format,
+            dpi,
+            every,
+            crop,
+        }) => {
+            let mut project = Project::load(input)?;
+            if args.seed.is_some() {
+                project.seed = args.seed;
             }
-
-            current_shape.next_vertex_at(offset);
+            let crop = crop.crop(&project)?;
+            let recorder =
+                Recorder::frames(out_dir, *format, *dpi, *every, crop)?;
+            recorder::record_headless(
+                project,
+                recorder,
+                Steering {
+                    params_file: params_file.as_mut(),
+                    remote: remote.as_mut(),
+                },
+                args.stop.conditions(),
+            )
         }
-    });
-
-    gesture_drag.connect_drag_end(|gesture, _dx, _dy| {
-        gesture.set_state(gtk::EventSequenceState::Claimed);
-        if let Some((dx, dy)) = gesture.offset() {
-            let mut current_shape = CURRENT_SHAPE.write().unwrap();
-            current_shape.next_vertex(dx, dy);
-            ALL_SHAPES.write().unwrap().push(current_shape.clone());
+        Some(Command::Demo {
+            demo,
+            out: Some(out),
+            region,
+            preset,
+        }) => {
+            let seed = args.seed.unwrap_or(demo::DEFAULT_SEED);
+            let crop = region.map(Crop::region);
+            let mut preset = match preset {
+                Some(name) => Preset::named(name)?,
+                None => Preset::default(),
+            };
+            preset.size = args.size().or(preset.size);
+            let stop = args.stop.conditions();
+            demo.render(
+                seed,
+                out,
+                Steering {
+                    params_file: params_file.as_mut(),
+                    remote: remote.as_mut(),
+                },
+                stop,
+                crop.as_ref(),
+                &preset,
+            )
         }
-    });
-
-    window.add_controller(gesture_drag);
-
-    // Cursor Position
-
-    fn get_pointer_position(
-        window: gtk::ApplicationWindow,
-    ) -> Option<(Pos, gdk::ModifierType)> {
-        let display = gdk::Display::default().unwrap();
-        let pointer = display.default_seat().unwrap().pointer().unwrap();
-        let surface = window.root().unwrap().surface().unwrap();
-        surface
-            .device_position(&pointer)
-            .map(|(x, y, modt)| (Pos::new(x, y), modt))
-    }
-
-    glib::timeout_add_local(
-        std::time::Duration::from_millis(20),
-        glib::clone!(
-            #[weak]
-            window,
-            #[weak]
-            drawing_area,
-            #[upgrade_or]
-            glib::ControlFlow::Continue,
-            move || {
-                match get_pointer_position(window) {
-                    Some((pos, _)) => {
-                        *CURSOR_POSITION.write().unwrap() = Some(pos);
+        Some(Command::Snapshots { command }) => match command {
+            SnapshotCommand::Record { input, out, every } => {
+                let mut project = Project::load(input)?;
+                if args.seed.is_some() {
+                    project.seed = args.seed;
+                }
+                let mut writer = SnapshotWriter::create(out)?;
+                let controller = recorder::grow_headless(
+                    &project,
+                    &Params::DEFAULT,
+                    Steering {
+                        params_file: params_file.as_mut(),
+                        remote: remote.as_mut(),
+                    },
+                    args.stop.conditions(),
+                    |controller| {
+                        let iteration = controller.iteration();
+                        if iteration % every.max(&1) == 0 {
+                            writer.write(&GeometrySnapshot {
+                                iteration,
+                                paths: controller
+                                    .simulation()
+                                    .unwrap()
+                                    .paths(),
+                            })?;
+                        }
+                        Ok(ControlFlow::Continue(()))
+                    },
+                )?;
+                tracing::info!(
+                    "wrote {} snapshots of {} iterations",
+                    writer.written(),
+                    controller.iteration()
+                );
+                writer.finish()?;
+                Ok(())
+            }
+            SnapshotCommand::ToJson { input, out } => {
+                let reader = SnapshotReader::open(input)?;
+                match out {
+                    Some(out) => {
+                        let file = File::create(out).with_context(|| {
+                            format!("failed to create {}", out.display())
+                        })?;
+                        snapshots::write_json(reader, BufWriter::new(file))?
                     }
                     None => {
-                        *CURSOR_POSITION.write().unwrap() = None;
+                        snapshots::write_json(reader, io::stdout().lock())?
                     }
+                };
+                Ok(())
+            }
+            SnapshotCommand::FromJson { input, out } => {
+                let file = File::open(input).with_context(|| {
+                    format!("failed to open {}", input.display())
+                })?;
+                let mut writer = SnapshotWriter::create(out)?;
+                snapshots::read_json(BufReader::new(file), &mut writer)
+                    .with_context(|| {
+                        format!("invalid JSON snapshots: {}", input.display())
+                    })?;
+                tracing::info!("wrote {} snapshots", writer.written());
+                writer.finish()?;
+                Ok(())
+            }
+        },
+        Some(Command::Preset { command }) => match command {
+            PresetCommand::Save { name, preset } => {
+                let path = preset.preset().save_as(name)?;
+                tracing::info!("saved preset to {}", path.display());
+                Ok(())
+            }
+            PresetCommand::List => {
+                for name in Preset::names()? {
+                    println!("{name}");
                 }
-                drawing_area.queue_draw();
-                glib::ControlFlow::Continue
+                Ok(())
+            }
+            PresetCommand::Show { name } => {
+                print!("{}", Preset::named(name)?);
+                Ok(())
+            }
+        },
+        Some(Command::Style) => {
+            print!("{}", Style::default());
+            Ok(())
+        }
+        Some(Command::Theme { name: Some(name) }) => {
+            print!("{}", Palette::named(name)?);
+            Ok(())
+        }
+        Some(Command::Theme { name: None }) => {
+            for name in Palette::names()? {
+                println!("{name}");
             }
+            Ok(())
+        }
+        #[cfg(feature = "gui")]
+        Some(Command::Keys) => {
+            print!("{}", keys::Keymap::default());
+            Ok(())
+        }
+        Some(Command::Completions { shell }) => {
+            let mut command = Args::command();
+            let name = command.get_name().to_owned();
+            clap_complete::generate(
+                *shell,
+                &mut command,
+                name,
+                &mut std::io::stdout(),
+            );
+            Ok(())
+        }
+        Some(Command::Man { out_dir }) => {
+            clap_mangen::generate_to(Args::command(), out_dir).with_context(
+                || format!("failed to write to {}", out_dir.display()),
+            )
+        }
+        #[cfg(feature = "gui")]
+        Some(Command::Demo { out: None, .. }) | None => {
+            window::run(&args, style_file, params_file)
+        }
+        #[cfg(not(feature = "gui"))]
+        Some(Command::Demo { out: None, .. }) | None => anyhow::bail!(
+            "this build has no window, render with one of the commands \
+             instead"
         ),
-    );
-
-    // Cursor Color
-
-    glib::timeout_add_local(
-        std::time::Duration::from_millis(750),
-        glib::clone!(move || {
-            CURSOR_COLOR.fetch_xor(true, Ordering::Relaxed);
-            glib::ControlFlow::Continue
-        }),
-    );
-
-    // Present
-
-    window.present();
-}
-
-fn cb_key_pressed(
-    app: gtk::Application,
-    _controller: &gtk::EventControllerKey,
-    keyval: gdk::Key,
-    _keycode: u32,
-    modifier: gdk::ModifierType,
-) -> glib::Propagation {
-    if modifier == gdk::ModifierType::META_MASK && keyval == gdk::Key::q {
-        app.quit();
-    } else if keyval == gdk::Key::BackSpace {
-        ALL_SHAPES.write().unwrap().clear();
-        *CURRENT_SHAPE.write().unwrap() = Shape::new();
     }
-
-    glib::Propagation::Proceed
-}
-
-mod colors {
-    use gtk::gdk::RGBA;
-
-    const fn f(b: u8) -> f32 {
-        b as f32 / u8::MAX as f32
-    }
-
-    pub(crate) static WHITE: RGBA = RGBA::new(f(0xff), f(0xff), f(0xff), 1.);
-    pub(crate) static BLUE: RGBA = RGBA::new(f(0x60), f(0x60), f(0xff), 1.);
-    pub(crate) static RED: RGBA = RGBA::new(f(0xff), f(0x60), f(0x60), 1.);
-
-    pub(crate) static BG: RGBA = RGBA::new(0.2, 0.2, 0.2, 1.);
-    pub(crate) static CURSOR1: RGBA = BLUE;
-    pub(crate) static CURSOR2: RGBA = RED;
 }
 
 mod sizes {
     pub(crate) static CURSOR_RADIUS: f64 = 4.;
-}
-
-fn draw(
-    _widget: &gtk::DrawingArea,
-    ctx: &cairo::Context,
-    width: i32,
-    height: i32,
-) -> Result<()> {
-    ctx.set_source_color(&colors::BG);
-    ctx.rectangle(0.0, 0.0, width as f64, height as f64);
-    ctx.fill()?;
-
-    let (color, color_opposite) = if CURSOR_COLOR.load(Ordering::Relaxed) {
-        (&colors::CURSOR1, &colors::CURSOR2)
-    } else {
-        (&colors::CURSOR2, &colors::CURSOR1)
-    };
-
-    ctx.set_source_color(color);
-
-    if let Some(pos) = *CURSOR_POSITION.read().unwrap() {
-        ctx.arc(pos.x, pos.y, sizes::CURSOR_RADIUS, 0., TAU);
-        ctx.fill()?;
-    }
-
-    {
-        let shape = CURRENT_SHAPE.read().unwrap();
-        let start = shape.start();
-        ctx.new_path();
-        ctx.move_to(start.x, start.y);
-        for offset in shape.verticies() {
-            let x = start.x + offset.dx;
-            let y = start.y + offset.dy;
-            ctx.line_to(x, y);
-        }
-        ctx.stroke()?;
-    }
-
-    for shape in ALL_SHAPES.read().unwrap().iter() {
-        let start = shape.start();
-
-        ctx.set_source_color(color_opposite);
-        ctx.set_line_width(4.);
-        ctx.new_path();
-        for offset in shape.verticies() {
-            let x = start.x + offset.dx;
-            let y = start.y + offset.dy;
-            ctx.line_to(x, y);
-        }
-        ctx.close_path();
-        ctx.stroke()?;
-
-        ctx.set_source_color(&colors::WHITE);
-        ctx.set_line_width(1.);
-        for offset in shape.verticies() {
-            let x = start.x + offset.dx;
-            let y = start.y + offset.dy;
-            ctx.arc(x, y, 1.5, 0., TAU);
-            ctx.stroke()?;
-        }
-    }
-
-    Ok(())
+    /// Width of the shape being drawn.
+    pub(crate) static DRAWING_WIDTH: f64 = 2.;
+    /// Width of completed shapes at full pen pressure.
+    pub(crate) static STROKE_WIDTH: f64 = 4.;
+    /// Radius of vertex markers, in pixels of the window.
+    pub(crate) static MARKER_RADIUS: f64 = 1.5;
+    #[cfg(any(feature = "gui", test))]
+    pub(crate) static ERASER_TOLERANCE: f64 = 10.;
+    /// How close to a stroke of the anchor tool the growth is anchored onto
+    /// it, in pixels of the window.
+    #[cfg(any(feature = "gui", test))]
+    pub(crate) static ANCHOR_REACH: f64 = 10.;
+    /// Radius of the handles of the selected shape, in pixels of the window.
+    #[cfg(feature = "gui")]
+    pub(crate) static HANDLE_RADIUS: f64 = 4.;
+    /// Radius around the cursor in which it pushes or pulls the growth
+    /// while sculpting, in pixels of the canvas.
+    #[cfg(feature = "gui")]
+    pub(crate) static SCULPT_RADIUS: f64 = 40.;
 }
@@ -0,0 +1,48 @@
+//! Names of the modes of the growth parameters, as session logs and
+//! scripts write them.
+
+use super::algorithm::{BoundaryMode, IndexKind, SpawnMode};
+
+/// Names of the spawn modes.
+pub(crate) const SPAWN_MODES: [(&str, SpawnMode); 2] = [
+    ("uniform", SpawnMode::Uniform),
+    ("curvature", SpawnMode::Curvature),
+];
+
+/// Names of the boundary modes.
+pub(crate) const BOUNDARY_MODES: [(&str, BoundaryMode); 5] = [
+    ("stop", BoundaryMode::Stop),
+    ("clamp", BoundaryMode::Clamp),
+    ("reflect", BoundaryMode::Reflect),
+    ("wrap", BoundaryMode::Wrap),
+    ("kill", BoundaryMode::Kill),
+];
+
+/// Names of the spatial indexes.
+pub(crate) const INDEXES: [(&str, IndexKind); 2] = [
+    ("zone-map", IndexKind::ZoneMap),
+    ("quadtree", IndexKind::QuadTree),
+];
+
+/// The mode named `name` in `modes`.
+pub(crate) fn mode_named<T: Copy>(
+    modes: &[(&str, T)],
+    name: &str,
+) -> Option<T> {
+    modes
+        .iter()
+        .find(|&&(n, _)| n == name)
+        .map(|&(_, mode)| mode)
+}
+
+/// The name of `mode` in `modes`.
+#[cfg(any(feature = "gui", test))]
+pub(crate) fn mode_name<T: PartialEq>(
+    modes: &[(&'static str, T)],
+    mode: T,
+) -> &'static str {
+    modes
+        .iter()
+        .find(|(_, m)| *m == mode)
+        .map_or("", |&(name, _)| name)
+}
@@ -0,0 +1,115 @@
+use std::{cell::Cell, rc::Rc};
+
+use gtk::{glib, prelude::*};
+
+use super::{
+    algorithm::Params, app_state::SharedState, params_panel::PARAMS,
+    seed_menu::spin, window::show_simulation_error,
+};
+
+/// Restart the simulation of `state` with `params` and grow it for `steps`,
+/// showing any error in the window of `button`.
+fn preview(
+    button: &gtk::Button,
+    state: &SharedState,
+    params: &Params,
+    steps: u64,
+) {
+    if let Err(err) = state.borrow_mut().preview(params, steps) {
+        let window = button.root().and_downcast::<gtk::Window>();
+        show_simulation_error(window.as_ref(), err);
+    }
+}
+
+/// Button with a popover that mutates the parameters: each that shapes the
+/// growth is changed at random by up to the amount, and the simulation of
+/// `state` is grown with them for a few steps to show the difference. The
+/// mutation is then kept, or discarded for the parameters before it.
+///
+/// Mutating again before either mutates the parameters from before the
+/// first time. The seed moves on with every mutation, so that each is a
+/// new one, and the same seed mutates the same parameters the same way.
+pub(crate) fn build(state: &SharedState) -> gtk::MenuButton {
+    let grid = gtk::Grid::builder()
+        .row_spacing(4)
+        .column_spacing(8)
+        .build();
+
+    let amount = spin(&grid, 0, "Amount (%)", (1., 100., 1.), 20.);
+    let seed = spin(&grid, 1, "Seed", (0., 1e9, 1.), 1.);
+    let steps = spin(&grid, 2, "Preview steps", (0., 10_000., 50.), 500.);
+
+    let mutate = gtk::Button::with_label("Mutate");
+    grid.attach(&mutate, 0, 3, 2, 1);
+    let keep = gtk::Button::with_label("Keep");
+    grid.attach(&keep, 0, 4, 1, 1);
+    let discard = gtk::Button::with_label("Discard");
+    grid.attach(&discard, 1, 4, 1, 1);
+    keep.set_sensitive(false);
+    discard.set_sensitive(false);
+
+    let popover = gtk::Popover::builder().child(&grid).build();
+
+    // the parameters from before the mutation being tried, if any
+    let original = Rc::new(Cell::new(None::<Params>));
+
+    mutate.connect_clicked(glib::clone!(
+        #[strong]
+        state,
+        #[strong]
+        original,
+        #[weak]
+        keep,
+        #[weak]
+        discard,
+        move |mutate| {
+            let params =
+                original.get().unwrap_or_else(|| *PARAMS.read().unwrap());
+            original.set(Some(params));
+
+            let mutated =
+                params.mutated(amount.value() / 100., seed.value() as u64);
+            *PARAMS.write().unwrap() = mutated;
+            seed.set_value(seed.value() + 1.);
+
+            preview(mutate, &state, &mutated, steps.value() as u64);
+            keep.set_sensitive(true);
+            discard.set_sensitive(true);
+        }
+    ));
+
+    keep.connect_clicked(glib::clone!(
+        #[strong]
+        original,
+        #[weak]
+        discard,
+        #[weak]
+        popover,
+        move |keep| {
+            original.set(None);
+            keep.set_sensitive(false);
+            discard.set_sensitive(false);
+            popover.popdown();
+        }
+    ));
+
+    discard.connect_clicked(glib::clone!(
+        #[strong]
+        state,
+        #[weak]
+        keep,
+        move |discard| {
+            if let Some(params) = original.take() {
+                *PARAMS.write().unwrap() = params;
+                preview(discard, &state, &params, 0);
+            }
+            keep.set_sensitive(false);
+            discard.set_sensitive(false);
+        }
+    ));
+
+    gtk::MenuButton::builder()
+        .label("Mutate")
+        .popover(&popover)
+        .build()
+}
@@ -0,0 +1,361 @@
+//! Params files: growth parameters and colors to grow with, read again
+//! whenever the file changes, so that a long run can be tuned while it
+//! grows by editing the file, in the window or without it.
+//!
+//! Changes are found with [`notify`], which watches the directory of the
+//! file rather than the file itself, for editors that save by replacing it.
+//!
+//! A params file is TOML with any of these keys, distances in thousandths
+//! of the simulation space as in the parameters panel:
+//!
+//! ```toml
+//! near-l = 2
+//! far-l = 10
+//! step = 0.5
+//! spawn-probability = 0.01
+//!
+//! background = "#101014"
+//! growth = ["#e0e0e0", "#ff8040"]
+//! shape = "#808080"
+//! ```
+//!
+//! The parameters and colors it does not have are left as they are.
+//! Distances are finite and positive, and the spawn probability is
+//! between 0 and 1.
+
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::mpsc::{self, Receiver, TryRecvError},
+};
+
+use anyhow::{Context, Result};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+use super::{
+    algorithm::{ONE, Params},
+    scene::Color,
+    seed_file::{number, string},
+    style::{self, STYLE, Style},
+};
+
+/// How often the window checks a params file for changes.
+#[cfg(feature = "gui")]
+pub(crate) const INTERVAL: std::time::Duration =
+    std::time::Duration::from_millis(500);
+
+/// What a params file changes.
+#[derive(Debug, Default, PartialEq)]
+struct Changes {
+    near_l: Option<f64>,
+    far_l: Option<f64>,
+    step: Option<f64>,
+    spawn_probability: Option<f64>,
+    background: Option<Color>,
+    growth: Option<Vec<Color>>,
+    shape: Option<Color>,
+}
+
+impl FromStr for Changes {
+    type Err = anyhow::Error;
+
+    fn from_str(text: &str) -> Result<Self> {
+        Self::from_table(text.parse()?)
+    }
+}
+
+impl Changes {
+    fn from_table(table: toml::Table) -> Result<Self> {
+        let mut changes = Self::default();
+
+        for (key, value) in table {
+            let invalid = || format!("invalid {key}");
+            match key.as_str() {
+                "near-l" => {
+                    changes.near_l =
+                        Some(distance(value).with_context(invalid)?)
+                }
+                "far-l" => {
+                    changes.far_l =
+                        Some(distance(value).with_context(invalid)?)
+                }
+                "step" => {
+                    changes.step = Some(distance(value).with_context(invalid)?)
+                }
+                "spawn-probability" => {
+                    changes.spawn_probability =
+                        Some(probability(value).with_context(invalid)?)
+                }
+                "background" => {
+                    changes.background =
+                        Some(color(value).with_context(invalid)?)
+                }
+                "growth" => {
+                    let colors = match value {
+                        toml::Value::Array(values) => values,
+                        value => vec![value],
+                    };
+                    let colors = colors
+                        .into_iter()
+                        .map(color)
+                        .collect::<Result<Vec<_>>>()
+                        .with_context(invalid)?;
+                    anyhow::ensure!(!colors.is_empty(), "no growth colors");
+                    changes.growth = Some(colors);
+                }
+                "shape" => {
+                    changes.shape = Some(color(value).with_context(invalid)?)
+                }
+                _ => anyhow::bail!("unknown key: {key}"),
+            }
+        }
+
+        Ok(changes)
+    }
+
+    fn load(path: &Path) -> Result<Self> {
+        std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?
+            .parse()
+            .with_context(|| format!("invalid params file {}", path.display()))
+    }
+
+    fn apply_to_params(&self, params: &mut Params) {
+        let Self {
+            near_l,
+            far_l,
+            step,
+            spawn_probability,
+            ..
+        } = *self;
+        params.near_l = near_l.unwrap_or(params.near_l);
+        params.far_l = far_l.unwrap_or(params.far_l);
+        params.step = step.unwrap_or(params.step);
+        params.spawn_probability =
+            spawn_probability.unwrap_or(params.spawn_probability);
+    }
+
+    fn apply(&self, params: &mut Params) {
+        self.apply_to_params(params);
+        let style = self.apply_to_style(&STYLE.read().unwrap());
+        if let Some(style) = style {
+            style::set_style(style);
+        }
+    }
+
+    /// The style with the colors of the file, if it has any.
+    fn apply_to_style(&self, style: &Style) -> Option<Style> {
+        if self.background.is_none()
+            && self.growth.is_none()
+            && self.shape.is_none()
+        {
+            return None;
+        }
+        let mut style = style.clone();
+        style.background = self.background.unwrap_or(style.background);
+        if let Some(growth) = &self.growth {
+            style.growth.clone_from(growth);
+        }
+        style.shape = self.shape.unwrap_or(style.shape);
+        Some(style)
+    }
+}
+
+/// Apply `table`, with the keys of a params file, to `params`, and its
+/// colors to [`STYLE`]. An invalid table changes neither.
+pub(crate) fn apply(table: toml::Table, params: &mut Params) -> Result<()> {
+    Changes::from_table(table)?.apply(params);
+    Ok(())
+}
+
+fn distance(value: toml::Value) -> Result<f64> {
+    let distance = number(value)?;
+    anyhow::ensure!(
+        distance.is_finite() && distance > 0.,
+        "{distance} is not a positive distance"
+    );
+    Ok(distance * ONE)
+}
+
+fn probability(value: toml::Value) -> Result<f64> {
+    let probability = number(value)?;
+    anyhow::ensure!(
+        (0. ..=1.).contains(&probability),
+        "{probability} is not between 0 and 1"
+    );
+    Ok(probability)
+}
+
+fn color(value: toml::Value) -> Result<Color> {
+    style::parse_color(&string(value)?)
+}
+
+/// A params file, applied again whenever it changes.
+pub(crate) struct ParamsFile {
+    path: PathBuf,
+    /// Watches the directory of the file, for as long as it is applied.
+    _watcher: RecommendedWatcher,
+    /// Events in the directory of the file since it was last checked.
+    events: Receiver<notify::Result<Event>>,
+    /// Whether the file was applied yet.
+    applied: bool,
+}
+
+impl ParamsFile {
+    /// Open the params file at `path`, which is applied on the first
+    /// [`reload_if_changed`](Self::reload_if_changed). It is read once now,
+    /// for an invalid file to be found before anything is grown.
+    pub(crate) fn open(path: PathBuf) -> Result<Self> {
+        Changes::load(&path)?;
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let (sender, events) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(sender)
+            .context("failed to watch params file")?;
+        watcher
+            .watch(dir, RecursiveMode::NonRecursive)
+            .with_context(|| format!("failed to watch {}", dir.display()))?;
+        Ok(Self {
+            path,
+            _watcher: watcher,
+            events,
+            applied: false,
+        })
+    }
+
+    /// Whether the file changed since it was last checked.
+    fn changed(&self) -> Result<bool> {
+        let mut changed = false;
+        loop {
+            let event = match self.events.try_recv() {
+                Ok(event) => event.context("failed to watch params file")?,
+                Err(TryRecvError::Empty) => return Ok(changed),
+                Err(TryRecvError::Disconnected) => {
+                    anyhow::bail!("stopped watching params file")
+                }
+            };
+            // reading the file is an event too
+            changed |= !matches!(event.kind, EventKind::Access(_))
+                && event
+                    .paths
+                    .iter()
+                    .any(|path| path.file_name() == self.path.file_name());
+        }
+    }
+
+    /// Apply the file to `params`, and its colors to [`STYLE`], if it
+    /// changed since it was last applied. An invalid file leaves both as
+    /// they were, until it changes again.
+    pub(crate) fn reload_if_changed(
+        &mut self,
+        params: &mut Params,
+    ) -> Result<()> {
+        // the events are taken first, so that a change while the file is
+        // read is applied on the next check
+        if !self.changed()? && self.applied {
+            return Ok(());
+        }
+        let first = !self.applied;
+        self.applied = true;
+
+        Changes::load(&self.path)?.apply(params);
+        if !first {
+            tracing::info!("reloaded params from {}", self.path.display());
+        }
+        Ok(())
+    }
+
+    /// Reload the file into `params` if it changed, for loops that grow
+    /// without the window to call every step. An invalid file is logged,
+    /// not to stop a long run over a typo.
+    pub(crate) fn poll(&mut self, params: &mut Params) {
+        if let Err(err) = self.reload_if_changed(params) {
+            tracing::error!("{err:#}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn params_files_change_only_what_they_have() {
+        let changes = r##"
+            near-l = 3
+            step = 0.25
+            spawn-probability = 0.02
+            growth = ["#ff0000", "#00ff00"]
+        "##
+        .parse::<Changes>()
+        .unwrap();
+
+        let mut params = Params::DEFAULT;
+        changes.apply_to_params(&mut params);
+        assert_eq!(
+            params,
+            Params {
+                near_l: 3. * ONE,
+                step: 0.25 * ONE,
+                spawn_probability: 0.02,
+                ..Params::DEFAULT
+            }
+        );
+
+        let style = Style::default();
+        let changed = changes.apply_to_style(&style).unwrap();
+        assert_eq!(changed.background, style.background);
+        assert_eq!(changed.shape, style.shape);
+        assert_eq!(
+            changed.growth,
+            ["#ff0000", "#00ff00"]
+                .map(|color| style::parse_color(color).unwrap())
+        );
+
+        let changes = "far-l = 20".parse::<Changes>().unwrap();
+        assert!(changes.apply_to_style(&style).is_none());
+    }
+
+    #[test]
+    fn params_files_are_applied_again_when_they_change() {
+        let dir = std::env::temp_dir().join("dxdy-draw-params-file-test");
+        _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("params.toml");
+        std::fs::write(&path, "near-l = 3").unwrap();
+
+        let mut file = ParamsFile::open(path.clone()).unwrap();
+        let mut params = Params::DEFAULT;
+        file.reload_if_changed(&mut params).unwrap();
+        assert_eq!(params.near_l, 3. * ONE);
+
+        // editors may save by replacing the file
+        std::fs::write(dir.join("params.toml.new"), "near-l = 4").unwrap();
+        std::fs::rename(dir.join("params.toml.new"), &path).unwrap();
+        let start = std::time::Instant::now();
+        while params.near_l != 4. * ONE {
+            assert!(start.elapsed().as_secs() < 10, "change not found");
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            file.reload_if_changed(&mut params).unwrap();
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn invalid_params_files_are_rejected() {
+        assert!("near_l = 3".parse::<Changes>().is_err());
+        assert!("step = \"fast\"".parse::<Changes>().is_err());
+        assert!("background = \"nope\"".parse::<Changes>().is_err());
+        assert!("growth = []".parse::<Changes>().is_err());
+        assert!("far-l = 0".parse::<Changes>().is_err());
+        assert!("near-l = -1".parse::<Changes>().is_err());
+        assert!("step = nan".parse::<Changes>().is_err());
+        assert!("step = inf".parse::<Changes>().is_err());
+        assert!("spawn-probability = 1.5".parse::<Changes>().is_err());
+        assert!("spawn-probability = -0.1".parse::<Changes>().is_err());
+        assert!("spawn-probability = nan".parse::<Changes>().is_err());
+    }
+}
@@ -0,0 +1,393 @@
+use std::{sync::RwLock, time::Duration};
+
+use gtk::{glib, prelude::*};
+
+use super::{
+    algorithm::{BoundaryMode, IndexKind, ONE, Params, SpawnMode},
+    shape::smooth::{Method, SMOOTH_OPTIONS},
+    simulation::STOP_CONDITIONS,
+};
+
+pub(crate) static PARAMS: RwLock<Params> = RwLock::new(Params::DEFAULT);
+
+/// How often the controls follow changes to what they control made
+/// elsewhere, such as by a replay or a mutation.
+const SYNC_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Controls added to a panel, each writing into a static when changed and
+/// set again from it when it changes otherwise.
+struct Controls {
+    panel: gtk::Box,
+    /// Set each control from its static, if that changed.
+    syncs: Vec<Box<dyn Fn()>>,
+}
+
+impl Controls {
+    /// A labeled slider that writes its value into `target` when moved, and
+    /// shows the value `get` reads from it.
+    fn slider<S: Send + Sync + 'static>(
+        &mut self,
+        label: &str,
+        range: (f64, f64, f64),
+        target: &'static RwLock<S>,
+        get: fn(&S) -> f64,
+        set: fn(&mut S, f64),
+    ) {
+        let (min, max, step) = range;
+
+        self.panel
+            .append(&gtk::Label::builder().label(label).xalign(0.).build());
+
+        let scale = gtk::Scale::with_range(
+            gtk::Orientation::Horizontal,
+            min,
+            max,
+            step,
+        );
+        scale.set_value(get(&target.read().unwrap()));
+        scale.set_draw_value(true);
+        scale.set_digits(step.log10().min(0.).abs().ceil() as i32);
+        scale.connect_value_changed(move |scale| {
+            set(&mut target.write().unwrap(), scale.value());
+        });
+
+        self.panel.append(&scale);
+        self.syncs.push(Box::new(move || {
+            let value = get(&target.read().unwrap()).clamp(min, max);
+            // not for the rounding of values set by the slider itself
+            if (scale.value() - value).abs() > step / 2. {
+                scale.set_value(value);
+            }
+        }));
+    }
+
+    /// A labeled drop-down of `options` that writes the selected value into
+    /// `target`, and shows the value `get` reads from it.
+    fn choice<S: Send + Sync + 'static, T: Copy + PartialEq + 'static>(
+        &mut self,
+        label: &str,
+        options: &'static [(&'static str, T)],
+        target: &'static RwLock<S>,
+        get: fn(&S) -> T,
+        set: fn(&mut S, T),
+    ) {
+        self.panel
+            .append(&gtk::Label::builder().label(label).xalign(0.).build());
+
+        let names = options.iter().map(|&(name, _)| name).collect::<Vec<_>>();
+        let dropdown = gtk::DropDown::from_strings(&names);
+        let select = move |dropdown: &gtk::DropDown| {
+            let value = get(&target.read().unwrap());
+            let i = options.iter().position(|&(_, v)| v == value);
+            if let Some(i) = i.filter(|&i| i != dropdown.selected() as usize) {
+                dropdown.set_selected(i as u32);
+            }
+        };
+        select(&dropdown);
+        dropdown.connect_selected_notify(move |dropdown| {
+            if let Some(&(_, v)) = options.get(dropdown.selected() as usize) {
+                set(&mut target.write().unwrap(), v);
+            }
+        });
+
+        self.panel.append(&dropdown);
+        self.syncs.push(Box::new(move || select(&dropdown)));
+    }
+}
+
+/// Sidebar with a control for each of the growth [`Params`], followed by
+/// the stroke smoothing options and the stop conditions, each off at 0.
+///
+/// Distances are shown in units of [`ONE`], a thousandth of the simulation
+/// space. The controls show the values of the panels of every window, and
+/// of replays and mutations, as they change.
+pub(crate) fn build() -> gtk::Box {
+    let panel = gtk::Box::builder()
+        .orientation(gtk::Orientation::Vertical)
+        .spacing(4)
+        .margin_top(8)
+        .margin_bottom(8)
+        .margin_start(8)
+        .margin_end(8)
+        .width_request(220)
+        .build();
+    let mut controls = Controls {
+        panel: panel.clone(),
+        syncs: Vec::new(),
+    };
+
+    controls.slider(
+        "Near distance",
+        (0.5, 20., 0.1),
+        &PARAMS,
+        |p| p.near_l / ONE,
+        |p, v| p.near_l = v * ONE,
+    );
+    controls.slider(
+        "Far distance",
+        (5., 100., 1.),
+        &PARAMS,
+        |p| p.far_l / ONE,
+        |p, v| p.far_l = v * ONE,
+    );
+    controls.slider(
+        "Step",
+        (0.05, 2., 0.05),
+        &PARAMS,
+        |p| p.step / ONE,
+        |p, v| p.step = v * ONE,
+    );
+    controls.slider(
+        "Spawn probability",
+        (0., 0.1, 0.001),
+        &PARAMS,
+        |p| p.spawn_probability,
+        |p, v| p.spawn_probability = v,
+    );
+    controls.slider(
+        "Branch probability",
+        (0., 0.1, 0.001),
+        &PARAMS,
+        |p| p.branch_probability,
+        |p, v| p.branch_probability = v,
+    );
+    controls.slider(
+        "Edge equalization",
+        (0., 1., 0.05),
+        &PARAMS,
+        |p| p.equalize,
+        |p, v| p.equalize = v,
+    );
+    controls.slider(
+        "Stiffness",
+        (0., 10., 0.1),
+        &PARAMS,
+        |p| p.stiffness,
+        |p, v| p.stiffness = v,
+    );
+    controls.slider(
+        "Noise amplitude",
+        (0., 2., 0.05),
+        &PARAMS,
+        |p| p.noise_amplitude,
+        |p, v| p.noise_amplitude = v,
+    );
+    controls.slider(
+        "Noise scale",
+        (10., 500., 1.),
+        &PARAMS,
+        |p| p.noise_scale / ONE,
+        |p, v| p.noise_scale = v * ONE,
+    );
+    controls.slider(
+        "Max vertices",
+        (1e4, 1e7, 1e4),
+        &PARAMS,
+        |p| p.n_max as f64,
+        |p, v| p.n_max = v as u64,
+    );
+    controls.choice(
+        "Spawn mode",
+        &[
+            ("Uniform", SpawnMode::Uniform),
+            ("Curvature", SpawnMode::Curvature),
+        ],
+        &PARAMS,
+        |p| p.spawn_mode,
+        |p, v| p.spawn_mode = v,
+    );
+    controls.slider(
+        "Boundary margin",
+        (0., 50., 0.1),
+        &PARAMS,
+        |p| p.boundary_margin / ONE,
+        |p, v| p.boundary_margin = v * ONE,
+    );
+    controls.choice(
+        "At the boundary",
+        &[
+            ("Stop", BoundaryMode::Stop),
+            ("Clamp", BoundaryMode::Clamp),
+            ("Reflect", BoundaryMode::Reflect),
+            ("Wrap around", BoundaryMode::Wrap),
+            ("Delete vertices", BoundaryMode::Kill),
+        ],
+        &PARAMS,
+        |p| p.boundary_mode,
+        |p, v| p.boundary_mode = v,
+    );
+    controls.choice(
+        "Topology",
+        &[("Loose", false), ("Strict", true)],
+        &PARAMS,
+        |p| p.strict_topology,
+        |p, v| p.strict_topology = v,
+    );
+    controls.choice(
+        "Neighbor search",
+        &[
+            ("Zone map", IndexKind::ZoneMap),
+            ("Quadtree", IndexKind::QuadTree),
+        ],
+        &PARAMS,
+        |p| p.index,
+        |p, v| p.index = v,
+    );
+    controls.slider(
+        "Neighbor skin",
+        (0., 20., 0.5),
+        &PARAMS,
+        |p| p.neighbor_skin / ONE,
+        |p, v| p.neighbor_skin = v * ONE,
+    );
+    controls.slider(
+        "Min edge length",
+        (0., 10., 0.1),
+        &PARAMS,
+        |p| p.min_edge_length / ONE,
+        |p, v| p.min_edge_length = v * ONE,
+    );
+    controls.slider(
+        "Max line length",
+        (0., 50_000., 100.),
+        &PARAMS,
+        |p| p.max_length / ONE,
+        |p, v| p.max_length = v * ONE,
+    );
+    controls.slider(
+        "Region: left",
+        (0., 1000., 1.),
+        &PARAMS,
+        |p| p.region[0].x / ONE,
+        |p, v| p.region[0].x = v * ONE,
+    );
+    controls.slider(
+        "Region: top",
+        (0., 1000., 1.),
+        &PARAMS,
+        |p| p.region[0].y / ONE,
+        |p, v| p.region[0].y = v * ONE,
+    );
+    controls.slider(
+        "Region: right",
+        (0., 1000., 1.),
+        &PARAMS,
+        |p| p.region[1].x / ONE,
+        |p, v| p.region[1].x = v * ONE,
+    );
+    controls.slider(
+        "Region: bottom",
+        (0., 1000., 1.),
+        &PARAMS,
+        |p| p.region[1].y / ONE,
+        |p, v| p.region[1].y = v * ONE,
+    );
+    controls.slider(
+        "Seed padding",
+        (0., 200., 1.),
+        &PARAMS,
+        |p| p.seed_padding / ONE,
+        |p, v| p.seed_padding = v * ONE,
+    );
+    controls.slider(
+        "Spine corridor",
+        (5., 200., 1.),
+        &PARAMS,
+        |p| p.spine_corridor / ONE,
+        |p, v| p.spine_corridor = v * ONE,
+    );
+    controls.slider(
+        "Seed jitter: offset",
+        (0., 100., 1.),
+        &PARAMS,
+        |p| p.seed_jitter.offset / ONE,
+        |p, v| p.seed_jitter.offset = v * ONE,
+    );
+    controls.slider(
+        "Seed jitter: rotation (degrees)",
+        (0., 180., 1.),
+        &PARAMS,
+        |p| p.seed_jitter.rotation.to_degrees(),
+        |p, v| p.seed_jitter.rotation = v.to_radians(),
+    );
+    controls.slider(
+        "Seed jitter: scale (%)",
+        (0., 50., 1.),
+        &PARAMS,
+        |p| p.seed_jitter.scale * 100.,
+        |p, v| p.seed_jitter.scale = v / 100.,
+    );
+
+    panel.append(&gtk::Separator::new(gtk::Orientation::Horizontal));
+
+    controls.choice(
+        "Stroke smoothing",
+        &[
+            ("Off", Method::Off),
+            ("Chaikin", Method::Chaikin),
+            ("Catmull-Rom", Method::CatmullRom),
+        ],
+        &SMOOTH_OPTIONS,
+        |o| o.method,
+        |o, v| o.method = v,
+    );
+    controls.slider(
+        "Smoothing iterations",
+        (1., 5., 1.),
+        &SMOOTH_OPTIONS,
+        |o| o.iterations as f64,
+        |o, v| o.iterations = v as u32,
+    );
+    controls.slider(
+        "Stroke vertex spacing (px)",
+        (0., 100., 1.),
+        &SMOOTH_OPTIONS,
+        |o| o.spacing,
+        |o, v| o.spacing = v,
+    );
+
+    panel.append(&gtk::Separator::new(gtk::Orientation::Horizontal));
+
+    controls.slider(
+        "Stop after iterations",
+        (0., 100_000., 100.),
+        &STOP_CONDITIONS,
+        |s| s.max_iterations.unwrap_or(0) as f64,
+        |s, v| s.max_iterations = (v > 0.).then_some(v as u64),
+    );
+    controls.slider(
+        "Stop at vertices",
+        (0., 200_000., 1000.),
+        &STOP_CONDITIONS,
+        |s| s.max_vertices.unwrap_or(0) as f64,
+        |s, v| s.max_vertices = (v > 0.).then_some(v as usize),
+    );
+    controls.slider(
+        "Stop after minutes",
+        (0., 240., 1.),
+        &STOP_CONDITIONS,
+        |s| s.time_limit.map_or(0., |limit| limit.as_secs_f64() / 60.),
+        |s, v| {
+            s.time_limit = (v > 0.).then(|| Duration::from_secs_f64(v * 60.))
+        },
+    );
+    controls.slider(
+        "Stop when moving less than",
+        (0., 0.1, 0.001),
+        &STOP_CONDITIONS,
+        |s| s.stall.unwrap_or(0.) / ONE,
+        |s, v| s.stall = (v > 0.).then_some(v * ONE),
+    );
+
+    let syncs = controls.syncs;
+    let panel_ref = panel.downgrade();
+    glib::timeout_add_local(SYNC_INTERVAL, move || {
+        if panel_ref.upgrade().is_none() {
+            return glib::ControlFlow::Break;
+        }
+        syncs.iter().for_each(|sync| sync());
+        glib::ControlFlow::Continue
+    });
+
+    panel
+}
@@ -0,0 +1,56 @@
+#[cfg(feature = "gui")]
+use std::time::Instant;
+
+#[cfg(feature = "gui")]
+use super::shape::Shape;
+
+/// Speed multiplier used for fast playback.
+pub(crate) const FAST_SPEED: f64 = 4.;
+
+/// Replay of the completed shapes in the order and at the pace they were
+/// drawn.
+pub(crate) struct Playback {
+    #[cfg(feature = "gui")]
+    started: Instant,
+    #[cfg(feature = "gui")]
+    speed: f64,
+}
+
+impl Playback {
+    #[cfg_attr(not(feature = "gui"), expect(unused_variables))]
+    pub(crate) fn new(speed: f64) -> Self {
+        Self {
+            #[cfg(feature = "gui")]
+            started: Instant::now(),
+            #[cfg(feature = "gui")]
+            speed,
+        }
+    }
+
+    /// Stroke time in milliseconds since the playback started, scaled by
+    /// the playback speed.
+    #[cfg(feature = "gui")]
+    fn elapsed(&self) -> u32 {
+        (self.started.elapsed().as_secs_f64() * 1000. * self.speed) as u32
+    }
+
+    /// Index of the shape currently being replayed and its number of visible
+    /// vertices. Shapes before it are fully visible and shapes after it are
+    /// hidden.
+    ///
+    /// Returns `None` once every shape has been replayed.
+    #[cfg(feature = "gui")]
+    pub(crate) fn progress(&self, shapes: &[Shape]) -> Option<(usize, usize)> {
+        let mut t = self.elapsed();
+
+        for (i, shape) in shapes.iter().enumerate() {
+            let duration = shape.duration();
+            if t <= duration {
+                return Some((i, shape.n_verticies_at(t)));
+            }
+            t -= duration;
+        }
+
+        None
+    }
+}
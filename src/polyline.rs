@@ -0,0 +1,197 @@
+use glam::DVec2;
+
+use super::{algorithm::SegmentPath, shape::Shape};
+
+/// An ordered run of points that is either open or closed, so that code
+/// which only needs the geometry works on drawn shapes and simulation output
+/// alike.
+pub(crate) trait Polyline {
+    /// Points in order. A closed polyline does not repeat its first point at
+    /// the end.
+    fn points(&self) -> impl Iterator<Item = DVec2>;
+
+    /// Whether the last point is joined back to the first.
+    fn is_closed(&self) -> bool;
+
+    /// Points of each edge in order, including the closing edge if closed.
+    fn edges(&self) -> impl Iterator<Item = (DVec2, DVec2)> {
+        let points = self.points().collect::<Vec<_>>();
+        let closing = match (self.is_closed(), points.first(), points.last()) {
+            (true, Some(&first), Some(&last)) if points.len() > 2 => {
+                Some((last, first))
+            }
+            _ => None,
+        };
+
+        let open = (1..points.len()).map(move |i| (points[i - 1], points[i]));
+        open.chain(closing)
+    }
+
+    /// Total length of the edges.
+    fn length(&self) -> f64 {
+        self.edges().map(|(a, b)| a.distance(b)).sum()
+    }
+
+    /// Whether `p` is inside the polyline, which only a closed one has.
+    /// Parts that the polyline winds around twice are outside.
+    #[cfg(any(feature = "gui", test))]
+    fn contains(&self, p: DVec2) -> bool {
+        if !self.is_closed() {
+            return false;
+        }
+        // crossings of the edges with a ray from `p` along the x axis
+        let crossings = self
+            .edges()
+            .filter(|&(a, b)| {
+                (a.y > p.y) != (b.y > p.y)
+                    && p.x < a.x + (p.y - a.y) / (b.y - a.y) * (b.x - a.x)
+            })
+            .count();
+        crossings % 2 == 1
+    }
+
+    /// Points spaced `spacing` apart along the polyline, starting at its
+    /// first point. The result is closed if this polyline is.
+    fn resample(&self, spacing: f64) -> Vec<DVec2> {
+        let mut edges = self.edges().peekable();
+        let Some(&(first, _)) = edges.peek() else {
+            return self.points().collect();
+        };
+        if spacing <= 0. {
+            return self.points().collect();
+        }
+
+        let mut samples = vec![first];
+        let mut end = first;
+        // distance along the polyline to the next sample
+        let mut next = spacing;
+        // distance along the polyline to the start of the current edge
+        let mut travelled = 0.;
+
+        for (a, b) in edges {
+            let len = a.distance(b);
+            while next <= travelled + len {
+                samples.push(a.lerp(b, (next - travelled) / len));
+                next += spacing;
+            }
+            travelled += len;
+            end = b;
+        }
+
+        // a last sample this close to the end is replaced by the end, which
+        // for a closed polyline is the first sample
+        let remaining = travelled - (next - spacing);
+        if remaining < spacing / 2. && samples.len() > 1 {
+            samples.pop();
+        }
+        if !self.is_closed() {
+            samples.push(end);
+        }
+
+        samples
+    }
+}
+
+impl Polyline for Shape {
+    fn points(&self) -> impl Iterator<Item = DVec2> {
+        let start = self.start();
+        self.verticies().map(move |offset| start + offset)
+    }
+
+    fn is_closed(&self) -> bool {
+        self.closed()
+    }
+}
+
+impl Polyline for SegmentPath {
+    fn points(&self) -> impl Iterator<Item = DVec2> {
+        self.points.iter().copied()
+    }
+
+    fn is_closed(&self) -> bool {
+        self.closed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shape::Pen;
+
+    /// A shape through `points`, closed if `closed`.
+    fn shape(points: &[DVec2], closed: bool) -> Shape {
+        let mut shape = Shape::starting_at(points[0]);
+        for (i, &p) in points.iter().enumerate() {
+            shape.next_vertex_at(p - points[0], i as u32 * 10, Pen::DEFAULT);
+        }
+        shape.set_closed(closed);
+        shape
+    }
+
+    /// A path of the simulation through `points`, closed if `closed`.
+    fn path(points: &[DVec2], closed: bool) -> SegmentPath {
+        SegmentPath {
+            segment: 0,
+            passive: false,
+            points: points.to_vec(),
+            closed,
+        }
+    }
+
+    fn square(size: f64) -> Vec<DVec2> {
+        [(0., 0.), (1., 0.), (1., 1.), (0., 1.)]
+            .map(|(x, y)| DVec2::new(x, y) * size)
+            .to_vec()
+    }
+
+    #[test]
+    fn shapes_and_paths_agree() {
+        let points = square(10.);
+        for closed in [false, true] {
+            let (shape, path) =
+                (shape(&points, closed), path(&points, closed));
+            assert_eq!(
+                shape.edges().collect::<Vec<_>>(),
+                path.edges().collect::<Vec<_>>()
+            );
+            assert_eq!(shape.length(), path.length());
+            assert_eq!(shape.resample(3.), path.resample(3.));
+        }
+
+        let (open, closed) = (path(&points, false), path(&points, true));
+        assert_eq!(open.edges().count(), 3);
+        assert_eq!(open.length(), 30.);
+        // the closing edge back to the first point
+        assert_eq!(closed.edges().last(), Some((points[3], points[0])));
+        assert_eq!(closed.length(), 40.);
+    }
+
+    #[test]
+    fn only_closed_polylines_contain_points() {
+        let points = square(10.);
+        let inside = DVec2::splat(5.);
+        assert!(path(&points, true).contains(inside));
+        assert!(!path(&points, true).contains(DVec2::new(15., 5.)));
+        assert!(!path(&points, false).contains(inside));
+
+        // wound around the square twice, an even number of crossings
+        let twice = [points.clone(), points].concat();
+        assert!(!path(&twice, true).contains(inside));
+    }
+
+    #[test]
+    fn resampled_polylines_are_evenly_spaced() {
+        let line = path(&[DVec2::ZERO, DVec2::new(10., 0.)], false);
+        let xs = line.resample(3.).iter().map(|p| p.x).collect::<Vec<_>>();
+        // the last sample, 1 from the end, is replaced by the end
+        assert_eq!(xs, [0., 3., 6., 10.]);
+        assert_eq!(line.resample(0.), line.points);
+
+        // closed ones do not repeat their first point
+        let samples = path(&square(10.), true).resample(5.);
+        assert_eq!(samples.len(), 8);
+        for pair in samples.windows(2) {
+            assert!((pair[0].distance(pair[1]) - 5.).abs() < 1e-9);
+        }
+    }
+}
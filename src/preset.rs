@@ -0,0 +1,547 @@
+//! Export settings saved under a name, to export the same way again, as
+//! for a plotter, without setting everything up each time.
+
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use anyhow::{Context, Result};
+use glam::DVec2;
+
+use super::{
+    algorithm::Simulation,
+    config_dir,
+    export::{self, Crop, DOCUMENT_DPI, ImageFormat},
+    layers::{CanvasLayer, CanvasLayers},
+    scene::{self, Color, Layer, Paint, Scene},
+    shape::Shape,
+    style::{Style, next_color, write_color},
+    transform::DOCUMENT_SIZE,
+};
+
+/// First line of every preset file.
+const HEADER: &str = "dxdy.draw preset 1";
+
+/// Extension of preset files.
+const EXTENSION: &str = "preset";
+
+/// Which parts of the document are exported.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Layers {
+    pub(crate) background: bool,
+    pub(crate) shapes: bool,
+    pub(crate) growth: bool,
+}
+
+impl Layers {
+    pub(crate) const ALL: Self = Self {
+        background: true,
+        shapes: true,
+        growth: true,
+    };
+
+    const NAMES: [&str; 3] = ["background", "shapes", "growth"];
+
+    fn get_mut(&mut self, name: &str) -> Option<&mut bool> {
+        match name {
+            "background" => Some(&mut self.background),
+            "shapes" => Some(&mut self.shapes),
+            "growth" => Some(&mut self.growth),
+            _ => None,
+        }
+    }
+
+    fn get(self, name: &str) -> bool {
+        let mut layers = self;
+        layers.get_mut(name).is_some_and(|included| *included)
+    }
+}
+
+/// Parses the names of the included layers, separated by commas, as given
+/// on the command line.
+impl FromStr for Layers {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut layers = Self {
+            background: false,
+            shapes: false,
+            growth: false,
+        };
+        for name in s.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+            *layers.get_mut(name).with_context(|| {
+                format!(
+                    "unknown layer: {name}, expected {}",
+                    Self::NAMES.join(", ")
+                )
+            })? = true;
+        }
+        Ok(layers)
+    }
+}
+
+/// How a document is exported.
+///
+/// Preset files are plain text like styles, with one record per line, each
+/// the name of a setting followed by its values. Settings without a record
+/// keep their default, which together export the whole document like the
+/// `render` command. A `pen` record takes the color lines are drawn in and
+/// the color to draw them in instead, as with the pens of a plotter.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Preset {
+    /// Format of the images, or `None` to pick it by the extension of the
+    /// file they are written to.
+    pub(crate) format: Option<ImageFormat>,
+    /// Resolution of PNG images. The canvas is [`DOCUMENT_DPI`].
+    pub(crate) dpi: f64,
+    /// Size the document is scaled to fit in, in pixels of the canvas, or
+    /// `None` to keep its size.
+    pub(crate) size: Option<DVec2>,
+    pub(crate) layers: Layers,
+    /// Each color lines are drawn in, and the color of the pen they are
+    /// drawn with instead.
+    pub(crate) pens: Vec<(Color, Color)>,
+    /// Where images are written, if not given with each export.
+    pub(crate) out: Option<PathBuf>,
+}
+
+impl Default for Preset {
+    fn default() -> Self {
+        Self {
+            format: None,
+            dpi: DOCUMENT_DPI,
+            size: None,
+            layers: Layers::ALL,
+            pens: Vec::new(),
+            out: None,
+        }
+    }
+}
+
+/// Whether `name` can name a preset file in the presets directory.
+fn check_name(name: &str) -> Result<()> {
+    anyhow::ensure!(
+        !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'),
+        "invalid preset name, expected letters, digits, - and _: {name}"
+    );
+    Ok(())
+}
+
+impl Preset {
+    /// `presets` in the config directory, where presets are saved by name.
+    pub(crate) fn dir() -> Result<PathBuf> {
+        Ok(config_dir().context("no config directory")?.join("presets"))
+    }
+
+    /// Names of the saved presets, in alphabetical order.
+    pub(crate) fn names() -> Result<Vec<String>> {
+        let dir = Self::dir()?;
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(Vec::new());
+            }
+            Err(err) => {
+                return Err(err).with_context(|| {
+                    format!("failed to read {}", dir.display())
+                });
+            }
+        };
+
+        let mut names = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == EXTENSION) {
+                names.extend(
+                    path.file_stem()
+                        .and_then(|stem| stem.to_str())
+                        .map(str::to_owned),
+                );
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    fn path(name: &str) -> Result<PathBuf> {
+        check_name(name)?;
+        Ok(Self::dir()?.join(name).with_extension(EXTENSION))
+    }
+
+    /// The saved preset `name`.
+    pub(crate) fn named(name: &str) -> Result<Self> {
+        Self::load(&Self::path(name)?)
+            .with_context(|| format!("failed to load preset {name}"))
+    }
+
+    /// Save the preset as `name`, replacing any saved before, and return
+    /// where it was saved.
+    pub(crate) fn save_as(&self, name: &str) -> Result<PathBuf> {
+        let path = Self::path(name)?;
+        std::fs::create_dir_all(Self::dir()?)?;
+        self.save(&path)?;
+        Ok(path)
+    }
+
+    pub(crate) fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        text.parse()
+            .with_context(|| format!("invalid preset: {}", path.display()))
+    }
+
+    pub(crate) fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, self.to_string())
+            .with_context(|| format!("failed to write {}", path.display()))
+    }
+
+    /// The format of images written to `out`: the format of the preset,
+    /// which `out` has to have the extension of, or else the format of its
+    /// extension.
+    pub(crate) fn format_of(&self, out: &Path) -> Result<ImageFormat> {
+        let of_path = ImageFormat::of_path(out)?;
+        match self.format {
+            Some(format) if format != of_path => anyhow::bail!(
+                "the preset exports .{} images: {}",
+                format.extension(),
+                out.display()
+            ),
+            _ => Ok(of_path),
+        }
+    }
+
+    /// The layers of the preset of the selected shapes of `shapes` and
+    /// `simulation`, cut down to `crop` if there is one, and scaled and
+    /// drawn with the pens of the preset. Of the layers of the canvas, only
+    /// those `canvas` shows are drawn, as it shows them.
+    pub(crate) fn scene(
+        &self,
+        style: &Style,
+        canvas: &CanvasLayers,
+        shapes: &[Shape],
+        simulation: Option<&Simulation>,
+        crop: Option<&Crop>,
+    ) -> Scene {
+        let shapes = match crop {
+            Some(crop) => crop.select(shapes),
+            None => shapes.to_vec(),
+        };
+
+        let mut items = Vec::new();
+        for layer in canvas.order() {
+            let drawn = match (layer, simulation) {
+                (CanvasLayer::Obstacles | CanvasLayer::Sketches, _)
+                    if self.layers.shapes =>
+                {
+                    scene::layer_shapes(
+                        style,
+                        &shapes,
+                        style.shape,
+                        None,
+                        layer,
+                    )
+                }
+                (CanvasLayer::Growth, Some(simulation))
+                    if self.layers.growth =>
+                {
+                    scene::growth(style, simulation, 1.)
+                }
+                _ => continue,
+            };
+            items.extend(canvas.show(layer, drawn));
+        }
+
+        for item in &mut items {
+            let color = match &mut item.paint {
+                #[cfg(feature = "gui")]
+                Paint::Fill(color) => color,
+                Paint::Stroke(stroke) => &mut stroke.color,
+            };
+            let pen = self
+                .pens
+                .iter()
+                .find(|(from, _)| from.to_rgb8() == color.to_rgb8());
+            if let Some(&(_, pen)) = pen {
+                *color = pen;
+            }
+        }
+
+        let mut scene = Scene {
+            size: DOCUMENT_SIZE,
+            background: if self.layers.background {
+                canvas.background(style.background)
+            } else {
+                None
+            },
+            layers: vec![Layer::new(items)],
+        };
+        if let Some(crop) = crop {
+            scene = scene.crop(crop.region.min, crop.region.max);
+        }
+        match self.size {
+            Some(size) => {
+                let factor = (size / scene.size).min_element();
+                scene.scaled(factor)
+            }
+            None => scene,
+        }
+    }
+
+    /// Write `shapes` and `simulation` to `out` with the preset, cut down
+    /// to `crop` if there is one.
+    pub(crate) fn export(
+        &self,
+        out: &Path,
+        style: &Style,
+        shapes: &[Shape],
+        simulation: Option<&Simulation>,
+        crop: Option<&Crop>,
+    ) -> Result<()> {
+        let format = self.format_of(out)?;
+        let scene = self.scene(
+            style,
+            &CanvasLayers::DEFAULT,
+            shapes,
+            simulation,
+            crop,
+        );
+        export::write_image(out, format, self.dpi, &scene)
+    }
+}
+
+impl fmt::Display for Preset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{HEADER}")?;
+
+        if let Some(format) = self.format {
+            writeln!(f, "format {}", format.extension())?;
+        }
+        writeln!(f, "dpi {}", self.dpi)?;
+        if let Some(size) = self.size {
+            writeln!(f, "size {} {}", size.x, size.y)?;
+        }
+
+        write!(f, "layers")?;
+        for name in Layers::NAMES {
+            if self.layers.get(name) {
+                write!(f, " {name}")?;
+            }
+        }
+        writeln!(f)?;
+
+        for &(from, to) in &self.pens {
+            write!(f, "pen")?;
+            write_color(f, from)?;
+            write_color(f, to)?;
+            writeln!(f)?;
+        }
+
+        if let Some(out) = &self.out {
+            writeln!(f, "out {}", out.display())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Parse the next value of a record as a positive number.
+fn next_positive<'a>(
+    values: &mut impl Iterator<Item = &'a str>,
+) -> Result<f64> {
+    let value = values.next().context("missing value")?;
+    value
+        .parse()
+        .ok()
+        .filter(|value: &f64| *value > 0. && value.is_finite())
+        .with_context(|| format!("invalid value: {value}"))
+}
+
+/// Parses `WIDTH,HEIGHT`, as given on the command line.
+pub(crate) fn parse_size(s: &str) -> Result<DVec2> {
+    let mut values = s.split(',').map(str::trim);
+    let size =
+        DVec2::new(next_positive(&mut values)?, next_positive(&mut values)?);
+    anyhow::ensure!(values.next().is_none(), "expected WIDTH,HEIGHT: {s}");
+    Ok(size)
+}
+
+/// Parses `FROM=TO`, two colors as in style files, as given on the command
+/// line.
+pub(crate) fn parse_pen(s: &str) -> Result<(Color, Color)> {
+    let (from, to) = s
+        .split_once('=')
+        .with_context(|| format!("expected FROM=TO: {s}"))?;
+    Ok((
+        next_color(&mut [from].into_iter())?,
+        next_color(&mut [to].into_iter())?,
+    ))
+}
+
+impl FromStr for Preset {
+    type Err = anyhow::Error;
+
+    fn from_str(text: &str) -> Result<Self> {
+        let mut lines = text.lines().enumerate();
+
+        match lines.next() {
+            Some((_, HEADER)) => {}
+            _ => anyhow::bail!("missing header: {HEADER}"),
+        }
+
+        let mut preset = Self::default();
+
+        for (i, line) in lines {
+            let mut values = line.split_whitespace();
+            let Some(keyword) = values.next() else {
+                continue;
+            };
+
+            let mut record = || -> Result<()> {
+                match keyword {
+                    "format" => {
+                        let format =
+                            values.next().context("missing format")?;
+                        preset.format = Some(match format {
+                            "svg" => ImageFormat::Svg,
+                            "png" => ImageFormat::Png,
+                            _ => anyhow::bail!("unknown format: {format}"),
+                        });
+                    }
+                    "dpi" => preset.dpi = next_positive(&mut values)?,
+                    "size" => {
+                        preset.size = Some(DVec2::new(
+                            next_positive(&mut values)?,
+                            next_positive(&mut values)?,
+                        ))
+                    }
+                    "layers" => {
+                        let names = values.by_ref().collect::<Vec<_>>();
+                        preset.layers = names.join(",").parse()?;
+                    }
+                    "pen" => preset.pens.push((
+                        next_color(&mut values)?,
+                        next_color(&mut values)?,
+                    )),
+                    "out" => {
+                        // the rest of the line, which may have spaces
+                        let out = line.trim()[keyword.len()..].trim();
+                        anyhow::ensure!(!out.is_empty(), "missing path");
+                        preset.out = Some(PathBuf::from(out));
+                        values.by_ref().for_each(drop);
+                    }
+                    _ => anyhow::bail!("unknown setting: {keyword}"),
+                }
+
+                anyhow::ensure!(values.next().is_none(), "too many values");
+                Ok(())
+            };
+
+            record().with_context(|| format!("line {}", i + 1))?;
+        }
+
+        Ok(preset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{export::Region, layers::LayerSettings, shape::Pen};
+
+    fn plotter() -> Preset {
+        Preset {
+            format: Some(ImageFormat::Svg),
+            dpi: 300.,
+            size: Some(DVec2::new(400., 300.)),
+            layers: "shapes,growth".parse().unwrap(),
+            pens: vec![(Style::default().shape, Color::rgb8(0, 0, 0))],
+            out: Some(PathBuf::from("/tmp/my plots/drawing.svg")),
+        }
+    }
+
+    #[test]
+    fn saved_preset_loads_the_same() {
+        let preset = plotter();
+        assert_eq!(preset.to_string().parse::<Preset>().unwrap(), preset);
+
+        let default = Preset::default();
+        assert_eq!(default.to_string().parse::<Preset>().unwrap(), default);
+    }
+
+    #[test]
+    fn invalid_presets_are_rejected() {
+        for record in [
+            "format jpg",
+            "dpi 0",
+            "size 100",
+            "layers shapes cursor",
+            "pen #ffffff",
+            "out",
+            "plotter yes",
+        ] {
+            let text = format!("{HEADER}\n{record}\n");
+            assert!(text.parse::<Preset>().is_err(), "{record:?}");
+        }
+        assert!(check_name("../keys").is_err());
+    }
+
+    #[test]
+    fn scene_has_layers_pens_and_size_of_preset() {
+        let style = Style::default();
+        let mut shape = Shape::from_pos(0., 0., 0, Pen::DEFAULT);
+        shape.next_vertex(100., 0., 10, Pen::DEFAULT);
+        let shapes = [shape];
+        let crop =
+            Crop::region(Region::spanning(DVec2::ZERO, DVec2::splat(100.)));
+
+        let scene = plotter().scene(
+            &style,
+            &CanvasLayers::DEFAULT,
+            &shapes,
+            None,
+            Some(&crop),
+        );
+
+        assert_eq!(scene.background, None);
+        assert_eq!(scene.size, DVec2::splat(300.));
+        let [layer] = &scene.layers[..] else {
+            panic!("expected one layer");
+        };
+        assert_eq!(layer.scale, 3.);
+        let [item] = &layer.items[..] else {
+            panic!("expected one item");
+        };
+        assert!(matches!(
+            item.paint,
+            Paint::Stroke(stroke) if stroke.color == Color::rgb8(0, 0, 0)
+        ));
+
+        // layers hidden on the canvas are not exported
+        let mut canvas = CanvasLayers::DEFAULT;
+        canvas.set(
+            CanvasLayer::Sketches,
+            LayerSettings {
+                visible: false,
+                opacity: 1.,
+            },
+        );
+        let scene = plotter().scene(&style, &canvas, &shapes, None, None);
+        assert!(scene.layers[0].items.is_empty());
+    }
+
+    #[test]
+    fn format_has_to_match_output() {
+        let preset = plotter();
+        assert!(preset.format_of(Path::new("a.svg")).is_ok());
+        assert!(preset.format_of(Path::new("a.png")).is_err());
+        assert_eq!(
+            Preset::default().format_of(Path::new("a.png")).unwrap(),
+            ImageFormat::Png
+        );
+    }
+}
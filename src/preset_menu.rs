@@ -0,0 +1,74 @@
+use gtk::{glib, prelude::*};
+
+use super::{
+    app_state::SharedState,
+    preset::Preset,
+    window::{export_image, show_error},
+};
+
+/// Button with a popover that exports the drawing of `state` as an image,
+/// with the default settings or with one of the saved presets.
+///
+/// Presets are listed again each time the popover opens, so that presets
+/// saved from the command line in the meantime are there too.
+pub(crate) fn build(state: &SharedState) -> gtk::MenuButton {
+    let list = gtk::Box::new(gtk::Orientation::Vertical, 4);
+    let popover = gtk::Popover::builder().child(&list).build();
+
+    popover.connect_show(glib::clone!(
+        #[weak]
+        list,
+        #[strong]
+        state,
+        move |popover| {
+            while let Some(child) = list.first_child() {
+                list.remove(&child);
+            }
+
+            let names = Preset::names().unwrap_or_else(|err| {
+                tracing::error!("failed to list presets: {err:#}");
+                Vec::new()
+            });
+            let entries =
+                std::iter::once(None).chain(names.into_iter().map(Some));
+            for name in entries {
+                let label = name.as_deref().unwrap_or("Image…");
+                let button = gtk::Button::with_label(label);
+                button.connect_clicked(glib::clone!(
+                    #[weak]
+                    popover,
+                    #[strong]
+                    state,
+                    move |button| {
+                        popover.popdown();
+                        let window =
+                            button.root().and_downcast::<gtk::Window>();
+                        let preset = match &name {
+                            Some(name) => Preset::named(name),
+                            None => Ok(Preset::default()),
+                        };
+                        match preset {
+                            Ok(preset) => {
+                                export_image(window, state.clone(), preset)
+                            }
+                            Err(err) => {
+                                tracing::error!("{err:#}");
+                                show_error(
+                                    window.as_ref(),
+                                    "Invalid preset",
+                                    format!("{err:#}"),
+                                );
+                            }
+                        }
+                    }
+                ));
+                list.append(&button);
+            }
+        }
+    ));
+
+    gtk::MenuButton::builder()
+        .label("Export")
+        .popover(&popover)
+        .build()
+}
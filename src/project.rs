@@ -0,0 +1,354 @@
+use std::{
+    fmt,
+    path::Path,
+    str::{FromStr, SplitWhitespace},
+};
+
+use anyhow::{Context, Result};
+use glam::DVec2;
+
+use super::{
+    algorithm::{Domain, Field, FieldKind, Primitive, PrimitiveKind},
+    import, seed_file,
+    shape::{Pen, Shape},
+    simulation::SimulationController,
+};
+
+/// First line of every project file.
+const HEADER: &str = "dxdy.draw project 1";
+
+/// A saved drawing: the drawn shapes and everything the simulation is
+/// seeded from, but not the simulation itself.
+///
+/// Project files are plain text with one record per line, each a keyword
+/// followed by its values. A `shape` record is followed by the `tags` and
+/// `layer` of the shape, if it has any, and a `vertex` record for each of
+/// its vertices.
+#[derive(Clone, Default)]
+pub(crate) struct Project {
+    pub(crate) shapes: Vec<Shape>,
+    pub(crate) primitives: Vec<Primitive>,
+    /// Forces placed on the growth.
+    pub(crate) fields: Vec<Field>,
+    pub(crate) domain: Domain,
+    /// Seed for the simulation RNG, or a random one each time if `None`.
+    pub(crate) seed: Option<u64>,
+}
+
+impl Project {
+    /// Seed the next simulation of `controller` like this project, and
+    /// discard its current one. The shapes are passed on reset.
+    pub(crate) fn configure(&self, controller: &mut SimulationController) {
+        controller.clear();
+        controller.set_primitives(self.primitives.clone());
+        controller.set_fields(self.fields.clone());
+        controller.set_domain(self.domain.clone());
+        controller.set_seed(self.seed);
+    }
+
+    /// Load the project file at `path`, the seed file if it ends in
+    /// `.toml`, or the geometry of an SVG, GeoJSON or CSV file fit to the
+    /// canvas.
+    pub(crate) fn load(path: &Path) -> Result<Self> {
+        if path.extension().is_some_and(|ext| ext == "toml") {
+            return seed_file::load(path);
+        }
+        if import::is_geometry(path) {
+            return import::load(path);
+        }
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        text.parse()
+            .with_context(|| format!("invalid project: {}", path.display()))
+    }
+
+    #[cfg(any(feature = "gui", test))]
+    pub(crate) fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, self.to_string())
+            .with_context(|| format!("failed to write {}", path.display()))
+    }
+}
+
+impl fmt::Display for Project {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{HEADER}")?;
+
+        if let Some(seed) = self.seed {
+            writeln!(f, "seed {seed}")?;
+        }
+
+        match &self.domain {
+            Domain::Square => writeln!(f, "domain square")?,
+            Domain::Circle { center, radius } => writeln!(
+                f,
+                "domain circle {} {} {radius}",
+                center.x, center.y
+            )?,
+            Domain::Polygon(points) => {
+                write!(f, "domain polygon")?;
+                for p in points {
+                    write!(f, " {} {}", p.x, p.y)?;
+                }
+                writeln!(f)?;
+            }
+        }
+
+        for p in &self.primitives {
+            write!(f, "primitive ")?;
+            write_primitive(f, p)?;
+            writeln!(f)?;
+        }
+
+        for field in &self.fields {
+            let (kind, angle) = match field.kind {
+                FieldKind::Attractor => ("attractor", 0.),
+                FieldKind::Repulsor => ("repulsor", 0.),
+                FieldKind::Wind { angle } => ("wind", angle),
+            };
+            writeln!(
+                f,
+                "field {kind} {angle} {} {} {} {}",
+                field.position.x,
+                field.position.y,
+                field.radius,
+                field.strength,
+            )?;
+        }
+
+        for shape in &self.shapes {
+            let start = shape.start();
+            write!(
+                f,
+                "shape {} {} {} {} {}",
+                start.x,
+                start.y,
+                shape.closed() as u8,
+                shape.obstacle() as u8,
+                shape.spine() as u8,
+            )?;
+            // left out unless set, as they are for drawn shapes
+            match shape.stiffness() {
+                Some(stiffness) => {
+                    write!(f, " {} {stiffness}", shape.weight())?
+                }
+                None if shape.weight() != 1. => {
+                    write!(f, " {}", shape.weight())?
+                }
+                None => {}
+            }
+            writeln!(f)?;
+            if !shape.tags().is_empty() {
+                writeln!(f, "tags {}", shape.tags().join(" "))?;
+            }
+            if let Some(layer) = shape.layer() {
+                writeln!(f, "layer {layer}")?;
+            }
+            for ((offset, t), pen) in
+                shape.verticies().zip(shape.timestamps()).zip(shape.pens())
+            {
+                writeln!(
+                    f,
+                    "vertex {} {} {t} {} {} {}",
+                    offset.x, offset.y, pen.pressure, pen.tilt.x, pen.tilt.y,
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Parse the next value of a record.
+pub(crate) fn next<'a, T: FromStr>(
+    values: &mut impl Iterator<Item = &'a str>,
+) -> Result<T>
+where
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    let value = values.next().context("missing value")?;
+    value
+        .parse()
+        .with_context(|| format!("invalid value: {value}"))
+}
+
+pub(crate) fn next_flag<'a>(
+    values: &mut impl Iterator<Item = &'a str>,
+) -> Result<bool> {
+    Ok(next::<u8>(values)? != 0)
+}
+
+pub(crate) fn next_point<'a>(
+    values: &mut impl Iterator<Item = &'a str>,
+) -> Result<DVec2> {
+    Ok(DVec2::new(next(values)?, next(values)?))
+}
+
+/// Write the values of a `primitive` record of `p`: its kind, number of
+/// sides, center, radius, rotation, number of vertices and whether it is
+/// passive.
+pub(crate) fn write_primitive(
+    f: &mut fmt::Formatter<'_>,
+    p: &Primitive,
+) -> fmt::Result {
+    let (kind, sides) = match p.kind {
+        PrimitiveKind::Circle => ("circle", 0),
+        PrimitiveKind::Line => ("line", 0),
+        PrimitiveKind::Polygon { sides } => ("polygon", sides),
+    };
+    write!(
+        f,
+        "{kind} {sides} {} {} {} {} {} {}",
+        p.center.x,
+        p.center.y,
+        p.radius,
+        p.rotation,
+        p.n_vertices,
+        p.passive as u8,
+    )
+}
+
+/// Parse the values of a `primitive` record, as [`write_primitive`] writes
+/// them.
+pub(crate) fn parse_primitive<'a>(
+    values: &mut impl Iterator<Item = &'a str>,
+) -> Result<Primitive> {
+    let kind = values.next();
+    let sides = next(values)?;
+    Ok(Primitive {
+        kind: match kind {
+            Some("circle") => PrimitiveKind::Circle,
+            Some("line") => PrimitiveKind::Line,
+            Some("polygon") => PrimitiveKind::Polygon { sides },
+            other => anyhow::bail!("unknown primitive: {other:?}"),
+        },
+        center: next_point(values)?,
+        radius: next(values)?,
+        rotation: next(values)?,
+        n_vertices: next(values)?,
+        passive: next_flag(values)?,
+    })
+}
+
+impl Project {
+    /// Apply the record of a project file made of `keyword` and `values`,
+    /// the rest of its line.
+    pub(crate) fn parse_record(
+        &mut self,
+        keyword: &str,
+        values: &mut SplitWhitespace<'_>,
+    ) -> Result<()> {
+        match keyword {
+            "seed" => self.seed = Some(next(values)?),
+            "domain" => {
+                self.domain = match values.next() {
+                    Some("square") => Domain::Square,
+                    Some("circle") => Domain::Circle {
+                        center: next_point(values)?,
+                        radius: next(values)?,
+                    },
+                    Some("polygon") => {
+                        let mut points = Vec::new();
+                        while values.clone().next().is_some() {
+                            points.push(next_point(values)?);
+                        }
+                        Domain::Polygon(points)
+                    }
+                    other => {
+                        anyhow::bail!("unknown domain: {other:?}")
+                    }
+                };
+            }
+            "primitive" => self.primitives.push(parse_primitive(values)?),
+            "field" => {
+                let kind = values.next();
+                let angle = next(values)?;
+                self.fields.push(Field {
+                    kind: match kind {
+                        Some("attractor") => FieldKind::Attractor,
+                        Some("repulsor") => FieldKind::Repulsor,
+                        Some("wind") => FieldKind::Wind { angle },
+                        other => anyhow::bail!("unknown field: {other:?}"),
+                    },
+                    position: next_point(values)?,
+                    radius: next(values)?,
+                    strength: next(values)?,
+                });
+            }
+            "shape" => {
+                let mut shape = Shape::starting_at(next_point(values)?);
+                shape.set_closed(next_flag(values)?);
+                shape.set_obstacle(next_flag(values)?);
+                // projects saved before spines have no flag for it
+                if values.clone().next().is_some() {
+                    shape.set_spine(next_flag(values)?);
+                }
+                if values.clone().next().is_some() {
+                    shape.set_weight(next(values)?);
+                }
+                if values.clone().next().is_some() {
+                    shape.set_stiffness(Some(next(values)?));
+                }
+                self.shapes.push(shape);
+            }
+            "tags" => {
+                let shape =
+                    self.shapes.last_mut().context("tags before any shape")?;
+                for tag in values.by_ref() {
+                    shape.add_tag(tag.to_owned());
+                }
+            }
+            "layer" => {
+                let shape = self
+                    .shapes
+                    .last_mut()
+                    .context("layer before any shape")?;
+                shape.set_layer(Some(next(values)?));
+            }
+            "vertex" => {
+                let shape = self
+                    .shapes
+                    .last_mut()
+                    .context("vertex before any shape")?;
+                let offset = next_point(values)?;
+                let time = next(values)?;
+                let pen = Pen {
+                    pressure: next(values)?,
+                    tilt: next_point(values)?,
+                };
+                shape.next_vertex_at(offset, time, pen);
+            }
+            _ => anyhow::bail!("unknown record: {keyword}"),
+        }
+
+        anyhow::ensure!(values.next().is_none(), "too many values");
+        Ok(())
+    }
+}
+
+impl FromStr for Project {
+    type Err = anyhow::Error;
+
+    fn from_str(text: &str) -> Result<Self> {
+        let mut lines = text.lines().enumerate();
+
+        match lines.next() {
+            Some((_, HEADER)) => {}
+            _ => anyhow::bail!("missing header: {HEADER}"),
+        }
+
+        let mut project = Self::default();
+
+        for (i, line) in lines {
+            let mut values = line.split_whitespace();
+            let Some(keyword) = values.next() else {
+                continue;
+            };
+
+            project
+                .parse_record(keyword, &mut values)
+                .with_context(|| format!("line {}", i + 1))?;
+        }
+
+        Ok(project)
+    }
+}
@@ -0,0 +1,152 @@
+//! Rendering scenes with tiny-skia, which needs neither GTK nor cairo.
+
+use anyhow::{Context, Result};
+use tiny_skia::{
+    FillRule, LineCap, LineJoin, Mask, Paint, PathBuilder, Pixmap, StrokeDash,
+    Transform,
+};
+
+use super::{
+    export::{DOCUMENT_DPI, Image},
+    scene::{self, Color, Item, Path, Scene},
+};
+
+fn color(color: Color) -> tiny_skia::Color {
+    tiny_skia::Color::from_rgba(
+        color.red,
+        color.green,
+        color.blue,
+        color.alpha,
+    )
+    .unwrap_or(tiny_skia::Color::BLACK)
+}
+
+fn paint(color: Color) -> Paint<'static> {
+    let mut paint = Paint::default();
+    paint.set_color(self::color(color));
+    paint.anti_alias = true;
+    paint
+}
+
+/// The paths of `item` as one path, or `None` if they are all empty.
+fn path(item: &Item) -> Option<tiny_skia::Path> {
+    let mut builder = PathBuilder::new();
+    for path in &item.paths {
+        match path {
+            Path::Line { points, closed } => {
+                let mut points = points.iter();
+                let Some(first) = points.next() else {
+                    continue;
+                };
+                builder.move_to(first.x as f32, first.y as f32);
+                for p in points {
+                    builder.line_to(p.x as f32, p.y as f32);
+                }
+                if *closed {
+                    builder.close();
+                }
+            }
+            #[cfg(any(feature = "gui", test))]
+            Path::Circle { center, radius } => builder.push_circle(
+                center.x as f32,
+                center.y as f32,
+                *radius as f32,
+            ),
+        }
+    }
+    builder.finish()
+}
+
+fn draw_item(pixmap: &mut Pixmap, item: &Item, transform: Transform) {
+    let Some(path) = path(item) else {
+        return;
+    };
+
+    match item.paint {
+        #[cfg(feature = "gui")]
+        scene::Paint::Fill(color) => {
+            let paint = paint(color);
+            pixmap.fill_path(
+                &path,
+                &paint,
+                FillRule::Winding,
+                transform,
+                None,
+            );
+        }
+        scene::Paint::Stroke(stroke) => {
+            stroke_path(pixmap, &path, stroke, transform);
+        }
+    }
+}
+
+fn stroke_path(
+    pixmap: &mut Pixmap,
+    path: &tiny_skia::Path,
+    stroke: scene::Stroke,
+    transform: Transform,
+) {
+    let (line_cap, line_join) = if stroke.round {
+        (LineCap::Round, LineJoin::Round)
+    } else {
+        (LineCap::Butt, LineJoin::Miter)
+    };
+    let dash = stroke.dash.and_then(|dash| {
+        let dash = dash as f32;
+        StrokeDash::new(vec![dash, dash], 0.)
+    });
+    let mask = if stroke.inside {
+        let mut mask = Mask::new(pixmap.width(), pixmap.height());
+        if let Some(mask) = &mut mask {
+            mask.fill_path(path, FillRule::Winding, true, transform);
+        }
+        mask
+    } else {
+        None
+    };
+    let style = tiny_skia::Stroke {
+        width: stroke.width as f32,
+        line_cap,
+        line_join,
+        dash,
+        ..Default::default()
+    };
+
+    let paint = paint(stroke.color);
+    pixmap.stroke_path(path, &paint, &style, transform, mask.as_ref());
+}
+
+/// `scene` as an image of its size at [`DOCUMENT_DPI`], scaled up or down
+/// to `dpi`.
+pub(crate) fn rasterize(scene: &Scene, dpi: f64) -> Result<Image> {
+    let scale = dpi / DOCUMENT_DPI;
+    let size = (scene.size * scale).ceil();
+    let mut pixmap = Pixmap::new(size.x as u32, size.y as u32)
+        .with_context(|| format!("invalid image size: {size}"))?;
+
+    if let Some(background) = scene.background {
+        pixmap.fill(color(background));
+    }
+
+    for layer in &scene.layers {
+        let transform = Transform::from_scale(scale as f32, scale as f32)
+            .pre_translate(layer.offset.x as f32, layer.offset.y as f32)
+            .pre_scale(layer.scale as f32, layer.scale as f32);
+        for item in &layer.items {
+            draw_item(&mut pixmap, item, transform);
+        }
+    }
+
+    Ok(Image {
+        width: pixmap.width(),
+        height: pixmap.height(),
+        data: pixmap
+            .pixels()
+            .iter()
+            .flat_map(|p| {
+                let c = p.demultiply();
+                [c.red(), c.green(), c.blue(), c.alpha()]
+            })
+            .collect(),
+    })
+}
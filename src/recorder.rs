@@ -0,0 +1,353 @@
+#[cfg(feature = "gui")]
+use std::{
+    io::Write,
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::RwLock,
+};
+use std::{
+    ops::ControlFlow,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+
+use super::{
+    algorithm::{Params, Simulation},
+    export::{self, Crop, ImageFormat},
+    layers::LAYERS,
+    params_file::ParamsFile,
+    project::Project,
+    remote::Remote,
+    shape::Shape,
+    simulation::{RunState, SimulationController, StopConditions},
+    style::STYLE,
+};
+#[cfg(feature = "gui")]
+use super::{export::DOCUMENT_DPI, transform::DOCUMENT_SIZE};
+
+/// Frame rate of recorded videos.
+#[cfg(feature = "gui")]
+const FRAME_RATE: u32 = 30;
+
+/// Where the frames of a recording go.
+enum Output {
+    /// An `ffmpeg` process encoding the frames piped to it.
+    #[cfg(feature = "gui")]
+    Video {
+        ffmpeg: Child,
+        stdin: Option<ChildStdin>,
+    },
+    /// Numbered images in a directory, `frame_00001.png` and so on.
+    Frames { format: ImageFormat, dpi: f64 },
+}
+
+/// Captures the growth frame by frame, either into an animated GIF or an MP4
+/// video, or into a sequence of images for rendering elsewhere.
+///
+/// Frames show the document as exported, regardless of the viewport, or
+/// only the part of it cropped to.
+pub(crate) struct Recorder {
+    output: Output,
+    /// The video file or the directory of frames.
+    path: PathBuf,
+    /// Only every `every`th iteration is captured.
+    every: u64,
+    crop: Option<Crop>,
+    frames: u64,
+}
+
+impl Recorder {
+    /// Start `ffmpeg` writing to `path`, as a GIF or an MP4 by its
+    /// extension.
+    #[cfg(feature = "gui")]
+    pub(crate) fn video(
+        path: &Path,
+        every: u64,
+        mut crop: Option<Crop>,
+    ) -> Result<Self> {
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(str::to_ascii_lowercase);
+        let encoding: &[&str] = match extension.as_deref() {
+            // a palette made from all frames looks much better than the
+            // default one
+            Some("gif") => &[
+                "-vf",
+                "split[a][b];[a]palettegen[p];[b][p]paletteuse",
+                "-loop",
+                "0",
+            ],
+            Some("mp4") => &["-c:v", "libx264", "-pix_fmt", "yuv420p"],
+            _ => anyhow::bail!(
+                "unsupported recording format, expected .gif or .mp4: {}",
+                path.display()
+            ),
+        };
+
+        // MP4 frames are a whole number of pixel pairs wide and high, so
+        // regions are grown to fit
+        let size = match &mut crop {
+            Some(crop) => {
+                let size = (crop.region.size() / 2.).ceil() * 2.;
+                crop.region.max = crop.region.min + size;
+                size
+            }
+            None => DOCUMENT_SIZE,
+        };
+        let (width, height) = (size.x as i32, size.y as i32);
+
+        let mut ffmpeg = Command::new("ffmpeg")
+            .args(["-y", "-loglevel", "error"])
+            .args(["-f", "rawvideo", "-pixel_format", "rgba"])
+            .args(["-video_size", &format!("{width}x{height}")])
+            .args(["-framerate", &FRAME_RATE.to_string()])
+            .args(["-i", "-"])
+            .args(encoding)
+            .arg(path)
+            .stdin(Stdio::piped())
+            .spawn()
+            .context("failed to start ffmpeg")?;
+        let stdin = ffmpeg.stdin.take();
+
+        tracing::info!("recording to {}", path.display());
+
+        Ok(Self {
+            output: Output::Video { ffmpeg, stdin },
+            path: path.to_owned(),
+            every: every.max(1),
+            crop,
+            frames: 0,
+        })
+    }
+
+    /// Write numbered images to the directory `dir`, creating it if needed.
+    pub(crate) fn frames(
+        dir: &Path,
+        format: ImageFormat,
+        dpi: f64,
+        every: u64,
+        crop: Option<Crop>,
+    ) -> Result<Self> {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("failed to create {}", dir.display()))?;
+
+        tracing::info!("recording frames to {}", dir.display());
+
+        Ok(Self {
+            output: Output::Frames { format, dpi },
+            path: dir.to_owned(),
+            every: every.max(1),
+            crop,
+            frames: 0,
+        })
+    }
+
+    /// Add a frame of `shapes` and `simulation` if `iteration` is one of
+    /// those captured.
+    pub(crate) fn capture(
+        &mut self,
+        iteration: u64,
+        shapes: &[Shape],
+        simulation: &Simulation,
+    ) -> Result<()> {
+        if iteration % self.every != 0 {
+            return Ok(());
+        }
+
+        let scene = export::frame(
+            &STYLE.read().unwrap(),
+            &LAYERS.read().unwrap(),
+            shapes,
+            Some(simulation),
+            self.crop.as_ref(),
+        );
+        match &mut self.output {
+            #[cfg(feature = "gui")]
+            Output::Video { stdin, .. } => {
+                let stdin =
+                    stdin.as_mut().context("recording has finished")?;
+                let image = export::rasterize(&scene, DOCUMENT_DPI)?;
+                stdin.write_all(&image.data).context("ffmpeg stopped")?;
+            }
+            &mut Output::Frames { format, dpi } => {
+                let name = format!(
+                    "frame_{:05}.{}",
+                    self.frames + 1,
+                    format.extension()
+                );
+                export::write_image(
+                    &self.path.join(name),
+                    format,
+                    dpi,
+                    &scene,
+                )?;
+            }
+        }
+
+        self.frames += 1;
+        Ok(())
+    }
+
+    /// Wait for `ffmpeg` to write the file, if recording a video.
+    pub(crate) fn finish(self) -> Result<PathBuf> {
+        #[cfg(feature = "gui")]
+        if let Output::Video { mut ffmpeg, stdin } = self.output {
+            drop(stdin);
+            let status = ffmpeg.wait().context("ffmpeg failed")?;
+            anyhow::ensure!(status.success(), "ffmpeg failed: {status}");
+        }
+
+        tracing::info!(
+            "recorded {} frames to {}",
+            self.frames,
+            self.path.display()
+        );
+        Ok(self.path)
+    }
+}
+
+/// How recordings started from the window capture frames, set from the
+/// command line.
+#[cfg(feature = "gui")]
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct RecordOptions {
+    /// Only every this many iterations are captured.
+    pub(crate) every: u64,
+    /// Format of the images of frame sequences.
+    pub(crate) frame_format: ImageFormat,
+}
+
+#[cfg(feature = "gui")]
+impl RecordOptions {
+    pub(crate) const DEFAULT: Self = Self {
+        every: 1,
+        frame_format: ImageFormat::Png,
+    };
+}
+
+#[cfg(feature = "gui")]
+pub(crate) static RECORD_OPTIONS: RwLock<RecordOptions> =
+    RwLock::new(RecordOptions::DEFAULT);
+
+/// A recording of a video to `path`, limited to `crop` if there is one,
+/// with the options from the command line.
+#[cfg(feature = "gui")]
+pub(crate) fn start_recording(
+    path: &Path,
+    crop: Option<Crop>,
+) -> Result<Recorder> {
+    let options = *RECORD_OPTIONS.read().unwrap();
+    Recorder::video(path, options.every, crop)
+}
+
+/// A recording of every captured frame to the directory `dir`, limited to
+/// `crop` if there is one, with the options from the command line.
+#[cfg(feature = "gui")]
+pub(crate) fn start_recording_frames(
+    dir: &Path,
+    crop: Option<Crop>,
+) -> Result<Recorder> {
+    let options = *RECORD_OPTIONS.read().unwrap();
+    Recorder::frames(
+        dir,
+        options.frame_format,
+        DOCUMENT_DPI,
+        options.every,
+        crop,
+    )
+}
+
+/// What steers a run grown without the window while it grows.
+#[derive(Default)]
+pub(crate) struct Steering<'a> {
+    /// Params file the parameters follow.
+    pub(crate) params_file: Option<&'a mut ParamsFile>,
+    /// Control API the run answers.
+    pub(crate) remote: Option<&'a mut Remote>,
+}
+
+impl Steering<'_> {
+    /// Apply the changes made since the last call to `params`, with which
+    /// the run of `controller` grows `shapes`.
+    pub(crate) fn poll(
+        &mut self,
+        controller: &SimulationController,
+        shapes: &[Shape],
+        params: &mut Params,
+    ) -> Result<()> {
+        if let Some(file) = self.params_file.as_deref_mut() {
+            file.poll(params);
+        }
+        if let Some(remote) = self.remote.as_deref_mut() {
+            remote.poll(controller, shapes, params)?;
+        }
+        Ok(())
+    }
+}
+
+/// Grow `project` with `params` without opening the window, calling
+/// `on_step` after every step that advances the simulation, until it
+/// finishes, one of `stop` is met, or `on_step` breaks. Returns the grown
+/// simulation. The run is steered by `steering` in between its steps.
+pub(crate) fn grow_headless(
+    project: &Project,
+    params: &Params,
+    mut steering: Steering,
+    stop: StopConditions,
+    mut on_step: impl FnMut(&SimulationController) -> Result<ControlFlow<()>>,
+) -> Result<SimulationController> {
+    let mut params = *params;
+    let mut controller = SimulationController::new();
+    steering.poll(&controller, &project.shapes, &mut params)?;
+
+    project.configure(&mut controller);
+    controller.set_stop_conditions(stop);
+    controller.reset(&project.shapes, &params)?;
+    controller.toggle_pause(&project.shapes, &params)?;
+
+    while controller.state() == RunState::Running {
+        let iteration = controller.iteration();
+        steering.poll(&controller, &project.shapes, &mut params)?;
+        controller.tick(&params)?;
+
+        // the step that finds the simulation can not continue does not
+        // advance it
+        if controller.iteration() > iteration
+            && on_step(&controller)?.is_break()
+        {
+            break;
+        }
+    }
+
+    Ok(controller)
+}
+
+/// Grow `project` with the default parameters without opening the window,
+/// steered by `steering`, capturing frames with `recorder` until the
+/// simulation finishes or one of `stop` is met.
+pub(crate) fn record_headless(
+    project: Project,
+    mut recorder: Recorder,
+    steering: Steering,
+    stop: StopConditions,
+) -> Result<()> {
+    let controller = grow_headless(
+        &project,
+        &Params::DEFAULT,
+        steering,
+        stop,
+        |controller| {
+            recorder.capture(
+                controller.iteration(),
+                &project.shapes,
+                controller.simulation().unwrap(),
+            )?;
+            Ok(ControlFlow::Continue(()))
+        },
+    )?;
+
+    tracing::info!("stopped after {} iterations", controller.iteration());
+    recorder.finish()?;
+    Ok(())
+}
@@ -0,0 +1,298 @@
+//! The control API of `--serve`: a small HTTP API over a simulation grown
+//! without the window, to watch and steer a long run on another machine.
+//!
+//! - `GET /status`: the iteration, the number of vertices, whether the run
+//!   is paused, and the parameters of params files.
+//! - `POST /pause` and `POST /resume`: pause and resume the run.
+//! - `POST /params`: change the parameters and colors, with a JSON object
+//!   of the keys of a params file, such as `{"near-l": 3}`.
+//! - `GET /export`: the drawing as it is, as SVG, or as PNG with
+//!   `?format=png`.
+//!
+//! Every other answer is JSON, errors as `{"error": "..."}`. Bodies over
+//! [`MAX_BODY`] bytes are refused with 413. There is no authentication, so
+//! the API only listens on the loopback interface unless `--serve-bind`
+//! gives another address, such as that of a private network or of an SSH
+//! tunnel.
+
+use std::{fmt::Display, io::Read, net::IpAddr};
+
+use anyhow::{Context, Result};
+use serde_json::{Value, json};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use super::{
+    algorithm::{ONE, Params, Simulation},
+    export::{self, DOCUMENT_DPI},
+    layers::LAYERS,
+    params_file,
+    shape::Shape,
+    simulation::SimulationController,
+    style::STYLE,
+};
+
+/// The most bytes of the body of a request that are read, far more than
+/// the parameters of a params file take.
+const MAX_BODY: u64 = 64 * 1024;
+
+/// The answer to a request.
+struct Reply {
+    status: u16,
+    content_type: &'static str,
+    body: Vec<u8>,
+}
+
+impl Reply {
+    fn json(value: &Value) -> Self {
+        Self {
+            status: 200,
+            content_type: "application/json",
+            body: value.to_string().into_bytes(),
+        }
+    }
+
+    fn error(status: u16, err: impl Display) -> Self {
+        Self {
+            status,
+            ..Self::json(&json!({ "error": format!("{err:#}") }))
+        }
+    }
+
+    fn into_response(self) -> Response<std::io::Cursor<Vec<u8>>> {
+        let content_type =
+            Header::from_bytes("Content-Type", self.content_type).unwrap();
+        Response::from_data(self.body)
+            .with_status_code(self.status)
+            .with_header(content_type)
+    }
+}
+
+/// The control API of a run, answering requests in between its steps.
+pub(crate) struct Remote {
+    server: Server,
+    paused: bool,
+}
+
+impl Remote {
+    /// Listen for requests on `port` of `address`.
+    pub(crate) fn bind(address: IpAddr, port: u16) -> Result<Self> {
+        let server = Server::http((address, port)).map_err(|err| {
+            anyhow::anyhow!("failed to listen on {address} port {port}: {err}")
+        })?;
+        if !address.is_loopback() {
+            tracing::warn!(
+                "serving the control API on {address}, without \
+                 authentication"
+            );
+        }
+        tracing::info!("serving the control API on {address} port {port}");
+        Ok(Self {
+            server,
+            paused: false,
+        })
+    }
+
+    /// Answer the requests made since the last call about the run of
+    /// `controller`, growing `shapes` with `params`, which they can change.
+    /// While the run is paused, waits for requests until it is resumed.
+    pub(crate) fn poll(
+        &mut self,
+        controller: &SimulationController,
+        shapes: &[Shape],
+        params: &mut Params,
+    ) -> Result<()> {
+        loop {
+            let request = if self.paused {
+                Some(self.server.recv()?)
+            } else {
+                self.server.try_recv()?
+            };
+            let Some(request) = request else {
+                return Ok(());
+            };
+            self.answer(request, controller, shapes, params);
+        }
+    }
+
+    fn answer(
+        &mut self,
+        mut request: Request,
+        controller: &SimulationController,
+        shapes: &[Shape],
+        params: &mut Params,
+    ) {
+        let reply = match read_body(request.as_reader()) {
+            Ok(body) => {
+                let (method, url) = (request.method(), request.url());
+                self.reply(method, url, &body, controller, shapes, params)
+            }
+            Err(reply) => reply,
+        };
+        // a client that went away does not stop the run
+        if let Err(err) = request.respond(reply.into_response()) {
+            tracing::warn!("failed to answer a request: {err}");
+        }
+    }
+
+    fn reply(
+        &mut self,
+        method: &Method,
+        url: &str,
+        body: &str,
+        controller: &SimulationController,
+        shapes: &[Shape],
+        params: &mut Params,
+    ) -> Reply {
+        let (path, query) = url.split_once('?').unwrap_or((url, ""));
+        match (method, path) {
+            (Method::Get, "/status") => {}
+            (Method::Post, "/pause") => self.paused = true,
+            (Method::Post, "/resume") => self.paused = false,
+            (Method::Post, "/params") => {
+                let set = serde_json::from_str(body)
+                    .context("expected a JSON object")
+                    .and_then(|table| params_file::apply(table, params));
+                if let Err(err) = set {
+                    return Reply::error(400, err);
+                }
+            }
+            (Method::Get, "/export") => {
+                return export(query, shapes, controller.simulation())
+                    .unwrap_or_else(|err| Reply::error(400, err));
+            }
+            (_, "/status" | "/pause" | "/resume" | "/params" | "/export") => {
+                return Reply::error(405, format!("{method} {path}"));
+            }
+            _ => return Reply::error(404, format!("not found: {path}")),
+        }
+        Reply::json(&self.status(controller, params))
+    }
+
+    fn status(
+        &self,
+        controller: &SimulationController,
+        params: &Params,
+    ) -> Value {
+        json!({
+            "iteration": controller.iteration(),
+            "vertices": controller
+                .simulation()
+                .map_or(0, Simulation::vertex_count),
+            "paused": self.paused,
+            "params": {
+                "near-l": params.near_l / ONE,
+                "far-l": params.far_l / ONE,
+                "step": params.step / ONE,
+                "spawn-probability": params.spawn_probability,
+            },
+        })
+    }
+}
+
+/// The body of a request as text, or the reply to a body that is over
+/// [`MAX_BODY`] bytes or is not UTF-8.
+fn read_body(reader: impl Read) -> Result<String, Reply> {
+    let mut body = Vec::new();
+    // one byte more tells a body over the limit from one right at it
+    reader
+        .take(MAX_BODY + 1)
+        .read_to_end(&mut body)
+        .map_err(|err| Reply::error(400, err))?;
+    if body.len() as u64 > MAX_BODY {
+        let err = format!("the body is over {MAX_BODY} bytes");
+        return Err(Reply::error(413, err));
+    }
+    String::from_utf8(body).map_err(|err| Reply::error(400, err))
+}
+
+/// The drawing of `shapes` and `simulation`, in the format of `query`.
+fn export(
+    query: &str,
+    shapes: &[Shape],
+    simulation: Option<&Simulation>,
+) -> Result<Reply> {
+    let scene = export::frame(
+        &STYLE.read().unwrap(),
+        &LAYERS.read().unwrap(),
+        shapes,
+        simulation,
+        None,
+    );
+    match query {
+        "" | "format=svg" => Ok(Reply {
+            status: 200,
+            content_type: "image/svg+xml",
+            body: export::svg(&scene).into_bytes(),
+        }),
+        "format=png" => Ok(Reply {
+            status: 200,
+            content_type: "image/png",
+            body: export::rasterize(&scene, DOCUMENT_DPI)?.png()?,
+        }),
+        _ => anyhow::bail!("unknown export query: {query}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requests_steer_the_run() {
+        let localhost = IpAddr::from([127, 0, 0, 1]);
+        let mut remote = Remote::bind(localhost, 0).unwrap();
+        let controller = SimulationController::new();
+        let mut params = Params::DEFAULT;
+        let mut reply = |method, url, body| {
+            let reply = remote.reply(
+                &method,
+                url,
+                body,
+                &controller,
+                &[],
+                &mut params,
+            );
+            let body = match reply.content_type {
+                "application/json" => {
+                    serde_json::from_slice(&reply.body).unwrap()
+                }
+                _ => Value::Null,
+            };
+            (reply.status, body)
+        };
+
+        let (status, body) = reply(Method::Get, "/status", "");
+        assert_eq!(status, 200);
+        assert_eq!(body["iteration"], 0);
+        assert_eq!(body["paused"], false);
+        assert_eq!(reply(Method::Post, "/pause", "").1["paused"], true);
+        assert_eq!(reply(Method::Post, "/resume", "").1["paused"], false);
+
+        let (status, body) =
+            reply(Method::Post, "/params", r#"{"near-l": 3, "step": 0.5}"#);
+        assert_eq!(status, 200);
+        assert_eq!(body["params"]["near-l"], 3.);
+        assert_eq!(body["params"]["step"], 0.5);
+        assert_eq!(reply(Method::Post, "/params", r#"{"nope": 1}"#).0, 400);
+        assert_eq!(reply(Method::Post, "/params", "[1]").0, 400);
+        assert_eq!(reply(Method::Post, "/params", r#"{"far-l": 0}"#).0, 400);
+
+        assert_eq!(reply(Method::Get, "/export", "").0, 200);
+        assert_eq!(reply(Method::Get, "/export?format=gif", "").0, 400);
+        assert_eq!(reply(Method::Get, "/pause", "").0, 405);
+        assert_eq!(reply(Method::Get, "/", "").0, 404);
+
+        assert_eq!(params.near_l, 3. * ONE);
+        assert_eq!(params.step, 0.5 * ONE);
+        assert_eq!(params.far_l, Params::DEFAULT.far_l);
+    }
+
+    #[test]
+    fn bodies_over_the_limit_are_refused() {
+        let body = |len| read_body(&vec![b' '; len][..]).map_err(|r| r.status);
+        assert_eq!(body(0).unwrap(), "");
+        assert_eq!(body(MAX_BODY as usize).unwrap().len(), MAX_BODY as usize);
+        assert_eq!(body(MAX_BODY as usize + 1), Err(413));
+        assert_eq!(read_body(&[0xff][..]).map_err(|r| r.status), Err(400));
+    }
+}
@@ -0,0 +1,375 @@
+//! Drawing with cairo, in the window and for images.
+
+use std::f64::consts::TAU;
+
+use anyhow::Result;
+use glam::DVec2;
+use gtk::{cairo, gdk, glib};
+
+#[cfg(any(not(feature = "tiny-skia"), test))]
+use super::{export::DOCUMENT_DPI, scene::Scene};
+use super::{
+    export::Image,
+    scene::{Color, Item, Layer, Paint, Path},
+};
+
+pub(crate) fn set_color(ctx: &cairo::Context, color: Color) {
+    let Color {
+        red,
+        green,
+        blue,
+        alpha,
+    } = color;
+    ctx.set_source_rgba(red.into(), green.into(), blue.into(), alpha.into());
+}
+
+/// Fill the `size` rectangle at the origin with `color`.
+pub(crate) fn background(
+    ctx: &cairo::Context,
+    size: DVec2,
+    color: Color,
+) -> Result<()> {
+    set_color(ctx, color);
+    ctx.rectangle(0.0, 0.0, size.x, size.y);
+    ctx.fill()?;
+    Ok(())
+}
+
+fn path(ctx: &cairo::Context, path: &Path) {
+    ctx.new_sub_path();
+    match path {
+        Path::Line { points, closed } => {
+            for p in points {
+                ctx.line_to(p.x, p.y);
+            }
+            if *closed {
+                ctx.close_path();
+            }
+        }
+        Path::Circle { center, radius } => {
+            ctx.arc(center.x, center.y, *radius, 0., TAU);
+        }
+    }
+}
+
+/// Paint the paths of each of `items` in one go.
+pub(crate) fn items(ctx: &cairo::Context, items: &[Item]) -> Result<()> {
+    for item in items {
+        ctx.save()?;
+        ctx.new_path();
+        for p in &item.paths {
+            path(ctx, p);
+        }
+
+        match item.paint {
+            Paint::Fill(color) => {
+                set_color(ctx, color);
+                ctx.fill()?;
+            }
+            Paint::Stroke(stroke) => {
+                let (cap, join) = if stroke.round {
+                    (cairo::LineCap::Round, cairo::LineJoin::Round)
+                } else {
+                    (cairo::LineCap::Butt, cairo::LineJoin::Miter)
+                };
+                set_color(ctx, stroke.color);
+                ctx.set_line_width(stroke.width);
+                ctx.set_line_cap(cap);
+                ctx.set_line_join(join);
+                if let Some(dash) = stroke.dash {
+                    ctx.set_dash(&[dash, dash], 0.);
+                }
+                if stroke.inside {
+                    ctx.clip_preserve();
+                }
+                ctx.stroke()?;
+            }
+        }
+
+        ctx.restore()?;
+    }
+
+    Ok(())
+}
+
+/// Draw `layer` into `ctx`, in the coordinates of its scene.
+pub(crate) fn layer(ctx: &cairo::Context, layer: &Layer) -> Result<()> {
+    ctx.save()?;
+    ctx.translate(layer.offset.x, layer.offset.y);
+    ctx.scale(layer.scale, layer.scale);
+    items(ctx, &layer.items)?;
+    ctx.restore()?;
+    Ok(())
+}
+
+/// Write `text` line by line in `color`, in a monospace font, on a box of
+/// `background` with its top left corner at `pos`.
+pub(crate) fn text_box(
+    ctx: &cairo::Context,
+    pos: DVec2,
+    text: &str,
+    color: Color,
+    background: Color,
+) -> Result<()> {
+    const FONT_SIZE: f64 = 12.;
+    const LINE_HEIGHT: f64 = 1.3 * FONT_SIZE;
+    const PADDING: f64 = 6.;
+
+    ctx.save()?;
+    ctx.select_font_face(
+        "monospace",
+        cairo::FontSlant::Normal,
+        cairo::FontWeight::Normal,
+    );
+    ctx.set_font_size(FONT_SIZE);
+
+    let mut width = 0_f64;
+    for line in text.lines() {
+        width = width.max(ctx.text_extents(line)?.x_advance());
+    }
+    let height = text.lines().count() as f64 * LINE_HEIGHT;
+    set_color(ctx, background);
+    ctx.rectangle(pos.x, pos.y, width + 2. * PADDING, height + 2. * PADDING);
+    ctx.fill()?;
+
+    set_color(ctx, color);
+    for (i, line) in text.lines().enumerate() {
+        let baseline = pos.y + PADDING + (i as f64 + 1.) * LINE_HEIGHT;
+        ctx.move_to(pos.x + PADDING, baseline - 0.3 * FONT_SIZE);
+        ctx.show_text(line)?;
+    }
+
+    ctx.restore()?;
+    Ok(())
+}
+
+/// Draw all of `scene` into `ctx`.
+#[cfg(any(not(feature = "tiny-skia"), test))]
+pub(crate) fn scene(ctx: &cairo::Context, scene: &Scene) -> Result<()> {
+    if let Some(color) = scene.background {
+        background(ctx, scene.size, color)?;
+    }
+    for layer in &scene.layers {
+        self::layer(ctx, layer)?;
+    }
+    Ok(())
+}
+
+/// `scene` as an image of its size at [`DOCUMENT_DPI`], scaled up or down
+/// to `dpi`. Builds with tiny-skia rasterize with it instead, so this is
+/// only for the goldens of their tests.
+#[cfg(any(not(feature = "tiny-skia"), test))]
+pub(crate) fn rasterize(scene: &Scene, dpi: f64) -> Result<Image> {
+    let scale = dpi / DOCUMENT_DPI;
+    let size = (scene.size * scale).ceil();
+    let surface = cairo::ImageSurface::create(
+        cairo::Format::ARgb32,
+        size.x as i32,
+        size.y as i32,
+    )?;
+
+    let ctx = cairo::Context::new(&surface)?;
+    ctx.scale(scale, scale);
+    self::scene(&ctx, scene)?;
+    drop(ctx);
+    surface.flush();
+
+    let (width, stride) =
+        (surface.width() as usize, surface.stride() as usize);
+    let mut data = Vec::with_capacity(4 * width * surface.height() as usize);
+    surface.with_data(|pixels| {
+        // cairo stores pixels as native endian 32 bit words of alpha, red,
+        // green, and blue, the colors premultiplied by the alpha
+        for line in pixels.chunks(stride) {
+            for p in line[..4 * width].chunks_exact(4) {
+                let [a, r, g, b] =
+                    u32::from_ne_bytes([p[0], p[1], p[2], p[3]]).to_be_bytes();
+                let demultiply = |c: u8| match a {
+                    0 => 0,
+                    _ => ((c as u32 * 255 + a as u32 / 2) / a as u32) as u8,
+                };
+                data.extend([demultiply(r), demultiply(g), demultiply(b)]);
+                data.push(a);
+            }
+        }
+    })?;
+
+    Ok(Image {
+        width: width as u32,
+        height: surface.height() as u32,
+        data,
+    })
+}
+
+/// `image` as a texture, to show in a picture.
+pub(crate) fn texture(image: Image) -> gdk::MemoryTexture {
+    let stride = 4 * image.width as usize;
+    gdk::MemoryTexture::new(
+        image.width as i32,
+        image.height as i32,
+        gdk::MemoryFormat::R8g8b8a8,
+        &glib::Bytes::from_owned(image.data),
+        stride,
+    )
+}
+
+#[cfg(test)]
+mod benches {
+    extern crate test;
+
+    use test::Bencher;
+
+    use super::*;
+    use crate::{
+        algorithm::{Params, Primitive, PrimitiveKind, Simulation},
+        layers::CanvasLayer,
+        polyline::Polyline,
+        scene,
+        shape::{Pen, Shape},
+        style::Style,
+        transform::{DOCUMENT_SIZE, Transform},
+    };
+
+    /// Edges of the drawn shapes, and of the growth.
+    const N_EDGES: usize = 100_000;
+    const N_PATHS: usize = 100;
+
+    /// `N_PATHS` rows of zigzags with `N_EDGES` edges between them.
+    fn zigzags() -> Vec<Shape> {
+        let n = N_EDGES / N_PATHS;
+        (0..N_PATHS)
+            .map(|row| {
+                let y = 6. * row as f64;
+                let mut shape = Shape::from_pos(0., y, 0, Pen::DEFAULT);
+                for i in 1..=n {
+                    let offset =
+                        DVec2::new(800. * i as f64 / n as f64, (i % 2) as f64);
+                    shape.next_vertex_at(offset, i as u32, Pen::DEFAULT);
+                }
+                shape
+            })
+            .collect()
+    }
+
+    /// The drawn shapes, which are all sketches.
+    fn sketches(style: &Style, shapes: &[Shape]) -> Vec<Item> {
+        let layer = CanvasLayer::Sketches;
+        scene::layer_shapes(style, shapes, style.shape, None, layer)
+    }
+
+    /// `N_PATHS` circles with `N_EDGES` edges between them.
+    fn circles() -> Simulation {
+        let mut simulation = Simulation::new(&Params::DEFAULT, 0).unwrap();
+        for i in 0..N_PATHS {
+            let center = DVec2::new((i % 10) as f64, (i / 10) as f64);
+            simulation
+                .add_primitive(&Primitive {
+                    kind: PrimitiveKind::Circle,
+                    center: (center + 0.5) / 10.,
+                    radius: 0.04,
+                    rotation: 0.,
+                    n_vertices: N_EDGES / N_PATHS,
+                    passive: false,
+                })
+                .unwrap();
+        }
+        simulation
+    }
+
+    /// The shapes, markers, and growth drawn with a stroke for every edge,
+    /// marker, and path, to compare batching with.
+    fn frame_unbatched(
+        ctx: &cairo::Context,
+        style: &Style,
+        shapes: &[Shape],
+        simulation: &Simulation,
+    ) -> Result<()> {
+        for shape in shapes {
+            set_color(ctx, style.shape);
+            ctx.set_line_cap(cairo::LineCap::Round);
+            for (a, b) in shape.edges() {
+                ctx.set_line_width(style.stroke_width);
+                ctx.move_to(a.x, a.y);
+                ctx.line_to(b.x, b.y);
+                ctx.stroke()?;
+            }
+            ctx.set_line_cap(cairo::LineCap::Butt);
+        }
+
+        set_color(ctx, style.marker);
+        ctx.set_line_width(1.);
+        for shape in shapes {
+            let start = shape.start();
+            for offset in shape.verticies() {
+                let DVec2 { x, y } = start + offset;
+                ctx.arc(x, y, style.marker_radius, 0., TAU);
+                ctx.stroke()?;
+            }
+        }
+
+        for path in simulation.paths() {
+            set_color(ctx, scene::growth_color(style, &path));
+            ctx.new_path();
+            for pos in path.points() {
+                let pos = Transform::DEFAULT.unit_to_document(pos);
+                ctx.line_to(pos.x, pos.y);
+            }
+            if path.is_closed() {
+                ctx.close_path();
+            }
+            ctx.stroke()?;
+        }
+
+        Ok(())
+    }
+
+    fn bench_frame(
+        b: &mut Bencher,
+        draw: impl Fn(&cairo::Context, &Style, &[Shape], &Simulation) -> Result<()>,
+    ) {
+        let style = Style::default();
+        let (shapes, simulation) = (zigzags(), circles());
+        let surface = cairo::ImageSurface::create(
+            cairo::Format::ARgb32,
+            DOCUMENT_SIZE.x as i32,
+            DOCUMENT_SIZE.y as i32,
+        )
+        .unwrap();
+        let ctx = cairo::Context::new(&surface).unwrap();
+
+        b.iter(|| {
+            background(&ctx, DOCUMENT_SIZE, style.background).unwrap();
+            draw(&ctx, &style, &shapes, &simulation).unwrap();
+            surface.flush();
+        });
+    }
+
+    #[bench]
+    fn frame_100k_edges(b: &mut Bencher) {
+        bench_frame(b, |ctx, style, shapes, simulation| {
+            items(ctx, &sketches(style, shapes))?;
+            items(
+                ctx,
+                &scene::vertex_markers(style, shapes, None, 1., usize::MAX),
+            )?;
+            items(ctx, &scene::growth(style, simulation, 1.))
+        });
+    }
+
+    #[bench]
+    fn frame_100k_edges_without_markers(b: &mut Bencher) {
+        bench_frame(b, |ctx, style, shapes, simulation| {
+            items(ctx, &sketches(style, shapes))?;
+            items(
+                ctx,
+                &scene::vertex_markers(style, shapes, None, 1., 10_000),
+            )?;
+            items(ctx, &scene::growth(style, simulation, 1.))
+        });
+    }
+
+    #[bench]
+    fn frame_100k_edges_unbatched(b: &mut Bencher) {
+        bench_frame(b, frame_unbatched);
+    }
+}
@@ -0,0 +1,819 @@
+//! Logs of what is done in a window, which `--replay` does again in the
+//! same order, to reproduce a session step by step.
+
+#[cfg(feature = "gui")]
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::{
+    fmt,
+    fs::File,
+    io::{LineWriter, Write},
+    path::Path,
+    str::{FromStr, SplitWhitespace},
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, Result};
+use glam::DVec2;
+#[cfg(feature = "gui")]
+use gtk::glib;
+
+use super::{
+    algorithm::{Params, Primitive, SeedJitter, Symmetry},
+    app_state::AppState,
+    keys::Action,
+    modes::{BOUNDARY_MODES, INDEXES, SPAWN_MODES, mode_name, mode_named},
+    project::{next, next_flag, next_point, parse_primitive, write_primitive},
+    shape::{
+        Pen, Similarity,
+        smooth::{Method, SmoothOptions},
+    },
+};
+#[cfg(feature = "gui")]
+use super::{
+    app_state::SharedState, params_panel::PARAMS,
+    shape::smooth::SMOOTH_OPTIONS,
+};
+
+/// First line of every session log.
+const HEADER: &str = "dxdy.draw replay 1";
+
+/// Names of the smoothing methods in session logs.
+const METHODS: [(&str, Method); 3] = [
+    ("off", Method::Off),
+    ("chaikin", Method::Chaikin),
+    ("catmull-rom", Method::CatmullRom),
+];
+
+/// Something done in a window, or a setting it was done with.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Event {
+    /// The seed every simulation of the session is grown with.
+    Seed(u64),
+    Params(Params),
+    Smooth(SmoothOptions),
+    /// Whether new shapes are obstacles or spines.
+    Draw {
+        obstacles: bool,
+        spines: bool,
+    },
+    Pen(Pen),
+    /// The symmetry strokes are copied under, and whether the growth is
+    /// kept to it.
+    Symmetry {
+        symmetry: Option<Symmetry>,
+        growth: bool,
+    },
+    /// A stroke started at `pos` in world coordinates.
+    BeginStroke {
+        pos: DVec2,
+        time: u32,
+    },
+    /// The stroke was dragged to `offset` from its start.
+    ContinueStroke {
+        offset: DVec2,
+        time: u32,
+    },
+    /// The stroke ended at `offset` from its start, drawn at `zoom`.
+    EndStroke {
+        offset: DVec2,
+        time: u32,
+        closed: bool,
+        zoom: f64,
+    },
+    /// The shape nearest to `pos` in world coordinates was erased, if it
+    /// was within `tolerance`.
+    Erase {
+        pos: DVec2,
+        tolerance: f64,
+    },
+    /// A primitive seed was added, as by the seed menu.
+    Primitive(Primitive),
+    /// The shape at `index` was moved, turned and scaled with the select
+    /// tool.
+    Transform {
+        index: usize,
+        similarity: Similarity,
+    },
+    /// An action of the drawing or the simulation, as done by
+    /// [`AppState::act`].
+    Action(Action),
+    /// The window stepped the running simulation this many times.
+    Steps(u64),
+}
+
+/// The settings events are done with as of the last event logged, to log
+/// them only when they change, before the first event done with them.
+pub(crate) struct LoggedSettings {
+    params: Params,
+    smooth: SmoothOptions,
+    draw: (bool, bool),
+    pen: Pen,
+    symmetry: (Option<Symmetry>, bool),
+}
+
+impl LoggedSettings {
+    /// The settings of `state`, with `params` and `smooth`.
+    pub(crate) fn of(
+        state: &AppState,
+        params: &Params,
+        smooth: SmoothOptions,
+    ) -> Self {
+        Self {
+            params: *params,
+            smooth,
+            draw: (state.draw_obstacles(), state.draw_spines()),
+            pen: state.pen(),
+            symmetry: state.symmetry(),
+        }
+    }
+
+    /// The events of the settings that changed since the last event, to log
+    /// before the next, which is done to `state` with `params` and
+    /// `smooth`.
+    pub(crate) fn changes(
+        &mut self,
+        state: &AppState,
+        params: &Params,
+        smooth: SmoothOptions,
+    ) -> Vec<Event> {
+        let mut changes = Vec::new();
+        if *params != self.params {
+            self.params = *params;
+            changes.push(Event::Params(*params));
+        }
+        if smooth != self.smooth {
+            self.smooth = smooth;
+            changes.push(Event::Smooth(smooth));
+        }
+        let draw = (state.draw_obstacles(), state.draw_spines());
+        if draw != self.draw {
+            self.draw = draw;
+            changes.push(Event::Draw {
+                obstacles: draw.0,
+                spines: draw.1,
+            });
+        }
+        if state.pen() != self.pen {
+            self.pen = state.pen();
+            changes.push(Event::Pen(self.pen));
+        }
+        if state.symmetry() != self.symmetry {
+            self.symmetry = state.symmetry();
+            let (symmetry, growth) = self.symmetry;
+            changes.push(Event::Symmetry { symmetry, growth });
+        }
+        changes
+    }
+}
+
+/// A log of the events of a window being written, each with the time since
+/// the log was started.
+///
+/// The settings the events are done with are logged only when they change,
+/// before the first event done with them. Dialogs are not logged, nor what
+/// they do, such as opening a project, other than adding a primitive.
+pub(crate) struct SessionLog {
+    out: LineWriter<File>,
+    start: Instant,
+    settings: LoggedSettings,
+}
+
+impl SessionLog {
+    /// Start logging the events of `state` to `path`. A simulation seeded
+    /// at random is given a seed of its own first, so that it can be grown
+    /// again.
+    pub(crate) fn create(
+        path: &Path,
+        state: &mut AppState,
+        params: &Params,
+        smooth: SmoothOptions,
+    ) -> Result<Self> {
+        let file = File::create(path)
+            .with_context(|| format!("failed to create {}", path.display()))?;
+        let controller = state.simulation_mut();
+        let seed = controller.seed().unwrap_or_else(rand::random);
+        controller.set_seed(Some(seed));
+
+        let mut log = Self {
+            out: LineWriter::new(file),
+            start: Instant::now(),
+            settings: LoggedSettings::of(state, params, smooth),
+        };
+        writeln!(log.out, "{HEADER}")?;
+        let (symmetry, growth) = state.symmetry();
+        for event in [
+            Event::Seed(seed),
+            Event::Params(*params),
+            Event::Smooth(smooth),
+            Event::Draw {
+                obstacles: state.draw_obstacles(),
+                spines: state.draw_spines(),
+            },
+            Event::Pen(state.pen()),
+            Event::Symmetry { symmetry, growth },
+        ] {
+            log.write(&event)?;
+        }
+        Ok(log)
+    }
+
+    fn write(&mut self, event: &Event) -> Result<()> {
+        let ms = self.start.elapsed().as_millis();
+        writeln!(self.out, "{ms} {event}")?;
+        Ok(())
+    }
+
+    /// Log `event`, done to `state` with `params` and `smooth`, after any
+    /// of the settings that changed since the last event.
+    pub(crate) fn log(
+        &mut self,
+        event: Event,
+        state: &AppState,
+        params: &Params,
+        smooth: SmoothOptions,
+    ) -> Result<()> {
+        for change in self.settings.changes(state, params, smooth) {
+            self.write(&change)?;
+        }
+        self.write(&event)
+    }
+}
+
+/// The events of a session log, each with the time since the log was
+/// started.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct Replay {
+    events: Vec<(Duration, Event)>,
+}
+
+impl Replay {
+    /// A replay of `events`, each at its time since the start.
+    pub(crate) fn new(events: Vec<(Duration, Event)>) -> Self {
+        Self { events }
+    }
+
+    #[cfg(feature = "gui")]
+    pub(crate) fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        text.parse().with_context(|| {
+            format!("invalid session log: {}", path.display())
+        })
+    }
+}
+
+/// Do `event` to `state` as it was done when it was logged, with `params`
+/// and `smooth` as the settings, which the settings events change.
+pub(crate) fn apply(
+    state: &mut AppState,
+    event: &Event,
+    params: &mut Params,
+    smooth: &mut SmoothOptions,
+) -> Result<()> {
+    match *event {
+        Event::Seed(seed) => state.simulation_mut().set_seed(Some(seed)),
+        Event::Params(logged) => *params = logged,
+        Event::Smooth(logged) => *smooth = logged,
+        Event::Draw { obstacles, spines } => {
+            state.set_draw_obstacles(obstacles);
+            state.set_draw_spines(spines);
+        }
+        Event::Pen(pen) => state.set_pen(pen),
+        Event::Symmetry { symmetry, growth } => {
+            state.set_symmetry(symmetry, growth);
+        }
+        Event::BeginStroke { pos, time } => state.begin_stroke(pos, time),
+        Event::ContinueStroke { offset, time } => {
+            state.continue_stroke(offset, time);
+        }
+        Event::EndStroke {
+            offset,
+            time,
+            closed,
+            zoom,
+        } => state.end_stroke(offset, time, closed, zoom, *smooth),
+        Event::Erase { pos, tolerance } => {
+            state.erase_at(pos, tolerance);
+        }
+        Event::Primitive(primitive) => {
+            state.add_primitive(primitive, params)?;
+        }
+        Event::Transform { index, similarity } => {
+            let moved = state.transform_shape(index, similarity);
+            anyhow::ensure!(moved, "no shape {index} to transform");
+        }
+        Event::Action(action) => {
+            let acted = state.act(action, params)?;
+            anyhow::ensure!(acted, "{} is not replayed", action.name());
+        }
+        Event::Steps(n) => {
+            for _ in 0..n {
+                if !state.simulation_mut().tick(params)? {
+                    break;
+                }
+                state.capture_frame()?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Whether a replay is in progress, during which windows do not step their
+/// simulations themselves.
+#[cfg(feature = "gui")]
+pub(crate) static REPLAYING: AtomicBool = AtomicBool::new(false);
+
+/// How often a replay in the window does the events that are due.
+#[cfg(feature = "gui")]
+const REPLAY_TICK: Duration = Duration::from_millis(16);
+
+/// Do the events of `replay` to `state` at the times they were logged,
+/// setting the parameters and smoothing options of the application as
+/// they go.
+#[cfg(feature = "gui")]
+pub(crate) fn play(replay: Replay, state: SharedState) {
+    REPLAYING.store(true, Ordering::Relaxed);
+    let start = Instant::now();
+    let mut events = replay.events.into_iter().peekable();
+    let mut params = *PARAMS.read().unwrap();
+    let mut smooth = *SMOOTH_OPTIONS.read().unwrap();
+
+    glib::timeout_add_local(REPLAY_TICK, move || {
+        let mut state = state.borrow_mut();
+        let elapsed = start.elapsed();
+        let mut failed = false;
+        while let Some((_, event)) = events.next_if(|&(at, _)| at <= elapsed) {
+            if let Err(err) =
+                apply(&mut state, &event, &mut params, &mut smooth)
+            {
+                tracing::error!("replay stopped: {err:#}");
+                failed = true;
+                break;
+            }
+        }
+        *PARAMS.write().unwrap() = params;
+        *SMOOTH_OPTIONS.write().unwrap() = smooth;
+        state.damage_mut().add_all();
+
+        if failed || events.peek().is_none() {
+            tracing::info!("replay finished");
+            REPLAYING.store(false, Ordering::Relaxed);
+            glib::ControlFlow::Break
+        } else {
+            glib::ControlFlow::Continue
+        }
+    });
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let flag = |b| b as u8;
+        match self {
+            Self::Seed(seed) => write!(f, "seed {seed}"),
+            Self::Params(p) => write!(
+                f,
+                "params {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} \
+                 {} {} {} {} {} {} {} {}",
+                p.near_l,
+                p.far_l,
+                p.step,
+                p.spawn_probability,
+                p.branch_probability,
+                p.equalize,
+                p.stiffness,
+                p.n_max,
+                mode_name(&SPAWN_MODES, p.spawn_mode),
+                p.boundary_margin,
+                mode_name(&BOUNDARY_MODES, p.boundary_mode),
+                p.seed_padding,
+                p.seed_jitter.offset,
+                p.seed_jitter.rotation,
+                p.seed_jitter.scale,
+                p.spine_corridor,
+                p.noise_amplitude,
+                p.noise_scale,
+                flag(p.strict_topology),
+                p.max_length,
+                p.region[0].x,
+                p.region[0].y,
+                p.region[1].x,
+                p.region[1].y,
+                mode_name(&INDEXES, p.index),
+                p.neighbor_skin,
+                p.min_edge_length,
+            ),
+            Self::Smooth(o) => write!(
+                f,
+                "smooth {} {} {}",
+                mode_name(&METHODS, o.method),
+                o.iterations,
+                o.spacing
+            ),
+            Self::Draw { obstacles, spines } => {
+                write!(f, "draw {} {}", flag(*obstacles), flag(*spines))
+            }
+            Self::Pen(pen) => {
+                write!(f, "pen {} {} {}", pen.pressure, pen.tilt.x, pen.tilt.y)
+            }
+            Self::Symmetry { symmetry, growth } => {
+                let growth = flag(*growth);
+                match symmetry {
+                    Some(symmetry) => {
+                        write!(f, "symmetry {symmetry} {growth}")
+                    }
+                    None => write!(f, "symmetry off {growth}"),
+                }
+            }
+            Self::BeginStroke { pos, time } => {
+                write!(f, "begin {} {} {time}", pos.x, pos.y)
+            }
+            Self::ContinueStroke { offset, time } => {
+                write!(f, "continue {} {} {time}", offset.x, offset.y)
+            }
+            Self::EndStroke {
+                offset,
+                time,
+                closed,
+                zoom,
+            } => write!(
+                f,
+                "end {} {} {time} {} {zoom}",
+                offset.x,
+                offset.y,
+                flag(*closed)
+            ),
+            Self::Erase { pos, tolerance } => {
+                write!(f, "erase {} {} {tolerance}", pos.x, pos.y)
+            }
+            Self::Primitive(primitive) => {
+                write!(f, "primitive ")?;
+                write_primitive(f, primitive)
+            }
+            Self::Transform { index, similarity } => write!(
+                f,
+                "transform {index} {} {} {} {}",
+                similarity.offset.x,
+                similarity.offset.y,
+                similarity.angle,
+                similarity.scale
+            ),
+            Self::Action(action) => write!(f, "action {}", action.name()),
+            Self::Steps(n) => write!(f, "steps {n}"),
+        }
+    }
+}
+
+/// Parse the parameters of a `params` event, in the order of the fields of
+/// [`Params`]. They are written exactly as they are, rather than in the
+/// units of the parameters panel, for a replay to grow the same.
+fn parse_params(values: &mut SplitWhitespace<'_>) -> Result<Params> {
+    let (near_l, far_l, step) = (next(values)?, next(values)?, next(values)?);
+    let (spawn_probability, branch_probability) =
+        (next(values)?, next(values)?);
+    let (equalize, stiffness) = (next(values)?, next(values)?);
+    let n_max = next(values)?;
+    let spawn_mode = values.next().unwrap_or_default();
+    let spawn_mode = mode_named(&SPAWN_MODES, spawn_mode)
+        .with_context(|| format!("unknown spawn mode: {spawn_mode}"))?;
+    let boundary_margin = next(values)?;
+    let boundary_mode = values.next().unwrap_or_default();
+    let boundary_mode = mode_named(&BOUNDARY_MODES, boundary_mode)
+        .with_context(|| format!("unknown boundary mode: {boundary_mode}"))?;
+    Ok(Params {
+        near_l,
+        far_l,
+        step,
+        spawn_probability,
+        branch_probability,
+        equalize,
+        stiffness,
+        n_max,
+        spawn_mode,
+        boundary_margin,
+        boundary_mode,
+        seed_padding: next(values)?,
+        seed_jitter: SeedJitter {
+            offset: next(values)?,
+            rotation: next(values)?,
+            scale: next(values)?,
+        },
+        spine_corridor: next(values)?,
+        noise_amplitude: next(values)?,
+        noise_scale: next(values)?,
+        strict_topology: next_flag(values)?,
+        max_length: next(values)?,
+        region: [next_point(values)?, next_point(values)?],
+        index: {
+            let index = values.next().unwrap_or_default();
+            mode_named(&INDEXES, index)
+                .with_context(|| format!("unknown index: {index}"))?
+        },
+        neighbor_skin: next(values)?,
+        min_edge_length: next(values)?,
+    })
+}
+
+impl FromStr for Event {
+    type Err = anyhow::Error;
+
+    fn from_str(line: &str) -> Result<Self> {
+        let mut values = line.split_whitespace();
+        let keyword = values.next().context("missing event")?;
+        let values = &mut values;
+        let event = match keyword {
+            "seed" => Self::Seed(next(values)?),
+            "params" => Self::Params(parse_params(values)?),
+            "smooth" => {
+                let method = values.next().unwrap_or_default();
+                Self::Smooth(SmoothOptions {
+                    method: mode_named(&METHODS, method).with_context(
+                        || format!("unknown smoothing method: {method}"),
+                    )?,
+                    iterations: next(values)?,
+                    spacing: next(values)?,
+                })
+            }
+            "draw" => Self::Draw {
+                obstacles: next_flag(values)?,
+                spines: next_flag(values)?,
+            },
+            "pen" => Self::Pen(Pen {
+                pressure: next(values)?,
+                tilt: next_point(values)?,
+            }),
+            "symmetry" => Self::Symmetry {
+                symmetry: match values.next().context("missing value")? {
+                    "off" => None,
+                    symmetry => Some(symmetry.parse()?),
+                },
+                growth: next_flag(values)?,
+            },
+            "begin" => Self::BeginStroke {
+                pos: next_point(values)?,
+                time: next(values)?,
+            },
+            "continue" => Self::ContinueStroke {
+                offset: next_point(values)?,
+                time: next(values)?,
+            },
+            "end" => Self::EndStroke {
+                offset: next_point(values)?,
+                time: next(values)?,
+                closed: next_flag(values)?,
+                zoom: next(values)?,
+            },
+            "erase" => Self::Erase {
+                pos: next_point(values)?,
+                tolerance: next(values)?,
+            },
+            "primitive" => Self::Primitive(parse_primitive(values)?),
+            "transform" => Self::Transform {
+                index: next(values)?,
+                similarity: Similarity {
+                    offset: next_point(values)?,
+                    angle: next(values)?,
+                    scale: next(values)?,
+                },
+            },
+            "action" => {
+                let name = values.next().unwrap_or_default();
+                Self::Action(
+                    Action::ALL
+                        .into_iter()
+                        .find(|action| action.name() == name)
+                        .with_context(|| format!("unknown action: {name}"))?,
+                )
+            }
+            "steps" => Self::Steps(next(values)?),
+            _ => anyhow::bail!("unknown event: {keyword}"),
+        };
+        anyhow::ensure!(values.next().is_none(), "too many values");
+        Ok(event)
+    }
+}
+
+impl fmt::Display for Replay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{HEADER}")?;
+        for (at, event) in &self.events {
+            writeln!(f, "{} {event}", at.as_millis())?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Replay {
+    type Err = anyhow::Error;
+
+    fn from_str(text: &str) -> Result<Self> {
+        let mut lines = text.lines().enumerate();
+
+        match lines.next() {
+            Some((_, HEADER)) => {}
+            _ => anyhow::bail!("missing header: {HEADER}"),
+        }
+
+        let mut replay = Self::default();
+        for (i, line) in lines {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let parsed = line
+                .trim_start()
+                .split_once(' ')
+                .context("missing event")
+                .and_then(|(ms, event)| {
+                    let ms = ms
+                        .parse()
+                        .with_context(|| format!("invalid time: {ms}"))?;
+                    Ok((Duration::from_millis(ms), event.parse()?))
+                })
+                .with_context(|| format!("line {}", i + 1))?;
+            replay.events.push(parsed);
+        }
+        Ok(replay)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::{IndexKind, PrimitiveKind};
+
+    fn replay_into(text: &str) -> AppState {
+        let replay = text.parse::<Replay>().unwrap();
+        let mut state = AppState::default();
+        let mut params = Params::DEFAULT;
+        let mut smooth = SmoothOptions::DEFAULT;
+        for (_, event) in &replay.events {
+            apply(&mut state, event, &mut params, &mut smooth).unwrap();
+        }
+        state
+    }
+
+    #[test]
+    fn printed_events_parse_back() {
+        let events = [
+            Event::Seed(7),
+            Event::Params(Params {
+                branch_probability: 0.01,
+                equalize: 0.5,
+                stiffness: 2.,
+                index: IndexKind::QuadTree,
+                ..Params::DEFAULT
+            }),
+            Event::Smooth(SmoothOptions::DEFAULT),
+            Event::Draw {
+                obstacles: false,
+                spines: true,
+            },
+            Event::Pen(Pen {
+                pressure: 0.5,
+                tilt: DVec2::new(0.1, -0.2),
+            }),
+            Event::Symmetry {
+                symmetry: Some(Symmetry::Radial(6)),
+                growth: true,
+            },
+            Event::Symmetry {
+                symmetry: None,
+                growth: false,
+            },
+            Event::BeginStroke {
+                pos: DVec2::new(100.25, 200.),
+                time: 10,
+            },
+            Event::ContinueStroke {
+                offset: DVec2::new(1. / 3., -4.),
+                time: 20,
+            },
+            Event::EndStroke {
+                offset: DVec2::new(50., 0.),
+                time: 30,
+                closed: true,
+                zoom: 1.5,
+            },
+            Event::Erase {
+                pos: DVec2::new(10., 10.),
+                tolerance: 10.,
+            },
+            Event::Primitive(Primitive {
+                kind: PrimitiveKind::Polygon { sides: 5 },
+                center: DVec2::new(0.25, 0.5),
+                radius: 0.1,
+                rotation: 0.5,
+                n_vertices: 40,
+                passive: true,
+            }),
+            Event::Transform {
+                index: 0,
+                similarity: Similarity {
+                    offset: DVec2::new(4., -2.),
+                    angle: 0.25,
+                    scale: 1.5,
+                },
+            },
+            Event::Action(Action::PauseSim),
+            Event::Steps(12),
+        ];
+        let replay = Replay {
+            events: events
+                .into_iter()
+                .enumerate()
+                .map(|(i, e)| (Duration::from_millis(i as u64 * 5), e))
+                .collect(),
+        };
+        assert_eq!(replay.to_string().parse::<Replay>().unwrap(), replay);
+    }
+
+    #[test]
+    fn invalid_session_logs_are_rejected() {
+        for line in [
+            "0 fly",
+            "0 seed",
+            "0 seed 1 2",
+            "0 action undo-everything",
+            "0 smooth bezier 2 20",
+            "0 draw 0",
+            "0 symmetry radial-0 1",
+            "soon steps 1",
+        ] {
+            let text = format!("{HEADER}\n{line}");
+            assert!(text.parse::<Replay>().is_err(), "{line:?}");
+        }
+    }
+
+    #[test]
+    fn replays_draw_and_grow_the_same() {
+        let log = format!(
+            "{HEADER}
+0 seed 3
+0 smooth off 0 0
+5 begin 300 300 0
+6 continue 100 0 10
+7 continue 100 100 20
+8 end 0 100 30 1 1
+9 action pause-sim
+10 steps 40
+11 begin 500 300 40
+12 end 50 0 50 0 1
+13 action undo
+14 steps 10
+"
+        );
+
+        let [a, b] = [(); 2].map(|_| replay_into(&log));
+        let [shape] = a.shapes() else {
+            panic!("expected one shape");
+        };
+        assert!(shape.closed());
+        assert_eq!(a.simulation().iteration(), 50);
+        let paths = |state: &AppState| {
+            state.simulation().simulation().unwrap().paths()
+        };
+        assert_eq!(paths(&a), paths(&b));
+    }
+
+    #[test]
+    fn logged_sessions_replay() {
+        let path = std::env::temp_dir().join("dxdy-draw-replay-test.log");
+        let params = Params::DEFAULT;
+        let smooth = SmoothOptions::DEFAULT;
+        let mut state = AppState::default();
+        let mut log =
+            SessionLog::create(&path, &mut state, &params, smooth).unwrap();
+
+        let events = [
+            Event::BeginStroke {
+                pos: DVec2::new(300., 300.),
+                time: 0,
+            },
+            Event::ContinueStroke {
+                offset: DVec2::new(80., 20.),
+                time: 10,
+            },
+            Event::EndStroke {
+                offset: DVec2::new(40., 90.),
+                time: 20,
+                closed: true,
+                zoom: 1.,
+            },
+            Event::Action(Action::PauseSim),
+            Event::Steps(30),
+        ];
+        let (mut p, mut s) = (params, smooth);
+        for event in events {
+            log.log(event.clone(), &state, &params, smooth).unwrap();
+            apply(&mut state, &event, &mut p, &mut s).unwrap();
+        }
+        drop(log);
+
+        let replayed = replay_into(&std::fs::read_to_string(&path).unwrap());
+        assert_eq!(replayed.simulation().seed(), state.simulation().seed());
+        assert_eq!(replayed.shapes().len(), 1);
+        assert_eq!(
+            replayed.simulation().simulation().unwrap().paths(),
+            state.simulation().simulation().unwrap().paths()
+        );
+    }
+}
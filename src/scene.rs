@@ -0,0 +1,579 @@
+//! What to draw, described independently of how it is drawn.
+//!
+//! Scenes are built from the drawing, the simulation, and what the window
+//! shows around them, and then drawn by any of the renderers, so that
+//! images look the same whichever one made them.
+
+use glam::DVec2;
+
+#[cfg(any(feature = "gui", test))]
+use super::algorithm::{Domain, Params};
+#[cfg(feature = "gui")]
+use super::algorithm::{Field, FieldKind, Symmetry};
+use super::{
+    algorithm::{SegmentPath, Simulation},
+    layers::{CanvasLayer, CanvasLayers},
+    polyline::Polyline,
+    shape::Shape,
+    style::Style,
+    transform::{DOCUMENT_SIZE, Transform},
+};
+
+/// A color with straight alpha, each channel from `0.` to `1.`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Color {
+    pub(crate) red: f32,
+    pub(crate) green: f32,
+    pub(crate) blue: f32,
+    pub(crate) alpha: f32,
+}
+
+impl Color {
+    pub(crate) const fn new(
+        red: f32,
+        green: f32,
+        blue: f32,
+        alpha: f32,
+    ) -> Self {
+        Self {
+            red,
+            green,
+            blue,
+            alpha,
+        }
+    }
+
+    /// An opaque color from channels from `0` to `255`.
+    pub(crate) const fn rgb8(red: u8, green: u8, blue: u8) -> Self {
+        const fn f(b: u8) -> f32 {
+            b as f32 / u8::MAX as f32
+        }
+
+        Self::new(f(red), f(green), f(blue), 1.)
+    }
+
+    pub(crate) const fn with_alpha(self, alpha: f32) -> Self {
+        Self { alpha, ..self }
+    }
+
+    /// The channels from `0` to `255`, without the alpha.
+    pub(crate) fn to_rgb8(self) -> [u8; 3] {
+        [self.red, self.green, self.blue]
+            .map(|c| (c * u8::MAX as f32).round() as u8)
+    }
+}
+
+/// How the paths of an [`Item`] are drawn.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Paint {
+    /// Only the window fills paths, for the cursor and the dots of fields.
+    #[cfg(feature = "gui")]
+    Fill(Color),
+    Stroke(Stroke),
+}
+
+/// How the paths of an [`Item`] are stroked.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Stroke {
+    pub(crate) color: Color,
+    pub(crate) width: f64,
+    /// Whether the ends and corners of lines are round, or else cut off
+    /// square and mitered.
+    pub(crate) round: bool,
+    /// Length of the dashes and of the gaps between them, or `None` for a
+    /// solid line.
+    pub(crate) dash: Option<f64>,
+    /// Whether only the half of the line inside the paths is drawn, for a
+    /// band along the inside of an outline.
+    pub(crate) inside: bool,
+}
+
+impl Stroke {
+    /// A solid line with square ends.
+    pub(crate) const fn new(color: Color, width: f64) -> Self {
+        Self {
+            color,
+            width,
+            round: false,
+            dash: None,
+            inside: false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Path {
+    /// Points joined by lines, and the last back to the first if `closed`.
+    Line { points: Vec<DVec2>, closed: bool },
+    #[cfg(any(feature = "gui", test))]
+    Circle { center: DVec2, radius: f64 },
+}
+
+/// Paths painted the same way, drawn together.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Item {
+    pub(crate) paint: Paint,
+    pub(crate) paths: Vec<Path>,
+}
+
+/// Items drawn in order in the same coordinates.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Layer {
+    /// Scale from the coordinates of the items to those of the scene,
+    /// applied before `offset`.
+    pub(crate) scale: f64,
+    pub(crate) offset: DVec2,
+    /// Changes whenever the items do, so that renderers can keep the layer
+    /// drawn until it does, or `None` to draw it anew every time.
+    pub(crate) key: Option<u64>,
+    /// Name of the layer in exported documents that keep layers apart, or
+    /// `None` to draw its items with those of the scene itself.
+    pub(crate) name: Option<String>,
+    pub(crate) items: Vec<Item>,
+}
+
+impl Layer {
+    /// `items` in the coordinates of the scene.
+    pub(crate) fn new(items: Vec<Item>) -> Self {
+        Self {
+            scale: 1.,
+            offset: DVec2::ZERO,
+            key: None,
+            name: None,
+            items,
+        }
+    }
+}
+
+/// Layers drawn in order over a background.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Scene {
+    pub(crate) size: DVec2,
+    /// Color the scene is filled with first, or `None` to leave it clear
+    /// for something drawn below it.
+    pub(crate) background: Option<Color>,
+    pub(crate) layers: Vec<Layer>,
+}
+
+impl Scene {
+    /// `shapes` and the lines of `simulation` on the background, in
+    /// document coordinates, without the guides and markers of the window.
+    /// Shapes with a layer are drawn last, in a named layer for each.
+    #[cfg(any(feature = "gui", test))]
+    pub(crate) fn frame(
+        style: &Style,
+        shapes: &[Shape],
+        simulation: Option<&Simulation>,
+    ) -> Self {
+        Self::layered(style, &CanvasLayers::DEFAULT, shapes, simulation)
+    }
+
+    /// [`Scene::frame`] with the layers of the canvas shown as `canvas`
+    /// shows them, the obstacles and sketches of each named layer too.
+    pub(crate) fn layered(
+        style: &Style,
+        canvas: &CanvasLayers,
+        shapes: &[Shape],
+        simulation: Option<&Simulation>,
+    ) -> Self {
+        let in_layer = |name: Option<&str>| {
+            let shapes = shapes.iter().filter(|s| s.layer() == name);
+            shapes.cloned().collect::<Vec<_>>()
+        };
+        let items = |shapes: &[Shape], simulation: Option<&Simulation>| {
+            let mut items = Vec::new();
+            for layer in canvas.order() {
+                let drawn = match (layer, simulation) {
+                    (CanvasLayer::Background, _) => continue,
+                    (CanvasLayer::Growth, Some(simulation)) => {
+                        growth(style, simulation, 1.)
+                    }
+                    (CanvasLayer::Growth, None) => continue,
+                    _ => layer_shapes(style, shapes, style.shape, None, layer),
+                };
+                items.extend(canvas.show(layer, drawn));
+            }
+            items
+        };
+
+        let mut layers = vec![Layer::new(items(&in_layer(None), simulation))];
+
+        let mut names = Vec::new();
+        for name in shapes.iter().filter_map(Shape::layer) {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+        for name in names {
+            layers.push(Layer {
+                name: Some(name.to_owned()),
+                ..Layer::new(items(&in_layer(Some(name)), None))
+            });
+        }
+
+        Self {
+            size: DOCUMENT_SIZE,
+            background: canvas.background(style.background),
+            layers,
+        }
+    }
+
+    /// The scene `factor` times as large, lines and all.
+    pub(crate) fn scaled(mut self, factor: f64) -> Self {
+        self.size *= factor;
+        for layer in &mut self.layers {
+            layer.scale *= factor;
+            layer.offset *= factor;
+        }
+        self
+    }
+
+    /// The part of the scene from `min` to `max`, moved to the origin.
+    /// Renderers leave out whatever is outside of it.
+    pub(crate) fn crop(mut self, min: DVec2, max: DVec2) -> Self {
+        self.size = max - min;
+        for layer in &mut self.layers {
+            layer.offset -= min;
+        }
+        self
+    }
+}
+
+/// Number of vertices of shape `i` to draw, given the shape and vertex
+/// count reached by a playback, if any.
+pub(crate) fn n_verticies(
+    i: usize,
+    playback: Option<(usize, usize)>,
+) -> Option<usize> {
+    match playback {
+        Some((current, _)) if i > current => None,
+        Some((current, n)) if i == current => Some(n),
+        _ => Some(usize::MAX),
+    }
+}
+
+/// Completed shapes in `layer` of the canvas, in `color`, or the obstacle
+/// color for obstacles, each as wide as the pen pressure. Only the part
+/// reached so far is drawn during a playback, which still counts the shapes
+/// of the other layers.
+pub(crate) fn layer_shapes(
+    style: &Style,
+    shapes: &[Shape],
+    color: Color,
+    playback: Option<(usize, usize)>,
+    layer: CanvasLayer,
+) -> Vec<Item> {
+    let mut items = Vec::new();
+
+    for (i, shape) in shapes.iter().enumerate() {
+        let Some(n_verticies) = n_verticies(i, playback) else {
+            break;
+        };
+        if !layer.holds(shape) {
+            continue;
+        }
+        let start = shape.start();
+        let color = if shape.obstacle() {
+            style.obstacle
+        } else {
+            color
+        };
+
+        // the width of each edge follows the pen pressure at its ends, and
+        // runs of edges of the same width are drawn together, which is the
+        // whole shape unless the pressure changes
+        let points = shape
+            .verticies()
+            .zip(shape.pens())
+            .take(n_verticies)
+            .map(|(offset, pen)| (start + offset, pen.pressure))
+            .collect::<Vec<_>>();
+        let closing = (shape.is_closed()
+            && n_verticies == usize::MAX
+            && points.len() > 2)
+            .then(|| (points[points.len() - 1], points[0]));
+
+        let mut run: Option<(f64, Vec<DVec2>)> = None;
+        for ((a, pa), (b, pb)) in
+            points.windows(2).map(|w| (w[0], w[1])).chain(closing)
+        {
+            let width = style.stroke_width * (pa + pb) / 2.;
+            match &mut run {
+                Some((run_width, points)) if *run_width == width => {
+                    points.push(b)
+                }
+                _ => {
+                    items.extend(
+                        run.take().map(|(w, p)| run_item(color, w, p)),
+                    );
+                    run = Some((width, vec![a, b]));
+                }
+            }
+        }
+        items
+            .extend(run.map(|(width, points)| run_item(color, width, points)));
+    }
+
+    items
+}
+
+fn run_item(color: Color, width: f64, points: Vec<DVec2>) -> Item {
+    Item {
+        paint: Paint::Stroke(Stroke {
+            round: true,
+            ..Stroke::new(color, width)
+        }),
+        paths: vec![Path::Line {
+            points,
+            closed: false,
+        }],
+    }
+}
+
+/// Color of a path of the simulation: its segment's color, or the obstacle
+/// color for obstacles.
+pub(crate) fn growth_color(style: &Style, path: &SegmentPath) -> Color {
+    if path.passive {
+        style.obstacle
+    } else {
+        style.growth[path.segment as usize % style.growth.len()]
+    }
+}
+
+/// The lines of `simulation` in document coordinates, `width` wide, with
+/// all paths of a color in one item.
+pub(crate) fn growth(
+    style: &Style,
+    simulation: &Simulation,
+    width: f64,
+) -> Vec<Item> {
+    growth_paths(style, simulation.paths(), width)
+}
+
+/// Like [`growth`], of `paths` of a simulation.
+pub(crate) fn growth_paths(
+    style: &Style,
+    paths: Vec<SegmentPath>,
+    width: f64,
+) -> Vec<Item> {
+    let mut items = Vec::<Item>::new();
+
+    for path in paths {
+        let paint =
+            Paint::Stroke(Stroke::new(growth_color(style, &path), width));
+        let points = path
+            .points()
+            .map(|pos| Transform::DEFAULT.unit_to_document(pos))
+            .collect();
+        let path = Path::Line {
+            points,
+            closed: path.is_closed(),
+        };
+
+        match items.iter_mut().find(|item| item.paint == paint) {
+            Some(item) => item.paths.push(path),
+            None => items.push(Item {
+                paint,
+                paths: vec![path],
+            }),
+        }
+    }
+
+    items
+}
+
+/// Number of vertex markers in an item.
+#[cfg(any(feature = "gui", test))]
+const MARKER_BATCH: usize = 32;
+
+/// A small circle at every vertex of `shapes`, limited like
+/// [`layer_shapes`]
+/// during a playback. There are none if there are more than `max`
+/// vertices, as the markers would cover the shapes anyway.
+#[cfg(any(feature = "gui", test))]
+pub(crate) fn vertex_markers(
+    style: &Style,
+    shapes: &[Shape],
+    playback: Option<(usize, usize)>,
+    hairline: f64,
+    max: usize,
+) -> Vec<Item> {
+    if shapes.iter().map(Shape::n_verticies).sum::<usize>() > max {
+        return Vec::new();
+    }
+
+    let mut markers = Vec::new();
+    for (i, shape) in shapes.iter().enumerate() {
+        let Some(n_verticies) = n_verticies(i, playback) else {
+            break;
+        };
+        let start = shape.start();
+        markers.extend(shape.verticies().take(n_verticies).map(|offset| {
+            Path::Circle {
+                center: start + offset,
+                radius: style.marker_radius * hairline,
+            }
+        }));
+    }
+
+    // markers are stroked a batch at a time, as one path of many
+    // overlapping circles is slower to stroke than a few smaller ones
+    markers
+        .chunks(MARKER_BATCH)
+        .map(|batch| Item {
+            paint: Paint::Stroke(Stroke::new(style.marker, hairline)),
+            paths: batch.to_vec(),
+        })
+        .collect()
+}
+
+/// The reach of each of `fields` and a mark at its center, in document
+/// coordinates: a dot for attractors, a ring for repulsors, and a line the
+/// way it blows from the center to the reach for wind.
+#[cfg(feature = "gui")]
+pub(crate) fn field_guides(
+    style: &Style,
+    fields: &[Field],
+    hairline: f64,
+) -> Vec<Item> {
+    let transform = Transform::DEFAULT;
+    let mark = 3. * hairline;
+
+    let (mut dots, mut lines) = (Vec::new(), Vec::new());
+    for field in fields {
+        let center = transform.unit_to_document(field.position);
+        let radius = transform.unit_to_document_length(field.radius);
+        lines.push(Path::Circle { center, radius });
+        match field.kind {
+            FieldKind::Attractor => dots.push(Path::Circle {
+                center,
+                radius: mark,
+            }),
+            FieldKind::Repulsor => lines.push(Path::Circle {
+                center,
+                radius: mark,
+            }),
+            FieldKind::Wind { angle } => lines.push(Path::Line {
+                points: vec![
+                    center,
+                    center + radius * DVec2::from_angle(angle),
+                ],
+                closed: false,
+            }),
+        }
+    }
+
+    vec![
+        Item {
+            paint: Paint::Stroke(Stroke {
+                dash: Some(2. * hairline),
+                ..Stroke::new(style.marker, hairline)
+            }),
+            paths: lines,
+        },
+        Item {
+            paint: Paint::Fill(style.marker),
+            paths: dots,
+        },
+    ]
+}
+
+/// The axes of `symmetry` about the center of the document, in document
+/// coordinates: the mirror line, or a spoke to the edge for each part of
+/// the turn.
+#[cfg(feature = "gui")]
+pub(crate) fn symmetry_guides(
+    style: &Style,
+    symmetry: Symmetry,
+    hairline: f64,
+) -> Item {
+    let center = DOCUMENT_SIZE / 2.;
+    let line = |a, b| Path::Line {
+        points: vec![a, b],
+        closed: false,
+    };
+    let paths = match symmetry {
+        Symmetry::Horizontal => vec![line(
+            DVec2::new(center.x, 0.),
+            DVec2::new(center.x, DOCUMENT_SIZE.y),
+        )],
+        Symmetry::Vertical => vec![line(
+            DVec2::new(0., center.y),
+            DVec2::new(DOCUMENT_SIZE.x, center.y),
+        )],
+        Symmetry::Radial(_) => {
+            let spoke = DVec2::new(center.min_element(), 0.);
+            symmetry
+                .maps()
+                .into_iter()
+                .map(|m| line(center, center + m * spoke))
+                .collect()
+        }
+    };
+    Item {
+        paint: Paint::Stroke(Stroke {
+            dash: Some(6. * hairline),
+            ..Stroke::new(style.marker.with_alpha(0.5), hairline)
+        }),
+        paths,
+    }
+}
+
+/// The band inside the edge of `domain` in which the boundary applies, and
+/// the outline of the region seeds are placed in, in document coordinates.
+#[cfg(any(feature = "gui", test))]
+pub(crate) fn domain_guides(
+    style: &Style,
+    domain: &Domain,
+    params: &Params,
+    hairline: f64,
+) -> Vec<Item> {
+    let transform = Transform::DEFAULT;
+
+    // the part of simulation space within `inset` of its edges
+    let unit_rect = |inset: f64| {
+        let a = transform.unit_to_document(DVec2::splat(inset));
+        let b = transform.unit_to_document(DVec2::splat(1. - inset));
+        Path::Line {
+            points: vec![a, DVec2::new(b.x, a.y), b, DVec2::new(a.x, b.y)],
+            closed: true,
+        }
+    };
+
+    let outline = match domain {
+        Domain::Square => unit_rect(0.),
+        Domain::Circle { center, radius } => Path::Circle {
+            center: transform.unit_to_document(*center),
+            radius: transform.unit_to_document_length(*radius),
+        },
+        Domain::Polygon(points) => Path::Line {
+            points: points
+                .iter()
+                .map(|&pos| transform.unit_to_document(pos))
+                .collect(),
+            closed: true,
+        },
+    };
+
+    vec![
+        // the inner half of the outline stroked twice as wide as the margin
+        Item {
+            paint: Paint::Stroke(Stroke {
+                inside: true,
+                ..Stroke::new(
+                    style.boundary,
+                    2. * transform
+                        .unit_to_document_length(params.boundary_margin),
+                )
+            }),
+            paths: vec![outline],
+        },
+        Item {
+            paint: Paint::Stroke(Stroke {
+                dash: Some(4. * hairline),
+                ..Stroke::new(style.padding, hairline)
+            }),
+            paths: vec![unit_rect(params.seed_padding.min(0.5))],
+        },
+    ]
+}
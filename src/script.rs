@@ -0,0 +1,470 @@
+//! Scripts that seed, grow and export drawings without anyone at the
+//! window, run from the command line or from the window as a job.
+
+use std::{
+    cell::RefCell,
+    ops::ControlFlow,
+    path::{Path, PathBuf},
+    rc::Rc,
+    str::FromStr,
+};
+
+use anyhow::{Context, Result};
+use glam::DVec2;
+use rhai::{Dynamic, Engine, EvalAltResult, INT};
+
+use super::{
+    algorithm::{ONE, Params, Primitive, PrimitiveKind},
+    modes::{BOUNDARY_MODES, INDEXES, SPAWN_MODES, mode_named},
+    preset::Preset,
+    project::Project,
+    simulation::{RunState, SimulationController},
+    style::STYLE,
+};
+
+/// Events of the simulation that scripts can hook into.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Hook {
+    Step,
+    Spawn,
+    Collapse,
+    Stop,
+}
+
+/// Names of the hooks in scripts.
+const HOOKS: [(&str, Hook); 4] = [
+    ("step", Hook::Step),
+    ("spawn", Hook::Spawn),
+    ("collapse", Hook::Collapse),
+    ("stop", Hook::Stop),
+];
+
+/// A script in [Rhai](https://rhai.rs), run from the first statement to the
+/// last.
+///
+/// Besides the language itself, scripts have these functions:
+///
+/// - `seed(N)`, growing with the random seed N;
+/// - `circle(X, Y, RADIUS, N)`, adding a circle of N vertices as a seed,
+///   in the unit square;
+/// - `record(LINE)`, applying a record of a project file, such as a
+///   `shape` and its `vertex` records, or a `field`;
+/// - `load(PATH)`, replacing the seeds with those of a project file;
+/// - `param(NAME, VALUE)`, setting a growth parameter, with distances in
+///   thousandths of the simulation space as in the parameters panel;
+/// - `run(N)`, growing the simulation N more iterations, or until it stops,
+///   starting a new one if there is none, and returning the iteration;
+/// - `iteration()` and `vertices()`, the iteration of the simulation and
+///   its number of vertices;
+/// - `reset()`, discarding the simulation so the next run starts over;
+/// - `export_image(PATH)` and `export_image(PATH, PRESET)`, writing an image of the
+///   drawing and its growth, with a saved preset if one is named;
+/// - `log(EVENT)`, logging every `step`, `spawn` or `collapse` of
+///   vertices, or `stop` of a run, from then on.
+///
+/// Adding seeds or loading a project also discards the simulation. Paths
+/// are relative to the directory of the script, and `print` logs.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct Script {
+    source: String,
+    /// Directory that paths in the script are relative to.
+    dir: PathBuf,
+}
+
+/// Set the parameter `name` of `params` to `value`.
+fn set_param(params: &mut Params, name: &str, value: &str) -> Result<()> {
+    let number = || {
+        value
+            .parse::<f64>()
+            .with_context(|| format!("invalid value: {value}"))
+    };
+    match name {
+        "near" => params.near_l = number()? * ONE,
+        "far" => params.far_l = number()? * ONE,
+        "step" => params.step = number()? * ONE,
+        "spawn-probability" => params.spawn_probability = number()?,
+        "branch-probability" => params.branch_probability = number()?,
+        "equalize" => params.equalize = number()?,
+        "stiffness" => params.stiffness = number()?,
+        "n-max" => {
+            params.n_max = value
+                .parse()
+                .with_context(|| format!("invalid value: {value}"))?
+        }
+        "spawn-mode" => {
+            params.spawn_mode = mode_named(&SPAWN_MODES, value)
+                .with_context(|| format!("unknown spawn mode: {value}"))?
+        }
+        "boundary-margin" => params.boundary_margin = number()? * ONE,
+        "boundary-mode" => {
+            params.boundary_mode = mode_named(&BOUNDARY_MODES, value)
+                .with_context(|| format!("unknown boundary mode: {value}"))?
+        }
+        "seed-padding" => params.seed_padding = number()? * ONE,
+        "spine-corridor" => params.spine_corridor = number()? * ONE,
+        "noise-amplitude" => params.noise_amplitude = number()?,
+        "noise-scale" => params.noise_scale = number()? * ONE,
+        "strict-topology" => {
+            params.strict_topology = value
+                .parse()
+                .with_context(|| format!("invalid value: {value}"))?
+        }
+        "max-length" => params.max_length = number()? * ONE,
+        "region-left" => params.region[0].x = number()? * ONE,
+        "region-top" => params.region[0].y = number()? * ONE,
+        "region-right" => params.region[1].x = number()? * ONE,
+        "region-bottom" => params.region[1].y = number()? * ONE,
+        "neighbor-skin" => params.neighbor_skin = number()? * ONE,
+        "min-edge-length" => params.min_edge_length = number()? * ONE,
+        "index" => {
+            params.index = mode_named(&INDEXES, value)
+                .with_context(|| format!("unknown index: {value}"))?
+        }
+        _ => anyhow::bail!("unknown parameter: {name}"),
+    }
+    Ok(())
+}
+
+/// What a script runs on: the seeds, the parameters and the simulation
+/// grown from them.
+struct Runner {
+    project: Project,
+    params: Params,
+    controller: SimulationController,
+    /// Directory that paths in the script are relative to.
+    dir: PathBuf,
+    /// The images written so far.
+    written: Vec<PathBuf>,
+    on_progress: Box<dyn FnMut(u64) -> Result<()>>,
+}
+
+impl Runner {
+    fn seed(&mut self, seed: INT) -> Result<()> {
+        self.project.seed = Some(seed.try_into().context("negative seed")?);
+        self.controller.clear();
+        Ok(())
+    }
+
+    fn circle(
+        &mut self,
+        x: f64,
+        y: f64,
+        radius: f64,
+        n_vertices: INT,
+    ) -> Result<()> {
+        self.project.primitives.push(Primitive {
+            kind: PrimitiveKind::Circle,
+            center: DVec2::new(x, y),
+            radius,
+            rotation: 0.,
+            n_vertices: n_vertices
+                .try_into()
+                .context("negative number of vertices")?,
+            passive: false,
+        });
+        self.controller.clear();
+        Ok(())
+    }
+
+    fn record(&mut self, record: &str) -> Result<()> {
+        let mut values = record.split_whitespace();
+        let keyword = values.next().unwrap_or_default();
+        self.project.parse_record(keyword, &mut values)?;
+        anyhow::ensure!(values.next().is_none(), "too many values");
+        self.controller.clear();
+        Ok(())
+    }
+
+    fn load(&mut self, path: &str) -> Result<()> {
+        self.project = Project::load(&self.dir.join(path))?;
+        self.controller.clear();
+        Ok(())
+    }
+
+    fn run(&mut self, n: INT) -> Result<INT> {
+        let n = u64::try_from(n).context("negative number of iterations")?;
+        let controller = &mut self.controller;
+        if controller.simulation().is_none() {
+            self.project.configure(controller);
+        }
+        if controller.state() == RunState::Paused {
+            controller.toggle_pause(&self.project.shapes, &self.params)?;
+        }
+        let end = controller.iteration() + n;
+        while controller.state() == RunState::Running
+            && controller.iteration() < end
+        {
+            controller.tick(&self.params)?;
+            (self.on_progress)(controller.iteration())?;
+        }
+        if controller.state() == RunState::Running {
+            controller.toggle_pause(&self.project.shapes, &self.params)?;
+        }
+        Ok(controller.iteration() as INT)
+    }
+
+    fn export(&mut self, out: &str, preset: Option<&str>) -> Result<()> {
+        let preset = match preset {
+            Some(name) => Preset::named(name)?,
+            None => Preset::default(),
+        };
+        let out = self.dir.join(out);
+        preset.export(
+            &out,
+            &STYLE.read().unwrap(),
+            &self.project.shapes,
+            self.controller.simulation(),
+            None,
+        )?;
+        tracing::info!("exported {}", out.display());
+        self.written.push(out);
+        Ok(())
+    }
+
+    fn log(&mut self, name: &str) -> Result<()> {
+        let hook = mode_named(&HOOKS, name)
+            .with_context(|| format!("unknown event: {name}"))?;
+        let hooks = self.controller.hooks_mut();
+        match hook {
+            Hook::Step => hooks.on_step(|simulation, i| {
+                let n = simulation.vertex_count();
+                tracing::info!("step {i}: {n} vertices");
+                ControlFlow::Continue(())
+            }),
+            Hook::Spawn => {
+                hooks.on_spawn(|i, n| tracing::info!("step {i}: spawned {n}"))
+            }
+            Hook::Collapse => hooks
+                .on_collapse(|i, n| tracing::info!("step {i}: collapsed {n}")),
+            Hook::Stop => hooks.on_stop(|i, reason| match reason {
+                Some(reason) => {
+                    tracing::info!("stopped at step {i}: {reason}")
+                }
+                None => tracing::info!("stopped at step {i}: can not go on"),
+            }),
+        };
+        Ok(())
+    }
+}
+
+/// `err` as an error of the script at the call that failed.
+fn runtime_error(err: anyhow::Error) -> Box<EvalAltResult> {
+    format!("{err:#}").into()
+}
+
+/// The engine that runs scripts on `runner`.
+fn engine(runner: &Rc<RefCell<Runner>>) -> Engine {
+    let mut engine = Engine::new();
+    engine.on_print(|text| tracing::info!("{text}"));
+    engine.on_debug(|text, _, _| tracing::debug!("{text}"));
+
+    // a script stuck in a loop can be stopped in between any two of its
+    // operations
+    let r = runner.clone();
+    engine.on_progress(move |_| {
+        let mut runner = r.try_borrow_mut().ok()?;
+        let iteration = runner.controller.iteration();
+        let stopped = (runner.on_progress)(iteration).err()?;
+        Some(format!("{stopped:#}").into())
+    });
+
+    let r = runner.clone();
+    engine.register_fn("seed", move |seed: INT| {
+        r.borrow_mut().seed(seed).map_err(runtime_error)
+    });
+    let r = runner.clone();
+    engine.register_fn(
+        "circle",
+        move |x: f64, y: f64, radius: f64, n_vertices: INT| {
+            r.borrow_mut()
+                .circle(x, y, radius, n_vertices)
+                .map_err(runtime_error)
+        },
+    );
+    let r = runner.clone();
+    engine.register_fn("record", move |record: &str| {
+        r.borrow_mut().record(record).map_err(runtime_error)
+    });
+    let r = runner.clone();
+    engine.register_fn("load", move |path: &str| {
+        r.borrow_mut().load(path).map_err(runtime_error)
+    });
+    let r = runner.clone();
+    engine.register_fn("param", move |name: &str, value: Dynamic| {
+        let params = &mut r.borrow_mut().params;
+        set_param(params, name, &value.to_string()).map_err(runtime_error)
+    });
+    let r = runner.clone();
+    engine.register_fn("reset", move || r.borrow_mut().controller.clear());
+    let r = runner.clone();
+    engine.register_fn("run", move |n: INT| {
+        r.borrow_mut().run(n).map_err(runtime_error)
+    });
+    let r = runner.clone();
+    engine.register_fn("iteration", move || {
+        r.borrow().controller.iteration() as INT
+    });
+    let r = runner.clone();
+    engine.register_fn("vertices", move || {
+        let runner = r.borrow();
+        let simulation = runner.controller.simulation();
+        simulation.map_or(0, |simulation| simulation.vertex_count() as INT)
+    });
+    let r = runner.clone();
+    engine.register_fn("export_image", move |out: &str| {
+        r.borrow_mut().export(out, None).map_err(runtime_error)
+    });
+    let r = runner.clone();
+    engine.register_fn("export_image", move |out: &str, preset: &str| {
+        r.borrow_mut()
+            .export(out, Some(preset))
+            .map_err(runtime_error)
+    });
+    let r = runner.clone();
+    engine.register_fn("log", move |event: &str| {
+        r.borrow_mut().log(event).map_err(runtime_error)
+    });
+
+    engine
+}
+
+impl Script {
+    pub(crate) fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let mut script = text
+            .parse::<Self>()
+            .with_context(|| format!("invalid script: {}", path.display()))?;
+        script.dir = path.parent().unwrap_or(Path::new("")).to_owned();
+        Ok(script)
+    }
+
+    /// Run the script from the seeds of `project` with `params`, calling
+    /// `on_progress` with the iteration of the simulation after every step
+    /// and in between the operations of the script, to stop if it fails.
+    /// Returns the images written.
+    pub(crate) fn run(
+        &self,
+        project: Project,
+        params: Params,
+        on_progress: impl FnMut(u64) -> Result<()> + 'static,
+    ) -> Result<Vec<PathBuf>> {
+        let runner = Rc::new(RefCell::new(Runner {
+            project,
+            params,
+            controller: SimulationController::new(),
+            dir: self.dir.clone(),
+            written: Vec::new(),
+            on_progress: Box::new(on_progress),
+        }));
+
+        let result = engine(&runner).run(&self.source);
+        let mut runner = runner.borrow_mut();
+        match result.map_err(|err| *err) {
+            Ok(()) => {}
+            Err(EvalAltResult::ErrorTerminated(reason, pos)) => {
+                anyhow::bail!("{reason} ({pos})")
+            }
+            Err(err) => anyhow::bail!("{err}"),
+        }
+        let iteration = runner.controller.iteration();
+        (runner.on_progress)(iteration)?;
+        Ok(std::mem::take(&mut runner.written))
+    }
+}
+
+impl FromStr for Script {
+    type Err = anyhow::Error;
+
+    /// The script of `text`, which is compiled once now for its syntax
+    /// errors to be found before it runs.
+    fn from_str(text: &str) -> Result<Self> {
+        Engine::new()
+            .compile(text)
+            .map_err(|err| anyhow::anyhow!("{err}"))?;
+        Ok(Self {
+            source: text.to_owned(),
+            dir: PathBuf::new(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+
+    const SCRIPT: &str = r#"
+// a ring, grown twice as far apart
+seed(7);
+circle(0.5, 0.5, 0.1, 64);
+param("near", 4); // thousandths
+param("boundary-mode", "clamp");
+log("spawn");
+let grown = run(20);
+if grown != 20 || iteration() != 20 || vertices() < 64 {
+    throw `grew ${grown} iterations`;
+}
+export_image("ring.svg");
+"#;
+
+    fn run(text: &str) -> Result<Vec<PathBuf>> {
+        let script = text.parse::<Script>()?;
+        script.run(Project::default(), Params::DEFAULT, |_| Ok(()))
+    }
+
+    #[test]
+    fn scripts_grow_and_export() {
+        let dir = std::env::temp_dir().join("dxdy-draw-script-test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut script = SCRIPT.parse::<Script>().unwrap();
+        script.dir = dir.clone();
+
+        let iterations = Rc::new(Cell::new(0));
+        let seen = iterations.clone();
+        let written = script
+            .run(Project::default(), Params::DEFAULT, move |iteration| {
+                seen.set(seen.get().max(iteration));
+                Ok(())
+            })
+            .unwrap();
+
+        assert_eq!(iterations.get(), 20);
+        assert_eq!(written, [dir.join("ring.svg")]);
+        let svg = std::fs::read_to_string(&written[0]).unwrap();
+        assert!(svg.contains("<path"));
+    }
+
+    #[test]
+    fn invalid_scripts_are_rejected() {
+        for text in ["run(", "let = 1;", "param(\"near\" 4);"] {
+            assert!(text.parse::<Script>().is_err(), "{text:?}");
+        }
+        for text in [
+            "run(-1);",
+            "seed(-1);",
+            "param(\"near\", \"far\");",
+            "param(\"glow\", 1);",
+            "param(\"boundary-mode\", \"bounce\");",
+            "log(\"grow\");",
+            "record(\"vertex 0 0 0 1 0 0\");",
+            "record(\"seed 1 2\");",
+            "sing();",
+        ] {
+            assert!(text.parse::<Script>().is_ok(), "{text:?}");
+            assert!(run(text).is_err(), "{text:?}");
+        }
+    }
+
+    #[test]
+    fn scripts_stop_when_asked_to() {
+        let script = "loop { }".parse::<Script>().unwrap();
+        let err = script
+            .run(Project::default(), Params::DEFAULT, |_| {
+                anyhow::bail!("cancelled")
+            })
+            .unwrap_err();
+        assert!(err.to_string().starts_with("cancelled"), "{err}");
+    }
+}
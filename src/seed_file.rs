@@ -0,0 +1,585 @@
+//! Seed files: the seeds of an experiment written out by hand, so that a
+//! setup too complex to draw the same way twice can be reviewed and kept
+//! under version control as text. The window loads them with Load Seeds,
+//! and every command that takes a project file takes one too.
+//!
+//! A seed file is TOML with a `[[seed]]` table for each seed, in pixels of
+//! the canvas:
+//!
+//! ```toml
+//! rng-seed = 7
+//!
+//! [[seed]]
+//! shape = "circle"
+//! center = [400, 300]
+//! radius = 50
+//! weight = 2
+//!
+//! [[seed]]
+//! shape = "polygon"
+//! center = [200, 300]
+//! radius = 40
+//! sides = 5
+//! rotation = 36
+//! role = "obstacle"
+//!
+//! [[seed]]
+//! shape = "line"
+//! points = [[100, 500], [400, 450], [700, 500]]
+//! role = "spine"
+//! stiffness = 0.5
+//!
+//! [[seed]]
+//! shape = "path"
+//! d = "M 100 100 C 200 0 300 200 400 100"
+//! ```
+//!
+//! Every seed takes a `role` of `seed`, `obstacle` or `spine`, a `weight`
+//! that scales how fast it spawns new vertices, a `stiffness` of its own,
+//! and a number of `vertices` to have instead of one every few pixels.
+
+use std::{f64::consts::TAU, path::Path, str::FromStr};
+
+use anyhow::{Context, Result};
+use glam::DVec2;
+
+use super::{
+    polyline::Polyline,
+    project::Project,
+    shape::{Pen, Shape},
+};
+
+/// Distance in pixels of the canvas between the vertices of seeds without
+/// a number of vertices.
+const SPACING: f64 = 5.;
+
+/// Line segments each curve of a path is flattened to before it is
+/// resampled.
+const CURVE_STEPS: usize = 16;
+
+/// What a seed is to the simulation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Role {
+    /// Grows.
+    Seed,
+    /// Never moves, but is grown around.
+    Obstacle,
+    /// Grows, but keeps the growth seeded along it near it.
+    Spine,
+}
+
+/// The project of the seed file at `path`.
+pub(crate) fn load(path: &Path) -> Result<Project> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    let SeedFile(project) = text
+        .parse()
+        .with_context(|| format!("invalid seed file: {}", path.display()))?;
+    Ok(project)
+}
+
+/// A parsed seed file, as a project of its seeds.
+struct SeedFile(Project);
+
+impl FromStr for SeedFile {
+    type Err = anyhow::Error;
+
+    fn from_str(text: &str) -> Result<Self> {
+        let mut table = text.parse::<toml::Table>()?;
+        let mut project = Project::default();
+
+        if let Some(value) = table.remove("rng-seed") {
+            let seed = value
+                .as_integer()
+                .and_then(|seed| u64::try_from(seed).ok())
+                .context("expected a positive integer for rng-seed")?;
+            project.seed = Some(seed);
+        }
+
+        let seeds = match table.remove("seed") {
+            Some(toml::Value::Array(seeds)) => seeds,
+            Some(value) => anyhow::bail!(
+                "expected [[seed]] tables, found {}",
+                value.type_str()
+            ),
+            None => Vec::new(),
+        };
+        if let Some(name) = table.keys().next() {
+            anyhow::bail!("unknown setting: {name}");
+        }
+
+        for (i, seed) in seeds.into_iter().enumerate() {
+            let shapes = match seed {
+                toml::Value::Table(seed) => shapes(seed),
+                value => Err(anyhow::anyhow!(
+                    "expected a table, found {}",
+                    value.type_str()
+                )),
+            };
+            project.shapes.extend(
+                shapes.with_context(|| format!("invalid seed {}", i + 1))?,
+            );
+        }
+
+        Ok(Self(project))
+    }
+}
+
+/// The shapes of a `[[seed]]` table: one, or one for each subpath of a
+/// path.
+fn shapes(mut seed: toml::Table) -> Result<Vec<Shape>> {
+    let mut take = |key: &str| seed.remove(key);
+
+    let shape = match take("shape") {
+        Some(toml::Value::String(shape)) => shape,
+        Some(value) => {
+            anyhow::bail!("expected a shape, found {}", value.type_str())
+        }
+        None => anyhow::bail!("missing shape"),
+    };
+    let role = match take("role").map(string).transpose()?.as_deref() {
+        None | Some("seed") => Role::Seed,
+        Some("obstacle") => Role::Obstacle,
+        Some("spine") => Role::Spine,
+        Some(role) => anyhow::bail!("unknown role: {role}"),
+    };
+    let weight = take("weight").map(number).transpose()?.unwrap_or(1.);
+    anyhow::ensure!(weight > 0., "weight must be positive");
+    let stiffness = take("stiffness").map(number).transpose()?;
+    anyhow::ensure!(
+        stiffness.is_none_or(|s| s >= 0.),
+        "stiffness must not be negative"
+    );
+    let n_vertices = match take("vertices") {
+        Some(value) => {
+            let n = value
+                .as_integer()
+                .and_then(|n| usize::try_from(n).ok())
+                .context("expected a number of vertices")?;
+            anyhow::ensure!(n >= 2, "a seed needs at least 2 vertices");
+            Some(n)
+        }
+        None => None,
+    };
+
+    // each outline as its points and whether it is closed
+    let outlines = match shape.as_str() {
+        "circle" | "polygon" => {
+            let center = point(take("center").context("missing center")?)?;
+            let radius = number(take("radius").context("missing radius")?)?;
+            anyhow::ensure!(radius > 0., "radius must be positive");
+            let rotation = take("rotation")
+                .map(number)
+                .transpose()?
+                .unwrap_or(0.)
+                .to_radians();
+            let corners = if shape == "circle" {
+                let n = n_vertices.unwrap_or_else(|| {
+                    ((TAU * radius / SPACING).round() as usize).max(8)
+                });
+                n.max(3)
+            } else {
+                let sides = take("sides")
+                    .context("missing sides")?
+                    .as_integer()
+                    .and_then(|n| usize::try_from(n).ok())
+                    .context("expected a number of sides")?;
+                anyhow::ensure!(sides >= 3, "a polygon needs 3 sides");
+                sides
+            };
+            // the first corner at the top, as with primitives
+            let points = (0..corners)
+                .map(|k| {
+                    let theta =
+                        rotation + TAU * k as f64 / corners as f64 - TAU / 4.;
+                    center + radius * DVec2::from_angle(theta)
+                })
+                .collect::<Vec<_>>();
+            vec![(points, true)]
+        }
+        "line" => {
+            let points = match take("points") {
+                Some(toml::Value::Array(points)) => points
+                    .into_iter()
+                    .map(point)
+                    .collect::<Result<Vec<_>>>()?,
+                _ => anyhow::bail!("expected a list of points"),
+            };
+            let closed = match take("closed") {
+                Some(value) => {
+                    value.as_bool().context("expected true or false")?
+                }
+                None => false,
+            };
+            vec![(points, closed)]
+        }
+        "path" => {
+            let d = string(take("d").context("missing path data d")?)?;
+            let offset = take("offset").map(point).transpose()?;
+            let scale = take("scale").map(number).transpose()?.unwrap_or(1.);
+            parse_path(&d)?
+                .into_iter()
+                .map(|(points, closed)| {
+                    let points = points
+                        .into_iter()
+                        .map(|p| p * scale + offset.unwrap_or(DVec2::ZERO))
+                        .collect();
+                    (points, closed)
+                })
+                .collect()
+        }
+        _ => anyhow::bail!("unknown shape: {shape}"),
+    };
+    if let Some(name) = seed.keys().next() {
+        anyhow::bail!("unknown setting for a {shape}: {name}");
+    }
+
+    outlines
+        .into_iter()
+        .map(|(points, closed)| {
+            anyhow::ensure!(points.len() >= 2, "a seed needs 2 points");
+            anyhow::ensure!(
+                role != Role::Spine || !closed,
+                "a spine can not be closed"
+            );
+            let mut shape = outline(&points, closed, n_vertices);
+            shape.set_obstacle(role == Role::Obstacle);
+            shape.set_spine(role == Role::Spine);
+            shape.set_weight(weight);
+            shape.set_stiffness(stiffness);
+            Ok(shape)
+        })
+        .collect()
+}
+
+/// A shape along the outline through `points`, with its vertices evenly
+/// spaced so that they all grow alike, `n_vertices` of them if given.
+pub(crate) fn outline(
+    points: &[DVec2],
+    closed: bool,
+    n_vertices: Option<usize>,
+) -> Shape {
+    let mut corners = Shape::starting_at(points[0]);
+    corners.set_closed(closed);
+    for &p in points {
+        corners.next_vertex_at(p - points[0], 0, Pen::DEFAULT);
+    }
+
+    let spacing = match n_vertices {
+        // an open outline ends on a vertex, so has one edge less
+        Some(n) if closed => corners.length() / n as f64,
+        Some(n) => corners.length() / (n - 1) as f64,
+        None => SPACING,
+    };
+    let points = corners.resample(spacing);
+
+    let mut shape = Shape::starting_at(points[0]);
+    shape.set_closed(closed);
+    // a vertex per millisecond, drawn at the same speed throughout
+    for (t, p) in points.iter().enumerate() {
+        shape.next_vertex_at(*p - points[0], t as u32, Pen::DEFAULT);
+    }
+    shape
+}
+
+pub(crate) fn number(value: toml::Value) -> Result<f64> {
+    match value {
+        toml::Value::Integer(n) => Ok(n as f64),
+        toml::Value::Float(n) => Ok(n),
+        value => {
+            anyhow::bail!("expected a number, found {}", value.type_str())
+        }
+    }
+}
+
+pub(crate) fn string(value: toml::Value) -> Result<String> {
+    match value {
+        toml::Value::String(s) => Ok(s),
+        value => {
+            anyhow::bail!("expected a string, found {}", value.type_str())
+        }
+    }
+}
+
+/// A point written as `[x, y]`.
+fn point(value: toml::Value) -> Result<DVec2> {
+    match value {
+        toml::Value::Array(xy) if xy.len() == 2 => {
+            let [x, y] = <[_; 2]>::try_from(xy).unwrap();
+            Ok(DVec2::new(number(x)?, number(y)?))
+        }
+        value => {
+            anyhow::bail!("expected [x, y], found {}", value.type_str())
+        }
+    }
+}
+
+/// The subpaths of SVG path data, each as its points and whether it is
+/// closed, with curves flattened. Arcs are not supported.
+pub(crate) fn parse_path(d: &str) -> Result<Vec<(Vec<DVec2>, bool)>> {
+    let tokens = path_tokens(d)?;
+    let mut tokens = tokens.iter().peekable();
+
+    let mut subpaths = Vec::new();
+    let mut points = Vec::<DVec2>::new();
+    let mut current = DVec2::ZERO;
+    let mut start = DVec2::ZERO;
+    // the control point of the last curve, for smooth curves to mirror
+    let mut last_control = None;
+    let mut command = None;
+
+    let mut finish = |points: &mut Vec<DVec2>, closed| {
+        if points.len() >= 2 {
+            subpaths.push((std::mem::take(points), closed));
+        }
+        points.clear();
+    };
+
+    while let Some(token) = tokens.peek() {
+        let c = match token {
+            PathToken::Command(c) => {
+                tokens.next();
+                *c
+            }
+            // the command repeats for more numbers, a move as a line
+            PathToken::Number(_) => match command {
+                Some('M') => 'L',
+                Some('m') => 'l',
+                Some(c) => c,
+                None => anyhow::bail!("path data must start with a command"),
+            },
+        };
+        command = Some(c);
+
+        let relative = c.is_ascii_lowercase();
+        let mut number = || match tokens.next() {
+            Some(PathToken::Number(n)) => Ok(*n),
+            _ => anyhow::bail!("missing a number for {c}"),
+        };
+        let point = |number: &mut dyn FnMut() -> Result<f64>| {
+            let p = DVec2::new(number()?, number()?);
+            anyhow::Ok(if relative { current + p } else { p })
+        };
+
+        let mut control = None;
+        match c.to_ascii_uppercase() {
+            'M' => {
+                finish(&mut points, false);
+                current = point(&mut number)?;
+                start = current;
+                points.push(current);
+            }
+            'L' => {
+                current = point(&mut number)?;
+                points.push(current);
+            }
+            'H' => {
+                let x = number()?;
+                current.x = if relative { current.x + x } else { x };
+                points.push(current);
+            }
+            'V' => {
+                let y = number()?;
+                current.y = if relative { current.y + y } else { y };
+                points.push(current);
+            }
+            'C' | 'S' => {
+                let c1 = match c.to_ascii_uppercase() {
+                    'C' => point(&mut number)?,
+                    _ => last_control.map_or(current, |k| 2. * current - k),
+                };
+                let c2 = point(&mut number)?;
+                let end = point(&mut number)?;
+                let p0 = current;
+                points.extend((1..=CURVE_STEPS).map(|i| {
+                    let t = i as f64 / CURVE_STEPS as f64;
+                    let u = 1. - t;
+                    u * u * u * p0
+                        + 3. * u * u * t * c1
+                        + 3. * u * t * t * c2
+                        + t * t * t * end
+                }));
+                control = Some(c2);
+                current = end;
+            }
+            'Q' | 'T' => {
+                let c1 = match c.to_ascii_uppercase() {
+                    'Q' => point(&mut number)?,
+                    _ => last_control.map_or(current, |k| 2. * current - k),
+                };
+                let end = point(&mut number)?;
+                let p0 = current;
+                points.extend((1..=CURVE_STEPS).map(|i| {
+                    let t = i as f64 / CURVE_STEPS as f64;
+                    let u = 1. - t;
+                    u * u * p0 + 2. * u * t * c1 + t * t * end
+                }));
+                control = Some(c1);
+                current = end;
+            }
+            'Z' => {
+                // the closing edge is implied, so the start is not repeated
+                if points.len() > 1 && points.last() == Some(&start) {
+                    points.pop();
+                }
+                finish(&mut points, true);
+                current = start;
+                points.push(current);
+            }
+            'A' => anyhow::bail!("arcs are not supported in paths"),
+            _ => anyhow::bail!("unknown path command: {c}"),
+        }
+        last_control = control;
+    }
+    finish(&mut points, false);
+
+    anyhow::ensure!(!subpaths.is_empty(), "empty path");
+    Ok(subpaths)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum PathToken {
+    Command(char),
+    Number(f64),
+}
+
+/// The commands and numbers of SVG path data, which can be separated by
+/// whitespace or commas, or not at all where a number starts with a sign
+/// or a second decimal point.
+fn path_tokens(d: &str) -> Result<Vec<PathToken>> {
+    let mut tokens = Vec::new();
+    let mut chars = d.char_indices().peekable();
+
+    while let Some(&(i, c)) = chars.peek() {
+        if c.is_whitespace() || c == ',' {
+            chars.next();
+        } else if c.is_ascii_alphabetic() && c != 'e' && c != 'E' {
+            tokens.push(PathToken::Command(c));
+            chars.next();
+        } else {
+            let mut end = i;
+            let mut seen_dot = false;
+            let mut seen_exponent = false;
+            while let Some(&(j, c)) = chars.peek() {
+                let accepted = match c {
+                    '0'..='9' => true,
+                    '+' | '-' => {
+                        j == i || matches!(&d[end - 1..end], "e" | "E")
+                    }
+                    '.' => !seen_dot && !seen_exponent,
+                    'e' | 'E' => !seen_exponent && j > i,
+                    _ => false,
+                };
+                if !accepted {
+                    break;
+                }
+                seen_dot |= c == '.';
+                seen_exponent |= matches!(c, 'e' | 'E');
+                end = j + c.len_utf8();
+                chars.next();
+            }
+            let text = &d[i..end];
+            let number = text.parse().with_context(|| {
+                format!("invalid number in path: {text:?}")
+            })?;
+            tokens.push(PathToken::Number(number));
+        }
+    }
+
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seed_files_describe_shapes() {
+        let text = r#"
+            rng-seed = 7
+
+            [[seed]]
+            shape = "circle"
+            center = [400, 300]
+            radius = 50
+            vertices = 40
+            weight = 2
+
+            [[seed]]
+            shape = "polygon"
+            center = [200, 300]
+            radius = 40
+            sides = 4
+            role = "obstacle"
+
+            [[seed]]
+            shape = "line"
+            points = [[100, 500], [700, 500]]
+            role = "spine"
+            stiffness = 0.5
+
+            [[seed]]
+            shape = "path"
+            d = "M0,0 h10 v10 z m20-5 q10 10 20 0"
+            offset = [100, 100]
+        "#;
+        let SeedFile(project) = text.parse().unwrap();
+        assert_eq!(project.seed, Some(7));
+
+        let [circle, square, spine, path, curve] = &project.shapes[..] else {
+            panic!("expected 5 shapes, found {}", project.shapes.len());
+        };
+        assert_eq!(circle.n_verticies(), 40);
+        assert!(circle.closed());
+        assert_eq!(circle.weight(), 2.);
+        assert!(
+            circle
+                .points()
+                .all(|p| (p.distance(DVec2::new(400., 300.)) - 50.).abs()
+                    < 1e-9)
+        );
+        assert!(circle.speed_weights().iter().all(|w| (w - 1.).abs() < 1e-6));
+
+        assert!(square.obstacle() && square.closed());
+        // the first corner at the top
+        assert!(square.start().abs_diff_eq(DVec2::new(200., 260.), 1e-9));
+
+        assert!(spine.spine() && !spine.closed());
+        assert_eq!(spine.stiffness(), Some(0.5));
+        assert_eq!(spine.n_verticies(), 121);
+
+        assert!(path.closed());
+        assert_eq!(path.start(), DVec2::new(100., 100.));
+        assert!((path.length() - 34.14).abs() < 1.);
+        assert!(!curve.closed());
+        assert_eq!(curve.start(), DVec2::new(120., 95.));
+        assert!(
+            curve
+                .points()
+                .last()
+                .unwrap()
+                .abs_diff_eq(DVec2::new(140., 95.), 1e-9)
+        );
+
+        for (text, error) in [
+            ("[[seed]]\nshape = \"star\"", "unknown shape: star"),
+            (
+                "[[seed]]\nshape = \"circle\"\ncenter = [0, 0]",
+                "missing radius",
+            ),
+            (
+                "[[seed]]\nshape = \"line\"\npoints = [[0, 0], [1, 1]]\n\
+                 closed = true\nrole = \"spine\"",
+                "a spine can not be closed",
+            ),
+            (
+                "[[seed]]\nshape = \"path\"\nd = \"M0 0 A 1 1 0 0 1 2 2\"",
+                "arcs are not supported in paths",
+            ),
+            ("colour = 1", "unknown setting: colour"),
+        ] {
+            let err = text.parse::<SeedFile>().err().unwrap();
+            assert!(format!("{err:#}").contains(error), "{err:#}");
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use gtk::{glib, prelude::*};
+
+use super::{
+    algorithm::{ONE, Primitive, PrimitiveKind},
+    app_state::SharedState,
+    params_panel::PARAMS,
+    replay::Event,
+    shape::smooth::SMOOTH_OPTIONS,
+    transform::{DOCUMENT_SIZE, Transform},
+    window::show_simulation_error,
+};
+
+const KINDS: &[&str] = &["Circle", "Line", "Polygon"];
+
+/// A labeled spin button in row `row` of `grid`.
+pub(crate) fn spin(
+    grid: &gtk::Grid,
+    row: i32,
+    label: &str,
+    range: (f64, f64, f64),
+    value: f64,
+) -> gtk::SpinButton {
+    let (min, max, step) = range;
+
+    grid.attach(
+        &gtk::Label::builder().label(label).xalign(0.).build(),
+        0,
+        row,
+        1,
+        1,
+    );
+
+    let spin = gtk::SpinButton::with_range(min, max, step);
+    spin.set_value(value);
+    grid.attach(&spin, 1, row, 1, 1);
+
+    spin
+}
+
+/// Button with a popover that adds a circle, line, or regular polygon seed
+/// or obstacle at the center of the canvas, or the crossing of the grid
+/// nearest to it if snapping to the grid, restarting the simulation with
+/// it.
+///
+/// The radius is in units of [`ONE`], like the distances of the params
+/// panel. The simulation is seeded with the shapes of `state` too.
+pub(crate) fn build(state: &SharedState) -> gtk::MenuButton {
+    let grid = gtk::Grid::builder()
+        .row_spacing(4)
+        .column_spacing(8)
+        .build();
+
+    grid.attach(
+        &gtk::Label::builder().label("Shape").xalign(0.).build(),
+        0,
+        0,
+        1,
+        1,
+    );
+    let kind = gtk::DropDown::from_strings(KINDS);
+    grid.attach(&kind, 1, 0, 1, 1);
+
+    let radius = spin(&grid, 1, "Radius", (10., 500., 10.), 100.);
+    let n_vertices = spin(&grid, 2, "Vertices", (3., 2000., 1.), 60.);
+    let sides = spin(&grid, 3, "Sides", (3., 24., 1.), 6.);
+
+    let passive = gtk::CheckButton::with_label("Obstacle");
+    grid.attach(&passive, 0, 4, 2, 1);
+
+    // only polygons have sides
+    sides.set_sensitive(false);
+    kind.connect_selected_notify(glib::clone!(
+        #[weak]
+        sides,
+        move |kind| sides.set_sensitive(kind.selected() == 2)
+    ));
+
+    let add = gtk::Button::with_label("Add seed");
+    grid.attach(&add, 0, 5, 2, 1);
+
+    let popover = gtk::Popover::builder().child(&grid).build();
+
+    add.connect_clicked(glib::clone!(
+        #[weak]
+        popover,
+        #[strong]
+        state,
+        move |add| {
+            let mut state = state.borrow_mut();
+            let center = state.snap().point(DOCUMENT_SIZE / 2.);
+            let primitive = Primitive {
+                kind: match kind.selected() {
+                    0 => PrimitiveKind::Circle,
+                    1 => PrimitiveKind::Line,
+                    _ => PrimitiveKind::Polygon {
+                        sides: sides.value_as_int() as usize,
+                    },
+                },
+                center: Transform::DEFAULT.document_to_unit(center),
+                radius: radius.value() * ONE,
+                rotation: 0.,
+                n_vertices: n_vertices.value_as_int() as usize,
+                passive: passive.is_active(),
+            };
+
+            let params = *PARAMS.read().unwrap();
+            match state.add_primitive(primitive, &params) {
+                Ok(()) => {
+                    let smooth = *SMOOTH_OPTIONS.read().unwrap();
+                    state.log(Event::Primitive(primitive), &params, smooth);
+                }
+                Err(err) => {
+                    let window = add.root().and_downcast::<gtk::Window>();
+                    show_simulation_error(window.as_ref(), err);
+                }
+            }
+
+            popover.popdown();
+        }
+    ));
+
+    gtk::MenuButton::builder()
+        .label("Seed")
+        .popover(&popover)
+        .build()
+}
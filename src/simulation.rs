@@ -0,0 +1,1179 @@
+#[cfg(feature = "gui")]
+use std::sync::RwLock;
+use std::{
+    fmt,
+    ops::ControlFlow,
+    time::{Duration, Instant},
+};
+
+#[cfg(any(feature = "gui", test))]
+use super::algorithm::SegmentPath;
+use super::{
+    algorithm::{
+        Domain, Field, Params, Positions, Primitive, SegmentsError,
+        Simulation, Symmetry,
+    },
+    polyline::Polyline,
+    shape::Shape,
+    transform::{DOCUMENT_SIZE, Transform},
+};
+
+/// Whether the simulation advances on its own.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum RunState {
+    Running,
+    Paused,
+    /// The simulation can not continue, only a reset starts it again.
+    Finished,
+}
+
+/// How fast a running simulation steps.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Speed {
+    /// Steps per second, however often the window is drawn. The growth is
+    /// shown moving from one step to the next in between them, unless
+    /// turned off with [`SimulationController::set_interpolate`].
+    PerSecond(f64),
+    /// As many steps as the window has time for.
+    #[cfg(any(feature = "gui", test))]
+    Unlimited,
+}
+
+impl Speed {
+    /// The speeds to pick from, slowest first.
+    #[cfg(any(feature = "gui", test))]
+    pub(crate) const LEVELS: [Self; 10] = [
+        Self::PerSecond(1.),
+        Self::PerSecond(2.),
+        Self::PerSecond(5.),
+        Self::PerSecond(10.),
+        Self::PerSecond(20.),
+        Self::PerSecond(30.),
+        Self::PerSecond(60.),
+        Self::PerSecond(120.),
+        Self::PerSecond(240.),
+        Self::Unlimited,
+    ];
+
+    /// About a step for every frame of the window.
+    pub(crate) const DEFAULT: Self = Self::PerSecond(60.);
+
+    /// Index of this speed in [`Speed::LEVELS`], or of the closest one
+    /// faster than it.
+    #[cfg(any(feature = "gui", test))]
+    pub(crate) fn level(self) -> usize {
+        Self::LEVELS
+            .iter()
+            .position(|&level| match (self, level) {
+                (_, Self::Unlimited) => true,
+                (Self::PerSecond(rate), Self::PerSecond(level)) => {
+                    rate <= level
+                }
+                (Self::Unlimited, Self::PerSecond(_)) => false,
+            })
+            .unwrap_or(Self::LEVELS.len() - 1)
+    }
+}
+
+impl fmt::Display for Speed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::PerSecond(1.) => write!(f, "1 step/s"),
+            Self::PerSecond(rate) => write!(f, "{rate} steps/s"),
+            #[cfg(any(feature = "gui", test))]
+            Self::Unlimited => write!(f, "unlimited"),
+        }
+    }
+}
+
+/// Conditions that finish a running simulation before it can not go on, so
+/// that long runs end on their own. Each is off if `None`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct StopConditions {
+    pub(crate) max_iterations: Option<u64>,
+    pub(crate) max_vertices: Option<usize>,
+    /// Time since the simulation first stepped.
+    pub(crate) time_limit: Option<Duration>,
+    /// Growth has stalled once the vertices move less than this far in a
+    /// step on average.
+    pub(crate) stall: Option<f64>,
+}
+
+impl StopConditions {
+    pub(crate) const NONE: Self = Self {
+        max_iterations: None,
+        max_vertices: None,
+        time_limit: None,
+        stall: None,
+    };
+
+    /// Why `simulation` should stop at `iteration`, having stepped since
+    /// `started`, if it should.
+    fn reason(
+        &self,
+        simulation: &Simulation,
+        iteration: u64,
+        started: Instant,
+    ) -> Option<StopReason> {
+        if self.max_iterations.is_some_and(|max| iteration >= max) {
+            Some(StopReason::Iterations)
+        } else if self
+            .max_vertices
+            .is_some_and(|max| simulation.vertex_count() >= max)
+        {
+            Some(StopReason::Vertices)
+        } else if self.time_limit.is_some_and(|max| started.elapsed() >= max) {
+            Some(StopReason::TimeLimit)
+        } else if self
+            .stall
+            .is_some_and(|min| simulation.mean_displacement() < min)
+        {
+            Some(StopReason::Stalled)
+        } else {
+            None
+        }
+    }
+}
+
+/// The stop conditions of the window, from the command line and the
+/// parameters panel.
+#[cfg(feature = "gui")]
+pub(crate) static STOP_CONDITIONS: RwLock<StopConditions> =
+    RwLock::new(StopConditions::NONE);
+
+/// Which of the [`StopConditions`] finished a simulation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum StopReason {
+    Iterations,
+    Vertices,
+    TimeLimit,
+    Stalled,
+    /// A hook of [`Hooks::on_step`] broke.
+    Hook,
+}
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Iterations => write!(f, "iteration limit"),
+            Self::Vertices => write!(f, "vertex limit"),
+            Self::TimeLimit => write!(f, "time limit"),
+            Self::Stalled => write!(f, "growth stalled"),
+            Self::Hook => write!(f, "hook"),
+        }
+    }
+}
+
+/// Callbacks run as the simulation steps, for logging, stopping on rules of
+/// their own, or showing the growth elsewhere without another stepping
+/// loop. Hooks of each kind run in the order they were added.
+#[derive(Default)]
+pub(crate) struct Hooks {
+    on_step: Vec<StepHook>,
+    on_spawn: Vec<CountHook>,
+    on_collapse: Vec<CountHook>,
+    on_stop: Vec<StopHook>,
+}
+
+type StepHook = Box<dyn FnMut(&Simulation, u64) -> ControlFlow<()>>;
+type CountHook = Box<dyn FnMut(u64, usize)>;
+type StopHook = Box<dyn FnMut(u64, Option<StopReason>)>;
+
+impl Hooks {
+    pub(crate) const fn new() -> Self {
+        Self {
+            on_step: Vec::new(),
+            on_spawn: Vec::new(),
+            on_collapse: Vec::new(),
+            on_stop: Vec::new(),
+        }
+    }
+
+    /// Call `hook` with the simulation and the iteration after every step
+    /// that advances it. The run stops with [`StopReason::Hook`] if it
+    /// breaks.
+    #[cfg(any(feature = "script", test))]
+    pub(crate) fn on_step(
+        &mut self,
+        hook: impl FnMut(&Simulation, u64) -> ControlFlow<()> + 'static,
+    ) -> &mut Self {
+        self.on_step.push(Box::new(hook));
+        self
+    }
+
+    /// Call `hook` with the iteration and the number of vertices added after
+    /// every step that adds any, by splitting edges or sprouting branches.
+    #[cfg(any(feature = "script", test))]
+    pub(crate) fn on_spawn(
+        &mut self,
+        hook: impl FnMut(u64, usize) + 'static,
+    ) -> &mut Self {
+        self.on_spawn.push(Box::new(hook));
+        self
+    }
+
+    /// Call `hook` with the iteration and the number of vertices deleted
+    /// after every step that deletes any.
+    #[cfg(any(feature = "script", test))]
+    pub(crate) fn on_collapse(
+        &mut self,
+        hook: impl FnMut(u64, usize) + 'static,
+    ) -> &mut Self {
+        self.on_collapse.push(Box::new(hook));
+        self
+    }
+
+    /// Call `hook` with the iteration once the run finishes, and with the
+    /// stop condition that finished it, or `None` if the simulation could
+    /// not go on.
+    #[cfg(any(feature = "script", test))]
+    pub(crate) fn on_stop(
+        &mut self,
+        hook: impl FnMut(u64, Option<StopReason>) + 'static,
+    ) -> &mut Self {
+        self.on_stop.push(Box::new(hook));
+        self
+    }
+
+    /// Whether any hooks are told how many vertices a step added or
+    /// deleted, which takes counting them.
+    fn counts_vertices(&self) -> bool {
+        !self.on_spawn.is_empty() || !self.on_collapse.is_empty()
+    }
+}
+
+/// Steps between the checkpoints of a rewindable simulation.
+const HISTORY_INTERVAL: u64 = 100;
+
+/// Checkpoints kept of a rewindable simulation, the oldest dropped first,
+/// so that it can be rewound by up to this many intervals.
+const HISTORY_CAPACITY: usize = 10;
+
+/// Steps the simulation goes back by when rewound from the window.
+#[cfg(any(feature = "gui", test))]
+pub(crate) const REWIND_STEPS: u64 = 100;
+
+/// Owns the simulation seeded from the drawn shapes and primitives, its run
+/// state, and the number of steps taken.
+pub(crate) struct SimulationController {
+    simulation: Option<Simulation>,
+    /// Seeds added from the seed menu, in addition to the drawn shapes.
+    primitives: Vec<Primitive>,
+    /// Forces placed on the growth.
+    fields: Vec<Field>,
+    /// Symmetry the growth is kept to about the center of the canvas.
+    symmetry: Option<Symmetry>,
+    /// Region every new simulation grows in.
+    domain: Domain,
+    state: RunState,
+    iteration: u64,
+    /// Seed for every new simulation, or a random one each time if `None`.
+    seed: Option<u64>,
+    /// Counts the changes to the simulation, so that what is drawn from it
+    /// can be kept until it changes again.
+    generation: u64,
+    speed: Speed,
+    /// Steps due but not taken yet, of which the fraction of the next one
+    /// has passed.
+    due: f64,
+    stop: StopConditions,
+    /// When the simulation first stepped.
+    started: Option<Instant>,
+    /// Which stop condition finished the simulation, if one did.
+    stop_reason: Option<StopReason>,
+    /// Whether the growth is shown in between steps at a steady speed.
+    interpolate: bool,
+    /// Positions of the vertices before the last step while the growth is
+    /// shown in between steps.
+    previous: Option<Positions>,
+    hooks: Hooks,
+    /// Whether every new simulation keeps checkpoints to be rewound to,
+    /// which take memory.
+    rewindable: bool,
+}
+
+/// Seed a segment of `simulation` from `shape`, along it if it is an open
+/// spine.
+fn add_seed_shape(
+    simulation: &mut Simulation,
+    shape: &Shape,
+) -> Result<(), SegmentsError> {
+    let xys = shape
+        .points()
+        .map(|pos| Transform::DEFAULT.document_to_unit(pos))
+        .collect::<Vec<_>>();
+    let weights = shape
+        .speed_weights()
+        .into_iter()
+        .map(|w| w * shape.weight())
+        .collect::<Vec<_>>();
+    let segment = simulation.segment_count();
+    if shape.spine() && !shape.is_closed() {
+        simulation.add_spine(&xys, &weights)?;
+    } else {
+        simulation.add_seed(&xys, &weights, shape.is_closed())?;
+    }
+    if shape.stiffness().is_some() {
+        simulation.set_segment_stiffness(segment, shape.stiffness());
+    }
+    Ok(())
+}
+
+/// Center of the symmetry the growth is kept to, the center of the canvas.
+fn symmetry_center() -> glam::DVec2 {
+    Transform::DEFAULT.document_to_unit(DOCUMENT_SIZE / 2.)
+}
+
+impl SimulationController {
+    pub(crate) const fn new() -> Self {
+        Self {
+            simulation: None,
+            primitives: Vec::new(),
+            fields: Vec::new(),
+            symmetry: None,
+            domain: Domain::Square,
+            state: RunState::Paused,
+            iteration: 0,
+            seed: None,
+            generation: 0,
+            speed: Speed::DEFAULT,
+            due: 0.,
+            stop: StopConditions::NONE,
+            started: None,
+            stop_reason: None,
+            interpolate: true,
+            previous: None,
+            hooks: Hooks::new(),
+            rewindable: false,
+        }
+    }
+
+    /// Like [`Self::new`], with every new simulation keeping checkpoints
+    /// to be rewound to with [`Self::rewind`].
+    #[cfg(any(feature = "gui", test))]
+    pub(crate) fn rewindable() -> Self {
+        Self {
+            rewindable: true,
+            ..Self::new()
+        }
+    }
+
+    #[cfg(feature = "gui")]
+    pub(crate) fn speed(&self) -> Speed {
+        self.speed
+    }
+
+    #[cfg(any(feature = "gui", test))]
+    pub(crate) fn set_speed(&mut self, speed: Speed) {
+        self.speed = speed;
+        if !self.interpolated() {
+            self.previous = None;
+        }
+        self.generation += 1;
+    }
+
+    /// Show the growth moving from one step to the next in between them
+    /// while running at a steady speed, rather than only as it is after
+    /// each step.
+    #[cfg(any(feature = "gui", test))]
+    pub(crate) fn set_interpolate(&mut self, interpolate: bool) {
+        self.interpolate = interpolate;
+        if !self.interpolated() {
+            self.previous = None;
+        }
+        self.generation += 1;
+    }
+
+    /// Whether the growth is shown in between steps.
+    fn interpolated(&self) -> bool {
+        self.interpolate && matches!(self.speed, Speed::PerSecond(_))
+    }
+
+    #[cfg(any(feature = "gui", test))]
+    pub(crate) fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    pub(crate) fn set_seed(&mut self, seed: Option<u64>) {
+        self.seed = seed;
+    }
+
+    /// Set the conditions that finish the simulation, checked after every
+    /// step from the next one.
+    pub(crate) fn set_stop_conditions(&mut self, stop: StopConditions) {
+        self.stop = stop;
+    }
+
+    /// Which stop condition finished the simulation, if one did.
+    #[cfg(any(feature = "gui", test))]
+    pub(crate) fn stop_reason(&self) -> Option<StopReason> {
+        self.stop_reason
+    }
+
+    /// Act on the simulation with the field of the cursor on every step
+    /// until it is set again, if there is a simulation.
+    #[cfg(feature = "gui")]
+    pub(crate) fn set_cursor_field(&mut self, field: Option<Field>) {
+        if let Some(simulation) = &mut self.simulation {
+            simulation.set_cursor_field(field);
+        }
+    }
+
+    #[cfg(any(feature = "gui", test))]
+    pub(crate) fn fields(&self) -> &[Field] {
+        &self.fields
+    }
+
+    /// Replace the fields acting on the simulation, which takes them from
+    /// its next step on, and on every new simulation.
+    pub(crate) fn set_fields(&mut self, fields: Vec<Field>) {
+        if let Some(simulation) = &mut self.simulation {
+            simulation.set_fields(fields.clone());
+        }
+        self.fields = fields;
+        self.generation += 1;
+    }
+
+    /// Anchor the growth of the simulation within `reach` of `stroke` onto
+    /// it, both in document coordinates, if there is a simulation. Returns
+    /// how many vertices were anchored.
+    #[cfg(any(feature = "gui", test))]
+    pub(crate) fn anchor(&mut self, stroke: &Shape, reach: f64) -> usize {
+        let Some(simulation) = &mut self.simulation else {
+            return 0;
+        };
+        let transform = Transform::DEFAULT;
+        let xys = stroke
+            .points()
+            .map(|pos| transform.document_to_unit(pos))
+            .collect::<Vec<_>>();
+        let reach = transform.document_to_unit_length(reach);
+        let anchored = simulation.anchor_to_polyline(&xys, reach);
+        self.generation += 1;
+        anchored
+    }
+
+    #[cfg(any(feature = "gui", test))]
+    pub(crate) fn symmetry(&self) -> Option<Symmetry> {
+        self.symmetry
+    }
+
+    /// Keep the growth of the simulation, from its next step on, and of
+    /// every new simulation to `symmetry`, or with `None` let it grow
+    /// freely.
+    #[cfg(any(feature = "gui", test))]
+    pub(crate) fn set_symmetry(&mut self, symmetry: Option<Symmetry>) {
+        if let Some(simulation) = &mut self.simulation {
+            simulation.set_symmetry(symmetry, symmetry_center());
+        }
+        self.symmetry = symmetry;
+    }
+
+    /// The hooks run as the simulation steps, kept from one simulation to
+    /// the next.
+    #[cfg(any(feature = "script", test))]
+    pub(crate) fn hooks_mut(&mut self) -> &mut Hooks {
+        &mut self.hooks
+    }
+
+    #[cfg(any(feature = "gui", test))]
+    pub(crate) fn domain(&self) -> &Domain {
+        &self.domain
+    }
+
+    /// Set the domain of the next simulation. The current one keeps its
+    /// domain until it is reset.
+    pub(crate) fn set_domain(&mut self, domain: Domain) {
+        self.domain = domain;
+    }
+
+    pub(crate) fn simulation(&self) -> Option<&Simulation> {
+        self.simulation.as_ref()
+    }
+
+    pub(crate) fn state(&self) -> RunState {
+        self.state
+    }
+
+    pub(crate) fn iteration(&self) -> u64 {
+        self.iteration
+    }
+
+    #[cfg(feature = "gui")]
+    pub(crate) fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Add a primitive seed and restart the simulation from `shapes` and
+    /// every primitive, so that the new seed shows up right away.
+    #[cfg(any(feature = "gui", test))]
+    pub(crate) fn add_primitive(
+        &mut self,
+        primitive: Primitive,
+        shapes: &[Shape],
+        params: &Params,
+    ) -> Result<(), SegmentsError> {
+        self.primitives.push(primitive);
+        self.reset(shapes, params)
+    }
+
+    #[cfg(any(feature = "gui", test))]
+    pub(crate) fn primitives(&self) -> &[Primitive] {
+        &self.primitives
+    }
+
+    /// Replace the primitive seeds of the next simulation.
+    pub(crate) fn set_primitives(&mut self, primitives: Vec<Primitive>) {
+        self.primitives = primitives;
+    }
+
+    #[cfg(any(feature = "gui", test))]
+    pub(crate) fn clear_primitives(&mut self) {
+        self.primitives.clear();
+    }
+
+    /// Discard the simulation, paused at iteration 0.
+    pub(crate) fn clear(&mut self) {
+        self.simulation = None;
+        self.state = RunState::Paused;
+        self.iteration = 0;
+        self.generation += 1;
+        self.due = 0.;
+        self.started = None;
+        self.stop_reason = None;
+        self.previous = None;
+    }
+
+    /// Replace the simulation with a new one seeded from `shapes` and the
+    /// primitives, paused at iteration 0. Seed shapes with fewer than 3
+    /// vertices are skipped. Obstacle shapes are added first, resampled so
+    /// that the line can not pass between their vertices. Open spine shapes
+    /// seed growth that stays near them.
+    pub(crate) fn reset(
+        &mut self,
+        shapes: &[Shape],
+        params: &Params,
+    ) -> Result<(), SegmentsError> {
+        self.clear();
+
+        let seed = self.seed.unwrap_or_else(rand::random);
+        tracing::info!("seeding simulation with --seed {seed}");
+        let simulation = self.seeded(shapes, params, seed)?;
+        self.simulation = Some(self.with_history(simulation));
+        Ok(())
+    }
+
+    /// Replace the simulation with one of `paths`, taken from another
+    /// simulation after `iteration` steps, paused there. The paths are put
+    /// back where they are, to grow on with the fields and symmetry of this
+    /// controller.
+    #[cfg(any(feature = "gui", test))]
+    pub(crate) fn restore(
+        &mut self,
+        paths: &[SegmentPath],
+        iteration: u64,
+        params: &Params,
+    ) -> Result<(), SegmentsError> {
+        self.clear();
+
+        let seed = self.seed.unwrap_or_else(rand::random);
+        let mut simulation =
+            Simulation::with_domain(params, seed, self.domain.clone())?;
+        for path in paths {
+            simulation.add_path(path)?;
+        }
+        simulation.set_fields(self.fields.clone());
+        simulation.set_symmetry(self.symmetry, symmetry_center());
+        self.simulation = Some(self.with_history(simulation));
+        self.iteration = iteration;
+        Ok(())
+    }
+
+    /// A new simulation seeded from `shapes` and the primitives like
+    /// [`Self::reset`] does, with `seed` for its RNG, leaving the
+    /// simulation of the controller as it is.
+    pub(crate) fn seeded(
+        &self,
+        shapes: &[Shape],
+        params: &Params,
+        seed: u64,
+    ) -> Result<Simulation, SegmentsError> {
+        let mut simulation =
+            Simulation::with_domain(params, seed, self.domain.clone())?;
+        for shape in shapes.iter().filter(|s| s.obstacle()) {
+            // spaced like the growing line, so that it can not slip between
+            // the vertices
+            let spacing =
+                Transform::DEFAULT.unit_to_document_length(params.near_l);
+            let xys = shape
+                .resample(spacing)
+                .into_iter()
+                .map(|pos| Transform::DEFAULT.document_to_unit(pos))
+                .collect::<Vec<_>>();
+            if xys.len() >= 2 {
+                simulation.add_obstacle(&xys, shape.is_closed())?;
+            }
+        }
+        for shape in shapes
+            .iter()
+            .filter(|s| !s.obstacle() && s.verticies().count() >= 3)
+        {
+            add_seed_shape(&mut simulation, shape)?;
+        }
+        for primitive in &self.primitives {
+            simulation.add_primitive(primitive)?;
+        }
+        simulation.set_fields(self.fields.clone());
+        simulation.set_symmetry(self.symmetry, symmetry_center());
+        Ok(simulation)
+    }
+
+    /// Seed a new segment of the simulation from `shape` and run it, or
+    /// start a new simulation from `shapes`, which `shape` is one of, if
+    /// there is none.
+    #[cfg(any(feature = "gui", test))]
+    pub(crate) fn grow_shape(
+        &mut self,
+        shape: &Shape,
+        shapes: &[Shape],
+        params: &Params,
+    ) -> Result<(), SegmentsError> {
+        match &mut self.simulation {
+            Some(simulation) => {
+                add_seed_shape(simulation, shape)?;
+                self.generation += 1;
+                self.previous = None;
+            }
+            None => self.reset(shapes, params)?,
+        }
+        self.stop_reason = None;
+        self.state = RunState::Running;
+        Ok(())
+    }
+
+    /// Run `simulation`, built elsewhere, from iteration 0. A reset starts
+    /// over from the shapes and primitives as usual.
+    pub(crate) fn run(&mut self, simulation: Simulation) {
+        self.run_from(simulation, 0);
+    }
+
+    /// Run `simulation`, grown elsewhere for `iteration` steps, on from
+    /// there.
+    pub(crate) fn run_from(&mut self, simulation: Simulation, iteration: u64) {
+        self.clear();
+        self.simulation = Some(self.with_history(simulation));
+        self.iteration = iteration;
+        self.state = RunState::Running;
+    }
+
+    /// `simulation`, keeping checkpoints to be rewound to if this is
+    /// rewindable.
+    fn with_history(&self, mut simulation: Simulation) -> Simulation {
+        if self.rewindable {
+            simulation.keep_history(HISTORY_INTERVAL, HISTORY_CAPACITY);
+        }
+        simulation
+    }
+
+    /// Go back `n` steps of the simulation, or as far as its checkpoints
+    /// allow, paused there to grow on, with other parameters maybe. The
+    /// simulation grows the same again for the same parameters, but with
+    /// the fields and symmetry of the controller as they are now.
+    #[cfg(any(feature = "gui", test))]
+    pub(crate) fn rewind(
+        &mut self,
+        n: u64,
+        params: &Params,
+    ) -> Result<(), SegmentsError> {
+        let Some(simulation) = &mut self.simulation else {
+            return Ok(());
+        };
+        let back = simulation.rewind(n, params)?;
+        if back == 0 {
+            return Ok(());
+        }
+        simulation.set_fields(self.fields.clone());
+        simulation.set_symmetry(self.symmetry, symmetry_center());
+        self.iteration = self.iteration.saturating_sub(back);
+        self.generation += 1;
+        self.state = RunState::Paused;
+        self.stop_reason = None;
+        self.previous = None;
+        Ok(())
+    }
+
+    /// Pause a running simulation or resume a paused one, starting a new
+    /// simulation from `shapes` if there is none yet. A simulation finished
+    /// by a stop condition resumes too, to stop again after the next step
+    /// unless the conditions have changed.
+    pub(crate) fn toggle_pause(
+        &mut self,
+        shapes: &[Shape],
+        params: &Params,
+    ) -> Result<(), SegmentsError> {
+        match self.state {
+            RunState::Running => self.state = RunState::Paused,
+            RunState::Paused => {
+                if self.simulation.is_none() {
+                    self.reset(shapes, params)?;
+                }
+                self.state = RunState::Running;
+            }
+            RunState::Finished => {
+                if self.stop_reason.take().is_some() {
+                    self.state = RunState::Running;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Take a single step if paused.
+    #[cfg(any(feature = "gui", test))]
+    pub(crate) fn step_once(
+        &mut self,
+        params: &Params,
+    ) -> Result<(), SegmentsError> {
+        if self.state == RunState::Paused {
+            self.step(params)?;
+        }
+        Ok(())
+    }
+
+    /// The number of steps a running simulation is to take for `elapsed`
+    /// time at its speed, or [`u64::MAX`] if unlimited, for as many as
+    /// there is time for.
+    ///
+    /// The time left over is kept for the next call, so that the simulation
+    /// steps at its speed however often this is called, and the growth
+    /// shown by [`Self::shown_paths`] moves on with it in between steps.
+    #[cfg(any(feature = "gui", test))]
+    pub(crate) fn steps_due(&mut self, elapsed: Duration) -> u64 {
+        if self.state != RunState::Running {
+            return 0;
+        }
+        let Speed::PerSecond(rate) = self.speed else {
+            return u64::MAX;
+        };
+
+        self.due += elapsed.as_secs_f64() * rate;
+        let steps = self.due.floor();
+        self.due -= steps;
+        if self.previous.is_some() {
+            self.generation += 1;
+        }
+        steps as u64
+    }
+
+    /// Every path of the simulation as shown, in between the last step and
+    /// the next while running at a steady speed, unless turned off.
+    #[cfg(any(feature = "gui", test))]
+    pub(crate) fn shown_paths(&self) -> Vec<SegmentPath> {
+        match (&self.simulation, &self.previous) {
+            (Some(simulation), Some(previous))
+                if self.state == RunState::Running =>
+            {
+                simulation.paths_between(previous, self.due)
+            }
+            (Some(simulation), _) => simulation.paths(),
+            (None, _) => Vec::new(),
+        }
+    }
+
+    /// Take a step if running. Returns whether a step was taken.
+    pub(crate) fn tick(
+        &mut self,
+        params: &Params,
+    ) -> Result<bool, SegmentsError> {
+        if self.state != RunState::Running {
+            return Ok(false);
+        }
+        self.step(params)?;
+        Ok(true)
+    }
+
+    fn step(&mut self, params: &Params) -> Result<(), SegmentsError> {
+        let interpolated = self.interpolated();
+        let Some(simulation) = &mut self.simulation else {
+            return Ok(());
+        };
+        self.previous = interpolated.then(|| simulation.positions());
+        let started = *self.started.get_or_insert_with(Instant::now);
+        let counts = self
+            .hooks
+            .counts_vertices()
+            .then(|| (simulation.vertices_added(), simulation.vertex_count()));
+
+        let result = simulation.step(params);
+        if let Ok(true) = result {
+            self.iteration += 1;
+            self.generation += 1;
+            if let Some((added, count)) = counts {
+                // vertices added by the step may have been deleted by it too
+                let spawned = simulation.vertices_added() - added;
+                let deleted = count + spawned - simulation.vertex_count();
+                if spawned > 0 {
+                    for hook in &mut self.hooks.on_spawn {
+                        hook(self.iteration, spawned);
+                    }
+                }
+                if deleted > 0 {
+                    for hook in &mut self.hooks.on_collapse {
+                        hook(self.iteration, deleted);
+                    }
+                }
+            }
+
+            // every hook runs, even once one has broken
+            let mut broke = false;
+            for hook in &mut self.hooks.on_step {
+                broke |= hook(simulation, self.iteration).is_break();
+            }
+            self.stop_reason = if broke {
+                Some(StopReason::Hook)
+            } else {
+                self.stop.reason(simulation, self.iteration, started)
+            };
+            if let Some(reason) = self.stop_reason {
+                tracing::info!(
+                    "simulation stopped at iteration {}: {reason}",
+                    self.iteration
+                );
+            }
+        }
+
+        if !matches!(result, Ok(true)) || self.stop_reason.is_some() {
+            self.state = RunState::Finished;
+            for hook in &mut self.hooks.on_stop {
+                hook(self.iteration, self.stop_reason);
+            }
+        }
+        result.map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::DVec2;
+
+    use super::*;
+    use crate::{
+        algorithm::{Primitive, PrimitiveKind},
+        shape::Pen,
+    };
+
+    /// A closed stroke around a circle, with vertices a few pixels apart.
+    fn circle() -> Shape {
+        let mut shape = Shape::starting_at(DVec2::new(400., 300.));
+        for i in 0..64 {
+            let angle = i as f64 / 64. * std::f64::consts::TAU;
+            let offset = DVec2::from_angle(angle) * 40.;
+            shape.next_vertex_at(offset, i * 10, Pen::DEFAULT);
+        }
+        shape.set_closed(true);
+        shape
+    }
+
+    #[test]
+    fn steps_are_due_at_the_speed() {
+        let mut controller = SimulationController::new();
+        let quarter = Duration::from_millis(250);
+        controller.set_speed(Speed::PerSecond(8.));
+        assert_eq!(controller.steps_due(quarter), 0, "paused");
+
+        let params = Params::DEFAULT;
+        controller.toggle_pause(&[circle()], &params).unwrap();
+        assert_eq!(controller.steps_due(quarter), 2);
+        controller.set_speed(Speed::PerSecond(2.));
+        assert_eq!(controller.steps_due(quarter), 0);
+        assert_eq!(controller.steps_due(quarter), 1);
+        controller.set_speed(Speed::Unlimited);
+        assert_eq!(controller.steps_due(quarter), u64::MAX);
+    }
+
+    #[test]
+    fn slow_growth_is_shown_in_between_steps() {
+        let params = Params::DEFAULT;
+        let mut controller = SimulationController::new();
+        controller.set_speed(Speed::PerSecond(2.));
+        controller.toggle_pause(&[circle()], &params).unwrap();
+        // past the first steps, which split up the long edges of the seed
+        for _ in 0..10 {
+            controller.tick(&params).unwrap();
+        }
+        let positions = controller.simulation().unwrap().positions();
+
+        controller.tick(&params).unwrap();
+        let simulation = controller.simulation().unwrap();
+        // no time has passed since the step
+        assert_eq!(
+            controller.shown_paths(),
+            simulation.paths_between(&positions, 0.)
+        );
+        assert_eq!(
+            simulation.paths_between(&positions, 1.),
+            simulation.paths()
+        );
+
+        controller.steps_due(Duration::from_millis(250));
+        let simulation = controller.simulation().unwrap();
+        let halfway = simulation.paths_between(&positions, 0.5);
+        assert_eq!(controller.shown_paths(), halfway);
+        assert_ne!(halfway, simulation.paths());
+
+        // or as it is after the step without interpolation
+        let paths = simulation.paths();
+        controller.set_interpolate(false);
+        assert_eq!(controller.shown_paths(), paths);
+        controller.set_interpolate(true);
+        controller.tick(&params).unwrap();
+        controller.steps_due(Duration::from_millis(250));
+        let simulation = controller.simulation().unwrap();
+        assert_ne!(controller.shown_paths(), simulation.paths());
+
+        // the growth is shown as it is once paused
+        controller.toggle_pause(&[], &params).unwrap();
+        let simulation = controller.simulation().unwrap();
+        assert_eq!(controller.shown_paths(), simulation.paths());
+    }
+
+    #[test]
+    fn rewound_simulations_grow_the_same_again() {
+        let params = Params::DEFAULT;
+        let mut controller = SimulationController::rewindable();
+        controller.set_seed(Some(1));
+        controller.toggle_pause(&[circle()], &params).unwrap();
+        let mut paths = Vec::new();
+        for _ in 0..250 {
+            controller.tick(&params).unwrap();
+            paths.push(controller.simulation().unwrap().paths());
+        }
+
+        controller.rewind(80, &params).unwrap();
+        assert_eq!(controller.iteration(), 170);
+        assert_eq!(controller.state(), RunState::Paused);
+        assert_eq!(controller.simulation().unwrap().paths(), paths[169]);
+        for _ in 0..80 {
+            controller.step_once(&params).unwrap();
+        }
+        assert_eq!(controller.iteration(), 250);
+        assert_eq!(controller.simulation().unwrap().paths(), paths[249]);
+
+        // simulations of other controllers keep no checkpoints
+        let mut controller = SimulationController::new();
+        controller.toggle_pause(&[circle()], &params).unwrap();
+        controller.tick(&params).unwrap();
+        controller.rewind(1, &params).unwrap();
+        assert_eq!(controller.iteration(), 1);
+    }
+
+    #[test]
+    fn spines_keep_their_ends_and_growth_near_them() {
+        let mut spine = Shape::starting_at(DVec2::new(250., 300.));
+        for i in 0..16 {
+            let offset = DVec2::new(i as f64 * 20., 0.);
+            spine.next_vertex_at(offset, i * 10, Pen::DEFAULT);
+        }
+        spine.set_spine(true);
+        let ends = [spine.points().next(), spine.points().last()]
+            .map(|p| Transform::DEFAULT.document_to_unit(p.unwrap()));
+
+        let params = Params::DEFAULT;
+        let mut controller = SimulationController::new();
+        controller.set_seed(Some(3));
+        controller.toggle_pause(&[spine], &params).unwrap();
+        for _ in 0..300 {
+            controller.tick(&params).unwrap();
+        }
+
+        let [path] = &controller.simulation().unwrap().paths()[..] else {
+            panic!("expected one path");
+        };
+        assert!(!path.passive);
+        assert!(path.points.len() > 16, "the spine grew");
+        assert_eq!([path.points[0], *path.points.last().unwrap()], ends);
+        let y = ends[0].y;
+        for p in &path.points {
+            assert!((p.y - y).abs() <= params.spine_corridor + 1e-12);
+        }
+    }
+
+    #[test]
+    fn branches_sprout_as_new_paths() {
+        let params = Params {
+            branch_probability: 0.2,
+            ..Params::DEFAULT
+        };
+        let mut controller = SimulationController::new();
+        controller.set_seed(Some(1));
+        controller.toggle_pause(&[circle()], &params).unwrap();
+        for _ in 0..100 {
+            controller.tick(&params).unwrap();
+        }
+
+        let paths = controller.simulation().unwrap().paths();
+        // the circle is segment 0, and every branch a segment of its own
+        assert!(paths.iter().any(|path| path.segment > 0 && !path.closed));
+        assert!(paths.iter().all(|path| path.segment > 0 || path.closed));
+    }
+
+    #[test]
+    fn equalized_edges_are_more_even() {
+        // spread of the lengths of the edges, relative to their mean
+        let spread = |equalize| {
+            let params = Params {
+                spawn_probability: 0.05,
+                equalize,
+                ..Params::DEFAULT
+            };
+            let mut controller = SimulationController::new();
+            controller.set_seed(Some(7));
+            controller.toggle_pause(&[circle()], &params).unwrap();
+            for _ in 0..200 {
+                controller.tick(&params).unwrap();
+            }
+
+            let paths = controller.simulation().unwrap().paths();
+            let lengths = paths[0]
+                .points
+                .windows(2)
+                .map(|w| w[0].distance(w[1]))
+                .collect::<Vec<_>>();
+            let n = lengths.len() as f64;
+            let mean = lengths.iter().sum::<f64>() / n;
+            let variance =
+                lengths.iter().map(|l| (l - mean).powi(2)).sum::<f64>() / n;
+            variance.sqrt() / mean
+        };
+
+        assert!(spread(0.5) < 0.5 * spread(0.));
+    }
+
+    #[test]
+    fn stiff_segments_bend_less() {
+        let params = Params {
+            spawn_probability: 0.05,
+            ..Params::DEFAULT
+        };
+        let mut simulation = Simulation::new(&params, 1).unwrap();
+        for x in [0.3, 0.7] {
+            simulation
+                .add_primitive(&Primitive {
+                    kind: PrimitiveKind::Circle,
+                    center: DVec2::new(x, 0.5),
+                    radius: 0.05,
+                    rotation: 0.,
+                    n_vertices: 64,
+                    passive: false,
+                })
+                .unwrap();
+        }
+        simulation.set_segment_stiffness(1, Some(10.));
+        for _ in 0..200 {
+            simulation.step(&params).unwrap();
+        }
+
+        // mean of how sharply the path turns at each vertex, from 0 where
+        // it is straight to 1 where it folds back
+        let turn = |path: &SegmentPath| {
+            let n = path.points.len();
+            let turns = (0..n).map(|i| {
+                let p = path.points[i];
+                let a = (path.points[(i + n - 1) % n] - p).normalize();
+                let b = (path.points[(i + 1) % n] - p).normalize();
+                0.5 * (1. + a.dot(b))
+            });
+            turns.sum::<f64>() / n as f64
+        };
+        let paths = simulation.paths();
+        let [floppy, stiff] = [0, 1].map(|s| {
+            turn(paths.iter().find(|path| path.segment == s).unwrap())
+        });
+        assert!(stiff < 0.5 * floppy, "{stiff} {floppy}");
+    }
+
+    #[test]
+    fn speeds_round_up_to_a_level() {
+        assert_eq!(Speed::DEFAULT.level(), 6);
+        assert_eq!(Speed::PerSecond(3.).level(), 2);
+        assert_eq!(Speed::PerSecond(1000.).level(), 9);
+        assert_eq!(Speed::LEVELS[0].to_string(), "1 step/s");
+    }
+
+    #[test]
+    fn stop_conditions_finish_the_run() {
+        let params = Params::DEFAULT;
+        let mut controller = SimulationController::new();
+        controller.set_stop_conditions(StopConditions {
+            max_iterations: Some(5),
+            ..StopConditions::NONE
+        });
+        controller.toggle_pause(&[circle()], &params).unwrap();
+        while controller.tick(&params).unwrap() {}
+        assert_eq!(controller.iteration(), 5);
+        assert_eq!(controller.state(), RunState::Finished);
+        assert_eq!(controller.stop_reason(), Some(StopReason::Iterations));
+
+        // resumed with a higher limit, it goes on until the next one
+        controller.set_stop_conditions(StopConditions {
+            max_vertices: Some(100),
+            stall: Some(f64::MIN_POSITIVE),
+            ..StopConditions::NONE
+        });
+        controller.toggle_pause(&[circle()], &params).unwrap();
+        assert_eq!(controller.state(), RunState::Running);
+        while controller.tick(&params).unwrap() {}
+        assert_eq!(controller.stop_reason(), Some(StopReason::Vertices));
+        let vertices = controller.simulation().unwrap().vertex_count();
+        assert!(vertices >= 100, "stopped at {vertices} vertices");
+
+        controller.clear();
+        assert_eq!(controller.stop_reason(), None);
+    }
+
+    #[test]
+    fn hooks_see_each_step_and_can_stop_the_run() {
+        use std::{cell::Cell, rc::Rc};
+
+        let params = Params {
+            spawn_probability: 0.5,
+            ..Params::DEFAULT
+        };
+        let mut controller = SimulationController::new();
+        let [spawned, collapsed, stops] =
+            [(); 3].map(|_| Rc::new(Cell::new(0)));
+        controller
+            .hooks_mut()
+            .on_step(|_, iteration| match iteration {
+                10 => ControlFlow::Break(()),
+                _ => ControlFlow::Continue(()),
+            })
+            .on_spawn({
+                let spawned = spawned.clone();
+                move |_, n| spawned.set(spawned.get() + n)
+            })
+            .on_collapse({
+                let collapsed = collapsed.clone();
+                move |_, n| collapsed.set(collapsed.get() + n)
+            })
+            .on_stop({
+                let stops = stops.clone();
+                move |iteration, reason| {
+                    assert_eq!(iteration, 10);
+                    assert_eq!(reason, Some(StopReason::Hook));
+                    stops.set(stops.get() + 1);
+                }
+            });
+
+        controller.toggle_pause(&[circle()], &params).unwrap();
+        let seeded = controller.simulation().unwrap().vertex_count();
+        while controller.tick(&params).unwrap() {}
+
+        assert_eq!(controller.iteration(), 10);
+        assert_eq!(controller.state(), RunState::Finished);
+        assert_eq!(stops.get(), 1);
+        let grown = controller.simulation().unwrap().vertex_count();
+        assert!(spawned.get() > 0);
+        assert_eq!(spawned.get() - collapsed.get(), grown - seeded);
+    }
+}
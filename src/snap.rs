@@ -0,0 +1,91 @@
+//! Snapping what is drawn to a grid over the canvas, and the segments of
+//! strokes to angles of 45°.
+
+use std::f64::consts::FRAC_PI_4;
+
+use glam::DVec2;
+
+use super::scene::Path;
+
+/// The grid over the canvas, and whether what is drawn snaps to it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct SnapOptions {
+    /// Whether the grid is drawn over the canvas.
+    pub(crate) show_grid: bool,
+    /// Whether strokes start, and primitive seeds are centered, on the
+    /// grid.
+    pub(crate) to_grid: bool,
+    /// Distance between the lines of the grid, in pixels of the canvas.
+    pub(crate) spacing: f64,
+}
+
+impl SnapOptions {
+    pub(crate) const DEFAULT: Self = Self {
+        show_grid: false,
+        to_grid: false,
+        spacing: 20.,
+    };
+
+    /// `pos` moved to the nearest crossing of the grid, if snapping to it.
+    pub(crate) fn point(self, pos: DVec2) -> DVec2 {
+        if !self.to_grid || self.spacing <= 0. {
+            return pos;
+        }
+        (pos / self.spacing).round() * self.spacing
+    }
+
+    /// The lines of the grid across a canvas of `size`, if it is shown.
+    pub(crate) fn grid(self, size: DVec2) -> Vec<Path> {
+        if !self.show_grid || self.spacing <= 0. {
+            return Vec::new();
+        }
+        let line = |a, b| Path::Line {
+            points: vec![a, b],
+            closed: false,
+        };
+        let steps = |length: f64| {
+            let n = (length / self.spacing).floor() as usize;
+            (0..=n).map(move |i| i as f64 * self.spacing)
+        };
+        let columns = steps(size.x)
+            .map(|x| line(DVec2::new(x, 0.), DVec2::new(x, size.y)));
+        let rows = steps(size.y)
+            .map(|y| line(DVec2::new(0., y), DVec2::new(size.x, y)));
+        columns.chain(rows).collect()
+    }
+}
+
+/// `segment` turned to the nearest multiple of 45°, keeping as much of its
+/// length as lies along that way.
+pub(crate) fn constrain_angle(segment: DVec2) -> DVec2 {
+    if segment == DVec2::ZERO {
+        return segment;
+    }
+    let angle = (segment.to_angle() / FRAC_PI_4).round() * FRAC_PI_4;
+    let direction = DVec2::from_angle(angle);
+    direction * segment.dot(direction)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn points_and_segments_snap() {
+        let mut options = SnapOptions::DEFAULT;
+        let pos = DVec2::new(31., 49.);
+        assert_eq!(options.point(pos), pos);
+        assert!(options.grid(DVec2::new(100., 50.)).is_empty());
+
+        options.to_grid = true;
+        options.show_grid = true;
+        assert_eq!(options.point(pos), DVec2::new(40., 40.));
+        // 6 columns and 3 rows, both edges included
+        assert_eq!(options.grid(DVec2::new(100., 50.)).len(), 9);
+
+        assert_eq!(constrain_angle(DVec2::ZERO), DVec2::ZERO);
+        assert_eq!(constrain_angle(DVec2::new(10., 1.)), DVec2::new(10., 0.));
+        let diagonal = constrain_angle(DVec2::new(-10., 9.));
+        assert!((diagonal - DVec2::new(-9.5, 9.5)).length() < 1e-9);
+    }
+}
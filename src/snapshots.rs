@@ -0,0 +1,363 @@
+//! Snapshot files: the geometry of a simulation after many of its steps,
+//! as zstd-compressed binary, for long recordings of large simulations that
+//! would be far too big as text. They convert to and from JSON Lines, one
+//! snapshot per line, to be read by other tools.
+//!
+//! A snapshot file starts with [`MAGIC`] and the version of the format,
+//! followed by a zstd stream of snapshots, each a `1` byte and then the
+//! snapshot, and a `0` byte at the end. Numbers are little-endian.
+
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use anyhow::{Context, Result};
+use glam::DVec2;
+use serde_json::{Value, json};
+
+use super::algorithm::{GeometrySnapshot, SegmentPath};
+
+/// First bytes of every snapshot file.
+const MAGIC: &[u8; 8] = b"dxdysnap";
+
+/// Version of the format after the magic bytes.
+const VERSION: u32 = 1;
+
+/// How hard snapshots are compressed, which zstd does fast up to about 3.
+const LEVEL: i32 = 3;
+
+/// Flags of a path.
+const CLOSED: u8 = 1;
+const PASSIVE: u8 = 2;
+
+/// Writes snapshots to a snapshot file as they are taken.
+pub(crate) struct SnapshotWriter<W: Write> {
+    encoder: zstd::Encoder<'static, W>,
+    written: usize,
+}
+
+impl SnapshotWriter<BufWriter<File>> {
+    /// A writer of a new snapshot file at `path`.
+    pub(crate) fn create(path: &Path) -> Result<Self> {
+        let file = File::create(path)
+            .with_context(|| format!("failed to create {}", path.display()))?;
+        Self::new(BufWriter::new(file))
+    }
+}
+
+impl<W: Write> SnapshotWriter<W> {
+    pub(crate) fn new(mut inner: W) -> Result<Self> {
+        inner.write_all(MAGIC)?;
+        inner.write_all(&VERSION.to_le_bytes())?;
+        Ok(Self {
+            encoder: zstd::Encoder::new(inner, LEVEL)?,
+            written: 0,
+        })
+    }
+
+    pub(crate) fn write(&mut self, snapshot: &GeometrySnapshot) -> Result<()> {
+        let w = &mut self.encoder;
+        w.write_all(&[1])?;
+        w.write_all(&snapshot.iteration.to_le_bytes())?;
+        w.write_all(&(snapshot.paths.len() as u32).to_le_bytes())?;
+        for path in &snapshot.paths {
+            let flags = if path.closed { CLOSED } else { 0 }
+                | if path.passive { PASSIVE } else { 0 };
+            w.write_all(&path.segment.to_le_bytes())?;
+            w.write_all(&[flags])?;
+            w.write_all(&(path.points.len() as u32).to_le_bytes())?;
+            for p in &path.points {
+                w.write_all(&p.x.to_le_bytes())?;
+                w.write_all(&p.y.to_le_bytes())?;
+            }
+        }
+        self.written += 1;
+        Ok(())
+    }
+
+    /// The number of snapshots written so far.
+    pub(crate) fn written(&self) -> usize {
+        self.written
+    }
+
+    /// End the file, which a file cut short does not have.
+    pub(crate) fn finish(mut self) -> Result<W> {
+        self.encoder.write_all(&[0])?;
+        let mut inner = self.encoder.finish()?;
+        inner.flush()?;
+        Ok(inner)
+    }
+}
+
+/// Reads the snapshots of a snapshot file in order, ending with an error
+/// if the file was cut short.
+pub(crate) struct SnapshotReader<R: BufRead> {
+    decoder: zstd::Decoder<'static, R>,
+    done: bool,
+}
+
+impl SnapshotReader<BufReader<File>> {
+    /// A reader of the snapshot file at `path`.
+    pub(crate) fn open(path: &Path) -> Result<Self> {
+        let file = File::open(path)
+            .with_context(|| format!("failed to open {}", path.display()))?;
+        Self::new(BufReader::new(file))
+            .with_context(|| format!("invalid snapshots: {}", path.display()))
+    }
+}
+
+impl<R: BufRead> SnapshotReader<R> {
+    pub(crate) fn new(mut inner: R) -> Result<Self> {
+        let mut magic = [0; MAGIC.len()];
+        inner.read_exact(&mut magic)?;
+        anyhow::ensure!(&magic == MAGIC, "not a snapshot file");
+        let version = u32::from_le_bytes(read(&mut inner)?);
+        anyhow::ensure!(
+            version == VERSION,
+            "unsupported snapshot file version: {version}"
+        );
+
+        Ok(Self {
+            decoder: zstd::Decoder::with_buffer(inner)?,
+            done: false,
+        })
+    }
+
+    /// The next snapshot, or `None` at the end of the file.
+    fn read_snapshot(&mut self) -> Result<Option<GeometrySnapshot>> {
+        let r = &mut self.decoder;
+        match read::<1>(r)? {
+            [0] => return Ok(None),
+            [1] => {}
+            [tag] => anyhow::bail!("unknown record: {tag}"),
+        }
+
+        let iteration = u64::from_le_bytes(read(r)?);
+        let n_paths = u32::from_le_bytes(read(r)?);
+        let mut paths = Vec::new();
+        for _ in 0..n_paths {
+            let segment = i64::from_le_bytes(read(r)?);
+            let [flags] = read(r)?;
+            let n_points = u32::from_le_bytes(read(r)?);
+            let points = (0..n_points)
+                .map(|_| {
+                    let x = f64::from_le_bytes(read(r)?);
+                    let y = f64::from_le_bytes(read(r)?);
+                    Ok(DVec2::new(x, y))
+                })
+                .collect::<Result<_>>()?;
+            paths.push(SegmentPath {
+                segment,
+                passive: flags & PASSIVE != 0,
+                points,
+                closed: flags & CLOSED != 0,
+            });
+        }
+
+        Ok(Some(GeometrySnapshot { iteration, paths }))
+    }
+}
+
+impl<R: BufRead> Iterator for SnapshotReader<R> {
+    type Item = Result<GeometrySnapshot>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let snapshot = self.read_snapshot().transpose();
+        // nothing is read after the end or an error
+        self.done = !matches!(snapshot, Some(Ok(_)));
+        snapshot
+    }
+}
+
+/// The next `N` bytes of `r`.
+fn read<const N: usize>(r: &mut impl Read) -> Result<[u8; N]> {
+    let mut bytes = [0; N];
+    r.read_exact(&mut bytes).map_err(|err| match err.kind() {
+        io::ErrorKind::UnexpectedEof => {
+            anyhow::anyhow!("snapshot file cut short")
+        }
+        _ => err.into(),
+    })?;
+    Ok(bytes)
+}
+
+/// `snapshot` as a line of JSON.
+fn json(snapshot: &GeometrySnapshot) -> Value {
+    let paths = snapshot
+        .paths
+        .iter()
+        .map(|path| {
+            let points = path
+                .points
+                .iter()
+                .map(|p| json!([p.x, p.y]))
+                .collect::<Vec<_>>();
+            json!({
+                "segment": path.segment,
+                "passive": path.passive,
+                "closed": path.closed,
+                "points": points,
+            })
+        })
+        .collect::<Vec<_>>();
+    json!({ "iteration": snapshot.iteration, "paths": paths })
+}
+
+/// The snapshot of a line of JSON, as written by [`json`].
+fn from_json(value: &Value) -> Result<GeometrySnapshot> {
+    let field = |value: &'_ Value, name: &str| {
+        value.get(name).cloned().context(format!("missing {name}"))
+    };
+    let number = |value: Value| {
+        value
+            .as_f64()
+            .context(format!("expected a number, found {value}"))
+    };
+
+    let iteration = field(value, "iteration")?
+        .as_u64()
+        .context("expected an iteration")?;
+    let paths = match field(value, "paths")? {
+        Value::Array(paths) => paths,
+        _ => anyhow::bail!("expected a list of paths"),
+    };
+    let paths = paths
+        .iter()
+        .map(|path| {
+            let flag = |name| {
+                field(path, name)?
+                    .as_bool()
+                    .context(format!("expected true or false for {name}"))
+            };
+            let points = match field(path, "points")? {
+                Value::Array(points) => points,
+                _ => anyhow::bail!("expected a list of points"),
+            };
+            let points = points
+                .into_iter()
+                .map(|p| match p {
+                    Value::Array(xy) if xy.len() == 2 => {
+                        let [x, y] = <[_; 2]>::try_from(xy).unwrap();
+                        Ok(DVec2::new(number(x)?, number(y)?))
+                    }
+                    p => anyhow::bail!("expected [x, y], found {p}"),
+                })
+                .collect::<Result<_>>()?;
+            Ok(SegmentPath {
+                segment: field(path, "segment")?
+                    .as_i64()
+                    .context("expected a segment")?,
+                passive: flag("passive")?,
+                points,
+                closed: flag("closed")?,
+            })
+        })
+        .collect::<Result<_>>()?;
+
+    Ok(GeometrySnapshot { iteration, paths })
+}
+
+/// Write the snapshots of `reader` to `out` as JSON Lines, returning how
+/// many there were.
+pub(crate) fn write_json(
+    reader: impl Iterator<Item = Result<GeometrySnapshot>>,
+    mut out: impl Write,
+) -> Result<usize> {
+    let mut n = 0;
+    for snapshot in reader {
+        serde_json::to_writer(&mut out, &json(&snapshot?))?;
+        writeln!(out)?;
+        n += 1;
+    }
+    out.flush()?;
+    Ok(n)
+}
+
+/// Write the snapshots of the JSON Lines of `input` with `writer`, skipping
+/// blank lines.
+pub(crate) fn read_json<W: Write>(
+    input: impl BufRead,
+    writer: &mut SnapshotWriter<W>,
+) -> Result<()> {
+    for (i, line) in input.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let snapshot = serde_json::from_str(&line)
+            .map_err(anyhow::Error::from)
+            .and_then(|value| from_json(&value))
+            .with_context(|| format!("invalid snapshot on line {}", i + 1))?;
+        writer.write(&snapshot)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::{Params, Primitive, PrimitiveKind, Simulation};
+
+    #[test]
+    fn snapshots_round_trip_through_binary_and_json() {
+        let params = Params::DEFAULT;
+        let mut simulation = Simulation::new(&params, 1).unwrap();
+        simulation
+            .add_primitive(&Primitive {
+                kind: PrimitiveKind::Circle,
+                center: DVec2::splat(0.5),
+                radius: 0.1,
+                rotation: 0.,
+                n_vertices: 64,
+                passive: false,
+            })
+            .unwrap();
+        simulation
+            .add_obstacle(&[DVec2::splat(0.2), DVec2::splat(0.3)], false)
+            .unwrap();
+        let snapshots = simulation
+            .run(params)
+            .take(20)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        let mut writer = SnapshotWriter::new(Vec::new()).unwrap();
+        for snapshot in &snapshots {
+            writer.write(snapshot).unwrap();
+        }
+        assert_eq!(writer.written(), 20);
+        let bytes = writer.finish().unwrap();
+        let text_size = snapshots
+            .iter()
+            .map(|s| json(s).to_string().len())
+            .sum::<usize>();
+        assert!(bytes.len() * 4 < text_size, "{} bytes", bytes.len());
+
+        let read = SnapshotReader::new(&bytes[..])
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(read, snapshots);
+
+        let mut json = Vec::new();
+        let n =
+            write_json(SnapshotReader::new(&bytes[..]).unwrap(), &mut json)
+                .unwrap();
+        assert_eq!(n, 20);
+        let mut writer = SnapshotWriter::new(Vec::new()).unwrap();
+        read_json(&json[..], &mut writer).unwrap();
+        assert_eq!(writer.finish().unwrap(), bytes);
+
+        // cut short
+        let cut = &bytes[..bytes.len() / 2];
+        let read = SnapshotReader::new(cut).unwrap().collect::<Vec<_>>();
+        let err = read.last().unwrap().as_ref().unwrap_err();
+        assert!(format!("{err:#}").contains("cut short"), "{err:#}");
+        assert!(SnapshotReader::new(&b"dxdy.draw project 1"[..]).is_err());
+    }
+}
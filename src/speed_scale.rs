@@ -0,0 +1,52 @@
+use gtk::{gio, glib, prelude::*};
+
+use super::simulation::Speed;
+
+/// Labeled slider picking how fast the simulation runs among
+/// [`Speed::LEVELS`], by the index of the level. It changes the state of
+/// `action`, the speed action of the window, and follows it when the
+/// speed is changed with keys.
+pub(crate) fn build(action: &gio::SimpleAction) -> gtk::Box {
+    let container = gtk::Box::new(gtk::Orientation::Vertical, 4);
+    container.append(
+        &gtk::Label::builder()
+            .label("Simulation speed")
+            .xalign(0.)
+            .build(),
+    );
+
+    let max = (Speed::LEVELS.len() - 1) as f64;
+    let scale =
+        gtk::Scale::with_range(gtk::Orientation::Horizontal, 0., max, 1.);
+    scale.set_round_digits(0);
+    scale.set_draw_value(true);
+    scale.set_format_value_func(|_, level| {
+        Speed::LEVELS[level as usize].to_string()
+    });
+    if let Some(level) = action.state().and_then(|s| s.get::<i32>()) {
+        scale.set_value(level as f64);
+    }
+
+    scale.connect_value_changed(glib::clone!(
+        #[weak]
+        action,
+        move |scale| {
+            let level = scale.value() as i32;
+            if action.state().and_then(|s| s.get::<i32>()) != Some(level) {
+                action.change_state(&level.to_variant());
+            }
+        }
+    ));
+    action.connect_state_notify(glib::clone!(
+        #[weak]
+        scale,
+        move |action| {
+            if let Some(level) = action.state().and_then(|s| s.get::<i32>()) {
+                scale.set_value(level as f64);
+            }
+        }
+    ));
+
+    container.append(&scale);
+    container
+}
@@ -0,0 +1,128 @@
+//! Live numbers about the simulation of a window and how fast it is drawn,
+//! shown over the drawing while the stats are on.
+
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
+
+use super::algorithm::{OccupancyStats, Simulation};
+
+/// How often the rates are worked out, and the counts taken again.
+const PERIOD: Duration = Duration::from_millis(500);
+
+/// The stats of a window, updated as it steps its simulation and as it is
+/// drawn.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Stats {
+    vertices: usize,
+    edges: usize,
+    active_vertices: usize,
+    iterations_per_second: f64,
+    /// How long the last draw of anything in the window took.
+    render: Duration,
+    /// Sphere queries of the spatial index per second.
+    queries_per_second: f64,
+    /// Vertices examined per sphere query.
+    candidates_per_query: f64,
+    /// When the current period started, and the iterations since.
+    period: Option<(Instant, u64)>,
+}
+
+impl Stats {
+    /// Count `iterations` more steps of `simulation`, taken by `now`, and
+    /// take the counts of the simulation again once a period is over.
+    pub(crate) fn record_steps(
+        &mut self,
+        iterations: u64,
+        simulation: Option<&Simulation>,
+        now: Instant,
+    ) {
+        let (start, steps) = self.period.get_or_insert((now, 0));
+        *steps += iterations;
+
+        let elapsed = now - *start;
+        if elapsed < PERIOD {
+            return;
+        }
+        let seconds = elapsed.as_secs_f64();
+        self.iterations_per_second = *steps as f64 / seconds;
+        self.period = Some((now, 0));
+
+        let index = match simulation {
+            Some(simulation) => {
+                self.vertices = simulation.vertex_count();
+                self.edges = simulation.edge_count();
+                self.active_vertices = simulation.active_vertex_count();
+                simulation.take_index_stats()
+            }
+            None => {
+                (self.vertices, self.edges, self.active_vertices) = (0, 0, 0);
+                OccupancyStats::default()
+            }
+        };
+        self.queries_per_second = index.queries as f64 / seconds;
+        self.candidates_per_query = index.candidates_per_query();
+    }
+
+    /// Count a draw of the window that took `took`.
+    #[cfg(feature = "gui")]
+    pub(crate) fn record_render(&mut self, took: Duration) {
+        self.render = took;
+    }
+}
+
+/// One stat per line, as shown over the drawing.
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "vertices: {}", self.vertices)?;
+        writeln!(f, "edges: {}", self.edges)?;
+        writeln!(f, "active vertices: {}", self.active_vertices)?;
+        writeln!(f, "iterations/s: {:.1}", self.iterations_per_second)?;
+        writeln!(f, "render: {:.2} ms", self.render.as_secs_f64() * 1e3)?;
+        writeln!(f, "queries/s: {:.0}", self.queries_per_second)?;
+        write!(f, "candidates/query: {:.1}", self.candidates_per_query)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithm::{Params, Primitive, PrimitiveKind};
+
+    #[test]
+    fn rates_are_taken_once_a_period_is_over() {
+        let params = Params::DEFAULT;
+        let mut simulation = Simulation::new(&params, 1).unwrap();
+        simulation
+            .add_primitive(&Primitive {
+                kind: PrimitiveKind::Circle,
+                center: glam::DVec2::splat(0.5),
+                radius: 0.1,
+                rotation: 0.,
+                n_vertices: 32,
+                passive: false,
+            })
+            .unwrap();
+        for _ in 0..10 {
+            simulation.step(&params).unwrap();
+        }
+
+        let mut stats = Stats::default();
+        let start = Instant::now();
+        stats.record_steps(0, Some(&simulation), start);
+        stats.record_steps(10, Some(&simulation), start + PERIOD / 2);
+        assert_eq!(stats.vertices, 0, "taken before the period is over");
+
+        stats.record_steps(10, Some(&simulation), start + PERIOD * 2);
+        assert_eq!(stats.iterations_per_second, 20.);
+        assert_eq!(stats.vertices, simulation.vertex_count());
+        assert_eq!(stats.active_vertices, stats.vertices);
+        assert_eq!(stats.edges, stats.vertices, "a closed loop");
+        assert!(stats.queries_per_second > 0.);
+
+        stats.record_steps(0, None, start + PERIOD * 4);
+        assert_eq!(stats.vertices, 0);
+        assert_eq!(stats.to_string().lines().count(), 7);
+    }
+}
@@ -0,0 +1,338 @@
+//! The colors and sizes of what is drawn, which can be tuned in a style
+//! file while the window is open.
+
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{
+        LazyLock, RwLock,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::SystemTime,
+};
+
+use anyhow::{Context, Result};
+
+use super::{scene::Color, sizes, theme::Palette};
+
+/// First line of every style file.
+const HEADER: &str = "dxdy.draw style 1";
+
+/// The colors and sizes scenes are drawn with.
+///
+/// Style files are plain text like projects, with one record per line, each
+/// the name of a setting followed by its values. Settings without a record
+/// keep their default. Colors are written `#rrggbb`, followed by `/` and an
+/// opacity from `0` to `1` for translucent colors, as in `#ff6060/0.15`.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Style {
+    pub(crate) background: Color,
+    /// The two colors the cursor blinks between, the completed shapes
+    /// being drawn in the other one.
+    pub(crate) cursor: [Color; 2],
+    /// Growth of each segment, cycling through the list by segment index.
+    pub(crate) growth: Vec<Color>,
+    /// Completed shapes in exported images, which do not follow the
+    /// blinking of the cursor.
+    pub(crate) shape: Color,
+    pub(crate) obstacle: Color,
+    pub(crate) marker: Color,
+    /// Band along the edge of the simulation space in which growth stops.
+    pub(crate) boundary: Color,
+    /// Outline of the region seeds are placed in.
+    pub(crate) padding: Color,
+    pub(crate) cursor_radius: f64,
+    /// Width of the shape being drawn.
+    pub(crate) drawing_width: f64,
+    /// Width of completed shapes at full pen pressure.
+    pub(crate) stroke_width: f64,
+    /// Radius of the markers at the vertices of completed shapes, in pixels
+    /// of the window at any zoom.
+    pub(crate) marker_radius: f64,
+}
+
+/// The dark theme.
+impl Default for Style {
+    fn default() -> Self {
+        let Palette {
+            background,
+            cursor,
+            growth,
+            shape,
+            obstacle,
+            marker,
+            boundary,
+            padding,
+        } = Palette::dark();
+        Self {
+            background,
+            cursor,
+            growth,
+            shape,
+            obstacle,
+            marker,
+            boundary,
+            padding,
+            cursor_radius: sizes::CURSOR_RADIUS,
+            drawing_width: sizes::DRAWING_WIDTH,
+            stroke_width: sizes::STROKE_WIDTH,
+            marker_radius: sizes::MARKER_RADIUS,
+        }
+    }
+}
+
+impl Style {
+    pub(crate) fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        text.parse()
+            .with_context(|| format!("invalid style: {}", path.display()))
+    }
+}
+
+/// `color` as it is parsed by [`next_color`].
+pub(crate) fn color_text(color: Color) -> String {
+    let [r, g, b] = color.to_rgb8();
+    match color.alpha {
+        1. => format!("#{r:02x}{g:02x}{b:02x}"),
+        alpha => format!("#{r:02x}{g:02x}{b:02x}/{alpha}"),
+    }
+}
+
+/// Write ` ` and `color` as it is parsed by [`next_color`].
+pub(crate) fn write_color(
+    f: &mut fmt::Formatter<'_>,
+    color: Color,
+) -> fmt::Result {
+    write!(f, " {}", color_text(color))
+}
+
+impl fmt::Display for Style {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{HEADER}")?;
+
+        let colors = [
+            ("background", std::slice::from_ref(&self.background)),
+            ("cursor", &self.cursor[..]),
+            ("growth", &self.growth[..]),
+            ("shape", std::slice::from_ref(&self.shape)),
+            ("obstacle", std::slice::from_ref(&self.obstacle)),
+            ("marker", std::slice::from_ref(&self.marker)),
+            ("boundary", std::slice::from_ref(&self.boundary)),
+            ("padding", std::slice::from_ref(&self.padding)),
+        ];
+        for (name, colors) in colors {
+            write!(f, "{name}")?;
+            for &color in colors {
+                write_color(f, color)?;
+            }
+            writeln!(f)?;
+        }
+
+        writeln!(f, "cursor-radius {}", self.cursor_radius)?;
+        writeln!(f, "drawing-width {}", self.drawing_width)?;
+        writeln!(f, "stroke-width {}", self.stroke_width)?;
+        writeln!(f, "marker-radius {}", self.marker_radius)?;
+
+        Ok(())
+    }
+}
+
+/// Parses a color as in style files, as given on the command line.
+pub(crate) fn parse_color(s: &str) -> Result<Color> {
+    next_color(&mut [s].into_iter())
+}
+
+/// Parse the next value of a record as a color.
+pub(crate) fn next_color<'a>(
+    values: &mut impl Iterator<Item = &'a str>,
+) -> Result<Color> {
+    let value = values.next().context("missing color")?;
+    let (hex, opacity) = match value.split_once('/') {
+        Some((hex, opacity)) => (hex, Some(opacity)),
+        None => (value, None),
+    };
+
+    let hex = hex
+        .strip_prefix('#')
+        .filter(|hex| hex.len() == 6)
+        .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+        .with_context(|| format!("invalid color: {value}"))?;
+    let [_, r, g, b] = hex.to_be_bytes();
+    let color = Color::rgb8(r, g, b);
+
+    match opacity {
+        Some(opacity) => {
+            let opacity = opacity
+                .parse::<f32>()
+                .ok()
+                .filter(|opacity| (0. ..=1.).contains(opacity))
+                .with_context(|| format!("invalid opacity: {opacity}"))?;
+            Ok(color.with_alpha(opacity))
+        }
+        None => Ok(color),
+    }
+}
+
+/// Parse the next value of a record as a size.
+fn next_size<'a>(values: &mut impl Iterator<Item = &'a str>) -> Result<f64> {
+    let value = values.next().context("missing value")?;
+    value
+        .parse()
+        .ok()
+        .filter(|size: &f64| *size >= 0. && size.is_finite())
+        .with_context(|| format!("invalid size: {value}"))
+}
+
+impl FromStr for Style {
+    type Err = anyhow::Error;
+
+    fn from_str(text: &str) -> Result<Self> {
+        let mut lines = text.lines().enumerate();
+
+        match lines.next() {
+            Some((_, HEADER)) => {}
+            _ => anyhow::bail!("missing header: {HEADER}"),
+        }
+
+        let mut style = Self::default();
+
+        for (i, line) in lines {
+            let mut values = line.split_whitespace();
+            let Some(keyword) = values.next() else {
+                continue;
+            };
+
+            let mut record = || -> Result<()> {
+                match keyword {
+                    "background" => {
+                        style.background = next_color(&mut values)?
+                    }
+                    "cursor" => {
+                        style.cursor = [
+                            next_color(&mut values)?,
+                            next_color(&mut values)?,
+                        ]
+                    }
+                    "growth" => {
+                        let mut growth = Vec::new();
+                        while values.clone().next().is_some() {
+                            growth.push(next_color(&mut values)?);
+                        }
+                        anyhow::ensure!(!growth.is_empty(), "missing color");
+                        style.growth = growth;
+                    }
+                    "shape" => style.shape = next_color(&mut values)?,
+                    "obstacle" => style.obstacle = next_color(&mut values)?,
+                    "marker" => style.marker = next_color(&mut values)?,
+                    "boundary" => style.boundary = next_color(&mut values)?,
+                    "padding" => style.padding = next_color(&mut values)?,
+                    "cursor-radius" => {
+                        style.cursor_radius = next_size(&mut values)?
+                    }
+                    "drawing-width" => {
+                        style.drawing_width = next_size(&mut values)?
+                    }
+                    "stroke-width" => {
+                        style.stroke_width = next_size(&mut values)?
+                    }
+                    "marker-radius" => {
+                        style.marker_radius = next_size(&mut values)?
+                    }
+                    _ => anyhow::bail!("unknown setting: {keyword}"),
+                }
+
+                anyhow::ensure!(values.next().is_none(), "too many values");
+                Ok(())
+            };
+
+            record().with_context(|| format!("line {}", i + 1))?;
+        }
+
+        Ok(style)
+    }
+}
+
+/// The style everything is drawn with, set from the command line and
+/// reloaded by the window when its file changes.
+pub(crate) static STYLE: LazyLock<RwLock<Style>> =
+    LazyLock::new(|| RwLock::new(Style::default()));
+
+/// Counts the changes to [`STYLE`], so that what is drawn with it can be
+/// kept until it changes again.
+static STYLE_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Background drawn instead of the one of any style, given on the command
+/// line.
+static BACKGROUND: RwLock<Option<Color>> = RwLock::new(None);
+
+/// Replace [`STYLE`] with `style`, on the background set with
+/// [`set_background`] if there is one.
+pub(crate) fn set_style(mut style: Style) {
+    if let Some(background) = *BACKGROUND.read().unwrap() {
+        style.background = background;
+    }
+    *STYLE.write().unwrap() = style;
+    STYLE_GENERATION.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Draw on `background` instead of the background of the style, from now
+/// on and with every style set later.
+pub(crate) fn set_background(background: Color) {
+    *BACKGROUND.write().unwrap() = Some(background);
+    let style = STYLE.read().unwrap().clone();
+    set_style(style);
+}
+
+/// Number of times [`STYLE`] has been replaced.
+#[cfg(feature = "gui")]
+pub(crate) fn style_generation() -> u64 {
+    STYLE_GENERATION.load(Ordering::Relaxed)
+}
+
+/// A style file, loaded again whenever it is modified.
+pub(crate) struct StyleFile {
+    path: PathBuf,
+    /// Modification time of the file when it was last loaded.
+    modified: Option<SystemTime>,
+}
+
+impl StyleFile {
+    /// Load the style at `path` into [`STYLE`].
+    pub(crate) fn load(path: PathBuf) -> Result<Self> {
+        let mut file = Self {
+            path,
+            modified: None,
+        };
+        file.reload()?;
+        Ok(file)
+    }
+
+    fn reload(&mut self) -> Result<()> {
+        // the time is read first, so that a change while the file is read
+        // is loaded on the next check
+        let modified = std::fs::metadata(&self.path)
+            .and_then(|metadata| metadata.modified())
+            .ok();
+        let style = Style::load(&self.path);
+        self.modified = modified;
+        set_style(style?);
+        Ok(())
+    }
+
+    /// Load the file into [`STYLE`] again if it was modified since it was
+    /// last loaded. An invalid file leaves the style as it was, until it is
+    /// modified again.
+    #[cfg(feature = "gui")]
+    pub(crate) fn reload_if_modified(&mut self) -> Result<()> {
+        let modified = std::fs::metadata(&self.path)
+            .and_then(|metadata| metadata.modified())
+            .ok();
+        if modified != self.modified {
+            self.reload()?;
+            tracing::info!("reloaded style from {}", self.path.display());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,83 @@
+use gtk::{glib, prelude::*};
+
+use super::{algorithm::Symmetry, app_state::SharedState, seed_menu::spin};
+
+const MODES: [&str; 4] = ["Off", "Horizontal", "Vertical", "Radial"];
+
+/// Button with a popover picking the symmetry the strokes drawn on the
+/// canvas of `state` are copied under, and whether the growth is kept to it.
+pub(crate) fn build(state: &SharedState) -> gtk::MenuButton {
+    let grid = gtk::Grid::builder()
+        .row_spacing(4)
+        .column_spacing(8)
+        .build();
+
+    let (symmetry, growth) = state.borrow().symmetry();
+    grid.attach(
+        &gtk::Label::builder().label("Mirror").xalign(0.).build(),
+        0,
+        0,
+        1,
+        1,
+    );
+    let mode = gtk::DropDown::from_strings(&MODES);
+    mode.set_selected(match symmetry {
+        None => 0,
+        Some(Symmetry::Horizontal) => 1,
+        Some(Symmetry::Vertical) => 2,
+        Some(Symmetry::Radial(_)) => 3,
+    });
+    grid.attach(&mode, 1, 0, 1, 1);
+    let folds = match symmetry {
+        Some(Symmetry::Radial(n)) => n,
+        _ => 6,
+    };
+    let folds = spin(&grid, 1, "Folds", (2., 24., 1.), folds.into());
+    let mirror_growth = gtk::CheckButton::builder()
+        .label("Mirror growth")
+        .active(growth)
+        .build();
+    grid.attach(&mirror_growth, 0, 2, 2, 1);
+
+    // only radial symmetry has folds
+    folds.set_sensitive(mode.selected() == 3);
+    let update = glib::clone!(
+        #[strong]
+        state,
+        #[weak]
+        mode,
+        #[weak]
+        folds,
+        #[weak]
+        mirror_growth,
+        move || {
+            folds.set_sensitive(mode.selected() == 3);
+            let symmetry = match mode.selected() {
+                0 => None,
+                1 => Some(Symmetry::Horizontal),
+                2 => Some(Symmetry::Vertical),
+                _ => Some(Symmetry::Radial(folds.value_as_int() as u32)),
+            };
+            state
+                .borrow_mut()
+                .set_symmetry(symmetry, mirror_growth.is_active());
+        }
+    );
+    mode.connect_selected_notify(glib::clone!(
+        #[strong]
+        update,
+        move |_| update()
+    ));
+    folds.connect_value_changed(glib::clone!(
+        #[strong]
+        update,
+        move |_| update()
+    ));
+    mirror_growth.connect_toggled(move |_| update());
+
+    let popover = gtk::Popover::builder().child(&grid).build();
+    gtk::MenuButton::builder()
+        .label("Symmetry")
+        .popover(&popover)
+        .build()
+}
@@ -0,0 +1,365 @@
+//! Named sets of the colors everything is drawn in, built in or loaded from
+//! the config directory, which the window can switch between.
+
+use std::{fmt, path::PathBuf, str::FromStr};
+
+use anyhow::{Context, Result};
+
+use super::{
+    config_dir,
+    scene::Color,
+    style::{STYLE, Style, color_text, next_color, set_style},
+};
+
+/// The colors of a [`Style`], without its sizes.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Palette {
+    pub(crate) background: Color,
+    /// The two colors the cursor blinks between, the completed shapes
+    /// being drawn in the other one.
+    pub(crate) cursor: [Color; 2],
+    /// Growth of each segment, cycling through the list by segment index.
+    pub(crate) growth: Vec<Color>,
+    /// Completed shapes in exported images, which do not follow the
+    /// blinking of the cursor.
+    pub(crate) shape: Color,
+    pub(crate) obstacle: Color,
+    pub(crate) marker: Color,
+    /// Band along the edge of the simulation space in which growth stops.
+    pub(crate) boundary: Color,
+    /// Outline of the region seeds are placed in.
+    pub(crate) padding: Color,
+}
+
+impl Palette {
+    /// Light lines on dark gray, the colors of the default style.
+    pub(crate) fn dark() -> Self {
+        const WHITE: Color = Color::rgb8(0xff, 0xff, 0xff);
+        const BLUE: Color = Color::rgb8(0x60, 0x60, 0xff);
+        const RED: Color = Color::rgb8(0xff, 0x60, 0x60);
+        const GREEN: Color = Color::rgb8(0x60, 0xff, 0x60);
+        const YELLOW: Color = Color::rgb8(0xff, 0xe0, 0x60);
+        const PURPLE: Color = Color::rgb8(0xc0, 0x60, 0xff);
+
+        Self {
+            background: Color::new(0.2, 0.2, 0.2, 1.),
+            cursor: [BLUE, RED],
+            growth: vec![WHITE, BLUE, RED, GREEN, YELLOW, PURPLE],
+            shape: RED,
+            obstacle: Color::new(0.55, 0.55, 0.55, 1.),
+            marker: WHITE,
+            boundary: RED.with_alpha(0.15),
+            padding: WHITE.with_alpha(0.3),
+        }
+    }
+
+    /// Dark lines on off-white, as on paper.
+    pub(crate) fn light() -> Self {
+        const BLACK: Color = Color::rgb8(0x20, 0x20, 0x20);
+        const BLUE: Color = Color::rgb8(0x30, 0x50, 0xd0);
+        const RED: Color = Color::rgb8(0xd0, 0x30, 0x30);
+        const GREEN: Color = Color::rgb8(0x20, 0x80, 0x40);
+        const YELLOW: Color = Color::rgb8(0xb0, 0x80, 0x00);
+        const PURPLE: Color = Color::rgb8(0x80, 0x30, 0xc0);
+
+        Self {
+            background: Color::rgb8(0xf4, 0xf4, 0xf0),
+            cursor: [BLUE, RED],
+            growth: vec![BLACK, BLUE, RED, GREEN, YELLOW, PURPLE],
+            shape: RED,
+            obstacle: Color::rgb8(0x90, 0x90, 0x90),
+            marker: BLACK,
+            boundary: RED.with_alpha(0.15),
+            padding: BLACK.with_alpha(0.3),
+        }
+    }
+
+    /// Saturated lines on black, with stronger guides.
+    pub(crate) fn high_contrast() -> Self {
+        const WHITE: Color = Color::rgb8(0xff, 0xff, 0xff);
+        const CYAN: Color = Color::rgb8(0x00, 0xff, 0xff);
+        const YELLOW: Color = Color::rgb8(0xff, 0xff, 0x00);
+
+        Self {
+            background: Color::rgb8(0x00, 0x00, 0x00),
+            cursor: [CYAN, YELLOW],
+            growth: vec![
+                WHITE,
+                CYAN,
+                YELLOW,
+                Color::rgb8(0x00, 0xff, 0x00),
+                Color::rgb8(0xff, 0x00, 0xff),
+                Color::rgb8(0xff, 0x80, 0x00),
+            ],
+            shape: YELLOW,
+            obstacle: Color::rgb8(0xa0, 0xa0, 0xa0),
+            marker: WHITE,
+            boundary: Color::rgb8(0xff, 0x00, 0x00).with_alpha(0.3),
+            padding: WHITE.with_alpha(0.6),
+        }
+    }
+
+    /// `themes` in the config directory, where custom themes are kept as
+    /// `NAME.toml`.
+    pub(crate) fn dir() -> Result<PathBuf> {
+        Ok(config_dir().context("no config directory")?.join("themes"))
+    }
+
+    /// Names of the built-in themes and then the custom ones, in
+    /// alphabetical order.
+    pub(crate) fn names() -> Result<Vec<String>> {
+        let mut names =
+            built_in_names().map(str::to_owned).collect::<Vec<_>>();
+
+        let dir = Self::dir()?;
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(names);
+            }
+            Err(err) => {
+                return Err(err).with_context(|| {
+                    format!("failed to read {}", dir.display())
+                });
+            }
+        };
+
+        let mut custom = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "toml") {
+                custom.extend(
+                    path.file_stem()
+                        .and_then(|stem| stem.to_str())
+                        .filter(|name| built_in(name).is_none())
+                        .map(str::to_owned),
+                );
+            }
+        }
+        custom.sort();
+        names.extend(custom);
+        Ok(names)
+    }
+
+    /// The built-in theme `name`, or else the custom one.
+    pub(crate) fn named(name: &str) -> Result<Self> {
+        if let Some(palette) = built_in(name) {
+            return Ok(palette);
+        }
+
+        anyhow::ensure!(
+            !name.is_empty() && !name.contains(['/', '\\', '.']),
+            "invalid theme name: {name}"
+        );
+        let path = Self::dir()?.join(name).with_extension("toml");
+        let text = std::fs::read_to_string(&path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        text.parse()
+            .with_context(|| format!("invalid theme: {}", path.display()))
+    }
+}
+
+/// A built-in theme: its name, and its palette.
+type BuiltIn = (&'static str, fn() -> Palette);
+
+/// The themes that are always there.
+const BUILT_IN: [BuiltIn; 3] = [
+    ("dark", Palette::dark),
+    ("light", Palette::light),
+    ("high-contrast", Palette::high_contrast),
+];
+
+pub(crate) fn built_in_names() -> impl Iterator<Item = &'static str> {
+    BUILT_IN.iter().map(|&(name, _)| name)
+}
+
+fn built_in(name: &str) -> Option<Palette> {
+    BUILT_IN
+        .iter()
+        .find(|&&(n, _)| n == name)
+        .map(|(_, palette)| palette())
+}
+
+impl Style {
+    pub(crate) fn set_palette(&mut self, palette: Palette) {
+        self.background = palette.background;
+        self.cursor = palette.cursor;
+        self.growth = palette.growth;
+        self.shape = palette.shape;
+        self.obstacle = palette.obstacle;
+        self.marker = palette.marker;
+        self.boundary = palette.boundary;
+        self.padding = palette.padding;
+    }
+}
+
+/// Draw everything in the colors of `palette`, keeping the sizes of the
+/// style. A style file that changes replaces them again.
+pub(crate) fn apply(palette: Palette) {
+    let mut style = STYLE.read().unwrap().clone();
+    style.set_palette(palette);
+    set_style(style);
+}
+
+/// Writes the palette as a theme file, which sets every color.
+impl fmt::Display for Palette {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let colors = [
+            ("background", std::slice::from_ref(&self.background)),
+            ("cursor", &self.cursor[..]),
+            ("growth", &self.growth[..]),
+            ("shape", std::slice::from_ref(&self.shape)),
+            ("obstacle", std::slice::from_ref(&self.obstacle)),
+            ("marker", std::slice::from_ref(&self.marker)),
+            ("boundary", std::slice::from_ref(&self.boundary)),
+            ("padding", std::slice::from_ref(&self.padding)),
+        ];
+        for (name, colors) in colors {
+            match colors {
+                [color] if name != "growth" => {
+                    writeln!(f, "{name} = \"{}\"", color_text(*color))?
+                }
+                colors => {
+                    write!(f, "{name} = [")?;
+                    for (i, &color) in colors.iter().enumerate() {
+                        let separator = if i == 0 { "" } else { ", " };
+                        write!(f, "{separator}\"{}\"", color_text(color))?;
+                    }
+                    writeln!(f, "]")?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The colors of the value of a theme setting, a color or a list of them.
+fn colors(value: toml::Value) -> Result<Vec<Color>> {
+    let values = match value {
+        toml::Value::Array(values) => values,
+        value => vec![value],
+    };
+    values
+        .into_iter()
+        .map(|value| match value {
+            toml::Value::String(color) => {
+                next_color(&mut std::iter::once(color.as_str()))
+            }
+            value => {
+                anyhow::bail!("expected a color, found {}", value.type_str())
+            }
+        })
+        .collect()
+}
+
+/// Parses a theme file: TOML, with each color of the style set to a color
+/// written as in style files, as in `background = "#202020"`, or to a list
+/// of them for `cursor` and `growth`. Colors without a setting keep those
+/// of the built-in theme named by `base`, or of the dark one.
+impl FromStr for Palette {
+    type Err = anyhow::Error;
+
+    fn from_str(text: &str) -> Result<Self> {
+        let mut table = text.parse::<toml::Table>()?;
+
+        let mut palette = match table.remove("base") {
+            Some(toml::Value::String(base)) => built_in(&base)
+                .with_context(|| format!("unknown built-in theme: {base}"))?,
+            Some(value) => anyhow::bail!(
+                "expected the name of a theme for base, found {}",
+                value.type_str()
+            ),
+            None => Self::dark(),
+        };
+
+        for (name, value) in table {
+            let colors =
+                colors(value).with_context(|| format!("invalid {name}"))?;
+            let single = || match colors[..] {
+                [color] => Ok(color),
+                _ => anyhow::bail!("expected one color for {name}"),
+            };
+            match name.as_str() {
+                "background" => palette.background = single()?,
+                "cursor" => {
+                    palette.cursor = colors[..].try_into().map_err(|_| {
+                        anyhow::anyhow!("expected two colors for cursor")
+                    })?
+                }
+                "growth" => {
+                    anyhow::ensure!(
+                        !colors.is_empty(),
+                        "missing growth color"
+                    );
+                    palette.growth = colors;
+                }
+                "shape" => palette.shape = single()?,
+                "obstacle" => palette.obstacle = single()?,
+                "marker" => palette.marker = single()?,
+                "boundary" => palette.boundary = single()?,
+                "padding" => palette.padding = single()?,
+                _ => anyhow::bail!("unknown setting: {name}"),
+            }
+        }
+
+        Ok(palette)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_style_is_dark() {
+        let mut style = Style::default();
+        style.set_palette(Palette::dark());
+        assert_eq!(style, Style::default());
+    }
+
+    #[test]
+    fn theme_files_override_their_base() {
+        let palette = "base = \"light\"\nbackground = \"#000000\"\n\
+                       growth = [\"#ffffff\", \"#ff0000/0.5\"]"
+            .parse::<Palette>()
+            .unwrap();
+
+        assert_eq!(palette.background, Color::rgb8(0, 0, 0));
+        assert_eq!(
+            palette.growth,
+            [
+                Color::rgb8(0xff, 0xff, 0xff),
+                Color::rgb8(0xff, 0, 0).with_alpha(0.5)
+            ]
+        );
+        assert_eq!(palette.cursor, Palette::light().cursor);
+    }
+
+    #[test]
+    fn invalid_theme_files_are_rejected() {
+        for text in [
+            "base = \"sepia\"",
+            "background = [\"#000000\", \"#ffffff\"]",
+            "cursor = \"#000000\"",
+            "growth = []",
+            "shape = 1",
+            "shape = \"red\"",
+            "glow = \"#ffffff\"",
+        ] {
+            assert!(text.parse::<Palette>().is_err(), "{text:?}");
+        }
+    }
+
+    #[test]
+    fn printed_theme_parses_back() {
+        for (_, palette) in BUILT_IN {
+            let palette = palette();
+            let text = palette.to_string();
+            let parsed = text.parse::<Palette>().unwrap();
+            // colors are written with 8 bits per channel
+            assert_eq!(parsed.to_string(), text);
+        }
+        let light = Palette::light();
+        assert_eq!(light.to_string().parse::<Palette>().unwrap(), light);
+    }
+}
@@ -0,0 +1,98 @@
+//! Mapping between the coordinate spaces of the app:
+//!
+//! - Document coordinates, in which shapes are drawn. The canvas shows
+//!   [`DOCUMENT_SIZE`] of them at the default zoom.
+//! - Unit coordinates, the unit square the simulation runs in.
+//! - Screen pixels, mapped to document coordinates by the
+//!   [`Viewport`](super::viewport::Viewport).
+
+use glam::DVec2;
+
+/// Size of the canvas in document coordinates.
+pub(crate) const DOCUMENT_SIZE: DVec2 = DVec2::new(800., 600.);
+
+/// Space between the document and the edges of the unit square, in document
+/// coordinates, so that seeds drawn near the edge of the canvas have room to
+/// grow before they reach the boundary of the simulation.
+pub(crate) const MARGIN: f64 = 20.;
+
+/// Mapping between document and unit coordinates. The unit square is
+/// centered on the document, and scaled uniformly so that it covers the
+/// whole document and margin along its longer side.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Transform {
+    /// Document position of the unit square origin.
+    origin: DVec2,
+    /// Side length of the unit square in document coordinates.
+    side: f64,
+}
+
+impl Transform {
+    pub(crate) const DEFAULT: Self = Self::covering(DOCUMENT_SIZE, MARGIN);
+
+    /// The transform for a document of `size` with `margin` on every side.
+    pub(crate) const fn covering(size: DVec2, margin: f64) -> Self {
+        let side = size.x.max(size.y) + 2. * margin;
+        Self {
+            origin: DVec2::new((size.x - side) / 2., (size.y - side) / 2.),
+            side,
+        }
+    }
+
+    pub(crate) fn document_to_unit(self, pos: DVec2) -> DVec2 {
+        (pos - self.origin) / self.side
+    }
+
+    pub(crate) fn unit_to_document(self, pos: DVec2) -> DVec2 {
+        pos * self.side + self.origin
+    }
+
+    /// Unit length of a distance `len` in document coordinates.
+    #[cfg(any(feature = "gui", test))]
+    pub(crate) fn document_to_unit_length(self, len: f64) -> f64 {
+        len / self.side
+    }
+
+    /// Document length of a distance `len` in unit coordinates.
+    pub(crate) fn unit_to_document_length(self, len: f64) -> f64 {
+        len * self.side
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_unit_square_covers_the_document_and_its_margin() {
+        let transform = Transform::DEFAULT;
+        let center = transform.document_to_unit(DOCUMENT_SIZE / 2.);
+        assert!(center.distance(DVec2::splat(0.5)) < 1e-12);
+
+        // the margin on either side of the longer side, and more along the
+        // shorter one
+        let side = DOCUMENT_SIZE.x + 2. * MARGIN;
+        let min = transform.document_to_unit(DVec2::ZERO);
+        let max = transform.document_to_unit(DOCUMENT_SIZE);
+        assert!((min.x - MARGIN / side).abs() < 1e-12);
+        assert!((1. - max.x - MARGIN / side).abs() < 1e-12);
+        assert!(min.y > min.x && (min.y - (1. - max.y)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn positions_and_lengths_map_back_and_forth() {
+        let transform = Transform::covering(DVec2::new(300., 500.), 10.);
+        for pos in [DVec2::ZERO, DVec2::new(120., 480.), DVec2::splat(-5.)] {
+            let unit = transform.document_to_unit(pos);
+            assert!(transform.unit_to_document(unit).distance(pos) < 1e-9);
+        }
+        assert_eq!(transform.document_to_unit_length(52.), 0.1);
+        assert_eq!(transform.unit_to_document_length(0.5), 260.);
+        // lengths scale like positions
+        let (a, b) = (DVec2::new(10., 20.), DVec2::new(40., 60.));
+        let unit = transform
+            .document_to_unit(a)
+            .distance(transform.document_to_unit(b));
+        assert!((unit - transform.document_to_unit_length(50.)).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,81 @@
+use std::hash::{Hash, Hasher};
+
+use glam::DVec2;
+
+#[cfg(feature = "gui")]
+use super::scene::{Item, Layer};
+
+/// Camera over the drawing. Shapes and the simulation live in world
+/// coordinates, which are mapped to the screen by scaling by `zoom` and then
+/// translating by `offset`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Viewport {
+    /// Screen position of the world origin.
+    offset: DVec2,
+    /// Screen pixels per world unit.
+    zoom: f64,
+}
+
+impl Viewport {
+    pub(crate) const DEFAULT: Self = Self {
+        offset: DVec2::ZERO,
+        zoom: 1.,
+    };
+
+    pub(crate) const MIN_ZOOM: f64 = 0.1;
+    pub(crate) const MAX_ZOOM: f64 = 50.;
+
+    pub(crate) fn zoom(&self) -> f64 {
+        self.zoom
+    }
+
+    pub(crate) fn to_world(self, screen: DVec2) -> DVec2 {
+        (screen - self.offset) / self.zoom
+    }
+
+    #[cfg(feature = "gui")]
+    pub(crate) fn to_screen(self, world: DVec2) -> DVec2 {
+        world * self.zoom + self.offset
+    }
+
+    /// Length in world units of a vector of screen pixels, e.g. a drag
+    /// offset.
+    pub(crate) fn to_world_vector(self, screen: DVec2) -> DVec2 {
+        screen / self.zoom
+    }
+
+    /// Move the view so that the world follows the pointer by `delta`
+    /// screen pixels.
+    #[cfg(feature = "gui")]
+    pub(crate) fn pan(&mut self, delta: DVec2) {
+        self.offset += delta;
+    }
+
+    /// Multiply the zoom by `factor`, keeping the world point under `anchor`
+    /// (in screen coordinates) in place.
+    pub(crate) fn zoom_at(&mut self, anchor: DVec2, factor: f64) {
+        let world = self.to_world(anchor);
+        self.zoom = (self.zoom * factor).clamp(Self::MIN_ZOOM, Self::MAX_ZOOM);
+        self.offset = anchor - world * self.zoom;
+    }
+
+    /// `items` in world coordinates as a layer of the window, with `key`
+    /// for keeping it drawn.
+    #[cfg(feature = "gui")]
+    pub(crate) fn layer(self, key: Option<u64>, items: Vec<Item>) -> Layer {
+        Layer {
+            scale: self.zoom,
+            offset: self.offset,
+            key,
+            name: None,
+            items,
+        }
+    }
+}
+
+impl Hash for Viewport {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.offset.to_array().map(f64::to_bits).hash(state);
+        self.zoom.to_bits().hash(state);
+    }
+}
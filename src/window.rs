@@ -0,0 +1,2361 @@
+//! The window, in which shapes are drawn and the growth is watched.
+
+use std::{
+    cell::{Cell, RefCell},
+    hash::{DefaultHasher, Hash, Hasher},
+    path::PathBuf,
+    rc::Rc,
+    sync::{
+        RwLock,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use glam::DVec2;
+use gtk::{cairo, gdk, gio, glib, prelude::*};
+
+#[cfg(feature = "script")]
+use super::script::Script;
+use super::{
+    Args, Command,
+    algorithm::{self, Domain, Field, FieldKind, Params, Symmetry},
+    app_state::{AppState, SharedState, Tool, pointer::Modifiers},
+    autosave::{self, Recovered},
+    candidates_panel,
+    damage::{Damage, Rect},
+    demo, domain_menu,
+    export::{self, Crop, ImageFormat, Region},
+    gpu::{self, RENDERER, Renderer},
+    grid_panel,
+    grow_preset::GrowPreset,
+    import_dialog,
+    jobs::JOBS,
+    jobs_panel,
+    keys::{Action, Chord, KEYMAP, Keymap},
+    layers::{CanvasLayer, LAYERS, layers_generation},
+    layers_panel, macros, mutate_menu,
+    params_file::{self, ParamsFile},
+    params_panel::{self, PARAMS},
+    polyline::Polyline,
+    preset::Preset,
+    preset_menu,
+    project::Project,
+    recorder::{self, RECORD_OPTIONS, RecordOptions},
+    render,
+    replay::{self, Event, REPLAYING, Replay, SessionLog},
+    scene::{self, Item, Layer, Paint, Path, Scene, Stroke},
+    seed_file, seed_menu,
+    shape::*,
+    simulation::*,
+    sizes,
+    snap::SnapOptions,
+    speed_scale,
+    style::{STYLE, StyleFile, style_generation},
+    symmetry_panel,
+    theme::{self, Palette},
+    transform::{DOCUMENT_SIZE, Transform},
+    viewport::*,
+};
+
+const APP_ID: &str = "com.nelsonearle.dxdy.draw";
+
+/// Zoom factor of one scroll wheel notch.
+const ZOOM_STEP: f64 = 1.1;
+
+/// Vertex markers are hidden once drawn shapes have more vertices than this.
+static MAX_VERTEX_MARKERS: AtomicUsize = AtomicUsize::new(usize::MAX);
+
+/// Where to export the drawing when one of the stop conditions stops the
+/// simulation, set from the command line.
+static EXPORT_ON_STOP: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+/// Whether the windows are autosaved, which they are once the autosaves of
+/// the session have started.
+static AUTOSAVING: AtomicBool = AtomicBool::new(false);
+
+/// Size of the canvas of each window, set from the command line.
+static CANVAS_SIZE: RwLock<DVec2> = RwLock::new(DOCUMENT_SIZE);
+
+/// Whether the next window opens fullscreen, which only the first one does
+/// when asked to on the command line.
+static FULLSCREEN: AtomicBool = AtomicBool::new(false);
+
+/// Speed of the simulation of each new window, set from the command line.
+static SPEED: RwLock<Speed> = RwLock::new(Speed::DEFAULT);
+
+/// Whether each new window shows the growth in between steps, unless turned
+/// off on the command line.
+static INTERPOLATE: AtomicBool = AtomicBool::new(true);
+
+/// Open the window, growing the demo of `args` if it has one, until it and
+/// any other windows opened from it are closed. The style is reloaded
+/// whenever `style_file` changes, and the parameters and colors whenever
+/// `params_file` does.
+pub(crate) fn run(
+    args: &Args,
+    style_file: Option<StyleFile>,
+    params_file: Option<ParamsFile>,
+) -> Result<()> {
+    let state = SharedState::default();
+    state.borrow_mut().simulation_mut().set_seed(args.seed);
+
+    if let Some(Command::Demo { demo, .. }) = &args.command {
+        let params = demo.params();
+        let seed = args.seed.unwrap_or(demo::DEFAULT_SEED);
+        *PARAMS.write().unwrap() = params;
+        state
+            .borrow_mut()
+            .simulation_mut()
+            .run(demo.build(&params, seed)?);
+    }
+
+    *RECORD_OPTIONS.write().unwrap() = RecordOptions {
+        every: args.record_every,
+        frame_format: args.frame_format,
+    };
+    *CANVAS_SIZE.write().unwrap() = args.size().unwrap_or(DOCUMENT_SIZE);
+    FULLSCREEN.store(args.fullscreen, Ordering::Relaxed);
+    if let Some(rate) = args.steps_per_second {
+        *SPEED.write().unwrap() = Speed::PerSecond(rate);
+    }
+    INTERPOLATE.store(!args.no_interpolation, Ordering::Relaxed);
+    MAX_VERTEX_MARKERS.store(args.max_vertex_markers, Ordering::Relaxed);
+    THROTTLE_UNFOCUSED.store(args.throttle_unfocused, Ordering::Relaxed);
+    *RENDERER.write().unwrap() = args.renderer;
+    *STOP_CONDITIONS.write().unwrap() = args.stop.conditions();
+    EXPORT_ON_STOP
+        .write()
+        .unwrap()
+        .clone_from(&args.export_on_stop);
+
+    if let Some(path) = &args.record_session {
+        let params = *PARAMS.read().unwrap();
+        let smooth = *smooth::SMOOTH_OPTIONS.read().unwrap();
+        let mut state = state.borrow_mut();
+        let log = SessionLog::create(path, &mut state, &params, smooth)?;
+        state.start_session_log(log);
+    }
+    let replay =
+        RefCell::new(args.replay.as_deref().map(Replay::load).transpose()?);
+
+    if let Some(path) = Keymap::default_path().filter(|path| path.exists()) {
+        let keymap = Keymap::load(&path)?;
+        let unknown = keymap
+            .chords()
+            .find(|chord| gdk::Key::from_name(&chord.key).is_none());
+        if let Some(chord) = unknown {
+            anyhow::bail!("unknown key {} in {}", chord.key, path.display());
+        }
+        tracing::info!("loaded key bindings from {}", path.display());
+        *KEYMAP.write().unwrap() = keymap;
+    }
+
+    let app = gtk::Application::builder().application_id(APP_ID).build();
+    // only the first instance of the app starts the autosaves, the others
+    // open their windows in it
+    let recovered = Rc::new(RefCell::new(Vec::new()));
+    app.connect_startup(glib::clone!(
+        #[strong]
+        recovered,
+        move |_| match autosave::start() {
+            Ok(saved) => {
+                AUTOSAVING.store(true, Ordering::Relaxed);
+                *recovered.borrow_mut() = saved;
+            }
+            Err(err) => tracing::warn!("not autosaving: {err:#}"),
+        }
+    ));
+    app.connect_shutdown(|_| {
+        if AUTOSAVING.load(Ordering::Relaxed) {
+            if let Err(err) = autosave::finish() {
+                tracing::warn!("{err:#}");
+            }
+        }
+    });
+
+    let style_file = RefCell::new(style_file);
+    let params_file = RefCell::new(params_file);
+    let theme = args.theme.clone();
+    app.connect_activate(move |app| {
+        cb_activate(
+            app,
+            &state,
+            style_file.borrow_mut().take(),
+            params_file.borrow_mut().take(),
+            theme.clone(),
+        );
+        if let Some(replay) = replay.borrow_mut().take() {
+            replay::play(replay, state.clone());
+        }
+        let recovered = recovered.take();
+        if !recovered.is_empty() {
+            offer_recovery(app, &state, recovered);
+        }
+    });
+
+    let exit_code = app.run_with_args(&[] as &[&str]);
+    if exit_code != glib::ExitCode::SUCCESS {
+        eprintln!("{exit_code:?}");
+    }
+
+    Ok(())
+}
+
+fn eat_err(r: Result<()>) {
+    if let Err(err) = r {
+        glib::g_error!("dxdy.draw", "{err}");
+    }
+}
+
+pub(crate) fn show_error(
+    window: Option<&impl IsA<gtk::Window>>,
+    message: &str,
+    detail: String,
+) {
+    gtk::AlertDialog::builder()
+        .modal(true)
+        .message(message)
+        .detail(detail)
+        .build()
+        .show(window);
+}
+
+pub(crate) fn show_simulation_error(
+    window: Option<&impl IsA<gtk::Window>>,
+    err: algorithm::SegmentsError,
+) {
+    tracing::error!("simulation stopped: {err}");
+    show_error(window, "Simulation error", err.to_string());
+}
+
+/// Ask for a file to save the drawing of `state` to as a project.
+fn save_project(window: Option<gtk::Window>, state: SharedState) {
+    let dialog = gtk::FileDialog::builder()
+        .title("Save project")
+        .initial_name("drawing.project")
+        .build();
+    dialog.save(
+        window.clone().as_ref(),
+        None::<&gio::Cancellable>,
+        move |result| {
+            let Some(path) = result.ok().and_then(|file| file.path()) else {
+                return;
+            };
+            let project = state.borrow().project();
+            if let Err(err) = project.save(&path) {
+                tracing::error!("{err:#}");
+                show_error(window.as_ref(), "Save failed", format!("{err:#}"));
+            }
+        },
+    );
+}
+
+/// Finish the recording of `state` and show why it failed, if it did.
+fn stop_recording(window: Option<&gtk::Window>, state: &mut AppState) {
+    if let Err(err) = state.stop_recording() {
+        tracing::error!("{err:#}");
+        show_error(window, "Recording failed", format!("{err:#}"));
+    }
+}
+
+/// Start recording the growth of `state` with the recorder of `start`, and
+/// show why it failed, if it did.
+fn start_recording(
+    window: Option<&gtk::Window>,
+    state: &SharedState,
+    start: Result<recorder::Recorder>,
+) {
+    let started = start
+        .and_then(|recorder| state.borrow_mut().start_recording(recorder));
+    if let Err(err) = started {
+        tracing::error!("{err:#}");
+        show_error(window, "Recording failed", format!("{err:#}"));
+    }
+}
+
+/// Finish the recording of `state` in progress, or ask for a file to record
+/// its growth to.
+fn toggle_recording(window: Option<gtk::Window>, state: SharedState) {
+    if state.borrow().is_recording() {
+        stop_recording(window.as_ref(), &mut state.borrow_mut());
+        return;
+    }
+
+    let dialog = gtk::FileDialog::builder()
+        .title("Record growth")
+        .initial_name("growth.mp4")
+        .build();
+    dialog.save(
+        window.clone().as_ref(),
+        None::<&gio::Cancellable>,
+        move |result| {
+            let Some(path) = result.ok().and_then(|file| file.path()) else {
+                return;
+            };
+            let crop = state.borrow().region().map(Crop::region);
+            let start = recorder::start_recording(&path, crop);
+            start_recording(window.as_ref(), &state, start);
+        },
+    );
+}
+
+/// Finish the recording of `state` in progress, or ask for a directory to
+/// write the frames of its growth to.
+fn toggle_recording_frames(window: Option<gtk::Window>, state: SharedState) {
+    if state.borrow().is_recording() {
+        toggle_recording(window, state);
+        return;
+    }
+
+    let dialog = gtk::FileDialog::builder().title("Record frames").build();
+    dialog.select_folder(
+        window.clone().as_ref(),
+        None::<&gio::Cancellable>,
+        move |result| {
+            let Some(path) = result.ok().and_then(|file| file.path()) else {
+                return;
+            };
+            let crop = state.borrow().region().map(Crop::region);
+            let start = recorder::start_recording_frames(&path, crop);
+            start_recording(window.as_ref(), &state, start);
+        },
+    );
+}
+
+/// Queue a job writing the drawing and growth of `state` to `path` with
+/// `preset`, cut down to its region if it has one.
+fn write_image(
+    window: Option<&gtk::Window>,
+    state: &SharedState,
+    preset: &Preset,
+    path: &std::path::Path,
+) {
+    let format = match preset.format_of(path) {
+        Ok(format) => format,
+        Err(err) => {
+            tracing::error!("{err:#}");
+            show_error(window, "Export failed", format!("{err:#}"));
+            return;
+        }
+    };
+
+    // the scene is made now, so that the image is of the drawing as it is
+    // when exported, and only rendering it is left to the job
+    let state = state.borrow();
+    let crop = state.region().map(Crop::region);
+    let scene = preset.scene(
+        &STYLE.read().unwrap(),
+        &LAYERS.read().unwrap(),
+        state.shapes(),
+        state.simulation().simulation(),
+        crop.as_ref(),
+    );
+    let (path, dpi) = (path.to_owned(), preset.dpi);
+    let title = format!("Export {}", path.display());
+    JOBS.add(title, move |_| {
+        export::write_image(&path, format, dpi, &scene)?;
+        Ok(format!("Exported to {}", path.display()))
+    });
+}
+
+/// Write the drawing and growth of `state` to an image with `preset`, to
+/// the output of the preset or else to a file asked for.
+pub(crate) fn export_image(
+    window: Option<gtk::Window>,
+    state: SharedState,
+    preset: Preset,
+) {
+    if let Some(out) = &preset.out {
+        write_image(window.as_ref(), &state, &preset, out);
+        return;
+    }
+
+    let extension = preset.format.unwrap_or(ImageFormat::Svg).extension();
+    let dialog = gtk::FileDialog::builder()
+        .title("Export image")
+        .initial_name(format!("drawing.{extension}"))
+        .build();
+    dialog.save(
+        window.clone().as_ref(),
+        None::<&gio::Cancellable>,
+        move |result| {
+            let Some(path) = result.ok().and_then(|file| file.path()) else {
+                return;
+            };
+            write_image(window.as_ref(), &state, &preset, &path);
+        },
+    );
+}
+
+/// Put the drawing and growth of `state` on the clipboard of `window`, as
+/// a PNG image and as SVG, both markup and text, cut down to its region if
+/// it has one.
+fn copy_image(window: &gtk::Window, state: &AppState) {
+    let preset = Preset::default();
+    let crop = state.region().map(Crop::region);
+    let scene = preset.scene(
+        &STYLE.read().unwrap(),
+        &LAYERS.read().unwrap(),
+        state.shapes(),
+        state.simulation().simulation(),
+        crop.as_ref(),
+    );
+    let png = match export::rasterize(&scene, preset.dpi)
+        .and_then(|image| image.png())
+    {
+        Ok(png) => png,
+        Err(err) => {
+            tracing::error!("{err:#}");
+            show_error(Some(window), "Copy failed", format!("{err:#}"));
+            return;
+        }
+    };
+    let svg = export::svg(&scene);
+
+    let bytes = |mime_type, bytes| {
+        let bytes = glib::Bytes::from_owned(bytes);
+        gdk::ContentProvider::for_bytes(mime_type, &bytes)
+    };
+    let content = gdk::ContentProvider::new_union(&[
+        bytes("image/png", png),
+        bytes("image/svg+xml", svg.clone().into_bytes()),
+        gdk::ContentProvider::for_value(&svg.to_value()),
+    ]);
+    if let Err(err) = window.clipboard().set_content(Some(&content)) {
+        tracing::error!("{err:#}");
+        show_error(Some(window), "Copy failed", format!("{err:#}"));
+    }
+}
+
+/// Ask for a project file and replace the drawing of `state` with it.
+fn open_project(window: Option<gtk::Window>, state: SharedState) {
+    let dialog = gtk::FileDialog::builder().title("Open project").build();
+    dialog.open(
+        window.clone().as_ref(),
+        None::<&gio::Cancellable>,
+        move |result| {
+            let Some(path) = result.ok().and_then(|file| file.path()) else {
+                return;
+            };
+            match Project::load(&path) {
+                Ok(project) => state.borrow_mut().open(project),
+                Err(err) => {
+                    tracing::error!("{err:#}");
+                    show_error(
+                        window.as_ref(),
+                        "Open failed",
+                        format!("{err:#}"),
+                    );
+                }
+            }
+        },
+    );
+}
+
+/// Ask for a seed file and replace the drawing of `state` with its seeds.
+fn load_seeds(window: Option<gtk::Window>, state: SharedState) {
+    let filter = gtk::FileFilter::new();
+    filter.set_name(Some("Seed files"));
+    filter.add_suffix("toml");
+    let filters = gio::ListStore::new::<gtk::FileFilter>();
+    filters.append(&filter);
+
+    let dialog = gtk::FileDialog::builder()
+        .title("Load seeds")
+        .filters(&filters)
+        .build();
+    dialog.open(
+        window.clone().as_ref(),
+        None::<&gio::Cancellable>,
+        move |result| {
+            let Some(path) = result.ok().and_then(|file| file.path()) else {
+                return;
+            };
+            match seed_file::load(&path) {
+                Ok(project) => state.borrow_mut().open(project),
+                Err(err) => {
+                    tracing::error!("{err:#}");
+                    show_error(
+                        window.as_ref(),
+                        "Load seeds failed",
+                        format!("{err:#}"),
+                    );
+                }
+            }
+        },
+    );
+}
+
+/// Keep the macro being recorded in `slot` if there is one, or play the
+/// one kept there to `state` otherwise.
+fn play_macro(window: Option<&gtk::Window>, state: &SharedState, slot: usize) {
+    let recorded = state.borrow_mut().finish_macro();
+    let result = match recorded {
+        Some(recorded) => macros::save(slot, &recorded).map(|path| {
+            tracing::info!("macro {slot} kept in {}", path.display());
+        }),
+        // one at a time, as the window does not step during a replay
+        None if REPLAYING.load(Ordering::Relaxed) => Ok(()),
+        None => macros::load(slot).map(|kept| match kept {
+            Some(kept) => replay::play(kept, state.clone()),
+            None => show_error(
+                window,
+                "No macro",
+                format!(
+                    "Macro {slot} has not been recorded yet. Record one, \
+                     then play macro {slot} to keep it."
+                ),
+            ),
+        }),
+    };
+    if let Err(err) = result {
+        tracing::error!("{err:#}");
+        show_error(window, "Macro failed", format!("{err:#}"));
+    }
+}
+
+/// Ask for a script and queue a job that runs it from the seeds of `state`
+/// with the current parameters.
+#[cfg(feature = "script")]
+fn run_script(window: Option<gtk::Window>, state: SharedState) {
+    let dialog = gtk::FileDialog::builder().title("Run script").build();
+    dialog.open(
+        window.clone().as_ref(),
+        None::<&gio::Cancellable>,
+        move |result| {
+            let Some(path) = result.ok().and_then(|file| file.path()) else {
+                return;
+            };
+            let script = match Script::load(&path) {
+                Ok(script) => script,
+                Err(err) => {
+                    tracing::error!("{err:#}");
+                    show_error(
+                        window.as_ref(),
+                        "Run script failed",
+                        format!("{err:#}"),
+                    );
+                    return;
+                }
+            };
+            let project = state.borrow().project();
+            let params = *PARAMS.read().unwrap();
+
+            let title = format!("Run {}", path.display());
+            JOBS.add(title, move |job| {
+                let job = job.clone();
+                let written =
+                    script.run(project, params, move |iteration| {
+                        job.check_cancelled()?;
+                        job.set_progress(iteration, None);
+                        Ok(())
+                    })?;
+                Ok(format!(
+                    "Ran {}, writing {} images",
+                    path.display(),
+                    written.len()
+                ))
+            });
+        },
+    );
+}
+
+/// Tell that this build runs no scripts.
+#[cfg(not(feature = "script"))]
+fn run_script(window: Option<gtk::Window>, _state: SharedState) {
+    show_error(
+        window.as_ref(),
+        "Run script failed",
+        "this build has no scripting, build it with the script feature"
+            .to_owned(),
+    );
+}
+
+/// Pop up a menu at `x`, `y` of `parent` to grow the shape at `index` of
+/// `state` as one of the presets, setting the parameters for it.
+fn grow_as_menu(
+    parent: &gtk::Widget,
+    state: &SharedState,
+    index: usize,
+    x: f64,
+    y: f64,
+) {
+    let content = gtk::Box::new(gtk::Orientation::Vertical, 4);
+    content
+        .append(&gtk::Label::builder().label("Grow as…").xalign(0.).build());
+    let popover = gtk::Popover::builder().child(&content).build();
+    popover.set_parent(parent);
+    popover
+        .set_pointing_to(Some(&gdk::Rectangle::new(x as i32, y as i32, 1, 1)));
+
+    for preset in GrowPreset::ALL {
+        let button = gtk::Button::with_label(preset.label());
+        button.connect_clicked(glib::clone!(
+            #[strong]
+            state,
+            #[weak]
+            popover,
+            #[weak]
+            parent,
+            move |_| {
+                popover.popdown();
+                let params = *PARAMS.read().unwrap();
+                let grown = state.borrow_mut().grow_as(index, preset, &params);
+                match grown {
+                    Ok(params) => *PARAMS.write().unwrap() = params,
+                    Err(err) => {
+                        let window =
+                            parent.root().and_downcast::<gtk::Window>();
+                        show_simulation_error(window.as_ref(), err);
+                    }
+                }
+            }
+        ));
+        content.append(&button);
+    }
+
+    // once closed, the menu is not needed again
+    popover.connect_closed(|popover| {
+        glib::idle_add_local_once(glib::clone!(
+            #[weak]
+            popover,
+            move || popover.unparent()
+        ));
+    });
+    popover.popup();
+}
+
+fn window_title(state: &AppState) -> String {
+    let controller = state.simulation();
+    if controller.simulation().is_none() {
+        return "DxDy Draw".to_owned();
+    }
+
+    let run_state = match (controller.state(), controller.stop_reason()) {
+        (RunState::Running, _) => String::new(),
+        (RunState::Paused, _) => " (paused)".to_owned(),
+        (RunState::Finished, Some(reason)) => format!(" (stopped: {reason})"),
+        (RunState::Finished, None) => " (finished)".to_owned(),
+    };
+    let recording = if state.is_recording() {
+        " (recording)"
+    } else {
+        ""
+    };
+    let recording_macro = if state.is_recording_macro() {
+        " (recording macro)"
+    } else {
+        ""
+    };
+    format!(
+        "DxDy Draw \u{2014} iteration {}{run_state}{recording}{recording_macro}",
+        controller.iteration()
+    )
+}
+
+fn cb_activate(
+    app: &gtk::Application,
+    state: &SharedState,
+    style_file: Option<StyleFile>,
+    params_file: Option<ParamsFile>,
+    theme: Option<String>,
+) {
+    // Style
+
+    // the windows are drawn again once the change to the style is found
+    // along with their other damage
+    if let Some(mut style_file) = style_file {
+        glib::timeout_add_local(
+            std::time::Duration::from_millis(500),
+            move || {
+                if let Err(err) = style_file.reload_if_modified() {
+                    tracing::error!("{err:#}");
+                }
+                glib::ControlFlow::Continue
+            },
+        );
+    }
+
+    // Params
+
+    // applied to the parameters of every window, which the panel follows
+    if let Some(mut params_file) = params_file {
+        let mut reload = move || {
+            let mut params = *PARAMS.read().unwrap();
+            match params_file.reload_if_changed(&mut params) {
+                Ok(()) => *PARAMS.write().unwrap() = params,
+                Err(err) => tracing::error!("{err:#}"),
+            }
+        };
+        reload();
+        glib::timeout_add_local(params_file::INTERVAL, move || {
+            reload();
+            glib::ControlFlow::Continue
+        });
+    }
+
+    // Jobs
+
+    jobs_panel::notify_when_done(app);
+
+    // Actions
+
+    let quit = gio::SimpleAction::new("quit", None);
+    quit.connect_activate(glib::clone!(
+        #[weak]
+        app,
+        move |_, _| self::quit(&app)
+    ));
+    app.add_action(&quit);
+
+    let throttle = gio::SimpleAction::new_stateful(
+        "throttle-unfocused",
+        None,
+        &THROTTLE_UNFOCUSED.load(Ordering::Relaxed).to_variant(),
+    );
+    throttle.connect_activate(|action, _| {
+        let throttle = !THROTTLE_UNFOCUSED.load(Ordering::Relaxed);
+        THROTTLE_UNFOCUSED.store(throttle, Ordering::Relaxed);
+        action.set_state(&throttle.to_variant());
+    });
+    app.add_action(&throttle);
+
+    // no theme is picked while drawing with the colors of the style
+    let theme = gio::SimpleAction::new_stateful(
+        "theme",
+        Some(glib::VariantTy::STRING),
+        &theme.unwrap_or_default().to_variant(),
+    );
+    theme.connect_activate(glib::clone!(
+        #[weak]
+        app,
+        move |action, name| {
+            let Some(name) = name.and_then(|name| name.str()) else {
+                return;
+            };
+            match Palette::named(name) {
+                Ok(palette) => {
+                    theme::apply(palette);
+                    action.set_state(&name.to_variant());
+                }
+                Err(err) => {
+                    tracing::error!("{err:#}");
+                    let window = app.active_window();
+                    show_error(
+                        window.as_ref(),
+                        "Invalid theme",
+                        format!("{err:#}"),
+                    );
+                }
+            }
+        }
+    ));
+    app.add_action(&theme);
+    set_accelerators(app, &KEYMAP.read().unwrap());
+
+    build_window(app, state.clone());
+}
+
+/// Ask whether to recover the drawings autosaved by the last session,
+/// which did not end cleanly: into `state` if nothing is drawn in it yet,
+/// and into new windows otherwise.
+fn offer_recovery(
+    app: &gtk::Application,
+    state: &SharedState,
+    recovered: Vec<Recovered>,
+) {
+    let detail = match recovered.len() {
+        1 => "The last session did not end cleanly. Its drawing was \
+              autosaved."
+            .to_owned(),
+        n => format!(
+            "The last session did not end cleanly. Its {n} drawings were \
+             autosaved."
+        ),
+    };
+    let dialog = gtk::AlertDialog::builder()
+        .modal(true)
+        .message("Recover the last session?")
+        .detail(detail)
+        .buttons(["Discard", "Recover"])
+        .cancel_button(0)
+        .default_button(1)
+        .build();
+    let window = app.active_window();
+    dialog.choose(
+        window.clone().as_ref(),
+        None::<&gio::Cancellable>,
+        glib::clone!(
+            #[weak]
+            app,
+            #[strong]
+            state,
+            move |choice| {
+                if choice != Ok(1) {
+                    return;
+                }
+                let params = *PARAMS.read().unwrap();
+                let mut empty = {
+                    let state = state.borrow();
+                    state.shapes().is_empty()
+                        && state.simulation().simulation().is_none()
+                };
+                for saved in recovered {
+                    let into = if std::mem::take(&mut empty) {
+                        state.clone()
+                    } else {
+                        let new = SharedState::default();
+                        build_window(&app, new.clone());
+                        new
+                    };
+                    let restored =
+                        saved.restore(&mut into.borrow_mut(), &params);
+                    if let Err(err) = restored {
+                        tracing::error!("{err:#}");
+                        show_error(
+                            window.as_ref(),
+                            "Recovery failed",
+                            format!("{err:#}"),
+                        );
+                    }
+                }
+            }
+        ),
+    );
+}
+
+/// Open a window showing `state`, which it owns from then on along with its
+/// simulation and recording.
+fn build_window(app: &gtk::Application, state: SharedState) {
+    {
+        let mut state = state.borrow_mut();
+        let simulation = state.simulation_mut();
+        simulation.set_speed(*SPEED.read().unwrap());
+        simulation.set_interpolate(INTERPOLATE.load(Ordering::Relaxed));
+    }
+
+    // Drawing Area
+
+    let size = *CANVAS_SIZE.read().unwrap();
+    let drawing_area = gtk::DrawingArea::builder()
+        .content_width(size.x as i32)
+        .content_height(size.y as i32)
+        .build();
+
+    // Layout
+
+    let layout = gtk::Box::new(gtk::Orientation::Horizontal, 0);
+    let sidebar = params_panel::build();
+    sidebar.append(&gtk::Separator::new(gtk::Orientation::Horizontal));
+    sidebar.append(&seed_menu::build(&state));
+    sidebar.append(&domain_menu::build(&state));
+    sidebar.append(&mutate_menu::build(&state));
+    sidebar.append(&candidates_panel::build(&state));
+    sidebar.append(&layers_panel::build());
+    sidebar.append(&grid_panel::build(&state));
+    sidebar.append(&symmetry_panel::build(&state));
+    sidebar.append(&preset_menu::build(&state));
+    sidebar.append(&jobs_panel::build(&state));
+
+    let draw_obstacles = gtk::CheckButton::with_label("Draw obstacles");
+    draw_obstacles.set_active(state.borrow().draw_obstacles());
+    draw_obstacles.connect_toggled(glib::clone!(
+        #[strong]
+        state,
+        move |check| state.borrow_mut().set_draw_obstacles(check.is_active())
+    ));
+    sidebar.append(&draw_obstacles);
+
+    // new shapes are either obstacles or spines
+    let draw_spines = gtk::CheckButton::with_label("Draw spines");
+    draw_spines.set_active(state.borrow().draw_spines());
+    draw_spines.connect_toggled(glib::clone!(
+        #[strong]
+        state,
+        #[weak]
+        draw_obstacles,
+        move |check| {
+            if check.is_active() {
+                draw_obstacles.set_active(false);
+            }
+            state.borrow_mut().set_draw_spines(check.is_active())
+        }
+    ));
+    draw_obstacles.connect_toggled(glib::clone!(
+        #[weak]
+        draw_spines,
+        move |check| {
+            if check.is_active() {
+                draw_spines.set_active(false);
+            }
+        }
+    ));
+    sidebar.append(&draw_spines);
+
+    // dragging either draws, places fields, which Shift-click removes,
+    // anchors the growth to a stroke, or moves the shape it selects
+    const TOOLS: [(&str, Tool); 6] = [
+        ("Draw", Tool::Draw),
+        ("Place attractors", Tool::Field(FieldKind::Attractor)),
+        ("Place repulsors", Tool::Field(FieldKind::Repulsor)),
+        ("Place wind", Tool::Field(FieldKind::Wind { angle: 0. })),
+        ("Anchor growth", Tool::Anchor),
+        ("Select", Tool::Select),
+    ];
+    let tool = gtk::DropDown::from_strings(&TOOLS.map(|(label, _)| label));
+    tool.connect_selected_notify(glib::clone!(
+        #[strong]
+        state,
+        move |tool| {
+            let (_, selected) = TOOLS[tool.selected() as usize];
+            state.borrow_mut().set_tool(selected);
+        }
+    ));
+    sidebar.append(&gtk::Label::builder().label("Tool").xalign(0.).build());
+    sidebar.append(&tool);
+
+    let growth_area = match *RENDERER.read().unwrap() {
+        Renderer::Cairo => {
+            layout.append(&drawing_area);
+            None
+        }
+        Renderer::Gl => {
+            // the drawing area is transparent where it draws nothing, so the
+            // growth shows through from below
+            let growth_area = gpu::build(&state);
+            let overlay = gtk::Overlay::builder().child(&growth_area).build();
+            overlay.add_overlay(&drawing_area);
+            layout.append(&overlay);
+            Some(growth_area)
+        }
+    };
+    layout.append(&sidebar);
+
+    // Window
+
+    let window = gtk::ApplicationWindow::builder()
+        .application(app)
+        .title("DxDy Draw")
+        .default_width(size.x as i32)
+        .default_height(size.y as i32)
+        .resizable(false)
+        .child(&layout)
+        .build();
+
+    // Autosave
+
+    let autosave_slot =
+        AUTOSAVING.load(Ordering::Relaxed).then(autosave::next_slot);
+    if let Some(slot) = autosave_slot {
+        // saved again only once the drawing or growth has changed
+        let saved = Cell::new(None);
+        glib::timeout_add_local(
+            autosave::INTERVAL,
+            glib::clone!(
+                #[weak]
+                window,
+                #[strong]
+                state,
+                #[upgrade_or]
+                glib::ControlFlow::Break,
+                move || {
+                    let state = state.borrow();
+                    let controller = state.simulation();
+                    let changes = (
+                        state.shapes_generation(),
+                        controller.generation(),
+                        controller.iteration(),
+                    );
+                    // not saved again once closed, which discarded it
+                    if window.is_visible() && saved.get() != Some(changes) {
+                        match autosave::save(slot, &state) {
+                            Ok(()) => saved.set(Some(changes)),
+                            Err(err) => tracing::warn!("{err:#}"),
+                        }
+                    }
+                    glib::ControlFlow::Continue
+                }
+            ),
+        );
+    }
+
+    window.connect_close_request(glib::clone!(
+        #[strong]
+        state,
+        move |window| {
+            let window = window.upcast_ref::<gtk::Window>();
+            stop_recording(Some(window), &mut state.borrow_mut());
+            let discarded = autosave_slot.map(autosave::discard);
+            if let Some(Err(err)) = discarded {
+                tracing::warn!("{err:#}");
+            }
+            glib::Propagation::Proceed
+        }
+    ));
+
+    // Draw
+
+    let canvas = RefCell::new(None);
+    drawing_area.set_draw_func(glib::clone!(
+        #[strong]
+        state,
+        move |widget, ctx, w, h| {
+            let mut canvas = canvas.borrow_mut();
+            let mut state = state.borrow_mut();
+            eat_err(draw(widget, ctx, w, h, &mut canvas, &mut state))
+        }
+    ));
+
+    // the view as of the last check for damage
+    let tracked_view = Rc::new(RefCell::new(None));
+
+    // Actions
+
+    let groups = ["canvas", "sim"].map(|group| {
+        let actions = gio::SimpleActionGroup::new();
+        window.insert_action_group(group, Some(&actions));
+        (group, actions)
+    });
+    for action in Action::ALL {
+        let Some((group, name)) = action.detailed_name().split_once('.')
+        else {
+            continue;
+        };
+        if group == "app" {
+            continue;
+        }
+        let simple = gio::SimpleAction::new(name, None);
+        simple.connect_activate(glib::clone!(
+            #[weak]
+            app,
+            #[weak]
+            window,
+            #[strong]
+            state,
+            move |_, _| cb_action(&app, &window, &state, action)
+        ));
+        match group {
+            "win" => window.add_action(&simple),
+            group => {
+                let (_, actions) = groups
+                    .iter()
+                    .find(|(name, _)| *name == group)
+                    .expect("the group of every action is known");
+                actions.add_action(&simple);
+            }
+        }
+    }
+
+    // the speed, which the slider in the sidebar shows
+    let [_, (_, sim_actions)] = &groups;
+    let level = state.borrow().simulation().speed().level() as i32;
+    let speed = gio::SimpleAction::new_stateful(
+        "speed",
+        Some(glib::VariantTy::INT32),
+        &level.to_variant(),
+    );
+    speed.connect_change_state(glib::clone!(
+        #[strong]
+        state,
+        move |speed, level| {
+            let Some(level) = level.and_then(i32::from_variant) else {
+                return;
+            };
+            let level = level.clamp(0, Speed::LEVELS.len() as i32 - 1);
+            let mut state = state.borrow_mut();
+            state
+                .simulation_mut()
+                .set_speed(Speed::LEVELS[level as usize]);
+            speed.set_state(&level.to_variant());
+        }
+    ));
+    sim_actions.add_action(&speed);
+    sidebar.append(&speed_scale::build(&speed));
+
+    // Menu
+
+    let menu_button = gtk::MenuButton::builder()
+        .icon_name("open-menu-symbolic")
+        .menu_model(&menu())
+        .build();
+    let header = gtk::HeaderBar::new();
+    header.pack_end(&menu_button);
+    window.set_titlebar(Some(&header));
+    window.set_help_overlay(Some(&shortcuts_window()));
+
+    // Drag Gesture
+
+    let gesture_drag = gtk::GestureDrag::new();
+    gesture_drag.set_button(gdk::BUTTON_PRIMARY);
+
+    gesture_drag.connect_drag_begin(glib::clone!(
+        #[strong]
+        state,
+        move |gesture, x, y| {
+            gesture.set_state(gtk::EventSequenceState::Claimed);
+            let time = gesture.current_event_time();
+            let modifiers = modifiers(gesture.current_event_state());
+            let (params, smooth) = settings();
+            state.borrow_mut().pointer_down(
+                DVec2::new(x, y),
+                time,
+                modifiers,
+                &params,
+                smooth,
+            );
+        }
+    ));
+
+    gesture_drag.connect_drag_update(glib::clone!(
+        #[strong]
+        state,
+        move |gesture, _dx, _dy| {
+            gesture.set_state(gtk::EventSequenceState::Claimed);
+            if let Some((dx, dy)) = gesture.offset() {
+                let mut state = state.borrow_mut();
+                let time = gesture.current_event_time();
+                let modifiers = modifiers(gesture.current_event_state());
+                let (params, smooth) = settings();
+                let offset = DVec2::new(dx, dy);
+                let edge = state
+                    .pointer_drag(offset, time, modifiers, &params, smooth);
+                if let Some((a, b)) = edge {
+                    damage_stroke(&mut state, a, b);
+                }
+            }
+        }
+    ));
+
+    gesture_drag.connect_drag_end(glib::clone!(
+        #[strong]
+        state,
+        move |gesture, _dx, _dy| {
+            gesture.set_state(gtk::EventSequenceState::Claimed);
+            if let Some((dx, dy)) = gesture.offset() {
+                let time = gesture.current_event_time();
+                let modifiers = modifiers(gesture.current_event_state());
+                let (params, smooth) = settings();
+                state.borrow_mut().pointer_up(
+                    DVec2::new(dx, dy),
+                    time,
+                    modifiers,
+                    &params,
+                    smooth,
+                );
+            }
+        }
+    ));
+
+    window.add_controller(gesture_drag.clone());
+
+    // Stylus
+
+    fn read_pen(gesture: &gtk::GestureStylus) -> Pen {
+        let axis = |axis, default| gesture.axis(axis).unwrap_or(default);
+        Pen {
+            pressure: axis(gdk::AxisUse::Pressure, 1.),
+            tilt: DVec2::new(
+                axis(gdk::AxisUse::Xtilt, 0.),
+                axis(gdk::AxisUse::Ytilt, 0.),
+            ),
+        }
+    }
+
+    let gesture_stylus = gtk::GestureStylus::new();
+
+    gesture_stylus.connect_down(glib::clone!(
+        #[strong]
+        state,
+        move |gesture, _x, _y| state.borrow_mut().set_pen(read_pen(gesture))
+    ));
+    gesture_stylus.connect_motion(glib::clone!(
+        #[strong]
+        state,
+        move |gesture, _x, _y| state.borrow_mut().set_pen(read_pen(gesture))
+    ));
+    gesture_stylus.connect_up(glib::clone!(
+        #[strong]
+        state,
+        move |_gesture, _x, _y| state.borrow_mut().set_pen(Pen::DEFAULT)
+    ));
+
+    window.add_controller(gesture_stylus.clone());
+
+    // grouped so that both see the stylus events the drag gesture claims
+    gesture_stylus.group_with(&gesture_drag);
+
+    // Eraser
+
+    let gesture_erase = gtk::GestureClick::new();
+    gesture_erase.set_button(gdk::BUTTON_SECONDARY);
+
+    gesture_erase.connect_pressed(glib::clone!(
+        #[strong]
+        state,
+        move |gesture, _n_press, x, y| {
+            gesture.set_state(gtk::EventSequenceState::Claimed);
+            let (params, smooth) = settings();
+            let pos = DVec2::new(x, y);
+            let inside =
+                state.borrow_mut().secondary_click(pos, &params, smooth);
+            let Some(index) = inside else {
+                return;
+            };
+            if let Some(widget) = gesture.widget() {
+                grow_as_menu(&widget, &state, index, x, y);
+            }
+        }
+    ));
+
+    window.add_controller(gesture_erase);
+
+    // Pan
+
+    let gesture_pan = gtk::GestureDrag::new();
+    gesture_pan.set_button(gdk::BUTTON_MIDDLE);
+
+    // the viewport when the pan began
+    let pan_start = Rc::new(Cell::new(Viewport::DEFAULT));
+
+    gesture_pan.connect_drag_begin(glib::clone!(
+        #[strong]
+        state,
+        #[strong]
+        pan_start,
+        move |gesture, _x, _y| {
+            gesture.set_state(gtk::EventSequenceState::Claimed);
+            pan_start.set(state.borrow().viewport());
+        }
+    ));
+
+    gesture_pan.connect_drag_update(glib::clone!(
+        #[strong]
+        state,
+        move |gesture, dx, dy| {
+            gesture.set_state(gtk::EventSequenceState::Claimed);
+            let mut viewport = pan_start.get();
+            viewport.pan(DVec2::new(dx, dy));
+            *state.borrow_mut().viewport_mut() = viewport;
+        }
+    ));
+
+    window.add_controller(gesture_pan);
+
+    // Zoom
+
+    let scroll_controller = gtk::EventControllerScroll::new(
+        gtk::EventControllerScrollFlags::BOTH_AXES,
+    );
+
+    scroll_controller.connect_scroll(glib::clone!(
+        #[strong]
+        state,
+        move |controller, dx, dy| {
+            let mut state = state.borrow_mut();
+            let anchor = state.cursor_position().unwrap_or(DVec2::ZERO);
+            let viewport = state.viewport_mut();
+
+            // a wheel zooms, a touchpad pans with two fingers and zooms
+            // with Ctrl held
+            let zoom = controller.unit() == gdk::ScrollUnit::Wheel
+                || controller
+                    .current_event_state()
+                    .contains(gdk::ModifierType::CONTROL_MASK);
+
+            if zoom {
+                viewport.zoom_at(anchor, ZOOM_STEP.powf(-dy));
+            } else {
+                viewport.pan(-DVec2::new(dx, dy));
+            }
+
+            glib::Propagation::Stop
+        }
+    ));
+
+    window.add_controller(scroll_controller);
+
+    // Timers
+
+    let ticking = Ticking {
+        window: window.downgrade(),
+        drawing_area: drawing_area.downgrade(),
+        growth_area: growth_area.map(|area| area.downgrade()),
+        state: state.clone(),
+        tracked_view,
+        timers: Rc::default(),
+    };
+    ticking.set_pace();
+    window.connect_is_active_notify(glib::clone!(
+        #[strong]
+        ticking,
+        move |_| ticking.set_pace()
+    ));
+    window.connect_suspended_notify(glib::clone!(
+        #[strong]
+        ticking,
+        move |_| ticking.set_pace()
+    ));
+    window.connect_destroy(move |_| ticking.stop());
+
+    // Present
+
+    if FULLSCREEN.swap(false, Ordering::Relaxed) {
+        window.fullscreen();
+    }
+    window.present();
+}
+
+/// Whether the simulation of windows out of focus steps only every
+/// [`THROTTLED_TICK`], set from the command line and the menu.
+pub(crate) static THROTTLE_UNFOCUSED: AtomicBool = AtomicBool::new(false);
+
+/// Time between steps of the simulation.
+const TICK: Duration = Duration::from_millis(16);
+
+/// Time between steps of the simulation of throttled windows.
+const THROTTLED_TICK: Duration = Duration::from_millis(100);
+
+/// How far the cursor moves the vertices right by it per step while
+/// sculpting, in steps of the simulation.
+const SCULPT_STRENGTH: f64 = 4.;
+
+/// Most time a tick spends stepping the simulation, so that the window
+/// keeps drawing however fast it runs.
+const STEP_BUDGET: Duration = Duration::from_millis(12);
+
+/// How busy the timers of a window are.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Pace {
+    Active,
+    /// Out of focus with the simulation running and [`THROTTLE_UNFOCUSED`]
+    /// on, stepping the simulation more slowly.
+    Throttled,
+    /// Out of focus or minimized with nothing running, without any timers
+    /// until focused again.
+    Idle,
+}
+
+impl Pace {
+    fn of(window: &gtk::ApplicationWindow, state: &AppState) -> Self {
+        if window.is_active() && !window.is_suspended() {
+            return Self::Active;
+        }
+        let running = state.simulation().state() == RunState::Running
+            || state.playback().is_some();
+        if !running {
+            Self::Idle
+        } else if THROTTLE_UNFOCUSED.load(Ordering::Relaxed) {
+            Self::Throttled
+        } else {
+            Self::Active
+        }
+    }
+}
+
+/// The timers of a window, which are all stopped while it idles.
+#[derive(Default)]
+struct Timers {
+    pace: Option<Pace>,
+    sources: Vec<glib::SourceId>,
+    /// Draws the growth with OpenGL.
+    growth: Option<gtk::TickCallbackId>,
+    /// When the simulation last ticked.
+    last_tick: Option<Instant>,
+}
+
+/// What the timers of a window poll the pointer, blink the cursor, and step
+/// the simulation of.
+#[derive(Clone)]
+struct Ticking {
+    window: glib::WeakRef<gtk::ApplicationWindow>,
+    drawing_area: glib::WeakRef<gtk::DrawingArea>,
+    growth_area: Option<glib::WeakRef<gtk::GLArea>>,
+    state: SharedState,
+    tracked_view: Rc<RefCell<Option<View>>>,
+    timers: Rc<RefCell<Timers>>,
+}
+
+impl Ticking {
+    /// Start or stop the timers for the pace the window should be at, if it
+    /// is not at it already.
+    fn set_pace(&self) {
+        let Some(window) = self.window.upgrade() else {
+            return;
+        };
+        let pace = Pace::of(&window, &self.state.borrow());
+        if self.timers.borrow().pace == Some(pace) {
+            return;
+        }
+
+        self.stop();
+        tracing::debug!("window pace: {pace:?}");
+        let mut timers = self.timers.borrow_mut();
+        timers.pace = Some(pace);
+        let tick = match pace {
+            Pace::Active => TICK,
+            Pace::Throttled => THROTTLED_TICK,
+            Pace::Idle => return,
+        };
+
+        let ticking = self.clone();
+        timers.sources.push(glib::timeout_add_local(
+            Duration::from_millis(20),
+            move || ticking.poll_pointer(),
+        ));
+        let ticking = self.clone();
+        timers.sources.push(glib::timeout_add_local(
+            Duration::from_millis(750),
+            move || ticking.blink(),
+        ));
+        let ticking = self.clone();
+        timers
+            .sources
+            .push(glib::timeout_add_local(tick, move || ticking.tick()));
+        if let Some(area) = self.growth_area.as_ref().and_then(|a| a.upgrade())
+        {
+            timers.growth = Some(gpu::follow_growth(&area));
+        }
+    }
+
+    fn stop(&self) {
+        let mut timers = self.timers.borrow_mut();
+        timers.pace = None;
+        timers.last_tick = None;
+        for source in timers.sources.drain(..) {
+            source.remove();
+        }
+        if let Some(growth) = timers.growth.take() {
+            growth.remove();
+        }
+    }
+
+    fn poll_pointer(&self) -> glib::ControlFlow {
+        let (Some(window), Some(drawing_area)) =
+            (self.window.upgrade(), self.drawing_area.upgrade())
+        else {
+            return glib::ControlFlow::Break;
+        };
+
+        let pos = pointer_position(&window);
+        let mut state = self.state.borrow_mut();
+        let previous = state.move_cursor(pos);
+        if pos != previous {
+            let radius = if state.sculpting() {
+                sizes::SCULPT_RADIUS * state.viewport().zoom()
+            } else {
+                STYLE.read().unwrap().cursor_radius
+            };
+            let damage = state.damage_mut();
+            for pos in [previous, pos].into_iter().flatten() {
+                // and a pixel of antialiasing
+                let margin = radius + 1.;
+                damage.extend(Rect::around([pos], margin));
+            }
+        }
+        redraw_damage(&drawing_area, &mut state, &self.tracked_view);
+        glib::ControlFlow::Continue
+    }
+
+    fn blink(&self) -> glib::ControlFlow {
+        self.state.borrow_mut().blink();
+        glib::ControlFlow::Continue
+    }
+
+    fn tick(&self) -> glib::ControlFlow {
+        let (Some(window), Some(drawing_area)) =
+            (self.window.upgrade(), self.drawing_area.upgrade())
+        else {
+            return glib::ControlFlow::Break;
+        };
+
+        let params = *PARAMS.read().unwrap();
+        let mut state = self.state.borrow_mut();
+
+        // at most two ticks' worth, so that stalls and throttled windows
+        // slow the simulation down rather than have it catch up
+        let now = Instant::now();
+        let last_tick = self.timers.borrow_mut().last_tick.replace(now);
+        let elapsed =
+            last_tick.map_or(TICK, |last| (now - last).min(2 * TICK));
+
+        // a replay takes the steps that were logged instead, and stops
+        // where the session did
+        let (due, stop) = if REPLAYING.load(Ordering::Relaxed) {
+            (0, StopConditions::NONE)
+        } else {
+            (
+                state.simulation_mut().steps_due(elapsed),
+                *STOP_CONDITIONS.read().unwrap(),
+            )
+        };
+        state.simulation_mut().set_stop_conditions(stop);
+        let field = cursor_field(&window, &state).filter(|_| {
+            // a replay grows as the session did, without the cursor
+            !REPLAYING.load(Ordering::Relaxed)
+        });
+        state.simulation_mut().set_cursor_field(field);
+        let was_stopped = state.simulation().stop_reason().is_some();
+        let mut taken = 0;
+        for _ in 0..due {
+            match state.simulation_mut().tick(&params) {
+                Ok(true) => taken += 1,
+                Ok(false) => break,
+                Err(err) => {
+                    show_simulation_error(Some(&window), err);
+                    break;
+                }
+            }
+            if let Err(err) = state.capture_frame() {
+                tracing::error!("{err:#}");
+                _ = state.stop_recording();
+                show_error(
+                    Some(&window),
+                    "Recording failed",
+                    format!("{err:#}"),
+                );
+            }
+            if now.elapsed() >= STEP_BUDGET {
+                break;
+            }
+        }
+
+        if taken > 0 {
+            log(&mut state, Event::Steps(taken));
+        }
+        state.record_steps(taken, now);
+        let stopped =
+            !was_stopped && state.simulation().stop_reason().is_some();
+
+        let title = window_title(&state);
+        if window.title().is_none_or(|t| t != title) {
+            window.set_title(Some(&title));
+        }
+
+        // drawn right away, rather than on the next check for damage
+        redraw_damage(&drawing_area, &mut state, &self.tracked_view);
+        // the stats change with every tick, and are drawn over the canvas
+        if state.show_stats() {
+            drawing_area.queue_draw();
+        }
+
+        // the simulation may have stopped, or throttling been turned on,
+        // which the timers are changed for once this one is done
+        if self.timers.borrow().pace != Some(Pace::of(&window, &state)) {
+            glib::idle_add_local_once(glib::clone!(
+                #[strong(rename_to = ticking)]
+                self,
+                move || ticking.set_pace()
+            ));
+        }
+
+        drop(state);
+        if let Some(path) =
+            EXPORT_ON_STOP.read().unwrap().as_ref().filter(|_| stopped)
+        {
+            write_image(
+                Some(window.upcast_ref()),
+                &self.state,
+                &Preset::default(),
+                path,
+            );
+        }
+
+        glib::ControlFlow::Continue
+    }
+}
+
+/// Position of the pointer in `window`, if it is over it.
+fn pointer_position(window: &gtk::ApplicationWindow) -> Option<DVec2> {
+    let display = gdk::Display::default()?;
+    let pointer = display.default_seat()?.pointer()?;
+    let surface = window.surface()?;
+    surface
+        .device_position(&pointer)
+        .map(|(x, y, _)| DVec2::new(x, y))
+}
+
+/// The field of the cursor on the growth of `state` while sculpting, which
+/// pulls the growth in with Shift held and otherwise pushes it away.
+fn cursor_field(
+    window: &gtk::ApplicationWindow,
+    state: &AppState,
+) -> Option<Field> {
+    if !state.sculpting() {
+        return None;
+    }
+    let pos = state.cursor_position()?;
+    let display = gdk::Display::default()?;
+    let pointer = display.default_seat()?.pointer()?;
+    let (_, _, modifiers) = window.surface()?.device_position(&pointer)?;
+    let kind = if modifiers.contains(gdk::ModifierType::SHIFT_MASK) {
+        FieldKind::Attractor
+    } else {
+        FieldKind::Repulsor
+    };
+
+    let transform = Transform::DEFAULT;
+    Some(Field {
+        kind,
+        position: transform.document_to_unit(state.viewport().to_world(pos)),
+        radius: transform.document_to_unit_length(sizes::SCULPT_RADIUS),
+        strength: SCULPT_STRENGTH,
+    })
+}
+
+/// The GTK accelerator of `chord`, with Shift for upper case keys, or
+/// `None` if it has no key of that name.
+fn accelerator(chord: &Chord) -> Option<glib::GString> {
+    let key = gdk::Key::from_name(&chord.key)?;
+    let mut modifiers = gdk::ModifierType::empty();
+    for (held, mask) in [
+        (chord.modifiers.ctrl, gdk::ModifierType::CONTROL_MASK),
+        (chord.modifiers.alt, gdk::ModifierType::ALT_MASK),
+        (chord.modifiers.meta, gdk::ModifierType::META_MASK),
+        (key.is_upper(), gdk::ModifierType::SHIFT_MASK),
+    ] {
+        modifiers.set(mask, held);
+    }
+    Some(gtk::accelerator_name(key.to_lower(), modifiers))
+}
+
+/// Set the accelerators of the actions of `app` and its windows to the
+/// chords of `keymap`.
+fn set_accelerators(app: &gtk::Application, keymap: &Keymap) {
+    for action in Action::ALL {
+        let accels = keymap
+            .chords_of(action)
+            .iter()
+            .filter_map(accelerator)
+            .collect::<Vec<_>>();
+        let accels = accels.iter().map(|a| a.as_str()).collect::<Vec<_>>();
+        app.set_accels_for_action(action.detailed_name(), &accels);
+    }
+}
+
+/// The sections of the window menu.
+const MENU: [&[Action]; 5] = [
+    &[
+        Action::NewWindow,
+        Action::Open,
+        Action::LoadSeeds,
+        Action::Import,
+        Action::Save,
+        Action::Export,
+        Action::CopyImage,
+        Action::Record,
+        Action::RecordFrames,
+        Action::RunScript,
+    ],
+    &[
+        Action::Undo,
+        Action::Clear,
+        Action::BakeSegments,
+        Action::BakeGenerations,
+        Action::BakeObstacles,
+        Action::ResetView,
+        Action::Play,
+        Action::PlayFast,
+        Action::RotateSelectionLeft,
+        Action::RotateSelectionRight,
+        Action::GrowSelection,
+        Action::ShrinkSelection,
+    ],
+    &[
+        Action::PauseSim,
+        Action::StepSim,
+        Action::RewindSim,
+        Action::ResetSim,
+        Action::SlowerSim,
+        Action::FasterSim,
+        Action::ToggleStats,
+        Action::ToggleSculpt,
+    ],
+    &[
+        Action::RecordMacro,
+        Action::PlayMacro1,
+        Action::PlayMacro2,
+        Action::PlayMacro3,
+        Action::PlayMacro4,
+    ],
+    &[Action::Quit],
+];
+
+/// Submenu picking the theme of every window, among the built-in and the
+/// custom themes there are when the window opens.
+fn theme_menu() -> gio::Menu {
+    let names = Palette::names().unwrap_or_else(|err| {
+        tracing::error!("failed to list themes: {err:#}");
+        theme::built_in_names().map(str::to_owned).collect()
+    });
+    let menu = gio::Menu::new();
+    for name in names {
+        let item = gio::MenuItem::new(Some(&name), None);
+        item.set_action_and_target_value(
+            Some("app.theme"),
+            Some(&name.to_variant()),
+        );
+        menu.append_item(&item);
+    }
+    menu
+}
+
+/// The menu of the window, with every action and the shortcuts window.
+fn menu() -> gio::Menu {
+    let menu = gio::Menu::new();
+    for (i, actions) in MENU.into_iter().enumerate() {
+        let section = gio::Menu::new();
+        // between the simulation and the app
+        if i == MENU.len() - 1 {
+            let themes = gio::Menu::new();
+            themes.append_submenu(Some("Theme"), &theme_menu());
+            menu.append_section(None, &themes);
+        }
+        for &action in actions {
+            section.append(Some(action.label()), Some(action.detailed_name()));
+        }
+        // above quitting
+        if i == MENU.len() - 1 {
+            section.prepend(
+                Some("Keyboard Shortcuts"),
+                Some("win.show-help-overlay"),
+            );
+            section.prepend(
+                Some("Throttle When Unfocused"),
+                Some("app.throttle-unfocused"),
+            );
+        }
+        menu.append_section(None, &section);
+    }
+    menu
+}
+
+/// Window listing the accelerators of the actions in the sections of the
+/// menu, shown from the menu or with Ctrl+?.
+fn shortcuts_window() -> gtk::ShortcutsWindow {
+    let titles = ["File", "Drawing", "Simulation", "Application"];
+    let section = gtk::ShortcutsSection::builder().build();
+    for (title, actions) in titles.into_iter().zip(MENU) {
+        let group = gtk::ShortcutsGroup::builder().title(title).build();
+        for &action in actions {
+            group.add_shortcut(
+                &gtk::ShortcutsShortcut::builder()
+                    .title(action.label())
+                    .action_name(action.detailed_name())
+                    .build(),
+            );
+        }
+        section.add_group(&group);
+    }
+
+    let window = gtk::ShortcutsWindow::builder().modal(true).build();
+    window.add_section(&section);
+    window
+}
+
+/// Close every window of `app`, one by one to finish their recordings, and
+/// so quit once the last is closed.
+fn quit(app: &gtk::Application) {
+    for window in app.windows() {
+        window.close();
+    }
+}
+
+/// Add `event` to the session log of `state`, if there is one, done with
+/// the current settings.
+fn log(state: &mut AppState, event: Event) {
+    let (params, smooth) = settings();
+    state.log(event, &params, smooth);
+}
+
+/// The parameters and smoothing options of the application, which what is
+/// done in the window is done and logged with.
+fn settings() -> (Params, smooth::SmoothOptions) {
+    let params = *PARAMS.read().unwrap();
+    (params, *smooth::SMOOTH_OPTIONS.read().unwrap())
+}
+
+/// The keys held in `state` that the pointer does something else with.
+fn modifiers(state: gdk::ModifierType) -> Modifiers {
+    Modifiers {
+        ctrl: state.contains(gdk::ModifierType::CONTROL_MASK),
+        shift: state.contains(gdk::ModifierType::SHIFT_MASK),
+    }
+}
+
+/// Do `action` in `window`, which shows `state`.
+fn cb_action(
+    app: &gtk::Application,
+    window: &gtk::ApplicationWindow,
+    state: &SharedState,
+    action: Action,
+) {
+    let window = Some(window.clone().upcast::<gtk::Window>());
+    let params = *PARAMS.read().unwrap();
+    let simulated = match action {
+        Action::Quit => {
+            quit(app);
+            Ok(())
+        }
+        Action::NewWindow => {
+            // a new drawing, grown with the same seed
+            let new = SharedState::default();
+            let seed = state.borrow().simulation().seed();
+            new.borrow_mut().simulation_mut().set_seed(seed);
+            build_window(app, new);
+            Ok(())
+        }
+        Action::Save => {
+            save_project(window.clone(), state.clone());
+            Ok(())
+        }
+        Action::Open => {
+            open_project(window.clone(), state.clone());
+            Ok(())
+        }
+        Action::LoadSeeds => {
+            load_seeds(window.clone(), state.clone());
+            Ok(())
+        }
+        Action::Import => {
+            import_dialog::import(window.clone(), state.clone());
+            Ok(())
+        }
+        Action::Export => {
+            export_image(window.clone(), state.clone(), Preset::default());
+            Ok(())
+        }
+        Action::CopyImage => {
+            if let Some(window) = &window {
+                copy_image(window, &state.borrow());
+            }
+            Ok(())
+        }
+        Action::RunScript => {
+            run_script(window.clone(), state.clone());
+            Ok(())
+        }
+        Action::Record => {
+            toggle_recording(window.clone(), state.clone());
+            Ok(())
+        }
+        Action::RecordFrames => {
+            toggle_recording_frames(window.clone(), state.clone());
+            Ok(())
+        }
+        Action::Clear
+        | Action::Undo
+        | Action::BakeSegments
+        | Action::BakeGenerations
+        | Action::BakeObstacles
+        | Action::ResetView
+        | Action::Play
+        | Action::PlayFast
+        | Action::PauseSim
+        | Action::StepSim
+        | Action::RewindSim
+        | Action::ResetSim => {
+            let mut state = state.borrow_mut();
+            log(&mut state, Event::Action(action));
+            state.act(action, &params).map(drop)
+        }
+        Action::RotateSelectionLeft
+        | Action::RotateSelectionRight
+        | Action::GrowSelection
+        | Action::ShrinkSelection => {
+            let smooth = *smooth::SMOOTH_OPTIONS.read().unwrap();
+            state.borrow_mut().nudge_selection(action, &params, smooth);
+            Ok(())
+        }
+        Action::ToggleStats => {
+            let mut state = state.borrow_mut();
+            state.toggle_stats();
+            // the stats are drawn over the canvas, which is not damaged
+            state.damage_mut().add_all();
+            Ok(())
+        }
+        Action::ToggleSculpt => {
+            let mut state = state.borrow_mut();
+            state.toggle_sculpting();
+            // to show or hide the reach of the cursor
+            state.damage_mut().add_all();
+            Ok(())
+        }
+        Action::RecordMacro => {
+            let mut state = state.borrow_mut();
+            if state.finish_macro().is_none() {
+                let smooth = *smooth::SMOOTH_OPTIONS.read().unwrap();
+                state.start_macro(&params, smooth);
+            }
+            Ok(())
+        }
+        Action::PlayMacro1
+        | Action::PlayMacro2
+        | Action::PlayMacro3
+        | Action::PlayMacro4 => {
+            let slot = action.macro_slot().expect("plays a macro");
+            play_macro(window.as_ref(), state, slot);
+            Ok(())
+        }
+        Action::SlowerSim | Action::FasterSim => {
+            let level = state.borrow().simulation().speed().level();
+            let level = match action {
+                Action::SlowerSim => level.saturating_sub(1),
+                _ => level + 1,
+            };
+            if let Some(window) = &window {
+                let level = level as i32;
+                _ = window
+                    .activate_action("sim.speed", Some(&level.to_variant()));
+            }
+            Ok(())
+        }
+    };
+
+    if let Err(err) = simulated {
+        show_simulation_error(window.as_ref(), err);
+    }
+}
+
+/// What the window shows besides the cursor and the shape being drawn,
+/// compared between checks for damage to find when all of it changed.
+#[derive(Clone, PartialEq)]
+struct View {
+    viewport: Viewport,
+    params: Params,
+    domain: Domain,
+    fields: Vec<Field>,
+    shapes: u64,
+    selected: Option<usize>,
+    snap: SnapOptions,
+    symmetry: Option<Symmetry>,
+    style: u64,
+    layers: u64,
+    /// Generation of the simulation, or `None` with OpenGL, which draws the
+    /// growth below the window instead.
+    simulation: Option<u64>,
+    cursor_color: bool,
+    playback: bool,
+    region: Option<Region>,
+}
+
+impl View {
+    fn current(state: &AppState) -> Self {
+        let gl = *RENDERER.read().unwrap() == Renderer::Gl;
+        let controller = state.simulation();
+        Self {
+            viewport: state.viewport(),
+            params: *PARAMS.read().unwrap(),
+            domain: controller.domain().clone(),
+            fields: controller.fields().to_vec(),
+            shapes: state.shapes_generation(),
+            selected: state.selected(),
+            snap: state.snap(),
+            symmetry: state.symmetry().0,
+            style: style_generation(),
+            layers: layers_generation(),
+            simulation: (!gl).then(|| controller.generation()),
+            cursor_color: state.cursor_color(),
+            playback: state.playback().is_some(),
+            region: state.region(),
+        }
+    }
+}
+
+/// Damage the part of the window showing `state` covered by the shape being
+/// drawn where it was extended from `a` to `b`, in world coordinates.
+fn damage_stroke(state: &mut AppState, a: DVec2, b: DVec2) {
+    let viewport = state.viewport();
+    // a miter at a sharp corner reaches out up to the miter limit of 10
+    // times half the width, and a pixel of antialiasing past that
+    let width = STYLE.read().unwrap().drawing_width;
+    let margin = 10. * width / 2. * viewport.zoom() + 1.;
+    let rect = Rect::around([a, b].map(|p| viewport.to_screen(p)), margin);
+    state.damage_mut().extend(rect);
+}
+
+/// Damage all of the window if what it shows of `state` and the rest has
+/// changed since `tracked`, and queue a draw of `area` if any of it is
+/// damaged.
+fn redraw_damage(
+    area: &gtk::DrawingArea,
+    state: &mut AppState,
+    tracked: &RefCell<Option<View>>,
+) {
+    let view = View::current(state);
+    let damage = state.damage_mut();
+
+    // a playback moves on with every frame
+    if view.playback {
+        damage.add_all();
+    }
+
+    let mut tracked = tracked.borrow_mut();
+    if tracked.as_ref() != Some(&view) {
+        damage.add_all();
+        *tracked = Some(view);
+    }
+
+    if !damage.is_empty() {
+        area.queue_draw();
+    }
+}
+
+/// An image surface of `width` by `height` pixels at `scale` device pixels
+/// per pixel.
+fn scaled_surface(
+    width: i32,
+    height: i32,
+    scale: i32,
+) -> Result<cairo::ImageSurface> {
+    let surface = cairo::ImageSurface::create(
+        cairo::Format::ARgb32,
+        width * scale,
+        height * scale,
+    )?;
+    surface.set_device_scale(scale.into(), scale.into());
+    Ok(surface)
+}
+
+/// Layers with a key kept drawn, enough for the completed shapes in each of
+/// the two colors they blink between.
+const KEPT_LAYERS: usize = 2;
+
+/// The window as last drawn, kept so that only its damaged parts are drawn
+/// again.
+struct Canvas {
+    surface: cairo::ImageSurface,
+    width: i32,
+    height: i32,
+    scale: i32,
+    /// The last drawn layers with a key, by their key, latest first.
+    layers: Vec<(u64, cairo::ImageSurface)>,
+}
+
+impl Canvas {
+    fn new(width: i32, height: i32, scale: i32) -> Result<Self> {
+        Ok(Self {
+            surface: scaled_surface(width, height, scale)?,
+            width,
+            height,
+            scale,
+            layers: Vec::new(),
+        })
+    }
+
+    /// `layer` drawn on its own, drawn again only if no layer with its key
+    /// is kept.
+    fn layer(
+        &mut self,
+        layer: &Layer,
+        key: u64,
+    ) -> Result<&cairo::ImageSurface> {
+        match self.layers.iter().position(|(k, _)| *k == key) {
+            Some(i) => {
+                let kept = self.layers.remove(i);
+                self.layers.insert(0, kept);
+            }
+            None => {
+                let surface =
+                    scaled_surface(self.width, self.height, self.scale)?;
+                render::layer(&cairo::Context::new(&surface)?, layer)?;
+                self.layers.insert(0, (key, surface));
+                self.layers.truncate(KEPT_LAYERS);
+            }
+        }
+        Ok(&self.layers[0].1)
+    }
+
+    /// Draw `scene` into `ctx`, a context of the canvas.
+    fn draw(&mut self, ctx: &cairo::Context, scene: &Scene) -> Result<()> {
+        // the canvas still has the last frame on it
+        ctx.set_operator(cairo::Operator::Clear);
+        ctx.paint()?;
+        ctx.set_operator(cairo::Operator::Over);
+        if let Some(color) = scene.background {
+            render::background(ctx, scene.size, color)?;
+        }
+
+        for layer in &scene.layers {
+            match layer.key {
+                Some(key) => {
+                    ctx.set_source_surface(self.layer(layer, key)?, 0., 0.)?;
+                    ctx.paint()?;
+                }
+                None => render::layer(ctx, layer)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Draw the damaged parts of the window showing `state` onto `canvas`, and
+/// then all of `canvas` into `ctx`.
+fn draw(
+    widget: &gtk::DrawingArea,
+    ctx: &cairo::Context,
+    width: i32,
+    height: i32,
+    canvas: &mut Option<Canvas>,
+    state: &mut AppState,
+) -> Result<()> {
+    let scale = widget.scale_factor();
+    let mut damage = state.take_damage();
+
+    let fits = |canvas: &Canvas| {
+        (canvas.width, canvas.height, canvas.scale) == (width, height, scale)
+    };
+    if !canvas.as_ref().is_some_and(fits) {
+        damage.add_all();
+        *canvas = Some(Canvas::new(width, height, scale)?);
+    }
+    let canvas = canvas.as_mut().unwrap();
+
+    if !damage.is_empty() {
+        let start = Instant::now();
+        let ctx = cairo::Context::new(&canvas.surface)?;
+        if let Damage::Rects(rects) = &damage {
+            for rect in rects {
+                let size = rect.size();
+                ctx.rectangle(rect.min.x, rect.min.y, size.x, size.y);
+            }
+            ctx.clip();
+        }
+        let scene = scene(state, DVec2::new(width as f64, height as f64));
+        canvas.draw(&ctx, &scene)?;
+        state.stats_mut().record_render(start.elapsed());
+    }
+
+    ctx.set_source_surface(&canvas.surface, 0., 0.)?;
+    ctx.paint()?;
+
+    if state.show_stats() {
+        let style = STYLE.read().unwrap();
+        render::text_box(
+            ctx,
+            DVec2::splat(8.),
+            &state.stats().to_string(),
+            style.marker,
+            style.background.with_alpha(0.8),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// The window showing `state` as a scene of `size`: the cursor, the guides
+/// and the shape being drawn, the completed shapes, and the growth, each a
+/// layer.
+fn scene(state: &mut AppState, size: DVec2) -> Scene {
+    // with OpenGL the background and growth are drawn below the window
+    let gl = *RENDERER.read().unwrap() == Renderer::Gl;
+
+    let style = STYLE.read().unwrap();
+    let blink = state.cursor_color();
+    let [color, color_opposite] = if blink {
+        style.cursor
+    } else {
+        [style.cursor[1], style.cursor[0]]
+    };
+
+    let cursor = state.cursor_position().map(|pos| Item {
+        paint: Paint::Fill(color),
+        paths: vec![Path::Circle {
+            center: pos,
+            radius: style.cursor_radius,
+        }],
+    });
+    // the reach of the cursor while sculpting
+    let reach =
+        state
+            .cursor_position()
+            .filter(|_| state.sculpting())
+            .map(|pos| Item {
+                paint: Paint::Stroke(Stroke::new(color, 1.)),
+                paths: vec![Path::Circle {
+                    center: pos,
+                    radius: sizes::SCULPT_RADIUS * state.viewport().zoom(),
+                }],
+            });
+
+    let viewport = state.viewport();
+    let playback = state.playback_progress();
+
+    // widths that stay the same on screen at any zoom
+    let hairline = 1. / viewport.zoom();
+
+    let mut guides = {
+        let params = *PARAMS.read().unwrap();
+        let domain = state.simulation().domain();
+        let mut guides =
+            scene::domain_guides(&style, domain, &params, hairline);
+        let fields = state.simulation().fields();
+        guides.extend(scene::field_guides(&style, fields, hairline));
+        guides
+    };
+
+    let grid = state.snap().grid(DOCUMENT_SIZE);
+    if !grid.is_empty() {
+        let color = style.marker.with_alpha(0.3);
+        guides.insert(
+            0,
+            Item {
+                paint: Paint::Stroke(Stroke::new(color, hairline)),
+                paths: grid,
+            },
+        );
+    }
+
+    if let Some(symmetry) = state.symmetry().0 {
+        guides.push(scene::symmetry_guides(&style, symmetry, hairline));
+    }
+
+    {
+        let shape = state.current_shape();
+        let start = shape.start();
+        let color = if state.draw_obstacles() {
+            style.obstacle
+        } else {
+            color
+        };
+        guides.push(Item {
+            paint: Paint::Stroke(Stroke::new(color, style.drawing_width)),
+            paths: vec![Path::Line {
+                points: std::iter::once(start)
+                    .chain(shape.verticies().map(|offset| start + offset))
+                    .chain(state.snapped_segment())
+                    .collect(),
+                closed: false,
+            }],
+        });
+    }
+
+    // the region recordings are cropped to
+    if let Some(Region { min, max }) = state.region() {
+        guides.push(Item {
+            paint: Paint::Stroke(Stroke {
+                dash: Some(4. * hairline),
+                ..Stroke::new(style.marker, hairline)
+            }),
+            paths: vec![Path::Line {
+                points: vec![
+                    min,
+                    DVec2::new(max.x, min.y),
+                    max,
+                    DVec2::new(min.x, max.y),
+                ],
+                closed: true,
+            }],
+        });
+    }
+
+    // the shape selected with the select tool, in its box, and its handles
+    if let Some(index) = state.selected() {
+        let shape = &state.shapes()[index];
+        if let Some((min, max)) = shape.bounds() {
+            guides.push(Item {
+                paint: Paint::Stroke(Stroke {
+                    dash: Some(4. * hairline),
+                    ..Stroke::new(style.marker, hairline)
+                }),
+                paths: vec![Path::Line {
+                    points: vec![
+                        min,
+                        DVec2::new(max.x, min.y),
+                        max,
+                        DVec2::new(min.x, max.y),
+                    ],
+                    closed: true,
+                }],
+            });
+        }
+        guides.push(Item {
+            paint: Paint::Stroke(Stroke::new(style.marker, 2. * hairline)),
+            paths: vec![Path::Line {
+                points: shape.points().collect(),
+                closed: shape.closed(),
+            }],
+        });
+        let handles = state.selection_handles().into_iter().flatten();
+        guides.push(Item {
+            paint: Paint::Fill(style.marker),
+            paths: handles
+                .map(|(_, center)| Path::Circle {
+                    center,
+                    radius: sizes::HANDLE_RADIUS * hairline,
+                })
+                .collect(),
+        });
+    }
+
+    let all_shapes = state.shapes();
+    let canvas = LAYERS.read().unwrap();
+    let mut markers = scene::vertex_markers(
+        &style,
+        all_shapes,
+        playback,
+        hairline,
+        MAX_VERTEX_MARKERS.load(Ordering::Relaxed),
+    );
+
+    // the completed shapes are the slowest to draw, and are kept drawn until
+    // they change, which they do on every frame of a playback
+    let shapes_key = |layer: CanvasLayer| {
+        playback.is_none().then(|| {
+            let mut hasher = DefaultHasher::new();
+            (
+                layer,
+                state.shapes_generation(),
+                style_generation(),
+                layers_generation(),
+                blink,
+                viewport,
+            )
+                .hash(&mut hasher);
+            hasher.finish()
+        })
+    };
+
+    let mut layers = vec![
+        Layer::new(cursor.into_iter().chain(reach).collect()),
+        viewport.layer(None, guides),
+    ];
+    for layer in canvas.order() {
+        let (key, mut items) = match layer {
+            CanvasLayer::Background => continue,
+            // with OpenGL the growth is drawn below the window
+            CanvasLayer::Growth if gl => continue,
+            CanvasLayer::Growth => {
+                let paths = state.simulation().shown_paths();
+                (None, scene::growth_paths(&style, paths, hairline))
+            }
+            CanvasLayer::Obstacles | CanvasLayer::Sketches => {
+                let items = scene::layer_shapes(
+                    &style,
+                    all_shapes,
+                    color_opposite,
+                    playback,
+                    layer,
+                );
+                (shapes_key(layer), items)
+            }
+        };
+        // the vertices are marked with the sketches they seed
+        if layer == CanvasLayer::Sketches {
+            items.append(&mut markers);
+        }
+        layers.push(viewport.layer(key, canvas.show(layer, items)));
+    }
+
+    Scene {
+        size,
+        background: if gl {
+            None
+        } else {
+            canvas.background(style.background)
+        },
+        layers,
+    }
+}